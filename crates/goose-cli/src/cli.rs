@@ -1,21 +1,28 @@
 use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
 
-use goose::config::{Config, ExtensionConfig};
+use goose::agents::conversation_template::TemplateTurn;
+use goose::agents::tool_mocks::ToolMocks;
+use goose::agents::TerminationCondition;
+use goose::config::{Config, ExtensionConfig, ExtensionConfigManager};
+use goose::recipe::{OutputChecks, RecipeTree};
 
 use crate::commands::bench::agent_generator;
 use crate::commands::configure::handle_configure;
+use crate::commands::doctor::handle_doctor;
 use crate::commands::info::handle_info;
 use crate::commands::mcp::run_server;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
-use crate::commands::recipe::{handle_deeplink, handle_validate};
+use crate::commands::recipe::{handle_deeplink, handle_pack, handle_unpack, handle_validate};
 // Import the new handlers from commands::schedule
 use crate::commands::schedule::{
     handle_schedule_add, handle_schedule_cron_help, handle_schedule_list, handle_schedule_remove,
     handle_schedule_run_now, handle_schedule_services_status, handle_schedule_services_stop,
     handle_schedule_sessions,
 };
-use crate::commands::session::{handle_session_list, handle_session_remove};
+use crate::commands::session::{
+    handle_session_diff, handle_session_list, handle_session_remove, handle_session_search,
+};
 use crate::logging::setup_logging;
 use crate::recipes::recipe::{explain_recipe_with_parameters, load_recipe_as_template};
 use crate::session;
@@ -115,6 +122,50 @@ enum SessionCommand {
             long_help = "Path to save the exported Markdown. If not provided, output will be sent to stdout"
         )]
         output: Option<PathBuf>,
+
+        #[arg(
+            long,
+            value_name = "NAME",
+            help = "Export the session as it was at a checkpoint"
+        )]
+        checkpoint: Option<String>,
+    },
+    #[command(about = "Compare two sessions")]
+    Diff {
+        #[arg(help = "Name of the session to compare from")]
+        left: String,
+
+        #[arg(help = "Name of the session to compare against")]
+        right: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+    #[command(about = "Search past sessions by keyword")]
+    Search {
+        #[arg(help = "Words to search for in session descriptions and messages")]
+        query: String,
+
+        #[arg(
+            short,
+            long,
+            help = "Maximum number of sessions to show",
+            default_value = "10"
+        )]
+        limit: usize,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
     },
 }
 
@@ -244,6 +295,39 @@ enum RecipeCommand {
         )]
         recipe_name: String,
     },
+
+    /// Pack a recipe and everything it references into a single archive
+    #[command(about = "Pack a recipe into a shareable archive")]
+    Pack {
+        /// Path to the recipe file to pack
+        #[arg(help = "Path to the recipe file to pack")]
+        recipe_path: PathBuf,
+
+        /// Where to write the archive
+        #[arg(
+            short,
+            long,
+            help = "Output archive path (defaults to the recipe name with a .goose-recipe extension)"
+        )]
+        output: Option<PathBuf>,
+    },
+
+    /// Verify and extract a recipe archive
+    #[command(about = "Unpack a recipe archive")]
+    Unpack {
+        /// Path to the archive
+        #[arg(help = "Path to the recipe archive")]
+        archive_path: PathBuf,
+
+        /// Directory to extract into
+        #[arg(
+            short,
+            long,
+            default_value = ".",
+            help = "Directory to extract the recipe files into"
+        )]
+        dest: PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -260,6 +344,26 @@ enum Command {
         verbose: bool,
     },
 
+    /// Check the environment before starting a session
+    #[command(about = "Check that goose is ready to start a session")]
+    Doctor {
+        /// Working directory to check
+        #[arg(
+            short,
+            long,
+            help = "Working directory of the session (defaults to the current directory)"
+        )]
+        working_dir: Option<PathBuf>,
+
+        #[arg(
+            short,
+            long,
+            help = "Output format (text, json)",
+            default_value = "text"
+        )]
+        format: String,
+    },
+
     /// Manage system prompts and behaviors
     #[command(about = "Run one of the mcp servers bundled with goose")]
     Mcp { name: String },
@@ -492,6 +596,15 @@ enum Command {
         )]
         builtins: Vec<String>,
 
+        /// Answer tools with canned responses from a mock file
+        #[arg(
+            long = "mock-tools",
+            value_name = "FILE",
+            help = "Answer tools with canned responses from a YAML mock file instead of running them",
+            long_help = "Dry run a recipe: tools listed in the mock file return its canned responses instead of running, so instructions and prompts can be iterated on without touching real systems."
+        )]
+        mock_tools: Option<PathBuf>,
+
         /// Quiet mode - suppress non-response output
         #[arg(
             short = 'q',
@@ -561,6 +674,31 @@ enum Command {
         #[arg(long, help = "Open browser automatically when server starts")]
         open: bool,
     },
+
+    /// Run tools assigned to this host by remote agents
+    #[command(about = "Serve tool calls for agents that assign tools to this worker")]
+    Worker {
+        /// Port to listen on
+        #[arg(
+            short,
+            long,
+            default_value = "7878",
+            help = "Port to listen on for tool calls"
+        )]
+        port: u16,
+
+        /// Host to bind to
+        #[arg(long, default_value = "127.0.0.1", help = "Host to bind the worker to")]
+        host: String,
+
+        /// Secret holding the token agents must present
+        #[arg(
+            long = "token-key",
+            value_name = "KEY",
+            help = "Name of the secret holding the bearer token agents must present"
+        )]
+        token_key: Option<String>,
+    },
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -575,6 +713,11 @@ struct InputConfig {
     contents: Option<String>,
     extensions_override: Option<Vec<ExtensionConfig>>,
     additional_system_prompt: Option<String>,
+    stop_conditions: Option<Vec<TerminationCondition>>,
+    acceptance_criteria: Option<Vec<String>>,
+    conversation_template: Option<Vec<TemplateTurn>>,
+    output_checks: Option<OutputChecks>,
+    sub_recipes: Option<RecipeTree>,
 }
 
 pub async fn cli() -> Result<()> {
@@ -585,6 +728,14 @@ pub async fn cli() -> Result<()> {
         eprintln!("Warning: Failed to update project tracker: {}", e);
     }
 
+    // Extensions that older configs let in with invalid names are renamed before anything reads
+    // them, but not by the built-in servers that goose itself runs as extensions
+    if !matches!(cli.command, Some(Command::Mcp { .. })) {
+        if let Err(e) = ExtensionConfigManager::migrate_names() {
+            eprintln!("Warning: Failed to migrate extension names: {}", e);
+        }
+    }
+
     match cli.command {
         Some(Command::Configure {}) => {
             let _ = handle_configure().await;
@@ -594,6 +745,13 @@ pub async fn cli() -> Result<()> {
             handle_info(verbose)?;
             return Ok(());
         }
+        Some(Command::Doctor {
+            working_dir,
+            format,
+        }) => {
+            handle_doctor(working_dir, format).await?;
+            return Ok(());
+        }
         Some(Command::Mcp { name }) => {
             let _ = run_server(&name).await;
         }
@@ -621,7 +779,11 @@ pub async fn cli() -> Result<()> {
                     handle_session_remove(id, regex)?;
                     return Ok(());
                 }
-                Some(SessionCommand::Export { identifier, output }) => {
+                Some(SessionCommand::Export {
+                    identifier,
+                    output,
+                    checkpoint,
+                }) => {
                     let session_identifier = if let Some(id) = identifier {
                         extract_identifier(id)
                     } else {
@@ -635,7 +797,27 @@ pub async fn cli() -> Result<()> {
                         }
                     };
 
-                    crate::commands::session::handle_session_export(session_identifier, output)?;
+                    crate::commands::session::handle_session_export(
+                        session_identifier,
+                        output,
+                        checkpoint,
+                    )?;
+                    Ok(())
+                }
+                Some(SessionCommand::Diff {
+                    left,
+                    right,
+                    format,
+                }) => {
+                    handle_session_diff(left, right, format)?;
+                    Ok(())
+                }
+                Some(SessionCommand::Search {
+                    query,
+                    limit,
+                    format,
+                }) => {
+                    handle_session_search(query, limit, format)?;
                     Ok(())
                 }
                 None => {
@@ -697,6 +879,7 @@ pub async fn cli() -> Result<()> {
             builtins,
             params,
             explain,
+            mock_tools,
             quiet,
         }) => {
             let (input_config, session_settings) = match (instructions, input_text, recipe, explain)
@@ -712,6 +895,11 @@ pub async fn cli() -> Result<()> {
                             contents: Some(input),
                             extensions_override: None,
                             additional_system_prompt: system,
+                            stop_conditions: None,
+                            acceptance_criteria: None,
+                            conversation_template: None,
+                            output_checks: None,
+                            sub_recipes: None,
                         },
                         None,
                     )
@@ -729,6 +917,11 @@ pub async fn cli() -> Result<()> {
                             contents: Some(contents),
                             extensions_override: None,
                             additional_system_prompt: None,
+                            stop_conditions: None,
+                            acceptance_criteria: None,
+                            conversation_template: None,
+                            output_checks: None,
+                            sub_recipes: None,
                         },
                         None,
                     )
@@ -738,6 +931,11 @@ pub async fn cli() -> Result<()> {
                         contents: Some(text),
                         extensions_override: None,
                         additional_system_prompt: system,
+                        stop_conditions: None,
+                        acceptance_criteria: None,
+                        conversation_template: None,
+                        output_checks: None,
+                        sub_recipes: None,
                     },
                     None,
                 ),
@@ -746,8 +944,8 @@ pub async fn cli() -> Result<()> {
                         explain_recipe_with_parameters(&recipe_name, params)?;
                         return Ok(());
                     }
-                    let recipe =
-                        load_recipe_as_template(&recipe_name, params).unwrap_or_else(|err| {
+                    let (recipe, sub_recipes) = load_recipe_as_template(&recipe_name, params)
+                        .unwrap_or_else(|err| {
                             eprintln!("{}: {}", console::style("Error").red().bold(), err);
                             std::process::exit(1);
                         });
@@ -756,6 +954,11 @@ pub async fn cli() -> Result<()> {
                             contents: recipe.prompt,
                             extensions_override: recipe.extensions,
                             additional_system_prompt: recipe.instructions,
+                            stop_conditions: recipe.stop_conditions,
+                            acceptance_criteria: recipe.acceptance_criteria,
+                            conversation_template: recipe.conversation_template,
+                            output_checks: recipe.output_checks,
+                            sub_recipes,
                         },
                         recipe.settings.map(|s| SessionSettings {
                             goose_provider: s.goose_provider,
@@ -787,6 +990,37 @@ pub async fn cli() -> Result<()> {
             })
             .await;
 
+            if let Some(stop_conditions) = input_config.stop_conditions {
+                session.set_termination_conditions(stop_conditions).await;
+            }
+            if let Some(acceptance_criteria) = input_config.acceptance_criteria {
+                session.set_acceptance_criteria(acceptance_criteria).await;
+            }
+            if let Some(turns) = input_config.conversation_template {
+                session
+                    .set_conversation_template(turns)
+                    .await
+                    .unwrap_or_else(|err| {
+                        eprintln!("{}: {}", console::style("Error").red().bold(), err);
+                        std::process::exit(1);
+                    });
+            }
+
+            if let Some(tree) = input_config.sub_recipes {
+                session.run_sub_recipes(&tree).await.unwrap_or_else(|err| {
+                    eprintln!("{}: {}", console::style("Error").red().bold(), err);
+                    std::process::exit(1);
+                });
+            }
+
+            if let Some(mock_file) = mock_tools {
+                let mocks = ToolMocks::from_file(&mock_file).unwrap_or_else(|err| {
+                    eprintln!("{}: {}", console::style("Error").red().bold(), err);
+                    std::process::exit(1);
+                });
+                session.set_tool_mocks(mocks).await;
+            }
+
             setup_logging(
                 session.session_file().file_stem().and_then(|s| s.to_str()),
                 None,
@@ -795,7 +1029,21 @@ pub async fn cli() -> Result<()> {
             if interactive {
                 let _ = session.interactive(input_config.contents).await;
             } else if let Some(contents) = input_config.contents {
-                let _ = session.headless(contents).await;
+                match input_config.output_checks {
+                    Some(checks) => {
+                        if let Ok(report) =
+                            session.headless_with_output_checks(contents, &checks).await
+                        {
+                            eprintln!("{}", report);
+                            if !report.passed {
+                                std::process::exit(1);
+                            }
+                        }
+                    }
+                    None => {
+                        let _ = session.headless(contents).await;
+                    }
+                }
             } else {
                 eprintln!("Error: no text provided for prompt in headless mode");
                 std::process::exit(1);
@@ -874,6 +1122,15 @@ pub async fn cli() -> Result<()> {
                 RecipeCommand::Deeplink { recipe_name } => {
                     handle_deeplink(&recipe_name)?;
                 }
+                RecipeCommand::Pack {
+                    recipe_path,
+                    output,
+                } => {
+                    handle_pack(&recipe_path, output)?;
+                }
+                RecipeCommand::Unpack { archive_path, dest } => {
+                    handle_unpack(&archive_path, &dest)?;
+                }
             }
             return Ok(());
         }
@@ -881,6 +1138,14 @@ pub async fn cli() -> Result<()> {
             crate::commands::web::handle_web(port, host, open).await?;
             return Ok(());
         }
+        Some(Command::Worker {
+            port,
+            host,
+            token_key,
+        }) => {
+            crate::commands::worker::handle_worker(host, port, token_key).await?;
+            return Ok(());
+        }
         None => {
             return if !Config::global().exists() {
                 let _ = handle_configure().await;
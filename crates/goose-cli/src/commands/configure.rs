@@ -2,6 +2,7 @@ use cliclack::spinner;
 use console::style;
 use goose::agents::extension::ToolInfo;
 use goose::agents::extension_manager::get_parameter_names;
+use goose::agents::extension_names::{check_unique, validate_name};
 use goose::agents::platform_tools::{
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
 };
@@ -79,6 +80,8 @@ pub async fn handle_configure() -> Result<(), Box<dyn Error>> {
                         name: "developer".to_string(),
                         display_name: Some(goose::config::DEFAULT_DISPLAY_NAME.to_string()),
                         timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
+                        tool_timeout: None,
+                        tool_filter: None,
                         bundled: Some(true),
                     },
                 })?;
@@ -327,24 +330,20 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
         }
     }
 
-    // Attempt to fetch supported models for this provider
+    // Check the credentials with a minimal call, which also fetches the supported models
     let spin = spinner();
-    spin.start("Attempting to fetch supported models...");
-    let models_res = {
-        let temp_model_config = goose::model::ModelConfig::new(provider_meta.default_model.clone());
-        let temp_provider = create(provider_name, temp_model_config)?;
-        temp_provider.fetch_supported_models_async().await
-    };
-    spin.stop(style("Model fetch complete").green());
+    spin.start("Checking your credentials...");
+    let verification = goose::providers::verify(provider_name).await?;
+    if !verification.is_ok() {
+        spin.stop(style("Credential check failed").red());
+        cliclack::outro(style(verification.message).on_red().white())?;
+        return Ok(false);
+    }
+    spin.stop(style("Credentials verified").green());
 
-    // Select a model: on fetch error show styled error and abort; if Some(models), show list; if None, free-text input
-    let model: String = match models_res {
-        Err(e) => {
-            // Provider hook error
-            cliclack::outro(style(e.to_string()).on_red().white())?;
-            return Ok(false);
-        }
-        Ok(Some(models)) => cliclack::select("Select a model:")
+    // Select a model: if the provider listed its models, show the list; otherwise free-text input
+    let model: String = match verification.models {
+        Some(models) => cliclack::select("Select a model:")
             .items(
                 &models
                     .iter()
@@ -353,7 +352,7 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
             )
             .interact()?
             .to_string(),
-        Ok(None) => {
+        None => {
             let default_model =
                 std::env::var("GOOSE_MODEL").unwrap_or(provider_meta.default_model.clone());
             cliclack::input("Enter a model from that provider:")
@@ -551,6 +550,8 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     name: extension.clone(),
                     display_name: Some(display_name),
                     timeout: Some(timeout),
+                    tool_timeout: None,
+                    tool_filter: None,
                     bundled: Some(true),
                 },
             })?;
@@ -563,11 +564,15 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                 .placeholder("my-extension")
                 .validate(move |input: &String| {
                     if input.is_empty() {
-                        Err("Please enter a name")
+                        Err("Please enter a name".to_string())
                     } else if extensions.contains(input) {
-                        Err("An extension with this name already exists")
+                        Err("An extension with this name already exists".to_string())
                     } else {
-                        Ok(())
+                        validate_name(input)
+                            .and_then(|_| {
+                                check_unique(input, extensions.iter().map(String::as_str))
+                            })
+                            .map_err(|e| e.to_string())
                     }
                 })
                 .interact()?;
@@ -658,6 +663,8 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     env_keys,
                     description,
                     timeout: Some(timeout),
+                    tool_timeout: None,
+                    tool_filter: None,
                     bundled: None,
                 },
             })?;
@@ -670,11 +677,15 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                 .placeholder("my-remote-extension")
                 .validate(move |input: &String| {
                     if input.is_empty() {
-                        Err("Please enter a name")
+                        Err("Please enter a name".to_string())
                     } else if extensions.contains(input) {
-                        Err("An extension with this name already exists")
+                        Err("An extension with this name already exists".to_string())
                     } else {
-                        Ok(())
+                        validate_name(input)
+                            .and_then(|_| {
+                                check_unique(input, extensions.iter().map(String::as_str))
+                            })
+                            .map_err(|e| e.to_string())
                     }
                 })
                 .interact()?;
@@ -760,6 +771,8 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     env_keys,
                     description,
                     timeout: Some(timeout),
+                    tool_timeout: None,
+                    tool_filter: None,
                     bundled: None,
                 },
             })?;
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use console::style;
+use goose::preflight::{run_preflight, CheckStatus, PreflightReport};
+use std::path::PathBuf;
+
+fn print_report(report: &PreflightReport) {
+    for check in &report.checks {
+        let status = match check.status {
+            CheckStatus::Pass => style("pass").green(),
+            CheckStatus::Warn => style("warn").yellow(),
+            CheckStatus::Fail => style("fail").red(),
+            CheckStatus::Skipped => style("skip").dim(),
+        };
+        println!("  {} {:<28} {}", status, check.id, check.message);
+    }
+    println!();
+    if report.ok {
+        println!("{}", style("Ready to start a session").green().bold());
+    } else {
+        println!(
+            "{}",
+            style("Fix the failed checks before starting a session")
+                .red()
+                .bold()
+        );
+    }
+}
+
+/// Check the environment for a session and print what needs fixing
+pub async fn handle_doctor(working_dir: Option<PathBuf>, format: String) -> Result<()> {
+    let working_dir = match working_dir {
+        Some(dir) => dir,
+        None => std::env::current_dir()?,
+    };
+    let report = run_preflight(&working_dir).await;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        println!("{}", style("Goose Preflight:").cyan().bold());
+        print_report(&report);
+    }
+    if !report.ok {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,5 +1,6 @@
 pub mod bench;
 pub mod configure;
+pub mod doctor;
 pub mod info;
 pub mod mcp;
 pub mod project;
@@ -8,3 +9,4 @@ pub mod schedule;
 pub mod session;
 pub mod update;
 pub mod web;
+pub mod worker;
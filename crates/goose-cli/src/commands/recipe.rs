@@ -1,6 +1,10 @@
+use std::path::{Path, PathBuf};
+
 use anyhow::Result;
 use base64::Engine;
 use console::style;
+use goose::recipe::archive::ARCHIVE_FILE_EXTENSION;
+use goose::recipe::{pack_recipe, unpack_recipe};
 
 use crate::recipes::recipe::load_recipe;
 
@@ -58,3 +62,61 @@ pub fn handle_deeplink(recipe_name: &str) -> Result<()> {
         }
     }
 }
+/// Packs a recipe and the files it references into a single archive
+///
+/// # Arguments
+///
+/// * `recipe_path` - Path to the recipe file
+/// * `output` - Where to write the archive, next to the recipe by default
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub fn handle_pack(recipe_path: &Path, output: Option<PathBuf>) -> Result<()> {
+    let output = output.unwrap_or_else(|| recipe_path.with_extension(ARCHIVE_FILE_EXTENSION));
+    match pack_recipe(recipe_path, &output) {
+        Ok(archive) => {
+            println!(
+                "{} Packed {} files into {}",
+                style("✓").green().bold(),
+                archive.files.len(),
+                output.display()
+            );
+            for file in &archive.files {
+                println!("  {} {}", style(&file.sha256[..12]).dim(), file.path);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            println!("{} {}", style("✗").red().bold(), err);
+            Err(err.into())
+        }
+    }
+}
+
+/// Verifies a recipe archive and extracts it
+///
+/// # Arguments
+///
+/// * `archive_path` - Path to the archive
+/// * `dest` - Directory to extract into
+///
+/// # Returns
+///
+/// Result indicating success or failure
+pub fn handle_unpack(archive_path: &Path, dest: &Path) -> Result<()> {
+    match unpack_recipe(archive_path, dest) {
+        Ok(root) => {
+            println!(
+                "{} Unpacked recipe to {}",
+                style("✓").green().bold(),
+                root.display()
+            );
+            Ok(())
+        }
+        Err(err) => {
+            println!("{} {}", style("✗").red().bold(), err);
+            Err(err.into())
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::session::message_to_markdown;
 use anyhow::{Context, Result};
 use cliclack::{confirm, multiselect, select};
 use goose::session::info::{get_session_info, SessionInfo, SortOrder};
-use goose::session::{self, Identifier};
+use goose::session::{self, diff_sessions, Identifier, SessionSnapshot};
 use regex::Regex;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -169,11 +169,136 @@ pub fn handle_session_list(verbose: bool, format: String, ascending: bool) -> Re
     Ok(())
 }
 
+/// Compare two sessions by name and print the differences
+pub fn handle_session_diff(left: String, right: String, format: String) -> Result<()> {
+    let load = |name: String| -> Result<SessionSnapshot> {
+        let session_file = session::get_path(Identifier::Name(name.clone()));
+        if !session_file.exists() {
+            return Err(anyhow::anyhow!(
+                "Session file not found (expected path: {})",
+                session_file.display()
+            ));
+        }
+        SessionSnapshot::load(name, &session_file)
+    };
+    let diff = diff_sessions(&load(left)?, &load(right)?);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(&diff)?);
+        return Ok(());
+    }
+
+    println!("Comparing {} -> {}", diff.left_id, diff.right_id);
+    println!(
+        "Messages: {} -> {} ({} in common)",
+        diff.messages.left_count, diff.messages.right_count, diff.messages.common_prefix
+    );
+    for summary in &diff.messages.left_only {
+        println!(
+            "  - [{}] {:?}: {}",
+            summary.index,
+            summary.role,
+            first_line(&summary.text, &summary.tool_calls)
+        );
+    }
+    for summary in &diff.messages.right_only {
+        println!(
+            "  + [{}] {:?}: {}",
+            summary.index,
+            summary.role,
+            first_line(&summary.text, &summary.tool_calls)
+        );
+    }
+
+    println!("Tools:");
+    for tool in &diff.tools.added {
+        println!("  + {}", tool);
+    }
+    for tool in &diff.tools.removed {
+        println!("  - {}", tool);
+    }
+    for change in &diff.tools.changed {
+        println!(
+            "  ~ {}: {} -> {} calls",
+            change.tool, change.left, change.right
+        );
+    }
+
+    let tokens = &diff.usage.total_tokens;
+    let display = |t: Option<i32>| t.map_or("unknown".to_string(), |t| t.to_string());
+    println!(
+        "Total tokens: {} -> {}{}",
+        display(tokens.left),
+        display(tokens.right),
+        tokens
+            .delta
+            .map_or(String::new(), |d| format!(" ({:+})", d))
+    );
+    println!(
+        "Tool errors: {} -> {}",
+        diff.outcome.left.tool_errors, diff.outcome.right.tool_errors
+    );
+    println!(
+        "Final response: {}",
+        if diff.outcome.same_final_response {
+            "unchanged"
+        } else {
+            "changed"
+        }
+    );
+    Ok(())
+}
+
+/// Search past sessions by keyword and print the best matches
+pub fn handle_session_search(query: String, limit: usize, format: String) -> Result<()> {
+    let results = session::search_sessions(&query, limit)?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string(&results)?);
+        return Ok(());
+    }
+
+    if results.is_empty() {
+        println!("No sessions found matching \"{}\"", query);
+        return Ok(());
+    }
+    for result in results {
+        let description = if result.description.is_empty() {
+            "(none)"
+        } else {
+            &result.description
+        };
+        println!(
+            "{} - {} - {}",
+            result.session_id, description, result.working_dir
+        );
+        for found in &result.matches {
+            println!(
+                "  [{}] {:?}: {}",
+                found.message_index, found.role, found.snippet
+            );
+        }
+    }
+    Ok(())
+}
+
+fn first_line(text: &str, tool_calls: &[String]) -> String {
+    match text.lines().find(|l| !l.trim().is_empty()) {
+        Some(line) => line.trim().to_string(),
+        None if !tool_calls.is_empty() => format!("calls {}", tool_calls.join(", ")),
+        None => "(no text)".to_string(),
+    }
+}
+
 /// Export a session to Markdown without creating a full Session object
 ///
 /// This function directly reads messages from the session file and converts them to Markdown
 /// without creating an Agent or prompting about working directories.
-pub fn handle_session_export(identifier: Identifier, output_path: Option<PathBuf>) -> Result<()> {
+pub fn handle_session_export(
+    identifier: Identifier,
+    output_path: Option<PathBuf>,
+    checkpoint: Option<String>,
+) -> Result<()> {
     // Get the session file path
     let session_file_path = goose::session::get_path(identifier.clone());
 
@@ -185,7 +310,13 @@ pub fn handle_session_export(identifier: Identifier, output_path: Option<PathBuf
     }
 
     // Read messages directly without using Session
-    let messages = match goose::session::read_messages(&session_file_path) {
+    let messages = match checkpoint {
+        Some(name) => {
+            goose::session::checkpoints::messages_at_checkpoint(&session_file_path, &name)
+        }
+        None => goose::session::read_messages(&session_file_path),
+    };
+    let messages = match messages {
         Ok(msgs) => msgs,
         Err(e) => {
             return Err(anyhow::anyhow!("Failed to read session messages: {}", e));
@@ -12,6 +12,7 @@ use futures::{sink::SinkExt, stream::StreamExt};
 use goose::agents::{Agent, AgentEvent};
 use goose::message::Message as GooseMessage;
 use goose::session;
+use goose::shutdown::{ShutdownGuard, ShutdownSignal};
 use serde::{Deserialize, Serialize};
 use std::{net::SocketAddr, sync::Arc};
 use tokio::sync::{Mutex, RwLock};
@@ -162,6 +163,8 @@ pub async fn handle_web(port: u16, host: String, open: bool) -> Result<()> {
         }
     }
 
+    // Servers have no reply to interrupt, so Ctrl+C shuts down and stops the extensions
+    let _shutdown = ShutdownGuard::install_for(&[ShutdownSignal::Interrupt]);
     let listener = tokio::net::TcpListener::bind(addr).await?;
     axum::serve(listener, app).await?;
 
@@ -415,6 +418,7 @@ async fn process_message_streaming(
     use futures::StreamExt;
     use goose::agents::SessionConfig;
     use goose::message::MessageContent;
+    use goose::providers::transcription::apply_transcripts;
     use goose::session;
 
     // Create a user message
@@ -454,6 +458,9 @@ async fn process_message_streaming(
         id: session::Identifier::Path(session_file.clone()),
         working_dir: std::env::current_dir()?,
         schedule_id: None,
+        sampling: None,
+        tags: Default::default(),
+        config_overrides: Default::default(),
     };
 
     // Get response from agent
@@ -584,6 +591,9 @@ async fn process_message_streaming(
                             }
                         }
                     }
+                    Ok(AgentEvent::TextChunk(_)) => {
+                        // The text is sent once its response is complete
+                    }
                     Ok(AgentEvent::McpNotification(_notification)) => {
                         // Handle MCP notifications if needed
                         // For now, we'll just log them
@@ -593,6 +603,48 @@ async fn process_message_streaming(
                         // Log model change
                         tracing::info!("Model changed to {} in {} mode", model, mode);
                     }
+                    Ok(AgentEvent::TurnBudget(budget)) => {
+                        tracing::info!("Turn budget set to {} turns", budget.max_turns);
+                    }
+                    Ok(AgentEvent::ExtensionsDegraded(warnings)) => {
+                        for warning in warnings {
+                            tracing::warn!(
+                                "Extension {} is unavailable: {}",
+                                warning.extension,
+                                warning.message
+                            );
+                        }
+                    }
+                    Ok(AgentEvent::PolicyViolations(violations)) => {
+                        for violation in violations {
+                            tracing::warn!(
+                                "Response policy violation ({}): {}",
+                                violation.rule,
+                                violation.message
+                            );
+                        }
+                    }
+                    Ok(AgentEvent::InputQueued(input)) => {
+                        tracing::info!("Queued message {} received", input.id);
+                    }
+                    Ok(AgentEvent::QuotaWarning(status)) => {
+                        tracing::warn!("{}", status);
+                    }
+                    Ok(AgentEvent::ContextUsage(usage)) => {
+                        tracing::debug!("Context usage: {:?}", usage);
+                    }
+                    Ok(AgentEvent::ToolRouting(status)) => {
+                        tracing::warn!("{}", status);
+                    }
+                    Ok(AgentEvent::UiResources(resources)) => {
+                        tracing::debug!("Interactive views returned: {:?}", resources);
+                    }
+                    Ok(AgentEvent::Transcribed(transcribed)) => {
+                        apply_transcripts(&mut messages, &transcribed);
+                    }
+                    Ok(AgentEvent::Cancelled) => {
+                        tracing::info!("Reply cancelled");
+                    }
                     Err(e) => {
                         error!("Error in message stream: {}", e);
                         let mut sender = sender.lock().await;
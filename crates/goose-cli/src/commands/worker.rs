@@ -0,0 +1,170 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use futures::StreamExt;
+use goose::agents::remote_workers::{WorkerEvent, WorkerRequest};
+use goose::agents::ExtensionManager;
+use goose::config::Config;
+use goose::shutdown::{ShutdownGuard, ShutdownSignal};
+use mcp_core::ToolCall;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
+
+#[derive(Clone)]
+struct WorkerState {
+    extension_manager: Arc<ExtensionManager>,
+    token: Option<String>,
+}
+
+/// Serve tool calls from agents that assign tools to this worker
+pub async fn handle_worker(host: String, port: u16, token_key: Option<String>) -> Result<()> {
+    let token = match &token_key {
+        Some(key) => Some(Config::global().get_secret::<String>(key)?),
+        None => None,
+    };
+
+    let mut extension_manager = ExtensionManager::new();
+    let extensions = goose::config::ExtensionConfigManager::get_all()?;
+    for ext_config in extensions {
+        if ext_config.enabled {
+            if let Err(e) = extension_manager
+                .add_extension(ext_config.config.clone())
+                .await
+            {
+                eprintln!(
+                    "Warning: Failed to load extension {}: {}",
+                    ext_config.config.name(),
+                    e
+                );
+            }
+        }
+    }
+
+    let state = WorkerState {
+        extension_manager: Arc::new(extension_manager),
+        token,
+    };
+    let app = Router::new()
+        .route("/tool_calls", post(tool_call))
+        .with_state(state);
+
+    let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+
+    println!("\n🪿 Starting Goose worker");
+    println!(
+        "   Working directory: {}",
+        std::env::current_dir()?.display()
+    );
+    println!("   Server: http://{}", addr);
+    if token_key.is_none() {
+        println!("   Warning: no token is required, any client can run tools");
+    }
+    println!("   Press Ctrl+C to stop\n");
+
+    // Servers have no reply to interrupt, so Ctrl+C shuts down and stops the extensions
+    let _shutdown = ShutdownGuard::install_for(&[ShutdownSignal::Interrupt]);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+fn event_line(event: &WorkerEvent) -> String {
+    let mut line = serde_json::to_string(event).unwrap_or_default();
+    line.push('\n');
+    line
+}
+
+async fn tool_call(
+    State(state): State<WorkerState>,
+    headers: HeaderMap,
+    Json(request): Json<WorkerRequest>,
+) -> Response {
+    if let Some(token) = &state.token {
+        let authorized = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|provided| provided == token);
+        if !authorized {
+            return StatusCode::UNAUTHORIZED.into_response();
+        }
+    }
+
+    tracing::info!(
+        "Running {} ({}) for a remote agent",
+        request.name,
+        request.id
+    );
+    let (tx, rx) = mpsc::channel::<String>(64);
+    tokio::spawn(async move {
+        let call = ToolCall::new(request.name, request.arguments);
+        // The call is cancelled when the agent that asked for it goes away
+        let cancel = CancellationToken::new();
+        tokio::spawn({
+            let tx = tx.clone();
+            let cancel = cancel.clone();
+            async move {
+                tx.closed().await;
+                cancel.cancel();
+            }
+        });
+        let call = match state
+            .extension_manager
+            .dispatch_tool_call(call, cancel)
+            .await
+        {
+            Ok(call) => call,
+            Err(e) => {
+                let error = WorkerEvent::Error {
+                    message: e.to_string(),
+                };
+                let _ = tx.send(event_line(&error)).await;
+                return;
+            }
+        };
+
+        // Notifications are forwarded until the tool finishes, their stream does not end
+        let forwarder = call.notification_stream.map(|mut notifications| {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while let Some(notification) = notifications.next().await {
+                    if let Some(event) = WorkerEvent::from_notification(&notification) {
+                        if tx.send(event_line(&event)).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            })
+        });
+
+        let event = match call.result.await {
+            Ok(content) => WorkerEvent::Result { content },
+            Err(e) => WorkerEvent::Error {
+                message: e.to_string(),
+            },
+        };
+        if let Some(forwarder) = forwarder {
+            forwarder.abort();
+        }
+        let _ = tx.send(event_line(&event)).await;
+    });
+
+    let body = ReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    (
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
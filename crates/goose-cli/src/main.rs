@@ -1,7 +1,11 @@
 use anyhow::Result;
+use goose::shutdown::{ShutdownGuard, ShutdownSignal};
 use goose_cli::cli::cli;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Ctrl+C is left to sessions, which use it to interrupt a reply
+    let _shutdown =
+        ShutdownGuard::install_for(&[ShutdownSignal::Terminate, ShutdownSignal::Hangup]);
     cli().await
 }
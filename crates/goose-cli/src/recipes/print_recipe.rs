@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use console::style;
-use goose::recipe::Recipe;
+use goose::recipe::{Recipe, RecipeTree};
 
 use crate::recipes::recipe::BUILT_IN_RECIPE_DIR_PARAM;
 
@@ -35,6 +35,31 @@ pub fn print_recipe_explanation(recipe: &Recipe) {
     }
 }
 
+pub fn print_sub_recipe_tree(tree: &RecipeTree) {
+    if tree.children.is_empty() {
+        return;
+    }
+    println!("{}", style("Sub-recipes:").bold());
+    print_sub_recipe_children(tree);
+}
+
+fn print_sub_recipe_children(tree: &RecipeTree) {
+    for child in &tree.children {
+        let path = child
+            .path
+            .as_ref()
+            .map(|p| format!(" ({})", p.display()))
+            .unwrap_or_default();
+        println!(
+            "{}- {}{}",
+            "   ".repeat(child.depth),
+            style(&child.name).cyan(),
+            path
+        );
+        print_sub_recipe_children(child);
+    }
+}
+
 pub fn print_parameters_with_values(params: HashMap<String, String>) {
     for (key, value) in params {
         let label = if key == BUILT_IN_RECIPE_DIR_PARAM {
@@ -3,10 +3,10 @@ use console::style;
 
 use crate::recipes::print_recipe::{
     missing_parameters_command_line, print_parameters_with_values, print_recipe_explanation,
-    print_required_parameters_for_template,
+    print_required_parameters_for_template, print_sub_recipe_tree,
 };
 use crate::recipes::search_recipe::retrieve_recipe_file;
-use goose::recipe::{Recipe, RecipeParameter, RecipeParameterRequirement};
+use goose::recipe::{Recipe, RecipeParameter, RecipeParameterRequirement, RecipeTree};
 use minijinja::{Environment, Error, Template, UndefinedBehavior};
 use serde_json::Value as JsonValue;
 use serde_yaml::Value as YamlValue;
@@ -24,20 +24,23 @@ pub const RECIPE_FILE_EXTENSIONS: &[&str] = &["yaml", "json"];
 ///
 /// # Returns
 ///
-/// The rendered recipe if successful
+/// The rendered recipe if successful, and its tree of sub-recipes when it declares any
 ///
 /// # Errors
 ///
 /// Returns an error if:
 /// - Recipe is not valid
 /// - The required fields are missing
-pub fn load_recipe_as_template(recipe_name: &str, params: Vec<(String, String)>) -> Result<Recipe> {
+pub fn load_recipe_as_template(
+    recipe_name: &str,
+    params: Vec<(String, String)>,
+) -> Result<(Recipe, Option<RecipeTree>)> {
     let (recipe_file_content, recipe_parent_dir) = retrieve_recipe_file(recipe_name)?;
 
     let recipe = validate_recipe_file_parameters(&recipe_file_content)?;
 
     let (params_for_template, missing_params) =
-        apply_values_to_parameters(&params, recipe.parameters, recipe_parent_dir, true)?;
+        apply_values_to_parameters(&params, recipe.parameters, recipe_parent_dir.clone(), true)?;
     if !missing_params.is_empty() {
         return Err(anyhow::anyhow!(
             "Please provide the following parameters in the command line: {}",
@@ -61,8 +64,21 @@ pub fn load_recipe_as_template(recipe_name: &str, params: Vec<(String, String)>)
         println!("{}", style("Parameters used to load this recipe:").bold());
         print_parameters_with_values(params_for_template);
     }
+
+    // Resolve nested sub-recipes up front so depth and cycle problems surface before running
+    let tree = if recipe.sub_recipes.is_some() {
+        let tree = RecipeTree::resolve(
+            recipe.clone(),
+            &recipe_parent_dir,
+            RecipeTree::max_depth_from_config(),
+        )?;
+        print_sub_recipe_tree(&tree);
+        Some(tree)
+    } else {
+        None
+    };
     println!();
-    Ok(recipe)
+    Ok((recipe, tree))
 }
 
 /// Loads and validates a recipe from a YAML or JSON file
@@ -341,7 +357,7 @@ mod tests {
         let (_temp_dir, recipe_path) = setup_recipe_file(instructions_and_parameters);
 
         let params = vec![("my_name".to_string(), "value".to_string())];
-        let recipe = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
+        let (recipe, _) = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
 
         assert_eq!(recipe.title, "Test Recipe");
         assert_eq!(recipe.description, "A test recipe");
@@ -375,7 +391,7 @@ mod tests {
         let (_temp_dir, recipe_path) = setup_recipe_file(instructions_and_parameters);
 
         let params = vec![("my_name".to_string(), "value".to_string())];
-        let recipe = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
+        let (recipe, _) = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
 
         assert_eq!(recipe.title, "Test Recipe");
         assert_eq!(recipe.description, "A test recipe");
@@ -441,7 +457,7 @@ mod tests {
         let (_temp_dir, recipe_path) = setup_recipe_file(instructions_and_parameters);
         let params = vec![("param_without_default".to_string(), "value1".to_string())];
 
-        let recipe = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
+        let (recipe, _) = load_recipe_as_template(recipe_path.to_str().unwrap(), params).unwrap();
 
         assert_eq!(recipe.title, "Test Recipe");
         assert_eq!(recipe.description, "A test recipe");
@@ -466,7 +482,8 @@ mod tests {
             ]"#;
         let (_temp_dir, recipe_path) = setup_recipe_file(instructions_and_parameters);
 
-        let recipe = load_recipe_as_template(recipe_path.to_str().unwrap(), Vec::new()).unwrap();
+        let (recipe, _) =
+            load_recipe_as_template(recipe_path.to_str().unwrap(), Vec::new()).unwrap();
         assert_eq!(recipe.title, "Test Recipe");
         assert_eq!(recipe.description, "A test recipe");
         assert_eq!(recipe.instructions.unwrap(), "Test instructions with ");
@@ -525,7 +542,8 @@ mod tests {
             "#;
         let (_temp_dir, recipe_path) = setup_recipe_file(instructions_and_parameters);
 
-        let recipe = load_recipe_as_template(recipe_path.to_str().unwrap(), Vec::new()).unwrap();
+        let (recipe, _) =
+            load_recipe_as_template(recipe_path.to_str().unwrap(), Vec::new()).unwrap();
         assert_eq!(recipe.instructions.unwrap(), "Test instructions");
         assert!(recipe.parameters.is_none());
     }
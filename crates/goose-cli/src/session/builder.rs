@@ -459,6 +459,20 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         session.agent.override_system_prompt(override_prompt).await;
     }
 
+    // Let editor plugins and scripts drive this session over a local socket
+    if let Ok(socket) = config.get_param::<String>("GOOSE_CONTROL_SOCKET") {
+        if let Err(e) = session.start_control_server(socket.into()) {
+            eprintln!(
+                "{}",
+                style(format!(
+                    "Warning: Failed to start the control socket: {}",
+                    e
+                ))
+                .yellow()
+            );
+        }
+    }
+
     // Display session information unless in quiet mode
     if !session_config.quiet {
         output::display_session_info(
@@ -20,6 +20,9 @@ pub enum InputResult {
     Clear,
     Recipe(Option<String>),
     Summarize,
+    Changes,
+    Checkpoint(String),
+    ReloadExtensions,
 }
 
 #[derive(Debug)]
@@ -95,6 +98,9 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
     const CMD_CLEAR: &str = "/clear";
     const CMD_RECIPE: &str = "/recipe";
     const CMD_SUMMARIZE: &str = "/summarize";
+    const CMD_CHANGES: &str = "/changes";
+    const CMD_CHECKPOINT: &str = "/checkpoint ";
+    const CMD_RELOAD: &str = "/reload";
 
     match input {
         "/exit" | "/quit" => Some(InputResult::Exit),
@@ -139,6 +145,11 @@ fn handle_slash_command(input: &str) -> Option<InputResult> {
         s if s == CMD_CLEAR => Some(InputResult::Clear),
         s if s.starts_with(CMD_RECIPE) => parse_recipe_command(s),
         s if s == CMD_SUMMARIZE => Some(InputResult::Summarize),
+        s if s == CMD_CHANGES => Some(InputResult::Changes),
+        s if s.starts_with(CMD_CHECKPOINT) => Some(InputResult::Checkpoint(
+            s[CMD_CHECKPOINT.len()..].trim().to_string(),
+        )),
+        s if s == CMD_RELOAD => Some(InputResult::ReloadExtensions),
         _ => None,
     }
 }
@@ -248,6 +259,9 @@ fn print_help() {
 /recipe [filepath] - Generate a recipe from the current conversation and save it to the specified filepath (must end with .yaml).
                        If no filepath is provided, it will be saved to ./recipe.yaml.
 /summarize - Summarize the current conversation to reduce context length while preserving key information.
+/changes - Summarize the uncommitted git changes in the working directory and attach the summary to the session.
+/checkpoint <name> - Tag the conversation as it is now, to export or branch from it later (e.g. 'goose session export --checkpoint <name>').
+/reload - Apply changes to the extensions in the config file, starting, stopping and restarting extensions as needed.
 /? or /help - Display this help message
 /clear - Clears the current chat history
 
@@ -493,4 +507,26 @@ mod tests {
         let result = handle_slash_command("  /summarize  ");
         assert!(matches!(result, Some(InputResult::Summarize)));
     }
+    #[test]
+    fn test_changes_command() {
+        let result = handle_slash_command("/changes");
+        assert!(matches!(result, Some(InputResult::Changes)));
+    }
+
+    #[test]
+    fn test_reload_command() {
+        let result = handle_slash_command("/reload");
+        assert!(matches!(result, Some(InputResult::ReloadExtensions)));
+    }
+
+    #[test]
+    fn test_checkpoint_command() {
+        if let Some(InputResult::Checkpoint(name)) =
+            handle_slash_command("/checkpoint before refactor ")
+        {
+            assert_eq!(name, "before refactor");
+        } else {
+            panic!("Expected Checkpoint");
+        }
+    }
 }
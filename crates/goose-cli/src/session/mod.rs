@@ -14,25 +14,35 @@ use goose::permission::permission_confirmation::PrincipalType;
 use goose::permission::Permission;
 use goose::permission::PermissionConfirmation;
 use goose::providers::base::Provider;
+use goose::providers::transcription::apply_transcripts;
 pub use goose::session::Identifier;
 
 use anyhow::{Context, Result};
 use completion::GooseCompleter;
 use etcetera::{choose_app_strategy, AppStrategy};
+use goose::agents::cancellation::session_key;
+use goose::agents::change_review::git_changes_enabled;
+use goose::agents::conversation_template::TemplateTurn;
 use goose::agents::extension::{Envs, ExtensionConfig};
-use goose::agents::{Agent, SessionConfig};
+use goose::agents::extension_prompts::append_prompt_messages;
+use goose::agents::tool_mocks::ToolMocks;
+use goose::agents::{Agent, SessionConfig, TerminationCondition};
 use goose::config::Config;
+use goose::control::{ControlCommand, ControlEvent, ControlServer};
 use goose::message::{Message, MessageContent};
+use goose::recipe::output_check::final_output;
+use goose::recipe::{
+    sub_recipe_instructions, AgentRunner, OutputCheckReport, OutputChecks, RecipeTree, RetryScope,
+};
 use goose::session;
 use input::InputResult;
 use mcp_core::handler::ToolError;
-use mcp_core::prompt::PromptMessage;
 use mcp_core::protocol::JsonRpcMessage;
 use mcp_core::protocol::JsonRpcNotification;
 
 use rand::{distributions::Alphanumeric, Rng};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
@@ -51,6 +61,9 @@ pub struct Session {
     completion_cache: Arc<std::sync::RwLock<CompletionCache>>,
     debug: bool, // New field for debug mode
     run_mode: RunMode,
+    control: Option<ControlServer>,
+    // Messages sent over the control socket while the agent was busy
+    control_messages: VecDeque<String>,
 }
 
 // Cache structure for completion data
@@ -123,6 +136,97 @@ impl Session {
             completion_cache: Arc::new(std::sync::RwLock::new(CompletionCache::new())),
             debug,
             run_mode: RunMode::Normal,
+            control: None,
+            control_messages: VecDeque::new(),
+        }
+    }
+
+    /// Accept messages and cancellation from other processes over a local socket
+    pub fn start_control_server(&mut self, path: PathBuf) -> Result<()> {
+        let control = ControlServer::bind(path)?;
+        control.update_status(|status| {
+            status.session_id = self
+                .session_file
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_string());
+            status.message_count = self.messages.len();
+        });
+        self.control = Some(control);
+        Ok(())
+    }
+
+    fn update_control_status(&self, busy: bool) {
+        if let Some(control) = &self.control {
+            control.update_status(|status| {
+                status.busy = busy;
+                status.message_count = self.messages.len();
+                status.queued_messages = self.control_messages.len();
+            });
+        }
+    }
+
+    fn publish_message(&self, message: &Message) {
+        if let Some(control) = &self.control {
+            control.publish(ControlEvent::Message {
+                message: message.clone(),
+            });
+        }
+    }
+
+    /// Reply to messages sent over the control socket, in the order they arrived. Nobody is
+    /// at the terminal to answer for them, so they are processed as in headless mode.
+    async fn process_control_messages(&mut self) -> Result<()> {
+        loop {
+            if let Some(control) = self.control.as_mut() {
+                while let Some(command) = control.try_recv() {
+                    // A cancel with no reply in progress has nothing to cancel
+                    if let ControlCommand::SendMessage(text) = command {
+                        self.control_messages.push_back(text);
+                    }
+                }
+            }
+            let Some(text) = self.control_messages.pop_front() else {
+                return Ok(());
+            };
+            output::render_text(
+                &format!("Message from the control socket:\n{}", text),
+                Some(Color::Cyan),
+                true,
+            );
+            self.process_message(text).await?;
+        }
+    }
+
+    /// Read the next input at the prompt, queueing messages from the control socket that arrive
+    /// while waiting for it. The editor blocks on the terminal, so it reads on a blocking task and
+    /// is handed back with the input. The queued messages are replied to once the prompt returns,
+    /// as replying may ask for tool confirmations on the terminal the editor still holds.
+    async fn read_input(
+        &mut self,
+        mut editor: rustyline::Editor<GooseCompleter, rustyline::history::DefaultHistory>,
+    ) -> Result<(
+        rustyline::Editor<GooseCompleter, rustyline::history::DefaultHistory>,
+        input::InputResult,
+    )> {
+        let mut reading = tokio::task::spawn_blocking(move || {
+            let input = input::get_input(&mut editor);
+            (editor, input)
+        });
+        loop {
+            tokio::select! {
+                read = &mut reading => {
+                    let (editor, input) = read?;
+                    return Ok((editor, input?));
+                }
+                command = next_control_command(&mut self.control) => {
+                    // A cancel with no reply in progress has nothing to cancel
+                    if let ControlCommand::SendMessage(text) = command {
+                        self.control_messages.push_back(text);
+                        self.update_control_status(false);
+                    }
+                }
+            }
         }
     }
 
@@ -181,6 +285,8 @@ impl Session {
             description: Some(goose::config::DEFAULT_EXTENSION_DESCRIPTION.to_string()),
             // TODO: should set timeout
             timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
+            tool_timeout: None,
+            tool_filter: None,
             bundled: None,
         };
 
@@ -214,6 +320,8 @@ impl Session {
             description: Some(goose::config::DEFAULT_EXTENSION_DESCRIPTION.to_string()),
             // TODO: should set timeout
             timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
+            tool_timeout: None,
+            tool_filter: None,
             bundled: None,
         };
 
@@ -239,6 +347,8 @@ impl Session {
                 display_name: None,
                 // TODO: should set a timeout
                 timeout: Some(goose::config::DEFAULT_EXTENSION_TIMEOUT),
+                tool_timeout: None,
+                tool_filter: None,
                 bundled: None,
             };
             self.agent
@@ -295,14 +405,10 @@ impl Session {
         Ok(None)
     }
 
-    pub async fn get_prompt(&mut self, name: &str, arguments: Value) -> Result<Vec<PromptMessage>> {
-        let result = self.agent.get_prompt(name, arguments).await?;
-        Ok(result.messages)
-    }
-
     /// Process a single message and get the response
     async fn process_message(&mut self, message: String) -> Result<()> {
         self.messages.push(Message::user().with_text(&message));
+        self.publish_message(&self.messages[self.messages.len() - 1]);
         // Get the provider from the agent for description generation
         let provider = self.agent.provider().await?;
 
@@ -384,16 +490,23 @@ impl Session {
 
         output::display_greeting();
         loop {
+            self.process_control_messages().await?;
+
             // Display context usage before each prompt
             self.display_context_usage().await?;
 
-            match input::get_input(&mut editor)? {
+            let (returned, input) = self.read_input(editor).await?;
+            editor = returned;
+            // Messages that arrived while the prompt was open were sent first
+            self.process_control_messages().await?;
+            match input {
                 input::InputResult::Message(content) => {
                     match self.run_mode {
                         RunMode::Normal => {
                             save_history(&mut editor);
 
                             self.messages.push(Message::user().with_text(&content));
+                            self.publish_message(&self.messages[self.messages.len() - 1]);
 
                             // Track the current directory and last instruction in projects.json
                             let session_id = self
@@ -625,9 +738,78 @@ impl Session {
 
                     continue;
                 }
+                InputResult::Changes => {
+                    save_history(&mut editor);
+                    self.print_change_summary().await;
+                    continue;
+                }
+                InputResult::Checkpoint(name) => {
+                    save_history(&mut editor);
+                    match session::checkpoints::tag_checkpoint(
+                        &self.session_file,
+                        &name,
+                        self.messages.len(),
+                    ) {
+                        Ok(checkpoint) => println!(
+                            "{}",
+                            console::style(format!(
+                                "Tagged checkpoint '{}' at message {}",
+                                checkpoint.name, checkpoint.message_count
+                            ))
+                            .green()
+                        ),
+                        Err(e) => println!(
+                            "{}: {}",
+                            console::style("Failed to tag checkpoint").red(),
+                            e
+                        ),
+                    }
+                    continue;
+                }
+                InputResult::ReloadExtensions => {
+                    save_history(&mut editor);
+                    match self.agent.reload_extensions().await {
+                        Ok(summary) if summary.is_empty() => {
+                            println!("{}", console::style("Extensions are up to date").dim())
+                        }
+                        Ok(summary) => {
+                            for (label, names) in [
+                                ("Added", &summary.added),
+                                ("Removed", &summary.removed),
+                                ("Restarted", &summary.restarted),
+                            ] {
+                                if !names.is_empty() {
+                                    println!(
+                                        "{} {}",
+                                        console::style(format!("{}:", label)).green(),
+                                        names.join(", ")
+                                    );
+                                }
+                            }
+                            for failure in &summary.failed {
+                                println!(
+                                    "{} {}: {}",
+                                    console::style("Failed to start").red(),
+                                    failure.extension,
+                                    failure.message
+                                );
+                            }
+                        }
+                        Err(e) => println!(
+                            "{}: {}",
+                            console::style("Failed to reload extensions").red(),
+                            e
+                        ),
+                    }
+                    continue;
+                }
             }
         }
 
+        if git_changes_enabled() {
+            self.print_change_summary().await;
+        }
+
         println!(
             "\nClosing session. Recorded to {}",
             self.session_file.display()
@@ -635,6 +817,26 @@ impl Session {
         Ok(())
     }
 
+    /// Summarize the uncommitted changes in the working directory and print the summary
+    async fn print_change_summary(&self) {
+        output::show_thinking();
+        let summary = self.agent.summarize_changes(Some(&self.session_file)).await;
+        output::hide_thinking();
+
+        match summary {
+            Ok(Some(summary)) => println!("\n{}", summary.to_text()),
+            Ok(None) => println!(
+                "{}",
+                console::style("The working directory is not in a git repository").yellow()
+            ),
+            Err(e) => println!(
+                "{}: {:?}",
+                console::style("Failed to summarize changes").red(),
+                e
+            ),
+        }
+    }
+
     async fn plan_with_reasoner_model(
         &mut self,
         plan_messages: Vec<Message>,
@@ -714,7 +916,42 @@ impl Session {
 
     /// Process a single message and exit
     pub async fn headless(&mut self, message: String) -> Result<()> {
-        self.process_message(message).await
+        self.process_message(message).await?;
+        self.process_control_messages().await
+    }
+
+    /// Process a single message and exit, trying again while the final output fails the checks
+    pub async fn headless_with_output_checks(
+        &mut self,
+        message: String,
+        checks: &OutputChecks,
+    ) -> Result<OutputCheckReport> {
+        let working_dir = std::env::current_dir()?;
+        let mut report = OutputCheckReport::default();
+        self.process_message(message.clone()).await?;
+        while let Some(correction) = checks
+            .review(&mut report, &final_output(&self.messages), &working_dir)
+            .await
+        {
+            output::render_text(
+                &format!(
+                    "Output checks failed on attempt {}, trying again",
+                    report.attempts
+                ),
+                Some(Color::Yellow),
+                true,
+            );
+            match checks.retry.scope {
+                RetryScope::Turn => self.process_message(correction).await?,
+                RetryScope::Recipe => {
+                    self.messages.clear();
+                    self.process_message(format!("{}\n\n{}", message, correction))
+                        .await?;
+                }
+            }
+        }
+        self.process_control_messages().await?;
+        Ok(report)
     }
 
     async fn process_agent_response(&mut self, interactive: bool) -> Result<()> {
@@ -728,11 +965,17 @@ impl Session {
                     working_dir: std::env::current_dir()
                         .expect("failed to get current session working directory"),
                     schedule_id: None,
+                    sampling: None,
+                    tags: Default::default(),
+                    config_overrides: Default::default(),
                 }),
             )
             .await?;
 
         let mut progress_bars = output::McpSpinners::new();
+        self.update_control_status(true);
+        // Whether the text of the response that comes next has been printed as it streamed
+        let mut streamed_text = false;
 
         use futures::StreamExt;
         loop {
@@ -744,8 +987,16 @@ impl Session {
                             if let Some(MessageContent::ToolConfirmationRequest(confirmation)) = message.content.first() {
                                 output::hide_thinking();
 
-                                // Format the confirmation prompt
-                                let prompt = "Goose would like to call the above tool, do you allow?".to_string();
+                                if let Some(impact) = &confirmation.impact {
+                                    output::render_text(impact, Some(Color::Cyan), false);
+                                }
+
+                                // Format the confirmation prompt, which names the host for network extensions
+                                let prompt = confirmation
+                                    .prompt
+                                    .as_deref()
+                                    .map(|prompt| prompt.trim_end_matches(" (y/n):").to_string())
+                                    .unwrap_or_else(|| "Goose would like to call the above tool, do you allow?".to_string());
 
                                 // Get confirmation from user
                                 let permission_result = cliclack::select(prompt)
@@ -863,6 +1114,9 @@ impl Session {
                                             working_dir: std::env::current_dir()
                                                 .expect("failed to get current session working directory"),
                                             schedule_id: None,
+                                            sampling: None,
+                                            tags: Default::default(),
+                                            config_overrides: Default::default(),
                                         }),
                                     )
                                     .await?;
@@ -876,10 +1130,21 @@ impl Session {
 
                                 if interactive {output::hide_thinking()};
                                 let _ = progress_bars.hide();
-                                output::render_message(&message, self.debug);
+                                if std::mem::take(&mut streamed_text) {
+                                    output::render_streamed_message(&message, self.debug);
+                                } else {
+                                    output::render_message(&message, self.debug);
+                                }
+                                self.publish_message(&message);
                                 if interactive {output::show_thinking()};
                             }
                         }
+                        Some(Ok(AgentEvent::TextChunk(text))) => {
+                            if interactive {output::hide_thinking()};
+                            let _ = progress_bars.hide();
+                            streamed_text = true;
+                            output::render_text_chunk(&text);
+                        }
                         Some(Ok(AgentEvent::McpNotification((_id, message)))) => {
                                 if let JsonRpcMessage::Notification(JsonRpcNotification{
                                     method,
@@ -934,6 +1199,78 @@ impl Session {
                                 eprintln!("Model changed to {} in {} mode", model, mode);
                             }
                         }
+                        Some(Ok(AgentEvent::TurnBudget(budget))) => {
+                            if self.debug {
+                                eprintln!("Turn budget set to {} turns", budget.max_turns);
+                            }
+                        }
+                        Some(Ok(AgentEvent::ExtensionsDegraded(warnings))) => {
+                            for warning in warnings {
+                                output::render_extension_warning(&warning);
+                            }
+                        }
+                        Some(Ok(AgentEvent::PolicyViolations(violations))) => {
+                            if self.debug {
+                                for violation in violations {
+                                    eprintln!("Response policy violation ({}): {}", violation.rule, violation.message);
+                                }
+                            }
+                        }
+                        Some(Ok(AgentEvent::InputQueued(input))) => {
+                            if self.debug {
+                                eprintln!("Queued message {} received", input.id);
+                            }
+                        }
+                        Some(Ok(AgentEvent::QuotaWarning(status))) => {
+                            output::render_text(&status.to_string(), Some(Color::Yellow), true);
+                        }
+                        Some(Ok(AgentEvent::ToolRouting(status))) => {
+                            output::render_text(&status.to_string(), Some(Color::Yellow), true);
+                        }
+                        Some(Ok(AgentEvent::UiResources(resources))) => {
+                            for resource in resources {
+                                output::render_text(
+                                    &format!(
+                                        "The tool returned an interactive view that the CLI cannot display: {}",
+                                        resource.uri
+                                    ),
+                                    Some(Color::Yellow),
+                                    true,
+                                );
+                            }
+                        }
+                        Some(Ok(AgentEvent::Cancelled)) => {
+                            output::render_text("Reply cancelled", Some(Color::Yellow), true);
+                        }
+                        Some(Ok(AgentEvent::Transcribed(transcribed))) => {
+                            apply_transcripts(&mut self.messages, &transcribed);
+                        }
+                        Some(Ok(AgentEvent::ContextUsage(usage))) => {
+                            if self.debug {
+                                let extensions: Vec<String> = usage
+                                    .tools_by_extension
+                                    .iter()
+                                    .map(|item| format!("{} {}", item.name, item.tokens))
+                                    .collect();
+                                eprintln!(
+                                    "Context: {} of {} tokens (system prompt {}, tools {} [{}], history {}, resources {})",
+                                    usage.total_tokens,
+                                    usage.context_limit,
+                                    usage.system_prompt_tokens,
+                                    usage.tool_tokens,
+                                    extensions.join(", "),
+                                    usage.history_tokens,
+                                    usage.resource_tokens
+                                );
+                                if let Some(compression) = &usage.compression {
+                                    eprintln!(
+                                        "Compression: saved {} tokens in {} sections",
+                                        compression.tokens_saved(),
+                                        compression.sections
+                                    );
+                                }
+                            }
+                        }
                         Some(Err(e)) => {
                             eprintln!("Error: {}", e);
                             drop(stream);
@@ -958,9 +1295,27 @@ impl Session {
                     }
                     break;
                 }
+                command = next_control_command(&mut self.control) => {
+                    match command {
+                        ControlCommand::SendMessage(text) => {
+                            self.control_messages.push_back(text);
+                            self.update_control_status(true);
+                        }
+                        ControlCommand::Cancel => {
+                            if interactive {output::hide_thinking()};
+                            output::render_text("Cancelling the reply from the control socket.", Some(Color::Yellow), true);
+                            // The reply goes on until the agent has stopped it, sending what the
+                            // turn got done, which is kept like the rest of the reply
+                            if let Some(session_id) = session_key(&session_id) {
+                                self.agent.cancel(&session_id);
+                            }
+                        }
+                    }
+                }
             }
         }
 
+        self.update_control_status(false);
         Ok(())
     }
 
@@ -1128,6 +1483,42 @@ impl Session {
         );
     }
 
+    /// Answer the mocked tools with canned responses instead of running them
+    pub async fn set_tool_mocks(&self, mocks: ToolMocks) {
+        self.agent.set_tool_mocks(Some(mocks)).await;
+    }
+
+    /// Stop replies early once an assistant turn meets any of the conditions
+    pub async fn set_termination_conditions(&self, conditions: Vec<TerminationCondition>) {
+        self.agent.set_termination_conditions(conditions).await;
+    }
+
+    /// Track a checklist that replies must resolve before they finish
+    pub async fn set_acceptance_criteria(&self, criteria: Vec<String>) {
+        self.agent.set_acceptance_criteria(criteria).await;
+    }
+
+    /// Show the model these example turns before the conversation in every reply
+    pub async fn set_conversation_template(&self, turns: Vec<TemplateTurn>) -> Result<()> {
+        self.agent.set_conversation_template(turns).await
+    }
+
+    /// Run the sub-recipes of a recipe, each headless in an agent of its own on this session's
+    /// provider, and tell this session's agent how they went
+    pub async fn run_sub_recipes(&self, tree: &RecipeTree) -> Result<()> {
+        let runner = AgentRunner::new(self.agent.provider().await?);
+        println!("{}", console::style("Running sub-recipes").green());
+        let reports = tree.run_children(&runner).await;
+        for report in &reports {
+            println!("{}", report.summary());
+        }
+        println!();
+        if let Some(instructions) = sub_recipe_instructions(&reports) {
+            self.agent.extend_system_prompt(instructions).await;
+        }
+        Ok(())
+    }
+
     /// Get the session metadata
     pub fn get_metadata(&self) -> Result<session::SessionMetadata> {
         if !self.session_file.exists() {
@@ -1181,41 +1572,41 @@ impl Session {
             let arguments = serde_json::to_value(opts.arguments)
                 .map_err(|e| anyhow::anyhow!("Failed to serialize arguments: {}", e))?;
 
-            match self.get_prompt(&opts.name, arguments).await {
-                Ok(messages) => {
-                    let start_len = self.messages.len();
-                    let mut valid = true;
-                    for (i, prompt_message) in messages.into_iter().enumerate() {
-                        let msg = Message::from(prompt_message);
-                        // ensure we get a User - Assistant - User type pattern
-                        let expected_role = if i % 2 == 0 {
-                            mcp_core::Role::User
-                        } else {
-                            mcp_core::Role::Assistant
-                        };
-
-                        if msg.role != expected_role {
-                            output::render_error(&format!(
-                                "Expected {:?} message at position {}, but found {:?}",
-                                expected_role, i, msg.role
-                            ));
-                            valid = false;
-                            // get rid of everything we added to messages
-                            self.messages.truncate(start_len);
-                            break;
-                        }
+            let Some(extension) = self
+                .get_prompt_info(&opts.name)
+                .await?
+                .and_then(|info| info.extension)
+            else {
+                output::render_error(&format!("Prompt '{}' not found", opts.name));
+                return Ok(());
+            };
 
-                        if msg.role == mcp_core::Role::User {
-                            output::render_message(&msg, self.debug);
-                        }
-                        self.messages.push(msg);
+            match self
+                .agent
+                .run_extension_prompt(&extension, &opts.name, arguments)
+                .await
+            {
+                Ok(messages) => {
+                    // The model answers the prompt, so it has to end with the user's turn
+                    if messages
+                        .last()
+                        .is_none_or(|msg| msg.role != mcp_core::Role::User)
+                    {
+                        output::render_error(&format!(
+                            "Prompt '{}' does not end with a user message",
+                            opts.name
+                        ));
+                        return Ok(());
                     }
 
-                    if valid {
-                        output::show_thinking();
-                        self.process_agent_response(true).await?;
-                        output::hide_thinking();
+                    for msg in messages.iter().filter(|m| m.role == mcp_core::Role::User) {
+                        output::render_message(msg, self.debug);
                     }
+                    append_prompt_messages(&mut self.messages, messages);
+
+                    output::show_thinking();
+                    self.process_agent_response(true).await?;
+                    output::hide_thinking();
                 }
                 Err(e) => output::render_error(&e.to_string()),
             }
@@ -1268,6 +1659,16 @@ impl Session {
     }
 }
 
+/// The next command from the control socket, or never if there is no control socket
+async fn next_control_command(control: &mut Option<ControlServer>) -> ControlCommand {
+    if let Some(control) = control {
+        if let Some(command) = control.recv().await {
+            return command;
+        }
+    }
+    std::future::pending().await
+}
+
 fn get_reasoner() -> Result<Arc<dyn Provider>, anyhow::Error> {
     use goose::model::ModelConfig;
     use goose::providers::create;
@@ -1,5 +1,7 @@
 use bat::WrappingMode;
 use console::{style, Color};
+use goose::agents::extension::ExtensionWarning;
+use goose::agents::streaming::without_text;
 use goose::config::Config;
 use goose::message::{Message, MessageContent, ToolRequest, ToolResponse};
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
@@ -8,7 +10,7 @@ use mcp_core::tool::ToolCall;
 use serde_json::Value;
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Error;
+use std::io::{Error, Write};
 use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
@@ -155,6 +157,21 @@ pub fn render_message(message: &Message, debug: bool) {
     println!();
 }
 
+/// Print the text of a response as it streams, before it can be rendered as markdown
+pub fn render_text_chunk(text: &str) {
+    print!("{}", text);
+    let _ = std::io::stdout().flush();
+}
+
+/// Render a response whose text was printed already as it streamed
+pub fn render_streamed_message(message: &Message, debug: bool) {
+    println!();
+    match without_text(message.clone()) {
+        Some(rest) => render_message(&rest, debug),
+        None => println!(),
+    }
+}
+
 pub fn render_text(text: &str, color: Option<Color>, dim: bool) {
     render_text_no_newlines(format!("\n{}\n\n", text).as_str(), color, dim);
 }
@@ -250,6 +267,15 @@ pub fn render_error(message: &str) {
     println!("\n  {} {}\n", style("error:").red().bold(), message);
 }
 
+pub fn render_extension_warning(warning: &ExtensionWarning) {
+    println!(
+        "\n  {} extension {} is unavailable: {}\n",
+        style("warning:").yellow().bold(),
+        style(&warning.extension).cyan(),
+        style(&warning.message).dim()
+    );
+}
+
 pub fn render_prompts(prompts: &HashMap<String, Vec<String>>) {
     println!();
     for (extension, prompts) in prompts {
@@ -263,12 +263,45 @@ pub unsafe extern "C" fn goose_agent_send_message(
                         full_response.push_str(&json);
                     }
                 }
+                Ok(AgentEvent::TextChunk(_)) => {
+                    // The response follows whole once it is complete
+                }
                 Ok(AgentEvent::McpNotification(_)) => {
                     // TODO: Handle MCP notifications.
                 }
                 Ok(AgentEvent::ModelChange { .. }) => {
                     // Model change events are informational, just continue
                 }
+                Ok(AgentEvent::TurnBudget(_)) => {
+                    // Turn budget events are informational, just continue
+                }
+                Ok(AgentEvent::ExtensionsDegraded(_)) => {
+                    // Degraded extensions are logged by the agent, just continue
+                }
+                Ok(AgentEvent::PolicyViolations(_)) => {
+                    // Policy violations are informational, just continue
+                }
+                Ok(AgentEvent::InputQueued(_)) => {
+                    // Queued input acknowledgements are informational, just continue
+                }
+                Ok(AgentEvent::QuotaWarning(_)) => {
+                    // Quota warnings are informational, just continue
+                }
+                Ok(AgentEvent::ContextUsage(_)) => {
+                    // Context usage is informational, just continue
+                }
+                Ok(AgentEvent::ToolRouting(_)) => {
+                    // Tool routing changes are informational, just continue
+                }
+                Ok(AgentEvent::UiResources(_)) => {
+                    // Interactive views have a text fallback in the tool response
+                }
+                Ok(AgentEvent::Transcribed(_)) => {
+                    // Transcripts are informational, just continue
+                }
+                Ok(AgentEvent::Cancelled) => {
+                    full_response.push_str("\nReply cancelled");
+                }
                 Err(e) => {
                     full_response.push_str(&format!("\nError in message stream: {}", e));
                 }
@@ -138,6 +138,9 @@ async fn execute_recipe(job_id: &str, recipe_path: &str) -> Result<String> {
         id: goose::session::storage::Identifier::Name(session_id.clone()),
         working_dir: current_dir.clone(),
         schedule_id: Some(job_id.to_string()),
+        sampling: None,
+        tags: Default::default(),
+        config_overrides: Default::default(),
     };
 
     // Execute the recipe
@@ -162,12 +165,45 @@ async fn execute_recipe(job_id: &str, recipe_path: &str) -> Result<String> {
                 }
                 messages.push(msg);
             }
+            Ok(AgentEvent::TextChunk(_)) => {
+                // The response follows whole once it is complete
+            }
             Ok(AgentEvent::McpNotification(_)) => {
                 // Handle notifications if needed
             }
             Ok(AgentEvent::ModelChange { .. }) => {
                 // Model change events are informational, just continue
             }
+            Ok(AgentEvent::TurnBudget(_)) => {
+                // Turn budget events are informational, just continue
+            }
+            Ok(AgentEvent::ExtensionsDegraded(_)) => {
+                // Degraded extensions are logged by the agent, just continue
+            }
+            Ok(AgentEvent::PolicyViolations(_)) => {
+                // Policy violations are informational, just continue
+            }
+            Ok(AgentEvent::InputQueued(_)) => {
+                // Queued input acknowledgements are informational, just continue
+            }
+            Ok(AgentEvent::QuotaWarning(status)) => {
+                tracing::warn!("{}", status);
+            }
+            Ok(AgentEvent::ContextUsage(_)) => {
+                // Context usage is informational, just continue
+            }
+            Ok(AgentEvent::ToolRouting(status)) => {
+                tracing::warn!("{}", status);
+            }
+            Ok(AgentEvent::UiResources(_)) => {
+                // Interactive views are only rendered by frontends, just continue
+            }
+            Ok(AgentEvent::Transcribed(_)) => {
+                // Transcripts are only kept by frontends, just continue
+            }
+            Ok(AgentEvent::Cancelled) => {
+                tracing::warn!("The reply was cancelled");
+            }
             Err(e) => {
                 return Err(anyhow!("Error receiving message from agent: {}", e));
             }
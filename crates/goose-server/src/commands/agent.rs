@@ -5,22 +5,33 @@ use crate::state;
 use anyhow::Result;
 use etcetera::{choose_app_strategy, AppStrategy};
 use goose::agents::Agent;
-use goose::config::APP_STRATEGY;
+use goose::config::{ExtensionConfigManager, APP_STRATEGY};
 use goose::scheduler_factory::SchedulerFactory;
+use goose::shutdown::ShutdownGuard;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 
 pub async fn run() -> Result<()> {
     // Initialize logging
     crate::logging::setup_logging(Some("goosed"))?;
+    // Stop the extensions and remove temporary files when the server is stopped
+    let _shutdown = ShutdownGuard::install();
 
     let settings = configuration::Settings::new()?;
 
+    // Extensions that older configs let in with invalid names are renamed before anything reads
+    // them
+    if let Err(e) = ExtensionConfigManager::migrate_names() {
+        warn!("Failed to migrate extension names: {}", e);
+    }
+
     let secret_key =
         std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
 
     let new_agent = Agent::new();
     let agent_ref = Arc::new(new_agent);
+    Agent::spawn_idle_monitor(&agent_ref);
+    Agent::spawn_health_monitor(&agent_ref);
 
     let app_state = state::AppState::new(agent_ref.clone(), secret_key.clone()).await;
 
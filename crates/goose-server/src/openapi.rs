@@ -1,16 +1,37 @@
+use goose::agents::estimate::{CostRange, TurnEstimate};
 use goose::agents::extension::Envs;
+use goose::agents::extension::ToolFilter;
 use goose::agents::extension::ToolInfo;
+use goose::agents::extension_health::{ExtensionState, ExtensionStatus};
+use goose::agents::extension_telemetry::ExtensionHealth;
+use goose::agents::frontend_tools::FrontendToolProgress;
+use goose::agents::input_queue::QueueMode;
+use goose::agents::prompt_history::{
+    PromptDiff, PromptSectionDiff, SectionChange, ToolFingerprint, TurnSnapshot,
+};
 use goose::agents::ExtensionConfig;
 use goose::config::permission::PermissionLevel;
 use goose::config::ExtensionEntry;
 use goose::message::{
-    ContextLengthExceeded, FrontendToolRequest, Message, MessageContent, RedactedThinkingContent,
-    SummarizationRequested, ThinkingContent, ToolConfirmationRequest, ToolRequest, ToolResponse,
+    AudioContent, ContextLengthExceeded, FrontendToolRequest, Message, MessageContent,
+    RedactedThinkingContent, SummarizationRequested, ThinkingContent, ToolConfirmationRequest,
+    ToolRequest, ToolResponse,
 };
 use goose::permission::permission_confirmation::PrincipalType;
+use goose::preflight::{CheckStatus, PreflightCheck, PreflightReport, Remediation};
 use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata};
+use goose::providers::verify::{Verification, VerificationIssue};
+use goose::session::changes::FileChange;
+use goose::session::diff::{
+    MessageDiff, MessageSummary, OutcomeDiff, SessionDiff, SessionOutcome, TokenDiff,
+    ToolCountChange, ToolUsageDiff, UsageDiff,
+};
 use goose::session::info::SessionInfo;
+use goose::session::search::{MessageMatch, SearchMode, SessionSearchResult};
+use goose::session::Anchor;
+use goose::session::ChangeSummary;
 use goose::session::SessionMetadata;
+use goose::session::{Alternative, Checkpoint};
 use mcp_core::content::{Annotations, Content, EmbeddedResource, ImageContent, TextContent};
 use mcp_core::handler::ToolResultSchema;
 use mcp_core::resource::ResourceContents;
@@ -32,12 +53,34 @@ use utoipa::OpenApi;
         super::routes::config_management::get_extensions,
         super::routes::config_management::read_all_config,
         super::routes::config_management::providers,
+        super::routes::config_management::verify_provider,
         super::routes::config_management::upsert_permissions,
+        super::routes::config_management::preflight,
         super::routes::agent::get_tools,
+        super::routes::agent::get_extension_health,
+        super::routes::agent::get_extension_status,
+        super::routes::agent::estimate_turn,
+        super::routes::agent::get_prompt_history,
+        super::routes::agent::get_prompt_diff,
         super::routes::reply::confirm_permission,
+        super::routes::reply::queue_message,
+        super::routes::reply::steer,
+        super::routes::reply::cancel_reply,
+        super::routes::reply::submit_tool_received,
+        super::routes::reply::submit_tool_progress,
         super::routes::context::manage_context,
         super::routes::session::list_sessions,
         super::routes::session::get_session_history,
+        super::routes::session::diff_session_history,
+        super::routes::session::get_session_anchors,
+        super::routes::session::create_session_anchor,
+        super::routes::session::get_session_changes,
+        super::routes::session::get_session_checkpoints,
+        super::routes::session::create_session_checkpoint,
+        super::routes::session::branch_session_checkpoint,
+        super::routes::session::get_session_alternatives,
+        super::routes::session::search_sessions,
+        super::routes::session::regenerate_session_turn,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
         super::routes::schedule::delete_schedule,
@@ -59,11 +102,36 @@ use utoipa::OpenApi;
         super::routes::config_management::ExtensionQuery,
         super::routes::config_management::ToolPermission,
         super::routes::config_management::UpsertPermissionsQuery,
+        PreflightReport,
+        PreflightCheck,
+        CheckStatus,
+        Remediation,
         super::routes::reply::PermissionConfirmationRequest,
+        super::routes::reply::QueueMessageRequest,
+        super::routes::reply::QueueMessageResponse,
+        super::routes::reply::SteerRequest,
+        super::routes::reply::CancelRequest,
+        super::routes::reply::ToolReceivedRequest,
+        super::routes::reply::ToolProgressRequest,
+        FrontendToolProgress,
+        QueueMode,
         super::routes::context::ContextManageRequest,
+        super::routes::agent::EstimateTurnRequest,
         super::routes::context::ContextManageResponse,
         super::routes::session::SessionListResponse,
         super::routes::session::SessionHistoryResponse,
+        super::routes::session::CreateAnchorRequest,
+        super::routes::session::CreateCheckpointRequest,
+        super::routes::session::BranchSessionResponse,
+        super::routes::session::RegenerateTurnRequest,
+        Anchor,
+        ChangeSummary,
+        Checkpoint,
+        Alternative,
+        SessionSearchResult,
+        MessageMatch,
+        SearchMode,
+        FileChange,
         Message,
         MessageContent,
         Content,
@@ -81,20 +149,43 @@ use utoipa::OpenApi;
         ResourceContents,
         ContextLengthExceeded,
         SummarizationRequested,
+        AudioContent,
         Role,
         ProviderMetadata,
+        Verification,
+        VerificationIssue,
         ExtensionEntry,
         ExtensionConfig,
         ConfigKey,
         Envs,
+        ToolFilter,
         Tool,
         ToolAnnotations,
         ToolInfo,
+        ExtensionHealth,
+        ExtensionStatus,
+        ExtensionState,
+        TurnEstimate,
+        CostRange,
+        TurnSnapshot,
+        ToolFingerprint,
+        PromptDiff,
+        PromptSectionDiff,
+        SectionChange,
         PermissionLevel,
         PrincipalType,
         ModelInfo,
         SessionInfo,
         SessionMetadata,
+        SessionDiff,
+        MessageDiff,
+        MessageSummary,
+        ToolUsageDiff,
+        ToolCountChange,
+        UsageDiff,
+        TokenDiff,
+        OutcomeDiff,
+        SessionOutcome,
         super::routes::schedule::CreateScheduleRequest,
         super::routes::schedule::UpdateScheduleRequest,
         super::routes::schedule::KillJobResponse,
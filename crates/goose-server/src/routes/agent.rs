@@ -8,15 +8,24 @@ use axum::{
 };
 use goose::config::Config;
 use goose::config::PermissionManager;
+use goose::message::Message;
 use goose::model::ModelConfig;
 use goose::providers::create;
 use goose::{
-    agents::{extension::ToolInfo, extension_manager::get_parameter_names},
+    agents::{
+        estimate::TurnEstimate,
+        extension::ToolInfo,
+        extension_health::ExtensionStatus,
+        extension_manager::get_parameter_names,
+        extension_telemetry::ExtensionHealth,
+        prompt_history::{PromptDiff, TurnSnapshot},
+    },
     config::permission::PermissionLevel,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use utoipa::ToSchema;
 
 #[derive(Serialize)]
 struct VersionsResponse {
@@ -67,6 +76,18 @@ pub struct GetToolsQuery {
     extension_name: Option<String>,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EstimateTurnRequest {
+    /// The conversation as it would be sent, including the next user message
+    pub messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+pub struct PromptDiffQuery {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
 async fn get_versions() -> Json<VersionsResponse> {
     let versions = ["goose".to_string()];
     let default_version = "goose".to_string();
@@ -173,6 +194,141 @@ async fn get_tools(
     Ok(Json(tools))
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/extension_health",
+    responses(
+        (status = 200, description = "Extension health retrieved successfully", body = Vec<ExtensionHealth>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_extension_health(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ExtensionHealth>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    Ok(Json(agent.get_extension_health().await))
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/extension_status",
+    responses(
+        (status = 200, description = "Extension status retrieved successfully", body = Vec<ExtensionStatus>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_extension_status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ExtensionStatus>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    Ok(Json(agent.list_extension_status().await))
+}
+
+#[utoipa::path(
+    post,
+    path = "/agent/estimate",
+    request_body = EstimateTurnRequest,
+    responses(
+        (status = 200, description = "Estimate of the next turn, without calling the provider", body = TurnEstimate),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+async fn estimate_turn(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<EstimateTurnRequest>,
+) -> Result<Json<TurnEstimate>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    agent
+        .estimate_turn(&payload.messages)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::error!("Failed to estimate turn: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/prompt_history",
+    responses(
+        (status = 200, description = "System prompt and tools of the most recent turns", body = Vec<TurnSnapshot>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_prompt_history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<TurnSnapshot>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    Ok(Json(agent.prompt_snapshots().await))
+}
+
+#[utoipa::path(
+    get,
+    path = "/agent/prompt_diff",
+    params(
+        ("from" = Option<u64>, Query, description = "Turn to compare from, defaults to the turn before `to`"),
+        ("to" = Option<u64>, Query, description = "Turn to compare to, defaults to the latest turn")
+    ),
+    responses(
+        (status = 200, description = "Changes to the system prompt and tools between the turns", body = PromptDiff),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "A turn is not in the recorded history"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_prompt_diff(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PromptDiffQuery>,
+) -> Result<Json<PromptDiff>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    agent
+        .diff_prompt_turns(query.from, query.to)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
 #[utoipa::path(
     post,
     path = "/agent/update_provider",
@@ -223,6 +379,11 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/agent/providers", get(list_providers))
         .route("/agent/prompt", post(extend_prompt))
         .route("/agent/tools", get(get_tools))
+        .route("/agent/extension_health", get(get_extension_health))
+        .route("/agent/extension_status", get(get_extension_status))
+        .route("/agent/estimate", post(estimate_turn))
+        .route("/agent/prompt_history", get(get_prompt_history))
+        .route("/agent/prompt_diff", get(get_prompt_diff))
         .route("/agent/update_provider", post(update_agent_provider))
         .with_state(state)
 }
@@ -2,18 +2,21 @@ use super::utils::verify_secret_key;
 use crate::routes::utils::check_provider_configured;
 use crate::state::AppState;
 use axum::{
-    extract::State,
+    extract::{Path, Query, State},
     routing::{delete, get, post},
     Json, Router,
 };
 use etcetera::{choose_app_strategy, AppStrategy};
+use goose::agents::extension_names::ExtensionNameError;
 use goose::config::Config;
 use goose::config::APP_STRATEGY;
 use goose::config::{extensions::name_to_key, PermissionManager};
 use goose::config::{ExtensionConfigManager, ExtensionEntry};
 use goose::model::ModelConfig;
+use goose::preflight::{run_preflight, PreflightReport};
 use goose::providers::base::ProviderMetadata;
 use goose::providers::providers as get_providers;
+use goose::providers::verify::Verification;
 use goose::{agents::ExtensionConfig, config::permission::PermissionLevel};
 use http::{HeaderMap, StatusCode};
 use serde::{Deserialize, Serialize};
@@ -234,6 +237,7 @@ pub async fn add_extension(
                 Ok(Json(format!("Added extension {}", extension_query.name)))
             }
         }
+        Err(e) if e.is::<ExtensionNameError>() => Err(StatusCode::BAD_REQUEST),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
@@ -314,6 +318,30 @@ pub async fn providers(
     Ok(Json(providers_response))
 }
 
+#[utoipa::path(
+    post,
+    path = "/config/providers/{name}/verify",
+    params(
+        ("name" = String, Path, description = "Name of the provider to verify")
+    ),
+    responses(
+        (status = 200, description = "Provider checked, see the issue for any problem", body = Verification),
+        (status = 404, description = "Unknown provider")
+    )
+)]
+pub async fn verify_provider(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<Json<Verification>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let verification = goose::providers::verify(&name)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(verification))
+}
+
 #[utoipa::path(
     post,
     path = "/config/init",
@@ -461,6 +489,37 @@ pub async fn get_current_model(
     })))
 }
 
+#[derive(Deserialize)]
+pub struct PreflightQuery {
+    working_dir: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/config/preflight",
+    params(
+        ("working_dir" = Option<String>, Query, description = "Working directory of the session, the server's if not set")
+    ),
+    responses(
+        (status = 200, description = "Environment checked", body = PreflightReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn preflight(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<PreflightQuery>,
+) -> Result<Json<PreflightReport>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let working_dir = match query.working_dir {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => std::env::current_dir().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+    };
+    Ok(Json(run_preflight(&working_dir).await))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/config", get(read_all_config))
@@ -471,10 +530,12 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/extensions", post(add_extension))
         .route("/config/extensions/{name}", delete(remove_extension))
         .route("/config/providers", get(providers))
+        .route("/config/providers/{name}/verify", post(verify_provider))
         .route("/config/init", post(init_config))
         .route("/config/backup", post(backup_config))
         .route("/config/permissions", post(upsert_permissions))
         .route("/config/current-model", get(get_current_model))
+        .route("/config/preflight", get(preflight))
         .with_state(state)
 }
 
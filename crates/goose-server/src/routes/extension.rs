@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::env;
 use std::path::Path;
 use std::sync::Arc;
@@ -6,7 +7,10 @@ use std::sync::OnceLock;
 use super::utils::verify_secret_key;
 use crate::state::AppState;
 use axum::{extract::State, routing::post, Json, Router};
-use goose::agents::{extension::Envs, ExtensionConfig};
+use goose::agents::{
+    extension::{Envs, ReloadSummary, ToolFilter},
+    ExtensionConfig,
+};
 use http::{HeaderMap, StatusCode};
 use serde::{Deserialize, Serialize};
 use tracing;
@@ -29,6 +33,30 @@ enum ExtensionConfigRequest {
         #[serde(default)]
         env_keys: Vec<String>,
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
+    },
+    /// WebSocket extension, reconnected when the connection drops.
+    #[serde(rename = "websocket")]
+    WebSocket {
+        /// The name to identify this extension
+        name: String,
+        /// The `ws://` or `wss://` endpoint of the extension.
+        uri: String,
+        /// Headers sent when connecting.
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
     },
     /// Standard I/O (stdio) extension.
     #[serde(rename = "stdio")]
@@ -47,6 +75,12 @@ enum ExtensionConfigRequest {
         #[serde(default)]
         env_keys: Vec<String>,
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
     },
     /// Built-in extension that is part of the goose binary.
     #[serde(rename = "builtin")]
@@ -55,6 +89,12 @@ enum ExtensionConfigRequest {
         name: String,
         display_name: Option<String>,
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
     },
     /// Frontend extension that provides tools to be executed by the frontend.
     #[serde(rename = "frontend")]
@@ -167,6 +207,8 @@ async fn add_extension(
             envs,
             env_keys,
             timeout,
+            tool_timeout,
+            tool_filter,
         } => ExtensionConfig::Sse {
             name,
             uri,
@@ -174,6 +216,25 @@ async fn add_extension(
             env_keys,
             description: None,
             timeout,
+            tool_timeout,
+            tool_filter,
+            bundled: None,
+        },
+        ExtensionConfigRequest::WebSocket {
+            name,
+            uri,
+            headers,
+            timeout,
+            tool_timeout,
+            tool_filter,
+        } => ExtensionConfig::WebSocket {
+            name,
+            uri,
+            headers,
+            description: None,
+            timeout,
+            tool_timeout,
+            tool_filter,
             bundled: None,
         },
         ExtensionConfigRequest::Stdio {
@@ -183,6 +244,8 @@ async fn add_extension(
             envs,
             env_keys,
             timeout,
+            tool_timeout,
+            tool_filter,
         } => {
             // TODO: We can uncomment once bugs are fixed. Check allowlist for Stdio extensions
             // if !is_command_allowed(&cmd, &args) {
@@ -204,6 +267,8 @@ async fn add_extension(
                 envs,
                 env_keys,
                 timeout,
+                tool_timeout,
+                tool_filter,
                 bundled: None,
             }
         }
@@ -211,10 +276,14 @@ async fn add_extension(
             name,
             display_name,
             timeout,
+            tool_timeout,
+            tool_filter,
         } => ExtensionConfig::Builtin {
             name,
             display_name,
             timeout,
+            tool_timeout,
+            tool_filter,
             bundled: None,
         },
         ExtensionConfigRequest::Frontend {
@@ -280,11 +349,29 @@ async fn remove_extension(
     }
 }
 
+/// Handler for applying changes to the extension config on disk to the running agent
+async fn reload_extensions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadSummary>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    agent.reload_extensions().await.map(Json).map_err(|e| {
+        tracing::error!("Failed to reload extensions: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
 /// Registers the extension management routes with the Axum router.
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/extensions/add", post(add_extension))
         .route("/extensions/remove", post(remove_extension))
+        .route("/extensions/reload", post(reload_extensions))
         .with_state(state)
 }
 
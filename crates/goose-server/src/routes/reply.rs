@@ -10,9 +10,24 @@ use axum::{
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
 use goose::{
-    agents::{AgentEvent, SessionConfig},
+    agents::{
+        context_usage::ContextUsage,
+        extension::ExtensionWarning,
+        frontend_tools::FrontendToolProgress,
+        input_queue::{QueueMode, QueuedInput},
+        response_policy::PolicyViolation,
+        router_fallback::ToolRoutingStatus,
+        ui_resources::UiResource,
+        AgentEvent, SessionConfig, TurnBudget,
+    },
+    config::ConfigOverrides,
     message::{Message, MessageContent},
     permission::permission_confirmation::PrincipalType,
+    providers::{
+        attribution::AttributionTags,
+        quota::QuotaStatus,
+        transcription::{apply_transcripts, TranscribedMessage},
+    },
 };
 use goose::{
     permission::{Permission, PermissionConfirmation},
@@ -40,6 +55,12 @@ struct ChatRequest {
     messages: Vec<Message>,
     session_id: Option<String>,
     session_working_dir: String,
+    /// Attribution tags charged with the usage of the reply, such as team or user id
+    #[serde(default)]
+    tags: AttributionTags,
+    /// Config values this session uses instead of the server's, such as `GOOSE_MODE`
+    #[serde(default)]
+    config_overrides: ConfigOverrides,
 }
 
 pub struct SseResponse {
@@ -82,6 +103,10 @@ enum MessageEvent {
     Message {
         message: Message,
     },
+    /// Text of the response being written; the response follows as a `Message`
+    TextChunk {
+        text: String,
+    },
     Error {
         error: String,
     },
@@ -92,6 +117,36 @@ enum MessageEvent {
         model: String,
         mode: String,
     },
+    TurnBudget {
+        budget: TurnBudget,
+    },
+    ExtensionsDegraded {
+        warnings: Vec<ExtensionWarning>,
+    },
+    PolicyViolations {
+        violations: Vec<PolicyViolation>,
+    },
+    InputQueued {
+        input: QueuedInput,
+    },
+    QuotaWarning {
+        status: QuotaStatus,
+    },
+    ContextUsage {
+        usage: ContextUsage,
+    },
+    ToolRouting {
+        status: ToolRoutingStatus,
+    },
+    UiResources {
+        resources: Vec<UiResource>,
+    },
+    /// User messages whose audio was transcribed, to keep in place of the audio
+    Transcribed {
+        messages: Vec<TranscribedMessage>,
+    },
+    /// The reply was cancelled, and no further events follow
+    Cancelled,
     Notification {
         request_id: String,
         message: JsonRpcMessage,
@@ -123,6 +178,8 @@ async fn handler(
 
     let messages = request.messages;
     let session_working_dir = request.session_working_dir;
+    let tags = request.tags;
+    let config_overrides = request.config_overrides;
 
     let session_id = request
         .session_id
@@ -182,6 +239,9 @@ async fn handler(
                     id: session::Identifier::Name(session_id.clone()),
                     working_dir: PathBuf::from(session_working_dir),
                     schedule_id: None,
+                    sampling: None,
+                    tags,
+                    config_overrides,
                 }),
             )
             .await
@@ -237,6 +297,19 @@ async fn handler(
                                 }
                             });
                         }
+                        Ok(Some(Ok(AgentEvent::TextChunk(text)))) => {
+                            // Only the complete response is kept in the session
+                            if let Err(e) = stream_event(MessageEvent::TextChunk { text }, &tx).await {
+                                tracing::error!("Error sending text through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                                break;
+                            }
+                        }
                         Ok(Some(Ok(AgentEvent::ModelChange { model, mode }))) => {
                             if let Err(e) = stream_event(MessageEvent::ModelChange { model, mode }, &tx).await {
                                 tracing::error!("Error sending model change through channel: {}", e);
@@ -248,6 +321,118 @@ async fn handler(
                                 ).await;
                             }
                         }
+                        Ok(Some(Ok(AgentEvent::TurnBudget(budget)))) => {
+                            if let Err(e) = stream_event(MessageEvent::TurnBudget { budget }, &tx).await {
+                                tracing::error!("Error sending turn budget through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::ExtensionsDegraded(warnings)))) => {
+                            if let Err(e) = stream_event(MessageEvent::ExtensionsDegraded { warnings }, &tx).await {
+                                tracing::error!("Error sending degraded extensions through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::PolicyViolations(violations)))) => {
+                            if let Err(e) = stream_event(MessageEvent::PolicyViolations { violations }, &tx).await {
+                                tracing::error!("Error sending policy violations through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::InputQueued(input)))) => {
+                            if let Err(e) = stream_event(MessageEvent::InputQueued { input }, &tx).await {
+                                tracing::error!("Error sending queued input acknowledgement through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::QuotaWarning(status)))) => {
+                            if let Err(e) = stream_event(MessageEvent::QuotaWarning { status }, &tx).await {
+                                tracing::error!("Error sending quota warning through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::ContextUsage(usage)))) => {
+                            if let Err(e) = stream_event(MessageEvent::ContextUsage { usage }, &tx).await {
+                                tracing::error!("Error sending context usage through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::ToolRouting(status)))) => {
+                            if let Err(e) = stream_event(MessageEvent::ToolRouting { status }, &tx).await {
+                                tracing::error!("Error sending tool routing status through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::UiResources(resources)))) => {
+                            if let Err(e) = stream_event(MessageEvent::UiResources { resources }, &tx).await {
+                                tracing::error!("Error sending UI resources through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::Transcribed(messages)))) => {
+                            // The session keeps the transcript rather than the audio
+                            apply_transcripts(&mut all_messages, &messages);
+                            if let Err(e) = stream_event(MessageEvent::Transcribed { messages }, &tx).await {
+                                tracing::error!("Error sending transcripts through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
+                        Ok(Some(Ok(AgentEvent::Cancelled))) => {
+                            if let Err(e) = stream_event(MessageEvent::Cancelled, &tx).await {
+                                tracing::error!("Error sending cancellation through channel: {}", e);
+                                let _ = stream_event(
+                                    MessageEvent::Error {
+                                        error: e.to_string(),
+                                    },
+                                    &tx,
+                                ).await;
+                            }
+                        }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
                             if let Err(e) = stream_event(MessageEvent::Notification{
                                 request_id: request_id.clone(),
@@ -277,6 +462,8 @@ async fn handler(
                         }
                         Err(_) => { // Heartbeat, used to detect disconnected clients
                             if tx.is_closed() {
+                                // Stop the tool calls of a reply nobody is listening to
+                                agent.cancel(&session_id);
                                 break;
                             }
                             continue;
@@ -303,6 +490,12 @@ struct AskRequest {
     prompt: String,
     session_id: Option<String>,
     session_working_dir: String,
+    /// Attribution tags charged with the usage of the reply, such as team or user id
+    #[serde(default)]
+    tags: AttributionTags,
+    /// Config values this session uses instead of the server's, such as `GOOSE_MODE`
+    #[serde(default)]
+    config_overrides: ConfigOverrides,
 }
 
 #[derive(Debug, Serialize)]
@@ -318,6 +511,8 @@ async fn ask_handler(
     verify_secret_key(&headers, &state)?;
 
     let session_working_dir = request.session_working_dir;
+    let tags = request.tags;
+    let config_overrides = request.config_overrides;
 
     let session_id = request
         .session_id
@@ -340,6 +535,9 @@ async fn ask_handler(
                 id: session::Identifier::Name(session_id.clone()),
                 working_dir: PathBuf::from(session_working_dir),
                 schedule_id: None,
+                sampling: None,
+                tags,
+                config_overrides,
             }),
         )
         .await
@@ -367,10 +565,43 @@ async fn ask_handler(
                     }
                 }
             }
+            Ok(AgentEvent::TextChunk(_)) => {
+                // The response follows whole once it is complete
+            }
             Ok(AgentEvent::ModelChange { model, mode }) => {
                 // Log model change for non-streaming
                 tracing::info!("Model changed to {} in {} mode", model, mode);
             }
+            Ok(AgentEvent::TurnBudget(budget)) => {
+                tracing::info!("Turn budget set to {} turns", budget.max_turns);
+            }
+            Ok(AgentEvent::ExtensionsDegraded(warnings)) => {
+                tracing::warn!("Extensions unavailable: {:?}", warnings);
+            }
+            Ok(AgentEvent::PolicyViolations(violations)) => {
+                tracing::warn!("Response policy violations: {:?}", violations);
+            }
+            Ok(AgentEvent::InputQueued(input)) => {
+                tracing::info!("Queued message {} received", input.id);
+            }
+            Ok(AgentEvent::QuotaWarning(status)) => {
+                tracing::warn!("{}", status);
+            }
+            Ok(AgentEvent::ContextUsage(_)) => {
+                // Context usage is only streamed to clients
+            }
+            Ok(AgentEvent::ToolRouting(status)) => {
+                tracing::warn!("{}", status);
+            }
+            Ok(AgentEvent::UiResources(_)) => {
+                // Interactive views are only streamed to clients
+            }
+            Ok(AgentEvent::Transcribed(transcribed)) => {
+                apply_transcripts(&mut all_messages, &transcribed);
+            }
+            Ok(AgentEvent::Cancelled) => {
+                tracing::info!("as_ai reply was cancelled");
+            }
             Ok(AgentEvent::McpNotification(n)) => {
                 // Handle notifications if needed
                 tracing::info!("Received notification: {:?}", n);
@@ -455,6 +686,115 @@ pub async fn confirm_permission(
     Ok(Json(Value::Object(serde_json::Map::new())))
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct QueueMessageRequest {
+    session_id: String,
+    message: Message,
+    /// Defaults to GOOSE_INPUT_QUEUE_MODE
+    mode: Option<QueueMode>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueueMessageResponse {
+    id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/queue",
+    request_body = QueueMessageRequest,
+    responses(
+        (status = 200, description = "Message queued for the running reply", body = QueueMessageResponse),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+pub async fn queue_message(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<QueueMessageRequest>,
+) -> Result<Json<QueueMessageResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    let id = agent
+        .queue_message(&request.session_id, request.message, request.mode)
+        .await;
+    Ok(Json(QueueMessageResponse { id }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SteerRequest {
+    session_id: String,
+    note: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/steer",
+    request_body = SteerRequest,
+    responses(
+        (status = 200, description = "Note queued to steer the running reply", body = QueueMessageResponse),
+        (status = 400, description = "Empty note"),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+pub async fn steer(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<SteerRequest>,
+) -> Result<Json<QueueMessageResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    if request.note.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    let id = agent.steer(&request.session_id, &request.note).await;
+    Ok(Json(QueueMessageResponse { id }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CancelRequest {
+    session_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/cancel",
+    request_body = CancelRequest,
+    responses(
+        (status = 200, description = "The running replies of the session are being cancelled", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+pub async fn cancel_reply(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<CancelRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    agent.cancel(&request.session_id);
+    Ok(Json(Value::Object(serde_json::Map::new())))
+}
+
 #[derive(Debug, Deserialize)]
 struct ToolResultRequest {
     id: String,
@@ -493,12 +833,82 @@ async fn submit_tool_result(
     Ok(Json(json!({"status": "ok"})))
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ToolReceivedRequest {
+    /// The id of the frontend tool request
+    id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tool_received",
+    request_body = ToolReceivedRequest,
+    responses(
+        (status = 200, description = "Frontend tool call acknowledged", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+pub async fn submit_tool_received(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ToolReceivedRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    agent.handle_tool_received(request.id).await;
+    Ok(Json(json!({"status": "ok"})))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ToolProgressRequest {
+    /// The id of the frontend tool request
+    id: String,
+    progress: FrontendToolProgress,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tool_progress",
+    request_body = ToolProgressRequest,
+    responses(
+        (status = 200, description = "Progress of the frontend tool call passed on", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+pub async fn submit_tool_progress(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ToolProgressRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    agent
+        .handle_tool_progress(request.id, request.progress)
+        .await;
+    Ok(Json(json!({"status": "ok"})))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/reply", post(handler))
         .route("/ask", post(ask_handler))
         .route("/confirm", post(confirm_permission))
+        .route("/queue", post(queue_message))
+        .route("/steer", post(steer))
+        .route("/cancel", post(cancel_reply))
         .route("/tool_result", post(submit_tool_result))
+        .route("/tool_received", post(submit_tool_received))
+        .route("/tool_progress", post(submit_tool_progress))
         .with_state(state)
 }
 
@@ -3,16 +3,23 @@ use std::sync::Arc;
 
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
-    routing::get,
+    routing::{get, post},
     Json, Router,
 };
 use goose::message::Message;
 use goose::session;
+use goose::session::alternatives::{list_alternatives, Alternative};
+use goose::session::anchors::{anchor_message, list_anchors, Anchor};
+use goose::session::changes::{read_change_summary, ChangeSummary};
+use goose::session::checkpoints::{
+    branch_from_checkpoint, list_checkpoints, tag_checkpoint, Checkpoint,
+};
 use goose::session::info::{get_session_info, SessionInfo, SortOrder};
-use goose::session::SessionMetadata;
-use serde::Serialize;
+use goose::session::search::{SearchMode, SessionSearchResult};
+use goose::session::{diff_sessions, SessionDiff, SessionMetadata, SessionSnapshot};
+use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 #[derive(Serialize, ToSchema)]
@@ -104,10 +111,456 @@ async fn get_session_history(
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/diff/{other_session_id}",
+    params(
+        ("session_id" = String, Path, description = "The session to compare from"),
+        ("other_session_id" = String, Path, description = "The session to compare against")
+    ),
+    responses(
+        (status = 200, description = "Session diff computed successfully", body = SessionDiff),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Compare two sessions
+async fn diff_session_history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((session_id, other_session_id)): Path<(String, String)>,
+) -> Result<Json<SessionDiff>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let load = |id: String| {
+        let session_path = session::get_path(session::Identifier::Name(id.clone()));
+        SessionSnapshot::load(id, &session_path).map_err(|e| {
+            tracing::error!("Failed to load session for diff: {:?}", e);
+            StatusCode::NOT_FOUND
+        })
+    };
+    let left = load(session_id)?;
+    let right = load(other_session_id)?;
+
+    Ok(Json(diff_sessions(&left, &right)))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAnchorRequest {
+    /// Name of the anchor, replacing any anchor with the same name
+    name: String,
+    /// Index of the message in the session to anchor
+    message_index: usize,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/anchors",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session anchors retrieved successfully", body = [Anchor]),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// List the anchors in a session
+async fn get_session_anchors(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<Anchor>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let anchors = list_anchors(&session_path).map_err(|e| {
+        tracing::error!("Failed to read session anchors: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(anchors))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/anchors",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    request_body = CreateAnchorRequest,
+    responses(
+        (status = 200, description = "Message anchored successfully", body = Anchor),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or message not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Anchor a message of a session under a name
+async fn create_session_anchor(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<CreateAnchorRequest>,
+) -> Result<Json<Anchor>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let anchor =
+        anchor_message(&session_path, &request.name, request.message_index).map_err(|e| {
+            tracing::error!("Failed to anchor message: {:?}", e);
+            StatusCode::NOT_FOUND
+        })?;
+    Ok(Json(anchor))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/changes",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session change summary retrieved successfully", body = ChangeSummary),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "No changes have been summarized for the session"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Get the last summary of the working tree changes attached to a session
+async fn get_session_changes(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<Json<ChangeSummary>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    read_change_summary(&session_path)
+        .map_err(|e| {
+            tracing::error!("Failed to read session change summary: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateCheckpointRequest {
+    /// Name of the checkpoint, replacing any checkpoint with the same name
+    name: String,
+    /// Number of messages at the checkpoint, defaults to all messages in the session
+    message_count: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BranchSessionResponse {
+    /// Identifier of the new session
+    session_id: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/checkpoints",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Session checkpoints retrieved successfully", body = [Checkpoint]),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// List the checkpoints tagged in a session
+async fn get_session_checkpoints(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<Checkpoint>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let checkpoints = list_checkpoints(&session_path).map_err(|e| {
+        tracing::error!("Failed to read session checkpoints: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(checkpoints))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/checkpoints",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    request_body = CreateCheckpointRequest,
+    responses(
+        (status = 200, description = "Checkpoint tagged successfully", body = Checkpoint),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or message not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Tag a point in the history of a session
+async fn create_session_checkpoint(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Json(request): Json<CreateCheckpointRequest>,
+) -> Result<Json<Checkpoint>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let message_count = match request.message_count {
+        Some(count) => count,
+        None => session::read_messages(&session_path)
+            .map_err(|_| StatusCode::NOT_FOUND)?
+            .len(),
+    };
+    let checkpoint = tag_checkpoint(&session_path, &request.name, message_count).map_err(|e| {
+        tracing::error!("Failed to tag checkpoint: {:?}", e);
+        StatusCode::NOT_FOUND
+    })?;
+    Ok(Json(checkpoint))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/checkpoints/{name}/branch",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("name" = String, Path, description = "Name of the checkpoint to branch from")
+    ),
+    responses(
+        (status = 200, description = "Session branched successfully", body = BranchSessionResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or checkpoint not found")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Start a new session from a session as it was at a checkpoint
+async fn branch_session_checkpoint(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((session_id, name)): Path<(String, String)>,
+) -> Result<Json<BranchSessionResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let new_session_id = session::generate_session_id();
+    let new_session_path = session::get_path(session::Identifier::Name(new_session_id.clone()));
+    branch_from_checkpoint(&session_path, &name, &new_session_path).map_err(|e| {
+        tracing::error!("Failed to branch session: {:?}", e);
+        StatusCode::NOT_FOUND
+    })?;
+    Ok(Json(BranchSessionResponse {
+        session_id: new_session_id,
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RegenerateTurnRequest {
+    /// Provider to generate the turn with
+    provider: String,
+    /// Model of the provider to generate the turn with
+    model: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/alternatives",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session")
+    ),
+    responses(
+        (status = 200, description = "Regenerated turns retrieved successfully", body = [Alternative]),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// List the assistant turns of a session that were generated again with other models
+async fn get_session_alternatives(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<Alternative>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let alternatives = list_alternatives(&session_path).map_err(|e| {
+        tracing::error!("Failed to read session alternatives: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(alternatives))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sessions/{session_id}/messages/{message_index}/regenerate",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ("message_index" = usize, Path, description = "Index of the assistant message to generate again")
+    ),
+    request_body = RegenerateTurnRequest,
+    responses(
+        (status = 200, description = "Turn generated again and recorded alongside the original", body = Alternative),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Failed to generate the turn")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Generate an assistant turn again with another provider or model, from the same history
+async fn regenerate_session_turn(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((session_id, message_index)): Path<(String, usize)>,
+    Json(request): Json<RegenerateTurnRequest>,
+) -> Result<Json<Alternative>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    let session_path = session::get_path(session::Identifier::Name(session_id));
+    let alternative = agent
+        .regenerate_turn(
+            &session_path,
+            message_index,
+            &request.provider,
+            &request.model,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to regenerate turn: {:?}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(alternative))
+}
+
+/// Sessions returned by a search when no limit is given
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+#[derive(Deserialize)]
+pub struct SearchSessionsQuery {
+    query: String,
+    #[serde(default)]
+    mode: SearchMode,
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/sessions/search",
+    params(
+        ("query" = String, Query, description = "Words to search for, or a description of the session for semantic search"),
+        ("mode" = Option<SearchMode>, Query, description = "keyword (default) or semantic"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of sessions to return")
+    ),
+    responses(
+        (status = 200, description = "Matching sessions, best first", body = [SessionSearchResult]),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 424, description = "Agent not initialized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Search past sessions by their description and messages
+async fn search_sessions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<SearchSessionsQuery>,
+) -> Result<Json<Vec<SessionSearchResult>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let results = match query.mode {
+        SearchMode::Keyword => session::search_sessions(&query.query, limit),
+        SearchMode::Semantic => {
+            let agent = state
+                .get_agent()
+                .await
+                .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+            agent.search_sessions(&query.query, query.mode, limit).await
+        }
+    }
+    .map_err(|e| {
+        tracing::error!("Failed to search sessions: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(results))
+}
+
 // Configure routes for this module
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/sessions", get(list_sessions))
+        .route("/sessions/search", get(search_sessions))
         .route("/sessions/{session_id}", get(get_session_history))
+        .route(
+            "/sessions/{session_id}/diff/{other_session_id}",
+            get(diff_session_history),
+        )
+        .route(
+            "/sessions/{session_id}/anchors",
+            get(get_session_anchors).post(create_session_anchor),
+        )
+        .route("/sessions/{session_id}/changes", get(get_session_changes))
+        .route(
+            "/sessions/{session_id}/checkpoints",
+            get(get_session_checkpoints).post(create_session_checkpoint),
+        )
+        .route(
+            "/sessions/{session_id}/checkpoints/{name}/branch",
+            post(branch_session_checkpoint),
+        )
+        .route(
+            "/sessions/{session_id}/alternatives",
+            get(get_session_alternatives),
+        )
+        .route(
+            "/sessions/{session_id}/messages/{message_index}/regenerate",
+            post(regenerate_session_turn),
+        )
         .with_state(state)
 }
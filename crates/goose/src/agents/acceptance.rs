@@ -0,0 +1,289 @@
+//! Acceptance criteria tracked as a checklist
+//!
+//! Callers and recipes can give a run a list of acceptance criteria. They are kept as a
+//! checklist in the system prompt, and the model reports progress on them with the
+//! `platform__update_acceptance_criteria` tool: each item is marked met once it is verified, or
+//! waived with the reason it does not apply. The checklist is the last section of the prompt,
+//! since it changes during the run.
+//!
+//! A reply cannot finish while items are still open. When the model gives its final answer with
+//! open items, it is reminded of them and keeps working, up to [`MAX_REMINDERS`] times per reply.
+//!
+//! ```yaml
+//! acceptance_criteria:
+//!   - The new endpoint is covered by an integration test
+//!   - The changelog mentions the breaking change
+//! ```
+use mcp_core::{Content, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::platform_tools::PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME;
+use super::Agent;
+
+/// Times a reply is sent back to resolve open items before it may finish anyway
+pub const MAX_REMINDERS: usize = 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CriterionStatus {
+    #[default]
+    Open,
+    Met,
+    Waived,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptanceCriterion {
+    pub description: String,
+    pub status: CriterionStatus,
+    /// How the item was verified, or why it was waived
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// One change to the checklist, as sent by the model
+#[derive(Debug, Clone, Deserialize)]
+struct CriterionUpdate {
+    /// 1-based position in the checklist
+    item: usize,
+    status: CriterionStatus,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AcceptanceChecklist {
+    pub items: Vec<AcceptanceCriterion>,
+}
+
+impl AcceptanceChecklist {
+    pub fn new(criteria: Vec<String>) -> Self {
+        Self {
+            items: criteria
+                .into_iter()
+                .map(|description| description.trim().to_string())
+                .filter(|description| !description.is_empty())
+                .map(|description| AcceptanceCriterion {
+                    description,
+                    status: CriterionStatus::Open,
+                    note: None,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The items that are neither met nor waived, with their 1-based positions
+    pub fn open(&self) -> Vec<(usize, &AcceptanceCriterion)> {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.status == CriterionStatus::Open)
+            .map(|(index, item)| (index + 1, item))
+            .collect()
+    }
+
+    /// Apply the updates from a call to the tool; none are applied if any is invalid
+    fn update(&mut self, updates: Vec<CriterionUpdate>) -> Result<(), String> {
+        for update in &updates {
+            if update.item == 0 || update.item > self.items.len() {
+                return Err(format!(
+                    "There is no item {}, the checklist has items 1 to {}",
+                    update.item,
+                    self.items.len()
+                ));
+            }
+            let has_note = update.note.as_deref().is_some_and(|n| !n.trim().is_empty());
+            if update.status == CriterionStatus::Waived && !has_note {
+                return Err(format!(
+                    "Item {} can only be waived with a note giving the reason",
+                    update.item
+                ));
+            }
+        }
+        for update in updates {
+            let item = &mut self.items[update.item - 1];
+            item.status = update.status;
+            item.note = update.note.filter(|note| !note.trim().is_empty());
+        }
+        Ok(())
+    }
+
+    fn render_items(&self) -> String {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let mark = match item.status {
+                    CriterionStatus::Open => "[ ]",
+                    CriterionStatus::Met => "[x]",
+                    CriterionStatus::Waived => "[-]",
+                };
+                let mut line = format!("{} {}. {}", mark, index + 1, item.description);
+                match (&item.status, &item.note) {
+                    (CriterionStatus::Waived, Some(note)) => {
+                        line.push_str(&format!(" (waived: {})", note))
+                    }
+                    (_, Some(note)) => line.push_str(&format!(" ({})", note)),
+                    _ => {}
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The system prompt section showing the state of the checklist
+    pub fn render(&self) -> String {
+        format!(
+            "# Acceptance Criteria\n\n\
+             The task is done only when every item below is met or waived. After verifying an \
+             item, mark it met with {}. If an item does not apply, waive it with the reason.\n\n{}",
+            PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME,
+            self.render_items()
+        )
+    }
+
+    /// The reminder sent when the model tries to finish with open items
+    pub fn reminder(&self) -> Option<String> {
+        let open = self.open();
+        if open.is_empty() {
+            return None;
+        }
+        let items: Vec<String> = open
+            .iter()
+            .map(|(position, item)| format!("{}. {}", position, item.description))
+            .collect();
+        Some(format!(
+            "The task is not finished: {} acceptance criteria are still open:\n{}\n\
+             Verify them and mark them met with {}, or waive them with the reason they do not \
+             apply.",
+            open.len(),
+            items.join("\n"),
+            PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME
+        ))
+    }
+}
+
+impl Agent {
+    /// Track acceptance criteria for subsequent replies, replacing any earlier checklist.
+    /// An empty list stops tracking.
+    pub async fn set_acceptance_criteria(&self, criteria: Vec<String>) {
+        let checklist = AcceptanceChecklist::new(criteria);
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.set_acceptance_checklist((!checklist.is_empty()).then_some(checklist));
+    }
+
+    pub async fn acceptance_checklist(&self) -> Option<AcceptanceChecklist> {
+        self.prompt_manager
+            .lock()
+            .await
+            .acceptance_checklist()
+            .cloned()
+    }
+
+    /// Handle the acceptance criteria platform tool
+    pub(super) async fn handle_update_acceptance_criteria(
+        &self,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let updates: Vec<CriterionUpdate> = arguments
+            .get("updates")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| ToolError::InvalidParameters(format!("Invalid updates: {}", e)))?
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'updates'".to_string()))?;
+
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        let Some(checklist) = prompt_manager.acceptance_checklist_mut() else {
+            return Err(ToolError::ExecutionError(
+                "No acceptance criteria are being tracked".to_string(),
+            ));
+        };
+        checklist
+            .update(updates)
+            .map_err(ToolError::InvalidParameters)?;
+
+        let open = checklist.open().len();
+        let summary = match open {
+            0 => "All acceptance criteria are resolved".to_string(),
+            n => format!(
+                "{} of {} acceptance criteria are still open",
+                n,
+                checklist.len()
+            ),
+        };
+        Ok(vec![Content::text(format!(
+            "{}\n\n{}",
+            summary,
+            checklist.render_items()
+        ))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn updates(value: Value) -> Vec<CriterionUpdate> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_checklist_updates() {
+        let mut checklist = AcceptanceChecklist::new(vec![
+            "Tests pass".to_string(),
+            " ".to_string(),
+            "Changelog updated".to_string(),
+            "Docs updated".to_string(),
+        ]);
+        assert_eq!(checklist.len(), 3);
+        assert!(checklist
+            .reminder()
+            .unwrap()
+            .contains("3 acceptance criteria"));
+
+        // Waiving needs a reason, and an invalid update leaves the checklist unchanged
+        let invalid = updates(json!([
+            {"item": 1, "status": "met"},
+            {"item": 3, "status": "waived"}
+        ]));
+        assert!(checklist.update(invalid).is_err());
+        assert_eq!(checklist.open().len(), 3);
+        assert!(checklist
+            .update(updates(json!([{"item": 4, "status": "met"}])))
+            .is_err());
+
+        checklist
+            .update(updates(json!([
+                {"item": 1, "status": "met", "note": "cargo test is green"},
+                {"item": 3, "status": "waived", "note": "no user-facing change"}
+            ])))
+            .unwrap();
+        assert_eq!(
+            checklist.render_items(),
+            "[x] 1. Tests pass (cargo test is green)\n\
+             [ ] 2. Changelog updated\n\
+             [-] 3. Docs updated (waived: no user-facing change)"
+        );
+        assert!(checklist
+            .reminder()
+            .unwrap()
+            .contains("2. Changelog updated"));
+
+        checklist
+            .update(updates(json!([{"item": 2, "status": "met"}])))
+            .unwrap();
+        assert!(checklist.reminder().is_none());
+    }
+}
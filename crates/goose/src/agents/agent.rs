@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::future::Future;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
@@ -10,43 +12,103 @@ use futures_util::stream;
 use futures_util::stream::StreamExt;
 use mcp_core::protocol::JsonRpcMessage;
 
+use crate::config::overrides::{with_overrides, OverriddenStream};
 use crate::config::{Config, ExtensionConfigManager, PermissionManager};
-use crate::message::Message;
+use crate::message::{Message, MessageContent};
+use crate::model::SamplingOverrides;
+use crate::permission::network_policy::NetworkPolicy;
 use crate::permission::permission_judge::check_tool_permissions;
-use crate::permission::PermissionConfirmation;
+use crate::permission::{ExternalApproval, PermissionConfirmation};
+use crate::providers::attribution::{configured_tags, merge_tags, tagged};
 use crate::providers::base::Provider;
 use crate::providers::errors::ProviderError;
+use crate::providers::quota::{QuotaLevel, QuotaStatus};
+use crate::providers::speech::{create_speech_provider, speak_message};
+use crate::providers::transcription::{
+    create_transcription_provider, has_audio, transcribe_messages, TranscribedMessage,
+};
 use crate::recipe::{Author, Recipe, Settings};
 use crate::scheduler_trait::SchedulerTrait;
-use crate::tool_monitor::{ToolCall, ToolMonitor};
+use crate::token_counter::TokenCounter;
+use crate::tool_monitor::{RepetitionConfig, ToolCall, ToolMonitor};
 use regex::Regex;
 use serde_json::Value;
 use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, instrument};
 
-use crate::agents::extension::{ExtensionConfig, ExtensionError, ExtensionResult, ToolInfo};
+use crate::agents::acceptance::MAX_REMINDERS;
+use crate::agents::answer_synthesis::SynthesisConfig;
+use crate::agents::answer_verification::VerificationConfig;
+use crate::agents::audit::{audit_session_id, AuditApproval, AuditLog};
+use crate::agents::calculator;
+use crate::agents::cancellation::{
+    answer_cancelled_calls, carries_marker, carry_session_marker, mark_cancelled_responses,
+    mark_session_cancelled, next_until_abandoned, salvage_partial_response, session_key,
+    until_cancelled, RunningReplies,
+};
+use crate::agents::change_review::git_changes_enabled;
+use crate::agents::code_actions::{code_actions_enabled, program_tools};
+use crate::agents::code_sandbox::CodeSandbox;
+use crate::agents::context_usage::{context_usage, load_counter, ContextUsage};
+use crate::agents::conversation_template::{with_template, ConversationTemplate};
+use crate::agents::cost_tracker::CostTracker;
+use crate::agents::edit_transactions::{edit_transactions_enabled, EditTransaction};
+use crate::agents::extension::{
+    ExtensionConfig, ExtensionDetails, ExtensionError, ExtensionResult, ExtensionWarning,
+    ReloadSummary, ToolInfo,
+};
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
+use crate::agents::extension_telemetry::{health_note, with_health_note, ExtensionHealth};
+use crate::agents::frontend_tools::FrontendToolUpdate;
+use crate::agents::idle::IdleState;
+use crate::agents::input_queue::{merge_steering, InputQueues, QueuedInput};
+use crate::agents::model_pins::{ModelPins, PinnedModel};
 use crate::agents::platform_tools::{
+    PLATFORM_ANCHOR_TOOL_NAME, PLATFORM_CALCULATE_TOOL_NAME, PLATFORM_EDIT_TRANSACTION_TOOL_NAME,
+    PLATFORM_EXECUTE_CODE_TOOL_NAME, PLATFORM_EXTENSION_LOGS_TOOL_NAME,
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
     PLATFORM_MANAGE_SCHEDULE_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
-    PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
-};
-use crate::agents::prompt_manager::PromptManager;
-use crate::agents::router_tool_selector::{
-    create_tool_selector, RouterToolSelectionStrategy, RouterToolSelector,
+    PLATFORM_REVIEW_CHANGES_TOOL_NAME, PLATFORM_RUN_PROGRAM_TOOL_NAME,
+    PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME, PLATFORM_SEARCH_WORKSPACE_TOOL_NAME,
+    PLATFORM_UNDO_TOOL_NAME, PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME,
 };
+use crate::agents::prompt_history::PromptHistory;
+use crate::agents::prompt_manager::{PinnedRequest, PromptManager};
+use crate::agents::quarantine::QuarantineConfig;
+use crate::agents::quota::QuotaState;
+use crate::agents::remote_workers::RemoteWorkers;
+use crate::agents::response_policy::{PolicyViolation, ResponsePolicy};
+use crate::agents::result_processors::{process_result, ResultProcessors};
+use crate::agents::router_fallback::{RouterFallback, ToolRoutingStatus};
+use crate::agents::router_tool_selector::{RouterToolSelectionStrategy, RouterToolSelector};
 use crate::agents::router_tools::{ROUTER_LLM_SEARCH_TOOL_NAME, ROUTER_VECTOR_SEARCH_TOOL_NAME};
+use crate::agents::snapshots::{SharedSnapshotStore, SnapshotPolicy, SnapshotStore};
+use crate::agents::streaming::{append_to_response, text_chunk};
+use crate::agents::termination::TerminationCondition;
+use crate::agents::time_box::{final_answer, TimeBox};
+use crate::agents::tool_budgets::ToolBudgets;
+use crate::agents::tool_descriptions::ToolDescriptions;
+use crate::agents::tool_mocks::ToolMocks;
 use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
-use crate::agents::tool_vectordb::generate_table_id;
+use crate::agents::tool_stats::ToolStatsStore;
+use crate::agents::turn_budget::{ComplexityEstimator, TurnBudget};
+use crate::agents::turn_webhook::{TurnRecord, TurnWebhook};
 use crate::agents::types::SessionConfig;
 use crate::agents::types::{FrontendTool, ToolResultReceiver};
+use crate::agents::ui_resources::{ui_resources, UiResource};
+use crate::agents::workspace_routers::{RouterSelector, WorkspaceRouters};
+use crate::agents::workspace_search::{workspace_search_enabled, WorkspaceIndex};
+use crate::context_mgmt::compress::PromptCompressor;
 use mcp_core::{
     prompt::Prompt, protocol::GetPromptResult, tool::Tool, Content, ToolError, ToolResult,
 };
 
 use super::platform_tools;
 use super::router_tools;
-use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::tool_execution::{
+    ToolCallResult, ToolConcurrency, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE,
+};
 
 /// The main goose Agent
 pub struct Agent {
@@ -57,18 +119,71 @@ pub struct Agent {
     pub(super) prompt_manager: Mutex<PromptManager>,
     pub(super) confirmation_tx: mpsc::Sender<(String, PermissionConfirmation)>,
     pub(super) confirmation_rx: Mutex<mpsc::Receiver<(String, PermissionConfirmation)>>,
-    pub(super) tool_result_tx: mpsc::Sender<(String, ToolResult<Vec<Content>>)>,
+    pub(super) tool_result_tx: mpsc::Sender<(String, FrontendToolUpdate)>,
     pub(super) tool_result_rx: ToolResultReceiver,
     pub(super) tool_monitor: Mutex<Option<ToolMonitor>>,
     pub(super) router_tool_selector: Mutex<Option<Arc<Box<dyn RouterToolSelector>>>>,
+    pub(super) workspace_routers: Mutex<WorkspaceRouters>,
     pub(super) scheduler_service: Mutex<Option<Arc<dyn SchedulerTrait>>>,
+    pub(super) turn_budget_override: Mutex<Option<TurnBudget>>,
+    pub(super) time_box_override: Mutex<Option<TimeBox>>,
+    pub(super) router_fallback: Mutex<Option<RouterFallback>>,
+    pub(super) streaming_override: Mutex<Option<bool>>,
+    pub(super) running_replies: RunningReplies,
+    pub(super) conversation_template: Mutex<Option<ConversationTemplate>>,
+    pub(super) cost_tracker: Mutex<CostTracker>,
+    pub(super) complexity_estimator: Mutex<Option<Arc<dyn ComplexityEstimator>>>,
+    pub(super) termination_conditions: Mutex<Vec<TerminationCondition>>,
+    pub(super) external_approval: Mutex<Option<ExternalApproval>>,
+    pub(super) tool_stats_store: Mutex<Option<ToolStatsStore>>,
+    pub(super) idle_state: Mutex<IdleState>,
+    pub(super) replies_in_flight: AtomicUsize,
+    pub(super) tool_snapshots: SharedSnapshotStore,
+    pub(super) working_dir: Mutex<Option<PathBuf>>,
+    pub(super) workspace_index: Mutex<Option<Arc<WorkspaceIndex>>>,
+    pub(super) tool_mocks: Mutex<Option<ToolMocks>>,
+    pub(super) input_queues: Mutex<InputQueues>,
+    pub(super) tool_budgets: Arc<std::sync::Mutex<ToolBudgets>>,
+    pub(super) prompt_history: Mutex<PromptHistory>,
+    pub(super) quota_state: Mutex<QuotaState>,
+    pub(super) result_processors: Mutex<ResultProcessors>,
+    pub(super) network_policy: Mutex<NetworkPolicy>,
+    pub(super) remote_workers: Mutex<RemoteWorkers>,
+    pub(super) edit_transaction: Mutex<Option<EditTransaction>>,
+    pub(super) tool_concurrency: ToolConcurrency,
+    pub(super) audit_log: Option<AuditLog>,
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
+    /// Text of the response the model is writing, sent as it arrives when streaming. The
+    /// response follows as one `Message` once it is complete.
+    TextChunk(String),
     McpNotification((String, JsonRpcMessage)),
-    ModelChange { model: String, mode: String },
+    ModelChange {
+        model: String,
+        mode: String,
+    },
+    TurnBudget(TurnBudget),
+    /// Extensions that failed to list their tools and are unavailable for this reply
+    ExtensionsDegraded(Vec<ExtensionWarning>),
+    /// Rules of the response policy that the last assistant message did not follow
+    PolicyViolations(Vec<PolicyViolation>),
+    /// A message queued while the reply was running has been received
+    InputQueued(QueuedInput),
+    /// The provider is near or over one of its usage quotas
+    QuotaWarning(QuotaStatus),
+    /// Where the tokens of the prompt for the next turn come from
+    ContextUsage(ContextUsage),
+    /// Vector tool routing stopped working or came back
+    ToolRouting(ToolRoutingStatus),
+    /// Interactive views returned by a tool call, for frontends to render
+    UiResources(Vec<UiResource>),
+    /// User messages whose audio was transcribed, for frontends to keep in place of the audio
+    Transcribed(Vec<TranscribedMessage>),
+    /// The reply was stopped with `Agent::cancel`; this is its last event
+    Cancelled,
 }
 
 impl Agent {
@@ -89,13 +204,59 @@ impl Agent {
             tool_result_rx: Arc::new(Mutex::new(tool_rx)),
             tool_monitor: Mutex::new(None),
             router_tool_selector: Mutex::new(None),
+            workspace_routers: Mutex::new(WorkspaceRouters::default()),
             scheduler_service: Mutex::new(None),
+            turn_budget_override: Mutex::new(None),
+            time_box_override: Mutex::new(None),
+            router_fallback: Mutex::new(None),
+            streaming_override: Mutex::new(None),
+            running_replies: Default::default(),
+            conversation_template: Mutex::new(None),
+            cost_tracker: Mutex::new(CostTracker::from_config()),
+            complexity_estimator: Mutex::new(None),
+            termination_conditions: Mutex::new(Vec::new()),
+            external_approval: Mutex::new(ExternalApproval::from_config()),
+            tool_stats_store: Mutex::new(None),
+            idle_state: Mutex::new(IdleState::default()),
+            replies_in_flight: AtomicUsize::new(0),
+            tool_snapshots: Arc::new(std::sync::Mutex::new(SnapshotStore::new(
+                SnapshotPolicy::from_config(),
+            ))),
+            working_dir: Mutex::new(None),
+            workspace_index: Mutex::new(None),
+            tool_mocks: Mutex::new(None),
+            input_queues: Mutex::new(InputQueues::new()),
+            tool_budgets: Arc::new(std::sync::Mutex::new(ToolBudgets::from_config())),
+            prompt_history: Mutex::new(PromptHistory::from_config()),
+            quota_state: Mutex::new(QuotaState::from_config()),
+            result_processors: Mutex::new(ResultProcessors::default()),
+            network_policy: Mutex::new(NetworkPolicy::from_config()),
+            remote_workers: Mutex::new(RemoteWorkers::from_config()),
+            edit_transaction: Mutex::new(None),
+            tool_concurrency: ToolConcurrency::from_config(),
+            audit_log: AuditLog::from_config(),
         }
     }
 
     pub async fn configure_tool_monitor(&self, max_repetitions: Option<u32>) {
         let mut tool_monitor = self.tool_monitor.lock().await;
-        *tool_monitor = Some(ToolMonitor::new(max_repetitions));
+        *tool_monitor = Some(ToolMonitor::from_config(max_repetitions));
+    }
+
+    /// Configure the tool monitor with per-turn, per-session and burst limits,
+    /// including per-tool overrides
+    pub async fn configure_tool_repetition_policy(&self, config: RepetitionConfig) {
+        let mut monitor = ToolMonitor::with_policy(config.default);
+        for (tool_name, policy) in config.tools {
+            monitor.set_tool_override(tool_name, policy);
+        }
+        *self.tool_monitor.lock().await = Some(monitor);
+    }
+
+    /// Have tool approvals decided by an external policy service, or pass `None` to
+    /// decide them locally again
+    pub async fn set_external_approval(&self, approval: Option<ExternalApproval>) {
+        *self.external_approval.lock().await = approval;
     }
 
     pub async fn get_tool_stats(&self) -> Option<HashMap<String, u32>> {
@@ -193,27 +354,135 @@ impl Agent {
         Ok(tools)
     }
 
-    /// Dispatch a single tool call to the appropriate client
+    /// Dispatch a single tool call to the appropriate client, outside of any reply
     #[instrument(skip(self, tool_call, request_id), fields(input, output))]
     pub async fn dispatch_tool_call(
         &self,
         tool_call: mcp_core::tool::ToolCall,
         request_id: String,
+    ) -> (String, Result<ToolCallResult, ToolError>) {
+        let router = self.router_tool_selector.lock().await.clone();
+        self.dispatch_audited_tool_call(
+            tool_call,
+            request_id,
+            AuditApproval::Automatic,
+            router.as_ref(),
+            &CancellationToken::new(),
+            None,
+        )
+        .await
+    }
+
+    /// Dispatch a tool call, recording it in the audit log as approved by `approval`. The call
+    /// searches tools with `router`, is cancelled with `cancel` and belongs to `session`, the
+    /// router selector, token and session of the reply that made it.
+    pub(crate) async fn dispatch_audited_tool_call(
+        &self,
+        tool_call: mcp_core::tool::ToolCall,
+        request_id: String,
+        approval: AuditApproval,
+        router: Option<&RouterSelector>,
+        cancel: &CancellationToken,
+        session: Option<&SessionConfig>,
+    ) -> (String, Result<ToolCallResult, ToolError>) {
+        let audited = self
+            .audit_log
+            .as_ref()
+            .map(|log| log.start(audit_session_id(session), &request_id, &tool_call, approval));
+        let (request_id, result) = self
+            .run_tool_call(tool_call, request_id, router, cancel, session)
+            .await;
+        match audited {
+            Some(audited) => (request_id, audited.track(result)),
+            None => (request_id, result),
+        }
+    }
+
+    /// Run a tool call through the monitors, budgets and result processing around its dispatch
+    async fn run_tool_call(
+        &self,
+        mut tool_call: mcp_core::tool::ToolCall,
+        request_id: String,
+        router: Option<&RouterSelector>,
+        cancel: &CancellationToken,
+        session: Option<&SessionConfig>,
     ) -> (String, Result<ToolCallResult, ToolError>) {
         // Check if this tool call should be allowed based on repetition monitoring
+        let mut argument_diff = None;
         if let Some(monitor) = self.tool_monitor.lock().await.as_mut() {
             let tool_call_info = ToolCall::new(tool_call.name.clone(), tool_call.arguments.clone());
+            argument_diff = monitor.argument_diff(&tool_call_info);
 
-            if !monitor.check_tool_call(tool_call_info) {
+            if let Err(violation) = monitor.check(tool_call_info) {
                 return (
                     request_id,
-                    Err(ToolError::ExecutionError(
-                        "Tool call rejected: exceeded maximum allowed repetitions".to_string(),
-                    )),
+                    Err(ToolError::ExecutionError(format!(
+                        "Tool call rejected: {}",
+                        violation
+                    ))),
                 );
             }
         }
 
+        // Dry runs answer mocked tools without running them
+        if let Some(result) = self.mocked_tool_result(&tool_call).await {
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        let budgeted = match self.start_budgeted_call(&tool_call.name, session) {
+            Ok(budgeted) => budgeted,
+            Err(exceeded) => return (request_id, Err(exceeded.into())),
+        };
+
+        let tool_name = tool_call.name.clone();
+        let snapshot = self.capture_tool_snapshot(&tool_call, session).await;
+        let processors = self.result_processors.lock().await.for_tool(&tool_name);
+        // Untrusted output reaches the model only as the facts another model extracts from it
+        let quarantined = self.quarantine_tool_call(&mut tool_call).await;
+        let dispatch =
+            self.dispatch_allowed_tool_call(tool_call, request_id.clone(), router, cancel, session);
+        let (request_id, result) = match &budgeted {
+            Some(budgeted) => match budgeted.dispatch(dispatch).await {
+                Ok(dispatched) => dispatched,
+                Err(e) => (request_id, Err(e)),
+            },
+            None => dispatch.await,
+        };
+        let result = result.map(ToolCallResult::with_ui_fallbacks);
+        let result = result.map(|r| process_result(r, processors));
+        let result = match quarantined {
+            Some(quarantined) => result.map(|r| quarantined.wrap(r)),
+            None => result,
+        };
+        let result = match (budgeted, result) {
+            (Some(budgeted), Ok(result)) => Ok(budgeted.finish(result)),
+            (Some(budgeted), Err(e)) => {
+                budgeted.failed();
+                Err(e)
+            }
+            (None, result) => result,
+        };
+        let result = match snapshot {
+            Some(snapshot) => result.map(|r| self.record_tool_snapshot(r, snapshot)),
+            None => result,
+        };
+        // Outermost, so budgets and deadlines only count time the call spends running
+        let result = result.map(|r| self.tool_concurrency.limit(r));
+        match argument_diff {
+            Some(diff) => (request_id, result.map(|r| r.with_note(diff))),
+            None => (request_id, result),
+        }
+    }
+
+    /// Dispatch a tool call that passed the repetition checks
+    async fn dispatch_allowed_tool_call(
+        &self,
+        tool_call: mcp_core::tool::ToolCall,
+        request_id: String,
+        router: Option<&RouterSelector>,
+        cancel: &CancellationToken,
+        session: Option<&SessionConfig>,
+    ) -> (String, Result<ToolCallResult, ToolError>) {
         if tool_call.name == PLATFORM_MANAGE_SCHEDULE_TOOL_NAME {
             let result = self
                 .handle_schedule_management(tool_call.arguments, request_id.clone())
@@ -221,6 +490,60 @@ impl Agent {
             return (request_id, Ok(ToolCallResult::from(result)));
         }
 
+        if tool_call.name == PLATFORM_ANCHOR_TOOL_NAME {
+            let result = self.handle_anchor(tool_call.arguments, session).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_CALCULATE_TOOL_NAME {
+            let result = calculator::handle_calculate(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_SEARCH_WORKSPACE_TOOL_NAME {
+            let result = self.handle_search_workspace(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_REVIEW_CHANGES_TOOL_NAME {
+            let result = self.handle_review_changes(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_EDIT_TRANSACTION_TOOL_NAME {
+            let result = self.handle_edit_transaction(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME {
+            let result = self
+                .handle_update_acceptance_criteria(tool_call.arguments)
+                .await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        // Text editor calls are staged while an edit transaction is open
+        if let Some(result) = self.stage_edit(&tool_call).await {
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_UNDO_TOOL_NAME {
+            let result = self.handle_undo(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_RUN_PROGRAM_TOOL_NAME {
+            let result = self
+                .handle_run_program(tool_call.arguments, &request_id, router, cancel, session)
+                .await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
+        if tool_call.name == PLATFORM_EXECUTE_CODE_TOOL_NAME {
+            let result = self.handle_execute_code(tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
         if tool_call.name == PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME {
             let extension_name = tool_call
                 .arguments
@@ -235,12 +558,20 @@ impl Agent {
                 .unwrap_or("")
                 .to_string();
             let (request_id, result) = self
-                .manage_extensions(action, extension_name, request_id)
+                .manage_extensions(action, extension_name, request_id, router)
                 .await;
 
             return (request_id, Ok(ToolCallResult::from(result)));
         }
 
+        // Heavy tools run on the worker they are assigned to
+        if let Some(result) = self
+            .dispatch_remote_tool_call(&tool_call, &request_id)
+            .await
+        {
+            return (request_id, Ok(result));
+        }
+
         let extension_manager = self.extension_manager.lock().await;
         let result: ToolCallResult = if tool_call.name == PLATFORM_READ_RESOURCE_TOOL_NAME {
             // Check if the tool is read_resource and handle it separately
@@ -257,6 +588,29 @@ impl Agent {
             )
         } else if tool_call.name == PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME {
             ToolCallResult::from(extension_manager.search_available_extensions().await)
+        } else if tool_call.name == PLATFORM_EXTENSION_LOGS_TOOL_NAME {
+            let extension_name = tool_call
+                .arguments
+                .get("extension_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+            let lines = tool_call
+                .arguments
+                .get("lines")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(50) as usize;
+            let result = extension_manager
+                .get_extension_logs(extension_name, lines)
+                .map(|logs| {
+                    let text = if logs.is_empty() {
+                        format!("Extension '{}' has not written any logs", extension_name)
+                    } else {
+                        logs.join("\n")
+                    };
+                    vec![Content::text(text)]
+                })
+                .map_err(|e| ToolError::ExecutionError(e.to_string()));
+            ToolCallResult::from(result)
         } else if self.is_frontend_tool(&tool_call.name).await {
             // For frontend tools, return an error indicating we need frontend execution
             ToolCallResult::from(Err(ToolError::ExecutionError(
@@ -265,8 +619,7 @@ impl Agent {
         } else if tool_call.name == ROUTER_VECTOR_SEARCH_TOOL_NAME
             || tool_call.name == ROUTER_LLM_SEARCH_TOOL_NAME
         {
-            let selector = self.router_tool_selector.lock().await.clone();
-            let selected_tools = match selector.as_ref() {
+            let selected_tools = match router {
                 Some(selector) => match selector.select_tools(tool_call.arguments.clone()).await {
                     Ok(tools) => tools,
                     Err(e) => {
@@ -292,7 +645,7 @@ impl Agent {
         } else {
             // Clone the result to ensure no references to extension_manager are returned
             let result = extension_manager
-                .dispatch_tool_call(tool_call.clone())
+                .dispatch_tool_call(tool_call.clone(), cancel.clone())
                 .await;
             match result {
                 Ok(call_result) => call_result,
@@ -318,6 +671,7 @@ impl Agent {
         action: String,
         extension_name: String,
         request_id: String,
+        router: Option<&RouterSelector>,
     ) -> (String, Result<Vec<Content>, ToolError>) {
         let mut extension_manager = self.extension_manager.lock().await;
 
@@ -337,15 +691,18 @@ impl Agent {
 
         let config = match ExtensionConfigManager::get_config_by_name(&extension_name) {
             Ok(Some(config)) => config,
-            Ok(None) => {
-                return (
-                    request_id,
-                    Err(ToolError::ExecutionError(format!(
+            Ok(None) => match extension_manager.discovered_config(&extension_name) {
+                Some(config) => config,
+                None => {
+                    return (
+                        request_id,
+                        Err(ToolError::ExecutionError(format!(
                         "Extension '{}' not found. Please check the extension name and try again.",
                         extension_name
                     ))),
-                )
-            }
+                    )
+                }
+            },
             Err(e) => {
                 return (
                     request_id,
@@ -367,32 +724,40 @@ impl Agent {
                 ))]
             })
             .map_err(|e| ToolError::ExecutionError(e.to_string()));
+        drop(extension_manager);
 
-        // Update vector index if operation was successful and vector routing is enabled
+        // Update the index of the reply's router and the agent's own, if routing is enabled
         if result.is_ok() {
-            let selector = self.router_tool_selector.lock().await.clone();
-            if ToolRouterIndexManager::is_tool_router_enabled(&selector) {
-                if let Some(selector) = selector {
-                    let vector_action = if action == "disable" { "remove" } else { "add" };
-                    let extension_manager = self.extension_manager.lock().await;
-                    let selector = Arc::new(selector);
-                    if let Err(e) = ToolRouterIndexManager::update_extension_tools(
-                        &selector,
-                        &extension_manager,
-                        &extension_name,
-                        vector_action,
-                    )
-                    .await
-                    {
-                        return (
-                            request_id,
-                            Err(ToolError::ExecutionError(format!(
-                                "Failed to update vector index: {}",
-                                e
-                            ))),
-                        );
+            let vector_action = if action == "disable" { "remove" } else { "add" };
+            for selector in self.selectors_to_index(router).await {
+                if !ToolRouterIndexManager::is_tool_router_enabled(&Some(selector.clone())) {
+                    continue;
+                }
+                let extension_manager = self.extension_manager.lock().await;
+                if let Err(e) = ToolRouterIndexManager::update_extension_tools(
+                    &selector,
+                    &extension_manager,
+                    &extension_name,
+                    vector_action,
+                )
+                .await
+                {
+                    // A broken vector store leaves routing, not the extension, unavailable
+                    if selector.selector_type() == RouterToolSelectionStrategy::Vector {
+                        drop(extension_manager);
+                        self.degrade_tool_routing(e.to_string()).await;
+                        return (request_id, result);
                     }
+                    return (
+                        request_id,
+                        Err(ToolError::ExecutionError(format!(
+                            "Failed to update vector index: {}",
+                            e
+                        ))),
+                    );
                 }
+                drop(extension_manager);
+                self.record_indexed(&selector).await;
             }
         }
 
@@ -447,6 +812,12 @@ impl Agent {
                 )
                 .await
                 {
+                    // A broken vector store leaves routing, not the extension, unavailable
+                    if selector.selector_type() == RouterToolSelectionStrategy::Vector {
+                        drop(extension_manager);
+                        self.degrade_tool_routing(e.to_string()).await;
+                        return Ok(());
+                    }
                     return Err(ExtensionError::SetupError(format!(
                         "Failed to index tools for extension {}: {}",
                         extension.name(),
@@ -471,13 +842,58 @@ impl Agent {
             prefixed_tools.push(platform_tools::search_available_extensions_tool());
             prefixed_tools.push(platform_tools::manage_extensions_tool());
             prefixed_tools.push(platform_tools::manage_schedule_tool());
+            prefixed_tools.push(platform_tools::extension_logs_tool());
+            prefixed_tools.push(platform_tools::anchor_tool());
+            prefixed_tools.push(platform_tools::calculate_tool());
+
+            if self.tool_snapshots.lock().unwrap().is_enabled() {
+                prefixed_tools.push(platform_tools::undo_tool());
+            }
+
+            if workspace_search_enabled() {
+                prefixed_tools.push(platform_tools::search_workspace_tool());
+            }
+
+            if git_changes_enabled() {
+                prefixed_tools.push(platform_tools::review_changes_tool());
+            }
+
+            if edit_transactions_enabled() {
+                prefixed_tools.push(platform_tools::edit_transaction_tool());
+            }
+
+            if self.acceptance_checklist().await.is_some() {
+                prefixed_tools.push(platform_tools::update_acceptance_criteria_tool());
+            }
+
+            // Let the model call extension tools from a program
+            if code_actions_enabled() {
+                let extension_tools = extension_manager
+                    .get_prefixed_tools(None)
+                    .await
+                    .unwrap_or_default();
+                prefixed_tools.push(platform_tools::run_program_tool(&program_tools(
+                    extension_tools,
+                )));
+            }
+
+            // Add the code sandbox if a runtime is configured
+            let sandbox = CodeSandbox::from_config();
+            if sandbox.is_available() {
+                prefixed_tools.push(platform_tools::execute_code_tool(
+                    &sandbox.available_languages(),
+                ));
+            }
 
             // Add resource tools if supported
             if extension_manager.supports_resources() {
                 prefixed_tools.push(platform_tools::read_resource_tool());
                 prefixed_tools.push(platform_tools::list_resources_tool());
             }
+
+            ToolDescriptions::from_config().apply(&mut prefixed_tools);
         }
+        QuarantineConfig::from_config().annotate(&mut prefixed_tools);
 
         prefixed_tools
     }
@@ -485,6 +901,17 @@ impl Agent {
     pub async fn list_tools_for_router(
         &self,
         strategy: Option<RouterToolSelectionStrategy>,
+    ) -> Vec<Tool> {
+        let router = self.router_tool_selector.lock().await.clone();
+        self.list_router_tools(strategy, router.as_ref()).await
+    }
+
+    /// The router's search tool and the other tools always offered, with the recent tool calls
+    /// of `router`
+    pub(super) async fn list_router_tools(
+        &self,
+        strategy: Option<RouterToolSelectionStrategy>,
+        router: Option<&RouterSelector>,
     ) -> Vec<Tool> {
         let mut prefixed_tools = vec![];
         match strategy {
@@ -496,10 +923,14 @@ impl Agent {
             }
             None => {}
         }
+        // The checklist belongs to this agent, so its tool is always offered rather than indexed
+        if self.acceptance_checklist().await.is_some() {
+            prefixed_tools.push(platform_tools::update_acceptance_criteria_tool());
+        }
+        ToolDescriptions::from_config().apply(&mut prefixed_tools);
 
         // Get recent tool calls from router tool selector if available
-        let selector = self.router_tool_selector.lock().await.clone();
-        if let Some(selector) = selector {
+        if let Some(selector) = router {
             if let Ok(recent_calls) = selector.get_recent_tool_calls(20).await {
                 let extension_manager = self.extension_manager.lock().await;
                 // Add recent tool calls to the list, avoiding duplicates
@@ -516,6 +947,7 @@ impl Agent {
                 }
             }
         }
+        QuarantineConfig::from_config().annotate(&mut prefixed_tools);
 
         prefixed_tools
     }
@@ -529,19 +961,66 @@ impl Agent {
         if ToolRouterIndexManager::is_tool_router_enabled(&selector) {
             if let Some(selector) = selector {
                 let extension_manager = self.extension_manager.lock().await;
-                ToolRouterIndexManager::update_extension_tools(
+                if let Err(e) = ToolRouterIndexManager::update_extension_tools(
                     &selector,
                     &extension_manager,
                     name,
                     "remove",
                 )
-                .await?;
+                .await
+                {
+                    if selector.selector_type() != RouterToolSelectionStrategy::Vector {
+                        return Err(e);
+                    }
+                    drop(extension_manager);
+                    self.degrade_tool_routing(e.to_string()).await;
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Apply changes to the extension config on disk without recreating the agent; see
+    /// [`ExtensionManager::reload`]
+    pub async fn reload_extensions(&self) -> ExtensionResult<ReloadSummary> {
+        let summary = self.extension_manager.lock().await.reload().await?;
+
+        // Keep the tool index in step with the extensions that changed
+        let selector = self.router_tool_selector.lock().await.clone();
+        if ToolRouterIndexManager::is_tool_router_enabled(&selector) {
+            if let Some(selector) = selector {
+                let extension_manager = self.extension_manager.lock().await;
+                let changes = summary
+                    .removed
+                    .iter()
+                    .chain(&summary.restarted)
+                    .map(|name| (name, "remove"))
+                    .chain(
+                        summary
+                            .restarted
+                            .iter()
+                            .chain(&summary.added)
+                            .map(|name| (name, "add")),
+                    );
+                for (name, action) in changes {
+                    if let Err(e) = ToolRouterIndexManager::update_extension_tools(
+                        &selector,
+                        &extension_manager,
+                        name,
+                        action,
+                    )
+                    .await
+                    {
+                        tracing::warn!("Failed to update the tool index for {}: {}", name, e);
+                    }
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+
     pub async fn list_extensions(&self) -> Vec<String> {
         let extension_manager = self.extension_manager.lock().await;
         extension_manager
@@ -550,6 +1029,16 @@ impl Agent {
             .expect("Failed to list extensions")
     }
 
+    /// Get the MCP protocol version, server version and capabilities of each extension
+    pub async fn get_extension_details(&self) -> Vec<ExtensionDetails> {
+        self.extension_manager.lock().await.get_extension_details()
+    }
+
+    /// Get the tool call latency and error rates of each extension that has been called
+    pub async fn get_extension_health(&self) -> Vec<ExtensionHealth> {
+        self.extension_manager.lock().await.get_extension_health()
+    }
+
     /// Handle a confirmation response for a tool request
     pub async fn handle_confirmation(
         &self,
@@ -561,11 +1050,61 @@ impl Agent {
         }
     }
 
-    #[instrument(skip(self, messages, session), fields(user_message))]
+    /// The given provider with the sampling overrides applied to its model config, falling back
+    /// to the given provider if that fails
+    fn provider_for_sampling(
+        provider: Arc<dyn Provider>,
+        sampling: &SamplingOverrides,
+    ) -> Arc<dyn Provider> {
+        match provider.with_model_override(&|model| model.with_sampling(sampling)) {
+            Ok(sampled) => sampled,
+            Err(e) => {
+                tracing::warn!("Failed to apply sampling overrides: {}", e);
+                provider
+            }
+        }
+    }
+
     pub async fn reply(
         &self,
         messages: &[Message],
         session: Option<SessionConfig>,
+    ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
+        let sampling = session.as_ref().and_then(|s| s.sampling);
+        self.reply_with_sampling(messages, session, sampling).await
+    }
+
+    /// Reply with temperature, max output tokens or top_p overridden for this reply only,
+    /// e.g. to run planning turns cool and creative turns warmer. Overrides that are not set
+    /// keep the provider's configured values.
+    #[instrument(skip(self, messages, session, sampling), fields(user_message))]
+    pub async fn reply_with_sampling(
+        &self,
+        messages: &[Message],
+        session: Option<SessionConfig>,
+        sampling: Option<SamplingOverrides>,
+    ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
+        // The session's config overrides apply to setting up the reply and to every turn of it
+        let overrides = session
+            .as_ref()
+            .map(|s| s.config_overrides.clone())
+            .filter(|overrides| !overrides.is_empty());
+        let Some(overrides) = overrides.map(Arc::new) else {
+            return self.start_reply(messages, session, sampling).await;
+        };
+        let stream = with_overrides(
+            overrides.clone(),
+            self.start_reply(messages, session, sampling),
+        )
+        .await?;
+        Ok(Box::pin(OverriddenStream::new(overrides, stream)))
+    }
+
+    async fn start_reply(
+        &self,
+        messages: &[Message],
+        session: Option<SessionConfig>,
+        sampling: Option<SamplingOverrides>,
     ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
         let mut messages = messages.to_vec();
         let reply_span = tracing::Span::current();
@@ -573,9 +1112,53 @@ impl Agent {
         // Load settings from config
         let config = Config::global();
 
+        // Keep the task the session started with in the system prompt on long runs
+        if config
+            .get_param::<bool>("GOOSE_PIN_ORIGINAL_REQUEST")
+            .unwrap_or(false)
+        {
+            self.prompt_manager
+                .lock()
+                .await
+                .pin_first_user_message(&messages);
+        }
+
+        // Audio from voice frontends enters the conversation as its transcript
+        let mut transcribed = Vec::new();
+        if has_audio(&messages) {
+            let transcriber = create_transcription_provider()?.ok_or_else(|| {
+                anyhow!("Audio input requires a transcription provider, set GOOSE_TRANSCRIPTION_PROVIDER")
+            })?;
+            transcribed = transcribe_messages(&mut messages, transcriber.as_ref()).await?;
+        }
+
+        // The model is told where a reply cancelled earlier in the session stopped
+        if let Some(session) = &session {
+            carry_session_marker(session, &mut messages).await;
+        }
+
+        // Restart anything released while the agent was idle, and keep the agent from being
+        // suspended until the reply is done
+        let activity = self.hold_activity();
+        self.mark_active().await?;
+
+        // Route tools with the selector of this workspace, started with the tools it used in
+        // earlier sessions
+        let workspace = session
+            .as_ref()
+            .map(|s| s.working_dir.clone())
+            .or_else(|| std::env::current_dir().ok());
+        *self.working_dir.lock().await = workspace.clone();
+        let tool_routing = self.check_tool_routing().await;
+        let router = self.workspace_router(workspace.as_deref()).await;
+        if let (Some(router), Some(workspace)) = (&router, &workspace) {
+            self.warm_start_router(router, workspace).await;
+        }
+
         // Setup tools and prompt
         let (mut tools, mut toolshim_tools, mut system_prompt) =
-            self.prepare_tools_and_prompt().await?;
+            self.prepare_reply_tools_and_prompt(router.as_ref()).await?;
+        let degraded_extensions = self.extension_manager.lock().await.degraded_extensions();
 
         let goose_mode = config.get_param("GOOSE_MODE").unwrap_or("auto".to_string());
 
@@ -590,17 +1173,305 @@ impl Agent {
             debug!("user_message" = &content);
         }
 
-        Ok(Box::pin(async_stream::try_stream! {
+        // Each reply is a new turn for the purposes of per-turn tool limits
+        if let Some(monitor) = self.tool_monitor.lock().await.as_mut() {
+            monitor.start_turn();
+        }
+
+        // Voice frontends get assistant replies as audio too, when a speech provider is set
+        let speech = create_speech_provider().unwrap_or_else(|e| {
+            tracing::warn!("Failed to create speech provider: {}", e);
+            None
+        });
+
+        let response_policy = self.prompt_manager.lock().await.response_policy().cloned();
+        let synthesis = SynthesisConfig::from_config();
+        let verification = VerificationConfig::from_config();
+
+        let turn_budget = self.resolve_turn_budget(&messages).await;
+        let time_box = self.resolve_time_box().await;
+        let streaming = self.resolve_streaming().await;
+        let session_id = session.as_ref().and_then(|s| session_key(&s.id));
+        let reply = self.start_cancellable(session_id.clone());
+        self.resume_session_cost(session.as_ref()).await;
+        let cancel = reply.cancel.clone();
+        let conversation_template = self.resolve_conversation_template().await;
+        let turn_webhook = TurnWebhook::from_config();
+        let budget_provider = match &turn_budget {
+            Some(budget) => self.provider_for_budget(budget).await,
+            None => None,
+        };
+        let budget_provider = match sampling.filter(|s| !s.is_empty()) {
+            Some(sampling) => {
+                let base = match budget_provider {
+                    Some(provider) => provider,
+                    None => self.provider().await?,
+                };
+                Some(Self::provider_for_sampling(base, &sampling))
+            }
+            None => budget_provider,
+        };
+
+        // The usage of the reply is charged to the tags of its session
+        let tags = merge_tags(
+            configured_tags(),
+            &session.as_ref().map(|s| s.tags.clone()).unwrap_or_default(),
+        );
+
+        let stream = async_stream::try_stream! {
             let _ = reply_span.enter();
+            // Dropped with the stream, so a finished reply is no longer cancelled with its session
+            let _reply = reply;
+            let _activity = activity;
+            if !transcribed.is_empty() {
+                yield AgentEvent::Transcribed(transcribed);
+            }
+            if let Some(budget) = &turn_budget {
+                yield AgentEvent::TurnBudget(budget.clone());
+            }
+            if !degraded_extensions.is_empty() {
+                yield AgentEvent::ExtensionsDegraded(degraded_extensions);
+            }
+            if let Some(status) = tool_routing {
+                yield AgentEvent::ToolRouting(status);
+            }
+
+            let mut turns_taken: u32 = 0;
+            let time_box = time_box.map(TimeBox::start);
+            let mut acceptance_reminders = 0;
+            let mut wrapping_up = false;
+            let mut synthesis_pending = synthesis.enabled;
+            let mut verification_pending = verification.enabled;
+            // Loaded once per reply, and again only if a provider switch changes the tokenizer
+            let mut usage_counter: Option<(String, TokenCounter)> = None;
+            let model_pins = ModelPins::from_config();
+            let compressor = PromptCompressor::from_config();
+            let mut pinned: Option<PinnedModel> = None;
             loop {
-                match Self::generate_response_from_provider(
-                    self.provider().await?,
-                    &system_prompt,
-                    &messages,
-                    &tools,
-                    &toolshim_tools,
-                ).await {
+                if crate::shutdown::is_shutting_down() {
+                    break;
+                }
+                if cancel.is_cancelled() {
+                    if let Some(session) = &session {
+                        mark_session_cancelled(session).await;
+                    }
+                    yield AgentEvent::Cancelled;
+                    break;
+                }
+                if let Some(budget) = &turn_budget {
+                    if turns_taken >= budget.max_turns {
+                        yield AgentEvent::Message(Message::assistant().with_text(format!(
+                            "I've reached the limit of {} turns for this task. Let me know if you'd like me to keep going.",
+                            budget.max_turns
+                        )));
+                        break;
+                    }
+                }
+                if let Some(message) = self.session_budget_exceeded(session.as_ref()).await {
+                    yield AgentEvent::Message(Message::assistant().with_text(message));
+                    break;
+                }
+                turns_taken += 1;
+                self.mark_active().await?;
+
+                // Past the wrap-up point the model gives its final answer, whatever it intends
+                if let Some(clock) = &time_box {
+                    if !wrapping_up && clock.should_wrap_up() {
+                        wrapping_up = true;
+                        tracing::info!("Time box reached after {:?}, wrapping up", clock.elapsed());
+                        let instruction = clock.wrap_up_instruction();
+                        match messages.last_mut() {
+                            Some(last) if last.role == mcp_core::Role::User => {
+                                last.content.push(MessageContent::text(instruction));
+                            }
+                            _ => messages.push(Message::user().with_text(instruction)),
+                        }
+                    }
+                }
+
+                for input in self.acknowledge_queued_input(session_id.as_deref()).await {
+                    yield AgentEvent::InputQueued(input);
+                }
+                // Steering that arrived during the final answer starts this turn
+                if messages.last().is_some_and(|m| m.role == mcp_core::Role::Assistant) {
+                    let steering = self.take_steering_input(session_id.as_deref()).await;
+                    if !steering.is_empty() {
+                        let mut message = Message::user();
+                        merge_steering(&mut message, steering);
+                        messages.push(message.clone());
+                        yield AgentEvent::Message(message);
+                    }
+                }
+
+                // The provider used before a quota was reached returns once the quota resets
+                match self.restore_quota_primary().await {
+                    Ok(Some(model)) => {
+                        yield AgentEvent::ModelChange {
+                            model,
+                            mode: "session".to_string(),
+                        };
+                    }
+                    Ok(None) => {}
+                    Err(e) => tracing::warn!("Failed to restore the provider: {}", e),
+                }
+
+                // A pinned model reads the results of the tools that pinned it
+                let pinned_provider = pinned.as_mut().and_then(PinnedModel::next_turn);
+                let pin_ended = pinned.is_some() && pinned_provider.is_none();
+                if pin_ended {
+                    pinned = None;
+                }
+                let provider = match (pinned_provider, &budget_provider) {
+                    (Some(provider), _) => provider,
+                    (None, Some(provider)) => provider.clone(),
+                    (None, None) => self.provider().await?,
+                };
+                if pin_ended {
+                    yield AgentEvent::ModelChange {
+                        model: provider.get_model_config().model_name.clone(),
+                        mode: "session".to_string(),
+                    };
+                }
+                self.record_prompt_snapshot(&system_prompt, &tools).await;
+                let model_config = provider.get_model_config();
+                let tokenizer_key = TokenCounter::tokenizer_key(&model_config);
+                if usage_counter.as_ref().is_none_or(|(key, _)| *key != tokenizer_key) {
+                    usage_counter = load_counter(&model_config)
+                        .await
+                        .map(|counter| (tokenizer_key, counter));
+                }
+                // Old tool outputs are pruned for the provider only; the session keeps them whole
+                let (compressed, compression) = match (&compressor, &usage_counter) {
+                    (Some(compressor), Some((_, counter))) => {
+                        let (compressed, stats) = compressor.compress(&messages, counter);
+                        (Some(compressed), Some(stats).filter(|stats| stats.sections > 0))
+                    }
+                    _ => (None, None),
+                };
+                let prompt_messages = compressed.as_deref().unwrap_or(&messages);
+                if let Some((_, counter)) = &usage_counter {
+                    let mut usage = context_usage(
+                        counter,
+                        &model_config,
+                        &system_prompt,
+                        prompt_messages,
+                        &tools,
+                    );
+                    usage.compression = compression;
+                    yield AgentEvent::ContextUsage(usage);
+                }
+                // Template turns and extension health are only sent to the provider, never
+                // kept in the session
+                let health = self.extension_manager.lock().await.get_extension_health();
+                let history = with_health_note(
+                    with_template(conversation_template.as_ref(), prompt_messages),
+                    health_note(&health),
+                );
+                // A draft of the final answer is thrown away, so none of it may be shown
+                let hold_draft = synthesis_pending && synthesis.applies_to(&messages);
+                let result = if streaming {
+                    // Text is sent to the frontend as it arrives, and the parts are joined into
+                    // the response that the rest of the turn works with and that is kept
+                    let started = until_cancelled(&cancel, Self::stream_response_from_provider(
+                        provider.clone(),
+                        &system_prompt,
+                        &history,
+                        &tools,
+                        &toolshim_tools,
+                    )).await;
+                    match started {
+                        None => {
+                            if let Some(session) = &session {
+                                mark_session_cancelled(session).await;
+                            }
+                            yield AgentEvent::Cancelled;
+                            break;
+                        }
+                        Some(Ok(mut parts)) => {
+                            let mut response = Message::assistant();
+                            let mut usage = None;
+                            let mut failure = None;
+                            while let Some(part) = until_cancelled(&cancel, parts.next()).await.flatten() {
+                                match part {
+                                    Ok((part, part_usage)) => {
+                                        if let Some(part) = part {
+                                            if let Some(chunk) = text_chunk(&part).filter(|_| !hold_draft) {
+                                                yield AgentEvent::TextChunk(chunk);
+                                            }
+                                            append_to_response(&mut response, part);
+                                        }
+                                        if part_usage.is_some() {
+                                            usage = part_usage;
+                                        }
+                                    }
+                                    Err(e) => {
+                                        failure = Some(e);
+                                        break;
+                                    }
+                                }
+                            }
+                            if cancel.is_cancelled() {
+                                let salvaged = salvage_partial_response(response);
+                                if !carries_marker(&salvaged) {
+                                    if let Some(session) = &session {
+                                        mark_session_cancelled(session).await;
+                                    }
+                                }
+                                for message in salvaged {
+                                    yield AgentEvent::Message(message);
+                                }
+                                yield AgentEvent::Cancelled;
+                                break;
+                            }
+                            match (failure, usage) {
+                                (Some(e), _) => Err(e),
+                                (None, Some(usage)) => {
+                                    crate::providers::base::set_current_model(&usage.model);
+                                    Ok((response, usage))
+                                }
+                                (None, None) => Err(ProviderError::ExecutionError(
+                                    "The response stream ended without usage".to_string(),
+                                )),
+                            }
+                        }
+                        Some(Err(e)) => Err(e),
+                    }
+                } else {
+                    let generated = until_cancelled(&cancel, Self::generate_response_from_provider(
+                        provider.clone(),
+                        &system_prompt,
+                        &history,
+                        &tools,
+                        &toolshim_tools,
+                    )).await;
+                    match generated {
+                        Some(result) => result,
+                        None => {
+                            if let Some(session) = &session {
+                                mark_session_cancelled(session).await;
+                            }
+                            yield AgentEvent::Cancelled;
+                            break;
+                        }
+                    }
+                };
+                match result {
                     Ok((response, usage)) => {
+                        // Tools stay advertised since some providers reject tool history
+                        // without them, but calls made after the wrap-up point are not run
+                        let response = if wrapping_up {
+                            final_answer(response)
+                        } else {
+                            response
+                        };
+                        // A fallback chain reports when another of its providers answered
+                        if let Some((model, fallback)) = provider.as_fallback().and_then(|f| f.take_switch()) {
+                            yield AgentEvent::ModelChange {
+                                model,
+                                mode: if fallback { "fallback" } else { "session" }.to_string(),
+                            };
+                        }
+
                         // Emit model change event if provider is lead-worker
                         let provider = self.provider().await?;
                         if let Some(lead_worker) = provider.as_lead_worker() {
@@ -621,9 +1492,78 @@ impl Agent {
                             };
                         }
 
-                        // record usage for the session in the session file
+                        // record usage and cost for the session in the session file
+                        let cost = self.record_session_cost(session.as_ref(), &model_config, &usage).await;
                         if let Some(session_config) = session.clone() {
-                            Self::update_session_metrics(session_config, &usage, messages.len()).await?;
+                            Self::update_session_metrics(session_config, &usage, messages.len(), &cost).await?;
+                        }
+                        if let Some(webhook) = &turn_webhook {
+                            webhook.send(TurnRecord::new(
+                                session.as_ref().map(|s| &s.id),
+                                turns_taken,
+                                &response,
+                                &usage,
+                            ));
+                        }
+
+                        // Count the usage against the provider's quotas, moving to the
+                        // fallback provider once a quota is reached
+                        if let Some(status) = self.record_provider_usage(&usage).await {
+                            yield AgentEvent::QuotaWarning(status.clone());
+                            if status.level == QuotaLevel::Exceeded && budget_provider.is_none() {
+                                match self.switch_to_quota_fallback(&status).await {
+                                    Ok(Some(model)) => {
+                                        yield AgentEvent::ModelChange {
+                                            model,
+                                            mode: "fallback".to_string(),
+                                        };
+                                    }
+                                    Ok(None) => {}
+                                    Err(e) => tracing::warn!("Failed to switch to the fallback provider: {}", e),
+                                }
+                            }
+                        }
+
+                        // Before the final answer of a long run, recall the earlier tool results
+                        // that matter most and answer again with them in view
+                        if synthesis_pending && !response.is_tool_call() {
+                            synthesis_pending = false;
+                            match self.recall_findings(&synthesis, &messages).await {
+                                Ok(Some(reminder)) => {
+                                    match messages.last_mut() {
+                                        Some(last) if last.role == mcp_core::Role::User => {
+                                            last.content.push(MessageContent::text(reminder));
+                                        }
+                                        _ => messages.push(Message::user().with_text(reminder)),
+                                    }
+                                    continue;
+                                }
+                                Ok(None) => {}
+                                Err(e) => tracing::warn!("Failed to recall tool results: {}", e),
+                            }
+                        }
+
+                        // Check the final answer against the tool results once, and give the
+                        // model one turn to correct what the evidence does not support
+                        if verification_pending && !wrapping_up && !response.is_tool_call() {
+                            verification_pending = false;
+                            match self.verify_answer(&verification, &messages, &response).await {
+                                Ok(Some(verdict)) => {
+                                    if let Some(correction) = verdict.correction() {
+                                        tracing::info!("Answer verification asked for a corrective turn");
+                                        // The checked answer is kept with the problems found in
+                                        // it, so the conversation shows what is being corrected
+                                        let correction = Message::user().with_text(correction);
+                                        yield AgentEvent::Message(response.clone());
+                                        yield AgentEvent::Message(correction.clone());
+                                        messages.push(response);
+                                        messages.push(correction);
+                                        continue;
+                                    }
+                                }
+                                Ok(None) => {}
+                                Err(e) => tracing::warn!("Failed to verify the answer: {}", e),
+                            }
                         }
 
                         // categorize the type of requests we need to handle
@@ -633,8 +1573,7 @@ impl Agent {
                             self.categorize_tool_requests(&response).await;
 
                         // Record tool calls in the router selector
-                        let selector = self.router_tool_selector.lock().await.clone();
-                        if let Some(selector) = selector {
+                        if let Some(selector) = &router {
                             // Record frontend tool calls
                             for request in &frontend_requests {
                                 if let Ok(tool_call) = &request.tool_call {
@@ -651,17 +1590,83 @@ impl Agent {
                                     }
                                 }
                             }
+
+                            if let Some(workspace) = &workspace {
+                                let tool_names: Vec<String> = frontend_requests
+                                    .iter()
+                                    .chain(remaining_requests.iter())
+                                    .filter_map(|request| request.tool_call.as_ref().ok())
+                                    .map(|tool_call| tool_call.name.clone())
+                                    .collect();
+                                self.persist_tool_calls(workspace, &tool_names).await;
+                            }
                         }
                         // Yield the assistant's response with frontend tool requests filtered out
+                        let mut filtered_response = filtered_response;
+                        if let Some(speech) = &speech {
+                            if let Err(e) = speak_message(&mut filtered_response, speech.as_ref()).await {
+                                tracing::warn!("Failed to synthesize speech: {}", e);
+                            }
+                        }
                         yield AgentEvent::Message(filtered_response.clone());
+                        if let Some(policy) = &response_policy {
+                            let violations = policy.validate(&filtered_response);
+                            if !violations.is_empty() {
+                                yield AgentEvent::PolicyViolations(violations);
+                            }
+                        }
 
                         tokio::task::yield_now().await;
 
                         let num_tool_requests = frontend_requests.len() + remaining_requests.len();
                         if num_tool_requests == 0 {
+                            // Steering messages that arrived during the final answer start
+                            // another turn
+                            if self.has_steering_input(session_id.as_deref()).await {
+                                messages.push(response);
+                                continue;
+                            }
+                            // The task is not done while acceptance criteria are open
+                            if goose_mode != "chat" && !wrapping_up && acceptance_reminders < MAX_REMINDERS {
+                                if let Some(reminder) = self
+                                    .acceptance_checklist()
+                                    .await
+                                    .and_then(|checklist| checklist.reminder())
+                                {
+                                    acceptance_reminders += 1;
+                                    let reminder = Message::user().with_text(reminder);
+                                    yield AgentEvent::Message(reminder.clone());
+                                    messages.push(response);
+                                    messages.push(reminder);
+                                    continue;
+                                }
+                            }
                             break;
                         }
 
+                        // Calls still without a response when the reply is cancelled are
+                        // answered as cancelled
+                        let request_ids: Vec<String> = frontend_requests
+                            .iter()
+                            .chain(remaining_requests.iter())
+                            .map(|request| request.id.clone())
+                            .collect();
+
+                        if !model_pins.is_empty() {
+                            let tool_names = frontend_requests
+                                .iter()
+                                .chain(remaining_requests.iter())
+                                .filter_map(|request| request.tool_call.as_ref().ok())
+                                .map(|tool_call| tool_call.name.as_str());
+                            if let Some(hint) = model_pins.routing_hint(tool_names) {
+                                yield AgentEvent::ModelChange {
+                                    model: hint.model.clone(),
+                                    mode: "pinned".to_string(),
+                                };
+                                pinned = Some(PinnedModel::new(hint));
+                            }
+                        }
+
                         // Process tool requests depending on frontend tools and then goose_mode
                         let message_tool_response = Arc::new(Mutex::new(Message::user()));
 
@@ -672,10 +1677,14 @@ impl Agent {
                         );
 
                         // we have a stream of frontend tools to handle, inside the stream
-                        // execution is yeield back to this reply loop, and is of the same Message
-                        // type, so we can yield that back up to be handled
-                        while let Some(msg) = frontend_tool_stream.try_next().await? {
-                            yield AgentEvent::Message(msg);
+                        // execution is yeield back to this reply loop, and the requests and
+                        // their progress are yielded back up to be handled
+                        while let Some(event) = until_cancelled(&cancel, frontend_tool_stream.try_next())
+                            .await
+                            .transpose()?
+                            .flatten()
+                        {
+                            yield event;
                         }
 
                         // Clone goose_mode once before the match to avoid move issues
@@ -694,13 +1703,25 @@ impl Agent {
                             // What remains is handling the remaining tool requests (enable extension,
                             // regular tool calls) in goose_mode == ["auto", "approve" or "smart_approve"]
                             let mut permission_manager = PermissionManager::default();
-                            let (permission_check_result, enable_extension_request_ids) = check_tool_permissions(
+                            let external_approval = self.external_approval.lock().await.clone();
+                            let (mut permission_check_result, enable_extension_request_ids) = check_tool_permissions(
                                 &remaining_requests,
                                 &mode,
                                 tools_with_readonly_annotation.clone(),
                                 tools_without_annotation.clone(),
                                 &mut permission_manager,
-                                self.provider().await?).await;
+                                self.provider().await?,
+                                external_approval.as_ref()).await;
+
+                            // Extensions that reach the network ask first, whatever the mode
+                            permission_check_result.approved = self.network_policy.lock().await.require_approval(
+                                std::mem::take(&mut permission_check_result.approved),
+                                &mut permission_check_result.needs_approval,
+                            );
+                            // Staged edits are committed only once the user approves their diff
+                            self.require_commit_approval(&mut permission_check_result).await;
+                            // Servers found by discovery were never configured, so the user decides
+                            self.require_discovery_approval(&mut permission_check_result).await;
 
                             // Handle pre-approved and read-only tools in parallel
                             let mut tool_futures: Vec<(String, ToolStream)> = Vec::new();
@@ -708,7 +1729,7 @@ impl Agent {
                             // Skip the confirmation for approved tools
                             for request in &permission_check_result.approved {
                                 if let Ok(tool_call) = request.tool_call.clone() {
-                                    let (req_id, tool_result) = self.dispatch_tool_call(tool_call, request.id.clone()).await;
+                                    let (req_id, tool_result) = self.dispatch_audited_tool_call(tool_call, request.id.clone(), AuditApproval::Automatic, router.as_ref(), &cancel, session.as_ref()).await;
 
                                     tool_futures.push((req_id, match tool_result {
                                         Ok(result) => tool_stream(
@@ -724,6 +1745,9 @@ impl Agent {
                             }
 
                             for request in &permission_check_result.denied {
+                                if let Ok(tool_call) = &request.tool_call {
+                                    self.audit_not_run(&request.id, tool_call, AuditApproval::Denied, session.as_ref());
+                                }
                                 let mut response = message_tool_response.lock().await;
                                 *response = response.clone().with_tool_response(
                                     request.id.clone(),
@@ -739,14 +1763,21 @@ impl Agent {
                                 &permission_check_result.needs_approval,
                                 tool_futures_arc.clone(),
                                 &mut permission_manager,
-                                message_tool_response.clone()
+                                message_tool_response.clone(),
+                                router.as_ref(),
+                                &cancel,
+                                session.as_ref(),
                             );
 
                             // We have a stream of tool_approval_requests to handle
                             // Execution is yielded back to this reply loop, and is of the same Message
                             // type, so we can yield the Message back up to be handled and grab any
                             // confirmations or denials
-                            while let Some(msg) = tool_approval_stream.try_next().await? {
+                            while let Some(msg) = until_cancelled(&cancel, tool_approval_stream.try_next())
+                                .await
+                                .transpose()?
+                                .flatten()
+                            {
                                 yield AgentEvent::Message(msg);
                             }
 
@@ -768,12 +1799,16 @@ impl Agent {
 
                             let mut all_install_successful = true;
 
-                            while let Some((request_id, item)) = combined.next().await {
+                            while let Some((request_id, item)) = next_until_abandoned(&cancel, &mut combined).await {
                                 match item {
                                     ToolStreamItem::Result(output) => {
                                         if enable_extension_request_ids.contains(&request_id) && output.is_err(){
                                             all_install_successful = false;
                                         }
+                                        let resources = ui_resources(&request_id, &output);
+                                        if !resources.is_empty() {
+                                            yield AgentEvent::UiResources(resources);
+                                        }
                                         let mut response = message_tool_response.lock().await;
                                         *response = response.clone().with_tool_response(request_id, output);
                                     },
@@ -784,16 +1819,36 @@ impl Agent {
                             }
 
                             // Update system prompt and tools if installations were successful
-                            if all_install_successful {
-                                (tools, toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
+                            if all_install_successful && !cancel.is_cancelled() {
+                                (tools, toolshim_tools, system_prompt) = self.prepare_reply_tools_and_prompt(router.as_ref()).await?;
                             }
                         }
 
-                        let final_message_tool_resp = message_tool_response.lock().await.clone();
+                        let mut final_message_tool_resp = message_tool_response.lock().await.clone();
+                        if cancel.is_cancelled() {
+                            final_message_tool_resp = mark_cancelled_responses(answer_cancelled_calls(final_message_tool_resp, request_ids));
+                        } else {
+                            // Steering that arrived while the tools ran joins their results
+                            for input in self.acknowledge_queued_input(session_id.as_deref()).await {
+                                yield AgentEvent::InputQueued(input);
+                            }
+                            merge_steering(&mut final_message_tool_resp, self.take_steering_input(session_id.as_deref()).await);
+                        }
                         yield AgentEvent::Message(final_message_tool_resp.clone());
 
+                        let termination_reason = self.check_termination(&response).await;
+
                         messages.push(response);
                         messages.push(final_message_tool_resp);
+
+                        if cancel.is_cancelled() {
+                            yield AgentEvent::Cancelled;
+                            break;
+                        }
+                        if let Some(reason) = termination_reason {
+                            tracing::info!("Stopping reply loop: {}", reason);
+                            break;
+                        }
                     },
                     Err(ProviderError::ContextLengthExceeded(_)) => {
                         // At this point, the last message should be a user message
@@ -815,7 +1870,8 @@ impl Agent {
                 // Yield control back to the scheduler to prevent blocking
                 tokio::task::yield_now().await;
             }
-        }))
+        };
+        Ok(Box::pin(tagged(tags, stream)))
     }
 
     /// Extend the system prompt with one line of additional instruction
@@ -824,6 +1880,29 @@ impl Agent {
         prompt_manager.add_system_prompt_extra(instruction);
     }
 
+    /// Pin the original request as a stable section of the system prompt for the rest of the
+    /// session. Its acceptance criteria, if any, are tracked as a checklist.
+    pub async fn pin_request(&self, request: String, acceptance_criteria: Vec<String>) {
+        if !acceptance_criteria.is_empty() {
+            self.set_acceptance_criteria(acceptance_criteria).await;
+        }
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.pin_request(PinnedRequest::new(request));
+    }
+
+    /// Set the response language and formatting policy for this agent, replacing the one from
+    /// the config. The policy is added to the system prompt and responses are checked against it.
+    pub async fn set_response_policy(&self, policy: Option<ResponsePolicy>) {
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.set_response_policy(policy);
+    }
+
+    /// Remove the pinned request from the system prompt
+    pub async fn clear_pinned_request(&self) {
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.clear_pinned_request();
+    }
+
     /// Update the provider used by this agent
     pub async fn update_provider(&self, provider: Arc<dyn Provider>) -> Result<()> {
         *self.provider.lock().await = Some(provider.clone());
@@ -831,37 +1910,18 @@ impl Agent {
         Ok(())
     }
 
-    async fn update_router_tool_selector(&self, provider: Arc<dyn Provider>) -> Result<()> {
-        let config = Config::global();
-        let router_tool_selection_strategy = config
-            .get_param("GOOSE_ROUTER_TOOL_SELECTION_STRATEGY")
-            .unwrap_or_else(|_| "default".to_string());
-
-        let strategy = match router_tool_selection_strategy.to_lowercase().as_str() {
-            "vector" => Some(RouterToolSelectionStrategy::Vector),
-            "llm" => Some(RouterToolSelectionStrategy::Llm),
-            _ => None,
-        };
-
-        let selector = match strategy {
-            Some(RouterToolSelectionStrategy::Vector) => {
-                let table_name = generate_table_id();
-                let selector = create_tool_selector(strategy, provider, Some(table_name))
-                    .await
-                    .map_err(|e| anyhow!("Failed to create tool selector: {}", e))?;
-                Arc::new(selector)
-            }
-            Some(RouterToolSelectionStrategy::Llm) => {
-                let selector = create_tool_selector(strategy, provider, None)
-                    .await
-                    .map_err(|e| anyhow!("Failed to create tool selector: {}", e))?;
-                Arc::new(selector)
-            }
-            None => return Ok(()),
+    pub(super) async fn update_router_tool_selector(
+        &self,
+        provider: Arc<dyn Provider>,
+    ) -> Result<()> {
+        // The selectors of other workspaces were made with the previous provider
+        self.workspace_routers.lock().await.clear();
+        let Some(selector) = self.create_router_tool_selector(provider).await? else {
+            return Ok(());
         };
-        let extension_manager = self.extension_manager.lock().await;
-        ToolRouterIndexManager::index_platform_tools(&selector, &extension_manager).await?;
-        *self.router_tool_selector.lock().await = Some(selector.clone());
+        *self.router_tool_selector.lock().await = Some(selector);
+        self.clear_router_fallback().await;
+        self.load_tool_stats_store().await;
         Ok(())
     }
 
@@ -922,12 +1982,6 @@ impl Agent {
         Ok(plan_prompt)
     }
 
-    pub async fn handle_tool_result(&self, id: String, result: ToolResult<Vec<Content>>) {
-        if let Err(e) = self.tool_result_tx.send((id, result)).await {
-            tracing::error!("Failed to send tool result: {}", e);
-        }
-    }
-
     pub async fn create_recipe(&self, mut messages: Vec<Message>) -> Result<Recipe> {
         let extension_manager = self.extension_manager.lock().await;
         let extensions_info = extension_manager.get_extensions_info().await;
@@ -937,9 +1991,11 @@ impl Agent {
         let model_config = provider.get_model_config();
         let model_name = &model_config.model_name;
 
+        let tools = extension_manager.get_prefixed_tools(None).await?;
         let prompt_manager = self.prompt_manager.lock().await;
         let system_prompt = prompt_manager.build_system_prompt(
             extensions_info,
+            &tools,
             self.frontend_instructions.lock().await.clone(),
             extension_manager.suggest_disable_extensions_prompt().await,
             Some(model_name),
@@ -947,7 +2003,6 @@ impl Agent {
         );
 
         let recipe_prompt = prompt_manager.get_recipe_prompt().await;
-        let tools = extension_manager.get_prefixed_tools(None).await?;
 
         messages.push(Message::user().with_text(recipe_prompt));
 
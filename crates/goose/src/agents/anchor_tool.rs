@@ -0,0 +1,78 @@
+//! Handler for the conversation anchors platform tool
+
+use mcp_core::{Content, Role, ToolError, ToolResult};
+
+use crate::session::anchors::{get_anchor, list_anchors, set_anchor, Anchor};
+
+use super::{Agent, SessionConfig};
+
+impl Agent {
+    /// Handle anchor tool calls against the anchors of the reply's session
+    pub(super) async fn handle_anchor(
+        &self,
+        arguments: serde_json::Value,
+        session: Option<&SessionConfig>,
+    ) -> ToolResult<Vec<Content>> {
+        let session_file = session
+            .map(|s| crate::session::get_path(s.id.clone()))
+            .ok_or_else(|| {
+                ToolError::ExecutionError(
+                    "Anchors are only available in a saved session".to_string(),
+                )
+            })?;
+
+        let action = arguments
+            .get("action")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'action' parameter".into()))?;
+        let name = || {
+            arguments
+                .get("name")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::InvalidParameters("Missing 'name' parameter".into()))
+        };
+
+        match action {
+            "set" => {
+                let name = name()?;
+                let content = arguments
+                    .get("content")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ToolError::InvalidParameters("Missing 'content' parameter".into())
+                    })?;
+                set_anchor(&session_file, Anchor::new(name, Role::Assistant, content))
+                    .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+                Ok(vec![Content::text(format!("Anchor '{}' set", name))])
+            }
+            "recall" => {
+                let name = name()?;
+                match get_anchor(&session_file, name)
+                    .map_err(|e| ToolError::ExecutionError(e.to_string()))?
+                {
+                    Some(anchor) => Ok(vec![Content::text(anchor.content)]),
+                    None => Err(ToolError::NotFound(format!("No anchor named '{}'", name))),
+                }
+            }
+            "list" => {
+                let anchors = list_anchors(&session_file)
+                    .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+                if anchors.is_empty() {
+                    return Ok(vec![Content::text("No anchors have been set")]);
+                }
+                let lines: Vec<String> = anchors
+                    .iter()
+                    .map(|anchor| {
+                        let preview: String = anchor.content.chars().take(80).collect();
+                        format!("- {}: {}", anchor.name, preview.replace('\n', " "))
+                    })
+                    .collect();
+                Ok(vec![Content::text(lines.join("\n"))])
+            }
+            _ => Err(ToolError::InvalidParameters(format!(
+                "Unknown action: {}",
+                action
+            ))),
+        }
+    }
+}
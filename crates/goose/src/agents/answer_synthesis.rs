@@ -0,0 +1,250 @@
+//! Recall of earlier tool results before the final answer
+//!
+//! On long runs the model often leaves out findings from early tool calls when it writes its
+//! final answer. With `GOOSE_ANSWER_SYNTHESIS: true`, once a reply has produced at least
+//! `GOOSE_ANSWER_SYNTHESIS_MIN_RESULTS` tool results, the first response without tool calls is
+//! treated as a draft: the `GOOSE_ANSWER_SYNTHESIS_TOP_K` tool results most similar to the
+//! user's request are added back to the conversation and the final answer is generated again.
+//! The draft is not shown: when responses stream, the text of a response that could be the
+//! draft is held back until it is known to be kept.
+//!
+//! Embeddings come from `GOOSE_EMBEDDING_MODEL_PROVIDER` if it is set, as for the vector
+//! tool router, or otherwise from the agent's provider.
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use mcp_core::Role;
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::providers::embedding::cosine_similarity;
+
+use super::router_tool_selector::create_embedding_provider;
+use super::Agent;
+
+const DEFAULT_MIN_TOOL_RESULTS: usize = 10;
+const DEFAULT_TOP_K: usize = 5;
+/// Longer tool results are cut to this many characters when they are recalled
+const MAX_FINDING_CHARS: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SynthesisConfig {
+    pub enabled: bool,
+    /// Number of tool results a reply needs before its final answer is synthesized
+    pub min_tool_results: usize,
+    /// Number of tool results recalled for the final answer
+    pub top_k: usize,
+}
+
+impl Default for SynthesisConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_tool_results: DEFAULT_MIN_TOOL_RESULTS,
+            top_k: DEFAULT_TOP_K,
+        }
+    }
+}
+
+impl SynthesisConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            enabled: config
+                .get_param("GOOSE_ANSWER_SYNTHESIS")
+                .unwrap_or(defaults.enabled),
+            min_tool_results: config
+                .get_param("GOOSE_ANSWER_SYNTHESIS_MIN_RESULTS")
+                .unwrap_or(defaults.min_tool_results),
+            top_k: config
+                .get_param("GOOSE_ANSWER_SYNTHESIS_TOP_K")
+                .unwrap_or(defaults.top_k),
+        }
+    }
+
+    /// Whether the next response without tool calls would be treated as a draft
+    pub fn applies_to(&self, messages: &[Message]) -> bool {
+        self.enabled && self.top_k > 0 && tool_findings(messages).len() >= self.min_tool_results
+    }
+}
+
+/// The text a tool call returned, with the name of the tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolFinding {
+    pub tool_name: String,
+    pub text: String,
+}
+
+/// Successful tool results in the conversation that returned text, oldest first
+pub fn tool_findings(messages: &[Message]) -> Vec<ToolFinding> {
+    let mut tool_names = HashMap::new();
+    let mut findings = Vec::new();
+    for content in messages.iter().flat_map(|message| message.content.iter()) {
+        match content {
+            MessageContent::ToolRequest(request) => {
+                if let Ok(tool_call) = &request.tool_call {
+                    tool_names.insert(request.id.clone(), tool_call.name.clone());
+                }
+            }
+            MessageContent::ToolResponse(response) if response.tool_result.is_ok() => {
+                let Some(text) = content.as_tool_response_text() else {
+                    continue;
+                };
+                if text.trim().is_empty() {
+                    continue;
+                }
+                findings.push(ToolFinding {
+                    tool_name: tool_names
+                        .get(&response.id)
+                        .cloned()
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    text: text.chars().take(MAX_FINDING_CHARS).collect(),
+                });
+            }
+            _ => {}
+        }
+    }
+    findings
+}
+
+/// The most recent user message that is a request rather than tool results
+pub(super) fn user_request(messages: &[Message]) -> Option<String> {
+    messages
+        .iter()
+        .rev()
+        .filter(|message| message.role == Role::User && !message.is_tool_response())
+        .map(|message| message.as_concat_text())
+        .find(|text| !text.trim().is_empty())
+}
+
+/// Indices of the `top_k` embeddings most similar to the query, in their original order
+pub fn rank_findings(query: &[f32], embeddings: &[Vec<f32>], top_k: usize) -> Vec<usize> {
+    let mut scored: Vec<(usize, f32)> = embeddings
+        .iter()
+        .enumerate()
+        .map(|(i, embedding)| (i, cosine_similarity(query, embedding)))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let mut ranked: Vec<usize> = scored.into_iter().take(top_k).map(|(i, _)| i).collect();
+    ranked.sort_unstable();
+    ranked
+}
+
+fn render_findings(findings: &[&ToolFinding]) -> String {
+    let sections: Vec<String> = findings
+        .iter()
+        .map(|finding| format!("## {}\n{}", finding.tool_name, finding.text))
+        .collect();
+    format!(
+        "Before you give your final answer, review these earlier tool results that are relevant \
+        to my request, and make sure your answer accounts for them:\n\n{}",
+        sections.join("\n\n")
+    )
+}
+
+impl Agent {
+    /// The earlier tool results most relevant to the user's request, as a reminder to add
+    /// before the final answer, or None if there are too few to be worth recalling
+    pub(super) async fn recall_findings(
+        &self,
+        config: &SynthesisConfig,
+        messages: &[Message],
+    ) -> Result<Option<String>> {
+        if !config.applies_to(messages) {
+            return Ok(None);
+        }
+        let findings = tool_findings(messages);
+        let Some(request) = user_request(messages) else {
+            return Ok(None);
+        };
+
+        let embedding_provider = create_embedding_provider(self.provider().await?)?;
+        if !embedding_provider.supports_embeddings() {
+            return Err(anyhow!(
+                "The provider does not support embeddings, set GOOSE_EMBEDDING_MODEL_PROVIDER to one that does"
+            ));
+        }
+        let mut texts = vec![request];
+        texts.extend(findings.iter().map(|finding| finding.text.clone()));
+        let mut embeddings = embedding_provider.create_embeddings(texts).await?;
+        if embeddings.len() != findings.len() + 1 {
+            return Err(anyhow!(
+                "Expected {} embeddings but got {}",
+                findings.len() + 1,
+                embeddings.len()
+            ));
+        }
+        let query = embeddings.remove(0);
+
+        let recalled: Vec<&ToolFinding> = rank_findings(&query, &embeddings, config.top_k)
+            .into_iter()
+            .map(|i| &findings[i])
+            .collect();
+        Ok(Some(render_findings(&recalled)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use mcp_core::Content;
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_findings_pairs_results_with_tool_names() {
+        let messages = vec![
+            Message::user().with_text("why is the build failing?"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "cargo build"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("error[E0308]")])),
+            Message::assistant().with_tool_request(
+                "2",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "true"}),
+                )),
+            ),
+            Message::user().with_tool_response("2", Ok(vec![Content::text("")])),
+        ];
+        assert_eq!(
+            tool_findings(&messages),
+            vec![ToolFinding {
+                tool_name: "developer__shell".to_string(),
+                text: "error[E0308]".to_string(),
+            }]
+        );
+        assert_eq!(
+            user_request(&messages).as_deref(),
+            Some("why is the build failing?")
+        );
+
+        // Only answers after enough tool results are drafts
+        let config = SynthesisConfig {
+            enabled: true,
+            min_tool_results: 1,
+            top_k: 5,
+        };
+        assert!(config.applies_to(&messages));
+        assert!(!config.applies_to(&messages[..1]));
+    }
+
+    #[test]
+    fn test_rank_findings_keeps_conversation_order() {
+        let query = vec![1.0, 0.0];
+        let embeddings = vec![
+            vec![0.0, 1.0],
+            vec![0.9, 0.1],
+            vec![0.5, 0.5],
+            vec![1.0, 0.0],
+        ];
+        assert_eq!(rank_findings(&query, &embeddings, 2), vec![1, 3]);
+        assert_eq!(rank_findings(&query, &embeddings, 10).len(), 4);
+    }
+}
@@ -0,0 +1,180 @@
+//! Verification of the final answer against the tool results it rests on
+//!
+//! With `GOOSE_ANSWER_VERIFICATION: true`, the first response of a reply without tool calls is
+//! checked before the reply ends: a verifier reads the user's request, the tool results of the
+//! reply and the answer, and either approves the answer or lists the claims the evidence does
+//! not support. Those are sent back to the model for one corrective turn, whose answer is final.
+//! The checked answer and the problems found in it are sent to the frontend like the rest of the
+//! reply, so the conversation it keeps is the one the model corrected.
+//!
+//! ```yaml
+//! GOOSE_ANSWER_VERIFICATION: true
+//! # A model of the configured provider for the check; the session model otherwise
+//! GOOSE_ANSWER_VERIFICATION_MODEL: gpt-4o-mini
+//! # Answers of replies with fewer tool results are not checked
+//! GOOSE_ANSWER_VERIFICATION_MIN_RESULTS: 1
+//! ```
+use anyhow::Result;
+
+use crate::config::Config;
+use crate::message::Message;
+
+use super::answer_synthesis::{tool_findings, user_request, ToolFinding};
+use super::Agent;
+
+const DEFAULT_MIN_TOOL_RESULTS: usize = 1;
+/// Tool results are cut to this many characters in the verification request
+const MAX_EVIDENCE_CHARS: usize = 4_000;
+
+const VERIFIER_SYSTEM_PROMPT: &str = "You check an assistant's answer against the evidence it \
+    gathered with tools. Judge only whether the claims in the answer are supported by the \
+    evidence and whether the answer addresses the request; do not judge style. Reply with \
+    APPROVED on the first line if the answer holds up. Otherwise reply with REVISE on the \
+    first line, followed by one line per unsupported, contradicted or missing point, naming \
+    the evidence that shows it.";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationConfig {
+    pub enabled: bool,
+    /// Model of the configured provider that checks answers
+    pub model: Option<String>,
+    /// Number of tool results a reply needs before its answer is checked
+    pub min_tool_results: usize,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: None,
+            min_tool_results: DEFAULT_MIN_TOOL_RESULTS,
+        }
+    }
+}
+
+impl VerificationConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            enabled: config
+                .get_param("GOOSE_ANSWER_VERIFICATION")
+                .unwrap_or(defaults.enabled),
+            model: config.get_param("GOOSE_ANSWER_VERIFICATION_MODEL").ok(),
+            min_tool_results: config
+                .get_param("GOOSE_ANSWER_VERIFICATION_MIN_RESULTS")
+                .unwrap_or(defaults.min_tool_results),
+        }
+    }
+}
+
+/// What the verifier made of an answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Approved,
+    /// The points to correct, as the verifier listed them
+    Revise(String),
+}
+
+impl Verdict {
+    /// Read the verifier's reply; a reply in neither format approves, so a confused verifier
+    /// never holds up the answer
+    pub fn parse(reply: &str) -> Self {
+        let reply = reply.trim();
+        let (first, rest) = reply.split_once('\n').unwrap_or((reply, ""));
+        let first = first.trim().trim_matches(|c: char| !c.is_alphanumeric());
+        if first.eq_ignore_ascii_case("revise") && !rest.trim().is_empty() {
+            Verdict::Revise(rest.trim().to_string())
+        } else {
+            Verdict::Approved
+        }
+    }
+
+    /// The message that asks the model for its corrective turn
+    pub fn correction(&self) -> Option<String> {
+        match self {
+            Verdict::Approved => None,
+            Verdict::Revise(issues) => Some(format!(
+                "A check of your answer against the tool results found these problems:\n{}\n\n\
+                 Correct your answer. Gather more evidence with tools if you need it, and say \
+                 plainly what you could not confirm.",
+                issues
+            )),
+        }
+    }
+}
+
+fn verification_request(request: &str, findings: &[ToolFinding], answer: &str) -> Message {
+    let evidence: Vec<String> = findings
+        .iter()
+        .enumerate()
+        .map(|(i, finding)| {
+            let text: String = finding.text.chars().take(MAX_EVIDENCE_CHARS).collect();
+            format!("[{}] {}\n{}", i + 1, finding.tool_name, text)
+        })
+        .collect();
+    Message::user().with_text(format!(
+        "Request:\n{}\n\nEvidence:\n{}\n\nAnswer:\n{}",
+        request,
+        evidence.join("\n\n"),
+        answer
+    ))
+}
+
+impl Agent {
+    /// Check the final answer of a reply against its tool results, or None if the reply has
+    /// too few of them to check against
+    pub(super) async fn verify_answer(
+        &self,
+        config: &VerificationConfig,
+        messages: &[Message],
+        answer: &Message,
+    ) -> Result<Option<Verdict>> {
+        let findings = tool_findings(messages);
+        if findings.len() < config.min_tool_results.max(1) {
+            return Ok(None);
+        }
+        let Some(request) = user_request(messages) else {
+            return Ok(None);
+        };
+        let provider = match &config.model {
+            Some(model) => crate::providers::create_for_model(model)?,
+            None => self.provider().await?,
+        };
+        let check = [verification_request(
+            &request,
+            &findings,
+            &answer.as_concat_text(),
+        )];
+        let (reply, _usage) = provider
+            .complete(VERIFIER_SYSTEM_PROMPT, &check, &[])
+            .await?;
+        Ok(Some(Verdict::parse(&reply.as_concat_text())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verdict() {
+        assert_eq!(Verdict::parse("APPROVED"), Verdict::Approved);
+        assert_eq!(
+            Verdict::parse("**Approved.**\nLooks right."),
+            Verdict::Approved
+        );
+        assert_eq!(
+            Verdict::parse("REVISE\n- The release date is 2024, not 2023 [2]\n"),
+            Verdict::Revise("- The release date is 2024, not 2023 [2]".to_string())
+        );
+        // Without points to correct there is nothing to revise
+        assert_eq!(Verdict::parse("REVISE"), Verdict::Approved);
+        assert_eq!(Verdict::parse("I am not sure."), Verdict::Approved);
+        assert!(Verdict::Approved.correction().is_none());
+        assert!(Verdict::Revise("- wrong".to_string())
+            .correction()
+            .unwrap()
+            .contains("- wrong"));
+    }
+}
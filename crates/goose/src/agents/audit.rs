@@ -0,0 +1,333 @@
+//! Audit log of tool calls
+//!
+//! For compliance review of what an agent did, every tool call it decides on can be appended
+//! to a JSONL audit log: the tool, a hash of its arguments, how the call was approved, how long
+//! it ran and whether it succeeded, along with the session it was made in. The arguments
+//! themselves are not logged, as they can hold file contents and secrets; the hash shows which
+//! calls were identical and can be checked against a session's history.
+//!
+//! The log is off by default, and written to `tool_audit.jsonl` in the goose data directory
+//! unless another path is given:
+//!
+//! ```yaml
+//! GOOSE_AUDIT_LOG: true
+//! GOOSE_AUDIT_LOG_PATH: /var/log/goose/tool_audit.jsonl
+//! ```
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use futures::FutureExt;
+use mcp_core::{Content, ToolCall, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use super::cancellation::session_key;
+use super::tool_execution::ToolCallResult;
+use super::{Agent, SessionConfig};
+use crate::config::{Config, APP_STRATEGY};
+
+/// How a tool call came to be run, or not
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditApproval {
+    /// Allowed without asking, by the mode, a permission or the tool being read-only
+    Automatic,
+    /// Approved by the user when asked
+    User,
+    /// Declined by the user when asked
+    Declined,
+    /// Refused by a permission without asking
+    Denied,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditStatus {
+    Success,
+    Error,
+    /// The call was declined or denied, so it never ran
+    NotRun,
+}
+
+/// One tool call in the audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub session_id: Option<String>,
+    pub request_id: String,
+    pub tool_name: String,
+    /// SHA-256 of the call's arguments as JSON
+    pub arguments_hash: String,
+    pub approval: AuditApproval,
+    pub status: AuditStatus,
+    /// How long the call took, for calls that ran
+    pub duration_ms: Option<u64>,
+}
+
+/// The hash of a tool call's arguments, the same for the same arguments in any key order
+pub fn arguments_hash(arguments: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(with_sorted_keys(arguments).to_string().as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn with_sorted_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), with_sorted_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(with_sorted_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The audit log if `GOOSE_AUDIT_LOG` is on
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        if !config.get_param::<bool>("GOOSE_AUDIT_LOG").unwrap_or(false) {
+            return None;
+        }
+        let path = config
+            .get_param::<String>("GOOSE_AUDIT_LOG_PATH")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| {
+                choose_app_strategy(APP_STRATEGY.clone())
+                    .ok()
+                    .map(|strategy| strategy.data_dir().join("tool_audit.jsonl"))
+            })?;
+        Some(Self::new(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record, logging rather than failing the tool call if it cannot be written
+    pub fn append(&self, record: &AuditRecord) {
+        if let Err(e) = self.try_append(record) {
+            tracing::error!(
+                "Failed to write to the audit log {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+
+    fn try_append(&self, record: &AuditRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // One write per record, so that concurrent calls do not interleave their lines
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// The records in the log, skipping lines that cannot be read
+    pub fn read(&self) -> Result<Vec<AuditRecord>> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(contents
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn record(
+        session_id: Option<String>,
+        request_id: &str,
+        tool_call: &ToolCall,
+        approval: AuditApproval,
+    ) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now(),
+            session_id,
+            request_id: request_id.to_string(),
+            tool_name: tool_call.name.clone(),
+            arguments_hash: arguments_hash(&tool_call.arguments),
+            approval,
+            status: AuditStatus::NotRun,
+            duration_ms: None,
+        }
+    }
+
+    /// Record a call that was declined or denied
+    pub fn not_run(
+        &self,
+        session_id: Option<String>,
+        request_id: &str,
+        tool_call: &ToolCall,
+        approval: AuditApproval,
+    ) {
+        self.append(&Self::record(session_id, request_id, tool_call, approval));
+    }
+
+    /// Start recording a call that is about to be dispatched
+    pub fn start(
+        &self,
+        session_id: Option<String>,
+        request_id: &str,
+        tool_call: &ToolCall,
+        approval: AuditApproval,
+    ) -> AuditedCall {
+        AuditedCall {
+            log: self.clone(),
+            record: Self::record(session_id, request_id, tool_call, approval),
+            started: Instant::now(),
+        }
+    }
+}
+
+/// A tool call being recorded, from its dispatch until its result is known
+pub struct AuditedCall {
+    log: AuditLog,
+    record: AuditRecord,
+    started: Instant,
+}
+
+impl AuditedCall {
+    fn finish(mut self, result: &ToolResult<Vec<Content>>) {
+        self.record.status = match result {
+            Ok(_) => AuditStatus::Success,
+            Err(_) => AuditStatus::Error,
+        };
+        self.record.duration_ms = Some(self.started.elapsed().as_millis() as u64);
+        self.log.append(&self.record);
+    }
+
+    /// Record the call once its result is known, passing the result through
+    pub fn track(
+        self,
+        result: Result<ToolCallResult, ToolError>,
+    ) -> Result<ToolCallResult, ToolError> {
+        match result {
+            Ok(call) => Ok(ToolCallResult {
+                result: Box::new(call.result.map(move |result| {
+                    self.finish(&result);
+                    result
+                })),
+                notification_stream: call.notification_stream,
+            }),
+            Err(e) => {
+                self.finish(&Err(e.clone()));
+                Err(e)
+            }
+        }
+    }
+}
+
+/// The id of a reply's session, as recorded in the audit log
+pub(super) fn audit_session_id(session: Option<&SessionConfig>) -> Option<String> {
+    session.and_then(|s| session_key(&s.id))
+}
+
+impl Agent {
+    /// Record a tool call that was declined or denied, if the audit log is on
+    pub(super) fn audit_not_run(
+        &self,
+        request_id: &str,
+        tool_call: &ToolCall,
+        approval: AuditApproval,
+        session: Option<&SessionConfig>,
+    ) {
+        if let Some(log) = &self.audit_log {
+            log.not_run(audit_session_id(session), request_id, tool_call, approval);
+        }
+    }
+
+    /// The audit log of a session, or of every session without one. Empty if the audit log
+    /// is off.
+    pub async fn get_audit_log(&self, session: Option<&SessionConfig>) -> Result<Vec<AuditRecord>> {
+        let Some(log) = &self.audit_log else {
+            return Ok(Vec::new());
+        };
+        let session_id = audit_session_id(session);
+        let mut records = log.read()?;
+        if session_id.is_some() {
+            records.retain(|record| record.session_id == session_id);
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_records_tool_calls() {
+        let dir = TempDir::new().unwrap();
+        let log = AuditLog::new(dir.path().join("audit").join("tool_audit.jsonl"));
+        let call = ToolCall::new("developer__shell", json!({"command": "ls", "timeout": 5}));
+
+        let result = log
+            .start(
+                Some("20250101_120000".to_string()),
+                "req_1",
+                &call,
+                AuditApproval::User,
+            )
+            .track(Ok(ToolCallResult::from(Ok(vec![Content::text("src")]))));
+        // Nothing is recorded until the call finishes
+        assert!(log.read().unwrap().is_empty());
+        result.unwrap().result.await.unwrap();
+
+        let failed = log
+            .start(None, "req_2", &call, AuditApproval::Automatic)
+            .track(Err(ToolError::NotFound("developer__shell".to_string())));
+        assert!(failed.is_err());
+        log.not_run(None, "req_3", &call, AuditApproval::Declined);
+
+        let records = log.read().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].status, AuditStatus::Success);
+        assert_eq!(records[0].approval, AuditApproval::User);
+        assert_eq!(records[0].session_id.as_deref(), Some("20250101_120000"));
+        assert!(records[0].duration_ms.is_some());
+        assert_eq!(records[1].status, AuditStatus::Error);
+        assert_eq!(records[2].status, AuditStatus::NotRun);
+        assert_eq!(records[2].duration_ms, None);
+        assert_eq!(
+            records[0].arguments_hash,
+            arguments_hash(&json!({"timeout": 5, "command": "ls"}))
+        );
+        // The arguments themselves are never written
+        let contents = fs::read_to_string(log.path()).unwrap();
+        assert!(!contents.contains("\"ls\""));
+    }
+}
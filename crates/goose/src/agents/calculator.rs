@@ -0,0 +1,745 @@
+//! A deterministic calculator for the model
+//!
+//! Models are unreliable at arithmetic done in tokens, so the calculate platform tool evaluates
+//! expressions with arbitrary precision decimals and converts the result between units from a
+//! static table. Currency conversions use a static table of approximate rates, or live rates
+//! when `GOOSE_CALCULATOR_RATES_URL` points at an endpoint returning `{"rates": {"EUR": 0.92}}`
+//! in units per US dollar. Live rates are cached for an hour, and the static table is used
+//! whenever they cannot be fetched.
+//!
+//! ```yaml
+//! GOOSE_CALCULATOR_RATES_URL: https://open.er-api.com/v6/latest/USD
+//! ```
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use bigdecimal::{BigDecimal, One, RoundingMode, ToPrimitive, Zero};
+use mcp_core::{Content, ToolError, ToolResult};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::config::Config;
+use crate::providers::http_client;
+
+/// Significant digits of results that are not whole numbers
+const SIGNIFICANT_DIGITS: u64 = 32;
+/// Largest exponent accepted by `^`, to keep results to a reasonable size
+const MAX_EXPONENT: i64 = 1_000;
+/// Largest number of digits a power may have, as nested powers grow faster than the exponent
+const MAX_RESULT_DIGITS: u64 = 10_000;
+const RATES_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+const RATES_TIMEOUT: Duration = Duration::from_secs(5);
+
+const PI: &str = "3.14159265358979323846264338327950288419716939937510";
+const E: &str = "2.71828182845904523536028747135266249775724709369995";
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CalculatorError {
+    #[error("Unexpected '{0}' in expression")]
+    UnexpectedToken(String),
+    #[error("Expression ended unexpectedly")]
+    UnexpectedEnd,
+    #[error("Unknown function or constant '{0}'")]
+    UnknownName(String),
+    #[error("{0}() takes {1} argument(s)")]
+    WrongArguments(&'static str, &'static str),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("{0}")]
+    Unsupported(String),
+    #[error("Unknown unit '{0}'")]
+    UnknownUnit(String),
+    #[error("Cannot convert {0} to {1}")]
+    IncompatibleUnits(String, String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigDecimal),
+    Name(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, CalculatorError> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_')
+            {
+                i += 1;
+            }
+            // An exponent, as in 1.5e-3, but not the constant e as in 2e
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let sign = matches!(chars.get(i + 1), Some('+' | '-')) as usize;
+                if chars.get(i + 1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+                    i += 1 + sign;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let literal: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let number = BigDecimal::from_str(&literal)
+                .map_err(|_| CalculatorError::UnexpectedToken(literal.clone()))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Name(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '%' | '^' => Token::Op(c),
+                '×' => Token::Op('*'),
+                '÷' => Token::Op('/'),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                _ => return Err(CalculatorError::UnexpectedToken(c.to_string())),
+            });
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive descent parser that evaluates as it parses
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), CalculatorError> {
+        match self.advance() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(unexpected(&token)),
+            None => Err(CalculatorError::UnexpectedEnd),
+        }
+    }
+
+    /// expression = term (("+" | "-") term)*
+    fn expression(&mut self) -> Result<BigDecimal, CalculatorError> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    /// term = unary (("*" | "/" | "%") unary)*
+    fn term(&mut self) -> Result<BigDecimal, CalculatorError> {
+        let mut value = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek().cloned() {
+            self.pos += 1;
+            let rhs = self.unary()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs.is_zero() => return Err(CalculatorError::DivisionByZero),
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Ok(value)
+    }
+
+    /// unary = ("-" | "+") unary | power
+    fn unary(&mut self) -> Result<BigDecimal, CalculatorError> {
+        match self.peek() {
+            Some(Token::Op('-')) => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some(Token::Op('+')) => {
+                self.pos += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// power = atom ("^" unary)?, so that 2^3^2 is 2^9 and -2^2 is -4
+    fn power(&mut self) -> Result<BigDecimal, CalculatorError> {
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Op('^')) {
+            self.pos += 1;
+            let exponent = self.unary()?;
+            return pow(&base, &exponent);
+        }
+        Ok(base)
+    }
+
+    /// atom = number | name | name "(" arguments ")" | "(" expression ")"
+    fn atom(&mut self) -> Result<BigDecimal, CalculatorError> {
+        match self.advance() {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Open) => {
+                let value = self.expression()?;
+                self.expect(Token::Close)?;
+                Ok(value)
+            }
+            Some(Token::Name(name)) if self.peek() == Some(&Token::Open) => {
+                self.pos += 1;
+                let mut arguments = vec![self.expression()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    arguments.push(self.expression()?);
+                }
+                self.expect(Token::Close)?;
+                call(&name, arguments)
+            }
+            Some(Token::Name(name)) => match name.to_lowercase().as_str() {
+                "pi" => Ok(BigDecimal::from_str(PI).unwrap()),
+                "e" => Ok(BigDecimal::from_str(E).unwrap()),
+                _ => Err(CalculatorError::UnknownName(name)),
+            },
+            Some(token) => Err(unexpected(&token)),
+            None => Err(CalculatorError::UnexpectedEnd),
+        }
+    }
+}
+
+fn unexpected(token: &Token) -> CalculatorError {
+    let text = match token {
+        Token::Number(number) => number.to_string(),
+        Token::Name(name) => name.clone(),
+        Token::Op(op) => op.to_string(),
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+        Token::Comma => ",".to_string(),
+    };
+    CalculatorError::UnexpectedToken(text)
+}
+
+fn pow(base: &BigDecimal, exponent: &BigDecimal) -> Result<BigDecimal, CalculatorError> {
+    if !exponent.is_integer() {
+        return Err(CalculatorError::Unsupported(
+            "Exponents must be whole numbers; use sqrt() for square roots".to_string(),
+        ));
+    }
+    let n = exponent
+        .to_i64()
+        .filter(|n| n.abs() <= MAX_EXPONENT)
+        .ok_or_else(|| {
+            CalculatorError::Unsupported(format!("Exponents are limited to ±{}", MAX_EXPONENT))
+        })?;
+    if n < 0 && base.is_zero() {
+        return Err(CalculatorError::DivisionByZero);
+    }
+    // The digits of the result, including zeros implied by the scale, grow with the exponent
+    let (_, scale) = base.as_bigint_and_exponent();
+    let base_digits = base.digits() + scale.unsigned_abs();
+    if base_digits.saturating_mul(n.unsigned_abs()) > MAX_RESULT_DIGITS {
+        return Err(CalculatorError::Unsupported(format!(
+            "Powers are limited to {} digits",
+            MAX_RESULT_DIGITS
+        )));
+    }
+    let mut result = BigDecimal::one();
+    let mut square = base.clone();
+    let mut remaining = n.unsigned_abs();
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            result = &result * &square;
+        }
+        square = &square * &square;
+        remaining >>= 1;
+    }
+    Ok(if n < 0 {
+        BigDecimal::one() / result
+    } else {
+        result
+    })
+}
+
+fn call(name: &str, arguments: Vec<BigDecimal>) -> Result<BigDecimal, CalculatorError> {
+    let single = |function: &'static str| match arguments.as_slice() {
+        [x] => Ok(x.clone()),
+        _ => Err(CalculatorError::WrongArguments(function, "1")),
+    };
+    match name.to_lowercase().as_str() {
+        "sqrt" => single("sqrt")?.sqrt().ok_or_else(|| {
+            CalculatorError::Unsupported("Square root of a negative number".to_string())
+        }),
+        "abs" => Ok(single("abs")?.abs()),
+        "floor" => Ok(single("floor")?.with_scale_round(0, RoundingMode::Floor)),
+        "ceil" => Ok(single("ceil")?.with_scale_round(0, RoundingMode::Ceiling)),
+        "round" => match arguments.as_slice() {
+            [x] => Ok(x.with_scale_round(0, RoundingMode::HalfUp)),
+            [x, digits] => {
+                let digits = digits
+                    .to_i64()
+                    .filter(|d| digits.is_integer() && d.abs() <= MAX_EXPONENT)
+                    .ok_or_else(|| {
+                        CalculatorError::Unsupported(
+                            "round() takes a whole number of digits".to_string(),
+                        )
+                    })?;
+                Ok(x.with_scale_round(digits, RoundingMode::HalfUp))
+            }
+            _ => Err(CalculatorError::WrongArguments("round", "1 or 2")),
+        },
+        "min" => Ok(arguments.into_iter().reduce(|a, b| a.min(b)).unwrap()),
+        "max" => Ok(arguments.into_iter().reduce(|a, b| a.max(b)).unwrap()),
+        _ => Err(CalculatorError::UnknownName(name.to_string())),
+    }
+}
+
+/// Evaluate an arithmetic expression
+pub fn evaluate(expression: &str) -> Result<BigDecimal, CalculatorError> {
+    let mut parser = Parser {
+        tokens: tokenize(expression)?,
+        pos: 0,
+    };
+    let value = parser.expression()?;
+    match parser.advance() {
+        None => Ok(value),
+        Some(token) => Err(unexpected(&token)),
+    }
+}
+
+/// A number as shown to the model: whole numbers exactly, others to 32 significant digits
+pub fn format_number(value: &BigDecimal) -> String {
+    if value.is_integer() {
+        return value.with_scale(0).to_string();
+    }
+    value.with_prec(SIGNIFICANT_DIGITS).normalized().to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+    Speed,
+    Data,
+    Temperature,
+}
+
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    /// The size of the unit in the base unit of its dimension, as an expression. Unused for
+    /// temperatures, which do not share a zero.
+    factor: &'static str,
+}
+
+const fn unit(names: &'static [&'static str], dimension: Dimension, factor: &'static str) -> Unit {
+    Unit {
+        names,
+        dimension,
+        factor,
+    }
+}
+
+/// Units by dimension, in meters, square meters, liters, kilograms, seconds, meters per
+/// second and bytes. Temperatures are converted through kelvin separately.
+#[rustfmt::skip]
+static UNITS: &[Unit] = &[
+    unit(&["m", "meter", "meters", "metre", "metres"], Dimension::Length, "1"),
+    unit(&["km", "kilometer", "kilometers", "kilometre", "kilometres"], Dimension::Length, "1000"),
+    unit(&["cm", "centimeter", "centimeters", "centimetre", "centimetres"], Dimension::Length, "0.01"),
+    unit(&["mm", "millimeter", "millimeters", "millimetre", "millimetres"], Dimension::Length, "0.001"),
+    unit(&["um", "µm", "micrometer", "micrometers", "micron", "microns"], Dimension::Length, "0.000001"),
+    unit(&["nm", "nanometer", "nanometers"], Dimension::Length, "0.000000001"),
+    unit(&["in", "inch", "inches"], Dimension::Length, "0.0254"),
+    unit(&["ft", "foot", "feet"], Dimension::Length, "0.3048"),
+    unit(&["yd", "yard", "yards"], Dimension::Length, "0.9144"),
+    unit(&["mi", "mile", "miles"], Dimension::Length, "1609.344"),
+    unit(&["nmi", "nautical_mile", "nautical_miles"], Dimension::Length, "1852"),
+    unit(&["m2", "sq_m", "square_meter", "square_meters"], Dimension::Area, "1"),
+    unit(&["km2", "sq_km", "square_kilometer", "square_kilometers"], Dimension::Area, "1000000"),
+    unit(&["cm2", "sq_cm"], Dimension::Area, "0.0001"),
+    unit(&["ha", "hectare", "hectares"], Dimension::Area, "10000"),
+    unit(&["acre", "acres"], Dimension::Area, "4046.8564224"),
+    unit(&["ft2", "sq_ft", "square_foot", "square_feet"], Dimension::Area, "0.09290304"),
+    unit(&["in2", "sq_in", "square_inch", "square_inches"], Dimension::Area, "0.00064516"),
+    unit(&["mi2", "sq_mi", "square_mile", "square_miles"], Dimension::Area, "2589988.110336"),
+    unit(&["l", "L", "liter", "liters", "litre", "litres"], Dimension::Volume, "1"),
+    unit(&["ml", "mL", "milliliter", "milliliters", "millilitre", "millilitres"], Dimension::Volume, "0.001"),
+    unit(&["m3", "cubic_meter", "cubic_meters"], Dimension::Volume, "1000"),
+    unit(&["gal", "gallon", "gallons"], Dimension::Volume, "3.785411784"),
+    unit(&["qt", "quart", "quarts"], Dimension::Volume, "0.946352946"),
+    unit(&["pt", "pint", "pints"], Dimension::Volume, "0.473176473"),
+    unit(&["cup", "cups"], Dimension::Volume, "0.2365882365"),
+    unit(&["floz", "fl_oz", "fluid_ounce", "fluid_ounces"], Dimension::Volume, "0.0295735295625"),
+    unit(&["tbsp", "tablespoon", "tablespoons"], Dimension::Volume, "0.01478676478125"),
+    unit(&["tsp", "teaspoon", "teaspoons"], Dimension::Volume, "0.00492892159375"),
+    unit(&["kg", "kilogram", "kilograms"], Dimension::Mass, "1"),
+    unit(&["g", "gram", "grams"], Dimension::Mass, "0.001"),
+    unit(&["mg", "milligram", "milligrams"], Dimension::Mass, "0.000001"),
+    unit(&["t", "tonne", "tonnes"], Dimension::Mass, "1000"),
+    unit(&["lb", "lbs", "pound", "pounds"], Dimension::Mass, "0.45359237"),
+    unit(&["oz", "ounce", "ounces"], Dimension::Mass, "0.028349523125"),
+    unit(&["st", "stone", "stones"], Dimension::Mass, "6.35029318"),
+    unit(&["s", "sec", "second", "seconds"], Dimension::Time, "1"),
+    unit(&["ms", "millisecond", "milliseconds"], Dimension::Time, "0.001"),
+    unit(&["us", "µs", "microsecond", "microseconds"], Dimension::Time, "0.000001"),
+    unit(&["ns", "nanosecond", "nanoseconds"], Dimension::Time, "0.000000001"),
+    unit(&["min", "minute", "minutes"], Dimension::Time, "60"),
+    unit(&["h", "hr", "hour", "hours"], Dimension::Time, "3600"),
+    unit(&["d", "day", "days"], Dimension::Time, "86400"),
+    unit(&["wk", "week", "weeks"], Dimension::Time, "604800"),
+    // A Julian year
+    unit(&["yr", "year", "years"], Dimension::Time, "31557600"),
+    unit(&["m/s", "mps"], Dimension::Speed, "1"),
+    unit(&["km/h", "kph", "kmh"], Dimension::Speed, "1000/3600"),
+    unit(&["mph", "mi/h"], Dimension::Speed, "1609.344/3600"),
+    unit(&["kn", "kt", "knot", "knots"], Dimension::Speed, "1852/3600"),
+    unit(&["ft/s", "fps"], Dimension::Speed, "0.3048"),
+    unit(&["B", "byte", "bytes"], Dimension::Data, "1"),
+    unit(&["bit", "bits"], Dimension::Data, "0.125"),
+    unit(&["KB", "kB", "kilobyte", "kilobytes"], Dimension::Data, "1000"),
+    unit(&["MB", "megabyte", "megabytes"], Dimension::Data, "1000^2"),
+    unit(&["GB", "gigabyte", "gigabytes"], Dimension::Data, "1000^3"),
+    unit(&["TB", "terabyte", "terabytes"], Dimension::Data, "1000^4"),
+    unit(&["KiB", "kibibyte", "kibibytes"], Dimension::Data, "1024"),
+    unit(&["MiB", "mebibyte", "mebibytes"], Dimension::Data, "1024^2"),
+    unit(&["GiB", "gibibyte", "gibibytes"], Dimension::Data, "1024^3"),
+    unit(&["TiB", "tebibyte", "tebibytes"], Dimension::Data, "1024^4"),
+    unit(&["K", "kelvin"], Dimension::Temperature, "1"),
+    unit(&["C", "°C", "celsius"], Dimension::Temperature, "1"),
+    unit(&["F", "°F", "fahrenheit"], Dimension::Temperature, "1"),
+];
+
+/// Approximate units of each currency per US dollar, used when live rates are not available
+#[rustfmt::skip]
+const STATIC_RATES: &[(&str, &str)] = &[
+    ("USD", "1"), ("EUR", "0.92"), ("GBP", "0.79"), ("JPY", "150"), ("CNY", "7.2"),
+    ("INR", "83"), ("CAD", "1.36"), ("AUD", "1.52"), ("NZD", "1.65"), ("CHF", "0.88"),
+    ("SEK", "10.5"), ("NOK", "10.6"), ("DKK", "6.9"), ("PLN", "4.0"), ("CZK", "23"),
+    ("MXN", "17"), ("BRL", "5.0"), ("ZAR", "18.5"), ("KRW", "1330"), ("SGD", "1.34"),
+    ("HKD", "7.8"), ("TRY", "32"), ("AED", "3.6725"), ("ILS", "3.7"),
+];
+
+/// Exchange rates in units of each currency per US dollar
+#[derive(Debug, Clone)]
+pub struct ExchangeRates {
+    per_usd: HashMap<String, BigDecimal>,
+    /// Where the rates came from, as told to the model
+    source: String,
+}
+
+static LIVE_RATES: Lazy<Mutex<Option<(Instant, HashMap<String, BigDecimal>)>>> =
+    Lazy::new(|| Mutex::new(None));
+
+impl ExchangeRates {
+    pub fn fixed() -> Self {
+        Self {
+            per_usd: STATIC_RATES
+                .iter()
+                .map(|(code, rate)| (code.to_string(), BigDecimal::from_str(rate).unwrap()))
+                .collect(),
+            source: "approximate static rates, which may be out of date".to_string(),
+        }
+    }
+
+    /// Live rates from `GOOSE_CALCULATOR_RATES_URL` if it is set and they can be fetched, and
+    /// the static rates otherwise
+    pub async fn load() -> Self {
+        let mut rates = Self::fixed();
+        let Ok(url) = Config::global().get_param::<String>("GOOSE_CALCULATOR_RATES_URL") else {
+            return rates;
+        };
+        let cached = LIVE_RATES
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|(fetched, _)| fetched.elapsed() < RATES_CACHE_TTL);
+        let live = match cached {
+            Some((_, live)) => live,
+            None => match fetch_rates(&url).await {
+                Ok(live) => {
+                    *LIVE_RATES.lock().unwrap() = Some((Instant::now(), live.clone()));
+                    live
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch exchange rates from {}: {}", url, e);
+                    return rates;
+                }
+            },
+        };
+        rates.per_usd.extend(live);
+        rates.source = format!("live rates from {}", url);
+        rates
+    }
+
+    fn get(&self, code: &str) -> Option<&BigDecimal> {
+        self.per_usd.get(&code.to_uppercase())
+    }
+}
+
+async fn fetch_rates(url: &str) -> anyhow::Result<HashMap<String, BigDecimal>> {
+    let body = http_client::default_client()?
+        .get(url)
+        .timeout(RATES_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_rates(&body)
+}
+
+#[derive(Deserialize)]
+struct RatesResponse<'a> {
+    #[serde(borrow)]
+    rates: HashMap<String, &'a RawValue>,
+}
+
+/// Rates are read from their literal text, so they are never rounded through a float
+fn parse_rates(body: &str) -> anyhow::Result<HashMap<String, BigDecimal>> {
+    let response: RatesResponse = serde_json::from_str(body)?;
+    Ok(response
+        .rates
+        .into_iter()
+        .filter_map(|(code, rate)| {
+            let rate = BigDecimal::from_str(rate.get()).ok()?;
+            (!rate.is_zero()).then(|| (code.to_uppercase(), rate))
+        })
+        .collect())
+}
+
+enum Quantity {
+    Unit(&'static Unit),
+    Currency(String),
+}
+
+fn find_unit(name: &str, rates: &ExchangeRates) -> Option<Quantity> {
+    let name = name.trim();
+    // Exact names first, so that "Cup" or "B" are not mistaken for another unit
+    if let Some(unit) = UNITS.iter().find(|unit| unit.names.contains(&name)) {
+        return Some(Quantity::Unit(unit));
+    }
+    if rates.get(name).is_some() {
+        return Some(Quantity::Currency(name.to_uppercase()));
+    }
+    UNITS
+        .iter()
+        .find(|unit| unit.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        .map(Quantity::Unit)
+}
+
+/// Temperatures in kelvin, and back
+fn to_kelvin(value: BigDecimal, unit: &Unit) -> BigDecimal {
+    let offset = BigDecimal::from_str("273.15").unwrap();
+    match unit.names[0] {
+        "C" => value + offset,
+        "F" => (value - BigDecimal::from(32)) * BigDecimal::from(5) / BigDecimal::from(9) + offset,
+        _ => value,
+    }
+}
+
+fn from_kelvin(value: BigDecimal, unit: &Unit) -> BigDecimal {
+    let offset = BigDecimal::from_str("273.15").unwrap();
+    match unit.names[0] {
+        "C" => value - offset,
+        "F" => (value - offset) * BigDecimal::from(9) / BigDecimal::from(5) + BigDecimal::from(32),
+        _ => value,
+    }
+}
+
+/// Convert a value between two units or currencies
+pub fn convert(
+    value: BigDecimal,
+    from: &str,
+    to: &str,
+    rates: &ExchangeRates,
+) -> Result<BigDecimal, CalculatorError> {
+    let source =
+        find_unit(from, rates).ok_or_else(|| CalculatorError::UnknownUnit(from.to_string()))?;
+    let target =
+        find_unit(to, rates).ok_or_else(|| CalculatorError::UnknownUnit(to.to_string()))?;
+    let incompatible = || CalculatorError::IncompatibleUnits(from.to_string(), to.to_string());
+    match (source, target) {
+        (Quantity::Currency(source), Quantity::Currency(target)) => {
+            let usd = value / rates.get(&source).unwrap();
+            Ok(usd * rates.get(&target).unwrap())
+        }
+        (Quantity::Unit(source), Quantity::Unit(target)) => {
+            if source.dimension != target.dimension {
+                return Err(incompatible());
+            }
+            if source.dimension == Dimension::Temperature {
+                return Ok(from_kelvin(to_kelvin(value, source), target));
+            }
+            Ok(value * evaluate(source.factor)? / evaluate(target.factor)?)
+        }
+        _ => Err(incompatible()),
+    }
+}
+
+/// Handle a call to the calculate platform tool
+pub async fn handle_calculate(arguments: Value) -> ToolResult<Vec<Content>> {
+    let expression = arguments
+        .get("expression")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidParameters("Missing 'expression' parameter".into()))?;
+    let from = arguments.get("from").and_then(|v| v.as_str());
+    let to = arguments.get("to").and_then(|v| v.as_str());
+    let invalid = |e: CalculatorError| ToolError::InvalidParameters(e.to_string());
+
+    let value = evaluate(expression).map_err(invalid)?;
+    let text = match (from, to) {
+        (None, None) => format!("{} = {}", expression, format_number(&value)),
+        (Some(from), Some(to)) => {
+            let currency = [from, to]
+                .iter()
+                .all(|unit| unit.len() == 3 && unit.chars().all(|c| c.is_ascii_alphabetic()));
+            let rates = if currency {
+                ExchangeRates::load().await
+            } else {
+                ExchangeRates::fixed()
+            };
+            let converted = convert(value.clone(), from, to, &rates).map_err(invalid)?;
+            let mut text = format!(
+                "{} {} = {} {}",
+                format_number(&value),
+                from,
+                format_number(&converted),
+                to
+            );
+            if let Some(Quantity::Currency(_)) = find_unit(from, &rates) {
+                text.push_str(&format!("\n(using {})", rates.source));
+            }
+            text
+        }
+        _ => {
+            return Err(ToolError::InvalidParameters(
+                "Give both 'from' and 'to' to convert the result".into(),
+            ))
+        }
+    };
+    Ok(vec![Content::text(text)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expression: &str) -> String {
+        format_number(&evaluate(expression).unwrap())
+    }
+
+    fn conv(value: &str, from: &str, to: &str) -> String {
+        let value = BigDecimal::from_str(value).unwrap();
+        format_number(&convert(value, from, to, &ExchangeRates::fixed()).unwrap())
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(calc("0.1 + 0.2"), "0.3");
+        assert_eq!(calc("2 + 3 * 4"), "14");
+        assert_eq!(calc("(2 + 3) * 4"), "20");
+        assert_eq!(calc("-2^2"), "-4");
+        assert_eq!(calc("2^3^2"), "512");
+        assert_eq!(calc("2^-2"), "0.25");
+        assert_eq!(calc("2^100"), "1267650600228229401496703205376");
+        assert_eq!(calc("17 % 5"), "2");
+        assert_eq!(calc("1_000 * 1.5e3"), "1500000");
+        assert_eq!(calc("1/3"), "0.33333333333333333333333333333333");
+        assert_eq!(calc("sqrt(16) + abs(-2)"), "6");
+        assert_eq!(calc("round(2.675, 2)"), "2.68");
+        assert_eq!(calc("floor(-1.5) + ceil(1.2)"), "0");
+        assert_eq!(calc("max(1, 7, 3) - min(4, 2)"), "5");
+        assert!(calc("2 * pi").starts_with("6.28318530717958647692"));
+    }
+
+    #[test]
+    fn test_evaluate_errors() {
+        assert_eq!(
+            evaluate("1 / (2 - 2)"),
+            Err(CalculatorError::DivisionByZero)
+        );
+        assert_eq!(evaluate("2 +"), Err(CalculatorError::UnexpectedEnd));
+        assert_eq!(
+            evaluate("2 3"),
+            Err(CalculatorError::UnexpectedToken("3".to_string()))
+        );
+        assert_eq!(
+            evaluate("log(2)"),
+            Err(CalculatorError::UnknownName("log".to_string()))
+        );
+        assert!(matches!(
+            evaluate("2^0.5"),
+            Err(CalculatorError::Unsupported(_))
+        ));
+        assert_eq!(calc("(2^10)^3"), "1073741824");
+        assert!(matches!(
+            evaluate("(10^1000)^1000"),
+            Err(CalculatorError::Unsupported(_))
+        ));
+        assert!(matches!(
+            evaluate("(1e5000)^1000"),
+            Err(CalculatorError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(conv("26.2", "mi", "km"), "42.1648128");
+        assert_eq!(conv("212", "F", "C"), "100");
+        assert_eq!(conv("0", "celsius", "K"), "273.15");
+        assert_eq!(conv("1", "GiB", "MB"), "1073.741824");
+        assert_eq!(
+            conv("100", "km/h", "m/s"),
+            "27.777777777777777777777777777778"
+        );
+        assert_eq!(conv("1", "gallon", "liters"), "3.785411784");
+        assert_eq!(conv("92", "EUR", "usd"), "100");
+        assert_eq!(
+            convert(BigDecimal::one(), "kg", "m", &ExchangeRates::fixed()),
+            Err(CalculatorError::IncompatibleUnits(
+                "kg".to_string(),
+                "m".to_string()
+            ))
+        );
+        assert_eq!(
+            convert(BigDecimal::one(), "parsec", "m", &ExchangeRates::fixed()),
+            Err(CalculatorError::UnknownUnit("parsec".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_rates() {
+        let rates =
+            parse_rates(r#"{"base": "USD", "rates": {"eur": 0.91827364519283746, "XXX": 0}}"#)
+                .unwrap();
+        assert_eq!(
+            rates.get("EUR"),
+            Some(&BigDecimal::from_str("0.91827364519283746").unwrap())
+        );
+        assert!(!rates.contains_key("XXX"));
+        assert!(parse_rates(r#"{"base": "USD"}"#).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+//! Cancelling a reply while it runs
+//!
+//! [`Agent::cancel`] stops the replies in progress for a session at the next point where they
+//! wait: the call to the provider, the tool calls of the turn, or the confirmation of a tool call.
+//! Each reply has its own token, so cancelling one session leaves the replies of other sessions
+//! sharing the agent running. The token goes down with every tool call the reply dispatches, and
+//! calls to extensions are cancelled through their MCP client, which tells the server to stop
+//! working on them with `notifications/cancelled`. Tools that cannot be interrupted get [`CANCEL_GRACE`] to
+//! finish before the reply stops waiting for them.
+//!
+//! Every call of the turn that did not finish is answered with an error, so the conversation can
+//! be continued, and the reply ends with `AgentEvent::Cancelled`.
+//!
+//! What the turn got done is kept rather than lost with it. The results of the calls that did
+//! finish are sent as usual, and a response cut off while it streamed keeps its text and the tool
+//! requests it completed, which are answered as cancelled. [`CANCELLED_MARKER`] tells the model
+//! where the reply stopped, so a follow-up such as "continue" picks up from what was already done
+//! instead of starting the turn over. Only messages from the user side carry it: the tool
+//! responses of the cancelled turn, or, when the turn has none, the next prompt of the session,
+//! which is marked in the session's metadata until then.
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use mcp_core::ToolError;
+use tokio_util::sync::CancellationToken;
+
+use crate::message::{Message, MessageContent};
+use crate::session;
+
+use super::types::SessionConfig;
+
+use super::Agent;
+
+/// How long tool calls get to stop once the reply is cancelled
+pub const CANCEL_GRACE: Duration = Duration::from_secs(5);
+
+/// The answer to a tool call that was cancelled before it finished
+pub const CANCELLED_RESPONSE: &str = "The tool call was cancelled before it finished.";
+
+/// Marks where a cancelled reply stopped, for the model to read when the conversation goes on
+pub const CANCELLED_MARKER: &str = "[The reply was cancelled here. The work above was kept, \
+    and tool calls answered as cancelled did not finish. Continue from this point when asked.]";
+
+/// The output of `future`, or None if the reply is cancelled first
+pub async fn until_cancelled<F: Future>(
+    cancel: &CancellationToken,
+    future: F,
+) -> Option<F::Output> {
+    tokio::select! {
+        output = future => Some(output),
+        _ = cancel.cancelled() => None,
+    }
+}
+
+/// The next result of the running tool calls. Once the reply is cancelled each result has to
+/// arrive within [`CANCEL_GRACE`], otherwise the remaining calls are abandoned.
+pub async fn next_until_abandoned<S: Stream + Unpin>(
+    cancel: &CancellationToken,
+    stream: &mut S,
+) -> Option<S::Item> {
+    if !cancel.is_cancelled() {
+        if let Some(item) = until_cancelled(cancel, stream.next()).await {
+            return item;
+        }
+    }
+    tokio::time::timeout(CANCEL_GRACE, stream.next())
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Answer the calls of a cancelled turn that have no response yet
+pub fn answer_cancelled_calls(
+    mut responses: Message,
+    request_ids: impl IntoIterator<Item = String>,
+) -> Message {
+    for id in request_ids {
+        let answered = responses.content.iter().any(|content| {
+            matches!(content, MessageContent::ToolResponse(response) if response.id == id)
+        });
+        if !answered {
+            responses = responses.with_tool_response(
+                id,
+                Err(ToolError::ExecutionError(CANCELLED_RESPONSE.to_string())),
+            );
+        }
+    }
+    responses
+}
+
+/// Close a turn cancelled after its tool calls started, marking their responses
+pub fn mark_cancelled_responses(responses: Message) -> Message {
+    responses.with_text(CANCELLED_MARKER)
+}
+
+/// What is kept of a response that was cut off while it streamed: the response as far as it got,
+/// and, if it completed tool requests, their responses, answered as cancelled since they never
+/// ran and carrying the marker
+pub fn salvage_partial_response(partial: Message) -> Vec<Message> {
+    let request_ids: Vec<String> = partial
+        .content
+        .iter()
+        .filter_map(|content| match content {
+            MessageContent::ToolRequest(request) => Some(request.id.clone()),
+            _ => None,
+        })
+        .collect();
+    if request_ids.is_empty() {
+        if partial.content.is_empty() {
+            return vec![];
+        }
+        return vec![partial];
+    }
+    vec![
+        partial,
+        mark_cancelled_responses(answer_cancelled_calls(Message::user(), request_ids)),
+    ]
+}
+
+/// A reply in progress, with the session it belongs to
+pub(super) struct RunningReply {
+    session_id: Option<String>,
+    cancel: CancellationToken,
+}
+
+/// The replies in progress, keyed by an id of their own
+pub(super) type RunningReplies = Mutex<HashMap<String, RunningReply>>;
+
+/// The token of a reply in progress, which stops being cancellable once this is dropped with
+/// the reply's stream
+pub(super) struct CancellableReply<'a> {
+    replies: &'a RunningReplies,
+    id: String,
+    pub cancel: CancellationToken,
+}
+
+impl Drop for CancellableReply<'_> {
+    fn drop(&mut self) {
+        self.replies.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// The id a session is cancelled by: its name, or the name of its file
+pub fn session_key(id: &session::Identifier) -> Option<String> {
+    match id {
+        session::Identifier::Name(name) => Some(name.clone()),
+        session::Identifier::Path(path) => path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string()),
+    }
+}
+
+/// Whether the messages of a cancelled reply end with the marker, or the session has to carry it
+pub fn carries_marker(messages: &[Message]) -> bool {
+    messages
+        .last()
+        .is_some_and(|message| message.as_concat_text().ends_with(CANCELLED_MARKER))
+}
+
+/// Mark the session of a reply cancelled after its last message from the user side, such as the
+/// prompt or the results of the previous turn, so its next prompt carries the marker
+pub async fn mark_session_cancelled(session: &SessionConfig) {
+    let path = session::storage::get_path(session.id.clone());
+    let marked = match session::storage::read_metadata(&path) {
+        Ok(mut metadata) => {
+            metadata.reply_cancelled = true;
+            session::storage::update_metadata(&path, &metadata).await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = marked {
+        tracing::warn!("Failed to mark the session as cancelled: {}", e);
+    }
+}
+
+/// Put the marker of a reply cancelled earlier in the session in front of the new prompt
+pub async fn carry_session_marker(session: &SessionConfig, messages: &mut [Message]) {
+    let path = session::storage::get_path(session.id.clone());
+    let Ok(mut metadata) = session::storage::read_metadata(&path) else {
+        return;
+    };
+    if !metadata.reply_cancelled {
+        return;
+    }
+    if let Some(prompt) = messages
+        .last_mut()
+        .filter(|message| message.role == mcp_core::Role::User)
+    {
+        prompt
+            .content
+            .insert(0, MessageContent::text(CANCELLED_MARKER));
+    }
+    metadata.reply_cancelled = false;
+    if let Err(e) = session::storage::update_metadata(&path, &metadata).await {
+        tracing::warn!("Failed to clear the cancelled mark of the session: {}", e);
+    }
+}
+
+impl Agent {
+    /// Cancel the replies in progress for a session, if there are any
+    pub fn cancel(&self, session_id: &str) {
+        for reply in self.running_replies.lock().unwrap().values() {
+            if reply.session_id.as_deref() == Some(session_id) {
+                reply.cancel.cancel();
+            }
+        }
+    }
+
+    /// A new token for a reply that is starting, passed to the tool calls it makes
+    pub(super) fn start_cancellable(&self, session_id: Option<String>) -> CancellableReply<'_> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let cancel = CancellationToken::new();
+        self.running_replies.lock().unwrap().insert(
+            id.clone(),
+            RunningReply {
+                session_id,
+                cancel: cancel.clone(),
+            },
+        );
+        CancellableReply {
+            replies: &self.running_replies,
+            id,
+            cancel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::Content;
+
+    #[tokio::test]
+    async fn test_cancelled_reply_stops_waiting() {
+        let agent = Agent::new();
+        let reply = agent.start_cancellable(Some("first".to_string()));
+        assert_eq!(until_cancelled(&reply.cancel, async { 42 }).await, Some(42));
+
+        // A reply of another session is left running
+        let other = agent.start_cancellable(Some("second".to_string()));
+        agent.cancel("first");
+        let pending = futures::future::pending::<()>();
+        assert_eq!(until_cancelled(&reply.cancel, pending).await, None);
+        assert!(!other.cancel.is_cancelled());
+
+        // A new reply is not affected by the earlier cancellation
+        assert!(!agent
+            .start_cancellable(Some("first".to_string()))
+            .cancel
+            .is_cancelled());
+
+        // A finished reply can no longer be cancelled
+        drop(reply);
+        drop(other);
+        assert!(agent.running_replies.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unanswered_calls_are_cancelled() {
+        let responses = Message::user().with_tool_response("call_1", Ok(vec![Content::text("ok")]));
+        let responses =
+            answer_cancelled_calls(responses, vec!["call_1".to_string(), "call_2".to_string()]);
+        assert_eq!(responses.content.len(), 2);
+        let MessageContent::ToolResponse(response) = &responses.content[1] else {
+            panic!("expected a tool response");
+        };
+        assert_eq!(response.id, "call_2");
+        assert!(response.tool_result.is_err());
+    }
+
+    #[test]
+    fn test_partial_responses_are_salvaged() {
+        // The response is kept as the model wrote it, and the marker is left to the session
+        let text_only = salvage_partial_response(Message::assistant().with_text("Looking at"));
+        assert_eq!(text_only.len(), 1);
+        assert_eq!(text_only[0].as_concat_text(), "Looking at");
+        assert!(!carries_marker(&text_only));
+        assert!(salvage_partial_response(Message::assistant()).is_empty());
+
+        let partial = Message::assistant()
+            .with_text("Let me read both files.")
+            .with_tool_request(
+                "call_1",
+                Ok(mcp_core::ToolCall::new("read", serde_json::json!({}))),
+            );
+        let salvaged = salvage_partial_response(partial);
+        assert_eq!(salvaged.len(), 2);
+        // The streamed text is kept with the tool request it led to
+        assert_eq!(salvaged[0].as_concat_text(), "Let me read both files.");
+        assert!(salvaged[0].is_tool_call());
+
+        let responses = &salvaged[1];
+        let MessageContent::ToolResponse(response) = &responses.content[0] else {
+            panic!("expected a tool response");
+        };
+        assert_eq!(response.id, "call_1");
+        assert!(response.tool_result.is_err());
+        assert_eq!(responses.as_concat_text(), CANCELLED_MARKER);
+        assert!(carries_marker(&salvaged));
+    }
+}
@@ -0,0 +1,88 @@
+//! Summaries of the working tree changes made in a session
+//!
+//! With `GOOSE_GIT_CHANGES: true`, the model gets a review changes tool to look over what it
+//! has changed so far, and frontends can ask for a summary of the uncommitted changes, e.g.
+//! when a session ends. Summaries are attached to the session next to its file.
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use mcp_core::{Content, ToolError, ToolResult};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::session::changes::{
+    collect_changes, describe_changes, save_change_summary, working_tree_diff, ChangeSummary,
+};
+
+use super::Agent;
+
+/// Longest diff returned by the review changes tool, in characters
+const MAX_TOOL_DIFF_CHARS: usize = 50_000;
+
+/// Whether git change summaries and the review changes tool are enabled
+pub fn git_changes_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_GIT_CHANGES")
+        .unwrap_or(false)
+}
+
+impl Agent {
+    async fn changes_dir(&self) -> PathBuf {
+        match self.working_dir.lock().await.clone() {
+            Some(dir) => dir,
+            None => std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    /// Summarize the uncommitted changes in the working directory with the agent's provider,
+    /// and attach the summary to the session, if there is one. Returns None outside a git
+    /// repository.
+    pub async fn summarize_changes(
+        &self,
+        session_file: Option<&Path>,
+    ) -> Result<Option<ChangeSummary>> {
+        let dir = self.changes_dir().await;
+        let Some(mut summary) = collect_changes(&dir)? else {
+            return Ok(None);
+        };
+
+        if !summary.is_empty() {
+            let diff = working_tree_diff(&dir)?;
+            summary.summary =
+                Some(describe_changes(self.provider().await?, &summary, &diff).await?);
+        }
+
+        if let Some(session_file) = session_file {
+            save_change_summary(session_file, &summary)?;
+        }
+        Ok(Some(summary))
+    }
+
+    /// Handle review changes tool calls against the working directory
+    pub(super) async fn handle_review_changes(&self, arguments: Value) -> ToolResult<Vec<Content>> {
+        let include_diff = arguments
+            .get("include_diff")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let dir = self.changes_dir().await;
+        let summary = collect_changes(&dir)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?
+            .ok_or_else(|| {
+                ToolError::ExecutionError(format!("{} is not in a git repository", dir.display()))
+            })?;
+
+        let mut text = summary.to_text();
+        if include_diff && !summary.files.is_empty() {
+            let diff =
+                working_tree_diff(&dir).map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            let truncated: String = diff.chars().take(MAX_TOOL_DIFF_CHARS).collect();
+            text.push_str("\n\n");
+            text.push_str(&truncated);
+            if truncated.len() < diff.len() {
+                text.push_str("\n[diff truncated]");
+            }
+        }
+        Ok(vec![Content::text(text)])
+    }
+}
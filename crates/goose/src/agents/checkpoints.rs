@@ -0,0 +1,109 @@
+//! Named checkpoints in the history of a session, and checkpoints of the agent's own state
+//! that let another process resume the session where it left off
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::message::Message;
+use crate::session::checkpoints::{self, Checkpoint};
+use crate::session::state::{self, SessionState};
+use crate::session::{self, storage};
+use crate::tool_monitor::ToolMonitor;
+
+use super::Agent;
+
+impl Agent {
+    /// Tag a session as it is now, e.g. "before refactor"
+    pub async fn tag_checkpoint(&self, session_file: &Path, name: &str) -> Result<Checkpoint> {
+        let message_count = storage::read_messages(session_file)?.len();
+        checkpoints::tag_checkpoint(session_file, name, message_count)
+    }
+
+    /// The checkpoints of a session, in the order they were tagged
+    pub async fn list_checkpoints(&self, session_file: &Path) -> Result<Vec<Checkpoint>> {
+        checkpoints::list_checkpoints(session_file)
+    }
+
+    /// Start a new session from another as it was at a checkpoint, returning the new session's
+    /// file
+    pub async fn branch_from_checkpoint(&self, session_file: &Path, name: &str) -> Result<PathBuf> {
+        let new_session_file =
+            session::get_path(session::Identifier::Name(session::generate_session_id()));
+        checkpoints::branch_from_checkpoint(session_file, name, &new_session_file)?;
+        Ok(new_session_file)
+    }
+
+    /// Save the messages of a session along with the enabled extensions, the system prompt
+    /// extras and the tool monitor's state, so the session can be resumed with
+    /// [`Agent::resume_from_checkpoint`] after a crash or restart
+    pub async fn save_checkpoint(
+        &self,
+        session_file: &Path,
+        messages: &[Message],
+    ) -> Result<SessionState> {
+        storage::persist_messages(session_file, messages, None).await?;
+
+        let mut state = SessionState::new(messages.len());
+        state.extensions = self.extension_manager.lock().await.extension_configs();
+        state.system_prompt_extras = self
+            .prompt_manager
+            .lock()
+            .await
+            .system_prompt_extras()
+            .to_vec();
+        state.tool_monitor = self
+            .tool_monitor
+            .lock()
+            .await
+            .as_ref()
+            .map(|monitor| monitor.state());
+        state::save_state(session_file, &state)?;
+        Ok(state)
+    }
+
+    /// Continue a session from its last saved checkpoint, returning its messages. Extensions
+    /// that are not enabled yet are added, and the saved system prompt extras and tool monitor
+    /// state replace the agent's own. A session without a saved checkpoint resumes with its
+    /// messages only.
+    pub async fn resume_from_checkpoint(&self, session_file: &Path) -> Result<Vec<Message>> {
+        let messages = storage::read_messages(session_file)?;
+        let Some(state) = state::read_state(session_file)? else {
+            return Ok(messages);
+        };
+        if state.message_count != messages.len() {
+            tracing::warn!(
+                "Session has {} messages but its checkpoint was saved with {}",
+                messages.len(),
+                state.message_count
+            );
+        }
+
+        let enabled: Vec<String> = self
+            .extension_manager
+            .lock()
+            .await
+            .extension_configs()
+            .iter()
+            .map(|config| config.key())
+            .collect();
+        for config in state.extensions {
+            if enabled.contains(&config.key()) {
+                continue;
+            }
+            let name = config.name();
+            if let Err(e) = self.add_extension(config).await {
+                tracing::warn!("Failed to resume extension {}: {}", name, e);
+            }
+        }
+
+        self.prompt_manager
+            .lock()
+            .await
+            .set_system_prompt_extras(state.system_prompt_extras);
+        if let Some(monitor) = state.tool_monitor {
+            *self.tool_monitor.lock().await = Some(ToolMonitor::from_state(monitor));
+        }
+        Ok(messages)
+    }
+}
@@ -0,0 +1,450 @@
+//! Tool use via code
+//!
+//! Instead of emitting one tool call per turn, the model can write a short program that calls
+//! tools as functions, e.g. to loop over many files in a single round-trip:
+//!
+//! ```text
+//! let files = developer__shell(#{ command: "ls src/*.rs" }).split("\n");
+//! for file in files {
+//!     if file != "" {
+//!         print(developer__text_editor(#{ command: "view", path: file }).len());
+//!     }
+//! }
+//! ```
+//!
+//! Programs are written in Rhai, a small scripting language embedded in-process. Scripts have
+//! no access to the filesystem, network or environment other than through the tools they are
+//! given, and run with limits on operations, tool calls and wall clock time. Programs can call
+//! extension tools, and every call goes through the same dispatch as a call made by the model:
+//! repetition limits, tool budgets, snapshots and the audit log all apply. A program cannot stop
+//! to ask the user, so a call that would need approval is refused instead.
+//!
+//! Enable the mode with `GOOSE_CODE_ACTIONS: true`.
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use mcp_core::{Content, Tool, ToolCall, ToolError, ToolResult};
+use rhai::{Dynamic, Engine, EvalAltResult, Map};
+use serde_json::Value;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::permission::PermissionLevel;
+use crate::config::{Config, PermissionManager};
+use crate::permission::ApprovalContext;
+
+use super::audit::AuditApproval;
+use super::workspace_routers::RouterSelector;
+use super::{Agent, SessionConfig};
+
+const DEFAULT_MAX_OPERATIONS: u64 = 1_000_000;
+const DEFAULT_MAX_TOOL_CALLS: usize = 50;
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramLimits {
+    /// Maximum number of interpreter operations, bounding runaway loops
+    pub max_operations: u64,
+    pub max_tool_calls: usize,
+    /// Wall clock limit, including time spent in tool calls
+    pub timeout: Duration,
+}
+
+impl Default for ProgramLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: DEFAULT_MAX_OPERATIONS,
+            max_tool_calls: DEFAULT_MAX_TOOL_CALLS,
+            timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+        }
+    }
+}
+
+impl ProgramLimits {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            max_operations: config
+                .get_param("GOOSE_CODE_ACTIONS_MAX_OPERATIONS")
+                .unwrap_or(defaults.max_operations),
+            max_tool_calls: config
+                .get_param("GOOSE_CODE_ACTIONS_MAX_TOOL_CALLS")
+                .unwrap_or(defaults.max_tool_calls),
+            timeout: config
+                .get_param("GOOSE_CODE_ACTIONS_TIMEOUT")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.timeout),
+        }
+    }
+}
+
+/// Whether the model is offered the run program tool
+pub fn code_actions_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_CODE_ACTIONS")
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramOutput {
+    /// Lines printed by the program
+    pub printed: Vec<String>,
+    /// The value of the program's final expression, if any
+    pub result: Option<String>,
+    pub tool_calls: usize,
+}
+
+impl ProgramOutput {
+    pub fn to_text(&self) -> String {
+        let mut lines = self.printed.clone();
+        if let Some(result) = &self.result {
+            lines.push(format!("=> {}", result));
+        }
+        if lines.is_empty() {
+            lines.push("(no output)".to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+struct ToolInvocation {
+    name: String,
+    arguments: Value,
+    reply: oneshot::Sender<Result<String, String>>,
+}
+
+fn call_tool(
+    tx: &mpsc::Sender<ToolInvocation>,
+    name: &str,
+    args: Map,
+) -> Result<Dynamic, Box<EvalAltResult>> {
+    let arguments: Value = rhai::serde::from_dynamic(&Dynamic::from_map(args))?;
+    let (reply_tx, reply_rx) = oneshot::channel();
+    tx.blocking_send(ToolInvocation {
+        name: name.to_string(),
+        arguments,
+        reply: reply_tx,
+    })
+    .map_err(|_| "the program was cancelled")?;
+
+    match reply_rx.blocking_recv() {
+        Ok(Ok(text)) => Ok(text.into()),
+        Ok(Err(e)) => Err(format!("{} failed: {}", name, e).into()),
+        Err(_) => Err("the program was cancelled".into()),
+    }
+}
+
+fn build_engine(
+    tool_names: &[String],
+    tx: mpsc::Sender<ToolInvocation>,
+    printed: Arc<std::sync::Mutex<Vec<String>>>,
+    limits: ProgramLimits,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(limits.max_operations);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(1024 * 1024);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+
+    let started = Instant::now();
+    engine.on_progress(move |_| {
+        (started.elapsed() > limits.timeout).then(|| Dynamic::from("timeout"))
+    });
+    engine.on_print(move |text| printed.lock().unwrap().push(text.to_string()));
+
+    engine.register_fn(
+        "parse_json",
+        |text: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+            rhai::serde::to_dynamic(value)
+        },
+    );
+
+    for name in tool_names {
+        let (tool, sender) = (name.clone(), tx.clone());
+        engine.register_fn(name.as_str(), move |args: Map| {
+            call_tool(&sender, &tool, args)
+        });
+        let (tool, sender) = (name.clone(), tx.clone());
+        engine.register_fn(name.as_str(), move || call_tool(&sender, &tool, Map::new()));
+    }
+    engine
+}
+
+/// Run a program that can call the named tools, dispatching each call with `dispatch`
+pub async fn run_program<F, Fut>(
+    script: String,
+    tool_names: Vec<String>,
+    limits: ProgramLimits,
+    mut dispatch: F,
+) -> Result<ProgramOutput>
+where
+    F: FnMut(String, Value) -> Fut,
+    Fut: Future<Output = Result<String, String>>,
+{
+    let (tx, mut rx) = mpsc::channel(1);
+    let printed = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    let engine_printed = printed.clone();
+    let handle = tokio::task::spawn_blocking(move || {
+        let engine = build_engine(&tool_names, tx, engine_printed, limits);
+        engine
+            .eval::<Dynamic>(&script)
+            .map(|value| (!value.is_unit()).then(|| value.to_string()))
+            .map_err(|e| match *e {
+                EvalAltResult::ErrorTerminated(..) => anyhow!(
+                    "Program exceeded the time limit of {}s",
+                    limits.timeout.as_secs()
+                ),
+                EvalAltResult::ErrorTooManyOperations(..) => {
+                    anyhow!(
+                        "Program exceeded the limit of {} operations",
+                        limits.max_operations
+                    )
+                }
+                other => anyhow!("Program failed: {}", other),
+            })
+    });
+
+    // The engine holds the only senders, so this ends once the program finishes
+    let mut tool_calls = 0;
+    while let Some(invocation) = rx.recv().await {
+        tool_calls += 1;
+        let reply = if tool_calls > limits.max_tool_calls {
+            Err(format!(
+                "the program exceeded the limit of {} tool calls",
+                limits.max_tool_calls
+            ))
+        } else {
+            dispatch(invocation.name, invocation.arguments).await
+        };
+        let _ = invocation.reply.send(reply);
+    }
+
+    let result = handle.await??;
+    let printed = std::mem::take(&mut *printed.lock().unwrap());
+    Ok(ProgramOutput {
+        printed,
+        result,
+        tool_calls,
+    })
+}
+
+/// The extension tools a program may call. Outside of auto mode calls cannot be confirmed
+/// by the user, so only read-only tools are available.
+pub fn program_tools(extension_tools: Vec<Tool>) -> Vec<Tool> {
+    let goose_mode = Config::global()
+        .get_param("GOOSE_MODE")
+        .unwrap_or("auto".to_string());
+    extension_tools
+        .into_iter()
+        .filter(|tool| {
+            goose_mode == "auto"
+                || tool
+                    .annotations
+                    .as_ref()
+                    .is_some_and(|annotations| annotations.read_only_hint)
+        })
+        .collect()
+}
+
+impl Agent {
+    /// Whether a program may make the call without asking, following the permission checks of
+    /// calls made by the model. Calls that would need approval are refused.
+    async fn check_program_permission(
+        &self,
+        request_id: &str,
+        name: &str,
+        arguments: &Value,
+    ) -> Result<(), String> {
+        let goose_mode = Config::global()
+            .get_param("GOOSE_MODE")
+            .unwrap_or("auto".to_string());
+        let refused = |reason: &str| Err(format!("Tool call rejected: {} {}", name, reason));
+
+        let external_approval = self.external_approval.lock().await.clone();
+        if let Some(approval) = &external_approval {
+            let context = ApprovalContext {
+                request_id: request_id.to_string(),
+                tool_name: name.to_string(),
+                arguments: arguments.clone(),
+                mode: goose_mode.clone(),
+            };
+            match approval.consult(&context).await {
+                Some(crate::permission::ApprovalDecision::Allow) => return Ok(()),
+                Some(crate::permission::ApprovalDecision::Deny) => {
+                    return refused("is not allowed");
+                }
+                Some(crate::permission::ApprovalDecision::AskUser) => {
+                    return refused("needs approval, which a program cannot ask for");
+                }
+                None => {}
+            }
+        }
+
+        if goose_mode == "auto" {
+            return Ok(());
+        }
+        match PermissionManager::default().get_user_permission(name) {
+            Some(PermissionLevel::AlwaysAllow) => Ok(()),
+            Some(PermissionLevel::NeverAllow) => refused("is not allowed"),
+            // Only read-only tools are offered outside of auto mode, which smart approve allows
+            None if goose_mode == "smart_approve" => Ok(()),
+            _ => refused("needs approval, which a program cannot ask for"),
+        }
+    }
+
+    async fn dispatch_program_tool_call(
+        &self,
+        request_id: String,
+        name: String,
+        arguments: Value,
+        router: Option<&RouterSelector>,
+        cancel: &CancellationToken,
+        session: Option<&SessionConfig>,
+    ) -> Result<String, String> {
+        self.check_program_permission(&request_id, &name, &arguments)
+            .await?;
+
+        // Boxed, since the call can reach the run program tool again
+        let (_, result) = Box::pin(self.dispatch_audited_tool_call(
+            ToolCall::new(name, arguments),
+            request_id,
+            AuditApproval::Automatic,
+            router,
+            cancel,
+            session,
+        ))
+        .await;
+        let contents = result
+            .map_err(|e| e.to_string())?
+            .result
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(contents
+            .iter()
+            .filter_map(|c| c.as_text())
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Handle the run program platform tool
+    pub(super) async fn handle_run_program(
+        &self,
+        arguments: Value,
+        request_id: &str,
+        router: Option<&RouterSelector>,
+        cancel: &CancellationToken,
+        session: Option<&SessionConfig>,
+    ) -> ToolResult<Vec<Content>> {
+        let script = arguments
+            .get("program")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'program' parameter".into()))?
+            .to_string();
+        let extension_tools = self
+            .extension_manager
+            .lock()
+            .await
+            .get_prefixed_tools(None)
+            .await
+            .unwrap_or_default();
+        let tool_names = program_tools(extension_tools)
+            .into_iter()
+            .map(|tool| tool.name)
+            .collect();
+
+        // The calls of a program are told apart in the audit log by their position in it
+        let mut calls = 0;
+        let output = run_program(
+            script,
+            tool_names,
+            ProgramLimits::from_config(),
+            |name, arguments| {
+                calls += 1;
+                let call_id = format!("{}_{}", request_id, calls);
+                self.dispatch_program_tool_call(call_id, name, arguments, router, cancel, session)
+            },
+        )
+        .await
+        .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        Ok(vec![Content::text(output.to_text())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(script: &str, limits: ProgramLimits) -> Result<ProgramOutput> {
+        run_program(
+            script.to_string(),
+            vec!["files__list".to_string(), "files__size".to_string()],
+            limits,
+            |name, arguments| async move {
+                match name.as_str() {
+                    "files__list" => Ok("a.rs\nb.rs\nc.rs".to_string()),
+                    "files__size" => match arguments.get("path").and_then(|p| p.as_str()) {
+                        Some(path) => Ok(path.len().to_string()),
+                        None => Err("missing path".to_string()),
+                    },
+                    _ => Err("unknown tool".to_string()),
+                }
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_program_calls_tools_in_a_loop() {
+        let output = run(
+            r#"
+            let total = 0;
+            for file in files__list().split("\n") {
+                print(file);
+                total += parse_int(files__size(#{ path: file }));
+            }
+            total
+            "#,
+            ProgramLimits::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output.printed, vec!["a.rs", "b.rs", "c.rs"]);
+        assert_eq!(output.result.as_deref(), Some("12"));
+        assert_eq!(output.tool_calls, 4);
+    }
+
+    #[tokio::test]
+    async fn test_tool_errors_fail_the_program() {
+        let err = run("files__size(#{})", ProgramLimits::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("missing path"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_are_enforced() {
+        let limits = ProgramLimits {
+            max_operations: 1_000,
+            ..Default::default()
+        };
+        let err = run("loop { }", limits).await.unwrap_err();
+        assert!(err.to_string().contains("operations"));
+
+        let limits = ProgramLimits {
+            max_tool_calls: 2,
+            ..Default::default()
+        };
+        let err = run("for i in 0..5 { files__list(); }", limits)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("tool calls"));
+    }
+}
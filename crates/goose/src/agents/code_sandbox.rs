@@ -0,0 +1,378 @@
+//! Inline code execution in a WASM sandbox
+//!
+//! Short snippets are run by an interpreter compiled to WASI (e.g. QuickJS for JavaScript or
+//! CPython for Python). The guest has no filesystem, network or environment access; it only
+//! sees its arguments and can write to stdout and stderr. Runs are bounded by a wall clock
+//! timeout and a memory limit. The WASM engine is only built with the `code-sandbox` feature,
+//! which is on by default; without it no language is available.
+//!
+//! ```yaml
+//! GOOSE_SANDBOX_JAVASCRIPT_RUNTIME: ~/.config/goose/sandbox/qjs.wasm
+//! GOOSE_SANDBOX_PYTHON_RUNTIME: ~/.config/goose/sandbox/python.wasm
+//! GOOSE_SANDBOX_TIMEOUT: 10
+//! GOOSE_SANDBOX_MEMORY_MB: 128
+//! ```
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(feature = "code-sandbox")]
+use anyhow::Context;
+use anyhow::{anyhow, Result};
+use mcp_core::{Content, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "code-sandbox")]
+use wasmtime::{Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+#[cfg(feature = "code-sandbox")]
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+#[cfg(feature = "code-sandbox")]
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+#[cfg(feature = "code-sandbox")]
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+use crate::config::Config;
+
+use super::Agent;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_MEMORY_MB: usize = 128;
+// Output beyond this is discarded so a runaway loop cannot flood the context
+#[cfg(feature = "code-sandbox")]
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SandboxLanguage {
+    Javascript,
+    Python,
+}
+
+impl SandboxLanguage {
+    pub fn all() -> [Self; 2] {
+        [Self::Javascript, Self::Python]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Javascript => "javascript",
+            Self::Python => "python",
+        }
+    }
+
+    fn runtime_config_key(&self) -> &'static str {
+        match self {
+            Self::Javascript => "GOOSE_SANDBOX_JAVASCRIPT_RUNTIME",
+            Self::Python => "GOOSE_SANDBOX_PYTHON_RUNTIME",
+        }
+    }
+
+    /// The guest's argv for evaluating `code`
+    fn args(&self, code: &str) -> Vec<String> {
+        let (program, flag) = match self {
+            Self::Javascript => ("qjs", "-e"),
+            Self::Python => ("python", "-c"),
+        };
+        vec![program.to_string(), flag.to_string(), code.to_string()]
+    }
+}
+
+impl std::str::FromStr for SandboxLanguage {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "javascript" | "js" => Ok(Self::Javascript),
+            "python" | "py" => Ok(Self::Python),
+            other => Err(anyhow!("Unsupported language '{}'", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+}
+
+impl SandboxOutput {
+    /// Render the output as tool result text
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        if !self.stdout.is_empty() {
+            text.push_str(&self.stdout);
+        }
+        if !self.stderr.is_empty() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str("stderr:\n");
+            text.push_str(&self.stderr);
+        }
+        if self.exit_code != 0 {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&format!("exit code: {}", self.exit_code));
+        }
+        if text.is_empty() {
+            text.push_str("(no output)");
+        }
+        text
+    }
+}
+
+#[cfg(feature = "code-sandbox")]
+struct SandboxState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSandbox {
+    pub javascript_runtime: Option<PathBuf>,
+    pub python_runtime: Option<PathBuf>,
+    pub timeout: Duration,
+    pub memory_limit_bytes: usize,
+}
+
+impl CodeSandbox {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let runtime = |language: SandboxLanguage| {
+            config
+                .get_param::<String>(language.runtime_config_key())
+                .ok()
+                .map(|path| PathBuf::from(shellexpand::tilde(&path).into_owned()))
+        };
+        Self {
+            javascript_runtime: runtime(SandboxLanguage::Javascript),
+            python_runtime: runtime(SandboxLanguage::Python),
+            timeout: Duration::from_secs(
+                config
+                    .get_param("GOOSE_SANDBOX_TIMEOUT")
+                    .unwrap_or(DEFAULT_TIMEOUT_SECS),
+            ),
+            memory_limit_bytes: config
+                .get_param::<usize>("GOOSE_SANDBOX_MEMORY_MB")
+                .unwrap_or(DEFAULT_MEMORY_MB)
+                * 1024
+                * 1024,
+        }
+    }
+
+    fn runtime(&self, language: SandboxLanguage) -> Option<&PathBuf> {
+        match language {
+            SandboxLanguage::Javascript => self.javascript_runtime.as_ref(),
+            SandboxLanguage::Python => self.python_runtime.as_ref(),
+        }
+    }
+
+    /// Languages with a configured runtime
+    pub fn available_languages(&self) -> Vec<SandboxLanguage> {
+        if !cfg!(feature = "code-sandbox") {
+            return Vec::new();
+        }
+        SandboxLanguage::all()
+            .into_iter()
+            .filter(|language| self.runtime(*language).is_some())
+            .collect()
+    }
+
+    pub fn is_available(&self) -> bool {
+        !self.available_languages().is_empty()
+    }
+
+    /// Run a snippet to completion, or until it exceeds the time or memory limit
+    pub async fn execute(&self, language: SandboxLanguage, code: &str) -> Result<SandboxOutput> {
+        let runtime = self.runtime(language).ok_or_else(|| {
+            anyhow!(
+                "No {} runtime configured, set {}",
+                language.as_str(),
+                language.runtime_config_key()
+            )
+        })?;
+        self.run(runtime, language, code).await
+    }
+
+    #[cfg(not(feature = "code-sandbox"))]
+    async fn run(
+        &self,
+        _runtime: &Path,
+        _language: SandboxLanguage,
+        _code: &str,
+    ) -> Result<SandboxOutput> {
+        Err(anyhow!("goose was built without the code-sandbox feature"))
+    }
+
+    #[cfg(feature = "code-sandbox")]
+    async fn run(
+        &self,
+        runtime: &Path,
+        language: SandboxLanguage,
+        code: &str,
+    ) -> Result<SandboxOutput> {
+        let mut wasm_config = wasmtime::Config::new();
+        wasm_config.epoch_interruption(true);
+        let engine = Engine::new(&wasm_config)?;
+        let module = Module::from_file(&engine, runtime)
+            .with_context(|| format!("Failed to load WASM runtime {}", runtime.display()))?;
+
+        // Interrupt the guest once the timeout elapses
+        let timer_engine = engine.clone();
+        let timeout = self.timeout;
+        let timer = tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            timer_engine.increment_epoch();
+        });
+
+        let args = language.args(code);
+        let memory_limit = self.memory_limit_bytes;
+        let result =
+            tokio::task::spawn_blocking(move || run_module(&engine, &module, &args, memory_limit))
+                .await;
+        timer.abort();
+
+        match result? {
+            Err(e) if matches!(e.downcast_ref::<Trap>(), Some(Trap::Interrupt)) => {
+                Err(anyhow!("Execution timed out after {}s", timeout.as_secs()))
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(feature = "code-sandbox")]
+fn run_module(
+    engine: &Engine,
+    module: &Module,
+    args: &[String],
+    memory_limit: usize,
+) -> Result<SandboxOutput> {
+    let stdout = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+    let stderr = MemoryOutputPipe::new(MAX_OUTPUT_BYTES);
+    let wasi = WasiCtxBuilder::new()
+        .stdin(MemoryInputPipe::new(Vec::new()))
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .args(args)
+        .build_p1();
+
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(memory_limit)
+        .instances(1)
+        .build();
+    let mut store = Store::new(engine, SandboxState { wasi, limits });
+    store.limiter(|state| &mut state.limits);
+    store.set_epoch_deadline(1);
+
+    let mut linker: Linker<SandboxState> = Linker::new(engine);
+    preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)?;
+    linker.module(&mut store, "", module)?;
+
+    let exit_code = match linker
+        .get_default(&mut store, "")?
+        .typed::<(), ()>(&store)?
+        .call(&mut store, ())
+    {
+        Ok(()) => 0,
+        Err(e) => match e.downcast_ref::<I32Exit>() {
+            Some(exit) => exit.0,
+            None => return Err(e),
+        },
+    };
+    drop(store);
+
+    Ok(SandboxOutput {
+        stdout: String::from_utf8_lossy(&stdout.contents()).into_owned(),
+        stderr: String::from_utf8_lossy(&stderr.contents()).into_owned(),
+        exit_code,
+    })
+}
+
+impl Agent {
+    /// Handle the execute code platform tool
+    pub async fn handle_execute_code(
+        &self,
+        arguments: serde_json::Value,
+    ) -> ToolResult<Vec<Content>> {
+        let language = arguments
+            .get("language")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'language' parameter".into()))?
+            .parse::<SandboxLanguage>()
+            .map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+        let code = arguments
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'code' parameter".into()))?;
+
+        let output = CodeSandbox::from_config()
+            .execute(language, code)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        Ok(vec![Content::text(output.to_text())])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sandbox() -> CodeSandbox {
+        CodeSandbox {
+            javascript_runtime: Some(PathBuf::from("/opt/sandbox/qjs.wasm")),
+            python_runtime: None,
+            timeout: Duration::from_secs(1),
+            memory_limit_bytes: 1024 * 1024,
+        }
+    }
+
+    #[test]
+    fn test_parse_language() {
+        assert_eq!(
+            "JS".parse::<SandboxLanguage>().unwrap(),
+            SandboxLanguage::Javascript
+        );
+        assert_eq!(
+            "python".parse::<SandboxLanguage>().unwrap(),
+            SandboxLanguage::Python
+        );
+        assert!("ruby".parse::<SandboxLanguage>().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "code-sandbox")]
+    fn test_available_languages() {
+        let sandbox = sandbox();
+        assert!(sandbox.is_available());
+        assert_eq!(
+            sandbox.available_languages(),
+            vec![SandboxLanguage::Javascript]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_runtime_is_an_error() {
+        let err = sandbox()
+            .execute(SandboxLanguage::Python, "print(1)")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("GOOSE_SANDBOX_PYTHON_RUNTIME"));
+    }
+
+    #[test]
+    fn test_output_text() {
+        let output = SandboxOutput {
+            stdout: "42".to_string(),
+            stderr: "warning".to_string(),
+            exit_code: 1,
+        };
+        assert_eq!(output.to_text(), "42\nstderr:\nwarning\nexit code: 1");
+        let empty = SandboxOutput {
+            stdout: String::new(),
+            stderr: String::new(),
+            exit_code: 0,
+        };
+        assert_eq!(empty.to_text(), "(no output)");
+    }
+}
@@ -16,7 +16,7 @@ impl Agent {
         messages: &[Message], // last message is a user msg that led to assistant message with_context_length_exceeded
     ) -> Result<(Vec<Message>, Vec<usize>), anyhow::Error> {
         let provider = self.provider().await?;
-        let token_counter = TokenCounter::new(provider.get_model_config().tokenizer_name());
+        let token_counter = TokenCounter::for_model(&provider.get_model_config());
         let target_context_limit = estimate_target_context_limit(provider);
         let token_counts = get_messages_token_counts(&token_counter, messages);
 
@@ -51,7 +51,7 @@ impl Agent {
         messages: &[Message], // last message is a user msg that led to assistant message with_context_length_exceeded
     ) -> Result<(Vec<Message>, Vec<usize>), anyhow::Error> {
         let provider = self.provider().await?;
-        let token_counter = TokenCounter::new(provider.get_model_config().tokenizer_name());
+        let token_counter = TokenCounter::for_model(&provider.get_model_config());
         let target_context_limit = estimate_target_context_limit(provider.clone());
 
         let (mut new_messages, mut new_token_counts) =
@@ -0,0 +1,243 @@
+//! Where the context window goes on each turn
+//!
+//! After the prompt is assembled the agent emits a breakdown of its tokens: the sections of
+//! the system prompt, the tool definitions of each extension, the conversation and the
+//! resources embedded in it. Users can see which extensions are costly to keep enabled and
+//! when the history is what fills the context.
+use std::collections::BTreeMap;
+
+use mcp_core::{Content, Tool};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::context_mgmt::compress::CompressionStats;
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::token_counter::TokenCounter;
+
+use super::prompt_history::prompt_sections;
+
+/// Tokens per message for the role and separators, as counted for the chat
+const TOKENS_PER_MESSAGE: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct UsageItem {
+    pub name: String,
+    pub tokens: usize,
+}
+
+/// Estimated tokens of one turn's prompt by where they come from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ContextUsage {
+    pub model: String,
+    pub context_limit: usize,
+    pub total_tokens: usize,
+    pub system_prompt_tokens: usize,
+    /// The system prompt by markdown section, in prompt order
+    pub system_prompt_sections: Vec<UsageItem>,
+    pub tool_tokens: usize,
+    /// Tool definitions by extension, largest first
+    pub tools_by_extension: Vec<UsageItem>,
+    /// Messages of the conversation, not counting embedded resources
+    pub history_tokens: usize,
+    /// Resources embedded in tool results
+    pub resource_tokens: usize,
+    /// Tokens saved by pruning old context, when compression is on and pruned anything
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<CompressionStats>,
+}
+
+impl ContextUsage {
+    /// Fraction of the context window the prompt takes
+    pub fn fraction_used(&self) -> f64 {
+        if self.context_limit == 0 {
+            return 0.0;
+        }
+        self.total_tokens as f64 / self.context_limit as f64
+    }
+}
+
+/// Load a tokenizer off the async runtime, since one that is not embedded is downloaded
+pub(super) async fn load_counter(model_config: &ModelConfig) -> Option<TokenCounter> {
+    let model_config = model_config.clone();
+    match tokio::task::spawn_blocking(move || TokenCounter::for_model(&model_config)).await {
+        Ok(counter) => Some(counter),
+        Err(e) => {
+            tracing::warn!("Failed to load tokenizer: {}", e);
+            None
+        }
+    }
+}
+
+/// Tokens of the conversation and of the resources embedded in it
+fn history_tokens(counter: &TokenCounter, messages: &[Message]) -> (usize, usize) {
+    let mut history = 0;
+    let mut resources = 0;
+    for message in messages {
+        history += TOKENS_PER_MESSAGE;
+        for content in &message.content {
+            match content {
+                MessageContent::ToolResponse(response) => {
+                    for content in response.tool_result.iter().flatten() {
+                        match content {
+                            Content::Text(text) => history += counter.count_tokens(&text.text),
+                            Content::Resource(resource) => {
+                                resources += counter.count_tokens(&resource.get_text())
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(call) = &request.tool_call {
+                        history += counter.count_tokens(&format!(
+                            "{}:{}:{}",
+                            request.id, call.name, call.arguments
+                        ));
+                    }
+                }
+                other => {
+                    if let Some(text) = other.as_text() {
+                        history += counter.count_tokens(text);
+                    }
+                }
+            }
+        }
+    }
+    (history, resources)
+}
+
+/// Break down the tokens of a prompt
+pub fn context_usage(
+    counter: &TokenCounter,
+    model_config: &ModelConfig,
+    system_prompt: &str,
+    messages: &[Message],
+    tools: &[Tool],
+) -> ContextUsage {
+    let system_prompt_sections: Vec<UsageItem> = prompt_sections(system_prompt)
+        .into_iter()
+        .filter(|(_, body)| !body.trim().is_empty())
+        .map(|(heading, body)| UsageItem {
+            tokens: counter.count_tokens(&format!("{}\n{}", heading, body)),
+            name: if heading.is_empty() {
+                "(preamble)".to_string()
+            } else {
+                heading
+            },
+        })
+        .collect();
+    let system_prompt_tokens = if system_prompt.is_empty() {
+        0
+    } else {
+        counter.count_tokens(system_prompt) + TOKENS_PER_MESSAGE
+    };
+
+    let mut extensions: BTreeMap<&str, Vec<Tool>> = BTreeMap::new();
+    for tool in tools {
+        let extension = tool
+            .name
+            .split_once("__")
+            .map(|(extension, _)| extension)
+            .unwrap_or("frontend");
+        extensions.entry(extension).or_default().push(tool.clone());
+    }
+    let mut tools_by_extension: Vec<UsageItem> = extensions
+        .into_iter()
+        .map(|(extension, tools)| UsageItem {
+            name: extension.to_string(),
+            tokens: counter.count_tokens_for_tools(&tools),
+        })
+        .collect();
+    tools_by_extension.sort_by(|a, b| b.tokens.cmp(&a.tokens));
+    let tool_tokens = counter.count_tokens_for_tools(tools);
+
+    let (history_tokens, resource_tokens) = history_tokens(counter, messages);
+
+    ContextUsage {
+        model: model_config.model_name.clone(),
+        context_limit: model_config.context_limit(),
+        total_tokens: system_prompt_tokens + tool_tokens + history_tokens + resource_tokens,
+        system_prompt_tokens,
+        system_prompt_sections,
+        tool_tokens,
+        tools_by_extension,
+        history_tokens,
+        resource_tokens,
+        compression: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GPT_4O_TOKENIZER;
+    use mcp_core::resource::ResourceContents;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_breakdown_by_source() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let tool = |name: &str| {
+            Tool::new(
+                name,
+                "Does something useful",
+                json!({"type": "object", "properties": {"path": {"type": "string"}}}),
+                None,
+            )
+        };
+        let tools = vec![
+            tool("developer__shell"),
+            tool("developer__text_editor"),
+            tool("github__list_issues"),
+        ];
+        let resource = Content::Resource(mcp_core::content::EmbeddedResource {
+            resource: ResourceContents::TextResourceContents {
+                uri: "file:///README.md".to_string(),
+                mime_type: None,
+                text: "Install goose with the install script.".to_string(),
+            },
+            annotations: None,
+        });
+        let messages = vec![
+            Message::user().with_text("Read the README"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__text_editor",
+                    json!({"path": "README.md"}),
+                )),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![resource])),
+        ];
+
+        let usage = context_usage(
+            &counter,
+            &model_config,
+            "You are goose.\n\n# Extensions\nUse the developer extension.\n",
+            &messages,
+            &tools,
+        );
+        assert_eq!(
+            usage
+                .system_prompt_sections
+                .iter()
+                .map(|section| section.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["(preamble)", "Extensions"]
+        );
+        assert_eq!(usage.tools_by_extension[0].name, "developer");
+        assert_eq!(usage.tools_by_extension.len(), 2);
+        assert!(usage.resource_tokens > 0);
+        assert!(usage.history_tokens > 0);
+        assert_eq!(
+            usage.total_tokens,
+            usage.system_prompt_tokens
+                + usage.tool_tokens
+                + usage.history_tokens
+                + usage.resource_tokens
+        );
+    }
+}
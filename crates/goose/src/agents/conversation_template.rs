@@ -0,0 +1,183 @@
+//! Conversation templates with pre-seeded turns
+//!
+//! Structured tasks are more reliable when the model has seen a worked example. A conversation
+//! template is a short exchange of user and assistant turns, declared in a recipe or in the
+//! config, that comes before the history in every request to the provider:
+//!
+//! ```yaml
+//! conversation_template:
+//!   - role: user
+//!     text: "Extract the invoice number and total from: Invoice INV-104, due 3 May, total $120.00"
+//!   - role: assistant
+//!     text: '{"invoice": "INV-104", "total": 120.00}'
+//! ```
+//!
+//! `GOOSE_CONVERSATION_TEMPLATE` takes the same list and applies when the session sets none. The
+//! turns are only part of what the model sees: they are never yielded to the frontend, stored in
+//! the session or counted in its message metrics. A template starts with a user turn and ends
+//! with an assistant turn, so the real conversation continues it with the user's message.
+use std::borrow::Cow;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::message::Message;
+
+use super::Agent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateRole {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateTurn {
+    pub role: TemplateRole,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ConversationTemplate {
+    messages: Vec<Message>,
+}
+
+impl ConversationTemplate {
+    /// A template from its turns; consecutive turns of the same role become one message
+    pub fn new(turns: Vec<TemplateTurn>) -> Result<Self> {
+        let mut messages: Vec<(TemplateRole, Message)> = Vec::new();
+        for turn in turns {
+            let text = turn.text.trim();
+            if text.is_empty() {
+                return Err(anyhow!("Conversation template turns cannot be empty"));
+            }
+            match messages.last_mut() {
+                Some((role, message)) if *role == turn.role => {
+                    *message = message.clone().with_text(text);
+                }
+                _ => {
+                    let message = match turn.role {
+                        TemplateRole::User => Message::user(),
+                        TemplateRole::Assistant => Message::assistant(),
+                    };
+                    messages.push((turn.role, message.with_text(text)));
+                }
+            }
+        }
+        match (messages.first(), messages.last()) {
+            (Some((TemplateRole::User, _)), Some((TemplateRole::Assistant, _))) => Ok(Self {
+                messages: messages.into_iter().map(|(_, message)| message).collect(),
+            }),
+            (None, _) => Err(anyhow!("A conversation template needs at least two turns")),
+            _ => Err(anyhow!(
+                "A conversation template starts with a user turn and ends with an assistant turn"
+            )),
+        }
+    }
+
+    /// The template from `GOOSE_CONVERSATION_TEMPLATE`, if one is configured and valid
+    pub fn from_config() -> Option<Self> {
+        let turns: Vec<TemplateTurn> = Config::global()
+            .get_param("GOOSE_CONVERSATION_TEMPLATE")
+            .ok()?;
+        match Self::new(turns) {
+            Ok(template) => Some(template),
+            Err(e) => {
+                tracing::warn!("Ignoring GOOSE_CONVERSATION_TEMPLATE: {}", e);
+                None
+            }
+        }
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// The history as the provider sees it, after the template turns
+    pub fn prepend<'a>(&self, history: &'a [Message]) -> Cow<'a, [Message]> {
+        let mut messages = self.messages.clone();
+        messages.extend_from_slice(history);
+        Cow::Owned(messages)
+    }
+}
+
+/// The history with the template in front of it, if there is one
+pub fn with_template<'a>(
+    template: Option<&ConversationTemplate>,
+    history: &'a [Message],
+) -> Cow<'a, [Message]> {
+    match template {
+        Some(template) => template.prepend(history),
+        None => Cow::Borrowed(history),
+    }
+}
+
+impl Agent {
+    /// Seed every request of later replies with these turns, replacing any earlier template.
+    /// An empty list falls back to the configured template.
+    pub async fn set_conversation_template(&self, turns: Vec<TemplateTurn>) -> Result<()> {
+        let template = if turns.is_empty() {
+            None
+        } else {
+            Some(ConversationTemplate::new(turns)?)
+        };
+        *self.conversation_template.lock().await = template;
+        Ok(())
+    }
+
+    /// The template for a reply: the one set on the agent, or the configured one
+    pub(super) async fn resolve_conversation_template(&self) -> Option<ConversationTemplate> {
+        match self.conversation_template.lock().await.clone() {
+            Some(template) => Some(template),
+            None => ConversationTemplate::from_config(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::role::Role;
+
+    fn turn(role: TemplateRole, text: &str) -> TemplateTurn {
+        TemplateTurn {
+            role,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_template_goes_before_history() {
+        let template = ConversationTemplate::new(vec![
+            turn(TemplateRole::User, "Extract: Invoice INV-104, $120.00"),
+            turn(TemplateRole::User, "Answer with JSON."),
+            turn(TemplateRole::Assistant, r#"{"invoice": "INV-104"}"#),
+        ])
+        .unwrap();
+        assert_eq!(template.messages().len(), 2);
+        assert_eq!(template.messages()[0].content.len(), 2);
+
+        let history = vec![Message::user().with_text("Extract: Invoice INV-200")];
+        let seeded = with_template(Some(&template), &history);
+        let roles: Vec<&Role> = seeded.iter().map(|m| &m.role).collect();
+        assert_eq!(roles, vec![&Role::User, &Role::Assistant, &Role::User]);
+        assert!(matches!(with_template(None, &history), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_template_must_hand_over_to_the_user() {
+        assert!(ConversationTemplate::new(vec![]).is_err());
+        assert!(ConversationTemplate::new(vec![
+            turn(TemplateRole::Assistant, "Hello"),
+            turn(TemplateRole::User, "Hi"),
+        ])
+        .is_err());
+        assert!(ConversationTemplate::new(vec![
+            turn(TemplateRole::User, "Hi"),
+            turn(TemplateRole::Assistant, " "),
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! Cost of a session, and a budget for it
+//!
+//! The agent adds up the cost of every provider response of a session from the input and
+//! output costs of the model, as listed in the known models of the provider that answered or
+//! in a model alias. A fallback or worker provider is priced as itself, not as the provider in
+//! `GOOSE_PROVIDER`.
+//! The total is stored in the session's metadata, so a resumed session carries on from it.
+//! With `GOOSE_MAX_SESSION_COST` each session stops calling the provider once it has spent
+//! the budget, in USD:
+//!
+//! ```yaml
+//! GOOSE_MAX_SESSION_COST: 5.00
+//! ```
+//!
+//! Responses from models without known costs are charged at the highest input and output costs
+//! of any known model rather than as free, so they cannot slip past the budget, and are counted
+//! as unpriced so a frontend can tell that the total is an estimate.
+use std::collections::HashMap;
+
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::model::ModelConfig;
+use crate::providers::base::{ProviderUsage, Usage};
+use crate::providers::providers;
+use crate::session::{self, storage::SessionMetadata};
+
+use super::cancellation::session_key;
+use super::types::SessionConfig;
+
+use super::estimate::model_costs;
+use super::Agent;
+
+/// The cost of a session so far, in USD
+#[derive(Debug, Clone, Default, PartialEq, Serialize, ToSchema)]
+pub struct SessionCost {
+    pub total: f64,
+    pub budget: Option<f64>,
+    /// Responses whose model has no known costs, charged at the highest known costs
+    pub unpriced_responses: u32,
+}
+
+impl SessionCost {
+    pub fn exceeded(&self) -> bool {
+        self.budget.is_some_and(|budget| self.total >= budget)
+    }
+}
+
+/// The cost of a response, with costs in USD per million tokens
+fn usage_cost(usage: &Usage, (input_cost, output_cost): (f64, f64)) -> f64 {
+    let input = usage.input_tokens.unwrap_or(0).max(0) as f64 * input_cost;
+    let output = usage.output_tokens.unwrap_or(0).max(0) as f64 * output_cost;
+    (input + output) / 1_000_000.0
+}
+
+/// The costs of the model that answered or else of the model that was asked, since providers
+/// often answer with a dated version of the model name, both at the provider that answered
+fn response_costs(model_config: &ModelConfig, usage: &ProviderUsage) -> Option<(f64, f64)> {
+    let provider = usage.provider.as_deref();
+    model_costs(provider, &usage.model).or_else(|| model_costs(provider, &model_config.model_name))
+}
+
+/// The highest input and output costs of any known model, for responses without known costs
+fn highest_known_costs() -> (f64, f64) {
+    providers()
+        .into_iter()
+        .flat_map(|metadata| metadata.known_models)
+        .fold((0.0, 0.0), |(input, output), info| {
+            (
+                info.input_cost.map_or(input, |cost| cost.max(input)),
+                info.output_cost.map_or(output, |cost| cost.max(output)),
+            )
+        })
+}
+
+/// The cost of a response in USD, at the highest known costs if its model has none
+pub fn response_cost(model_config: &ModelConfig, usage: &ProviderUsage) -> f64 {
+    let costs = response_costs(model_config, usage).unwrap_or_else(highest_known_costs);
+    usage_cost(&usage.usage, costs)
+}
+
+/// The cost of each session, by session key, against the same budget
+#[derive(Debug, Clone, Default)]
+pub struct CostTracker {
+    budget: Option<f64>,
+    sessions: HashMap<String, SessionCost>,
+}
+
+impl CostTracker {
+    pub fn new(budget: Option<f64>) -> Self {
+        Self {
+            budget: budget.filter(|budget| *budget > 0.0),
+            sessions: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(Config::global().get_param("GOOSE_MAX_SESSION_COST").ok())
+    }
+
+    /// Carry on from what a session spent before, unless it is already tracked
+    pub fn resume(&mut self, session_id: &str, metadata: &SessionMetadata) {
+        let budget = self.budget;
+        self.sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| SessionCost {
+                total: metadata.accumulated_cost.unwrap_or_default(),
+                budget,
+                unpriced_responses: metadata.unpriced_responses,
+            });
+    }
+
+    /// Add a response of a session, priced at the costs of its model, and return the new cost
+    pub fn record(
+        &mut self,
+        session_id: &str,
+        model_config: &ModelConfig,
+        usage: &ProviderUsage,
+    ) -> SessionCost {
+        let costs = response_costs(model_config, usage).ok_or_else(highest_known_costs);
+        self.add(session_id, &usage.usage, costs)
+    }
+
+    /// Add a response at the costs of its model, or at the given costs if it is unpriced
+    fn add(
+        &mut self,
+        session_id: &str,
+        usage: &Usage,
+        costs: Result<(f64, f64), (f64, f64)>,
+    ) -> SessionCost {
+        let budget = self.budget;
+        let cost = self
+            .sessions
+            .entry(session_id.to_string())
+            .or_insert_with(|| SessionCost {
+                budget,
+                ..SessionCost::default()
+            });
+        let costs = costs.unwrap_or_else(|costs| {
+            cost.unpriced_responses += 1;
+            costs
+        });
+        cost.total += usage_cost(usage, costs);
+        cost.clone()
+    }
+
+    pub fn cost(&self, session_id: &str) -> SessionCost {
+        self.sessions
+            .get(session_id)
+            .cloned()
+            .unwrap_or_else(|| SessionCost {
+                budget: self.budget,
+                ..SessionCost::default()
+            })
+    }
+}
+
+/// The key a reply's session is tracked by, empty for replies without a session
+fn tracked_key(session: Option<&SessionConfig>) -> String {
+    session
+        .and_then(|session| session_key(&session.id))
+        .unwrap_or_default()
+}
+
+impl Agent {
+    /// The cost of a session so far, by its name or the stem of its file
+    pub async fn get_session_cost(&self, session_id: &str) -> SessionCost {
+        self.cost_tracker.lock().await.cost(session_id)
+    }
+
+    /// Start tracking the cost of a reply's session from the total in its metadata
+    pub(super) async fn resume_session_cost(&self, session: Option<&SessionConfig>) {
+        let Some(session) = session else {
+            return;
+        };
+        let key = tracked_key(Some(session));
+        if let Ok(metadata) =
+            session::storage::read_metadata(&session::storage::get_path(session.id.clone()))
+        {
+            self.cost_tracker.lock().await.resume(&key, &metadata);
+        }
+    }
+
+    /// Add a response to the cost of a reply's session, returning the new cost
+    pub(super) async fn record_session_cost(
+        &self,
+        session: Option<&SessionConfig>,
+        model_config: &ModelConfig,
+        usage: &ProviderUsage,
+    ) -> SessionCost {
+        self.cost_tracker
+            .lock()
+            .await
+            .record(&tracked_key(session), model_config, usage)
+    }
+
+    /// The message that ends a reply once its session has spent its budget
+    pub(super) async fn session_budget_exceeded(
+        &self,
+        session: Option<&SessionConfig>,
+    ) -> Option<String> {
+        let cost = self.get_session_cost(&tracked_key(session)).await;
+        if !cost.exceeded() {
+            return None;
+        }
+        Some(format!(
+            "This session has spent ${:.2}, which reaches its budget of ${:.2} \
+             (GOOSE_MAX_SESSION_COST), so I've stopped here. Raise the budget to keep going.",
+            cost.total,
+            cost.budget.unwrap_or_default()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracker_adds_up_priced_responses() {
+        let mut tracker = CostTracker::new(Some(0.05));
+        let usage = Usage::new(Some(10_000), Some(2_000), Some(12_000));
+
+        // $3 input and $15 output per million tokens
+        tracker.add("a", &usage, Ok((3.0, 15.0)));
+        assert!((tracker.cost("a").total - 0.06).abs() < 1e-9);
+        assert!(tracker.cost("a").exceeded());
+
+        // Unpriced responses still count, at the costs they are charged at
+        let cost = tracker.add("a", &usage, Err((15.0, 75.0)));
+        assert_eq!(cost.unpriced_responses, 1);
+        assert!((cost.total - 0.36).abs() < 1e-9);
+
+        // Other sessions have budgets of their own
+        assert!(!tracker.cost("b").exceeded());
+        assert!(!CostTracker::new(None).cost("a").exceeded());
+        assert_eq!(CostTracker::new(Some(0.0)).cost("a").budget, None);
+    }
+
+    #[test]
+    fn test_resumed_sessions_carry_on_from_their_metadata() {
+        let mut tracker = CostTracker::new(Some(1.0));
+        let metadata = SessionMetadata {
+            accumulated_cost: Some(0.75),
+            unpriced_responses: 2,
+            ..SessionMetadata::default()
+        };
+        tracker.resume("a", &metadata);
+        let usage = Usage::new(Some(100_000), Some(0), Some(100_000));
+        let cost = tracker.add("a", &usage, Ok((3.0, 15.0)));
+        assert!((cost.total - 1.05).abs() < 1e-9);
+        assert_eq!(cost.unpriced_responses, 2);
+        assert!(cost.exceeded());
+
+        // A session already tracked keeps its running total
+        tracker.resume("a", &SessionMetadata::default());
+        assert!((tracker.cost("a").total - 1.05).abs() < 1e-9);
+    }
+}
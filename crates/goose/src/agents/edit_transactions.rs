@@ -0,0 +1,535 @@
+//! Staged multi-file edits that are applied together after one approval
+//!
+//! With `GOOSE_EDIT_TRANSACTIONS: true` the model gets a tool to begin, commit or discard a
+//! changeset. While one is open, the developer extension's text editor stages `write` and
+//! `str_replace` edits in memory instead of writing them, `view` shows the staged content and
+//! `undo_edit` drops the last staged edit of a file. Staged edits run without approval, as they
+//! write nothing. Committing always asks the user, whatever the goose mode, with the combined
+//! diff of every staged file. Once approved the files are
+//! written together: if any write fails the files already written are restored, and if a
+//! file changed on disk after it was staged nothing is written at all.
+//!
+//! Files restricted by `.gooseignore` are never staged, so the developer extension still
+//! refuses them.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use etcetera::{choose_app_strategy, AppStrategy};
+use ignore::gitignore::GitignoreBuilder;
+use mcp_core::{Content, Role, ToolCall, ToolError, ToolResult};
+use serde_json::Value;
+use similar::TextDiff;
+
+use crate::config::{Config, APP_STRATEGY};
+use crate::message::ToolRequest;
+use crate::permission::permission_judge::PermissionCheckResult;
+
+use super::platform_tools::PLATFORM_EDIT_TRANSACTION_TOOL_NAME;
+use super::Agent;
+
+const TEXT_EDITOR_TOOL_NAME: &str = "developer__text_editor";
+
+/// Whether the model can stage edits and commit them together
+pub fn edit_transactions_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_EDIT_TRANSACTIONS")
+        .unwrap_or(false)
+}
+
+/// Whether the user's `.gooseignore` keeps the developer extension away from a file
+fn restricted_by_gooseignore(path: &Path) -> bool {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let mut builder = GitignoreBuilder::new(&cwd);
+    let global = choose_app_strategy(APP_STRATEGY.clone())
+        .map(|strategy| strategy.in_config_dir(".gooseignore"))
+        .unwrap_or_default();
+    let mut has_ignore_file = false;
+    for ignore_file in [global, cwd.join(".gooseignore")] {
+        if ignore_file.is_file() {
+            let _ = builder.add(ignore_file);
+            has_ignore_file = true;
+        }
+    }
+    if !has_ignore_file {
+        let _ = builder.add_line(None, "**/.env");
+        let _ = builder.add_line(None, "**/.env.*");
+        let _ = builder.add_line(None, "**/secrets.*");
+    }
+    builder
+        .build()
+        .map(|ignore| ignore.matched(path, false).is_ignore())
+        .unwrap_or(false)
+}
+
+fn argument<'a>(arguments: &'a Value, name: &str) -> ToolResult<&'a str> {
+    arguments
+        .get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ToolError::InvalidParameters(format!("Missing '{}' parameter", name)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StagedFile {
+    /// The content on disk when the file was first staged, None if it did not exist
+    original: Option<String>,
+    /// The staged content after each edit, latest last
+    edits: Vec<String>,
+}
+
+impl StagedFile {
+    fn current(&self) -> Option<&str> {
+        self.edits
+            .last()
+            .map(String::as_str)
+            .or(self.original.as_deref())
+    }
+}
+
+/// Edits staged since a transaction began, by absolute path
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditTransaction {
+    files: BTreeMap<PathBuf, StagedFile>,
+}
+
+impl EditTransaction {
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.keys().cloned().collect()
+    }
+
+    fn staged(&mut self, path: &Path) -> ToolResult<&mut StagedFile> {
+        if !self.files.contains_key(path) {
+            let original = match fs::read_to_string(path) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(ToolError::ExecutionError(format!(
+                        "Failed to read {}: {}",
+                        path.display(),
+                        e
+                    )))
+                }
+            };
+            self.files.insert(
+                path.to_path_buf(),
+                StagedFile {
+                    original,
+                    edits: Vec::new(),
+                },
+            );
+        }
+        Ok(self.files.get_mut(path).expect("staged above"))
+    }
+
+    /// The command and path of a text editor call that is staged rather than dispatched
+    fn staged_call<'a>(&self, arguments: &'a Value) -> Option<(&'a str, &'a Path)> {
+        let command = arguments.get("command")?.as_str()?;
+        let path = Path::new(arguments.get("path")?.as_str()?);
+        if !path.is_absolute() || restricted_by_gooseignore(path) {
+            return None;
+        }
+        match command {
+            "write" | "str_replace" | "edit_file" => Some((command, path)),
+            "view" | "undo_edit" if self.files.contains_key(path) => Some((command, path)),
+            _ => None,
+        }
+    }
+
+    /// Whether a text editor call would be staged, so nothing is written until the commit
+    pub fn stages(&self, arguments: &Value) -> bool {
+        self.staged_call(arguments).is_some()
+    }
+
+    /// Stage a text editor call, or None for calls that are not staged
+    pub fn stage(&mut self, arguments: &Value) -> Option<ToolResult<Vec<Content>>> {
+        let (command, path) = self.staged_call(arguments)?;
+        let result = match command {
+            "view" => self.view(path),
+            "write" => self.write(path, arguments),
+            "undo_edit" => self.undo(path),
+            _ => self.replace(path, arguments),
+        };
+        // Files whose edits all failed or were undone are no longer part of the changeset
+        self.files.retain(|_, staged| !staged.edits.is_empty());
+        Some(result)
+    }
+
+    fn view(&mut self, path: &Path) -> ToolResult<Vec<Content>> {
+        let Some(content) = self.staged(path)?.current() else {
+            return Err(ToolError::InvalidParameters(format!(
+                "{} does not exist",
+                path.display()
+            )));
+        };
+        Ok(vec![Content::text(format!(
+            "### {} (staged)\n```\n{}\n```\n",
+            path.display(),
+            content
+        ))])
+    }
+
+    fn write(&mut self, path: &Path, arguments: &Value) -> ToolResult<Vec<Content>> {
+        let mut text = argument(arguments, "file_text")?.to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        self.staged(path)?.edits.push(text);
+        Ok(vec![Content::text(format!(
+            "Staged a write to {}, it is applied when the edits are committed",
+            path.display()
+        ))])
+    }
+
+    fn replace(&mut self, path: &Path, arguments: &Value) -> ToolResult<Vec<Content>> {
+        let old_str = argument(arguments, "old_str")?;
+        let new_str = argument(arguments, "new_str")?;
+        let staged = self.staged(path)?;
+        let Some(content) = staged.current() else {
+            return Err(ToolError::InvalidParameters(format!(
+                "File '{}' does not exist, you can write a new file with the `write` command",
+                path.display()
+            )));
+        };
+        match content.matches(old_str).count() {
+            1 => {}
+            0 => {
+                return Err(ToolError::InvalidParameters(
+                    "'old_str' must appear exactly once in the file, but it does not appear in the file. Make sure the string exactly matches the staged content, including whitespace!".into(),
+                ))
+            }
+            _ => {
+                return Err(ToolError::InvalidParameters(
+                    "'old_str' must appear exactly once in the file, but it appears multiple times"
+                        .into(),
+                ))
+            }
+        }
+        let edited = content.replace(old_str, new_str);
+        staged.edits.push(edited);
+        Ok(vec![Content::text(format!(
+            "Staged an edit to {}, it is applied when the edits are committed",
+            path.display()
+        ))])
+    }
+
+    fn undo(&mut self, path: &Path) -> ToolResult<Vec<Content>> {
+        self.staged(path)?.edits.pop();
+        Ok(vec![Content::text(format!(
+            "Dropped the last staged edit to {}",
+            path.display()
+        ))])
+    }
+
+    /// The combined unified diff of the staged files
+    pub fn diff(&self) -> String {
+        let mut diff = String::new();
+        for (path, staged) in &self.files {
+            let original = staged.original.as_deref().unwrap_or("");
+            let staged_content = staged.current().unwrap_or("");
+            let from = match staged.original {
+                Some(_) => format!("a{}", path.display()),
+                None => "/dev/null".to_string(),
+            };
+            diff.push_str(
+                &TextDiff::from_lines(original, staged_content)
+                    .unified_diff()
+                    .header(&from, &format!("b{}", path.display()))
+                    .to_string(),
+            );
+        }
+        diff
+    }
+
+    /// Write every staged file, restoring those already written if one fails
+    pub fn apply(&self) -> Result<()> {
+        for (path, staged) in &self.files {
+            if fs::read_to_string(path).ok() != staged.original {
+                bail!(
+                    "{} changed on disk after its edits were staged, nothing was applied",
+                    path.display()
+                );
+            }
+        }
+
+        // Write the staged content next to each file first, so the files are replaced by
+        // renames that do not fail part way through a file
+        let mut pending = Vec::new();
+        for (path, staged) in &self.files {
+            let Some(content) = staged.current() else {
+                continue;
+            };
+            let temp = path.with_file_name(format!(
+                ".{}.goose-staged",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&temp, content));
+            if let Err(e) = written {
+                for (temp, _) in &pending {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(e).with_context(|| format!("Failed to stage {}", path.display()));
+            }
+            pending.push((temp, path));
+        }
+
+        for (i, (temp, path)) in pending.iter().enumerate() {
+            if let Err(e) = fs::rename(temp, path) {
+                for (_, applied) in &pending[..i] {
+                    self.restore(applied);
+                }
+                for (temp, _) in &pending[i..] {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(e).with_context(|| {
+                    format!(
+                        "Failed to write {}, the changes were rolled back",
+                        path.display()
+                    )
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn restore(&self, path: &Path) {
+        let restored = match self.files.get(path).and_then(|f| f.original.as_ref()) {
+            Some(original) => fs::write(path, original),
+            None => fs::remove_file(path),
+        };
+        if let Err(e) = restored {
+            tracing::error!("Failed to roll back {}: {}", path.display(), e);
+        }
+    }
+}
+
+fn action(arguments: &Value) -> &str {
+    arguments
+        .get("action")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+}
+
+impl Agent {
+    /// Stage a text editor call when a transaction is open, or None to dispatch it as usual
+    pub(super) async fn stage_edit(
+        &self,
+        tool_call: &ToolCall,
+    ) -> Option<ToolResult<Vec<Content>>> {
+        if tool_call.name != TEXT_EDITOR_TOOL_NAME {
+            return None;
+        }
+        self.edit_transaction
+            .lock()
+            .await
+            .as_mut()?
+            .stage(&tool_call.arguments)
+    }
+
+    /// Ask the user before every commit of staged edits, whatever the goose mode. Edits that
+    /// are staged write nothing, so they need no approval of their own: the commit asks once
+    /// for all of them.
+    pub(super) async fn require_commit_approval(&self, result: &mut PermissionCheckResult) {
+        let (commits, approved): (Vec<ToolRequest>, Vec<ToolRequest>) =
+            std::mem::take(&mut result.approved)
+                .into_iter()
+                .partition(|request| {
+                    request.tool_call.as_ref().is_ok_and(|call| {
+                        call.name == PLATFORM_EDIT_TRANSACTION_TOOL_NAME
+                            && action(&call.arguments) == "commit"
+                    })
+                });
+        result.approved = approved;
+        result.needs_approval.extend(commits);
+
+        let transaction = self.edit_transaction.lock().await;
+        let Some(transaction) = transaction.as_ref() else {
+            return;
+        };
+        let (staged, needs_approval): (Vec<ToolRequest>, Vec<ToolRequest>) =
+            std::mem::take(&mut result.needs_approval)
+                .into_iter()
+                .partition(|request| {
+                    request.tool_call.as_ref().is_ok_and(|call| {
+                        call.name == TEXT_EDITOR_TOOL_NAME && transaction.stages(&call.arguments)
+                    })
+                });
+        result.needs_approval = needs_approval;
+        result.approved.extend(staged);
+    }
+
+    /// The approval prompt for a commit, with the combined diff of the staged edits
+    pub(super) async fn commit_prompt(&self, tool_call: &ToolCall) -> Option<String> {
+        if tool_call.name != PLATFORM_EDIT_TRANSACTION_TOOL_NAME
+            || action(&tool_call.arguments) != "commit"
+        {
+            return None;
+        }
+        let transaction = self.edit_transaction.lock().await;
+        let transaction = transaction.as_ref().filter(|t| !t.is_empty())?;
+        Some(format!(
+            "{}\nApply the staged changes to {} file(s)? (y/n):",
+            transaction.diff(),
+            transaction.len()
+        ))
+    }
+
+    /// Handle the edit transaction platform tool
+    pub(super) async fn handle_edit_transaction(
+        &self,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let mut transaction = self.edit_transaction.lock().await;
+        match action(&arguments) {
+            "begin" => {
+                if transaction.as_ref().is_some_and(|t| !t.is_empty()) {
+                    return Err(ToolError::ExecutionError(
+                        "Edits are already being staged, commit or discard them first".into(),
+                    ));
+                }
+                *transaction = Some(EditTransaction::default());
+                Ok(vec![Content::text(
+                    "Edits made with the text editor are now staged until you commit them",
+                )])
+            }
+            "commit" => {
+                let Some(staged) = transaction.as_ref() else {
+                    return Err(ToolError::ExecutionError(
+                        "No edits are being staged, begin a transaction first".into(),
+                    ));
+                };
+                if staged.is_empty() {
+                    *transaction = None;
+                    return Ok(vec![Content::text(
+                        "No edits were staged, nothing was applied",
+                    )]);
+                }
+                staged
+                    .apply()
+                    .map_err(|e| ToolError::ExecutionError(format!("{:#}", e)))?;
+                let paths: Vec<String> = staged
+                    .paths()
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                let diff = staged.diff();
+                *transaction = None;
+                Ok(vec![
+                    Content::text(format!("Applied the staged edits to {}", paths.join(", ")))
+                        .with_audience(vec![Role::Assistant]),
+                    Content::text(format!("```diff\n{}```\n", diff))
+                        .with_audience(vec![Role::User])
+                        .with_priority(0.2),
+                ])
+            }
+            "discard" => match transaction.take() {
+                Some(staged) => Ok(vec![Content::text(format!(
+                    "Discarded the staged edits to {} file(s)",
+                    staged.len()
+                ))]),
+                None => Ok(vec![Content::text("No edits were being staged")]),
+            },
+            other => Err(ToolError::InvalidParameters(format!(
+                "Unknown action '{}', expected begin, commit or discard",
+                other
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn edit(transaction: &mut EditTransaction, arguments: Value) -> ToolResult<Vec<Content>> {
+        transaction.stage(&arguments).expect("staged")
+    }
+
+    #[test]
+    fn test_edits_are_staged_until_applied() {
+        let dir = TempDir::new().unwrap();
+        let main = dir.path().join("main.rs");
+        let lib = dir.path().join("src/lib.rs");
+        fs::write(&main, "fn main() {}\n").unwrap();
+
+        let mut transaction = EditTransaction::default();
+        edit(
+            &mut transaction,
+            json!({"command": "str_replace", "path": main, "old_str": "{}", "new_str": "{ run() }"}),
+        )
+        .unwrap();
+        edit(
+            &mut transaction,
+            json!({"command": "write", "path": lib, "file_text": "pub fn run() {}"}),
+        )
+        .unwrap();
+        assert!(edit(
+            &mut transaction,
+            json!({"command": "str_replace", "path": main, "old_str": "missing", "new_str": ""}),
+        )
+        .is_err());
+
+        // Nothing is written before the changeset is applied
+        assert_eq!(fs::read_to_string(&main).unwrap(), "fn main() {}\n");
+        assert!(!lib.exists());
+        let diff = transaction.diff();
+        assert!(diff.contains("+fn main() { run() }"));
+        assert!(diff.contains("--- /dev/null"));
+
+        // Views are not staged for files without staged edits, so they are approved as usual
+        let other = dir.path().join("other.rs");
+        assert!(!transaction.stages(&json!({"command": "view", "path": other})));
+        assert!(transaction.stages(&json!({"command": "view", "path": main})));
+        assert!(transaction.stages(&json!({"command": "write", "path": other})));
+        assert!(transaction
+            .stage(&json!({"command": "view", "path": other}))
+            .is_none());
+
+        transaction.apply().unwrap();
+        assert_eq!(fs::read_to_string(&main).unwrap(), "fn main() { run() }\n");
+        assert_eq!(fs::read_to_string(&lib).unwrap(), "pub fn run() {}\n");
+    }
+
+    #[test]
+    fn test_conflicts_and_undo() {
+        let dir = TempDir::new().unwrap();
+        let notes = dir.path().join("notes.txt");
+        let todo = dir.path().join("todo.txt");
+        fs::write(&notes, "one\n").unwrap();
+
+        let mut transaction = EditTransaction::default();
+        edit(
+            &mut transaction,
+            json!({"command": "write", "path": notes, "file_text": "two"}),
+        )
+        .unwrap();
+        edit(
+            &mut transaction,
+            json!({"command": "write", "path": todo, "file_text": "later"}),
+        )
+        .unwrap();
+        edit(
+            &mut transaction,
+            json!({"command": "undo_edit", "path": todo}),
+        )
+        .unwrap();
+        assert_eq!(transaction.paths(), vec![notes.clone()]);
+
+        fs::write(&notes, "changed elsewhere\n").unwrap();
+        assert!(transaction.apply().is_err());
+        assert_eq!(fs::read_to_string(&notes).unwrap(), "changed elsewhere\n");
+    }
+}
@@ -0,0 +1,133 @@
+//! Estimate what the next turn will send to the provider, without calling it
+//!
+//! Frontends can show the estimate before an expensive run: how many tokens the prompt will
+//! take, which tools will be advertised, what it may cost and whether it fits in the context.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::message::Message;
+use crate::providers::model_aliases::ModelAliases;
+use crate::providers::providers;
+
+use super::context_usage::load_counter;
+use super::Agent;
+
+/// Output tokens assumed for the upper bound when the model has no max tokens set
+const DEFAULT_MAX_OUTPUT_TOKENS: usize = 4_096;
+
+/// Expected cost of a turn in USD
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct CostRange {
+    /// The cost of the prompt alone
+    pub min: f64,
+    /// The cost of the prompt and a response of the maximum length
+    pub max: f64,
+}
+
+impl CostRange {
+    /// The cost range for a prompt, with costs in USD per million tokens
+    pub fn new(
+        prompt_tokens: usize,
+        max_output_tokens: usize,
+        input_cost: f64,
+        output_cost: f64,
+    ) -> Self {
+        let min = prompt_tokens as f64 * input_cost / 1_000_000.0;
+        Self {
+            min,
+            max: min + max_output_tokens as f64 * output_cost / 1_000_000.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct TurnEstimate {
+    pub model: String,
+    /// Estimated tokens of the system prompt, messages and tool definitions
+    pub prompt_tokens: usize,
+    /// Of the prompt tokens, those taken by tool definitions
+    pub tool_tokens: usize,
+    /// Names of the tools that will be advertised
+    pub tools: Vec<String>,
+    pub context_limit: usize,
+    pub max_output_tokens: usize,
+    /// Whether the prompt and the longest response would not fit in the context
+    pub exceeds_context: bool,
+    /// Expected cost, if the model's costs are known
+    pub cost: Option<CostRange>,
+}
+
+/// The costs of a model from the known models of a provider, or of the alias the model was
+/// resolved from. Without a provider, the configured provider is used.
+pub(crate) fn model_costs(provider: Option<&str>, model_name: &str) -> Option<(f64, f64)> {
+    let provider: String = match provider {
+        Some(provider) => provider.to_string(),
+        None => Config::global().get_param("GOOSE_PROVIDER").ok()?,
+    };
+    let known = providers()
+        .into_iter()
+        .find(|metadata| metadata.name == provider)
+        .and_then(|metadata| {
+            metadata
+                .known_models
+                .into_iter()
+                .find(|info| info.name == model_name)
+        })
+        .and_then(|info| Some((info.input_cost?, info.output_cost?)));
+    known.or_else(|| {
+        let aliases = ModelAliases::from_config();
+        let alias = aliases.find_target(&provider, model_name)?;
+        Some((alias.input_cost?, alias.output_cost?))
+    })
+}
+
+impl Agent {
+    /// Estimate the next turn for these messages with the current tools and system prompt
+    pub async fn estimate_turn(&self, messages: &[Message]) -> Result<TurnEstimate> {
+        let (tools, _toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
+        let model_config = self.provider().await?.get_model_config();
+
+        let counter = load_counter(&model_config).await.ok_or_else(|| {
+            anyhow!(
+                "Failed to load the tokenizer for {}",
+                model_config.model_name
+            )
+        })?;
+        let prompt_tokens = counter.count_chat_tokens(&system_prompt, messages, &tools);
+        let tool_tokens = counter.count_tokens_for_tools(&tools);
+
+        let context_limit = model_config.context_limit();
+        let max_output_tokens = model_config
+            .max_tokens
+            .map(|max| max.max(0) as usize)
+            .unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS);
+
+        Ok(TurnEstimate {
+            cost: model_costs(None, &model_config.model_name).map(|(input_cost, output_cost)| {
+                CostRange::new(prompt_tokens, max_output_tokens, input_cost, output_cost)
+            }),
+            model: model_config.model_name,
+            prompt_tokens,
+            tool_tokens,
+            tools: tools.into_iter().map(|tool| tool.name).collect(),
+            context_limit,
+            max_output_tokens,
+            exceeds_context: prompt_tokens + max_output_tokens > context_limit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_range() {
+        let cost = CostRange::new(200_000, 4_000, 3.0, 15.0);
+        assert!((cost.min - 0.6).abs() < 1e-9);
+        assert!((cost.max - 0.66).abs() < 1e-9);
+    }
+}
@@ -1,12 +1,15 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use mcp_client::client::Error as ClientError;
+use mcp_core::protocol::InitializeResult;
 use mcp_core::tool::Tool;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::warn;
 use utoipa::ToSchema;
 
+use super::extension_names::ExtensionNameError;
 use crate::config;
 use crate::config::extensions::name_to_key;
 use crate::config::permission::PermissionLevel;
@@ -28,10 +31,40 @@ pub enum ExtensionError {
     SetupError(String),
     #[error("Join error occurred during task execution: {0}")]
     TaskJoinError(#[from] tokio::task::JoinError),
+    #[error(transparent)]
+    InvalidName(#[from] ExtensionNameError),
 }
 
 pub type ExtensionResult<T> = Result<T, ExtensionError>;
 
+/// An extension that failed while the agent was collecting tools from all extensions. Its
+/// tools are left out until it recovers, while the other extensions keep working.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ExtensionWarning {
+    pub extension: String,
+    pub message: String,
+}
+
+/// What reloading the extension config changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ReloadSummary {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Extensions whose config changed, started again with the new config
+    pub restarted: Vec<String>,
+    /// Extensions that could not be started with their new config
+    pub failed: Vec<ExtensionWarning>,
+}
+
+impl ReloadSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.restarted.is_empty()
+            && self.failed.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default, ToSchema)]
 pub struct Envs {
     /// A map of environment variables to set, e.g. API_KEY -> some_secret, HOST -> host
@@ -118,6 +151,67 @@ impl Envs {
     }
 }
 
+/// Which of an extension's tools are given to the model, by glob patterns on the tool names
+/// without the extension prefix, where `*` matches any run of characters and `?` any one.
+/// With `include`, only tools matching one of its patterns are given; tools matching an
+/// `exclude` pattern never are.
+///
+/// ```yaml
+/// tool_filter:
+///   include: ["get_*", "list_*", "search"]
+///   exclude: ["*_admin"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct ToolFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl ToolFilter {
+    /// Whether the tool of this name, without its extension prefix, is given to the model
+    pub fn allows(&self, tool_name: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| glob_matches(pattern, tool_name))
+        };
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// Whether the name matches the glob pattern, with `*` matching any run of characters and `?`
+/// any one character
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to resume after the last `*` if the rest fails to match
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 /// Represents the different types of MCP extensions that can be added to the manager
 #[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 #[serde(tag = "type")]
@@ -136,6 +230,34 @@ pub enum ExtensionConfig {
         // NOTE: set timeout to be optional for compatibility.
         // However, new configurations should include this field.
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds, before it is cancelled
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
+        /// Whether this extension is bundled with Goose
+        #[serde(default)]
+        bundled: Option<bool>,
+    },
+    /// WebSocket client with a `ws://` or `wss://` endpoint, which reconnects when the
+    /// connection drops
+    #[serde(rename = "websocket")]
+    WebSocket {
+        /// The name used to identify this extension
+        name: String,
+        uri: String,
+        /// Headers sent when connecting, e.g. for the authorization of a gateway
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        description: Option<String>,
+        timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds, before it is cancelled
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
         /// Whether this extension is bundled with Goose
         #[serde(default)]
         bundled: Option<bool>,
@@ -152,6 +274,12 @@ pub enum ExtensionConfig {
         #[serde(default)]
         env_keys: Vec<String>,
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds, before it is cancelled
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
         description: Option<String>,
         /// Whether this extension is bundled with Goose
         #[serde(default)]
@@ -164,6 +292,12 @@ pub enum ExtensionConfig {
         name: String,
         display_name: Option<String>, // needed for the UI
         timeout: Option<u64>,
+        /// Longest a tool call may run, in seconds, before it is cancelled
+        #[serde(default)]
+        tool_timeout: Option<u64>,
+        /// Which of the extension's tools are given to the model
+        #[serde(default)]
+        tool_filter: Option<ToolFilter>,
         /// Whether this extension is bundled with Goose
         #[serde(default)]
         bundled: Option<bool>,
@@ -189,6 +323,8 @@ impl Default for ExtensionConfig {
             name: config::DEFAULT_EXTENSION.to_string(),
             display_name: Some(config::DEFAULT_DISPLAY_NAME.to_string()),
             timeout: Some(config::DEFAULT_EXTENSION_TIMEOUT),
+            tool_timeout: None,
+            tool_filter: None,
             bundled: Some(true),
         }
     }
@@ -203,6 +339,26 @@ impl ExtensionConfig {
             env_keys: Vec::new(),
             description: Some(description.into()),
             timeout: Some(timeout.into()),
+            tool_timeout: None,
+            tool_filter: None,
+            bundled: None,
+        }
+    }
+
+    pub fn websocket<S: Into<String>, T: Into<u64>>(
+        name: S,
+        uri: S,
+        description: S,
+        timeout: T,
+    ) -> Self {
+        Self::WebSocket {
+            name: name.into(),
+            uri: uri.into(),
+            headers: HashMap::new(),
+            description: Some(description.into()),
+            timeout: Some(timeout.into()),
+            tool_timeout: None,
+            tool_filter: None,
             bundled: None,
         }
     }
@@ -221,6 +377,8 @@ impl ExtensionConfig {
             env_keys: Vec::new(),
             description: Some(description.into()),
             timeout: Some(timeout.into()),
+            tool_timeout: None,
+            tool_filter: None,
             bundled: None,
         }
     }
@@ -237,6 +395,8 @@ impl ExtensionConfig {
                 envs,
                 env_keys,
                 timeout,
+                tool_timeout,
+                tool_filter,
                 description,
                 bundled,
                 ..
@@ -248,12 +408,36 @@ impl ExtensionConfig {
                 args: args.into_iter().map(Into::into).collect(),
                 description,
                 timeout,
+                tool_timeout,
+                tool_filter,
                 bundled,
             },
             other => other,
         }
     }
 
+    /// The longest a tool call of this extension may run, if the extension sets it
+    pub fn tool_timeout(&self) -> Option<Duration> {
+        match self {
+            Self::Sse { tool_timeout, .. }
+            | Self::WebSocket { tool_timeout, .. }
+            | Self::Stdio { tool_timeout, .. }
+            | Self::Builtin { tool_timeout, .. } => tool_timeout.map(Duration::from_secs),
+            Self::Frontend { .. } => None,
+        }
+    }
+
+    /// Which of this extension's tools are given to the model, if the extension limits them
+    pub fn tool_filter(&self) -> Option<&ToolFilter> {
+        match self {
+            Self::Sse { tool_filter, .. }
+            | Self::WebSocket { tool_filter, .. }
+            | Self::Stdio { tool_filter, .. }
+            | Self::Builtin { tool_filter, .. } => tool_filter.as_ref(),
+            Self::Frontend { .. } => None,
+        }
+    }
+
     pub fn key(&self) -> String {
         let name = self.name();
         name_to_key(&name)
@@ -263,18 +447,33 @@ impl ExtensionConfig {
     pub fn name(&self) -> String {
         match self {
             Self::Sse { name, .. } => name,
+            Self::WebSocket { name, .. } => name,
             Self::Stdio { name, .. } => name,
             Self::Builtin { name, .. } => name,
             Self::Frontend { name, .. } => name,
         }
         .to_string()
     }
+
+    /// Give the extension another name, as when migrating a config with an invalid one
+    pub(crate) fn rename(&mut self, new_name: String) {
+        match self {
+            Self::Sse { name, .. }
+            | Self::WebSocket { name, .. }
+            | Self::Stdio { name, .. }
+            | Self::Builtin { name, .. }
+            | Self::Frontend { name, .. } => *name = new_name,
+        }
+    }
 }
 
 impl std::fmt::Display for ExtensionConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ExtensionConfig::Sse { name, uri, .. } => write!(f, "SSE({}: {})", name, uri),
+            ExtensionConfig::WebSocket { name, uri, .. } => {
+                write!(f, "WebSocket({}: {})", name, uri)
+            }
             ExtensionConfig::Stdio {
                 name, cmd, args, ..
             } => {
@@ -294,6 +493,22 @@ pub struct ExtensionInfo {
     pub name: String,
     pub instructions: String,
     pub has_resources: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<ExtensionDetails>,
+    /// What the extension's tools are, when tools are grouped by extension in the prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<ToolHint>,
+}
+
+/// A tool listed under its extension in the prompt, with what it is for
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ToolHint {
+    pub name: String,
+    /// The first sentence of the tool's description
+    pub hint: String,
+    pub read_only: bool,
 }
 
 impl ExtensionInfo {
@@ -302,6 +517,40 @@ impl ExtensionInfo {
             name: name.to_string(),
             instructions: instructions.to_string(),
             has_resources,
+            details: None,
+            summary: None,
+            tools: Vec::new(),
+        }
+    }
+
+    pub fn with_details(mut self, details: ExtensionDetails) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Version and capability information negotiated with an MCP server during initialization
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExtensionDetails {
+    pub name: String,
+    pub protocol_version: String,
+    pub server_name: String,
+    pub server_version: String,
+    pub supports_tools: bool,
+    pub supports_resources: bool,
+    pub supports_prompts: bool,
+}
+
+impl ExtensionDetails {
+    pub fn from_init_result(name: &str, init_result: &InitializeResult) -> Self {
+        Self {
+            name: name.to_string(),
+            protocol_version: init_result.protocol_version.clone(),
+            server_name: init_result.server_info.name.clone(),
+            server_version: init_result.server_info.version.clone(),
+            supports_tools: init_result.capabilities.tools.is_some(),
+            supports_resources: init_result.capabilities.resources.is_some(),
+            supports_prompts: init_result.capabilities.prompts.is_some(),
         }
     }
 }
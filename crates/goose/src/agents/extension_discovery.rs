@@ -0,0 +1,220 @@
+//! Discovery of running MCP servers
+//!
+//! Besides the extensions in the config, `search_available_extensions` can list MCP servers that
+//! are already running on the machine or the network. Each registry in
+//! `GOOSE_EXTENSION_DISCOVERY` is asked for the servers it knows about:
+//!
+//! ```yaml
+//! GOOSE_EXTENSION_DISCOVERY:
+//!   - http://localhost:7373/.well-known/mcp-servers
+//!   - http://registry.lan/.well-known/mcp-servers
+//! ```
+//!
+//! A registry answers with the servers it knows about, which a registry on the local network
+//! may have found over mDNS:
+//!
+//! ```json
+//! {"servers": [{"name": "jira", "description": "Jira issues", "uri": "http://10.0.0.5:8811/sse"}]}
+//! ```
+//!
+//! Discovered servers are enabled with `manage_extensions` like configured ones, as SSE
+//! extensions. Since nobody configured them, enabling one always asks the user first, whatever
+//! the goose mode.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::future;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::message::ToolRequest;
+use crate::permission::permission_judge::PermissionCheckResult;
+use crate::providers::http_client;
+
+use super::extension::{Envs, ExtensionConfig};
+use super::platform_tools::PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME;
+use super::Agent;
+
+/// Longest wait for a registry to answer
+pub const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A running MCP server listed by a registry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscoveredServer {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The SSE endpoint of the server
+    pub uri: String,
+    /// The registry that listed the server
+    #[serde(skip)]
+    pub registry: String,
+}
+
+impl DiscoveredServer {
+    pub fn to_config(&self) -> ExtensionConfig {
+        ExtensionConfig::Sse {
+            name: self.name.clone(),
+            uri: self.uri.clone(),
+            envs: Envs::default(),
+            env_keys: Vec::new(),
+            description: self.description.clone(),
+            timeout: None,
+            tool_timeout: None,
+            tool_filter: None,
+            bundled: None,
+        }
+    }
+}
+
+/// The registries in `GOOSE_EXTENSION_DISCOVERY`
+pub fn configured_registries() -> Vec<String> {
+    Config::global()
+        .get_param("GOOSE_EXTENSION_DISCOVERY")
+        .unwrap_or_default()
+}
+
+/// The servers in a registry's answer, skipping entries without a name or uri
+fn parse_registry(registry: &str, response: Value) -> Vec<DiscoveredServer> {
+    let servers = match response.get("servers").and_then(Value::as_array) {
+        Some(servers) => servers.clone(),
+        None => return Vec::new(),
+    };
+    servers
+        .into_iter()
+        .filter_map(|server| serde_json::from_value::<DiscoveredServer>(server).ok())
+        .filter(|server| !server.name.trim().is_empty() && !server.uri.trim().is_empty())
+        .map(|server| DiscoveredServer {
+            registry: registry.to_string(),
+            ..server
+        })
+        .collect()
+}
+
+async fn query_registry(
+    client: &reqwest::Client,
+    registry: &str,
+) -> anyhow::Result<Vec<DiscoveredServer>> {
+    let response = client
+        .get(registry)
+        .timeout(DISCOVERY_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(parse_registry(registry, response.json().await?))
+}
+
+/// Ask the registries for their servers; a server listed by several registries is taken from
+/// the first of them
+pub async fn discover(registries: &[String]) -> Vec<DiscoveredServer> {
+    if registries.is_empty() {
+        return Vec::new();
+    }
+    let client = match http_client::default_client() {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Failed to create client for extension discovery: {}", e);
+            return Vec::new();
+        }
+    };
+    let answers = future::join_all(
+        registries
+            .iter()
+            .map(|registry| query_registry(&client, registry)),
+    )
+    .await;
+
+    let mut servers: Vec<DiscoveredServer> = Vec::new();
+    for (registry, answer) in registries.iter().zip(answers) {
+        match answer {
+            Ok(found) => {
+                for server in found {
+                    if !servers.iter().any(|s| s.name == server.name) {
+                        servers.push(server);
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Extension registry {} did not answer: {}", registry, e),
+        }
+    }
+    servers
+}
+
+/// Discovered servers by name, as last listed to the model
+#[derive(Debug, Default)]
+pub struct DiscoveredServers {
+    servers: HashMap<String, DiscoveredServer>,
+}
+
+impl DiscoveredServers {
+    pub fn replace(&mut self, servers: Vec<DiscoveredServer>) {
+        self.servers = servers
+            .into_iter()
+            .map(|server| (server.name.clone(), server))
+            .collect();
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DiscoveredServer> {
+        self.servers.get(name)
+    }
+}
+
+fn enabled_extension(request: &ToolRequest) -> Option<&str> {
+    let call = request.tool_call.as_ref().ok()?;
+    if call.name != PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME
+        || call.arguments.get("action").and_then(Value::as_str) != Some("enable")
+    {
+        return None;
+    }
+    call.arguments.get("extension_name").and_then(Value::as_str)
+}
+
+impl Agent {
+    /// Move requests to enable discovered servers to the ones that need the user's approval
+    pub(super) async fn require_discovery_approval(&self, result: &mut PermissionCheckResult) {
+        let extension_manager = self.extension_manager.lock().await;
+        let (discovered, approved): (Vec<ToolRequest>, Vec<ToolRequest>) =
+            std::mem::take(&mut result.approved)
+                .into_iter()
+                .partition(|request| {
+                    enabled_extension(request)
+                        .is_some_and(|name| extension_manager.discovered_config(name).is_some())
+                });
+        result.approved = approved;
+        result.needs_approval.extend(discovered);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_registry() {
+        let servers = parse_registry(
+            "http://localhost:7373/.well-known/mcp-servers",
+            json!({
+                "servers": [
+                    {"name": "jira", "description": "Jira issues", "uri": "http://10.0.0.5:8811/sse"},
+                    {"name": "", "uri": "http://10.0.0.6:8811/sse"},
+                    {"name": "no-uri"},
+                    {"name": "wiki", "uri": "http://10.0.0.7:8811/sse"}
+                ]
+            }),
+        );
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].name, "jira");
+        assert_eq!(servers[1].description, None);
+        assert_eq!(
+            servers[1].registry,
+            "http://localhost:7373/.well-known/mcp-servers"
+        );
+        assert!(matches!(
+            servers[0].to_config(),
+            ExtensionConfig::Sse { ref uri, .. } if uri == "http://10.0.0.5:8811/sse"
+        ));
+        assert!(parse_registry("registry", json!({"items": []})).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Health checks and automatic restarts of extensions
+//!
+//! An extension whose server process dies mid-session would otherwise fail every tool call
+//! until the session ends. The health monitor pings each extension every
+//! `GOOSE_EXTENSION_HEALTH_INTERVAL` seconds. An extension that does not answer within
+//! `GOOSE_EXTENSION_PING_TIMEOUT` seconds is marked unhealthy and started again from its
+//! config, up to `GOOSE_EXTENSION_MAX_RESTARTS` times, after which it is reported as failed.
+//!
+//! ```yaml
+//! # 0 disables the health monitor
+//! GOOSE_EXTENSION_HEALTH_INTERVAL: 30
+//! GOOSE_EXTENSION_PING_TIMEOUT: 5
+//! GOOSE_EXTENSION_MAX_RESTARTS: 3
+//! ```
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+use super::Agent;
+
+const DEFAULT_INTERVAL_SECS: u64 = 30;
+const DEFAULT_PING_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_MAX_RESTARTS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HealthCheckPolicy {
+    /// How often each extension is pinged
+    pub interval: Duration,
+    /// How long an extension has to answer a ping
+    pub ping_timeout: Duration,
+    /// Restarts allowed per extension before it is given up on
+    pub max_restarts: u32,
+}
+
+impl Default for HealthCheckPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(DEFAULT_INTERVAL_SECS),
+            ping_timeout: Duration::from_secs(DEFAULT_PING_TIMEOUT_SECS),
+            max_restarts: DEFAULT_MAX_RESTARTS,
+        }
+    }
+}
+
+impl HealthCheckPolicy {
+    /// The configured policy, or None if health checks are disabled
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let interval = config
+            .get_param::<u64>("GOOSE_EXTENSION_HEALTH_INTERVAL")
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        if interval == 0 {
+            return None;
+        }
+        Some(Self {
+            interval: Duration::from_secs(interval),
+            ping_timeout: Duration::from_secs(
+                config
+                    .get_param("GOOSE_EXTENSION_PING_TIMEOUT")
+                    .unwrap_or(DEFAULT_PING_TIMEOUT_SECS),
+            ),
+            max_restarts: config
+                .get_param("GOOSE_EXTENSION_MAX_RESTARTS")
+                .unwrap_or(DEFAULT_MAX_RESTARTS),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtensionState {
+    /// Answered its last ping, or has not been checked yet
+    Healthy,
+    /// Did not answer its last ping and could not be restarted yet
+    Unhealthy,
+    /// Used up its restarts without recovering
+    Failed,
+}
+
+/// The outcome of the health checks of one extension
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ExtensionStatus {
+    pub name: String,
+    pub state: ExtensionState,
+    /// Times the extension was restarted after failing a health check
+    pub restarts: u32,
+    /// Why the last failed check or restart failed
+    pub last_error: Option<String>,
+    /// When the extension was last checked, if it has been
+    pub last_checked: Option<DateTime<Utc>>,
+}
+
+impl ExtensionStatus {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            state: ExtensionState::Healthy,
+            restarts: 0,
+            last_error: None,
+            last_checked: None,
+        }
+    }
+}
+
+impl Agent {
+    /// The health of each enabled extension, by name
+    pub async fn list_extension_status(&self) -> Vec<ExtensionStatus> {
+        self.extension_manager.lock().await.list_extension_status()
+    }
+
+    /// Ping every extension now, restarting those that do not answer
+    pub async fn check_extension_health(&self, policy: &HealthCheckPolicy) -> Vec<ExtensionStatus> {
+        self.extension_manager
+            .lock()
+            .await
+            .check_extension_health(policy)
+            .await
+    }
+
+    /// Periodically check the health of the agent's extensions, unless
+    /// `GOOSE_EXTENSION_HEALTH_INTERVAL` is 0. The monitor stops once the agent is dropped.
+    pub fn spawn_health_monitor(agent: &Arc<Agent>) -> Option<JoinHandle<()>> {
+        let policy = HealthCheckPolicy::from_config()?;
+        let agent: Weak<Agent> = Arc::downgrade(agent);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(policy.interval);
+            // The first tick completes immediately, before any extension has been added
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let Some(agent) = agent.upgrade() else {
+                    break;
+                };
+                for status in agent.check_extension_health(&policy).await {
+                    if status.state != ExtensionState::Healthy {
+                        tracing::warn!(
+                            extension = status.name,
+                            state = ?status.state,
+                            restarts = status.restarts,
+                            "Extension failed its health check: {}",
+                            status.last_error.unwrap_or_default()
+                        );
+                    }
+                }
+            }
+        }))
+    }
+}
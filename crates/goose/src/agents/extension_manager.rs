@@ -6,19 +6,30 @@ use mcp_core::protocol::GetPromptResult;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::sync::LazyLock;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::Mutex;
 use tokio::task;
 use tokio_stream::wrappers::ReceiverStream;
+use tokio_util::sync::CancellationToken;
 use tracing::{error, warn};
 
-use super::extension::{ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, ToolInfo};
+use super::extension::{
+    ExtensionConfig, ExtensionDetails, ExtensionError, ExtensionInfo, ExtensionResult,
+    ExtensionWarning, ReloadSummary, ToolInfo,
+};
+use super::extension_discovery::{configured_registries, discover, DiscoveredServers};
+use super::extension_health::{ExtensionState, ExtensionStatus, HealthCheckPolicy};
+use super::extension_names::{check_unique, validate_name};
+use super::extension_telemetry::{CallOutcome, ExtensionHealth, ExtensionTelemetry};
 use super::tool_execution::ToolCallResult;
 use crate::agents::extension::Envs;
-use crate::config::{Config, ExtensionConfigManager};
+use crate::config::{Config, ExtensionConfigManager, ExtensionEntry};
 use crate::prompt_template;
+use crate::providers::http_client::HttpClientSettings;
 use mcp_client::client::{ClientCapabilities, ClientInfo, McpClient, McpClientTrait};
-use mcp_client::transport::{SseTransport, StdioTransport, Transport};
+use mcp_client::transport::{
+    LogBuffer, SseTransport, StdioTransport, Transport, WebSocketTransport,
+};
 use mcp_core::{prompt::Prompt, Content, Tool, ToolCall, ToolError};
 use serde_json::Value;
 
@@ -34,6 +45,21 @@ pub struct ExtensionManager {
     clients: HashMap<String, McpClientBox>,
     instructions: HashMap<String, String>,
     resource_capable_extensions: HashSet<String>,
+    details: HashMap<String, ExtensionDetails>,
+    logs: HashMap<String, LogBuffer>,
+    /// Extensions whose tools could not be listed the last time they were asked
+    degraded: std::sync::Mutex<HashMap<String, ExtensionWarning>>,
+    /// The config each extension was started from, so it can be started again
+    configs: HashMap<String, ExtensionConfig>,
+    /// Latency and error counts of the tool calls made to each extension
+    telemetry: Arc<std::sync::Mutex<ExtensionTelemetry>>,
+    /// Running servers found by the last search of the extension registries
+    discovered: std::sync::Mutex<DiscoveredServers>,
+    /// Outcome of the last health check of each extension
+    health: HashMap<String, ExtensionStatus>,
+    /// Extensions listed in the config file at startup or the last reload, so that one deleted
+    /// from the file since is stopped while extensions added by other means are left alone
+    configured: HashSet<String>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -71,7 +97,7 @@ impl ResourceItem {
 
 /// Sanitizes a string by replacing invalid characters with underscores.
 /// Valid characters match [a-zA-Z0-9_-]
-fn normalize(input: String) -> String {
+pub(crate) fn normalize(input: String) -> String {
     let mut result = String::with_capacity(input.len());
     for c in input.chars() {
         result.push(match c {
@@ -83,6 +109,34 @@ fn normalize(input: String) -> String {
     result.to_lowercase()
 }
 
+/// Whether two configs start the same extension
+fn same_config(a: &ExtensionConfig, b: &ExtensionConfig) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// The longest a tool call may run: the extension's `tool_timeout`, or else
+/// `GOOSE_TOOL_TIMEOUT`, both in seconds
+fn tool_deadline(config: Option<&ExtensionConfig>) -> Duration {
+    config
+        .and_then(ExtensionConfig::tool_timeout)
+        .unwrap_or_else(|| {
+            Duration::from_secs(
+                Config::global()
+                    .get_param("GOOSE_TOOL_TIMEOUT")
+                    .unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
+            )
+        })
+}
+
+fn timed_out(tool_name: &str, deadline: Duration, elapsed: Duration) -> ToolError {
+    ToolError::Timeout(format!(
+        "{} was cancelled after {:.1}s, past its deadline of {}s",
+        tool_name,
+        elapsed.as_secs_f64(),
+        deadline.as_secs()
+    ))
+}
+
 pub fn get_parameter_names(tool: &Tool) -> Vec<String> {
     tool.input_schema
         .get("properties")
@@ -91,6 +145,20 @@ pub fn get_parameter_names(tool: &Tool) -> Vec<String> {
         .unwrap_or_default()
 }
 
+// Frontend extensions are provided by the frontend, not started from the config file
+fn is_frontend(config: &ExtensionConfig) -> bool {
+    matches!(config, ExtensionConfig::Frontend { .. })
+}
+
+/// The names of the extensions the config file lists, enabled or not
+fn listed_extensions(entries: &[ExtensionEntry]) -> HashSet<String> {
+    entries
+        .iter()
+        .filter(|entry| !is_frontend(&entry.config))
+        .map(|entry| normalize(entry.config.key()))
+        .collect()
+}
+
 impl Default for ExtensionManager {
     fn default() -> Self {
         Self::new()
@@ -100,10 +168,24 @@ impl Default for ExtensionManager {
 impl ExtensionManager {
     /// Create a new ExtensionManager instance
     pub fn new() -> Self {
+        Self::with_config_entries(&ExtensionConfigManager::get_all().unwrap_or_default())
+    }
+
+    /// Create an ExtensionManager that knows which extensions the config file lists as it
+    /// starts, so that one deleted from the file before the first reload is still stopped
+    fn with_config_entries(entries: &[ExtensionEntry]) -> Self {
         Self {
             clients: HashMap::new(),
             instructions: HashMap::new(),
             resource_capable_extensions: HashSet::new(),
+            details: HashMap::new(),
+            logs: HashMap::new(),
+            degraded: std::sync::Mutex::new(HashMap::new()),
+            configs: HashMap::new(),
+            telemetry: Arc::new(std::sync::Mutex::new(ExtensionTelemetry::from_config())),
+            discovered: std::sync::Mutex::new(DiscoveredServers::default()),
+            health: HashMap::new(),
+            configured: listed_extensions(entries),
         }
     }
 
@@ -114,8 +196,12 @@ impl ExtensionManager {
     /// Add a new MCP extension based on the provided client type
     // TODO IMPORTANT need to ensure this times out if the extension command is broken!
     pub async fn add_extension(&mut self, config: ExtensionConfig) -> ExtensionResult<()> {
+        validate_name(&config.name())?;
         let config_name = config.key().to_string();
         let sanitized_name = normalize(config_name.clone());
+        if let Some(existing) = self.configs.get(&sanitized_name) {
+            check_unique(&config.name(), [existing.name().as_str()])?;
+        }
 
         /// Helper function to merge environment variables from direct envs and keychain-stored env_keys
         async fn merge_environments(
@@ -183,7 +269,28 @@ impl ExtensionManager {
                 ..
             } => {
                 let all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
-                let transport = SseTransport::new(uri, all_envs);
+                let http_options = HttpClientSettings::from_config()
+                    .transport_options()
+                    .map_err(|e| ExtensionError::SetupError(e.to_string()))?;
+                let transport = SseTransport::new(uri, all_envs).with_http_options(http_options);
+                let handle = transport.start().await?;
+                Box::new(
+                    McpClient::connect(
+                        handle,
+                        Duration::from_secs(
+                            timeout.unwrap_or(crate::config::DEFAULT_EXTENSION_TIMEOUT),
+                        ),
+                    )
+                    .await?,
+                )
+            }
+            ExtensionConfig::WebSocket {
+                uri,
+                headers,
+                timeout,
+                ..
+            } => {
+                let transport = WebSocketTransport::new(uri, headers.clone());
                 let handle = transport.start().await?;
                 Box::new(
                     McpClient::connect(
@@ -204,7 +311,11 @@ impl ExtensionManager {
                 ..
             } => {
                 let all_envs = merge_environments(envs, env_keys, &sanitized_name).await?;
-                let transport = StdioTransport::new(cmd, args.to_vec(), all_envs);
+                // Registered before connecting so the logs explain a failed startup
+                let log = LogBuffer::default();
+                self.logs.insert(sanitized_name.clone(), log.clone());
+                let transport =
+                    StdioTransport::new(cmd, args.to_vec(), all_envs).with_stderr_log(log);
                 let handle = transport.start().await?;
                 Box::new(
                     McpClient::connect(
@@ -220,6 +331,8 @@ impl ExtensionManager {
                 name,
                 display_name: _,
                 timeout,
+                tool_timeout: _,
+                tool_filter: _,
                 bundled: _,
             } => {
                 let cmd = std::env::current_exe()
@@ -227,11 +340,14 @@ impl ExtensionManager {
                     .to_str()
                     .expect("should resolve executable to string path")
                     .to_string();
+                let log = LogBuffer::default();
+                self.logs.insert(sanitized_name.clone(), log.clone());
                 let transport = StdioTransport::new(
                     &cmd,
                     vec!["mcp".to_string(), name.clone()],
                     HashMap::new(),
-                );
+                )
+                .with_stderr_log(log);
                 let handle = transport.start().await?;
                 Box::new(
                     McpClient::connect(
@@ -258,6 +374,11 @@ impl ExtensionManager {
             .await
             .map_err(|e| ExtensionError::Initialization(config.clone(), e))?;
 
+        self.details.insert(
+            sanitized_name.clone(),
+            ExtensionDetails::from_init_result(&sanitized_name, &init_result),
+        );
+
         if let Some(instructions) = init_result.instructions {
             self.instructions
                 .insert(sanitized_name.clone(), instructions);
@@ -270,6 +391,7 @@ impl ExtensionManager {
 
         self.clients
             .insert(sanitized_name.clone(), Arc::new(Mutex::new(client)));
+        self.configs.insert(sanitized_name, config);
 
         Ok(())
     }
@@ -281,11 +403,42 @@ impl ExtensionManager {
             .map(|name| {
                 let instructions = self.instructions.get(name).cloned().unwrap_or_default();
                 let has_resources = self.resource_capable_extensions.contains(name);
-                ExtensionInfo::new(name, &instructions, has_resources)
+                let info = ExtensionInfo::new(name, &instructions, has_resources);
+                match self.details.get(name) {
+                    Some(details) => info.with_details(details.clone()),
+                    None => info,
+                }
             })
             .collect()
     }
 
+    /// Get the negotiated protocol version, server version and capabilities of each extension
+    pub fn get_extension_details(&self) -> Vec<ExtensionDetails> {
+        let mut details: Vec<ExtensionDetails> = self.details.values().cloned().collect();
+        details.sort_by(|a, b| a.name.cmp(&b.name));
+        details
+    }
+
+    /// Get the call latency and error rates of each extension that has been called
+    pub fn get_extension_health(&self) -> Vec<ExtensionHealth> {
+        self.telemetry.lock().unwrap().all_health()
+    }
+
+    /// Get the last `n` lines an extension wrote to stderr, oldest first. Logs are kept for
+    /// extensions that failed to start so the failure can be inspected.
+    pub fn get_extension_logs(&self, name: &str, n: usize) -> ExtensionResult<Vec<String>> {
+        let sanitized_name = normalize(name.to_string());
+        self.logs
+            .get(&sanitized_name)
+            .map(|log| log.tail(n))
+            .ok_or_else(|| {
+                ExtensionError::SetupError(format!(
+                    "No logs captured for extension '{}'. Logs are only available for stdio and builtin extensions",
+                    name
+                ))
+            })
+    }
+
     /// Get aggregated usage statistics
     pub async fn remove_extension(&mut self, name: &str) -> ExtensionResult<()> {
         let sanitized_name = normalize(name.to_string());
@@ -293,9 +446,103 @@ impl ExtensionManager {
         self.clients.remove(&sanitized_name);
         self.instructions.remove(&sanitized_name);
         self.resource_capable_extensions.remove(&sanitized_name);
+        self.details.remove(&sanitized_name);
+        self.logs.remove(&sanitized_name);
+        self.degraded.lock().unwrap().remove(&sanitized_name);
+        self.configs.remove(&sanitized_name);
+        self.telemetry.lock().unwrap().remove(&sanitized_name);
+        self.health.remove(&sanitized_name);
         Ok(())
     }
 
+    /// The configs of the enabled extensions, by name
+    pub fn extension_configs(&self) -> Vec<ExtensionConfig> {
+        let mut configs: Vec<(&String, &ExtensionConfig)> = self.configs.iter().collect();
+        configs.sort_by(|a, b| a.0.cmp(b.0));
+        configs
+            .into_iter()
+            .map(|(_, config)| config.clone())
+            .collect()
+    }
+
+    /// Bring the running extensions in line with the extension config on disk: start the
+    /// enabled extensions that are not running, stop those that were disabled or deleted, and
+    /// restart those whose config changed. Extensions that were not started from the config
+    /// file, such as those of a recipe, are left running.
+    pub async fn reload(&mut self) -> ExtensionResult<ReloadSummary> {
+        let entries = ExtensionConfigManager::get_all().map_err(|e| {
+            ExtensionError::SetupError(format!("Failed to read the extension config: {}", e))
+        })?;
+        self.reload_from(entries).await
+    }
+
+    async fn reload_from(
+        &mut self,
+        entries: Vec<ExtensionEntry>,
+    ) -> ExtensionResult<ReloadSummary> {
+        let listed = listed_extensions(&entries);
+        let mut enabled: Vec<(String, ExtensionConfig)> = entries
+            .into_iter()
+            .filter(|entry| entry.enabled && !is_frontend(&entry.config))
+            .map(|entry| (normalize(entry.config.key()), entry.config))
+            .collect();
+        enabled.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut summary = ReloadSummary::default();
+        let mut running: Vec<String> = self.configs.keys().cloned().collect();
+        running.sort();
+        for name in running {
+            let deleted = self.configured.contains(&name) && !listed.contains(&name);
+            let disabled = listed.contains(&name) && !enabled.iter().any(|(n, _)| *n == name);
+            if deleted || disabled {
+                self.remove_extension(&name).await?;
+                summary.removed.push(name);
+            }
+        }
+
+        for (name, config) in enabled {
+            let previous = match self.configs.get(&name) {
+                None => None,
+                Some(running) if same_config(running, &config) => continue,
+                Some(running) => Some(running.clone()),
+            };
+            if previous.is_some() {
+                self.remove_extension(&name).await?;
+            }
+            match (self.add_extension(config).await, previous) {
+                (Ok(()), None) => summary.added.push(name),
+                (Ok(()), Some(_)) => summary.restarted.push(name),
+                (Err(e), previous) => {
+                    // An extension that was running keeps running with its old config
+                    if let Some(previous) = previous {
+                        if let Err(e) = self.add_extension(previous).await {
+                            warn!("Failed to restore extension {}: {}", name, e);
+                        }
+                    }
+                    summary.failed.push(ExtensionWarning {
+                        extension: name,
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        self.configured = listed;
+        Ok(summary)
+    }
+
+    /// Stop all extensions, returning their configs so they can be added again later.
+    /// Dropping a stdio client kills its server process.
+    pub async fn suspend_extensions(&mut self) -> ExtensionResult<Vec<ExtensionConfig>> {
+        let mut configs: Vec<(String, ExtensionConfig)> = self.configs.drain().collect();
+        configs.sort_by(|a, b| a.0.cmp(&b.0));
+        let names: Vec<String> = self.clients.keys().cloned().collect();
+        for name in names {
+            self.remove_extension(&name).await?;
+        }
+        Ok(configs.into_iter().map(|(_, config)| config).collect())
+    }
+
     pub async fn suggest_disable_extensions_prompt(&self) -> Value {
         let enabled_extensions_count = self.clients.len();
 
@@ -332,11 +579,145 @@ impl ExtensionManager {
         Ok(self.clients.keys().cloned().collect())
     }
 
-    /// Get all tools from all clients with proper prefixing
+    /// Get the tools of all extensions, or of a single extension if `extension_name` is set.
+    ///
+    /// When listing all extensions, an extension that fails is left out and recorded as
+    /// degraded rather than failing the whole listing; see [`Self::degraded_extensions`].
     pub async fn get_prefixed_tools(
         &self,
         extension_name: Option<String>,
     ) -> ExtensionResult<Vec<Tool>> {
+        let (tools, mut failures) = self.list_tools_by_extension(extension_name.clone()).await;
+
+        // A caller asking for one extension wants to know that it failed
+        if extension_name.is_some() && !failures.is_empty() {
+            return Err(failures.remove(0).1);
+        }
+
+        for (name, err) in &failures {
+            warn!("Failed to list tools for extension {}: {}", name, err);
+        }
+        Ok(tools)
+    }
+
+    /// The health of each enabled extension, by name
+    pub fn list_extension_status(&self) -> Vec<ExtensionStatus> {
+        let mut names: Vec<&String> = self.clients.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                self.health
+                    .get(name)
+                    .cloned()
+                    .unwrap_or_else(|| ExtensionStatus::new(name))
+            })
+            .collect()
+    }
+
+    /// Ping every extension, restarting those that do not answer from their config while
+    /// they have restarts left. Extensions busy with a tool call are not pinged; the call's
+    /// own deadline covers them.
+    pub async fn check_extension_health(
+        &mut self,
+        policy: &HealthCheckPolicy,
+    ) -> Vec<ExtensionStatus> {
+        let pings = self.clients.iter().filter_map(|(name, client)| {
+            let client = client.clone().try_lock_owned().ok()?;
+            let name = name.clone();
+            let ping_timeout = policy.ping_timeout;
+            Some(async move {
+                let result = match tokio::time::timeout(ping_timeout, client.ping()).await {
+                    // An error response still shows the server is alive
+                    Ok(Ok(())) | Ok(Err(mcp_client::Error::RpcError { .. })) => Ok(()),
+                    Ok(Err(e)) => Err(e.to_string()),
+                    Err(_) => Err(format!(
+                        "No answer to ping within {}s",
+                        ping_timeout.as_secs()
+                    )),
+                };
+                (name, result)
+            })
+        });
+        let results = future::join_all(pings).await;
+
+        for (name, result) in results {
+            let mut status = self
+                .health
+                .remove(&name)
+                .unwrap_or_else(|| ExtensionStatus::new(&name));
+            status.last_checked = Some(Utc::now());
+            match result {
+                Ok(()) => {
+                    status.state = ExtensionState::Healthy;
+                    status.last_error = None;
+                }
+                Err(e) => {
+                    warn!("Extension {} failed its health check: {}", name, e);
+                    status.last_error = Some(e);
+                    status.state = self.restart_extension(&name, &mut status, policy).await;
+                }
+            }
+            self.health.insert(name, status);
+        }
+        self.list_extension_status()
+    }
+
+    /// Start an extension that stopped answering again, returning its state afterwards. The
+    /// unresponsive client is kept when the restart fails, so the next check tries again.
+    async fn restart_extension(
+        &mut self,
+        name: &str,
+        status: &mut ExtensionStatus,
+        policy: &HealthCheckPolicy,
+    ) -> ExtensionState {
+        if status.restarts >= policy.max_restarts {
+            return ExtensionState::Failed;
+        }
+        let Some(config) = self.configs.get(name).cloned() else {
+            status.last_error = Some(format!("No config to restart {} from", name));
+            return ExtensionState::Failed;
+        };
+        status.restarts += 1;
+        tracing::info!(
+            "Restarting extension {} (restart {}/{})",
+            name,
+            status.restarts,
+            policy.max_restarts
+        );
+
+        // Adding the extension again replaces the client, which stops the old server
+        match self.add_extension(config).await {
+            Ok(()) => {
+                self.degraded.lock().unwrap().remove(name);
+                status.last_error = None;
+                ExtensionState::Healthy
+            }
+            Err(e) => {
+                status.last_error = Some(format!("Restart failed: {}", e));
+                if status.restarts >= policy.max_restarts {
+                    ExtensionState::Failed
+                } else {
+                    ExtensionState::Unhealthy
+                }
+            }
+        }
+    }
+
+    /// Extensions that failed the last time their tools were listed
+    pub fn degraded_extensions(&self) -> Vec<ExtensionWarning> {
+        let mut warnings: Vec<ExtensionWarning> =
+            self.degraded.lock().unwrap().values().cloned().collect();
+        warnings.sort_by(|a, b| a.extension.cmp(&b.extension));
+        warnings
+    }
+
+    /// List tools from each extension concurrently, returning the tools of the extensions that
+    /// succeeded and the errors of those that failed
+    async fn list_tools_by_extension(
+        &self,
+        extension_name: Option<String>,
+    ) -> (Vec<Tool>, Vec<(String, ExtensionError)>) {
         // Filter clients based on the provided extension_name or include all if None
         let filtered_clients = self.clients.iter().filter(|(name, _)| {
             if let Some(ref name_filter) = extension_name {
@@ -348,17 +729,27 @@ impl ExtensionManager {
 
         let client_futures = filtered_clients.map(|(name, client)| {
             let name = name.clone();
+            let prefix = name.clone();
             let client = client.clone();
-
-            task::spawn(async move {
+            let filter = self
+                .configs
+                .get(&name)
+                .and_then(ExtensionConfig::tool_filter)
+                .cloned()
+                .unwrap_or_default();
+
+            let handle = task::spawn(async move {
                 let mut tools = Vec::new();
                 let client_guard = client.lock().await;
                 let mut client_tools = client_guard.list_tools(None).await?;
 
                 loop {
                     for tool in client_tools.tools {
+                        if !filter.allows(&tool.name) {
+                            continue;
+                        }
                         tools.push(Tool::new(
-                            format!("{}__{}", name, tool.name),
+                            format!("{}__{}", prefix, tool.name),
                             &tool.description,
                             tool.input_schema,
                             tool.annotations,
@@ -374,23 +765,38 @@ impl ExtensionManager {
                 }
 
                 Ok::<Vec<Tool>, ExtensionError>(tools)
-            })
+            });
+            handle.map(move |result| (name, result))
         });
 
         // Collect all results concurrently
         let results = future::join_all(client_futures).await;
 
-        // Aggregate tools and handle errors
+        // Aggregate tools, keeping track of the extensions that failed
         let mut tools = Vec::new();
-        for result in results {
-            match result {
-                Ok(Ok(client_tools)) => tools.extend(client_tools),
-                Ok(Err(err)) => return Err(err),
-                Err(join_err) => return Err(ExtensionError::from(join_err)),
-            }
+        let mut failures = Vec::new();
+        let mut degraded = self.degraded.lock().unwrap();
+        for (name, result) in results {
+            let err = match result {
+                Ok(Ok(client_tools)) => {
+                    degraded.remove(&name);
+                    tools.extend(client_tools);
+                    continue;
+                }
+                Ok(Err(err)) => err,
+                Err(join_err) => ExtensionError::from(join_err),
+            };
+            degraded.insert(
+                name.clone(),
+                ExtensionWarning {
+                    extension: name.clone(),
+                    message: err.to_string(),
+                },
+            );
+            failures.push((name, err));
         }
 
-        Ok(tools)
+        (tools, failures)
     }
 
     /// Get client resources and their contents
@@ -617,7 +1023,13 @@ impl ExtensionManager {
         }
     }
 
-    pub async fn dispatch_tool_call(&self, tool_call: ToolCall) -> Result<ToolCallResult> {
+    /// Dispatch a tool call to its extension, which is told to stop working on it when `cancel`
+    /// is cancelled
+    pub async fn dispatch_tool_call(
+        &self,
+        tool_call: ToolCall,
+        cancel: CancellationToken,
+    ) -> Result<ToolCallResult> {
         // Dispatch tool call based on the prefix naming convention
         let (client_name, client) = self
             .get_client_for_tool(&tool_call.name)
@@ -631,17 +1043,67 @@ impl ExtensionManager {
             .ok_or_else(|| ToolError::NotFound(tool_call.name.clone()))?
             .to_string();
 
+        // Tools left out by the extension's filter are not offered, so they cannot be called
+        let filtered_out = self
+            .configs
+            .get(client_name)
+            .and_then(ExtensionConfig::tool_filter)
+            .is_some_and(|filter| !filter.allows(&tool_name));
+        if filtered_out {
+            return Err(ToolError::NotFound(tool_call.name.clone()).into());
+        }
+
         let arguments = tool_call.arguments.clone();
         let client = client.clone();
         let notifications_receiver = client.lock().await.subscribe().await;
+        let extension_name = client_name.to_string();
+        let telemetry = self.telemetry.clone();
+        let deadline = tool_deadline(self.configs.get(client_name));
 
         let fut = async move {
-            let client_guard = client.lock().await;
-            client_guard
-                .call_tool(&tool_name, arguments)
-                .await
-                .map(|call| call.content)
-                .map_err(|e| ToolError::ExecutionError(e.to_string()))
+            // The deadline covers waiting for the client too, which an earlier hung call holds
+            let dispatched = Instant::now();
+            let call = async {
+                let client_guard = client.lock().await;
+                let started = Instant::now();
+                let result = client_guard
+                    .call_tool_cancellable(&tool_name, arguments, cancel)
+                    .await;
+                (started, result)
+            };
+            let (started, result) = match tokio::time::timeout(deadline, call).await {
+                Ok(call) => call,
+                Err(_) => {
+                    telemetry.lock().unwrap().record(
+                        &extension_name,
+                        dispatched.elapsed(),
+                        CallOutcome::Timeout,
+                    );
+                    return Err(timed_out(&tool_call.name, deadline, dispatched.elapsed()));
+                }
+            };
+            let outcome = match &result {
+                Ok(call) if call.is_error == Some(true) => Some(CallOutcome::Error),
+                Ok(_) => Some(CallOutcome::Success),
+                Err(mcp_client::Error::Timeout(_)) => Some(CallOutcome::Timeout),
+                // A cancelled call says nothing about the health of the extension
+                Err(mcp_client::Error::Cancelled) => None,
+                Err(_) => Some(CallOutcome::Error),
+            };
+            if let Some(outcome) = outcome {
+                telemetry
+                    .lock()
+                    .unwrap()
+                    .record(&extension_name, started.elapsed(), outcome);
+            }
+            result.map(|call| call.content).map_err(|e| match e {
+                mcp_client::Error::Timeout(_) => ToolError::Timeout(format!(
+                    "{} got no response from its extension after {:.1}s",
+                    tool_call.name,
+                    dispatched.elapsed().as_secs_f64()
+                )),
+                e => ToolError::ExecutionError(e.to_string()),
+            })
         };
 
         Ok(ToolCallResult {
@@ -731,6 +1193,15 @@ impl ExtensionManager {
             .map_err(|e| anyhow::anyhow!("Failed to get prompt: {}", e))
     }
 
+    /// The config of a server found by the last search, to enable it by name
+    pub fn discovered_config(&self, name: &str) -> Option<ExtensionConfig> {
+        self.discovered
+            .lock()
+            .unwrap()
+            .get(name)
+            .map(|server| server.to_config())
+    }
+
     pub async fn search_available_extensions(&self) -> Result<Vec<Content>, ToolError> {
         let mut output_parts = vec![];
 
@@ -752,10 +1223,13 @@ impl ExtensionManager {
                     ExtensionConfig::Sse {
                         description, name, ..
                     }
+                    | ExtensionConfig::WebSocket {
+                        description, name, ..
+                    }
                     | ExtensionConfig::Stdio {
                         description, name, ..
                     } => {
-                        // For SSE/Stdio, use description if available
+                        // For remote and Stdio extensions, use description if available
                         description
                             .as_ref()
                             .map(|s| s.to_string())
@@ -769,6 +1243,31 @@ impl ExtensionManager {
             }
         }
 
+        // Running servers that are neither configured nor enabled
+        let mut discovered = discover(&configured_registries()).await;
+        discovered.retain(|server| {
+            !self.clients.contains_key(&server.name)
+                && !matches!(
+                    ExtensionConfigManager::get_config_by_name(&server.name),
+                    Ok(Some(_))
+                )
+        });
+        let discovered_extensions: Vec<String> = discovered
+            .iter()
+            .map(|server| {
+                format!(
+                    "- {} - {} (running at {})",
+                    server.name,
+                    server
+                        .description
+                        .as_deref()
+                        .unwrap_or("Discovered MCP server"),
+                    server.uri
+                )
+            })
+            .collect();
+        self.discovered.lock().unwrap().replace(discovered);
+
         // Get currently enabled extensions that can be disabled
         let enabled_extensions: Vec<String> = self.clients.keys().cloned().collect();
 
@@ -782,6 +1281,13 @@ impl ExtensionManager {
             output_parts.push("No extensions available to enable.\n".to_string());
         }
 
+        if !discovered_extensions.is_empty() {
+            output_parts.push(format!(
+                "\n\nRunning MCP servers available to enable, once the user approves:\n{}\n",
+                discovered_extensions.join("\n")
+            ));
+        }
+
         if !enabled_extensions.is_empty() {
             output_parts.push(format!(
                 "\n\nExtensions available to disable:\n{}\n",
@@ -802,6 +1308,8 @@ impl ExtensionManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::agents::extension::ToolFilter;
+    use crate::agents::extension_names::ExtensionNameError;
     use mcp_client::client::Error;
     use mcp_client::client::McpClientTrait;
     use mcp_core::protocol::{
@@ -844,6 +1352,7 @@ mod tests {
                     content: vec![],
                     is_error: None,
                 }),
+                "hang" => std::future::pending().await,
                 _ => Err(Error::NotInitialized),
             }
         }
@@ -863,6 +1372,65 @@ mod tests {
             Err(Error::NotInitialized)
         }
 
+        async fn ping(&self) -> Result<(), Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
+            mpsc::channel(1).1
+        }
+    }
+
+    /// A client that only lists tools, with every other call delegated to [`MockClient`]
+    struct ToolsClient {}
+
+    #[async_trait::async_trait]
+    impl McpClientTrait for ToolsClient {
+        async fn initialize(
+            &mut self,
+            info: ClientInfo,
+            capabilities: ClientCapabilities,
+        ) -> Result<InitializeResult, Error> {
+            MockClient {}.initialize(info, capabilities).await
+        }
+
+        async fn list_resources(
+            &self,
+            next_cursor: Option<String>,
+        ) -> Result<ListResourcesResult, Error> {
+            MockClient {}.list_resources(next_cursor).await
+        }
+
+        async fn read_resource(&self, uri: &str) -> Result<ReadResourceResult, Error> {
+            MockClient {}.read_resource(uri).await
+        }
+
+        async fn list_tools(&self, _next_cursor: Option<String>) -> Result<ListToolsResult, Error> {
+            Ok(ListToolsResult {
+                tools: vec![Tool::new("tool", "A tool", json!({}), None)],
+                next_cursor: None,
+            })
+        }
+
+        async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
+            MockClient {}.call_tool(name, arguments).await
+        }
+
+        async fn list_prompts(
+            &self,
+            next_cursor: Option<String>,
+        ) -> Result<ListPromptsResult, Error> {
+            MockClient {}.list_prompts(next_cursor).await
+        }
+
+        async fn get_prompt(&self, name: &str, arguments: Value) -> Result<GetPromptResult, Error> {
+            MockClient {}.get_prompt(name, arguments).await
+        }
+
+        async fn ping(&self) -> Result<(), Error> {
+            Ok(())
+        }
+
         async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
             mpsc::channel(1).1
         }
@@ -914,6 +1482,77 @@ mod tests {
             .is_some());
     }
 
+    #[tokio::test]
+    async fn test_extension_details_in_info() {
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager.clients.insert(
+            "test_client".to_string(),
+            Arc::new(Mutex::new(Box::new(MockClient {}))),
+        );
+        let init_result: InitializeResult = serde_json::from_value(json!({
+            "protocolVersion": "2025-03-26",
+            "capabilities": { "tools": {}, "prompts": {} },
+            "serverInfo": { "name": "test-server", "version": "1.2.3" }
+        }))
+        .unwrap();
+        extension_manager.details.insert(
+            "test_client".to_string(),
+            ExtensionDetails::from_init_result("test_client", &init_result),
+        );
+
+        let details = extension_manager.get_extension_details();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].protocol_version, "2025-03-26");
+        assert_eq!(details[0].server_version, "1.2.3");
+        assert!(details[0].supports_tools);
+        assert!(details[0].supports_prompts);
+        assert!(!details[0].supports_resources);
+
+        let info = extension_manager.get_extensions_info().await;
+        assert_eq!(
+            info[0].details.as_ref().map(|d| d.server_name.as_str()),
+            Some("test-server")
+        );
+
+        extension_manager
+            .remove_extension("test_client")
+            .await
+            .unwrap();
+        assert!(extension_manager.get_extension_details().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_past_deadline_times_out() {
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager.clients.insert(
+            "slow".to_string(),
+            Arc::new(Mutex::new(Box::new(MockClient {}))),
+        );
+        let config: ExtensionConfig = serde_json::from_value(json!({
+            "type": "stdio",
+            "name": "slow",
+            "cmd": "slow-server",
+            "args": [],
+            "tool_timeout": 0
+        }))
+        .unwrap();
+        assert_eq!(config.tool_timeout(), Some(Duration::ZERO));
+        extension_manager.configs.insert("slow".to_string(), config);
+
+        let tool_call = ToolCall::new("slow__hang", json!({}));
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await
+            .unwrap();
+        match result.result.await {
+            Err(ToolError::Timeout(message)) => {
+                assert!(message.contains("slow__hang"));
+                assert!(message.contains("deadline of 0s"));
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_dispatch_tool_call() {
         // test that dispatch_tool_call parses out the sanitized name correctly, and extracts
@@ -942,7 +1581,9 @@ mod tests {
             arguments: json!({}),
         };
 
-        let result = extension_manager.dispatch_tool_call(tool_call).await;
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await;
         assert!(result.is_ok());
 
         let tool_call = ToolCall {
@@ -950,7 +1591,9 @@ mod tests {
             arguments: json!({}),
         };
 
-        let result = extension_manager.dispatch_tool_call(tool_call).await;
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await;
         assert!(result.is_ok());
 
         // verify a multiple underscores dispatch
@@ -959,7 +1602,9 @@ mod tests {
             arguments: json!({}),
         };
 
-        let result = extension_manager.dispatch_tool_call(tool_call).await;
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await;
         assert!(result.is_ok());
 
         // Test unicode in tool name, "client 🚀" should become "client_"
@@ -968,7 +1613,9 @@ mod tests {
             arguments: json!({}),
         };
 
-        let result = extension_manager.dispatch_tool_call(tool_call).await;
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await;
         assert!(result.is_ok());
 
         let tool_call = ToolCall {
@@ -976,7 +1623,9 @@ mod tests {
             arguments: json!({}),
         };
 
-        let result = extension_manager.dispatch_tool_call(tool_call).await;
+        let result = extension_manager
+            .dispatch_tool_call(tool_call, CancellationToken::new())
+            .await;
         assert!(result.is_ok());
 
         // this should error out, specifically for an ToolError::ExecutionError
@@ -986,7 +1635,7 @@ mod tests {
         };
 
         let result = extension_manager
-            .dispatch_tool_call(invalid_tool_call)
+            .dispatch_tool_call(invalid_tool_call, CancellationToken::new())
             .await
             .unwrap()
             .result
@@ -1004,7 +1653,7 @@ mod tests {
         };
 
         let result = extension_manager
-            .dispatch_tool_call(invalid_tool_call)
+            .dispatch_tool_call(invalid_tool_call, CancellationToken::new())
             .await;
         if let Err(err) = result {
             let tool_err = err.downcast_ref::<ToolError>().expect("Expected ToolError");
@@ -1013,4 +1662,228 @@ mod tests {
             panic!("Expected ToolError::NotFound");
         }
     }
+
+    #[test]
+    fn test_get_extension_logs() {
+        let mut extension_manager = ExtensionManager::new();
+        let log = LogBuffer::new(10);
+        log.push("starting");
+        log.push("error: missing API key");
+        extension_manager
+            .logs
+            .insert(normalize("Test Server".to_string()), log);
+
+        let logs = extension_manager
+            .get_extension_logs("Test Server", 1)
+            .unwrap();
+        assert_eq!(logs, vec!["error: missing API key"]);
+        assert!(extension_manager.get_extension_logs("unknown", 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_extension_does_not_break_tool_listing() {
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager.clients.insert(
+            "healthy".to_string(),
+            Arc::new(Mutex::new(Box::new(ToolsClient {}))),
+        );
+        extension_manager.clients.insert(
+            "broken".to_string(),
+            Arc::new(Mutex::new(Box::new(MockClient {}))),
+        );
+
+        let tools = extension_manager.get_prefixed_tools(None).await.unwrap();
+        let names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["healthy__tool"]);
+
+        let degraded = extension_manager.degraded_extensions();
+        assert_eq!(degraded.len(), 1);
+        assert_eq!(degraded[0].extension, "broken");
+
+        // Asking for the failed extension alone still reports the error
+        assert!(extension_manager
+            .get_prefixed_tools(Some("broken".to_string()))
+            .await
+            .is_err());
+
+        extension_manager.remove_extension("broken").await.unwrap();
+        assert!(extension_manager.degraded_extensions().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_add_extension_rejects_invalid_and_conflicting_names() {
+        let builtin = |name: &str| ExtensionConfig::Builtin {
+            name: name.to_string(),
+            display_name: None,
+            timeout: None,
+            tool_timeout: None,
+            tool_filter: None,
+            bundled: None,
+        };
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager
+            .configs
+            .insert("client_".to_string(), builtin("client _"));
+
+        let err = extension_manager
+            .add_extension(builtin("client 🚀"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ExtensionError::InvalidName(ExtensionNameError::InvalidCharacters { .. })
+        ));
+
+        let err = extension_manager
+            .add_extension(builtin("Client_"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The extension name 'Client_' conflicts with the extension 'client _', as both are known as 'client_'. Choose another name"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_filter() {
+        let filter = ToolFilter {
+            include: vec!["get_*".to_string(), "list_?ssues".to_string()],
+            exclude: vec!["*_secret*".to_string()],
+        };
+        assert!(filter.allows("get_issue"));
+        assert!(filter.allows("list_issues"));
+        assert!(!filter.allows("get_secret_key"));
+        assert!(!filter.allows("create_issue"));
+        assert!(ToolFilter::default().allows("anything"));
+
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager.clients.insert(
+            "chatty".to_string(),
+            Arc::new(Mutex::new(Box::new(ToolsClient {}))),
+        );
+        let config: ExtensionConfig = serde_json::from_value(json!({
+            "type": "stdio",
+            "name": "chatty",
+            "cmd": "chatty-server",
+            "args": [],
+            "tool_filter": {"exclude": ["t*l"]}
+        }))
+        .unwrap();
+        extension_manager
+            .configs
+            .insert("chatty".to_string(), config);
+
+        let tools = extension_manager.get_prefixed_tools(None).await.unwrap();
+        assert!(tools.is_empty());
+
+        // A filtered out tool cannot be called either
+        let result = extension_manager
+            .dispatch_tool_call(
+                ToolCall::new("chatty__tool", json!({})),
+                CancellationToken::new(),
+            )
+            .await;
+        let err = result.err().expect("expected the call to be refused");
+        assert!(matches!(
+            err.downcast_ref::<ToolError>(),
+            Some(ToolError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_check_extension_health() {
+        let mut extension_manager = ExtensionManager::new();
+        extension_manager.clients.insert(
+            "healthy".to_string(),
+            Arc::new(Mutex::new(Box::new(ToolsClient {}))),
+        );
+        extension_manager.clients.insert(
+            "broken".to_string(),
+            Arc::new(Mutex::new(Box::new(MockClient {}))),
+        );
+        let policy = HealthCheckPolicy {
+            interval: Duration::from_secs(30),
+            ping_timeout: Duration::from_secs(1),
+            max_restarts: 2,
+        };
+
+        let statuses = extension_manager.list_extension_status();
+        assert!(statuses.iter().all(|s| s.last_checked.is_none()));
+
+        let statuses = extension_manager.check_extension_health(&policy).await;
+        assert_eq!(statuses[0].name, "broken");
+        // Without a config there is nothing to restart it from
+        assert_eq!(statuses[0].state, ExtensionState::Failed);
+        assert!(statuses[0]
+            .last_error
+            .as_ref()
+            .unwrap()
+            .contains("No config"));
+        assert_eq!(statuses[1].name, "healthy");
+        assert_eq!(statuses[1].state, ExtensionState::Healthy);
+        assert!(statuses[1].last_checked.is_some());
+
+        // An extension busy with a tool call is left alone
+        let client = extension_manager.clients["healthy"].clone();
+        let _busy = client.lock().await;
+        let checked = statuses[1].last_checked;
+        let statuses = extension_manager.check_extension_health(&policy).await;
+        assert_eq!(statuses[1].last_checked, checked);
+
+        extension_manager.remove_extension("broken").await.unwrap();
+        assert_eq!(extension_manager.list_extension_status().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reload_from_config() {
+        let config = |name: &str| ExtensionConfig::stdio(name, "true", "", 10u64);
+        let entry = |config: ExtensionConfig, enabled| ExtensionEntry { enabled, config };
+        // "deleted" is in the config file at startup and removed from it before the reload
+        let mut extension_manager = ExtensionManager::with_config_entries(&[
+            entry(config("kept"), true),
+            entry(config("disabled"), true),
+            entry(config("deleted"), true),
+        ]);
+        for name in ["kept", "disabled", "deleted", "recipe"] {
+            extension_manager.clients.insert(
+                name.to_string(),
+                Arc::new(Mutex::new(Box::new(ToolsClient {}))),
+            );
+            extension_manager
+                .configs
+                .insert(name.to_string(), config(name));
+        }
+
+        let summary = extension_manager
+            .reload_from(vec![
+                entry(config("kept"), true),
+                entry(config("disabled"), false),
+                entry(
+                    ExtensionConfig::stdio("broken", "goose-test-no-such-command", "", 10u64),
+                    true,
+                ),
+            ])
+            .await
+            .unwrap();
+
+        assert_eq!(summary.removed, vec!["deleted", "disabled"]);
+        assert!(summary.added.is_empty());
+        assert!(summary.restarted.is_empty());
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].extension, "broken");
+        // The recipe's extension was never in the config file
+        let mut running = extension_manager.list_extensions().await.unwrap();
+        running.sort();
+        assert_eq!(running, vec!["kept", "recipe"]);
+
+        // Nothing changed on disk, so nothing changes
+        let summary = extension_manager
+            .reload_from(vec![
+                entry(config("kept"), true),
+                entry(config("disabled"), false),
+            ])
+            .await
+            .unwrap();
+        assert!(summary.is_empty());
+    }
 }
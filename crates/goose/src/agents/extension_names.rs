@@ -0,0 +1,200 @@
+//! Extension names and their normalized form
+//!
+//! Goose knows an extension by its normalized name, which prefixes the extension's tools: the
+//! name in lowercase, without whitespace, and with any character other than ASCII letters,
+//! digits, `_` and `-` replaced by `_`. Normalizing is lossy, so different names can end up the
+//! same, as `client 🚀` and `client _` both become `client_`, and the tools of the two
+//! extensions would then be mixed up. Names are checked instead of silently transformed: a name
+//! with characters that normalizing would replace is rejected, apart from the `.` that
+//! registries use in server names such as `io.github.jira`, and so is a name that normalizes to
+//! the name of an extension that is already there.
+//!
+//! Configs written before names were checked can still hold such names. They are migrated once
+//! at startup, by `ExtensionConfigManager::migrate_names`: each is renamed to its normalized
+//! name, with a numeric suffix when that is taken, its tool permissions are carried over, and
+//! the rename is logged.
+use std::collections::{HashMap, HashSet};
+
+use thiserror::Error;
+
+use super::extension_manager::normalize;
+use crate::config::extensions::{name_to_key, ExtensionEntry};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExtensionNameError {
+    #[error("Extension names cannot be empty")]
+    Empty,
+    #[error(
+        "The extension name '{name}' contains {invalid}. Use only letters, digits, spaces, '.', '_' and '-'"
+    )]
+    InvalidCharacters { name: String, invalid: String },
+    #[error(
+        "The extension name '{name}' conflicts with the extension '{existing}', as both are known as '{normalized}'. Choose another name"
+    )]
+    Conflict {
+        name: String,
+        existing: String,
+        normalized: String,
+    },
+}
+
+fn is_valid_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.'
+}
+
+/// Check that a name keeps its meaning when normalized, apart from case and whitespace
+pub fn validate_name(name: &str) -> Result<(), ExtensionNameError> {
+    if name.trim().is_empty() {
+        return Err(ExtensionNameError::Empty);
+    }
+    let invalid: Vec<String> = name
+        .chars()
+        .filter(|c| !is_valid_char(*c) && !c.is_whitespace())
+        .map(|c| format!("'{}'", c))
+        .collect();
+    if invalid.is_empty() {
+        return Ok(());
+    }
+    Err(ExtensionNameError::InvalidCharacters {
+        name: name.to_string(),
+        invalid: invalid.join(", "),
+    })
+}
+
+/// Check that no other extension is known by the same normalized name. A name equal to one of
+/// `existing` is the same extension, as when its config is updated.
+pub fn check_unique<'a>(
+    name: &str,
+    existing: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ExtensionNameError> {
+    let normalized = normalize(name.to_string());
+    match existing
+        .into_iter()
+        .filter(|other| *other != name)
+        .find(|other| normalize(other.to_string()) == normalized)
+    {
+        Some(other) => Err(ExtensionNameError::Conflict {
+            name: name.to_string(),
+            existing: other.to_string(),
+            normalized,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// The normalized name, with the lowest numeric suffix that makes it unique
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    let normalized = normalize(name.to_string());
+    if !taken.contains(&normalized) {
+        return normalized;
+    }
+    (2..)
+        .map(|n| format!("{}_{}", normalized, n))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("a free suffix")
+}
+
+/// Rename the configured extensions whose names are invalid or collide with another, keyed by
+/// the key of their new name. Valid names are kept first, in key order, so an extension only
+/// gets a new name when an older config let it in. Returns the renames made, old name first.
+pub fn migrate_names(
+    extensions: HashMap<String, ExtensionEntry>,
+) -> (HashMap<String, ExtensionEntry>, Vec<(String, String)>) {
+    let mut entries: Vec<(String, ExtensionEntry)> = extensions.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let (valid, invalid): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|(_, entry)| validate_name(&entry.config.name()).is_ok());
+
+    let mut taken = HashSet::new();
+    let mut migrated = HashMap::new();
+    let mut renames = Vec::new();
+    for (key, mut entry) in valid.into_iter().chain(invalid) {
+        let name = entry.config.name();
+        let normalized = normalize(name.clone());
+        if validate_name(&name).is_ok() && !taken.contains(&normalized) {
+            taken.insert(normalized);
+            migrated.insert(key, entry);
+            continue;
+        }
+        let renamed = unique_name(&name, &taken);
+        taken.insert(renamed.clone());
+        entry.config.rename(renamed.clone());
+        migrated.insert(name_to_key(&renamed), entry);
+        renames.push((name, renamed));
+    }
+    (migrated, renames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::ExtensionConfig;
+
+    fn entry(name: &str) -> ExtensionEntry {
+        ExtensionEntry {
+            enabled: true,
+            config: ExtensionConfig::Builtin {
+                name: name.to_string(),
+                display_name: None,
+                timeout: None,
+                tool_timeout: None,
+                tool_filter: None,
+                bundled: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_names_are_validated() {
+        assert!(validate_name("Test Server").is_ok());
+        assert!(validate_name("my-tools_2").is_ok());
+        assert!(validate_name("io.github.jira").is_ok());
+        assert_eq!(validate_name("  "), Err(ExtensionNameError::Empty));
+        let err = validate_name("client 🚀").unwrap_err();
+        assert!(err.to_string().contains("'🚀'"));
+    }
+
+    #[test]
+    fn test_names_that_normalize_alike_conflict() {
+        let existing = ["developer", "client _"];
+        assert!(check_unique("memory", existing).is_ok());
+        // Updating the config of an extension is not a conflict with itself
+        assert!(check_unique("client _", existing).is_ok());
+        assert_eq!(
+            check_unique("Client_", existing),
+            Err(ExtensionNameError::Conflict {
+                name: "Client_".to_string(),
+                existing: "client _".to_string(),
+                normalized: "client_".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_and_colliding_names_are_migrated() {
+        let extensions = HashMap::from([
+            (name_to_key("client 🚀"), entry("client 🚀")),
+            (name_to_key("client 🎉"), entry("client 🎉")),
+            (name_to_key("client _"), entry("client _")),
+            (name_to_key("GitHub"), entry("GitHub")),
+        ]);
+        let (migrated, renames) = migrate_names(extensions);
+
+        // The valid names are kept, and the invalid ones get the next free suffix in key order
+        assert_eq!(
+            renames,
+            vec![
+                ("client 🎉".to_string(), "client__2".to_string()),
+                ("client 🚀".to_string(), "client__3".to_string()),
+            ]
+        );
+        let mut keys: Vec<&String> = migrated.keys().collect();
+        keys.sort();
+        assert_eq!(keys, vec!["client_", "client__2", "client__3", "github"]);
+        assert_eq!(migrated["client__3"].config.name(), "client__3");
+
+        let (_, renames) = migrate_names(migrated);
+        assert!(renames.is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! Running extension prompts as conversation context
+//!
+//! MCP prompts are templates an extension fills in with arguments, returning a short
+//! conversation. [`Agent::run_extension_prompt`] fetches one and turns it into messages for the
+//! session: consecutive parts with the same role become one message, and
+//! [`append_prompt_messages`] merges the first of them into the end of the conversation when
+//! their roles match, so the session keeps alternating between user and assistant.
+use anyhow::{anyhow, Result};
+use mcp_core::prompt::PromptMessage;
+use serde_json::Value;
+
+use crate::message::Message;
+
+use super::Agent;
+
+/// The messages of a prompt, with consecutive parts of the same role merged into one message
+pub fn prompt_messages(parts: Vec<PromptMessage>) -> Vec<Message> {
+    let mut messages: Vec<Message> = Vec::new();
+    for part in parts {
+        let message = Message::from(part);
+        match messages.last_mut() {
+            Some(last) if last.role == message.role => last.content.extend(message.content),
+            _ => messages.push(message),
+        }
+    }
+    messages
+}
+
+/// Add the messages of a prompt to a conversation, merging a first message into the last one of
+/// the conversation when they have the same role
+pub fn append_prompt_messages(conversation: &mut Vec<Message>, messages: Vec<Message>) {
+    for message in messages {
+        match conversation.last_mut() {
+            Some(last) if last.role == message.role => last.content.extend(message.content),
+            _ => conversation.push(message),
+        }
+    }
+}
+
+impl Agent {
+    /// Fetch a prompt from an extension with the given arguments and return its messages, ready
+    /// to be added to the session with [`append_prompt_messages`]
+    pub async fn run_extension_prompt(
+        &self,
+        extension: &str,
+        name: &str,
+        arguments: Value,
+    ) -> Result<Vec<Message>> {
+        let extension_manager = self.extension_manager.lock().await;
+        let prompt = extension_manager
+            .list_prompts_from_extension(extension)
+            .await
+            .map_err(|e| anyhow!("{}", e))?
+            .into_iter()
+            .find(|prompt| prompt.name == name)
+            .ok_or_else(|| anyhow!("Extension {} has no prompt '{}'", extension, name))?;
+
+        let missing: Vec<&str> = prompt
+            .arguments
+            .iter()
+            .flatten()
+            .filter(|argument| argument.required == Some(true))
+            .filter(|argument| {
+                arguments
+                    .get(&argument.name)
+                    .is_none_or(|value| value.is_null() || value.as_str() == Some(""))
+            })
+            .map(|argument| argument.name.as_str())
+            .collect();
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Prompt '{}' requires the arguments: {}",
+                name,
+                missing.join(", ")
+            ));
+        }
+
+        let result = extension_manager
+            .get_prompt(extension, name, arguments)
+            .await?;
+        let messages = prompt_messages(result.messages);
+        if messages.is_empty() {
+            return Err(anyhow!("Prompt '{}' returned no messages", name));
+        }
+        Ok(messages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::prompt::PromptMessageRole;
+    use mcp_core::Role;
+
+    #[test]
+    fn test_prompt_messages_are_merged_by_role() {
+        let messages = prompt_messages(vec![
+            PromptMessage::new_text(PromptMessageRole::User, "Review this diff."),
+            PromptMessage::new_text(PromptMessageRole::User, "diff --git a/lib.rs b/lib.rs"),
+            PromptMessage::new_text(PromptMessageRole::Assistant, "What should I focus on?"),
+            PromptMessage::new_text(PromptMessageRole::User, "Error handling."),
+        ]);
+        let roles: Vec<&Role> = messages.iter().map(|m| &m.role).collect();
+        assert_eq!(roles, vec![&Role::User, &Role::Assistant, &Role::User]);
+        assert_eq!(messages[0].content.len(), 2);
+
+        let mut conversation = vec![
+            Message::user().with_text("Hi"),
+            Message::assistant().with_text("Hello! What can I do?"),
+        ];
+        append_prompt_messages(
+            &mut conversation,
+            prompt_messages(vec![
+                PromptMessage::new_text(PromptMessageRole::Assistant, "I can review code."),
+                PromptMessage::new_text(PromptMessageRole::User, "Review lib.rs."),
+            ]),
+        );
+        assert_eq!(conversation.len(), 3);
+        assert_eq!(
+            conversation[1].as_concat_text(),
+            "Hello! What can I do?\nI can review code."
+        );
+        assert_eq!(conversation[2].role, Role::User);
+    }
+}
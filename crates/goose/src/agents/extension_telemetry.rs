@@ -0,0 +1,269 @@
+//! Per-extension tool call latency and error rates
+//!
+//! Every tool call dispatched to an extension is timed and counted as a success, an error or
+//! a timeout. Extensions whose share of failed calls is over `GOOSE_EXTENSION_ERROR_BUDGET`
+//! (0.2 by default) are reported as unhealthy, and a compact summary is added to the end of
+//! the latest message sent to the model so it can prefer a healthy alternative. The numbers
+//! change with every call, so they are kept out of the system prompt to leave it cacheable.
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use mcp_core::Role;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+const DEFAULT_ERROR_BUDGET: f64 = 0.2;
+/// Extensions are not judged on fewer calls than this
+const MIN_CALLS_FOR_HEALTH: u64 = 5;
+/// Latencies of the most recent calls kept per extension
+const LATENCY_WINDOW: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallOutcome {
+    Success,
+    Error,
+    Timeout,
+}
+
+#[derive(Debug, Default)]
+struct ExtensionStats {
+    calls: u64,
+    errors: u64,
+    timeouts: u64,
+    latencies_ms: VecDeque<u64>,
+}
+
+impl ExtensionStats {
+    fn latency_percentile(&self, percentile: f64) -> Option<u64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<u64> = self.latencies_ms.iter().copied().collect();
+        sorted.sort_unstable();
+        let index = ((sorted.len() - 1) as f64 * percentile).round() as usize;
+        Some(sorted[index])
+    }
+}
+
+/// Call statistics of one extension
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ExtensionHealth {
+    pub name: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    /// Share of calls that failed or timed out
+    pub error_rate: f64,
+    pub p50_latency_ms: Option<u64>,
+    pub p95_latency_ms: Option<u64>,
+    /// Whether the error rate is within the error budget
+    pub healthy: bool,
+}
+
+impl ExtensionHealth {
+    /// A one line summary for the model
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} calls, {:.0}% failed",
+            self.calls,
+            self.error_rate * 100.0
+        );
+        if self.timeouts > 0 {
+            summary.push_str(&format!(", {} timed out", self.timeouts));
+        }
+        if let Some(p50) = self.p50_latency_ms {
+            summary.push_str(&format!(", typically {}ms", p50));
+        }
+        if !self.healthy {
+            summary.push_str(". Unhealthy: prefer an equivalent tool from another extension");
+        }
+        summary
+    }
+}
+
+/// Call statistics for each extension since it was added
+#[derive(Debug)]
+pub struct ExtensionTelemetry {
+    error_budget: f64,
+    extensions: HashMap<String, ExtensionStats>,
+}
+
+impl Default for ExtensionTelemetry {
+    fn default() -> Self {
+        Self::new(DEFAULT_ERROR_BUDGET)
+    }
+}
+
+impl ExtensionTelemetry {
+    pub fn new(error_budget: f64) -> Self {
+        Self {
+            error_budget,
+            extensions: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_EXTENSION_ERROR_BUDGET")
+                .unwrap_or(DEFAULT_ERROR_BUDGET),
+        )
+    }
+
+    pub fn record(&mut self, extension: &str, latency: Duration, outcome: CallOutcome) {
+        let stats = self.extensions.entry(extension.to_string()).or_default();
+        stats.calls += 1;
+        match outcome {
+            CallOutcome::Success => {}
+            CallOutcome::Error => stats.errors += 1,
+            CallOutcome::Timeout => stats.timeouts += 1,
+        }
+        stats.latencies_ms.push_back(latency.as_millis() as u64);
+        if stats.latencies_ms.len() > LATENCY_WINDOW {
+            stats.latencies_ms.pop_front();
+        }
+    }
+
+    /// Forget an extension's statistics, e.g. when it is removed
+    pub fn remove(&mut self, extension: &str) {
+        self.extensions.remove(extension);
+    }
+
+    pub fn health(&self, extension: &str) -> Option<ExtensionHealth> {
+        let stats = self.extensions.get(extension)?;
+        let error_rate = (stats.errors + stats.timeouts) as f64 / stats.calls as f64;
+        Some(ExtensionHealth {
+            name: extension.to_string(),
+            calls: stats.calls,
+            errors: stats.errors,
+            timeouts: stats.timeouts,
+            error_rate,
+            p50_latency_ms: stats.latency_percentile(0.5),
+            p95_latency_ms: stats.latency_percentile(0.95),
+            healthy: stats.calls < MIN_CALLS_FOR_HEALTH || error_rate <= self.error_budget,
+        })
+    }
+
+    /// Health of every extension that has been called, sorted by name
+    pub fn all_health(&self) -> Vec<ExtensionHealth> {
+        let mut health: Vec<ExtensionHealth> = self
+            .extensions
+            .keys()
+            .filter_map(|name| self.health(name))
+            .collect();
+        health.sort_by(|a, b| a.name.cmp(&b.name));
+        health
+    }
+}
+
+/// The health of the extensions that have been called, for the end of the latest message
+pub fn health_note(health: &[ExtensionHealth]) -> Option<String> {
+    if health.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = health
+        .iter()
+        .map(|h| format!("- {}: {}", h.name, h.summary()))
+        .collect();
+    Some(format!(
+        "Tool calls to extensions so far:\n{}",
+        lines.join("\n")
+    ))
+}
+
+/// Add the note to the end of the conversation sent to the provider: to the last message when
+/// it is the user's, or as a message of its own after the assistant's
+pub fn with_health_note<'a>(
+    history: Cow<'a, [Message]>,
+    note: Option<String>,
+) -> Cow<'a, [Message]> {
+    let Some(note) = note else {
+        return history;
+    };
+    let mut history = history.into_owned();
+    match history.last_mut() {
+        Some(last) if last.role == Role::User => last.content.push(MessageContent::text(note)),
+        _ => history.push(Message::user().with_text(note)),
+    }
+    Cow::Owned(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_tracks_errors_and_latency() {
+        let mut telemetry = ExtensionTelemetry::new(0.2);
+        for ms in [10, 20, 30, 40] {
+            telemetry.record("developer", Duration::from_millis(ms), CallOutcome::Success);
+        }
+        telemetry.record(
+            "developer",
+            Duration::from_millis(5000),
+            CallOutcome::Timeout,
+        );
+
+        let health = telemetry.health("developer").unwrap();
+        assert_eq!(health.calls, 5);
+        assert_eq!(health.timeouts, 1);
+        assert_eq!(health.p50_latency_ms, Some(30));
+        assert_eq!(health.p95_latency_ms, Some(5000));
+        assert!(health.healthy);
+        assert_eq!(
+            health.summary(),
+            "5 calls, 20% failed, 1 timed out, typically 30ms"
+        );
+
+        telemetry.record("developer", Duration::from_millis(10), CallOutcome::Error);
+        let health = telemetry.health("developer").unwrap();
+        assert!(!health.healthy);
+        assert!(health
+            .summary()
+            .ends_with("prefer an equivalent tool from another extension"));
+    }
+
+    #[test]
+    fn test_few_calls_are_not_judged() {
+        let mut telemetry = ExtensionTelemetry::new(0.2);
+        telemetry.record("github", Duration::from_millis(10), CallOutcome::Error);
+        assert!(telemetry.health("github").unwrap().healthy);
+        assert!(telemetry.health("jira").is_none());
+
+        telemetry.remove("github");
+        assert!(telemetry.all_health().is_empty());
+    }
+
+    #[test]
+    fn test_health_note_ends_the_latest_user_message() {
+        let mut telemetry = ExtensionTelemetry::new(0.2);
+        telemetry.record("developer", Duration::from_millis(10), CallOutcome::Success);
+        let note = health_note(&telemetry.all_health());
+        assert_eq!(
+            note.as_deref(),
+            Some(
+                "Tool calls to extensions so far:\n- developer: 1 calls, 0% failed, typically 10ms"
+            )
+        );
+
+        let history = vec![Message::user().with_text("list my files")];
+        let with_note = with_health_note(Cow::Borrowed(&history), note.clone());
+        assert_eq!(with_note.len(), 1);
+        assert_eq!(with_note[0].content.len(), 2);
+
+        let history = vec![Message::assistant().with_text("Done")];
+        let with_note = with_health_note(Cow::Borrowed(&history), note);
+        assert_eq!(with_note.len(), 2);
+        assert_eq!(with_note[1].role, Role::User);
+
+        assert!(health_note(&[]).is_none());
+        assert!(matches!(
+            with_health_note(Cow::Borrowed(&history), None),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,166 @@
+//! Scripted frontend tool responders for exercising the frontend tool path without a live frontend.
+//!
+//! Frontend tools are executed outside of the agent: the reply stream yields a
+//! `FrontendToolRequest` and then waits for the result to come back through
+//! [`Agent::handle_tool_result`]. The harness plays the part of the frontend by
+//! acknowledging those requests and answering them with scripted results.
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::StreamExt;
+use mcp_core::{Content, Tool, ToolCall, ToolError, ToolResult};
+use serde_json::Value;
+
+use crate::agents::{Agent, AgentEvent, ExtensionConfig, SessionConfig};
+use crate::message::{Message, MessageContent};
+
+type Responder = Arc<dyn Fn(&Value) -> ToolResult<Vec<Content>> + Send + Sync>;
+
+/// Answers frontend tool requests on behalf of a frontend
+#[derive(Clone, Default)]
+pub struct FrontendToolHarness {
+    tools: Vec<Tool>,
+    responders: HashMap<String, Responder>,
+    calls: Arc<Mutex<Vec<ToolCall>>>,
+}
+
+impl FrontendToolHarness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool whose result is computed from the call arguments
+    pub fn with_responder<F>(mut self, tool: Tool, responder: F) -> Self
+    where
+        F: Fn(&Value) -> ToolResult<Vec<Content>> + Send + Sync + 'static,
+    {
+        self.responders
+            .insert(tool.name.clone(), Arc::new(responder));
+        self.tools.push(tool);
+        self
+    }
+
+    /// Register a tool that always answers with the same result
+    pub fn with_response(self, tool: Tool, result: ToolResult<Vec<Content>>) -> Self {
+        self.with_responder(tool, move |_| result.clone())
+    }
+
+    /// Register a tool that answers with each scripted result in turn, failing once exhausted
+    pub fn with_script(self, tool: Tool, results: Vec<ToolResult<Vec<Content>>>) -> Self {
+        let name = tool.name.clone();
+        let script = Arc::new(Mutex::new(VecDeque::from(results)));
+        self.with_responder(tool, move |_| {
+            script.lock().unwrap().pop_front().unwrap_or_else(|| {
+                Err(ToolError::ExecutionError(format!(
+                    "No scripted responses left for '{}'",
+                    name
+                )))
+            })
+        })
+    }
+
+    /// The frontend extension that exposes the scripted tools to an agent
+    pub fn extension(&self, name: &str) -> ExtensionConfig {
+        ExtensionConfig::Frontend {
+            name: name.to_string(),
+            tools: self.tools.clone(),
+            instructions: None,
+            bundled: None,
+        }
+    }
+
+    /// Register the scripted tools with the agent as a frontend extension
+    pub async fn install(&self, agent: &Agent) -> Result<()> {
+        agent.add_extension(self.extension("frontend")).await?;
+        Ok(())
+    }
+
+    /// Every frontend tool call answered so far, in order
+    pub fn calls(&self) -> Vec<ToolCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Produce the result for a frontend tool call, recording the call
+    pub fn respond(&self, tool_call: &ToolCall) -> ToolResult<Vec<Content>> {
+        self.calls.lock().unwrap().push(tool_call.clone());
+        match self.responders.get(&tool_call.name) {
+            Some(responder) => responder(&tool_call.arguments),
+            None => Err(ToolError::NotFound(format!(
+                "No responder registered for frontend tool '{}'",
+                tool_call.name
+            ))),
+        }
+    }
+
+    /// Run a reply to completion, answering any frontend tool requests along the way.
+    /// Returns every event yielded by the reply stream.
+    pub async fn run(
+        &self,
+        agent: &Agent,
+        messages: &[Message],
+        session: Option<SessionConfig>,
+    ) -> Result<Vec<AgentEvent>> {
+        let mut stream = agent.reply(messages, session).await?;
+        let mut events = Vec::new();
+
+        while let Some(event) = stream.next().await {
+            let event = event?;
+            if let AgentEvent::Message(message) = &event {
+                for content in &message.content {
+                    if let MessageContent::FrontendToolRequest(request) = content {
+                        agent.handle_tool_received(request.id.clone()).await;
+                        let result = match &request.tool_call {
+                            Ok(tool_call) => self.respond(tool_call),
+                            Err(e) => Err(e.clone()),
+                        };
+                        agent.handle_tool_result(request.id.clone(), result).await;
+                    }
+                }
+            }
+            events.push(event);
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str) -> Tool {
+        Tool::new(name, "test tool", json!({"type": "object"}), None)
+    }
+
+    #[test]
+    fn test_script_is_consumed_in_order() {
+        let harness = FrontendToolHarness::new().with_script(
+            tool("click"),
+            vec![
+                Ok(vec![Content::text("first")]),
+                Ok(vec![Content::text("second")]),
+            ],
+        );
+        let call = ToolCall::new("click", json!({}));
+
+        assert_eq!(
+            harness.respond(&call).unwrap(),
+            vec![Content::text("first")]
+        );
+        assert_eq!(
+            harness.respond(&call).unwrap(),
+            vec![Content::text("second")]
+        );
+        assert!(harness.respond(&call).is_err());
+        assert_eq!(harness.calls().len(), 3);
+    }
+
+    #[test]
+    fn test_unknown_tool_is_not_found() {
+        let harness = FrontendToolHarness::new();
+        let result = harness.respond(&ToolCall::new("missing", json!({})));
+        assert!(matches!(result, Err(ToolError::NotFound(_))));
+    }
+}
@@ -0,0 +1,267 @@
+//! Receipts and progress for frontend tool calls
+//!
+//! A frontend tool is run by the frontend, so the agent sends the call and waits for its
+//! result to come back through [`Agent::handle_tool_result`]. A frontend can also report that
+//! it received the call with [`Agent::handle_tool_received`], and how far along it is with
+//! [`Agent::handle_tool_progress`]. Progress is passed on as a `notifications/progress`
+//! notification for the call, as the progress of extension tools is.
+//!
+//! With timeouts set, a call the frontend never acknowledges, or one that goes quiet after it
+//! was acknowledged, fails with a timeout error that says which of the two happened, rather
+//! than the reply waiting forever. Any progress or result counts as an acknowledgment, and
+//! each update restarts the wait for the next. Both timeouts are in seconds and off by
+//! default, as frontends that do not send receipts would otherwise time out:
+//!
+//! ```yaml
+//! GOOSE_FRONTEND_TOOL_ACK_TIMEOUT: 10
+//! GOOSE_FRONTEND_TOOL_TIMEOUT: 300
+//! ```
+use std::time::Duration;
+
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcNotification};
+use mcp_core::{Content, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use super::types::ToolResultReceiver;
+use super::Agent;
+use crate::config::Config;
+
+/// How far along the frontend is with a tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FrontendToolProgress {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: Option<String>,
+}
+
+impl FrontendToolProgress {
+    /// The progress as the notification extension tools report theirs with
+    pub fn notification(&self, request_id: &str) -> JsonRpcMessage {
+        JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: "notifications/progress".to_string(),
+            params: Some(json!({
+                "progressToken": request_id,
+                "progress": self.progress,
+                "total": self.total,
+                "message": self.message,
+            })),
+        })
+    }
+}
+
+/// What a frontend sends back about a tool call
+#[derive(Debug, Clone)]
+pub enum FrontendToolUpdate {
+    Received,
+    Progress(FrontendToolProgress),
+    Result(ToolResult<Vec<Content>>),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrontendToolTimeouts {
+    /// How long the frontend has to acknowledge a call
+    pub ack: Option<Duration>,
+    /// How long an acknowledged call can go without progress or a result
+    pub result: Option<Duration>,
+}
+
+impl FrontendToolTimeouts {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let seconds = |key: &str| {
+            config
+                .get_param::<u64>(key)
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs)
+        };
+        Self {
+            ack: seconds("GOOSE_FRONTEND_TOOL_ACK_TIMEOUT"),
+            result: seconds("GOOSE_FRONTEND_TOOL_TIMEOUT"),
+        }
+    }
+
+    fn error(timeout: Duration, tool_name: &str, received: bool) -> ToolError {
+        if received {
+            ToolError::Timeout(format!(
+                "The frontend received the call to '{}' but sent no progress or result for {}s. \
+                 It may still be running in the frontend; check the frontend before calling the \
+                 tool again.",
+                tool_name,
+                timeout.as_secs()
+            ))
+        } else {
+            ToolError::Timeout(format!(
+                "The frontend did not acknowledge the call to '{}' within {}s, so it most likely \
+                 never received it. Check that the frontend is connected and provides this tool.",
+                tool_name,
+                timeout.as_secs()
+            ))
+        }
+    }
+}
+
+/// What came of waiting for the frontend
+#[derive(Debug)]
+pub(super) enum Waited {
+    Update(FrontendToolUpdate),
+    TimedOut(ToolError),
+    /// The channel closed, so no update can come
+    Closed,
+}
+
+/// Wait for the next update on a frontend tool call, skipping updates left over from calls
+/// that are no longer waited on
+pub(super) async fn next_update(
+    receiver: &ToolResultReceiver,
+    request_id: &str,
+    tool_name: &str,
+    timeouts: FrontendToolTimeouts,
+    received: bool,
+) -> Waited {
+    let mut receiver = receiver.lock().await;
+    let next = async {
+        loop {
+            match receiver.recv().await {
+                Some((id, update)) if id == request_id => return Some(update),
+                Some((id, _)) => {
+                    tracing::warn!(
+                        "Ignoring an update for frontend tool call {} while waiting on {}",
+                        id,
+                        request_id
+                    );
+                }
+                None => return None,
+            }
+        }
+    };
+    let timeout = if received {
+        timeouts.result
+    } else {
+        timeouts.ack
+    };
+    let update = match timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, next).await {
+            Ok(update) => update,
+            Err(_) => {
+                return Waited::TimedOut(FrontendToolTimeouts::error(timeout, tool_name, received))
+            }
+        },
+        None => next.await,
+    };
+    match update {
+        Some(update) => Waited::Update(update),
+        None => Waited::Closed,
+    }
+}
+
+impl Agent {
+    async fn send_tool_update(&self, id: String, update: FrontendToolUpdate) {
+        if let Err(e) = self.tool_result_tx.send((id, update)).await {
+            tracing::error!("Failed to send frontend tool update: {}", e);
+        }
+    }
+
+    pub async fn handle_tool_result(&self, id: String, result: ToolResult<Vec<Content>>) {
+        self.send_tool_update(id, FrontendToolUpdate::Result(result))
+            .await;
+    }
+
+    /// Acknowledge that the frontend received a tool call
+    pub async fn handle_tool_received(&self, id: String) {
+        self.send_tool_update(id, FrontendToolUpdate::Received)
+            .await;
+    }
+
+    /// Report the progress of a frontend tool call
+    pub async fn handle_tool_progress(&self, id: String, progress: FrontendToolProgress) {
+        self.send_tool_update(id, FrontendToolUpdate::Progress(progress))
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::sync::{mpsc, Mutex};
+
+    fn channel() -> (
+        mpsc::Sender<(String, FrontendToolUpdate)>,
+        ToolResultReceiver,
+    ) {
+        let (tx, rx) = mpsc::channel(8);
+        (tx, Arc::new(Mutex::new(rx)))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeouts_tell_ack_from_result() {
+        let (tx, rx) = channel();
+        let timeouts = FrontendToolTimeouts {
+            ack: Some(Duration::from_secs(5)),
+            result: Some(Duration::from_secs(60)),
+        };
+
+        let Waited::TimedOut(error) = next_update(&rx, "req_1", "click", timeouts, false).await
+        else {
+            panic!("expected the call to time out");
+        };
+        assert!(error.to_string().contains("did not acknowledge"));
+
+        // A stale update for another call is skipped
+        tx.send(("req_0".to_string(), FrontendToolUpdate::Received))
+            .await
+            .unwrap();
+        tx.send(("req_2".to_string(), FrontendToolUpdate::Received))
+            .await
+            .unwrap();
+        assert!(matches!(
+            next_update(&rx, "req_2", "click", timeouts, false).await,
+            Waited::Update(FrontendToolUpdate::Received)
+        ));
+        let Waited::TimedOut(error) = next_update(&rx, "req_2", "click", timeouts, true).await
+        else {
+            panic!("expected the call to time out");
+        };
+        assert!(matches!(error, ToolError::Timeout(_)));
+        assert!(error.to_string().contains("no progress or result for 60s"));
+    }
+
+    #[tokio::test]
+    async fn test_updates_without_timeouts() {
+        let (tx, rx) = channel();
+        let progress = FrontendToolProgress {
+            progress: 1.0,
+            total: Some(4.0),
+            message: Some("Uploading".to_string()),
+        };
+        tx.send((
+            "req_1".to_string(),
+            FrontendToolUpdate::Progress(progress.clone()),
+        ))
+        .await
+        .unwrap();
+        let timeouts = FrontendToolTimeouts::default();
+        let Waited::Update(FrontendToolUpdate::Progress(received)) =
+            next_update(&rx, "req_1", "upload", timeouts, false).await
+        else {
+            panic!("expected progress");
+        };
+        assert_eq!(received, progress);
+
+        let JsonRpcMessage::Notification(notification) = progress.notification("req_1") else {
+            panic!("expected a notification");
+        };
+        assert_eq!(notification.method, "notifications/progress");
+        assert_eq!(notification.params.unwrap()["progressToken"], "req_1");
+
+        drop(tx);
+        assert!(matches!(
+            next_update(&rx, "req_1", "upload", timeouts, true).await,
+            Waited::Closed
+        ));
+    }
+}
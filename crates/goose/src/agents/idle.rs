@@ -0,0 +1,233 @@
+//! Releasing resources while an agent is idle
+//!
+//! Long-lived embedded agents keep their extension processes and tool index around between
+//! replies. Once an agent has been idle for `GOOSE_IDLE_TIMEOUT` seconds, the idle monitor
+//! stops its extensions, keeping their configs, and drops the router's vector index. Both
+//! are re-established when the next reply arrives. An agent is never idle while a reply is in
+//! flight, however long its provider calls and tool calls take.
+//!
+//! ```yaml
+//! GOOSE_IDLE_TIMEOUT: 900
+//! GOOSE_IDLE_SUSPEND_EXTENSIONS: true
+//! GOOSE_IDLE_RELEASE_INDEX: true
+//! ```
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+
+use super::extension::ExtensionConfig;
+use super::Agent;
+
+// Longest time between idle checks, so shorter timeouts are still honoured promptly
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdlePolicy {
+    /// How long the agent must go without a reply before it is suspended
+    pub timeout: Duration,
+    /// Stop extension processes, starting them again on the next reply
+    pub suspend_extensions: bool,
+    /// Drop the router tool index, rebuilding it on the next reply
+    pub release_index: bool,
+}
+
+impl IdlePolicy {
+    /// The configured policy, or None if idle suspension is disabled
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let timeout = config.get_param::<u64>("GOOSE_IDLE_TIMEOUT").ok()?;
+        Some(Self {
+            timeout: Duration::from_secs(timeout),
+            suspend_extensions: config
+                .get_param("GOOSE_IDLE_SUSPEND_EXTENSIONS")
+                .unwrap_or(true),
+            release_index: config.get_param("GOOSE_IDLE_RELEASE_INDEX").unwrap_or(true),
+        })
+    }
+
+    fn check_interval(&self) -> Duration {
+        (self.timeout / 2).clamp(Duration::from_secs(1), MAX_CHECK_INTERVAL)
+    }
+}
+
+/// What was released when the agent was suspended
+#[derive(Debug, Default)]
+struct Suspended {
+    extensions: Vec<ExtensionConfig>,
+    index_released: bool,
+}
+
+#[derive(Debug)]
+pub struct IdleState {
+    last_active: Instant,
+    suspended: Option<Suspended>,
+}
+
+impl Default for IdleState {
+    fn default() -> Self {
+        Self {
+            last_active: Instant::now(),
+            suspended: None,
+        }
+    }
+}
+
+/// Held for as long as a reply is in flight, so the agent is not suspended under it
+pub(super) struct ActivityGuard<'a>(&'a AtomicUsize);
+
+impl Drop for ActivityGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Agent {
+    pub async fn is_suspended(&self) -> bool {
+        self.idle_state.lock().await.suspended.is_some()
+    }
+
+    /// Suspend the agent if it has been idle for longer than the configured policy allows.
+    /// Returns whether the agent was suspended.
+    pub async fn suspend_if_idle(&self) -> Result<bool> {
+        match IdlePolicy::from_config() {
+            Some(policy) => self.suspend_if_idle_with(&policy).await,
+            None => Ok(false),
+        }
+    }
+
+    pub async fn suspend_if_idle_with(&self, policy: &IdlePolicy) -> Result<bool> {
+        let mut state = self.idle_state.lock().await;
+        // The idle time of an agent with replies in flight starts once they are done
+        if self.replies_in_flight.load(Ordering::SeqCst) > 0 {
+            state.last_active = Instant::now();
+            return Ok(false);
+        }
+        if state.suspended.is_some() || state.last_active.elapsed() < policy.timeout {
+            return Ok(false);
+        }
+
+        let mut suspended = Suspended::default();
+        if policy.suspend_extensions {
+            suspended.extensions = self
+                .extension_manager
+                .lock()
+                .await
+                .suspend_extensions()
+                .await?;
+        }
+        if policy.release_index {
+            suspended.index_released = self.router_tool_selector.lock().await.take().is_some();
+            self.workspace_routers.lock().await.clear();
+        }
+
+        tracing::info!(
+            extensions = suspended.extensions.len(),
+            index_released = suspended.index_released,
+            "Suspended idle agent"
+        );
+        state.suspended = Some(suspended);
+        Ok(true)
+    }
+
+    /// Keep the agent from being suspended until the guard is dropped
+    pub(super) fn hold_activity(&self) -> ActivityGuard<'_> {
+        self.replies_in_flight.fetch_add(1, Ordering::SeqCst);
+        ActivityGuard(&self.replies_in_flight)
+    }
+
+    /// Record activity, restoring anything released while the agent was idle
+    pub(super) async fn mark_active(&self) -> Result<()> {
+        let mut state = self.idle_state.lock().await;
+        state.last_active = Instant::now();
+        let Some(suspended) = state.suspended.take() else {
+            return Ok(());
+        };
+
+        // Rebuild the index first so the extensions' tools are indexed as they are added
+        if suspended.index_released {
+            let provider = self.provider.lock().await.clone();
+            if let Some(provider) = provider {
+                self.update_router_tool_selector(provider).await?;
+            }
+        }
+        for config in suspended.extensions {
+            let name = config.name();
+            if let Err(e) = self.add_extension(config).await {
+                tracing::warn!("Failed to resume extension {}: {}", name, e);
+            }
+        }
+
+        tracing::info!("Resumed idle agent");
+        Ok(())
+    }
+
+    /// Periodically suspend the agent while it is idle, if `GOOSE_IDLE_TIMEOUT` is set.
+    /// The monitor stops once the agent is dropped.
+    pub fn spawn_idle_monitor(agent: &Arc<Agent>) -> Option<JoinHandle<()>> {
+        let policy = IdlePolicy::from_config()?;
+        let agent: Weak<Agent> = Arc::downgrade(agent);
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(policy.check_interval());
+            loop {
+                interval.tick().await;
+                let Some(agent) = agent.upgrade() else {
+                    break;
+                };
+                if let Err(e) = agent.suspend_if_idle_with(&policy).await {
+                    tracing::warn!("Failed to suspend idle agent: {}", e);
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_suspend_and_resume() {
+        let agent = Agent::new();
+        let policy = IdlePolicy {
+            timeout: Duration::from_secs(3600),
+            suspend_extensions: true,
+            release_index: true,
+        };
+        assert!(!agent.suspend_if_idle_with(&policy).await.unwrap());
+
+        let policy = IdlePolicy {
+            timeout: Duration::ZERO,
+            ..policy
+        };
+        assert!(agent.suspend_if_idle_with(&policy).await.unwrap());
+        assert!(agent.is_suspended().await);
+        // Already suspended
+        assert!(!agent.suspend_if_idle_with(&policy).await.unwrap());
+
+        agent.mark_active().await.unwrap();
+        assert!(!agent.is_suspended().await);
+
+        // Not while a reply is in flight
+        let activity = agent.hold_activity();
+        assert!(!agent.suspend_if_idle_with(&policy).await.unwrap());
+        drop(activity);
+        assert!(agent.suspend_if_idle_with(&policy).await.unwrap());
+    }
+
+    #[test]
+    fn test_check_interval() {
+        let policy = |secs| IdlePolicy {
+            timeout: Duration::from_secs(secs),
+            suspend_extensions: true,
+            release_index: true,
+        };
+        assert_eq!(policy(0).check_interval(), Duration::from_secs(1));
+        assert_eq!(policy(30).check_interval(), Duration::from_secs(15));
+        assert_eq!(policy(3600).check_interval(), MAX_CHECK_INTERVAL);
+    }
+}
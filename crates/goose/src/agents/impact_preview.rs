@@ -0,0 +1,142 @@
+//! Impact previews for tool approvals
+//!
+//! Arguments of a tool call are hard to judge for users who do not read shell commands or JSON.
+//! With `GOOSE_IMPACT_PREVIEW` the agent asks a model to describe in one sentence what a call
+//! will do, such as "Deletes 3 files matching *.log in /tmp", before asking for approval. The
+//! summary is sent with the confirmation request as its `impact`.
+//!
+//! ```yaml
+//! GOOSE_IMPACT_PREVIEW: true
+//! # A cheaper model for the previews, from the configured provider; the session model otherwise
+//! GOOSE_IMPACT_PREVIEW_MODEL: gpt-4o-mini
+//! ```
+//!
+//! A preview is only a hint: when the model fails or takes longer than [`PREVIEW_TIMEOUT`], the
+//! confirmation is asked without one.
+use std::sync::Arc;
+use std::time::Duration;
+
+use mcp_core::ToolCall;
+
+use crate::config::Config;
+use crate::message::Message;
+use crate::providers::base::Provider;
+
+use super::Agent;
+
+/// Longest wait for a preview before asking for approval without one
+pub const PREVIEW_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Longest preview shown, in characters
+const MAX_PREVIEW_CHARS: usize = 300;
+
+const PREVIEW_SYSTEM_PROMPT: &str = "You explain the effect of a tool call to a user who has to \
+    approve it and may not be technical. In one short sentence, say concretely what the call will \
+    change or read: which files, commands, hosts or records, and how many if you can tell. \
+    Mention anything destructive or irreversible first. Do not judge whether it should be \
+    approved, and do not describe the tool in general.";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImpactPreviewConfig {
+    pub enabled: bool,
+    /// Model of the configured provider used for previews
+    pub model: Option<String>,
+}
+
+impl ImpactPreviewConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            enabled: config.get_param("GOOSE_IMPACT_PREVIEW").unwrap_or(false),
+            model: config.get_param("GOOSE_IMPACT_PREVIEW_MODEL").ok(),
+        }
+    }
+}
+
+fn preview_request(tool_call: &ToolCall) -> Message {
+    let arguments = serde_json::to_string_pretty(&tool_call.arguments)
+        .unwrap_or_else(|_| tool_call.arguments.to_string());
+    Message::user().with_text(format!(
+        "Tool: {}\nArguments:\n{}\n\nWhat will this call do?",
+        tool_call.name, arguments
+    ))
+}
+
+/// The first paragraph of the model's answer, shortened to fit a confirmation prompt
+fn clean_preview(text: &str) -> Option<String> {
+    let paragraph = text
+        .trim()
+        .split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if paragraph.is_empty() {
+        return None;
+    }
+    if paragraph.chars().count() <= MAX_PREVIEW_CHARS {
+        return Some(paragraph);
+    }
+    let shortened: String = paragraph.chars().take(MAX_PREVIEW_CHARS - 1).collect();
+    Some(format!("{}…", shortened.trim_end()))
+}
+
+impl Agent {
+    /// A one sentence summary of what a tool call will do, if previews are enabled
+    pub(super) async fn preview_tool_impact(&self, tool_call: &ToolCall) -> Option<String> {
+        let config = ImpactPreviewConfig::from_config();
+        if !config.enabled {
+            return None;
+        }
+        let provider = match &config.model {
+            Some(model) => preview_provider(model),
+            None => None,
+        };
+        let provider = match provider {
+            Some(provider) => provider,
+            None => self.provider().await.ok()?,
+        };
+
+        let request = [preview_request(tool_call)];
+        let preview = provider.complete(PREVIEW_SYSTEM_PROMPT, &request, &[]);
+        match tokio::time::timeout(PREVIEW_TIMEOUT, preview).await {
+            Ok(Ok((message, _usage))) => clean_preview(&message.as_concat_text()),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to preview tool call {}: {}", tool_call.name, e);
+                None
+            }
+            Err(_) => {
+                tracing::warn!("Preview of tool call {} timed out", tool_call.name);
+                None
+            }
+        }
+    }
+}
+
+fn preview_provider(model: &str) -> Option<Arc<dyn Provider>> {
+    match crate::providers::create_for_model(model) {
+        Ok(provider) => Some(provider),
+        Err(e) => {
+            tracing::warn!("Failed to create provider for model {}: {}", model, e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_is_one_short_paragraph() {
+        assert_eq!(clean_preview("  \n "), None);
+        assert_eq!(
+            clean_preview("Deletes 3 files matching *.log\n in /tmp.\n\nThis cannot be undone."),
+            Some("Deletes 3 files matching *.log in /tmp.".to_string())
+        );
+        let long = clean_preview(&"word ".repeat(100)).unwrap();
+        assert_eq!(long.chars().count(), MAX_PREVIEW_CHARS);
+        assert!(long.ends_with("word…"));
+    }
+}
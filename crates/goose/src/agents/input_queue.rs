@@ -0,0 +1,273 @@
+//! Messages submitted while a reply is in progress
+//!
+//! Frontends do not have to block input while a reply streams. Messages are queued with
+//! [`Agent::queue_message`] for a session, and only the replies of that session see them. They
+//! are acknowledged with an [`AgentEvent::InputQueued`] at the next turn boundary, and then
+//! handled by their mode:
+//!
+//! - `steer` messages are added to the conversation at the next turn boundary. Their content
+//!   joins the tool results of the turn, so the conversation keeps one user message between
+//!   assistant messages. A steering message that arrives while the model writes its final
+//!   answer starts another turn as a user message of its own.
+//! - `after_reply` messages wait until the reply has finished, and the frontend takes them
+//!   with [`Agent::next_queued_message`] to start the next reply.
+//!
+//! The mode defaults to `GOOSE_INPUT_QUEUE_MODE`, or `steer` if that is not set.
+//!
+//! [`Agent::steer`] queues a short note such as "skip the tests, just fix the bug" as a
+//! steering message, framed so the model treats it as a correction to the work in progress.
+//!
+//! [`AgentEvent::InputQueued`]: super::AgentEvent::InputQueued
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::message::Message;
+
+use super::Agent;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueMode {
+    /// Add the message to the running reply at the next turn boundary
+    #[default]
+    Steer,
+    /// Hold the message until the running reply has finished
+    AfterReply,
+}
+
+impl QueueMode {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param("GOOSE_INPUT_QUEUE_MODE")
+            .unwrap_or_default()
+    }
+}
+
+/// A user message that asks the model to take a note into account for the rest of the reply
+pub fn steering_message(note: &str) -> Message {
+    Message::user().with_text(format!(
+        "While you were working, the user added this note: {}\n\n\
+         Take it into account from now on, changing or dropping the rest of your plan as needed.",
+        note.trim()
+    ))
+}
+
+/// Add the content of steering messages to the user message of the turn
+pub fn merge_steering(target: &mut Message, steering: Vec<Message>) {
+    for message in steering {
+        target.content.extend(message.content);
+    }
+}
+
+/// Acknowledgement that a queued message was received
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct QueuedInput {
+    pub id: String,
+    pub mode: QueueMode,
+}
+
+#[derive(Debug)]
+struct PendingInput {
+    id: String,
+    message: Message,
+    mode: QueueMode,
+    acknowledged: bool,
+}
+
+/// Messages waiting to be added to a reply, oldest first
+#[derive(Debug, Default)]
+pub struct InputQueue {
+    pending: VecDeque<PendingInput>,
+}
+
+impl InputQueue {
+    pub fn push(&mut self, message: Message, mode: QueueMode) -> String {
+        let id = uuid::Uuid::new_v4().to_string();
+        self.pending.push_back(PendingInput {
+            id: id.clone(),
+            message,
+            mode,
+            acknowledged: false,
+        });
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Acknowledgements for the messages received since the last call
+    pub fn acknowledge(&mut self) -> Vec<QueuedInput> {
+        self.pending
+            .iter_mut()
+            .filter(|input| !input.acknowledged)
+            .map(|input| {
+                input.acknowledged = true;
+                QueuedInput {
+                    id: input.id.clone(),
+                    mode: input.mode,
+                }
+            })
+            .collect()
+    }
+
+    pub fn has_steering(&self) -> bool {
+        self.pending
+            .iter()
+            .any(|input| input.mode == QueueMode::Steer)
+    }
+
+    /// Remove and return the steering messages
+    pub fn take_steering(&mut self) -> Vec<Message> {
+        let (steering, waiting): (VecDeque<_>, VecDeque<_>) = self
+            .pending
+            .drain(..)
+            .partition(|input| input.mode == QueueMode::Steer);
+        self.pending = waiting;
+        steering.into_iter().map(|input| input.message).collect()
+    }
+
+    /// Remove and return the oldest message, whatever its mode
+    pub fn pop(&mut self) -> Option<Message> {
+        self.pending.pop_front().map(|input| input.message)
+    }
+}
+
+/// The input queue of each session, by session key
+pub(super) type InputQueues = HashMap<String, InputQueue>;
+
+impl Agent {
+    /// Queue a message submitted while a reply of the session is in progress, returning its
+    /// id. The mode defaults to `GOOSE_INPUT_QUEUE_MODE`.
+    pub async fn queue_message(
+        &self,
+        session_id: &str,
+        message: Message,
+        mode: Option<QueueMode>,
+    ) -> String {
+        let mode = mode.unwrap_or_else(QueueMode::from_config);
+        self.input_queues
+            .lock()
+            .await
+            .entry(session_id.to_string())
+            .or_default()
+            .push(message, mode)
+    }
+
+    /// Add a note from the user to the running reply of the session at the next turn
+    /// boundary, without restarting it. Returns the id of the queued note.
+    pub async fn steer(&self, session_id: &str, note: &str) -> String {
+        self.queue_message(session_id, steering_message(note), Some(QueueMode::Steer))
+            .await
+    }
+
+    /// Take the next message that is still queued for the session once a reply has finished,
+    /// to reply to it
+    pub async fn next_queued_message(&self, session_id: &str) -> Option<Message> {
+        let mut queues = self.input_queues.lock().await;
+        let queue = queues.get_mut(session_id)?;
+        let message = queue.pop();
+        if queue.is_empty() {
+            queues.remove(session_id);
+        }
+        message
+    }
+
+    /// Acknowledge the messages queued for the session since the last call
+    pub(super) async fn acknowledge_queued_input(
+        &self,
+        session_id: Option<&str>,
+    ) -> Vec<QueuedInput> {
+        let Some(session_id) = session_id else {
+            return Vec::new();
+        };
+        match self.input_queues.lock().await.get_mut(session_id) {
+            Some(queue) => queue.acknowledge(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Take the messages that steer the running reply of the session
+    pub(super) async fn take_steering_input(&self, session_id: Option<&str>) -> Vec<Message> {
+        let Some(session_id) = session_id else {
+            return Vec::new();
+        };
+        let mut queues = self.input_queues.lock().await;
+        let Some(queue) = queues.get_mut(session_id) else {
+            return Vec::new();
+        };
+        let steering = queue.take_steering();
+        if queue.is_empty() {
+            queues.remove(session_id);
+        }
+        steering
+    }
+
+    pub(super) async fn has_steering_input(&self, session_id: Option<&str>) -> bool {
+        let Some(session_id) = session_id else {
+            return false;
+        };
+        self.input_queues
+            .lock()
+            .await
+            .get(session_id)
+            .is_some_and(InputQueue::has_steering)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steering_and_after_reply_inputs() {
+        let mut queue = InputQueue::default();
+        let steer = queue.push(Message::user().with_text("use tabs"), QueueMode::Steer);
+        queue.push(
+            Message::user().with_text("then run the tests"),
+            QueueMode::AfterReply,
+        );
+        assert!(queue.has_steering());
+
+        let acks = queue.acknowledge();
+        assert_eq!(acks.len(), 2);
+        assert_eq!(acks[0].id, steer);
+        assert!(queue.acknowledge().is_empty());
+
+        let steering = queue.take_steering();
+        assert_eq!(steering.len(), 1);
+        assert_eq!(steering[0].as_concat_text(), "use tabs");
+        assert!(!queue.has_steering());
+        assert_eq!(queue.len(), 1);
+
+        assert_eq!(queue.pop().unwrap().as_concat_text(), "then run the tests");
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_steering_joins_the_turn_message() {
+        let mut turn = Message::user().with_text("tool results");
+        merge_steering(
+            &mut turn,
+            vec![
+                Message::user().with_text("use tabs"),
+                Message::user().with_text("and spaces after commas"),
+            ],
+        );
+        assert_eq!(turn.content.len(), 3);
+        assert_eq!(turn.content[2].as_text(), Some("and spaces after commas"));
+    }
+
+    #[test]
+    fn test_steering_message_frames_note() {
+        let text = steering_message("  skip the tests, just fix the bug\n").as_concat_text();
+        assert!(text.contains("note: skip the tests, just fix the bug\n\nTake it into account"));
+    }
+}
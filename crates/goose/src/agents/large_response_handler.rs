@@ -1,9 +1,15 @@
-use chrono::Utc;
-use mcp_core::{Content, ToolError};
+use mcp_core::{Content, TextContent, ToolError};
+use serde_json::{Map, Value};
 use std::fs::File;
 use std::io::Write;
 
+use crate::config::Config;
+
 const LARGE_TEXT_THRESHOLD: usize = 200_000;
+/// JSON results shorter than this are left as they are
+const TABULAR_TEXT_THRESHOLD: usize = 2_000;
+/// Tables with more columns than this are harder to read than the JSON
+const TABULAR_MAX_COLUMNS: usize = 40;
 
 /// Process tool response and handle large text content
 pub fn process_tool_response(
@@ -16,10 +22,18 @@ pub fn process_tool_response(
             for content in contents {
                 match content {
                     Content::Text(text_content) => {
+                        let text_content = match encode_tabular(&text_content.text) {
+                            Some(text) => TextContent {
+                                text,
+                                annotations: text_content.annotations,
+                            },
+                            None => text_content,
+                        };
+
                         // Check if text exceeds threshold
                         if text_content.text.len() > LARGE_TEXT_THRESHOLD {
                             // Write to temp file
-                            match write_large_text_to_file(&text_content.text) {
+                            match write_large_text_to_file(&text_content.text, "txt") {
                                 Ok(file_path) => {
                                     // Create a new text content with reference to the file
                                     let message = format!(
@@ -55,20 +69,98 @@ pub fn process_tool_response(
     }
 }
 
+/// Re-encode a JSON array of objects as TSV, which costs far fewer tokens than repeating every
+/// key on every row. The full JSON is kept in a file the model can read if it needs it.
+/// Set `GOOSE_TABULAR_RESULTS` to false to keep tool results as they are.
+fn encode_tabular(text: &str) -> Option<String> {
+    if text.len() < TABULAR_TEXT_THRESHOLD
+        || !Config::global()
+            .get_param::<bool>("GOOSE_TABULAR_RESULTS")
+            .unwrap_or(true)
+    {
+        return None;
+    }
+    let rows = match serde_json::from_str::<Value>(text.trim()).ok()? {
+        Value::Array(rows) => rows,
+        _ => return None,
+    };
+    let table = to_tsv(&rows)?;
+    // Only worth it when the table is clearly smaller than the JSON
+    if table.len() * 5 > text.len() * 4 {
+        return None;
+    }
+    let file_path = write_large_text_to_file(text, "json").ok()?;
+    Some(format!(
+        "The tool returned a JSON array of {} objects, shown here as TSV with a header row to save space. Nested values are inline JSON and empty cells are null or missing. The full JSON is stored in the file: {}\n\n{}",
+        rows.len(),
+        file_path,
+        table
+    ))
+}
+
+/// The rows as tab separated values with a header row, if they are all objects
+fn to_tsv(rows: &[Value]) -> Option<String> {
+    if rows.len() < 2 {
+        return None;
+    }
+    let objects: Vec<&Map<String, Value>> =
+        rows.iter().map(Value::as_object).collect::<Option<_>>()?;
+
+    // Columns in the order they first appear
+    let mut columns: Vec<&str> = Vec::new();
+    for object in &objects {
+        for key in object.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+    if columns.is_empty() || columns.len() > TABULAR_MAX_COLUMNS {
+        return None;
+    }
+
+    let mut table = columns
+        .iter()
+        .map(|column| escape_cell(column))
+        .collect::<Vec<_>>()
+        .join("\t");
+    for object in objects {
+        let cells: Vec<String> = columns
+            .iter()
+            .map(|column| match object.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => escape_cell(value),
+                Some(value) => escape_cell(&value.to_string()),
+            })
+            .collect();
+        table.push('\n');
+        table.push_str(&cells.join("\t"));
+    }
+    Some(table)
+}
+
+fn escape_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
 /// Write large text content to a temporary file
-fn write_large_text_to_file(content: &str) -> Result<String, std::io::Error> {
+fn write_large_text_to_file(content: &str, extension: &str) -> Result<String, std::io::Error> {
     // Create temp directory if it doesn't exist
     let temp_dir = std::env::temp_dir().join("goose_mcp_responses");
     std::fs::create_dir_all(&temp_dir)?;
 
-    // Generate a unique filename with timestamp
-    let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
-    let filename = format!("mcp_response_{}.txt", timestamp);
+    // Generate a unique filename, so responses written within the same second don't collide
+    let filename = format!("mcp_response_{}.{}", uuid::Uuid::new_v4(), extension);
     let file_path = temp_dir.join(&filename);
 
     // Write content to file
     let mut file = File::create(&file_path)?;
     file.write_all(content.as_bytes())?;
+    crate::shutdown::remove_on_shutdown(&file_path);
 
     Ok(file_path.to_string_lossy().to_string())
 }
@@ -225,6 +317,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_to_tsv() {
+        let rows = serde_json::json!([
+            {"id": 1, "title": "Fix\tlogin", "labels": ["bug"]},
+            {"id": 2, "title": "Add search", "assignee": null},
+            {"id": 3, "assignee": "alice"}
+        ]);
+        let table = to_tsv(rows.as_array().unwrap()).unwrap();
+        assert_eq!(
+            table,
+            "id\tlabels\ttitle\tassignee\n1\t[\"bug\"]\tFix\\tlogin\t\n2\t\tAdd search\t\n3\t\t\talice"
+        );
+
+        assert!(to_tsv(&[serde_json::json!({"id": 1})]).is_none());
+        assert!(to_tsv(&[serde_json::json!({"id": 1}), serde_json::json!(2)]).is_none());
+    }
+
+    #[test]
+    fn test_tabular_response_encoded() {
+        let rows: Vec<Value> = (0..100)
+            .map(|i| {
+                serde_json::json!({
+                    "issue_number": i,
+                    "issue_title": format!("Issue {}", i),
+                    "issue_state": "open"
+                })
+            })
+            .collect();
+        let json = serde_json::to_string(&rows).unwrap();
+
+        let processed = process_tool_response(Ok(vec![Content::text(json.clone())])).unwrap();
+        let Content::Text(text_content) = &processed[0] else {
+            panic!("Expected text content");
+        };
+        assert!(text_content.text.len() < json.len());
+        assert!(text_content
+            .text
+            .contains("issue_number\tissue_state\tissue_title\n0\topen\tIssue 0"));
+
+        let file_path = text_content
+            .text
+            .split("stored in the file: ")
+            .nth(1)
+            .and_then(|rest| rest.lines().next())
+            .unwrap();
+        let path = Path::new(file_path.trim());
+        assert_eq!(fs::read_to_string(path).unwrap(), json);
+        let _ = fs::remove_file(path);
+    }
+
     #[test]
     fn test_error_response_passes_through() {
         // Create an error response
@@ -1,22 +1,74 @@
+pub mod acceptance;
 mod agent;
+mod anchor_tool;
+pub mod answer_synthesis;
+pub mod answer_verification;
+pub mod audit;
+pub mod calculator;
+pub mod cancellation;
+pub mod change_review;
+mod checkpoints;
+pub mod code_actions;
+pub mod code_sandbox;
 mod context;
+pub mod context_usage;
+pub mod conversation_template;
+pub mod cost_tracker;
+pub mod edit_transactions;
+pub mod estimate;
 pub mod extension;
+pub mod extension_discovery;
+pub mod extension_health;
 pub mod extension_manager;
+pub mod extension_names;
+pub mod extension_prompts;
+pub mod extension_telemetry;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod frontend_tool_harness;
+pub mod frontend_tools;
+pub mod idle;
+pub mod impact_preview;
+pub mod input_queue;
 mod large_response_handler;
+pub mod model_pins;
 pub mod platform_tools;
+pub mod prompt_history;
 pub mod prompt_manager;
+pub mod quarantine;
+mod quota;
+mod regenerate;
+pub mod remote_workers;
 mod reply_parts;
+pub mod response_policy;
+pub mod result_processors;
+pub mod router_fallback;
 mod router_tool_selector;
 mod router_tools;
 mod schedule_tool;
+mod session_search;
+pub mod snapshots;
+pub mod streaming;
+pub mod termination;
+pub mod time_box;
 
+pub mod tool_budgets;
+pub mod tool_descriptions;
 mod tool_execution;
+pub mod tool_mocks;
 mod tool_router_index_manager;
+pub mod tool_stats;
 pub(crate) mod tool_vectordb;
+pub mod turn_budget;
+pub mod turn_webhook;
 mod types;
+pub mod ui_resources;
+mod workspace_routers;
+pub mod workspace_search;
 
 pub use agent::{Agent, AgentEvent};
 pub use extension::ExtensionConfig;
 pub use extension_manager::ExtensionManager;
 pub use prompt_manager::PromptManager;
+pub use termination::TerminationCondition;
+pub use turn_budget::{ComplexityEstimator, TaskComplexity, TurnBudget};
 pub use types::{FrontendTool, SessionConfig};
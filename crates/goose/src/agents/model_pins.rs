@@ -0,0 +1,185 @@
+//! Pinning the model for turns that follow certain tools
+//!
+//! Some tools work much better with a particular model, for example structured extraction that
+//! one model follows reliably and another does not. `GOOSE_MODEL_PINS` names a model of the
+//! configured provider for the turns that read the results of a tool or extension:
+//!
+//! ```yaml
+//! GOOSE_MODEL_PINS:
+//!   extractor:
+//!     model: gpt-4o
+//!   developer__shell:
+//!     model: claude-sonnet-4
+//!     turns: 2
+//! ```
+//!
+//! Keys are full tool names or extension names, and a tool's own pin comes before its
+//! extension's. Calling a pinned tool gives a [`RoutingHint`] for the next `turns` turns
+//! (one by default), which the reply loop follows when it picks the provider for a turn. A later
+//! call to another pinned tool replaces the hint, and the session model is used again once the
+//! hint runs out.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::providers::base::Provider;
+
+fn default_turns() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelPin {
+    pub model: String,
+    /// Turns after the tool call that use the model
+    #[serde(default = "default_turns")]
+    pub turns: u32,
+}
+
+/// The model the next turns should use, and why
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutingHint {
+    pub model: String,
+    pub turns: u32,
+    /// The tool or extension whose pin applies
+    pub scope: String,
+}
+
+/// Model pins by tool or extension name
+#[derive(Debug, Clone, Default)]
+pub struct ModelPins {
+    pins: HashMap<String, ModelPin>,
+}
+
+impl ModelPins {
+    pub fn new(pins: HashMap<String, ModelPin>) -> Self {
+        Self {
+            pins: pins
+                .into_iter()
+                .filter(|(_, pin)| pin.turns > 0 && !pin.model.trim().is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        match Config::global().get_param::<HashMap<String, ModelPin>>("GOOSE_MODEL_PINS") {
+            Ok(pins) => Self::new(pins),
+            Err(crate::config::ConfigError::NotFound(_)) => Self::default(),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid GOOSE_MODEL_PINS: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+
+    /// The hint for the turns after calls to these tools, from the first pinned one
+    pub fn routing_hint<'a>(
+        &self,
+        tool_names: impl IntoIterator<Item = &'a str>,
+    ) -> Option<RoutingHint> {
+        tool_names.into_iter().find_map(|tool_name| {
+            let extension = tool_name.split_once("__").map(|(extension, _)| extension);
+            [Some(tool_name), extension]
+                .into_iter()
+                .flatten()
+                .find_map(|scope| {
+                    self.pins.get(scope).map(|pin| RoutingHint {
+                        model: pin.model.clone(),
+                        turns: pin.turns,
+                        scope: scope.to_string(),
+                    })
+                })
+        })
+    }
+}
+
+/// A routing hint being followed by a reply, with the provider for its model
+pub struct PinnedModel {
+    hint: RoutingHint,
+    turns_used: u32,
+    provider: Option<Arc<dyn Provider>>,
+}
+
+impl PinnedModel {
+    pub fn new(hint: RoutingHint) -> Self {
+        Self {
+            hint,
+            turns_used: 0,
+            provider: None,
+        }
+    }
+
+    pub fn hint(&self) -> &RoutingHint {
+        &self.hint
+    }
+
+    /// The provider for the next turn, or None once the hint has run out or its model cannot
+    /// be used
+    pub fn next_turn(&mut self) -> Option<Arc<dyn Provider>> {
+        if self.turns_used >= self.hint.turns {
+            return None;
+        }
+        self.turns_used += 1;
+        if self.provider.is_none() {
+            match crate::providers::create_for_model(&self.hint.model) {
+                Ok(provider) => self.provider = Some(provider),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to create provider for model {} pinned by {}: {}",
+                        self.hint.model,
+                        self.hint.scope,
+                        e
+                    );
+                    self.turns_used = self.hint.turns;
+                    return None;
+                }
+            }
+        }
+        self.provider.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(model: &str, turns: u32) -> ModelPin {
+        ModelPin {
+            model: model.to_string(),
+            turns,
+        }
+    }
+
+    #[test]
+    fn test_tool_pin_comes_before_extension_pin() {
+        let pins = ModelPins::new(HashMap::from([
+            ("extractor".to_string(), pin("gpt-4o", 1)),
+            ("extractor__tables".to_string(), pin("o3", 2)),
+            ("developer".to_string(), pin("unused", 0)),
+        ]));
+
+        assert_eq!(pins.routing_hint(["developer__shell"]), None);
+        let hint = pins
+            .routing_hint(["developer__shell", "extractor__tables"])
+            .unwrap();
+        assert_eq!((hint.model.as_str(), hint.turns), ("o3", 2));
+        assert_eq!(hint.scope, "extractor__tables");
+
+        let hint = pins.routing_hint(["extractor__fields"]).unwrap();
+        assert_eq!((hint.model.as_str(), hint.turns), ("gpt-4o", 1));
+        assert_eq!(hint.scope, "extractor");
+    }
+
+    #[test]
+    fn test_pin_parses_with_default_turns() {
+        let pins: HashMap<String, ModelPin> =
+            serde_yaml::from_str("extractor:\n  model: gpt-4o\n").unwrap();
+        assert_eq!(pins["extractor"], pin("gpt-4o", 1));
+    }
+}
@@ -2,12 +2,25 @@ use indoc::indoc;
 use mcp_core::tool::{Tool, ToolAnnotations};
 use serde_json::json;
 
+use super::code_sandbox::SandboxLanguage;
+
 pub const PLATFORM_READ_RESOURCE_TOOL_NAME: &str = "platform__read_resource";
 pub const PLATFORM_LIST_RESOURCES_TOOL_NAME: &str = "platform__list_resources";
 pub const PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME: &str =
     "platform__search_available_extensions";
 pub const PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME: &str = "platform__manage_extensions";
 pub const PLATFORM_MANAGE_SCHEDULE_TOOL_NAME: &str = "platform__manage_schedule";
+pub const PLATFORM_EXTENSION_LOGS_TOOL_NAME: &str = "platform__extension_logs";
+pub const PLATFORM_EXECUTE_CODE_TOOL_NAME: &str = "platform__execute_code";
+pub const PLATFORM_RUN_PROGRAM_TOOL_NAME: &str = "platform__run_program";
+pub const PLATFORM_ANCHOR_TOOL_NAME: &str = "platform__anchor";
+pub const PLATFORM_UNDO_TOOL_NAME: &str = "platform__undo_tool_effects";
+pub const PLATFORM_SEARCH_WORKSPACE_TOOL_NAME: &str = "platform__search_workspace";
+pub const PLATFORM_REVIEW_CHANGES_TOOL_NAME: &str = "platform__review_changes";
+pub const PLATFORM_EDIT_TRANSACTION_TOOL_NAME: &str = "platform__edit_transaction";
+pub const PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME: &str =
+    "platform__update_acceptance_criteria";
+pub const PLATFORM_CALCULATE_TOOL_NAME: &str = "platform__calculate";
 
 pub fn read_resource_tool() -> Tool {
     Tool::new(
@@ -157,3 +170,337 @@ pub fn manage_schedule_tool() -> Tool {
         }),
     )
 }
+
+pub fn anchor_tool() -> Tool {
+    Tool::new(
+        PLATFORM_ANCHOR_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Mark and recall named anchors in this conversation.
+
+            Anchors keep important content verbatim, such as a decision and its reasoning, so it
+            can be recalled exactly even after earlier messages have been summarized or removed.
+
+            Actions:
+            - "set": Store `content` under `name`, replacing any anchor with the same name
+            - "recall": Get the content anchored under `name`
+            - "list": List the anchors in this conversation
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["action"],
+            "properties": {
+                "action": {"type": "string", "enum": ["set", "recall", "list"]},
+                "name": {"type": "string", "description": "Name of the anchor, e.g. 'decision point A'"},
+                "content": {"type": "string", "description": "The content to keep verbatim, for the set action"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Conversation anchors".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn undo_tool() -> Tool {
+    Tool::new(
+        PLATFORM_UNDO_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Undo the file changes made by the most recent tool calls.
+
+            Use this tool when the user asks to undo or revert what was just done. Each step
+            restores the files changed by one tool call, most recent first. Only files that were
+            named in a tool call's arguments can be restored; changes made by shell commands to
+            other files are not undone.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "steps": {"type": "integer", "description": "Number of tool calls to undo", "default": 1}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Undo tool changes".to_string()),
+            read_only_hint: false,
+            destructive_hint: true,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn search_workspace_tool() -> Tool {
+    Tool::new(
+        PLATFORM_SEARCH_WORKSPACE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Search the files in the working directory by meaning.
+
+            Returns the passages most relevant to the query with their file paths and line
+            numbers. Use this to find where something is implemented or discussed when you do not
+            know the exact names to grep for. Files ignored by git are not searched.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": {"type": "string", "description": "What to look for, e.g. 'where retries are configured'"},
+                "k": {"type": "integer", "description": "Number of passages to return", "default": 8}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Search workspace".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn calculate_tool() -> Tool {
+    Tool::new(
+        PLATFORM_CALCULATE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Evaluate an arithmetic expression exactly, and optionally convert the result between units.
+
+            Use this instead of working out arithmetic yourself. Expressions support + - * / % ^,
+            parentheses, the constants pi and e, and the functions sqrt, abs, round(x, digits),
+            floor, ceil, min and max. Numbers are decimal with arbitrary precision, so 0.1 + 0.2 is
+            exactly 0.3.
+
+            Give `from` and `to` to convert the result, e.g. from "mi" to "km", "F" to "C",
+            "GiB" to "MB" or "USD" to "EUR". Length, area, volume, mass, time, speed, data,
+            temperature and currency units are supported. Currency rates may be approximate.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["expression"],
+            "properties": {
+                "expression": {"type": "string", "description": "The expression to evaluate, e.g. '(1250 * 1.21) / 12'"},
+                "from": {"type": "string", "description": "Unit of the result of the expression, to convert from"},
+                "to": {"type": "string", "description": "Unit to convert the result to"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Calculate".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn review_changes_tool() -> Tool {
+    Tool::new(
+        PLATFORM_REVIEW_CHANGES_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Review the uncommitted changes in the working directory.
+
+            Returns a diff stat of the changes since the last commit and the new untracked
+            files, and with `include_diff` the full diff. Use this to check your accumulated
+            changes before reporting that a task is done.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "include_diff": {"type": "boolean", "description": "Include the full diff", "default": false}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Review changes".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn edit_transaction_tool() -> Tool {
+    Tool::new(
+        PLATFORM_EDIT_TRANSACTION_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Stage a series of file edits and apply them together once the user approves.
+
+            Use this when a change spans several files. After `begin`, edits made with the
+            text editor are staged instead of written, and viewing a staged file shows its
+            staged content. `commit` shows the user the combined diff and, once they approve,
+            writes every file; if any write fails none of the changes are kept. `discard` drops
+            the staged edits. Shell commands are not staged, so do not run builds or tests
+            that depend on the edits until they are committed.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["action"],
+            "properties": {
+                "action": {"type": "string", "enum": ["begin", "commit", "discard"], "description": "Begin staging edits, commit the staged edits, or discard them"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Stage and commit edits".to_string()),
+            read_only_hint: false,
+            destructive_hint: true,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn update_acceptance_criteria_tool() -> Tool {
+    Tool::new(
+        PLATFORM_UPDATE_ACCEPTANCE_CRITERIA_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Update the acceptance criteria checklist of the current task.
+
+            Mark an item `met` once you have verified it, with a note on how, for example the
+            test that covers it. Mark an item `waived` only when it does not apply, with a note
+            giving the reason. Mark it `open` again if later work undoes it. The task is not
+            finished until every item is met or waived.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["updates"],
+            "properties": {
+                "updates": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["item", "status"],
+                        "properties": {
+                            "item": {"type": "integer", "minimum": 1, "description": "Number of the item in the checklist"},
+                            "status": {"type": "string", "enum": ["met", "waived", "open"]},
+                            "note": {"type": "string", "description": "How the item was verified, or why it was waived"}
+                        }
+                    }
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Update acceptance criteria".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn extension_logs_tool() -> Tool {
+    Tool::new(
+        PLATFORM_EXTENSION_LOGS_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Read the most recent log output (stderr) of an extension.
+
+            Use this tool to find out why an extension is failing, for example when it does not
+            start, its tools return errors, or it stopped responding. Logs are kept for extensions
+            that failed to start.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["extension_name"],
+            "properties": {
+                "extension_name": {"type": "string", "description": "The name of the extension"},
+                "lines": {"type": "integer", "description": "Number of most recent lines to return", "default": 50}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Read extension logs".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn execute_code_tool(languages: &[SandboxLanguage]) -> Tool {
+    let languages: Vec<&str> = languages.iter().map(|l| l.as_str()).collect();
+    Tool::new(
+        PLATFORM_EXECUTE_CODE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Execute a short code snippet in an isolated sandbox and return its output.
+
+            Use this for small calculations, data and text transformations, or checking logic,
+            instead of a shell. The code has no access to files, network or environment
+            variables, and runs under strict time and memory limits. Print the results you need.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["language", "code"],
+            "properties": {
+                "language": {"type": "string", "enum": languages},
+                "code": {"type": "string", "description": "The code to run. Output is read from stdout"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Execute code".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn run_program_tool(callable_tools: &[Tool]) -> Tool {
+    let functions: Vec<String> = callable_tools
+        .iter()
+        .map(|tool| {
+            let params = tool
+                .input_schema
+                .get("properties")
+                .and_then(|p| p.as_object())
+                .map(|p| p.keys().cloned().collect::<Vec<_>>().join(", "))
+                .unwrap_or_default();
+            let summary = tool.description.lines().next().unwrap_or_default();
+            format!("- {}(#{{ {} }}): {}", tool.name, params, summary)
+        })
+        .collect();
+
+    Tool::new(
+        PLATFORM_RUN_PROGRAM_TOOL_NAME.to_string(),
+        format!(
+            indoc! {r#"
+                Run a short program that calls tools as functions, to do many tool calls in one step.
+
+                Prefer this over individual tool calls when repeating an action, e.g. over many files.
+                Programs are written in Rhai, which reads like a mix of Python and JavaScript:
+                `let x = 1;`, `for item in list {{ }}`, `if a == b {{ }} else {{ }}`, `text.split("\n")`.
+                Call a tool with a map of its arguments, e.g. `tool_name(#{{ path: "src/main.rs" }})`;
+                each call returns the tool's text output and a failed call stops the program.
+                Use `parse_json(text)` to turn JSON output into maps and arrays. The output of
+                `print(...)` and the value of the last expression are returned.
+
+                Available functions:
+                {}
+            "#},
+            functions.join("\n")
+        ),
+        json!({
+            "type": "object",
+            "required": ["program"],
+            "properties": {
+                "program": {"type": "string", "description": "The program to run"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Run a program".to_string()),
+            read_only_hint: false,
+            destructive_hint: true,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
@@ -0,0 +1,319 @@
+//! What the model saw on each turn
+//!
+//! The system prompt and tool list are rebuilt whenever extensions change, so the prompt of one
+//! turn can differ from the next. The agent keeps a snapshot of both for the most recent turns
+//! (`GOOSE_PROMPT_HISTORY_SIZE`, 20 by default, 0 to turn it off), and two turns can be
+//! compared to see which tools were added, removed or changed and which prompt sections
+//! changed.
+use std::collections::VecDeque;
+
+use chrono::Utc;
+use mcp_core::tool::Tool;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+
+use super::Agent;
+
+const DEFAULT_HISTORY_SIZE: usize = 20;
+
+/// A tool as the model saw it, with a fingerprint of its description and schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ToolFingerprint {
+    pub name: String,
+    pub fingerprint: String,
+}
+
+impl ToolFingerprint {
+    fn new(tool: &Tool) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(tool.description.as_bytes());
+        hasher.update(tool.input_schema.to_string().as_bytes());
+        Self {
+            name: tool.name.clone(),
+            fingerprint: format!("{:x}", hasher.finalize())[..12].to_string(),
+        }
+    }
+}
+
+/// The system prompt and tools sent to the model on one turn
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct TurnSnapshot {
+    /// Number of the turn since the agent was created, starting at 1
+    pub turn: u64,
+    /// Unix timestamp of when the turn started
+    pub timestamp: i64,
+    pub system_prompt: String,
+    pub tools: Vec<ToolFingerprint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SectionChange {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A section of the system prompt, identified by its markdown heading, that differs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PromptSectionDiff {
+    /// The heading of the section, or empty for the text before the first heading
+    pub heading: String,
+    pub change: SectionChange,
+}
+
+/// What changed between the prompts of two turns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct PromptDiff {
+    pub from_turn: u64,
+    pub to_turn: u64,
+    pub tools_added: Vec<String>,
+    pub tools_removed: Vec<String>,
+    /// Tools whose description or schema changed
+    pub tools_changed: Vec<String>,
+    pub sections: Vec<PromptSectionDiff>,
+}
+
+impl PromptDiff {
+    pub fn is_empty(&self) -> bool {
+        self.tools_added.is_empty()
+            && self.tools_removed.is_empty()
+            && self.tools_changed.is_empty()
+            && self.sections.is_empty()
+    }
+}
+
+/// Split a prompt into its sections by markdown heading, in order
+pub(super) fn prompt_sections(prompt: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = vec![(String::new(), String::new())];
+    for line in prompt.lines() {
+        if line.starts_with('#') {
+            sections.push((
+                line.trim_start_matches('#').trim().to_string(),
+                String::new(),
+            ));
+        } else if let Some((_, body)) = sections.last_mut() {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    sections
+}
+
+pub fn diff_snapshots(from: &TurnSnapshot, to: &TurnSnapshot) -> PromptDiff {
+    let find = |tools: &[ToolFingerprint], name: &str| -> Option<String> {
+        tools
+            .iter()
+            .find(|tool| tool.name == name)
+            .map(|tool| tool.fingerprint.clone())
+    };
+    let tools_added = to
+        .tools
+        .iter()
+        .filter(|tool| find(&from.tools, &tool.name).is_none())
+        .map(|tool| tool.name.clone())
+        .collect();
+    let tools_removed = from
+        .tools
+        .iter()
+        .filter(|tool| find(&to.tools, &tool.name).is_none())
+        .map(|tool| tool.name.clone())
+        .collect();
+    let tools_changed = to
+        .tools
+        .iter()
+        .filter(|tool| find(&from.tools, &tool.name).is_some_and(|f| f != tool.fingerprint))
+        .map(|tool| tool.name.clone())
+        .collect();
+
+    let from_sections = prompt_sections(&from.system_prompt);
+    let to_sections = prompt_sections(&to.system_prompt);
+    let body = |sections: &[(String, String)], heading: &str| -> Option<String> {
+        sections
+            .iter()
+            .find(|(h, _)| h == heading)
+            .map(|(_, body)| body.clone())
+    };
+    let mut sections = Vec::new();
+    for (heading, text) in &to_sections {
+        let change = match body(&from_sections, heading) {
+            None if text.trim().is_empty() => continue,
+            None => SectionChange::Added,
+            Some(previous) if previous != *text => SectionChange::Changed,
+            Some(_) => continue,
+        };
+        sections.push(PromptSectionDiff {
+            heading: heading.clone(),
+            change,
+        });
+    }
+    for (heading, text) in &from_sections {
+        if body(&to_sections, heading).is_none() && !text.trim().is_empty() {
+            sections.push(PromptSectionDiff {
+                heading: heading.clone(),
+                change: SectionChange::Removed,
+            });
+        }
+    }
+
+    PromptDiff {
+        from_turn: from.turn,
+        to_turn: to.turn,
+        tools_added,
+        tools_removed,
+        tools_changed,
+        sections,
+    }
+}
+
+/// Snapshots of the most recent turns, oldest first
+#[derive(Debug)]
+pub struct PromptHistory {
+    capacity: usize,
+    next_turn: u64,
+    snapshots: VecDeque<TurnSnapshot>,
+}
+
+impl Default for PromptHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_SIZE)
+    }
+}
+
+impl PromptHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_turn: 1,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_PROMPT_HISTORY_SIZE")
+                .unwrap_or(DEFAULT_HISTORY_SIZE),
+        )
+    }
+
+    pub fn record(&mut self, system_prompt: &str, tools: &[Tool]) {
+        let turn = self.next_turn;
+        self.next_turn += 1;
+        if self.capacity == 0 {
+            return;
+        }
+        let mut tools: Vec<ToolFingerprint> = tools.iter().map(ToolFingerprint::new).collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        self.snapshots.push_back(TurnSnapshot {
+            turn,
+            timestamp: Utc::now().timestamp(),
+            system_prompt: system_prompt.to_string(),
+            tools,
+        });
+        while self.snapshots.len() > self.capacity {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn snapshots(&self) -> Vec<TurnSnapshot> {
+        self.snapshots.iter().cloned().collect()
+    }
+
+    pub fn get(&self, turn: u64) -> Option<&TurnSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.turn == turn)
+    }
+
+    /// Diff two recorded turns, or the last two turns if none are given
+    pub fn diff(&self, from: Option<u64>, to: Option<u64>) -> Option<PromptDiff> {
+        let to = match to {
+            Some(turn) => self.get(turn)?,
+            None => self.snapshots.back()?,
+        };
+        let from = match from {
+            Some(turn) => self.get(turn)?,
+            None => self.get(to.turn.checked_sub(1)?)?,
+        };
+        Some(diff_snapshots(from, to))
+    }
+}
+
+impl Agent {
+    /// The system prompt and tools of the most recent turns, oldest first
+    pub async fn prompt_snapshots(&self) -> Vec<TurnSnapshot> {
+        self.prompt_history.lock().await.snapshots()
+    }
+
+    /// Compare what the model saw on two turns, by default the last turn and the one before
+    pub async fn diff_prompt_turns(
+        &self,
+        from: Option<u64>,
+        to: Option<u64>,
+    ) -> Option<PromptDiff> {
+        self.prompt_history.lock().await.diff(from, to)
+    }
+
+    pub(super) async fn record_prompt_snapshot(&self, system_prompt: &str, tools: &[Tool]) {
+        self.prompt_history
+            .lock()
+            .await
+            .record(system_prompt, tools);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool(name: &str, description: &str) -> Tool {
+        Tool::new(name, description, json!({"type": "object"}), None)
+    }
+
+    #[test]
+    fn test_diff_consecutive_turns() {
+        let mut history = PromptHistory::new(10);
+        history.record(
+            "You are goose.\n# Extensions\n## developer\nWrite code\n",
+            &[
+                tool("developer__shell", "Run a command"),
+                tool("jira__search", "Search"),
+            ],
+        );
+        history.record(
+            "You are goose.\n# Extensions\n## developer\nWrite code\n## github\nUse GitHub\n",
+            &[
+                tool("developer__shell", "Run a shell command"),
+                tool("github__create_issue", "Create an issue"),
+            ],
+        );
+
+        let diff = history.diff(None, None).unwrap();
+        assert_eq!((diff.from_turn, diff.to_turn), (1, 2));
+        assert_eq!(diff.tools_added, vec!["github__create_issue"]);
+        assert_eq!(diff.tools_removed, vec!["jira__search"]);
+        assert_eq!(diff.tools_changed, vec!["developer__shell"]);
+        assert_eq!(
+            diff.sections,
+            vec![PromptSectionDiff {
+                heading: "github".to_string(),
+                change: SectionChange::Added,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_history_keeps_recent_turns() {
+        let mut history = PromptHistory::new(2);
+        for _ in 0..3 {
+            history.record("prompt", &[]);
+        }
+        let turns: Vec<u64> = history.snapshots().iter().map(|s| s.turn).collect();
+        assert_eq!(turns, vec![2, 3]);
+        assert!(history.diff(Some(1), Some(3)).is_none());
+        assert!(history.diff(None, None).unwrap().is_empty());
+    }
+}
@@ -2,15 +2,91 @@ use chrono::Utc;
 use serde_json::Value;
 use std::collections::HashMap;
 
-use crate::agents::extension::ExtensionInfo;
+use crate::agents::acceptance::AcceptanceChecklist;
+use crate::agents::extension::{ExtensionInfo, ToolHint};
+use crate::agents::response_policy::ResponsePolicy;
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
 use crate::agents::router_tools::{llm_search_tool_prompt, vector_search_tool_prompt};
+use crate::message::Message;
 use crate::providers::base::get_current_model;
 use crate::{config::Config, prompt_template};
+use mcp_core::{Role, Tool};
+
+/// Longest usage hint listed for a tool, in characters
+const MAX_HINT_CHARS: usize = 160;
+
+/// Whether the system prompt lists tools under their extensions, on unless
+/// `GOOSE_TOOL_GROUPING` is false. Grouping is skipped with the tool router, which only
+/// advertises the tools it selected.
+pub fn tool_grouping_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_TOOL_GROUPING")
+        .unwrap_or(true)
+}
+
+/// The extension a tool belongs to; frontend tools have no prefix
+fn tool_group(tool_name: &str) -> &str {
+    tool_name
+        .split_once("__")
+        .map(|(extension, _)| extension)
+        .unwrap_or("frontend")
+}
+
+/// The first sentence of a tool's description
+fn usage_hint(description: &str) -> String {
+    let first_line = description.trim().lines().next().unwrap_or_default();
+    let sentence = match first_line.find(". ") {
+        Some(end) => &first_line[..=end],
+        None => first_line,
+    };
+    match sentence.char_indices().nth(MAX_HINT_CHARS) {
+        Some((end, _)) => format!("{}...", &sentence[..end]),
+        None => sentence.to_string(),
+    }
+}
+
+fn tools_summary(tools: &[ToolHint]) -> String {
+    let read_only = tools.iter().filter(|tool| tool.read_only).count();
+    let count = match tools.len() {
+        1 => "1 tool".to_string(),
+        n => format!("{} tools", n),
+    };
+    match read_only {
+        0 => count,
+        n if n == tools.len() => format!("{}, all read-only", count),
+        n => format!("{}, {} of them read-only", count, n),
+    }
+}
+
+/// The task the session was started with, kept in the system prompt so it stays in view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedRequest {
+    pub request: String,
+}
+
+impl PinnedRequest {
+    pub fn new(request: impl Into<String>) -> Self {
+        Self {
+            request: request.into(),
+        }
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "# Original Request\n\n\
+             This is the request this session was started for. Keep working towards it and \
+             check your progress against it before finishing.\n\n{}",
+            self.request.trim()
+        )
+    }
+}
 
 pub struct PromptManager {
     system_prompt_override: Option<String>,
     system_prompt_extras: Vec<String>,
+    pinned_request: Option<PinnedRequest>,
+    acceptance_checklist: Option<AcceptanceChecklist>,
+    response_policy: Option<ResponsePolicy>,
     current_date_timestamp: String,
 }
 
@@ -25,6 +101,9 @@ impl PromptManager {
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            pinned_request: None,
+            acceptance_checklist: None,
+            response_policy: ResponsePolicy::from_config(),
             // Use the fixed current date time so that prompt cache can be used.
             current_date_timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         }
@@ -35,11 +114,67 @@ impl PromptManager {
         self.system_prompt_extras.push(instruction);
     }
 
+    pub fn system_prompt_extras(&self) -> &[String] {
+        &self.system_prompt_extras
+    }
+
+    /// Replace all additional instructions, e.g. with those of a resumed session
+    pub fn set_system_prompt_extras(&mut self, extras: Vec<String>) {
+        self.system_prompt_extras = extras;
+    }
+
     /// Override the system prompt with custom text
     pub fn set_system_prompt_override(&mut self, template: String) {
         self.system_prompt_override = Some(template);
     }
 
+    /// Pin the original request so it is part of every system prompt for the rest of the session
+    pub fn pin_request(&mut self, request: PinnedRequest) {
+        self.pinned_request = Some(request);
+    }
+
+    /// Pin the first user message in the conversation, unless a request is already pinned
+    pub fn pin_first_user_message(&mut self, messages: &[Message]) {
+        if self.pinned_request.is_some() {
+            return;
+        }
+        if let Some(text) = messages
+            .iter()
+            .find(|m| m.role == Role::User && !m.as_concat_text().trim().is_empty())
+            .map(|m| m.as_concat_text())
+        {
+            self.pinned_request = Some(PinnedRequest::new(text));
+        }
+    }
+
+    pub fn clear_pinned_request(&mut self) {
+        self.pinned_request = None;
+    }
+
+    pub fn pinned_request(&self) -> Option<&PinnedRequest> {
+        self.pinned_request.as_ref()
+    }
+
+    pub fn set_acceptance_checklist(&mut self, checklist: Option<AcceptanceChecklist>) {
+        self.acceptance_checklist = checklist;
+    }
+
+    pub fn acceptance_checklist(&self) -> Option<&AcceptanceChecklist> {
+        self.acceptance_checklist.as_ref()
+    }
+
+    pub fn acceptance_checklist_mut(&mut self) -> Option<&mut AcceptanceChecklist> {
+        self.acceptance_checklist.as_mut()
+    }
+
+    pub fn set_response_policy(&mut self, policy: Option<ResponsePolicy>) {
+        self.response_policy = policy.filter(|policy| !policy.is_empty());
+    }
+
+    pub fn response_policy(&self) -> Option<&ResponsePolicy> {
+        self.response_policy.as_ref()
+    }
+
     /// Normalize a model name (replace - and / with _, lower case)
     fn normalize_model_name(name: &str) -> String {
         name.replace(['-', '/', '.'], "_").to_lowercase()
@@ -59,13 +194,52 @@ impl PromptManager {
         "system.md"
     }
 
+    /// Attach the advertised tools to their extensions, with a summary of each extension and
+    /// a usage hint for each tool. Tools of goose itself, such as the platform tools, are not
+    /// part of an extension and stay out of the groups.
+    pub fn group_tools(extensions_info: &mut [ExtensionInfo], tools: &[Tool]) {
+        for info in extensions_info.iter_mut() {
+            info.tools = tools
+                .iter()
+                .filter(|tool| tool_group(&tool.name) == info.name)
+                .map(|tool| ToolHint {
+                    name: tool.name.clone(),
+                    hint: usage_hint(&tool.description),
+                    read_only: tool
+                        .annotations
+                        .as_ref()
+                        .is_some_and(|annotations| annotations.read_only_hint),
+                })
+                .collect();
+            if !info.tools.is_empty() {
+                info.summary = Some(tools_summary(&info.tools));
+            }
+        }
+    }
+
+    /// Order tools the way the prompt groups them: by extension name, then frontend tools,
+    /// then the tools of goose itself, keeping the order within each group
+    pub fn order_tools(tools: &mut [Tool]) {
+        tools.sort_by_cached_key(|tool| {
+            let group = tool_group(&tool.name).to_string();
+            let rank = match group.as_str() {
+                "frontend" => 1,
+                "platform" => 2,
+                _ => 0,
+            };
+            (rank, group)
+        });
+    }
+
     /// Build the final system prompt
     ///
     /// * `extensions_info` – extension information for each extension/MCP
+    /// * `tools` – the advertised tools, listed under their extensions unless the router is used
     /// * `frontend_instructions` – instructions for the "frontend" tool
     pub fn build_system_prompt(
         &self,
         extensions_info: Vec<ExtensionInfo>,
+        tools: &[Tool],
         frontend_instructions: Option<String>,
         suggest_disable_extensions_prompt: Value,
         model_name: Option<&str>,
@@ -73,6 +247,8 @@ impl PromptManager {
     ) -> String {
         let mut context: HashMap<&str, Value> = HashMap::new();
         let mut extensions_info = extensions_info.clone();
+        // A stable order keeps the prompt cacheable
+        extensions_info.sort_by(|a, b| a.name.cmp(&b.name));
 
         // Add frontend instructions to extensions_info to simplify json rendering
         if let Some(frontend_instructions) = frontend_instructions {
@@ -83,6 +259,10 @@ impl PromptManager {
             ));
         }
 
+        if tool_selection_strategy.is_none() && tool_grouping_enabled() {
+            Self::group_tools(&mut extensions_info, tools);
+        }
+
         context.insert("extensions", serde_json::to_value(extensions_info).unwrap());
 
         match tool_selection_strategy {
@@ -136,6 +316,17 @@ impl PromptManager {
                 .expect("Prompt should render")
         };
 
+        // The pinned request does not change during a session, so keeping it directly after the
+        // base prompt leaves the cacheable prefix intact
+        let base_prompt = match &self.pinned_request {
+            Some(pinned) => format!("{}\n\n{}", base_prompt, pinned.render()),
+            None => base_prompt,
+        };
+        let base_prompt = match &self.response_policy {
+            Some(policy) => format!("{}\n\n{}", base_prompt, policy.render()),
+            None => base_prompt,
+        };
+
         let mut system_prompt_extras = self.system_prompt_extras.clone();
         let config = Config::global();
         let goose_mode = config.get_param("GOOSE_MODE").unwrap_or("auto".to_string());
@@ -149,7 +340,7 @@ impl PromptManager {
                 .push("Right now you are *NOT* in the chat only mode and have access to tool use and system.".to_string());
         }
 
-        if system_prompt_extras.is_empty() {
+        let system_prompt = if system_prompt_extras.is_empty() {
             base_prompt
         } else {
             format!(
@@ -157,6 +348,12 @@ impl PromptManager {
                 base_prompt,
                 system_prompt_extras.join("\n\n")
             )
+        };
+
+        // The checklist changes as items are resolved, so it comes last
+        match &self.acceptance_checklist {
+            Some(checklist) => format!("{}\n\n{}", system_prompt, checklist.render()),
+            None => system_prompt,
         }
     }
 
@@ -207,6 +404,104 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tools_grouped_by_extension() {
+        let tool = |name: &str, description: &str, read_only: bool| {
+            Tool::new(
+                name,
+                description,
+                serde_json::json!({"type": "object"}),
+                Some(mcp_core::tool::ToolAnnotations {
+                    title: None,
+                    read_only_hint: read_only,
+                    destructive_hint: false,
+                    idempotent_hint: read_only,
+                    open_world_hint: false,
+                }),
+            )
+        };
+        let mut tools = vec![
+            tool("platform__manage_extensions", "Manage extensions.", false),
+            tool(
+                "github__list_issues",
+                "List issues. Filters by label.",
+                true,
+            ),
+            tool("show_chart", "Show a chart to the user.", false),
+            tool(
+                "developer__shell",
+                "Run a shell command.\nMore details.",
+                false,
+            ),
+            tool("developer__text_editor", "View and edit files.", false),
+        ];
+        let mut extensions = vec![
+            ExtensionInfo::new("github", "", false),
+            ExtensionInfo::new("developer", "", false),
+            ExtensionInfo::new("frontend", "", false),
+        ];
+
+        PromptManager::group_tools(&mut extensions, &tools);
+        assert_eq!(
+            extensions[0].tools,
+            vec![ToolHint {
+                name: "github__list_issues".to_string(),
+                hint: "List issues.".to_string(),
+                read_only: true,
+            }]
+        );
+        assert_eq!(
+            extensions[0].summary.as_deref(),
+            Some("1 tool, all read-only")
+        );
+        assert_eq!(extensions[1].tools[0].hint, "Run a shell command.");
+        assert_eq!(extensions[1].summary.as_deref(), Some("2 tools"));
+        assert_eq!(extensions[2].tools[0].name, "show_chart");
+
+        PromptManager::order_tools(&mut tools);
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "developer__shell",
+                "developer__text_editor",
+                "github__list_issues",
+                "show_chart",
+                "platform__manage_extensions",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pin_first_user_message() {
+        let mut manager = PromptManager::new();
+        let messages = vec![
+            Message::user().with_text("Migrate the billing service to the new queue"),
+            Message::assistant().with_text("On it"),
+            Message::user().with_text("also update the docs"),
+        ];
+        manager.pin_first_user_message(&messages);
+        assert_eq!(
+            manager.pinned_request().map(|p| p.request.as_str()),
+            Some("Migrate the billing service to the new queue")
+        );
+
+        // An explicitly pinned request is not replaced
+        manager.pin_request(PinnedRequest::new("explicit"));
+        manager.pin_first_user_message(&messages);
+        assert_eq!(
+            manager.pinned_request().map(|p| p.request.as_str()),
+            Some("explicit")
+        );
+    }
+
+    #[test]
+    fn test_pinned_request_render() {
+        let section = PinnedRequest::new("  Ship the release\n").render();
+        assert!(section.starts_with("# Original Request"));
+        assert!(section.ends_with("before finishing.\n\nShip the release"));
+    }
+
     #[test]
     fn test_model_prompt_map_none() {
         // should return system.md for unrecognized/unsupported model names
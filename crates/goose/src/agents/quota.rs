@@ -0,0 +1,129 @@
+//! Counting the agent's provider usage against the configured quotas
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+use crate::config::Config;
+use crate::model::ModelConfig;
+use crate::providers::base::{Provider, ProviderUsage};
+use crate::providers::quota::{ProviderQuotas, QuotaStatus};
+use crate::providers::{create_bare, with_request_filters};
+
+use super::Agent;
+
+/// The fallback provider the agent switched to when a quota was reached
+struct QuotaFallback {
+    provider: String,
+    /// The provider the agent used before, restored once the quota's window resets
+    primary: Arc<dyn Provider>,
+    resets_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for QuotaFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuotaFallback")
+            .field("provider", &self.provider)
+            .field("resets_at", &self.resets_at)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct QuotaState {
+    quotas: ProviderQuotas,
+    fallback: Option<QuotaFallback>,
+}
+
+impl QuotaState {
+    pub(super) fn from_config() -> Self {
+        Self {
+            quotas: ProviderQuotas::from_config(),
+            fallback: None,
+        }
+    }
+
+    fn provider_name(&self) -> Option<String> {
+        self.fallback
+            .as_ref()
+            .map(|fallback| fallback.provider.clone())
+            .or_else(|| Config::global().get_param::<String>("GOOSE_PROVIDER").ok())
+    }
+}
+
+impl Agent {
+    /// Count a completion against the provider's quotas, returning the quota status when it
+    /// first reaches a warning or its limit
+    pub(super) async fn record_provider_usage(&self, usage: &ProviderUsage) -> Option<QuotaStatus> {
+        let mut state = self.quota_state.lock().await;
+        let provider = state.provider_name()?;
+        state
+            .quotas
+            .record(&provider, &usage.usage, Utc::now())
+            .await
+    }
+
+    /// Switch back to the provider used before a quota was reached once the quota's window has
+    /// reset, returning its model
+    pub(super) async fn restore_quota_primary(&self) -> Result<Option<String>> {
+        let mut state = self.quota_state.lock().await;
+        if state
+            .fallback
+            .as_ref()
+            .is_none_or(|fallback| Utc::now() < fallback.resets_at)
+        {
+            return Ok(None);
+        }
+        let Some(fallback) = state.fallback.take() else {
+            return Ok(None);
+        };
+        drop(state);
+
+        let model = fallback.primary.get_model_config().model_name.clone();
+        self.update_provider(fallback.primary).await?;
+        Ok(Some(model))
+    }
+
+    /// Switch to the fallback provider of the quota that was reached, returning its model. The
+    /// fallback is made bare, with only the request filters, as the agent's own wrappers belong
+    /// to the primary provider.
+    pub(super) async fn switch_to_quota_fallback(
+        &self,
+        status: &QuotaStatus,
+    ) -> Result<Option<String>> {
+        let mut state = self.quota_state.lock().await;
+        let Some(quota) = state.quotas.get(&status.provider) else {
+            return Ok(None);
+        };
+        let Some(fallback_provider) = quota.fallback_provider.clone() else {
+            return Ok(None);
+        };
+        let model = match &quota.fallback_model {
+            Some(model) => model.clone(),
+            None => crate::providers::providers()
+                .into_iter()
+                .find(|metadata| metadata.name == fallback_provider)
+                .map(|metadata| metadata.default_model)
+                .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", fallback_provider))?,
+        };
+        let provider = with_request_filters(
+            &fallback_provider,
+            create_bare(&fallback_provider, ModelConfig::new(model.clone()))?,
+        )?;
+        // A fallback that reaches its own quota keeps the first primary, until both have reset
+        let (primary, resets_at) = match state.fallback.take() {
+            Some(fallback) => (fallback.primary, fallback.resets_at.max(status.resets_at)),
+            None => (self.provider().await?, status.resets_at),
+        };
+        state.fallback = Some(QuotaFallback {
+            provider: fallback_provider,
+            primary,
+            resets_at,
+        });
+        drop(state);
+
+        self.update_provider(provider).await?;
+        Ok(Some(model))
+    }
+}
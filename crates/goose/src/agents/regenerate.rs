@@ -0,0 +1,58 @@
+//! Generating an assistant turn again with another provider or model
+
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::Utc;
+
+use crate::model::ModelConfig;
+use crate::providers::{create_bare, with_request_filters};
+use crate::session::alternatives::{self, Alternative};
+
+use super::Agent;
+
+impl Agent {
+    /// Generate the assistant message at `message_index` of a session again with another
+    /// provider and model, from the same history. The new answer is recorded alongside the
+    /// session as an alternative and the session history is left as it is.
+    ///
+    /// The agent's current tools and system prompt are used, and tool calls in the new answer
+    /// are not run.
+    pub async fn regenerate_turn(
+        &self,
+        session_file: &Path,
+        message_index: usize,
+        provider_name: &str,
+        model: &str,
+    ) -> Result<Alternative> {
+        let history = alternatives::history_before(session_file, message_index)?;
+        // Exactly the provider and model asked for, rather than a lead model or fallback the
+        // config would pick, with the same filters on what is sent
+        let provider = with_request_filters(
+            provider_name,
+            create_bare(provider_name, ModelConfig::new(model.to_string()))?,
+        )?;
+        let (tools, toolshim_tools, system_prompt) = self.prepare_tools_and_prompt().await?;
+
+        let (message, usage) = Self::generate_response_from_provider(
+            provider,
+            &system_prompt,
+            &history,
+            &tools,
+            &toolshim_tools,
+        )
+        .await?;
+
+        let alternative = Alternative {
+            message_index,
+            provider: provider_name.to_string(),
+            model: usage.model,
+            created: Utc::now().timestamp(),
+            message,
+            input_tokens: usage.usage.input_tokens,
+            output_tokens: usage.usage.output_tokens,
+        };
+        alternatives::add_alternative(session_file, alternative.clone())?;
+        Ok(alternative)
+    }
+}
@@ -0,0 +1,448 @@
+//! Running heavy tools on remote workers
+//!
+//! Builds and test suites can be sent to another process or host so they do not load the
+//! machine the agent runs on. Tools are assigned to workers by full name, or by extension with
+//! a name ending in `*`:
+//!
+//! ```yaml
+//! GOOSE_REMOTE_WORKERS:
+//!   workers:
+//!     build:
+//!       url: http://build-host:7878
+//!       token_key: BUILD_WORKER_TOKEN
+//!       timeout_secs: 3600
+//!   tools:
+//!     developer__shell: build
+//!     bench__*: build
+//! ```
+//!
+//! The protocol is one HTTP request per call. The agent posts a [`WorkerRequest`] as JSON to
+//! `<url>/tool_calls`, with `Authorization: Bearer <token>` when the worker has a token, read
+//! from the secret named by `token_key`. The worker answers with newline delimited JSON
+//! [`WorkerEvent`]s: logs and progress while the tool runs, which reach the session as tool
+//! notifications, artifacts, which are added to the result as embedded resources, and a
+//! final result or error. `goose worker` serves this protocol with the worker's own
+//! extensions.
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::StreamExt;
+use mcp_core::content::EmbeddedResource;
+use mcp_core::protocol::{JsonRpcMessage, JsonRpcNotification};
+use mcp_core::{Content, ResourceContents, ToolCall, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::config::Config;
+use crate::providers::http_client;
+
+use super::tool_execution::ToolCallResult;
+use super::Agent;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 1800;
+/// Notifications buffered before the worker's output is read more slowly
+const NOTIFICATION_BUFFER: usize = 64;
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkerConfig {
+    pub url: String,
+    /// Name of the secret holding the worker's bearer token
+    #[serde(default)]
+    pub token_key: Option<String>,
+    /// Longest a call may run on the worker
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// A tool call sent to a worker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerRequest {
+    pub id: String,
+    pub name: String,
+    pub arguments: Value,
+    /// The session's working directory, for workers that share the filesystem or a checkout
+    pub working_dir: Option<PathBuf>,
+}
+
+/// One line of a worker's response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorkerEvent {
+    Log {
+        message: String,
+    },
+    Progress {
+        progress: f64,
+        #[serde(default)]
+        total: Option<f64>,
+        #[serde(default)]
+        message: Option<String>,
+    },
+    /// A file produced by the tool, such as a test report
+    Artifact {
+        name: String,
+        #[serde(default)]
+        mime_type: Option<String>,
+        text: String,
+    },
+    Result {
+        content: Vec<Content>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl WorkerEvent {
+    /// The event a worker sends for a notification from the tool it runs
+    pub fn from_notification(message: &JsonRpcMessage) -> Option<Self> {
+        let JsonRpcMessage::Notification(JsonRpcNotification {
+            method,
+            params: Some(params),
+            ..
+        }) = message
+        else {
+            return None;
+        };
+        match method.as_str() {
+            "notifications/message" => {
+                let data = params.get("data").unwrap_or(&Value::Null);
+                let message = match data {
+                    Value::String(text) => text.clone(),
+                    Value::Object(object) => match object.get("output") {
+                        Some(Value::String(output)) => output.clone(),
+                        _ => data.to_string(),
+                    },
+                    other => other.to_string(),
+                };
+                Some(WorkerEvent::Log { message })
+            }
+            "notifications/progress" => Some(WorkerEvent::Progress {
+                progress: params.get("progress")?.as_f64()?,
+                total: params.get("total").and_then(Value::as_f64),
+                message: params
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            }),
+            _ => None,
+        }
+    }
+
+    /// The notification that shows this event in the session, for logs and progress
+    fn notification(&self, worker: &str, request_id: &str) -> Option<JsonRpcMessage> {
+        let params = match self {
+            WorkerEvent::Log { message } => json!({
+                "level": "info",
+                "logger": worker,
+                "data": message,
+            }),
+            WorkerEvent::Progress {
+                progress,
+                total,
+                message,
+            } => json!({
+                "progressToken": request_id,
+                "progress": progress,
+                "total": total,
+                "message": message,
+            }),
+            _ => return None,
+        };
+        let method = match self {
+            WorkerEvent::Log { .. } => "notifications/message",
+            _ => "notifications/progress",
+        };
+        Some(JsonRpcMessage::Notification(JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+        }))
+    }
+}
+
+/// Workers and the tools assigned to them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteWorkers {
+    pub workers: HashMap<String, WorkerConfig>,
+    /// Worker names by tool name or `extension__*` pattern
+    pub tools: HashMap<String, String>,
+}
+
+impl RemoteWorkers {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param("GOOSE_REMOTE_WORKERS")
+            .unwrap_or_default()
+    }
+
+    /// The worker a tool runs on, preferring an assignment by full name over a pattern
+    pub fn worker_for(&self, tool_name: &str) -> Option<(&str, &WorkerConfig)> {
+        let assigned = self.tools.get(tool_name).or_else(|| {
+            self.tools
+                .iter()
+                .filter_map(|(pattern, worker)| {
+                    let prefix = pattern.strip_suffix('*')?;
+                    tool_name
+                        .starts_with(prefix)
+                        .then_some((prefix.len(), worker))
+                })
+                .max_by_key(|(length, _)| *length)
+                .map(|(_, worker)| worker)
+        })?;
+        match self.workers.get_key_value(assigned) {
+            Some((name, config)) => Some((name.as_str(), config)),
+            None => {
+                tracing::warn!(
+                    "{} is assigned to unknown worker {}, running it locally",
+                    tool_name,
+                    assigned
+                );
+                None
+            }
+        }
+    }
+}
+
+fn artifact_content(worker: &str, name: &str, mime_type: Option<String>, text: String) -> Content {
+    Content::Resource(EmbeddedResource {
+        resource: ResourceContents::TextResourceContents {
+            uri: format!("artifact://{}/{}", worker, name),
+            mime_type,
+            text,
+        },
+        annotations: None,
+    })
+}
+
+/// Read a worker's response, forwarding notifications as they arrive
+async fn read_events(
+    response: reqwest::Response,
+    worker: &str,
+    request_id: &str,
+    notifications: mpsc::Sender<JsonRpcMessage>,
+) -> ToolResult<Vec<Content>> {
+    let mut artifacts = Vec::new();
+    let mut buffer = Vec::new();
+    let mut bytes = response.bytes_stream();
+    loop {
+        let line = match buffer.iter().position(|b| *b == b'\n') {
+            Some(end) => buffer.drain(..=end).collect::<Vec<u8>>(),
+            None => match bytes.next().await {
+                Some(Ok(chunk)) => {
+                    buffer.extend_from_slice(&chunk);
+                    continue;
+                }
+                Some(Err(e)) => {
+                    return Err(ToolError::ExecutionError(format!(
+                        "Lost the connection to worker {}: {}",
+                        worker, e
+                    )))
+                }
+                // A last line without a newline
+                None if !buffer.is_empty() => std::mem::take(&mut buffer),
+                None => {
+                    return Err(ToolError::ExecutionError(format!(
+                        "Worker {} closed the connection without a result",
+                        worker
+                    )))
+                }
+            },
+        };
+        let line = String::from_utf8_lossy(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: WorkerEvent = serde_json::from_str(line.trim()).map_err(|e| {
+            ToolError::ExecutionError(format!("Worker {} sent an invalid event: {}", worker, e))
+        })?;
+        if let Some(notification) = event.notification(worker, request_id) {
+            // The session may have stopped listening, the result is still wanted
+            let _ = notifications.send(notification).await;
+            continue;
+        }
+        match event {
+            WorkerEvent::Artifact {
+                name,
+                mime_type,
+                text,
+            } => artifacts.push(artifact_content(worker, &name, mime_type, text)),
+            WorkerEvent::Result { mut content } => {
+                content.append(&mut artifacts);
+                return Ok(content);
+            }
+            WorkerEvent::Error { message } => return Err(ToolError::ExecutionError(message)),
+            WorkerEvent::Log { .. } | WorkerEvent::Progress { .. } => {}
+        }
+    }
+}
+
+/// Send a call to a worker. The call runs in the background, so the agent stays responsive
+/// while the result and notifications stream back.
+pub fn dispatch_to_worker(
+    worker: &str,
+    config: &WorkerConfig,
+    request: WorkerRequest,
+) -> ToolCallResult {
+    let token = config.token_key.as_ref().and_then(|key| {
+        Config::global()
+            .get_secret::<String>(key)
+            .map_err(|e| tracing::warn!("Failed to read the token for worker {}: {}", worker, e))
+            .ok()
+    });
+    let url = format!("{}/tool_calls", config.url.trim_end_matches('/'));
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let worker = worker.to_string();
+
+    let (notification_tx, notification_rx) = mpsc::channel(NOTIFICATION_BUFFER);
+    let (result_tx, result_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let call = async {
+            let client = http_client::default_client().map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to create an HTTP client: {}", e))
+            })?;
+            let mut builder = client.post(&url).json(&request);
+            if let Some(token) = &token {
+                builder = builder.bearer_auth(token);
+            }
+            let response = builder.send().await.map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to reach worker {}: {}", worker, e))
+            })?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(ToolError::ExecutionError(format!(
+                    "Worker {} refused the call ({}): {}",
+                    worker, status, body
+                )));
+            }
+            read_events(response, &worker, &request.id, notification_tx).await
+        };
+        let result = tokio::time::timeout(timeout, call)
+            .await
+            .unwrap_or_else(|_| {
+                Err(ToolError::ExecutionError(format!(
+                    "Worker {} did not finish within {} seconds",
+                    worker,
+                    timeout.as_secs()
+                )))
+            });
+        let _ = result_tx.send(result);
+    });
+
+    ToolCallResult {
+        result: Box::new(Box::pin(async move {
+            result_rx.await.unwrap_or_else(|_| {
+                Err(ToolError::ExecutionError(
+                    "The worker call was dropped".to_string(),
+                ))
+            })
+        })),
+        notification_stream: Some(Box::new(ReceiverStream::new(notification_rx))),
+    }
+}
+
+impl Agent {
+    /// Run a tool on a worker instead of locally, or replace its assignment
+    pub async fn assign_tool_to_worker(&self, tool: &str, worker: &str, config: WorkerConfig) {
+        let mut workers = self.remote_workers.lock().await;
+        workers.workers.insert(worker.to_string(), config);
+        workers.tools.insert(tool.to_string(), worker.to_string());
+    }
+
+    /// Send a call to its worker, if the tool is assigned to one
+    pub(super) async fn dispatch_remote_tool_call(
+        &self,
+        tool_call: &ToolCall,
+        request_id: &str,
+    ) -> Option<ToolCallResult> {
+        let workers = self.remote_workers.lock().await;
+        let (worker, config) = workers.worker_for(&tool_call.name)?;
+        let request = WorkerRequest {
+            id: request_id.to_string(),
+            name: tool_call.name.clone(),
+            arguments: tool_call.arguments.clone(),
+            working_dir: self.working_dir.lock().await.clone(),
+        };
+        Some(dispatch_to_worker(worker, config, request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn workers() -> RemoteWorkers {
+        serde_yaml::from_str(
+            r#"
+            workers:
+              build:
+                url: http://build-host:7878
+              tests:
+                url: http://test-host:7878
+            tools:
+              developer__shell: build
+              bench__*: tests
+              bench__run_*: build
+              github__*: missing
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_worker_assignment() {
+        let workers = workers();
+        assert_eq!(
+            workers.worker_for("developer__shell").map(|(name, _)| name),
+            Some("build")
+        );
+        assert_eq!(
+            workers.worker_for("bench__report").map(|(name, _)| name),
+            Some("tests")
+        );
+        // The longest matching pattern wins
+        assert_eq!(
+            workers
+                .worker_for("bench__run_suite")
+                .map(|(name, config)| (name, config.timeout_secs)),
+            Some(("build", DEFAULT_TIMEOUT_SECS))
+        );
+        assert!(workers.worker_for("developer__text_editor").is_none());
+        assert!(workers.worker_for("github__list_issues").is_none());
+    }
+
+    #[test]
+    fn test_worker_events() {
+        let event: WorkerEvent =
+            serde_json::from_str(r#"{"type": "progress", "progress": 3, "total": 10}"#).unwrap();
+        let Some(JsonRpcMessage::Notification(notification)) =
+            event.notification("build", "call_1")
+        else {
+            panic!("progress should be a notification");
+        };
+        assert_eq!(notification.method, "notifications/progress");
+        assert_eq!(notification.params.unwrap()["progressToken"], "call_1");
+
+        let event: WorkerEvent = serde_json::from_str(
+            r#"{"type": "artifact", "name": "junit.xml", "text": "<testsuites/>"}"#,
+        )
+        .unwrap();
+        assert!(event.notification("build", "call_1").is_none());
+
+        // Notifications round trip through the worker
+        let log = WorkerEvent::Log {
+            message: "Compiling goose".to_string(),
+        };
+        let notification = log.notification("build", "call_1").unwrap();
+        assert_eq!(WorkerEvent::from_notification(&notification), Some(log));
+    }
+}
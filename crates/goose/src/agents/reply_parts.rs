@@ -2,10 +2,11 @@ use anyhow::Result;
 use std::collections::HashSet;
 use std::sync::Arc;
 
+use crate::agents::prompt_manager::{tool_grouping_enabled, PromptManager};
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
-use crate::config::Config;
+use crate::agents::workspace_routers::RouterSelector;
 use crate::message::{Message, MessageContent, ToolRequest};
-use crate::providers::base::{Provider, ProviderUsage};
+use crate::providers::base::{MessageStream, Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
 use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
@@ -21,26 +22,27 @@ impl Agent {
     pub(crate) async fn prepare_tools_and_prompt(
         &self,
     ) -> anyhow::Result<(Vec<Tool>, Vec<Tool>, String)> {
-        // Get tool selection strategy from config
-        let config = Config::global();
-        let router_tool_selection_strategy = config
-            .get_param("GOOSE_ROUTER_TOOL_SELECTION_STRATEGY")
-            .unwrap_or_else(|_| "default".to_string());
-
-        let tool_selection_strategy = match router_tool_selection_strategy.to_lowercase().as_str() {
-            "vector" => Some(RouterToolSelectionStrategy::Vector),
-            "llm" => Some(RouterToolSelectionStrategy::Llm),
-            _ => None,
-        };
+        let router = self.router_tool_selector.lock().await.clone();
+        self.prepare_reply_tools_and_prompt(router.as_ref()).await
+    }
+
+    /// Prepares tools and system prompt for a request of a reply that routes tools with
+    /// `router`
+    pub(crate) async fn prepare_reply_tools_and_prompt(
+        &self,
+        router: Option<&RouterSelector>,
+    ) -> anyhow::Result<(Vec<Tool>, Vec<Tool>, String)> {
+        // Get tool selection strategy from config, unless vector routing has fallen back
+        let tool_selection_strategy = self.router_strategy().await;
 
         // Get tools from extension manager
         let mut tools = match tool_selection_strategy {
             Some(RouterToolSelectionStrategy::Vector) => {
-                self.list_tools_for_router(Some(RouterToolSelectionStrategy::Vector))
+                self.list_router_tools(Some(RouterToolSelectionStrategy::Vector), router)
                     .await
             }
             Some(RouterToolSelectionStrategy::Llm) => {
-                self.list_tools_for_router(Some(RouterToolSelectionStrategy::Llm))
+                self.list_router_tools(Some(RouterToolSelectionStrategy::Llm), router)
                     .await
             }
             _ => self.list_tools(None).await,
@@ -50,6 +52,10 @@ impl Agent {
         for frontend_tool in frontend_tools.values() {
             tools.push(frontend_tool.tool.clone());
         }
+        // List tools in the same groups as the system prompt
+        if tool_selection_strategy.is_none() && tool_grouping_enabled() {
+            PromptManager::order_tools(&mut tools);
+        }
 
         // Prepare system prompt
         let extension_manager = self.extension_manager.lock().await;
@@ -63,6 +69,7 @@ impl Agent {
         let prompt_manager = self.prompt_manager.lock().await;
         let mut system_prompt = prompt_manager.build_system_prompt(
             extensions_info,
+            &tools,
             self.frontend_instructions.lock().await.clone(),
             extension_manager.suggest_disable_extensions_prompt().await,
             Some(model_name),
@@ -147,6 +154,34 @@ impl Agent {
         Ok((response, usage))
     }
 
+    /// Stream a response from the LLM provider, in parts that join into the message
+    /// [`Self::generate_response_from_provider`] would return. With toolshim the tool calls are
+    /// interpreted from the whole response, so it is sent in one part.
+    pub(crate) async fn stream_response_from_provider(
+        provider: Arc<dyn Provider>,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        toolshim_tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        if provider.get_model_config().toolshim {
+            let (response, usage) = Self::generate_response_from_provider(
+                provider,
+                system_prompt,
+                messages,
+                tools,
+                toolshim_tools,
+            )
+            .await?;
+            return Ok(Box::pin(futures::stream::once(async move {
+                Ok((Some(response), Some(usage)))
+            })));
+        }
+        provider
+            .complete_streaming(system_prompt, messages, tools)
+            .await
+    }
+
     /// Categorize tool requests from the response into different types
     /// Returns:
     /// - frontend_requests: Tool requests that should be handled by the frontend
@@ -221,11 +256,13 @@ impl Agent {
         session_config: crate::agents::types::SessionConfig,
         usage: &crate::providers::base::ProviderUsage,
         messages_length: usize,
+        cost: &crate::agents::cost_tracker::SessionCost,
     ) -> Result<()> {
         let session_file_path = session::storage::get_path(session_config.id.clone());
         let mut metadata = session::storage::read_metadata(&session_file_path)?;
 
         metadata.schedule_id = session_config.schedule_id.clone();
+        metadata.tags = usage.tags.clone();
 
         metadata.total_tokens = usage.usage.total_tokens;
         metadata.input_tokens = usage.usage.input_tokens;
@@ -247,6 +284,8 @@ impl Agent {
             metadata.accumulated_output_tokens,
             usage.usage.output_tokens,
         );
+        metadata.accumulated_cost = Some(cost.total);
+        metadata.unpriced_responses = cost.unpriced_responses;
 
         session::storage::update_metadata(&session_file_path, &metadata).await?;
 
@@ -0,0 +1,295 @@
+//! Response language and formatting policy
+//!
+//! Embedders that need consistent output can set a policy for a session with
+//! [`Agent::set_response_policy`](super::Agent::set_response_policy), or for a profile in the
+//! config:
+//!
+//! ```yaml
+//! GOOSE_RESPONSE_POLICY:
+//!   language: German
+//!   units: metric
+//!   date_format: iso
+//!   code_fence_languages: true
+//!   max_words: 300
+//! ```
+//!
+//! The policy is added to the system prompt, and each assistant response is checked against
+//! it afterwards. Violations are reported as an agent event rather than changing the response.
+//! The response language is only enforced through the prompt.
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::message::Message;
+
+static IMPERIAL_UNITS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b\d+(?:[.,]\d+)?\s?(?:miles?|mi|feet|foot|ft|inch(?:es)?|pounds?|lbs?|ounces?|oz|gallons?|yards?|yd|°F|degrees fahrenheit)\b").unwrap()
+});
+static METRIC_UNITS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)\b\d+(?:[.,]\d+)?\s?(?:kilomet(?:er|re)s?|km|met(?:er|re)s?|centimet(?:er|re)s?|cm|mm|kilograms?|kg|grams?|lit(?:er|re)s?|°C|degrees celsius)\b").unwrap()
+});
+static ISO_DATE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d{4}-\d{2}-\d{2}\b").unwrap());
+static SLASH_DATE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{2}|\d{4})\b").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Units {
+    Metric,
+    Imperial,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateFormat {
+    /// 2025-01-31
+    Iso,
+    /// 01/31/2025
+    Us,
+    /// 31/01/2025
+    European,
+}
+
+impl DateFormat {
+    fn example(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::Us => "MM/DD/YYYY",
+            DateFormat::European => "DD/MM/YYYY",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponsePolicy {
+    pub language: Option<String>,
+    pub units: Option<Units>,
+    pub date_format: Option<DateFormat>,
+    /// Whether every code block has to name its language
+    #[serde(default)]
+    pub code_fence_languages: bool,
+    /// Maximum number of words in a response, not counting code blocks
+    pub max_words: Option<usize>,
+}
+
+/// A rule of the response policy that a response did not follow
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PolicyViolation {
+    pub rule: String,
+    pub message: String,
+}
+
+impl PolicyViolation {
+    fn new(rule: &str, message: String) -> Self {
+        Self {
+            rule: rule.to_string(),
+            message,
+        }
+    }
+}
+
+/// Split text into prose and the opening lines of its code blocks
+fn split_code_blocks(text: &str) -> (String, Vec<&str>) {
+    let mut prose = Vec::new();
+    let mut openers = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        if let Some(info) = line.trim_start().strip_prefix("```") {
+            if !in_code {
+                openers.push(info.trim());
+            }
+            in_code = !in_code;
+        } else if !in_code {
+            prose.push(line);
+        }
+    }
+    (prose.join("\n"), openers)
+}
+
+/// The first date in the prose that is not written in the policy's format
+fn misformatted_date(date_format: DateFormat, prose: &str) -> Option<String> {
+    if date_format != DateFormat::Iso {
+        if let Some(found) = ISO_DATE.find(prose) {
+            return Some(found.as_str().to_string());
+        }
+    }
+    SLASH_DATE
+        .captures_iter(prose)
+        .find(|captures| {
+            let first: u32 = captures[1].parse().unwrap_or(0);
+            let second: u32 = captures[2].parse().unwrap_or(0);
+            match date_format {
+                DateFormat::Iso => true,
+                // The day can only come first if it is past the 12th
+                DateFormat::Us => first > 12,
+                DateFormat::European => second > 12,
+            }
+        })
+        .map(|captures| captures[0].to_string())
+}
+
+impl ResponsePolicy {
+    /// The policy configured in `GOOSE_RESPONSE_POLICY`, if any
+    pub fn from_config() -> Option<Self> {
+        Config::global()
+            .get_param::<ResponsePolicy>("GOOSE_RESPONSE_POLICY")
+            .ok()
+            .filter(|policy| !policy.is_empty())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The policy as a section of the system prompt
+    pub fn render(&self) -> String {
+        let mut rules = Vec::new();
+        if let Some(language) = &self.language {
+            rules.push(format!(
+                "Respond in {}, whatever language the user writes in.",
+                language
+            ));
+        }
+        match self.units {
+            Some(Units::Metric) => rules.push("Use metric units.".to_string()),
+            Some(Units::Imperial) => rules.push("Use imperial units.".to_string()),
+            None => {}
+        }
+        if let Some(date_format) = &self.date_format {
+            rules.push(format!("Write dates as {}.", date_format.example()));
+        }
+        if self.code_fence_languages {
+            rules.push("Label every code block with its language, e.g. ```python.".to_string());
+        }
+        if let Some(max_words) = self.max_words {
+            rules.push(format!(
+                "Keep responses under {} words, not counting code blocks.",
+                max_words
+            ));
+        }
+
+        let rules: Vec<String> = rules
+            .into_iter()
+            .map(|rule| format!("- {}", rule))
+            .collect();
+        format!(
+            "# Response Policy\n\nFollow these rules in every response:\n{}",
+            rules.join("\n")
+        )
+    }
+
+    /// Check the text of a response against the policy
+    pub fn validate(&self, message: &Message) -> Vec<PolicyViolation> {
+        let text = message.as_concat_text();
+        let (prose, code_openers) = split_code_blocks(&text);
+        let mut violations = Vec::new();
+
+        let wrong_units = match self.units {
+            Some(Units::Metric) => Some(&*IMPERIAL_UNITS),
+            Some(Units::Imperial) => Some(&*METRIC_UNITS),
+            None => None,
+        };
+        if let Some(found) = wrong_units.and_then(|re| re.find(&prose)) {
+            violations.push(PolicyViolation::new(
+                "units",
+                format!("'{}' does not use the required units", found.as_str()),
+            ));
+        }
+
+        if let Some(date_format) = self.date_format {
+            if let Some(date) = misformatted_date(date_format, &prose) {
+                violations.push(PolicyViolation::new(
+                    "date_format",
+                    format!("'{}' is not written as {}", date, date_format.example()),
+                ));
+            }
+        }
+
+        if self.code_fence_languages {
+            let unlabeled = code_openers.iter().filter(|info| info.is_empty()).count();
+            if unlabeled > 0 {
+                violations.push(PolicyViolation::new(
+                    "code_fence_languages",
+                    format!("{} code block(s) do not name a language", unlabeled),
+                ));
+            }
+        }
+
+        if let Some(max_words) = self.max_words {
+            let words = prose.split_whitespace().count();
+            if words > max_words {
+                violations.push(PolicyViolation::new(
+                    "max_words",
+                    format!(
+                        "The response has {} words, over the limit of {}",
+                        words, max_words
+                    ),
+                ));
+            }
+        }
+
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> ResponsePolicy {
+        ResponsePolicy {
+            language: Some("German".to_string()),
+            units: Some(Units::Metric),
+            date_format: Some(DateFormat::Iso),
+            code_fence_languages: true,
+            max_words: Some(20),
+        }
+    }
+
+    #[test]
+    fn test_render_lists_rules() {
+        let rendered = policy().render();
+        assert!(rendered.starts_with("# Response Policy"));
+        assert!(rendered.contains("- Respond in German"));
+        assert!(rendered.contains("YYYY-MM-DD"));
+        assert!(rendered.contains("under 20 words"));
+        assert!(ResponsePolicy::default().is_empty());
+    }
+
+    #[test]
+    fn test_compliant_response_has_no_violations() {
+        let message = Message::assistant().with_text(
+            "Der Lauf am 2025-01-31 war 5 km lang.\n```python\n# 3 miles on 01/31/2025\n```",
+        );
+        assert!(policy().validate(&message).is_empty());
+    }
+
+    #[test]
+    fn test_violations_are_flagged() {
+        let message = Message::assistant().with_text(format!(
+            "The run on 01/31/2025 was 3 miles long.\n```\nls\n```\n{}",
+            "word ".repeat(20)
+        ));
+        let rules: Vec<String> = policy()
+            .validate(&message)
+            .into_iter()
+            .map(|violation| violation.rule)
+            .collect();
+        assert_eq!(
+            rules,
+            vec!["units", "date_format", "code_fence_languages", "max_words"]
+        );
+
+        let us = ResponsePolicy {
+            date_format: Some(DateFormat::Us),
+            ..Default::default()
+        };
+        let message = Message::assistant().with_text("Due 01/31/2025, not 31/01/2025");
+        assert_eq!(
+            us.validate(&message)[0].message,
+            "'31/01/2025' is not written as MM/DD/YYYY"
+        );
+    }
+}
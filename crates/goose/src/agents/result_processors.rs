@@ -0,0 +1,156 @@
+//! Post-processing of tool results by the application embedding the agent
+//!
+//! An embedder can attach functions to a tool, or to every tool of an extension with a name
+//! ending in `*`, that rewrite its results before they reach the model, e.g. to strip ANSI
+//! codes from shell output or convert fetched HTML to markdown. Processors run in the order
+//! they were added.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use futures::FutureExt;
+use mcp_core::{Content, TextContent};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::tool_execution::ToolCallResult;
+use super::Agent;
+
+pub type ResultProcessor = Arc<dyn Fn(Vec<Content>) -> Vec<Content> + Send + Sync>;
+
+static ANSI_ESCAPE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-9;?]*[ -/]*[@-~]|\x1b\][^\x07]*\x07").unwrap());
+
+/// Remove terminal colour and cursor codes from text
+pub fn strip_ansi_codes(text: &str) -> String {
+    ANSI_ESCAPE.replace_all(text, "").into_owned()
+}
+
+/// A processor that rewrites the text content of a result
+pub fn text_processor<F>(f: F) -> ResultProcessor
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    Arc::new(move |contents: Vec<Content>| {
+        contents
+            .into_iter()
+            .map(|content| match content {
+                Content::Text(text) => Content::Text(TextContent {
+                    text: f(&text.text),
+                    annotations: text.annotations,
+                }),
+                other => other,
+            })
+            .collect()
+    })
+}
+
+/// Processors by tool name or `prefix*` pattern
+#[derive(Clone, Default)]
+pub struct ResultProcessors {
+    processors: Vec<(String, ResultProcessor)>,
+}
+
+impl std::fmt::Debug for ResultProcessors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tools: HashMap<&str, usize> = HashMap::new();
+        for (tool, _) in &self.processors {
+            *tools.entry(tool.as_str()).or_default() += 1;
+        }
+        f.debug_struct("ResultProcessors")
+            .field("tools", &tools)
+            .finish()
+    }
+}
+
+impl ResultProcessors {
+    pub fn add(&mut self, tool: &str, processor: ResultProcessor) {
+        self.processors.push((tool.to_string(), processor));
+    }
+
+    /// Remove the processors registered under a tool name or pattern, returning how many
+    pub fn remove(&mut self, tool: &str) -> usize {
+        let count = self.processors.len();
+        self.processors.retain(|(registered, _)| registered != tool);
+        count - self.processors.len()
+    }
+
+    /// The processors that apply to a tool, in the order they were added
+    pub fn for_tool(&self, tool_name: &str) -> Vec<ResultProcessor> {
+        self.processors
+            .iter()
+            .filter(|(pattern, _)| match pattern.strip_suffix('*') {
+                Some(prefix) => tool_name.starts_with(prefix),
+                None => pattern == tool_name,
+            })
+            .map(|(_, processor)| processor.clone())
+            .collect()
+    }
+}
+
+/// Run a tool's successful result through its processors
+pub(super) fn process_result(
+    result: ToolCallResult,
+    processors: Vec<ResultProcessor>,
+) -> ToolCallResult {
+    if processors.is_empty() {
+        return result;
+    }
+    ToolCallResult {
+        result: Box::new(result.result.map(move |result| {
+            result.map(|contents| {
+                processors
+                    .iter()
+                    .fold(contents, |contents, processor| processor(contents))
+            })
+        })),
+        notification_stream: result.notification_stream,
+    }
+}
+
+impl Agent {
+    /// Rewrite the results of a tool, or of tools matching `prefix*`, before the model sees them
+    pub async fn add_result_processor(&self, tool: &str, processor: ResultProcessor) {
+        self.result_processors.lock().await.add(tool, processor);
+    }
+
+    /// Remove the processors registered under a tool name or pattern
+    pub async fn remove_result_processors(&self, tool: &str) -> usize {
+        self.result_processors.lock().await.remove(tool)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi_codes() {
+        assert_eq!(
+            strip_ansi_codes("\x1b[32mok\x1b[0m 3 passed\x1b[2K"),
+            "ok 3 passed"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_processors_apply_in_order() {
+        let mut processors = ResultProcessors::default();
+        processors.add("developer__shell", text_processor(strip_ansi_codes));
+        processors.add(
+            "developer__*",
+            text_processor(|text| text.trim().to_string()),
+        );
+        processors.add("fetch__fetch", text_processor(|text| text.to_uppercase()));
+
+        let applied = processors.for_tool("developer__shell");
+        assert_eq!(applied.len(), 2);
+        let result = process_result(
+            ToolCallResult::from(Ok(vec![Content::text("  \x1b[31merror\x1b[0m \n")])),
+            applied,
+        );
+        let contents = result.result.await.unwrap();
+        assert_eq!(contents[0].as_text(), Some("error"));
+
+        assert_eq!(processors.remove("developer__*"), 1);
+        assert_eq!(processors.for_tool("developer__shell").len(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Tool routing that survives a broken vector store
+//!
+//! Vector routing keeps the tools of all extensions in a local vector store and lets the model
+//! search it. When the store cannot be opened or written, for example because LanceDB is missing
+//! a dependency or its table is corrupted, the agent falls back to offering every tool directly,
+//! as it does without a router, rather than failing. The fallback is reported once with
+//! `AgentEvent::ToolRouting`.
+//!
+//! While routing is degraded a background task keeps trying to open a fresh store, waiting
+//! longer after each failure. Once it succeeds the tools are indexed again at the start of the
+//! next reply, and vector routing is back.
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
+
+use crate::agents::router_tool_selector::{
+    RouterToolSelectionStrategy, RouterToolSelector, VectorToolSelector,
+};
+use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
+use crate::agents::tool_vectordb::generate_table_id;
+use crate::config::Config;
+use crate::providers::base::Provider;
+
+use super::Agent;
+
+/// Wait before the first attempt to open the store again
+const FIRST_RETRY: Duration = Duration::from_secs(30);
+/// Longest wait between attempts
+const MAX_RETRY: Duration = Duration::from_secs(15 * 60);
+
+/// The router strategy set with `GOOSE_ROUTER_TOOL_SELECTION_STRATEGY`, if any
+pub fn configured_router_strategy() -> Option<RouterToolSelectionStrategy> {
+    let strategy: String = Config::global()
+        .get_param("GOOSE_ROUTER_TOOL_SELECTION_STRATEGY")
+        .unwrap_or_else(|_| "default".to_string());
+    match strategy.to_lowercase().as_str() {
+        "vector" => Some(RouterToolSelectionStrategy::Vector),
+        "llm" => Some(RouterToolSelectionStrategy::Llm),
+        _ => None,
+    }
+}
+
+/// A change in how tools reach the model, reported to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ToolRoutingStatus {
+    /// Vector routing failed, and all tools are offered directly until it recovers
+    Degraded { reason: String },
+    /// Vector routing works again
+    Restored,
+}
+
+impl std::fmt::Display for ToolRoutingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Degraded { reason } => write!(
+                f,
+                "Tool search is unavailable, all tools are offered directly: {}",
+                reason
+            ),
+            Self::Restored => write!(f, "Tool search is available again"),
+        }
+    }
+}
+
+type RecoveredSelector = Arc<std::sync::Mutex<Option<Box<dyn RouterToolSelector>>>>;
+
+/// Vector routing that has failed, and the task trying to bring it back
+pub(super) struct RouterFallback {
+    reason: String,
+    reported: bool,
+    recovered: RecoveredSelector,
+    retry: Option<JoinHandle<()>>,
+}
+
+impl RouterFallback {
+    fn new(reason: String, provider: Option<Arc<dyn Provider>>) -> Self {
+        let recovered: RecoveredSelector = Arc::new(std::sync::Mutex::new(None));
+        let retry = provider.map(|provider| spawn_retry(provider, recovered.clone()));
+        Self {
+            reason,
+            reported: false,
+            recovered,
+            retry,
+        }
+    }
+}
+
+impl Drop for RouterFallback {
+    fn drop(&mut self) {
+        if let Some(retry) = self.retry.take() {
+            retry.abort();
+        }
+    }
+}
+
+/// The wait after `failures` failed attempts
+fn retry_delay(failures: u32) -> Duration {
+    FIRST_RETRY
+        .saturating_mul(2u32.saturating_pow(failures))
+        .min(MAX_RETRY)
+}
+
+fn spawn_retry(provider: Arc<dyn Provider>, recovered: RecoveredSelector) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut failures = 0;
+        loop {
+            tokio::time::sleep(retry_delay(failures)).await;
+            match VectorToolSelector::new(provider.clone(), generate_table_id()).await {
+                Ok(selector) => {
+                    tracing::info!("The vector store for tool routing is available again");
+                    *recovered.lock().unwrap() = Some(Box::new(selector));
+                    break;
+                }
+                Err(e) => {
+                    failures += 1;
+                    tracing::debug!(
+                        "The vector store for tool routing is still unavailable: {}",
+                        e
+                    );
+                }
+            }
+        }
+    })
+}
+
+impl Agent {
+    /// The router strategy in effect: the configured one, unless vector routing has failed and
+    /// all tools are offered directly
+    pub async fn router_strategy(&self) -> Option<RouterToolSelectionStrategy> {
+        match configured_router_strategy() {
+            Some(RouterToolSelectionStrategy::Vector)
+                if self.router_fallback.lock().await.is_some() =>
+            {
+                None
+            }
+            strategy => strategy,
+        }
+    }
+
+    /// Stop vector routing after its store failed, and keep trying to restore it in the
+    /// background
+    pub(super) async fn degrade_tool_routing(&self, reason: String) {
+        *self.router_tool_selector.lock().await = None;
+        self.workspace_routers.lock().await.clear();
+        let mut fallback = self.router_fallback.lock().await;
+        if fallback.is_some() {
+            return;
+        }
+        tracing::warn!(
+            "Vector tool routing is unavailable, offering all tools directly: {}",
+            reason
+        );
+        let provider = self.provider().await.ok();
+        *fallback = Some(RouterFallback::new(reason, provider));
+    }
+
+    /// Stop retrying vector routing, once a new selector has been set up
+    pub(super) async fn clear_router_fallback(&self) {
+        self.router_fallback.lock().await.take();
+    }
+
+    /// Report a new fallback, or restore vector routing once the store is available again.
+    /// Called at the start of a reply, before its tools are prepared.
+    pub(crate) async fn check_tool_routing(&self) -> Option<ToolRoutingStatus> {
+        let recovered = {
+            let mut guard = self.router_fallback.lock().await;
+            let fallback = guard.as_mut()?;
+            let recovered = fallback.recovered.lock().unwrap().take();
+            let Some(recovered) = recovered else {
+                if fallback.reported {
+                    return None;
+                }
+                fallback.reported = true;
+                return Some(ToolRoutingStatus::Degraded {
+                    reason: fallback.reason.clone(),
+                });
+            };
+            recovered
+        };
+
+        let selector = Arc::new(recovered);
+        let indexed = {
+            let extension_manager = self.extension_manager.lock().await;
+            ToolRouterIndexManager::index_all_tools(&selector, &extension_manager).await
+        };
+        match indexed {
+            Ok(()) => {
+                *self.router_tool_selector.lock().await = Some(selector);
+                self.clear_router_fallback().await;
+                tracing::info!("Vector tool routing restored");
+                Some(ToolRoutingStatus::Restored)
+            }
+            Err(e) => {
+                // Start over with a new store and a new retry
+                self.clear_router_fallback().await;
+                self.degrade_tool_routing(e.to_string()).await;
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_grows_to_a_limit() {
+        assert_eq!(retry_delay(0), FIRST_RETRY);
+        assert_eq!(retry_delay(2), Duration::from_secs(120));
+        assert_eq!(retry_delay(10), MAX_RETRY);
+        assert_eq!(retry_delay(40), MAX_RETRY);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_is_reported_once() {
+        let agent = Agent::new();
+        assert!(agent.check_tool_routing().await.is_none());
+
+        agent
+            .degrade_tool_routing("table tools_20250101 is corrupted".to_string())
+            .await;
+        assert_eq!(
+            agent.check_tool_routing().await,
+            Some(ToolRoutingStatus::Degraded {
+                reason: "table tools_20250101 is corrupted".to_string()
+            })
+        );
+        assert!(agent.check_tool_routing().await.is_none());
+        assert!(agent.router_tool_selector.lock().await.is_none());
+    }
+}
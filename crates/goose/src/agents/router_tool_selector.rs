@@ -11,6 +11,7 @@ use std::env;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::agents::tool_budgets::record_tool_call_usage;
 use crate::agents::tool_vectordb::ToolVectorDB;
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -32,6 +33,29 @@ pub trait RouterToolSelector: Send + Sync {
     fn selector_type(&self) -> RouterToolSelectionStrategy;
 }
 
+/// The provider used for embeddings, `GOOSE_EMBEDDING_MODEL_PROVIDER` if it is set or
+/// otherwise the agent's own provider
+pub(crate) fn create_embedding_provider(provider: Arc<dyn Provider>) -> Result<Arc<dyn Provider>> {
+    if env::var("GOOSE_EMBEDDING_MODEL_PROVIDER").is_ok() {
+        // If env var is set, create a new provider for embeddings
+        // Get embedding model and provider from environment variables
+        let embedding_model = env::var("GOOSE_EMBEDDING_MODEL")
+            .unwrap_or_else(|_| "text-embedding-3-small".to_string());
+        let embedding_provider_name =
+            env::var("GOOSE_EMBEDDING_MODEL_PROVIDER").unwrap_or_else(|_| "openai".to_string());
+
+        // Create the provider using the factory
+        let model_config = ModelConfig::new(embedding_model);
+        providers::create(&embedding_provider_name, model_config).context(format!(
+            "Failed to create {} provider for embeddings. If using OpenAI, make sure OPENAI_API_KEY env var is set or that you have configured the OpenAI provider via Goose before.",
+            embedding_provider_name
+        ))
+    } else {
+        // Otherwise fall back to using the same provider instance as used for base goose model
+        Ok(provider)
+    }
+}
+
 pub struct VectorToolSelector {
     vector_db: Arc<RwLock<ToolVectorDB>>,
     embedding_provider: Arc<dyn Provider>,
@@ -42,24 +66,7 @@ impl VectorToolSelector {
     pub async fn new(provider: Arc<dyn Provider>, table_name: String) -> Result<Self> {
         let vector_db = ToolVectorDB::new(Some(table_name)).await?;
 
-        let embedding_provider = if env::var("GOOSE_EMBEDDING_MODEL_PROVIDER").is_ok() {
-            // If env var is set, create a new provider for embeddings
-            // Get embedding model and provider from environment variables
-            let embedding_model = env::var("GOOSE_EMBEDDING_MODEL")
-                .unwrap_or_else(|_| "text-embedding-3-small".to_string());
-            let embedding_provider_name =
-                env::var("GOOSE_EMBEDDING_MODEL_PROVIDER").unwrap_or_else(|_| "openai".to_string());
-
-            // Create the provider using the factory
-            let model_config = ModelConfig::new(embedding_model);
-            providers::create(&embedding_provider_name, model_config).context(format!(
-                "Failed to create {} provider for embeddings. If using OpenAI, make sure OPENAI_API_KEY env var is set or that you have configured the OpenAI provider via Goose before.",
-                embedding_provider_name
-            ))?
-        } else {
-            // Otherwise fall back to using the same provider instance as used for base goose model
-            provider.clone()
-        };
+        let embedding_provider = create_embedding_provider(provider)?;
 
         Ok(Self {
             vector_db: Arc::new(RwLock::new(vector_db)),
@@ -69,6 +76,22 @@ impl VectorToolSelector {
     }
 }
 
+/// Each selector has a table of its own, so the table goes once the selector is dropped, as
+/// when its workspace is no longer among the recent ones
+impl Drop for VectorToolSelector {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let vector_db = self.vector_db.clone();
+        runtime.spawn(async move {
+            if let Err(e) = vector_db.read().await.drop_table().await {
+                tracing::warn!("Failed to drop the tool router's table: {}", e);
+            }
+        });
+    }
+}
+
 #[async_trait]
 impl RouterToolSelector for VectorToolSelector {
     async fn select_tools(&self, params: Value) -> Result<Vec<Content>, ToolError> {
@@ -260,8 +283,9 @@ impl RouterToolSelector for LLMToolSelector {
                 .await
                 .map_err(|e| ToolError::ExecutionError(format!("Failed to search tools: {}", e)))?;
 
-            // Extract just the message content from the response
-            let (message, _usage) = response;
+            // The search is part of the cost of the tool call, for its budget
+            let (message, usage) = response;
+            record_tool_call_usage(&self.llm_provider.get_model_config(), &usage);
             let text = message.content[0].as_text().unwrap_or_default();
 
             // Split the response into individual tool entries
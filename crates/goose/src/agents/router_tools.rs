@@ -1,4 +1,5 @@
 use super::platform_tools::{
+    PLATFORM_ANCHOR_TOOL_NAME, PLATFORM_EXTENSION_LOGS_TOOL_NAME,
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
     PLATFORM_READ_RESOURCE_TOOL_NAME, PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
 };
@@ -102,10 +103,14 @@ pub fn llm_search_tool_prompt() -> String {
     - {}
     - {}
     - {}
+    - {}
+    - {}
     "#,
         PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
         PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
         PLATFORM_READ_RESOURCE_TOOL_NAME,
-        PLATFORM_LIST_RESOURCES_TOOL_NAME
+        PLATFORM_LIST_RESOURCES_TOOL_NAME,
+        PLATFORM_EXTENSION_LOGS_TOOL_NAME,
+        PLATFORM_ANCHOR_TOOL_NAME
     )
 }
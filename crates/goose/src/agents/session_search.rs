@@ -0,0 +1,32 @@
+//! Search over past sessions from the agent, which provides the embeddings for semantic search
+
+use anyhow::Result;
+
+use crate::session::search::{open_synced_index, SearchMode, SessionSearchResult};
+
+use super::router_tool_selector::create_embedding_provider;
+use super::Agent;
+
+impl Agent {
+    /// Search every persisted session by keyword or by meaning. Embeddings come from
+    /// `GOOSE_EMBEDDING_MODEL_PROVIDER` if it is set, or otherwise from the agent's provider.
+    pub async fn search_sessions(
+        &self,
+        query: &str,
+        mode: SearchMode,
+        limit: usize,
+    ) -> Result<Vec<SessionSearchResult>> {
+        let mut index = open_synced_index()?;
+        match mode {
+            SearchMode::Keyword => Ok(index.search(query, limit)),
+            SearchMode::Semantic => {
+                let provider = create_embedding_provider(self.provider().await?)?;
+                let results = index
+                    .search_semantic(provider.as_ref(), query, limit)
+                    .await?;
+                index.save()?;
+                Ok(results)
+            }
+        }
+    }
+}
@@ -0,0 +1,531 @@
+//! Undo for file-modifying tool calls
+//!
+//! With `GOOSE_TOOL_SNAPSHOTS: true`, the files named in a tool call's arguments (`path`,
+//! `file_path` or `paths`, relative to the session's working directory) are read before the
+//! call runs. If the call changed any of them, the previous contents are kept so the change
+//! can be undone with [`Agent::undo_last_tool_effects`] or by asking for it in the
+//! conversation. A file that changed again after the call is not restored, so later edits are
+//! never lost. Changes made by shell commands to files that are not named in the arguments are
+//! not captured.
+//!
+//! Only the most recent `GOOSE_TOOL_SNAPSHOTS_MAX` changes are kept, and files larger than
+//! `GOOSE_TOOL_SNAPSHOTS_MAX_FILE_SIZE` bytes are not snapshotted.
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use futures::FutureExt;
+use mcp_core::{Content, ToolError, ToolResult};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+
+use super::tool_execution::ToolCallResult;
+use super::{Agent, SessionConfig};
+
+const DEFAULT_MAX_SNAPSHOTS: usize = 20;
+const DEFAULT_MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
+
+const PATH_ARGUMENTS: [&str; 2] = ["path", "file_path"];
+const PATHS_ARGUMENT: &str = "paths";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotPolicy {
+    pub enabled: bool,
+    /// Number of tool calls that can be undone
+    pub max_snapshots: usize,
+    pub max_file_size: u64,
+}
+
+impl Default for SnapshotPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_snapshots: DEFAULT_MAX_SNAPSHOTS,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+}
+
+impl SnapshotPolicy {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            enabled: config
+                .get_param("GOOSE_TOOL_SNAPSHOTS")
+                .unwrap_or(defaults.enabled),
+            max_snapshots: config
+                .get_param("GOOSE_TOOL_SNAPSHOTS_MAX")
+                .unwrap_or(defaults.max_snapshots),
+            max_file_size: config
+                .get_param("GOOSE_TOOL_SNAPSHOTS_MAX_FILE_SIZE")
+                .unwrap_or(defaults.max_file_size),
+        }
+    }
+
+    /// Snapshot the files a tool call names, relative to `working_dir`, or None if there is
+    /// nothing to snapshot
+    pub fn capture(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+        working_dir: &Path,
+    ) -> Option<ToolSnapshot> {
+        if !self.enabled {
+            return None;
+        }
+        let files: Vec<FileSnapshot> = argument_paths(arguments, working_dir)
+            .iter()
+            .filter(|path| match fs::metadata(path) {
+                Ok(metadata) => metadata.is_file() && metadata.len() <= self.max_file_size,
+                // The tool may create the file
+                Err(_) => true,
+            })
+            .map(|path| FileSnapshot::read(path))
+            .collect();
+        (!files.is_empty()).then(|| ToolSnapshot {
+            tool_name: tool_name.to_string(),
+            files,
+        })
+    }
+}
+
+/// Hash of a file's content, or None if it does not exist
+fn content_hash(path: &Path) -> Option<Vec<u8>> {
+    fs::read(path)
+        .ok()
+        .map(|content| Sha256::digest(content).to_vec())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileSnapshot {
+    path: PathBuf,
+    /// None if the file did not exist
+    content: Option<Vec<u8>>,
+    /// Hash of the file as the tool call left it, None if it did not exist
+    after: Option<Vec<u8>>,
+}
+
+impl FileSnapshot {
+    fn read(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            content: fs::read(path).ok(),
+            after: None,
+        }
+    }
+
+    fn is_current(&self) -> bool {
+        fs::read(&self.path).ok() == self.content
+    }
+
+    /// Whether the file changed again after the tool call
+    fn changed_since_call(&self) -> bool {
+        content_hash(&self.path) != self.after
+    }
+
+    fn restore(&self) -> Result<()> {
+        match &self.content {
+            Some(content) => fs::write(&self.path, content),
+            None if self.path.exists() => fs::remove_file(&self.path),
+            None => Ok(()),
+        }
+        .with_context(|| format!("Failed to restore {}", self.path.display()))
+    }
+}
+
+/// The state of the files a tool call was about to change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolSnapshot {
+    pub tool_name: String,
+    files: Vec<FileSnapshot>,
+}
+
+impl ToolSnapshot {
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.files.iter().map(|file| file.path.clone()).collect()
+    }
+
+    /// The snapshot of the files the tool call changed, with how it left them, or None if it
+    /// changed nothing
+    fn settle(mut self) -> Option<Self> {
+        self.files.retain(|file| !file.is_current());
+        if self.files.is_empty() {
+            return None;
+        }
+        for file in &mut self.files {
+            file.after = content_hash(&file.path);
+        }
+        Some(self)
+    }
+
+    /// Fail if any of the files changed after the tool call
+    fn check_unchanged(&self) -> Result<()> {
+        if let Some(file) = self.files.iter().find(|file| file.changed_since_call()) {
+            bail!(
+                "{} changed after {} ran, so undoing it would lose those changes",
+                file.path.display(),
+                self.tool_name
+            );
+        }
+        Ok(())
+    }
+
+    fn restore(&self) -> Result<()> {
+        self.files.iter().try_for_each(FileSnapshot::restore)
+    }
+}
+
+/// Files named in tool call arguments, resolved against the working directory
+fn argument_paths(arguments: &Value, working_dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<&str> = PATH_ARGUMENTS
+        .iter()
+        .filter_map(|key| arguments.get(key).and_then(|v| v.as_str()))
+        .collect();
+    if let Some(Value::Array(values)) = arguments.get(PATHS_ARGUMENT) {
+        paths.extend(values.iter().filter_map(|v| v.as_str()));
+    }
+
+    let mut resolved: Vec<PathBuf> = paths
+        .into_iter()
+        .map(|path| working_dir.join(shellexpand::tilde(path).as_ref()))
+        .collect();
+    resolved.dedup();
+    resolved
+}
+
+/// Snapshots of recent tool calls that changed files, oldest first
+#[derive(Debug, Default)]
+pub struct SnapshotStore {
+    policy: SnapshotPolicy,
+    snapshots: VecDeque<ToolSnapshot>,
+}
+
+impl SnapshotStore {
+    pub fn new(policy: SnapshotPolicy) -> Self {
+        Self {
+            policy,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.policy.enabled
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Snapshot the files a tool call names, relative to `working_dir`, or None if there is
+    /// nothing to snapshot
+    pub fn capture(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+        working_dir: &Path,
+    ) -> Option<ToolSnapshot> {
+        self.policy.capture(tool_name, arguments, working_dir)
+    }
+
+    pub fn policy(&self) -> SnapshotPolicy {
+        self.policy
+    }
+
+    /// Keep a snapshot once its tool call has finished, if the call changed any of its files
+    pub fn record(&mut self, snapshot: ToolSnapshot) {
+        if let Some(snapshot) = snapshot.settle() {
+            self.push(snapshot);
+        }
+    }
+
+    /// Keep a settled snapshot, dropping the oldest past the limit
+    fn push(&mut self, snapshot: ToolSnapshot) {
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.policy.max_snapshots {
+            self.snapshots.pop_front();
+        }
+    }
+
+    /// Restore the files changed by the most recent tool call. Nothing is restored if any of
+    /// them changed after the call, and the snapshot is kept.
+    pub fn undo_last(&mut self) -> Result<Option<ToolSnapshot>> {
+        let Some(snapshot) = self.snapshots.back() else {
+            return Ok(None);
+        };
+        snapshot.check_unchanged()?;
+        let Some(snapshot) = self.snapshots.pop_back() else {
+            return Ok(None);
+        };
+        snapshot.restore()?;
+        Ok(Some(snapshot))
+    }
+
+    /// Drop the most recent snapshot if it is still this one
+    fn remove_last(&mut self, snapshot: &ToolSnapshot) -> bool {
+        if self.snapshots.back() != Some(snapshot) {
+            return false;
+        }
+        self.snapshots.pop_back();
+        true
+    }
+}
+
+pub(super) type SharedSnapshotStore = Arc<Mutex<SnapshotStore>>;
+
+impl Agent {
+    /// Restore the files changed by the most recent file-modifying tool call, returning the
+    /// undone call or None if there is nothing to undo
+    ///
+    /// The files are read and written on the blocking pool, outside the lock on the snapshots.
+    pub async fn undo_last_tool_effects(&self) -> Result<Option<ToolSnapshot>> {
+        let Some(snapshot) = self
+            .tool_snapshots
+            .lock()
+            .unwrap()
+            .snapshots
+            .back()
+            .cloned()
+        else {
+            return Ok(None);
+        };
+        let snapshot =
+            tokio::task::spawn_blocking(move || snapshot.check_unchanged().map(|()| snapshot))
+                .await??;
+        if !self.tool_snapshots.lock().unwrap().remove_last(&snapshot) {
+            bail!(
+                "Another tool call changed files while {} was being undone, try again",
+                snapshot.tool_name
+            );
+        }
+        tokio::task::spawn_blocking(move || snapshot.restore().map(|()| snapshot))
+            .await?
+            .map(Some)
+    }
+
+    /// Snapshot the files a tool call may change, before it is dispatched. Relative paths are
+    /// resolved against the working directory of the reply's session.
+    pub(super) async fn capture_tool_snapshot(
+        &self,
+        tool_call: &mcp_core::tool::ToolCall,
+        session: Option<&SessionConfig>,
+    ) -> Option<ToolSnapshot> {
+        let policy = self.tool_snapshots.lock().unwrap().policy();
+        if !policy.enabled {
+            return None;
+        }
+        let working_dir = match session {
+            Some(session) => session.working_dir.clone(),
+            None => std::env::current_dir().unwrap_or_default(),
+        };
+        let tool_name = tool_call.name.clone();
+        let arguments = tool_call.arguments.clone();
+        tokio::task::spawn_blocking(move || policy.capture(&tool_name, &arguments, &working_dir))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to snapshot files for {}: {}", tool_call.name, e);
+                None
+            })
+    }
+
+    /// Record the snapshot once the tool call has finished, comparing its files on the
+    /// blocking pool before taking the lock on the snapshots
+    pub(super) fn record_tool_snapshot(
+        &self,
+        result: ToolCallResult,
+        snapshot: ToolSnapshot,
+    ) -> ToolCallResult {
+        let store = self.tool_snapshots.clone();
+        let call = result.result;
+        let recorded = async move {
+            let result = call.await;
+            if let Ok(Some(snapshot)) = tokio::task::spawn_blocking(move || snapshot.settle()).await
+            {
+                store.lock().unwrap().push(snapshot);
+            }
+            result
+        };
+        ToolCallResult {
+            result: Box::new(recorded.boxed()),
+            notification_stream: result.notification_stream,
+        }
+    }
+
+    /// Handle the undo platform tool
+    pub(super) async fn handle_undo(&self, arguments: Value) -> ToolResult<Vec<Content>> {
+        let steps = arguments
+            .get("steps")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .max(1);
+
+        let mut lines = Vec::new();
+        for _ in 0..steps {
+            match self.undo_last_tool_effects().await {
+                Ok(Some(snapshot)) => {
+                    let paths: Vec<String> = snapshot
+                        .paths()
+                        .iter()
+                        .map(|path| path.display().to_string())
+                        .collect();
+                    lines.push(format!(
+                        "Undid {}: restored {}",
+                        snapshot.tool_name,
+                        paths.join(", ")
+                    ));
+                }
+                Ok(None) => {
+                    lines.push("There are no more tool calls to undo".to_string());
+                    break;
+                }
+                Err(e) => return Err(ToolError::ExecutionError(e.to_string())),
+            }
+        }
+        Ok(vec![Content::text(lines.join("\n"))])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn store(max_snapshots: usize) -> SnapshotStore {
+        SnapshotStore::new(SnapshotPolicy {
+            enabled: true,
+            max_snapshots,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_undo_restores_changed_and_created_files() {
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().join("main.rs");
+        let created = dir.path().join("new.rs");
+        fs::write(&existing, "fn main() {}").unwrap();
+
+        let mut store = store(10);
+        let edit = store
+            .capture(
+                "developer__text_editor",
+                &json!({"path": existing}),
+                dir.path(),
+            )
+            .unwrap();
+        fs::write(&existing, "fn main() { panic!() }").unwrap();
+        store.record(edit);
+
+        let write = store
+            .capture(
+                "developer__text_editor",
+                &json!({"path": created}),
+                dir.path(),
+            )
+            .unwrap();
+        fs::write(&created, "pub fn new() {}").unwrap();
+        store.record(write);
+
+        // Calls that change nothing, such as viewing a file, are not kept
+        let view = store
+            .capture(
+                "developer__text_editor",
+                &json!({"path": existing}),
+                dir.path(),
+            )
+            .unwrap();
+        store.record(view);
+        assert_eq!(store.len(), 2);
+
+        assert_eq!(
+            store.undo_last().unwrap().unwrap().paths(),
+            vec![created.clone()]
+        );
+        assert!(!created.exists());
+        store.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "fn main() {}");
+        assert!(store.undo_last().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_retention_and_policy() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("notes.txt");
+
+        let mut store = store(2);
+        for i in 0..4 {
+            let snapshot = store
+                .capture("write", &json!({"paths": [file]}), dir.path())
+                .unwrap();
+            fs::write(&file, i.to_string()).unwrap();
+            store.record(snapshot);
+        }
+        assert_eq!(store.len(), 2);
+        store.undo_last().unwrap();
+        store.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "1");
+
+        assert!(store
+            .capture("shell", &json!({"command": "ls"}), dir.path())
+            .is_none());
+        let disabled = SnapshotStore::new(SnapshotPolicy::default());
+        assert!(disabled
+            .capture("write", &json!({"path": file}), dir.path())
+            .is_none());
+    }
+
+    #[test]
+    fn test_relative_paths_use_the_working_dir() {
+        let dir = TempDir::new().unwrap();
+        let mut store = store(10);
+        let snapshot = store
+            .capture("write", &json!({"path": "notes.txt"}), dir.path())
+            .unwrap();
+        assert_eq!(snapshot.paths(), vec![dir.path().join("notes.txt")]);
+
+        fs::write(dir.path().join("notes.txt"), "draft").unwrap();
+        store.record(snapshot);
+        store.undo_last().unwrap();
+        assert!(!dir.path().join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_files_changed_after_the_call_are_not_restored() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("main.rs");
+        fs::write(&file, "fn main() {}").unwrap();
+
+        let mut store = store(10);
+        let edit = store
+            .capture("developer__text_editor", &json!({"path": file}), dir.path())
+            .unwrap();
+        fs::write(&file, "fn main() { run() }").unwrap();
+        store.record(edit);
+
+        // The user edits the file again by hand
+        fs::write(&file, "fn main() { run(); log() }").unwrap();
+        let err = store.undo_last().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("changed after developer__text_editor"));
+        assert_eq!(store.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "fn main() { run(); log() }"
+        );
+
+        // Once it is back as the call left it, the call can be undone
+        fs::write(&file, "fn main() { run() }").unwrap();
+        store.undo_last().unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn main() {}");
+    }
+}
@@ -0,0 +1,93 @@
+//! Streaming responses as they are generated
+//!
+//! By default each response of the model reaches the frontend whole, as one
+//! `AgentEvent::Message`. With streaming on, its text is sent while the model writes it, as
+//! `AgentEvent::TextChunk`s, and the response still follows as one `AgentEvent::Message` once it
+//! is complete. Only that message is kept in the conversation: frontends show the chunks as they
+//! arrive and, of the message that follows, only what the chunks did not show, which
+//! [`without_text`] leaves. Providers that cannot stream send the whole text as one chunk.
+//!
+//! ```yaml
+//! GOOSE_STREAMING: true
+//! ```
+//!
+//! A response that goose may throw away, such as a final answer that has to recall earlier tool
+//! results first, is not streamed. Its text is shown with its message if it is kept.
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+pub use crate::providers::base::append_to_response;
+
+use super::Agent;
+
+/// Whether replies are streamed when no frontend chose, from `GOOSE_STREAMING`
+pub fn streaming_enabled() -> bool {
+    Config::global()
+        .get_param("GOOSE_STREAMING")
+        .unwrap_or(false)
+}
+
+/// The text of a streamed part, sent to the frontend as soon as it arrives
+pub fn text_chunk(part: &Message) -> Option<String> {
+    let text: String = part
+        .content
+        .iter()
+        .filter_map(|content| content.as_text())
+        .collect();
+    (!text.is_empty()).then_some(text)
+}
+
+/// What is left to show of a response once its text has been streamed
+pub fn without_text(mut response: Message) -> Option<Message> {
+    response
+        .content
+        .retain(|content| !matches!(content, MessageContent::Text(_)));
+    (!response.content.is_empty()).then_some(response)
+}
+
+impl Agent {
+    /// Stream the text of subsequent replies, overriding `GOOSE_STREAMING`
+    pub async fn set_streaming(&self, enabled: bool) {
+        *self.streaming_override.lock().await = Some(enabled);
+    }
+
+    pub(crate) async fn resolve_streaming(&self) -> bool {
+        self.streaming_override
+            .lock()
+            .await
+            .unwrap_or_else(streaming_enabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_streamed_response_is_sent_once() {
+        let parts = vec![
+            Message::assistant().with_text("Running "),
+            Message::assistant().with_text("the tests."),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "cargo test"}),
+                )),
+            ),
+        ];
+        let chunks: Vec<String> = parts.iter().filter_map(text_chunk).collect();
+        assert_eq!(chunks, vec!["Running ", "the tests."]);
+
+        let mut response = Message::assistant();
+        for part in parts {
+            append_to_response(&mut response, part);
+        }
+        assert_eq!(response.as_concat_text(), "Running the tests.");
+        let rest = without_text(response.clone()).unwrap();
+        assert!(rest.is_tool_call());
+        assert!(rest.as_concat_text().is_empty());
+        assert!(without_text(Message::assistant().with_text("Done.")).is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+use std::sync::Arc;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::agents::Agent;
+use crate::message::{Message, MessageContent};
+
+/// A caller supplied check run against each assistant message
+#[derive(Clone)]
+pub struct TerminationPredicate(Arc<dyn Fn(&Message) -> bool + Send + Sync>);
+
+impl TerminationPredicate {
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Message) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(predicate))
+    }
+}
+
+impl fmt::Debug for TerminationPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TerminationPredicate")
+    }
+}
+
+/// A condition that ends the reply loop once an assistant turn satisfies it
+///
+/// Conditions are checked after every assistant turn, once any tool calls in that turn
+/// have completed, so the conversation is always left in a consistent state.
+///
+/// ```yaml
+/// stop_conditions:
+///   - type: regex
+///     pattern: "TASK COMPLETE"
+///   - type: json_path
+///     path: "$.status"
+///     equals: "done"
+///   - type: tool_called
+///     tool: developer__shell
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TerminationCondition {
+    /// The assistant's text output matches the regex
+    Regex { pattern: String },
+    /// The assistant's text output is JSON with a value at `path`, optionally equal to `equals`
+    JsonPath {
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        equals: Option<Value>,
+    },
+    /// The assistant requested the named tool
+    ToolCalled { tool: String },
+    #[serde(skip)]
+    Predicate(TerminationPredicate),
+}
+
+impl TerminationCondition {
+    pub fn regex(pattern: impl Into<String>) -> Self {
+        Self::Regex {
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn json_path(path: impl Into<String>, equals: Option<Value>) -> Self {
+        Self::JsonPath {
+            path: path.into(),
+            equals,
+        }
+    }
+
+    pub fn tool_called(tool: impl Into<String>) -> Self {
+        Self::ToolCalled { tool: tool.into() }
+    }
+
+    pub fn predicate<F>(predicate: F) -> Self
+    where
+        F: Fn(&Message) -> bool + Send + Sync + 'static,
+    {
+        Self::Predicate(TerminationPredicate::new(predicate))
+    }
+
+    /// Check the condition against an assistant message, returning a description when it is met
+    pub fn check(&self, message: &Message) -> Option<String> {
+        match self {
+            Self::Regex { pattern } => {
+                let re = match Regex::new(pattern) {
+                    Ok(re) => re,
+                    Err(e) => {
+                        tracing::warn!("Invalid termination regex '{}': {}", pattern, e);
+                        return None;
+                    }
+                };
+                re.is_match(&message.as_concat_text())
+                    .then(|| format!("output matched '{}'", pattern))
+            }
+            Self::JsonPath { path, equals } => {
+                let json = parse_json_output(&message.as_concat_text())?;
+                let found = json_path_lookup(&json, path)?;
+                match equals {
+                    Some(expected) if expected != found => None,
+                    _ => Some(format!("output has a value at '{}'", path)),
+                }
+            }
+            Self::ToolCalled { tool } => message
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+                    _ => None,
+                })
+                .any(|call| &call.name == tool)
+                .then(|| format!("tool '{}' was called", tool)),
+            Self::Predicate(predicate) => {
+                (predicate.0)(message).then(|| "predicate was satisfied".to_string())
+            }
+        }
+    }
+}
+
+/// Parse assistant output as JSON, allowing it to be wrapped in a fenced code block
+pub(crate) fn parse_json_output(text: &str) -> Option<Value> {
+    let re = Regex::new(r"(?s)```[^\n]*\n(.*?)\n```").unwrap();
+    let content = re
+        .captures(text)
+        .and_then(|caps| caps.get(1).map(|m| m.as_str()))
+        .unwrap_or(text)
+        .trim();
+    serde_json::from_str(content).ok()
+}
+
+/// Resolve a simple JSON path such as `$.results[0].status` against a value
+fn json_path_lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+    for segment in path.split('.').filter(|s| !s.is_empty()) {
+        let (key, indexes) = match segment.find('[') {
+            Some(i) => (&segment[..i], &segment[i..]),
+            None => (segment, ""),
+        };
+        if !key.is_empty() {
+            current = current.get(key)?;
+        }
+        for index in indexes.split(['[', ']']).filter(|s| !s.is_empty()) {
+            current = current.get(index.parse::<usize>().ok()?)?;
+        }
+    }
+    Some(current)
+}
+
+impl Agent {
+    /// Add a condition that ends the reply loop when an assistant turn satisfies it
+    pub async fn add_termination_condition(&self, condition: TerminationCondition) {
+        self.termination_conditions.lock().await.push(condition);
+    }
+
+    /// Replace all termination conditions
+    pub async fn set_termination_conditions(&self, conditions: Vec<TerminationCondition>) {
+        *self.termination_conditions.lock().await = conditions;
+    }
+
+    /// Returns the reason the reply loop should stop after this assistant message, if any
+    pub(crate) async fn check_termination(&self, message: &Message) -> Option<String> {
+        self.termination_conditions
+            .lock()
+            .await
+            .iter()
+            .find_map(|condition| condition.check(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_regex_condition() {
+        let condition = TerminationCondition::regex(r"TASK\s+COMPLETE");
+        assert!(condition
+            .check(&Message::assistant().with_text("All done. TASK COMPLETE"))
+            .is_some());
+        assert!(condition
+            .check(&Message::assistant().with_text("still working"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_json_path_condition() {
+        let condition =
+            TerminationCondition::json_path("$.result.items[1].status", Some(json!("done")));
+        let message = Message::assistant().with_text(
+            "```json\n{\"result\": {\"items\": [{\"status\": \"todo\"}, {\"status\": \"done\"}]}}\n```",
+        );
+        assert!(condition.check(&message).is_some());
+
+        let pending = Message::assistant()
+            .with_text(r#"{"result": {"items": [{"status": "todo"}, {"status": "todo"}]}}"#);
+        assert!(condition.check(&pending).is_none());
+        assert!(TerminationCondition::json_path("$.result", None)
+            .check(&pending)
+            .is_some());
+    }
+
+    #[test]
+    fn test_tool_called_condition() {
+        let condition = TerminationCondition::tool_called("developer__shell");
+        let message = Message::assistant()
+            .with_tool_request("1", Ok(ToolCall::new("developer__shell", json!({}))));
+        assert!(condition.check(&message).is_some());
+        assert!(condition
+            .check(&Message::assistant().with_text("no tools"))
+            .is_none());
+    }
+
+    #[test]
+    fn test_predicate_condition() {
+        let condition = TerminationCondition::predicate(|m| m.content.len() > 1);
+        let message = Message::assistant().with_text("a").with_text("b");
+        assert!(condition.check(&message).is_some());
+    }
+
+    #[test]
+    fn test_deserialize_conditions() {
+        let conditions: Vec<TerminationCondition> = serde_json::from_value(json!([
+            {"type": "regex", "pattern": "DONE"},
+            {"type": "tool_called", "tool": "finish"}
+        ]))
+        .unwrap();
+        assert!(matches!(conditions[0], TerminationCondition::Regex { .. }));
+        assert!(matches!(
+            conditions[1],
+            TerminationCondition::ToolCalled { .. }
+        ));
+    }
+}
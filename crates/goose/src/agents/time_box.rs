@@ -0,0 +1,178 @@
+//! Replies bounded by wall-clock time
+//!
+//! A turn budget limits how many times the model is called, but exploratory tasks are easier to
+//! bound by time: "spend ten minutes on this and tell me what you found". With a time box, the
+//! reply loop checks its clock before every turn. Once the wrap-up point is reached it adds an
+//! instruction to stop exploring, and the next response ends the reply: any tool calls in it
+//! are dropped rather than run, so it is the final answer whatever the model would rather do.
+//!
+//! ```yaml
+//! # Ten minutes per reply, wrapping up two minutes before the end
+//! GOOSE_TIME_BOX: 600
+//! GOOSE_TIME_BOX_WRAP_UP: 120
+//! ```
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+use super::Agent;
+
+/// Share of the time box left for the final answer when no wrap-up time is set
+const DEFAULT_WRAP_UP_FRACTION: u32 = 5;
+
+/// How long a reply may run, and how much of that is kept for the final answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBox {
+    pub budget: Duration,
+    /// Time before the end of the budget at which the model is told to wrap up
+    pub wrap_up: Duration,
+}
+
+impl TimeBox {
+    /// A time box that wraps up for the last fifth of the budget
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            wrap_up: budget / DEFAULT_WRAP_UP_FRACTION,
+        }
+    }
+
+    pub fn with_wrap_up(mut self, wrap_up: Duration) -> Self {
+        self.wrap_up = wrap_up.min(self.budget);
+        self
+    }
+
+    /// The configured time box, or None if replies are not time boxed
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let budget = config.get_param::<u64>("GOOSE_TIME_BOX").ok()?;
+        let time_box = Self::new(Duration::from_secs(budget));
+        Some(match config.get_param::<u64>("GOOSE_TIME_BOX_WRAP_UP") {
+            Ok(wrap_up) => time_box.with_wrap_up(Duration::from_secs(wrap_up)),
+            Err(_) => time_box,
+        })
+    }
+
+    /// Start the clock for a reply
+    pub fn start(self) -> TimeBoxClock {
+        TimeBoxClock {
+            time_box: self,
+            started: Instant::now(),
+        }
+    }
+}
+
+/// The clock of a time-boxed reply
+#[derive(Debug, Clone, Copy)]
+pub struct TimeBoxClock {
+    time_box: TimeBox,
+    started: Instant,
+}
+
+impl TimeBoxClock {
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Whether the reply has reached the point where it should produce its final answer
+    pub fn should_wrap_up(&self) -> bool {
+        self.elapsed() >= self.time_box.budget.saturating_sub(self.time_box.wrap_up)
+    }
+
+    /// The instruction added to the conversation at the wrap-up point
+    pub fn wrap_up_instruction(&self) -> String {
+        format!(
+            "You have used {} of the {} available for this task. Do not call any more \
+             tools. Stop exploring and give your best final answer now: summarize what you \
+             found, what you changed, and what is still open or uncertain.",
+            format_duration(self.elapsed()),
+            format_duration(self.time_box.budget)
+        )
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 60, secs % 60) {
+        (0, secs) => format!("{}s", secs),
+        (mins, 0) => format!("{}m", mins),
+        (mins, secs) => format!("{}m {}s", mins, secs),
+    }
+}
+
+/// The final answer of a time-boxed reply, without any tool calls the model made regardless
+pub fn final_answer(mut answer: Message) -> Message {
+    if !answer.is_tool_call() {
+        return answer;
+    }
+    answer
+        .content
+        .retain(|content| !matches!(content, MessageContent::ToolRequest(_)));
+    if answer.as_concat_text().trim().is_empty() {
+        answer = answer.with_text(
+            "I ran out of time for this task before reaching a final answer. Let me know if you'd like me to keep going.",
+        );
+    }
+    answer
+}
+
+impl Agent {
+    /// Time box subsequent replies, overriding `GOOSE_TIME_BOX`.
+    /// Pass `None` to go back to the configured time box, if any.
+    pub async fn set_time_box(&self, time_box: Option<TimeBox>) {
+        *self.time_box_override.lock().await = time_box;
+    }
+
+    pub(crate) async fn resolve_time_box(&self) -> Option<TimeBox> {
+        match *self.time_box_override.lock().await {
+            Some(time_box) => Some(time_box),
+            None => TimeBox::from_config(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_wrap_up_point() {
+        let time_box = TimeBox::new(Duration::from_secs(600));
+        assert_eq!(time_box.wrap_up, Duration::from_secs(120));
+        assert!(!time_box.start().should_wrap_up());
+
+        let clock = time_box.with_wrap_up(Duration::from_secs(600)).start();
+        assert!(clock.should_wrap_up());
+        assert!(clock.wrap_up_instruction().contains("of the 10m available"));
+
+        assert_eq!(format_duration(Duration::from_secs(95)), "1m 35s");
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+    }
+
+    #[test]
+    fn test_final_answer_drops_tool_calls() {
+        let response = Message::assistant()
+            .with_text("The flaky test depends on the system clock.")
+            .with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            );
+        let answer = final_answer(response);
+        assert!(!answer.is_tool_call());
+        assert_eq!(
+            answer.as_concat_text(),
+            "The flaky test depends on the system clock."
+        );
+
+        let only_calls = Message::assistant().with_tool_request(
+            "call_2",
+            Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+        );
+        assert!(final_answer(only_calls)
+            .as_concat_text()
+            .starts_with("I ran out of time"));
+    }
+}
@@ -0,0 +1,523 @@
+//! Budgets for individual tools and extensions
+//!
+//! Session budgets bound a whole reply, but a single expensive integration can still use most
+//! of it. `GOOSE_TOOL_BUDGETS` limits what one tool or extension may spend in a session:
+//!
+//! ```yaml
+//! GOOSE_TOOL_BUDGETS:
+//!   router__llm_search:
+//!     max_calls: 10
+//!     max_result_tokens: 20000
+//!     max_cost_usd: 0.50
+//!   github:
+//!     max_calls: 30
+//!     max_call_ms: 20000
+//!     max_total_ms: 120000
+//!   paid_search:
+//!     cost_per_call_usd: 0.01
+//!     max_cost_usd: 1.00
+//! ```
+//!
+//! Keys are full tool names or extension names, and a call counts against both. Each session
+//! spends its own budgets. A call and its price per call are counted as soon as it starts, so
+//! calls running in parallel see each other. Once a limit is spent, further calls are refused
+//! before they are dispatched, and a call that runs past `max_call_ms` is cancelled, counting the work done
+//! while it is dispatched, as router searches and platform tools do all of theirs then. Either
+//! way the model gets a budget exceeded error naming the scope and limit, so it can work
+//! without that tool.
+//!
+//! The cost of a call, in USD, is what the model responses it asks for cost, as the LLM router
+//! search does, plus `cost_per_call_usd` for integrations that charge by the call.
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::FutureExt;
+use mcp_core::{Content, ToolError};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::config::Config;
+use crate::model::ModelConfig;
+use crate::providers::base::ProviderUsage;
+
+use super::audit::audit_session_id;
+use super::cost_tracker::response_cost;
+use super::tool_execution::ToolCallResult;
+use super::{Agent, SessionConfig};
+
+/// Limits on what one tool or extension may spend in a session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolBudget {
+    pub max_calls: Option<u64>,
+    /// Longest a single call may run, in milliseconds
+    pub max_call_ms: Option<u64>,
+    /// Total time all calls may run, in milliseconds
+    pub max_total_ms: Option<u64>,
+    /// Estimated tokens all results may add to the conversation
+    pub max_result_tokens: Option<u64>,
+    /// Total cost of all calls, in USD
+    pub max_cost_usd: Option<f64>,
+    /// What the integration charges for each call, in USD
+    pub cost_per_call_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetMetric {
+    Calls,
+    CallMs,
+    TotalMs,
+    ResultTokens,
+    CostUsd,
+}
+
+impl std::fmt::Display for BudgetMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            BudgetMetric::Calls => "calls",
+            BudgetMetric::CallMs => "ms per call",
+            BudgetMetric::TotalMs => "ms in total",
+            BudgetMetric::ResultTokens => "result tokens",
+            BudgetMetric::CostUsd => "USD",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A call refused or cancelled because a tool or extension spent its budget
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetExceeded {
+    /// The tool or extension whose budget was spent
+    pub scope: String,
+    pub metric: BudgetMetric,
+    pub limit: f64,
+    pub spent: f64,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.metric {
+            BudgetMetric::CostUsd => write!(
+                f,
+                "{} has used ${:.2} of its budget of ${:.2}",
+                self.scope, self.spent, self.limit
+            ),
+            _ => write!(
+                f,
+                "{} has used {} of its budget of {} {}",
+                self.scope, self.spent, self.limit, self.metric
+            ),
+        }
+    }
+}
+
+impl From<BudgetExceeded> for ToolError {
+    fn from(exceeded: BudgetExceeded) -> Self {
+        ToolError::ExecutionError(format!(
+            "Budget exceeded: {}. Continue without this tool. {}",
+            exceeded,
+            serde_json::to_string(&exceeded).unwrap_or_default()
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct BudgetSpend {
+    calls: u64,
+    total_ms: u64,
+    result_tokens: u64,
+    cost_usd: f64,
+}
+
+tokio::task_local! {
+    /// The cost of the model responses of the tool call being run, in USD
+    static TOOL_CALL_COST: Arc<Mutex<f64>>;
+}
+
+/// Count the cost of a model response made for the tool call being run, if there is one
+pub(crate) fn record_tool_call_usage(model_config: &ModelConfig, usage: &ProviderUsage) {
+    let _ = TOOL_CALL_COST
+        .try_with(|spent| *spent.lock().unwrap() += response_cost(model_config, usage));
+}
+
+/// Rough token count of the text in tool results
+fn estimate_result_tokens(contents: &[Content]) -> u64 {
+    contents
+        .iter()
+        .filter_map(|content| content.as_text())
+        .map(|text| text.len() as u64 / 4)
+        .sum()
+}
+
+/// Budgets by tool or extension name, and what each has spent in each session
+#[derive(Debug, Clone, Default)]
+pub struct ToolBudgets {
+    budgets: HashMap<String, ToolBudget>,
+    spent: HashMap<String, HashMap<String, BudgetSpend>>,
+}
+
+impl ToolBudgets {
+    pub fn new(budgets: HashMap<String, ToolBudget>) -> Self {
+        Self {
+            budgets,
+            spent: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_TOOL_BUDGETS")
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.budgets.is_empty()
+    }
+
+    /// The budgeted scopes a tool call counts against: the tool and its extension
+    fn scopes<'a>(&'a self, tool_name: &'a str) -> impl Iterator<Item = (&'a str, &'a ToolBudget)> {
+        let extension = tool_name.split_once("__").map(|(extension, _)| extension);
+        [Some(tool_name), extension]
+            .into_iter()
+            .flatten()
+            .filter_map(|scope| self.budgets.get(scope).map(|budget| (scope, budget)))
+    }
+
+    /// Check that neither the tool nor its extension has spent its budget in the session
+    pub fn check(&self, session_id: &str, tool_name: &str) -> Result<(), BudgetExceeded> {
+        let session = self.spent.get(session_id);
+        for (scope, budget) in self.scopes(tool_name) {
+            let spent = session
+                .and_then(|spent| spent.get(scope))
+                .cloned()
+                .unwrap_or_default();
+            let as_f64 = |limit: Option<u64>| limit.map(|limit| limit as f64);
+            let limits = [
+                (
+                    BudgetMetric::Calls,
+                    as_f64(budget.max_calls),
+                    spent.calls as f64,
+                ),
+                (
+                    BudgetMetric::TotalMs,
+                    as_f64(budget.max_total_ms),
+                    spent.total_ms as f64,
+                ),
+                (
+                    BudgetMetric::ResultTokens,
+                    as_f64(budget.max_result_tokens),
+                    spent.result_tokens as f64,
+                ),
+                (BudgetMetric::CostUsd, budget.max_cost_usd, spent.cost_usd),
+            ];
+            for (metric, limit, spent) in limits {
+                if let Some(limit) = limit.filter(|limit| spent >= *limit) {
+                    return Err(BudgetExceeded {
+                        scope: scope.to_string(),
+                        metric,
+                        limit,
+                        spent,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The tightest time limit for a single call of the tool, and the scope it comes from
+    pub fn call_timeout(&self, tool_name: &str) -> Option<(String, u64)> {
+        self.scopes(tool_name)
+            .filter_map(|(scope, budget)| budget.max_call_ms.map(|ms| (scope.to_string(), ms)))
+            .min_by_key(|(_, ms)| *ms)
+    }
+
+    /// Check the budgets and count a starting call against them, with each scope's price per
+    /// call, before the call runs
+    pub fn reserve(&mut self, session_id: &str, tool_name: &str) -> Result<(), BudgetExceeded> {
+        self.check(session_id, tool_name)?;
+        let scopes: Vec<(String, f64)> = self
+            .scopes(tool_name)
+            .map(|(scope, budget)| {
+                (
+                    scope.to_string(),
+                    budget.cost_per_call_usd.unwrap_or_default(),
+                )
+            })
+            .collect();
+        let session = self.spent.entry(session_id.to_string()).or_default();
+        for (scope, cost_per_call) in scopes {
+            let spent = session.entry(scope).or_default();
+            spent.calls += 1;
+            spent.cost_usd += cost_per_call;
+        }
+        Ok(())
+    }
+
+    /// Add what a reserved call spent once it is over: its time, its result and the cost of
+    /// its model responses
+    pub fn settle(
+        &mut self,
+        session_id: &str,
+        tool_name: &str,
+        elapsed: Duration,
+        result_tokens: u64,
+        cost_usd: f64,
+    ) {
+        let scopes: Vec<String> = self
+            .scopes(tool_name)
+            .map(|(scope, _)| scope.to_string())
+            .collect();
+        let session = self.spent.entry(session_id.to_string()).or_default();
+        for scope in scopes {
+            let spent = session.entry(scope).or_default();
+            spent.total_ms += elapsed.as_millis() as u64;
+            spent.result_tokens += result_tokens;
+            spent.cost_usd += cost_usd;
+        }
+    }
+}
+
+/// A tool call being timed and priced against its budgets, from its dispatch to its result
+pub(super) struct BudgetedCall {
+    budgets: Arc<Mutex<ToolBudgets>>,
+    session_id: String,
+    tool_name: String,
+    timeout: Option<(String, u64)>,
+    started: Instant,
+    cost: Arc<Mutex<f64>>,
+}
+
+impl BudgetedCall {
+    fn exceeded_time(scope: String, limit: u64, spent: Duration) -> ToolError {
+        BudgetExceeded {
+            scope,
+            metric: BudgetMetric::CallMs,
+            limit: limit as f64,
+            spent: spent.as_millis() as f64,
+        }
+        .into()
+    }
+
+    fn settle(&self, elapsed: Duration, result_tokens: u64) {
+        let cost = *self.cost.lock().unwrap();
+        self.budgets.lock().unwrap().settle(
+            &self.session_id,
+            &self.tool_name,
+            elapsed,
+            result_tokens,
+            cost,
+        );
+    }
+
+    /// Dispatch the call within its time limit. Router searches and platform tools do all of
+    /// their work here rather than in the result they return.
+    pub async fn dispatch<F: Future>(&self, dispatch: F) -> Result<F::Output, ToolError> {
+        let Some((scope, limit)) = &self.timeout else {
+            return Ok(TOOL_CALL_COST.scope(self.cost.clone(), dispatch).await);
+        };
+        let deadline = self.started + Duration::from_millis(*limit);
+        TOOL_CALL_COST
+            .scope(
+                self.cost.clone(),
+                tokio::time::timeout_at(deadline, dispatch),
+            )
+            .await
+            .map_err(|_| Self::exceeded_time(scope.clone(), *limit, self.started.elapsed()))
+    }
+
+    /// Settle a call that failed before it returned a result. It still counts as a call.
+    pub fn failed(self) {
+        self.settle(self.started.elapsed(), 0);
+    }
+
+    /// The result of the call, cancelled once the call has run for its time limit and
+    /// settled when it finishes. Time the result spends waiting to start does not count.
+    pub fn finish(self, result: ToolCallResult) -> ToolCallResult {
+        let dispatched = self.started.elapsed();
+        let call = result.result;
+        let cost = self.cost.clone();
+
+        let fut = async move {
+            let resumed = Instant::now();
+            let outcome = match self.timeout.clone() {
+                Some((scope, limit)) => {
+                    let remaining = Duration::from_millis(limit).saturating_sub(dispatched);
+                    match tokio::time::timeout(remaining, call).await {
+                        Ok(outcome) => outcome,
+                        Err(_) => Err(Self::exceeded_time(
+                            scope,
+                            limit,
+                            dispatched + resumed.elapsed(),
+                        )),
+                    }
+                }
+                None => call.await,
+            };
+            let tokens = outcome
+                .as_ref()
+                .map(|contents| estimate_result_tokens(contents))
+                .unwrap_or(0);
+            self.settle(dispatched + resumed.elapsed(), tokens);
+            outcome
+        };
+
+        ToolCallResult {
+            result: Box::new(TOOL_CALL_COST.scope(cost, fut).boxed()),
+            notification_stream: result.notification_stream,
+        }
+    }
+}
+
+impl Agent {
+    /// Replace the tool and extension budgets, starting their spend from zero
+    pub fn set_tool_budgets(&self, budgets: ToolBudgets) {
+        *self.tool_budgets.lock().unwrap() = budgets;
+    }
+
+    /// Check a tool call against the budgets of the tool and its extension in the session,
+    /// reserving it under the same lock, and start timing it if any budget applies
+    pub(super) fn start_budgeted_call(
+        &self,
+        tool_name: &str,
+        session: Option<&SessionConfig>,
+    ) -> Result<Option<BudgetedCall>, BudgetExceeded> {
+        if self.tool_budgets.lock().unwrap().is_empty() {
+            return Ok(None);
+        }
+        let session_id = audit_session_id(session).unwrap_or_default();
+        let mut budgets = self.tool_budgets.lock().unwrap();
+        budgets.reserve(&session_id, tool_name)?;
+        Ok(Some(BudgetedCall {
+            budgets: self.tool_budgets.clone(),
+            session_id,
+            tool_name: tool_name.to_string(),
+            timeout: budgets.call_timeout(tool_name),
+            started: Instant::now(),
+            cost: Arc::new(Mutex::new(0.0)),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budgets() -> ToolBudgets {
+        ToolBudgets::new(HashMap::from([
+            (
+                "router__llm_search".to_string(),
+                ToolBudget {
+                    max_calls: Some(2),
+                    ..Default::default()
+                },
+            ),
+            (
+                "github".to_string(),
+                ToolBudget {
+                    max_call_ms: Some(5_000),
+                    max_total_ms: Some(10_000),
+                    ..Default::default()
+                },
+            ),
+            (
+                "github__search_code".to_string(),
+                ToolBudget {
+                    max_call_ms: Some(1_000),
+                    ..Default::default()
+                },
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_tool_call_budget() {
+        let mut budgets = budgets();
+        let search = "router__llm_search";
+        budgets.reserve("a", search).unwrap();
+        budgets.settle("a", search, Duration::from_millis(10), 100, 0.0);
+        budgets.reserve("a", search).unwrap();
+
+        let exceeded = budgets.reserve("a", search).unwrap_err();
+        assert_eq!(exceeded.metric, BudgetMetric::Calls);
+        assert_eq!(exceeded.limit, 2.0);
+        assert_eq!(
+            exceeded.to_string(),
+            "router__llm_search has used 2 of its budget of 2 calls"
+        );
+        assert!(budgets.check("a", "router__vector_search").is_ok());
+        // Another session spends its own budget
+        assert!(budgets.check("b", search).is_ok());
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let mut budgets = ToolBudgets::new(HashMap::from([(
+            "paid".to_string(),
+            ToolBudget {
+                max_cost_usd: Some(0.05),
+                cost_per_call_usd: Some(0.02),
+                ..Default::default()
+            },
+        )]));
+        budgets.reserve("a", "paid__search").unwrap();
+        // A call's model responses add to its price per call
+        budgets.settle("a", "paid__search", Duration::ZERO, 0, 0.02);
+        budgets.reserve("a", "paid__search").unwrap();
+        let exceeded = budgets.reserve("a", "paid__lookup").unwrap_err();
+        assert_eq!(exceeded.metric, BudgetMetric::CostUsd);
+        assert_eq!(
+            exceeded.to_string(),
+            "paid has used $0.06 of its budget of $0.05"
+        );
+    }
+
+    #[test]
+    fn test_extension_budget_covers_its_tools() {
+        let mut budgets = budgets();
+        assert_eq!(
+            budgets.call_timeout("github__search_code"),
+            Some(("github__search_code".to_string(), 1_000))
+        );
+        assert_eq!(
+            budgets.call_timeout("github__create_issue"),
+            Some(("github".to_string(), 5_000))
+        );
+        assert_eq!(budgets.call_timeout("developer__shell"), None);
+
+        for (tool, secs) in [("github__create_issue", 6), ("github__search_code", 4)] {
+            budgets.reserve("a", tool).unwrap();
+            budgets.settle("a", tool, Duration::from_secs(secs), 0, 0.0);
+        }
+        let exceeded = budgets.check("a", "github__list_issues").unwrap_err();
+        assert_eq!(exceeded.scope, "github");
+        assert_eq!(exceeded.metric, BudgetMetric::TotalMs);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_calls_reserve_their_budget() {
+        let agent = Agent::new();
+        agent.set_tool_budgets(budgets());
+        let search = "router__llm_search";
+
+        // Two calls in flight spend the budget before either finishes
+        let first = agent.start_budgeted_call(search, None).unwrap().unwrap();
+        let second = agent.start_budgeted_call(search, None).unwrap().unwrap();
+        let exceeded = agent.start_budgeted_call(search, None).err().unwrap();
+        assert_eq!(exceeded.metric, BudgetMetric::Calls);
+        assert_eq!(exceeded.spent, 2.0);
+
+        // Settling them adds their time without counting them again
+        first.failed();
+        let result = second.finish(ToolCallResult::from(Ok::<_, ToolError>(vec![
+            Content::text("found"),
+        ])));
+        assert!(result.result.await.is_ok());
+        let spent = agent.tool_budgets.lock().unwrap().spent[""][search].clone();
+        assert_eq!(spent.calls, 2);
+        assert_eq!(spent.result_tokens, 1);
+    }
+}
@@ -0,0 +1,142 @@
+//! Deployment overrides for the descriptions of platform and router tools
+//!
+//! The descriptions of goose's own tools are written for every deployment. With
+//! `GOOSE_PLATFORM_TOOL_DESCRIPTIONS` a deployment can replace a description, add guidance
+//! after it, or document parameters its own way, e.g. to explain how its extensions are named:
+//!
+//! ```yaml
+//! GOOSE_PLATFORM_TOOL_DESCRIPTIONS:
+//!   platform__search_available_extensions:
+//!     append: "Internal extensions are named acme-<team>, e.g. acme-payments."
+//!   platform__manage_extensions:
+//!     parameters:
+//!       extension_name: "Name of the extension as listed in the Acme catalog"
+//! ```
+//!
+//! Overrides are applied whenever the tools are listed, so they reach the model, the tool
+//! router index and frontends alike.
+use std::collections::HashMap;
+
+use mcp_core::tool::Tool;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolDescriptionOverride {
+    /// Replaces the description
+    pub description: Option<String>,
+    /// Added after the description, on its own line
+    pub append: Option<String>,
+    /// Descriptions of parameters by name
+    pub parameters: HashMap<String, String>,
+}
+
+impl ToolDescriptionOverride {
+    pub fn apply(&self, tool: &mut Tool) {
+        if let Some(description) = &self.description {
+            tool.description = description.clone();
+        }
+        if let Some(append) = &self.append {
+            tool.description = format!("{}\n{}", tool.description.trim_end(), append);
+        }
+        for (parameter, description) in &self.parameters {
+            let Some(property) = tool
+                .input_schema
+                .get_mut("properties")
+                .and_then(|properties| properties.get_mut(parameter))
+                .and_then(Value::as_object_mut)
+            else {
+                tracing::warn!(
+                    "{} has no parameter named {}, its description was not changed",
+                    tool.name,
+                    parameter
+                );
+                continue;
+            };
+            property.insert(
+                "description".to_string(),
+                Value::String(description.clone()),
+            );
+        }
+    }
+}
+
+/// Overrides by full tool name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ToolDescriptions {
+    overrides: HashMap<String, ToolDescriptionOverride>,
+}
+
+impl ToolDescriptions {
+    pub fn new(overrides: HashMap<String, ToolDescriptionOverride>) -> Self {
+        Self { overrides }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_PLATFORM_TOOL_DESCRIPTIONS")
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Apply the overrides for these tools, leaving tools without one as they are
+    pub fn apply(&self, tools: &mut [Tool]) {
+        if self.overrides.is_empty() {
+            return;
+        }
+        for tool in tools.iter_mut() {
+            if let Some(tool_override) = self.overrides.get(&tool.name) {
+                tool_override.apply(tool);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::platform_tools::{
+        manage_extensions_tool, search_available_extensions_tool,
+        PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME, PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
+    };
+
+    #[test]
+    fn test_overrides_apply_by_name() {
+        let overrides: HashMap<String, ToolDescriptionOverride> = serde_yaml::from_str(
+            r#"
+            platform__search_available_extensions:
+              append: "Internal extensions are named acme-<team>."
+            platform__manage_extensions:
+              description: "Enable or disable an Acme extension."
+              parameters:
+                extension_name: "Name in the Acme catalog"
+                missing: "Not a parameter"
+            "#,
+        )
+        .unwrap();
+        let mut tools = vec![search_available_extensions_tool(), manage_extensions_tool()];
+        let original = tools[0].description.clone();
+        ToolDescriptions::new(overrides).apply(&mut tools);
+
+        assert_eq!(
+            tools[0].name,
+            PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME
+        );
+        assert!(tools[0].description.starts_with(original.trim_end()));
+        assert!(tools[0]
+            .description
+            .ends_with("\nInternal extensions are named acme-<team>."));
+
+        assert_eq!(tools[1].name, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME);
+        assert_eq!(tools[1].description, "Enable or disable an Acme extension.");
+        assert_eq!(
+            tools[1].input_schema["properties"]["extension_name"]["description"],
+            "Name in the Acme catalog"
+        );
+        assert!(tools[1].input_schema["properties"].get("missing").is_none());
+    }
+}
@@ -3,13 +3,19 @@ use std::sync::Arc;
 
 use async_stream::try_stream;
 use futures::stream::{self, BoxStream};
-use futures::{Stream, StreamExt};
+use futures::{FutureExt, Stream, StreamExt};
 use mcp_core::protocol::JsonRpcMessage;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Semaphore};
+use tokio_util::sync::CancellationToken;
 
+use crate::agents::audit::AuditApproval;
+use crate::agents::frontend_tools::{
+    next_update, FrontendToolTimeouts, FrontendToolUpdate, Waited,
+};
+use crate::agents::workspace_routers::RouterSelector;
 use crate::config::permission::PermissionLevel;
-use crate::config::PermissionManager;
-use crate::message::{Message, ToolRequest};
+use crate::config::{Config, PermissionManager};
+use crate::message::{Message, MessageContent, ToolConfirmationRequest, ToolRequest};
 use crate::permission::Permission;
 use mcp_core::{Content, ToolResult};
 
@@ -20,6 +26,64 @@ pub struct ToolCallResult {
     pub notification_stream: Option<Box<dyn Stream<Item = JsonRpcMessage> + Send + Unpin>>,
 }
 
+impl ToolCallResult {
+    /// Append a note for the model to a successful result
+    pub fn with_note(self, note: String) -> Self {
+        Self {
+            result: Box::new(self.result.map(move |result| {
+                result.map(|mut contents| {
+                    contents.push(Content::text(note));
+                    contents
+                })
+            })),
+            notification_stream: self.notification_stream,
+        }
+    }
+}
+
+/// Limits how many tool calls run at once
+///
+/// Approved calls of a turn are dispatched together and run in parallel. With
+/// `GOOSE_MAX_PARALLEL_TOOLS` set, a call waits for a free slot before it starts, so expensive
+/// MCP servers see at most that many calls at a time; `1` runs calls one after another.
+#[derive(Debug, Clone, Default)]
+pub struct ToolConcurrency {
+    slots: Option<Arc<Semaphore>>,
+}
+
+impl ToolConcurrency {
+    /// At most `max` calls at once, or any number without a limit or with a limit of 0
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            slots: max
+                .filter(|max| *max > 0)
+                .map(|max| Arc::new(Semaphore::new(max))),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(Config::global().get_param("GOOSE_MAX_PARALLEL_TOOLS").ok())
+    }
+
+    /// Hold the call back until a slot is free; its deadline starts once it runs
+    pub fn limit(&self, result: ToolCallResult) -> ToolCallResult {
+        let Some(slots) = self.slots.clone() else {
+            return result;
+        };
+        let call = result.result;
+        ToolCallResult {
+            result: Box::new(
+                async move {
+                    let _slot = slots.acquire_owned().await;
+                    call.await
+                }
+                .boxed(),
+            ),
+            notification_stream: result.notification_stream,
+        }
+    }
+}
+
 impl From<ToolResult<Vec<Content>>> for ToolCallResult {
     fn from(result: ToolResult<Vec<Content>>) -> Self {
         Self {
@@ -30,7 +94,7 @@ impl From<ToolResult<Vec<Content>>> for ToolCallResult {
 }
 
 use super::agent::{tool_stream, ToolStream};
-use crate::agents::Agent;
+use crate::agents::{Agent, AgentEvent, SessionConfig};
 
 pub const DECLINED_RESPONSE: &str = "The user has declined to run this tool. \
     DO NOT attempt to call this tool again. \
@@ -52,23 +116,40 @@ impl Agent {
         tool_futures: Arc<Mutex<Vec<(String, ToolStream)>>>,
         permission_manager: &'a mut PermissionManager,
         message_tool_response: Arc<Mutex<Message>>,
+        router: Option<&'a RouterSelector>,
+        cancel: &'a CancellationToken,
+        session: Option<&'a SessionConfig>,
     ) -> BoxStream<'a, anyhow::Result<Message>> {
         try_stream! {
             for request in tool_requests {
                 if let Ok(tool_call) = request.tool_call.clone() {
-                    let confirmation = Message::user().with_tool_confirmation_request(
-                        request.id.clone(),
-                        tool_call.name.clone(),
-                        tool_call.arguments.clone(),
-                        Some("Goose would like to call the above tool. Allow? (y/n):".to_string()),
-                    );
+                    let network_prompt = self
+                        .network_policy
+                        .lock()
+                        .await
+                        .prompt(&tool_call.name, &tool_call.arguments);
+                    let prompt = match network_prompt {
+                        Some(prompt) => Some(prompt),
+                        None => self.commit_prompt(&tool_call).await,
+                    }
+                    .unwrap_or_else(|| "Goose would like to call the above tool. Allow? (y/n):".to_string());
+                    let impact = self.preview_tool_impact(&tool_call).await;
+                    let confirmation = Message::user().with_content(MessageContent::ToolConfirmationRequest(
+                        ToolConfirmationRequest {
+                            id: request.id.clone(),
+                            tool_name: tool_call.name.clone(),
+                            arguments: tool_call.arguments.clone(),
+                            prompt: Some(prompt),
+                            impact,
+                        },
+                    ));
                     yield confirmation;
 
                     let mut rx = self.confirmation_rx.lock().await;
                     while let Some((req_id, confirmation)) = rx.recv().await {
                         if req_id == request.id {
                             if confirmation.permission == Permission::AllowOnce || confirmation.permission == Permission::AlwaysAllow {
-                                let (req_id, tool_result) = self.dispatch_tool_call(tool_call.clone(), request.id.clone()).await;
+                                let (req_id, tool_result) = self.dispatch_audited_tool_call(tool_call.clone(), request.id.clone(), AuditApproval::User, router, cancel, session).await;
                                 let mut futures = tool_futures.lock().await;
 
                                 futures.push((req_id, match tool_result {
@@ -84,9 +165,14 @@ impl Agent {
 
                                 if confirmation.permission == Permission::AlwaysAllow {
                                     permission_manager.update_user_permission(&tool_call.name, PermissionLevel::AlwaysAllow);
+                                    self.network_policy
+                                        .lock()
+                                        .await
+                                        .always_allow(&tool_call.name, &tool_call.arguments);
                                 }
                             } else {
                                 // User declined - add declined response
+                                self.audit_not_run(&request.id, &tool_call, AuditApproval::Declined, session);
                                 let mut response = message_tool_response.lock().await;
                                 *response = response.clone().with_tool_response(
                                     request.id.clone(),
@@ -105,20 +191,37 @@ impl Agent {
         &'a self,
         tool_requests: &'a [ToolRequest],
         message_tool_response: Arc<Mutex<Message>>,
-    ) -> BoxStream<'a, anyhow::Result<Message>> {
+    ) -> BoxStream<'a, anyhow::Result<AgentEvent>> {
         try_stream! {
+            let timeouts = FrontendToolTimeouts::from_config();
             for request in tool_requests {
                 if let Ok(tool_call) = request.tool_call.clone() {
                     if self.is_frontend_tool(&tool_call.name).await {
                         // Send frontend tool request and wait for response
-                        yield Message::assistant().with_frontend_tool_request(
+                        yield AgentEvent::Message(Message::assistant().with_frontend_tool_request(
                             request.id.clone(),
                             Ok(tool_call.clone())
-                        );
+                        ));
 
-                        if let Some((id, result)) = self.tool_result_rx.lock().await.recv().await {
+                        let mut received = false;
+                        let result = loop {
+                            match next_update(&self.tool_result_rx, &request.id, &tool_call.name, timeouts, received).await {
+                                Waited::Update(FrontendToolUpdate::Received) => received = true,
+                                Waited::Update(FrontendToolUpdate::Progress(progress)) => {
+                                    received = true;
+                                    yield AgentEvent::McpNotification((
+                                        request.id.clone(),
+                                        progress.notification(&request.id),
+                                    ));
+                                }
+                                Waited::Update(FrontendToolUpdate::Result(result)) => break Some(result),
+                                Waited::TimedOut(error) => break Some(Err(error)),
+                                Waited::Closed => break None,
+                            }
+                        };
+                        if let Some(result) = result {
                             let mut response = message_tool_response.lock().await;
-                            *response = response.clone().with_tool_response(id, result);
+                            *response = response.clone().with_tool_response(request.id.clone(), result);
                         }
                     }
                 }
@@ -127,3 +230,48 @@ impl Agent {
         .boxed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    fn tracked_call(running: Arc<AtomicUsize>, most: Arc<AtomicUsize>) -> ToolCallResult {
+        ToolCallResult {
+            result: Box::new(
+                async move {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(vec![])
+                }
+                .boxed(),
+            ),
+            notification_stream: None,
+        }
+    }
+
+    async fn most_at_once(concurrency: ToolConcurrency) -> usize {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let calls = (0..4).map(|_| {
+            concurrency
+                .limit(tracked_call(running.clone(), most.clone()))
+                .result
+        });
+        for result in futures::future::join_all(calls).await {
+            assert!(result.is_ok());
+        }
+        most.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits_calls_at_once() {
+        assert_eq!(most_at_once(ToolConcurrency::new(Some(1))).await, 1);
+        assert_eq!(most_at_once(ToolConcurrency::new(Some(2))).await, 2);
+        assert_eq!(most_at_once(ToolConcurrency::new(None)).await, 4);
+        assert_eq!(most_at_once(ToolConcurrency::new(Some(0))).await, 4);
+    }
+}
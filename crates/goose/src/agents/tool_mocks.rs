@@ -0,0 +1,190 @@
+//! Canned tool responses for recipe dry runs
+//!
+//! A mock file lists responses for the tools a recipe calls, so recipe authors can iterate
+//! on instructions and prompts without touching real systems. Mocked tools are not run.
+//!
+//! ```yaml
+//! tools:
+//!   developer__shell:
+//!     - when:
+//!         command: git status
+//!       response: "nothing to commit, working tree clean"
+//!     - when:
+//!         command: git push
+//!       error: "remote rejected"
+//!     - response: ""
+//!   github__create_issue:
+//!     - response: '{"number": 42}'
+//! block_unmocked: true
+//! ```
+//!
+//! The first response whose `when` arguments all equal the call's arguments is used; a
+//! response without `when` matches any call. With `block_unmocked`, calls to tools without a
+//! matching response fail instead of running.
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use mcp_core::{Content, ToolError, ToolResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::agents::Agent;
+
+/// One canned response for a tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockResponse {
+    /// Arguments the call must have for this response to be used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Map<String, Value>>,
+    /// Text returned to the model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    /// Error returned to the model instead of a response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl MockResponse {
+    fn matches(&self, arguments: &Value) -> bool {
+        self.when.as_ref().is_none_or(|expected| {
+            expected
+                .iter()
+                .all(|(key, value)| arguments.get(key) == Some(value))
+        })
+    }
+
+    fn result(&self) -> ToolResult<Vec<Content>> {
+        match &self.error {
+            Some(error) => Err(ToolError::ExecutionError(error.clone())),
+            None => Ok(vec![Content::text(
+                self.response.clone().unwrap_or_default(),
+            )]),
+        }
+    }
+}
+
+/// Canned responses by tool name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolMocks {
+    #[serde(default)]
+    pub tools: HashMap<String, Vec<MockResponse>>,
+    /// Fail calls to tools without a matching response instead of running them
+    #[serde(default)]
+    pub block_unmocked: bool,
+}
+
+impl ToolMocks {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mock file {}", path.display()))?;
+        let mocks: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid mock file {}", path.display()))?;
+        for (tool, responses) in &mocks.tools {
+            if let Some(both) = responses
+                .iter()
+                .find(|r| r.response.is_some() && r.error.is_some())
+            {
+                return Err(anyhow!(
+                    "Mock for {} has both a response and an error: {:?}",
+                    tool,
+                    both
+                ));
+            }
+        }
+        Ok(mocks)
+    }
+
+    /// The canned result for a tool call, or None if the tool should run
+    pub fn result_for(
+        &self,
+        tool_name: &str,
+        arguments: &Value,
+    ) -> Option<ToolResult<Vec<Content>>> {
+        let mocked = self
+            .tools
+            .get(tool_name)
+            .and_then(|responses| responses.iter().find(|r| r.matches(arguments)))
+            .map(MockResponse::result);
+        if mocked.is_none() && self.block_unmocked {
+            return Some(Err(ToolError::ExecutionError(format!(
+                "{} has no mocked response for these arguments and unmocked tools are blocked in this dry run",
+                tool_name
+            ))));
+        }
+        mocked
+    }
+}
+
+impl Agent {
+    /// Answer the tools listed in the mocks with canned responses instead of running them.
+    /// Pass `None` to run tools normally again.
+    pub async fn set_tool_mocks(&self, mocks: Option<ToolMocks>) {
+        *self.tool_mocks.lock().await = mocks;
+    }
+
+    /// The canned result for a tool call, if tool mocks are set and cover it
+    pub(super) async fn mocked_tool_result(
+        &self,
+        tool_call: &mcp_core::tool::ToolCall,
+    ) -> Option<ToolResult<Vec<Content>>> {
+        self.tool_mocks
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|mocks| mocks.result_for(&tool_call.name, &tool_call.arguments))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const MOCKS: &str = r#"
+tools:
+  developer__shell:
+    - when:
+        command: git status
+      response: "nothing to commit"
+    - when:
+        command: git push
+      error: "remote rejected"
+    - response: ""
+"#;
+
+    #[test]
+    fn test_first_matching_response_is_used() {
+        let mocks: ToolMocks = serde_yaml::from_str(MOCKS).unwrap();
+        let status = mocks
+            .result_for("developer__shell", &json!({"command": "git status"}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(status[0].as_text(), Some("nothing to commit"));
+
+        assert!(mocks
+            .result_for("developer__shell", &json!({"command": "git push"}))
+            .unwrap()
+            .is_err());
+
+        let other = mocks
+            .result_for("developer__shell", &json!({"command": "ls"}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(other[0].as_text(), Some(""));
+    }
+
+    #[test]
+    fn test_unmocked_tools_run_unless_blocked() {
+        let mut mocks: ToolMocks = serde_yaml::from_str(MOCKS).unwrap();
+        assert!(mocks
+            .result_for("developer__text_editor", &json!({}))
+            .is_none());
+
+        mocks.block_unmocked = true;
+        assert!(mocks
+            .result_for("developer__text_editor", &json!({}))
+            .unwrap()
+            .is_err());
+    }
+}
@@ -2,9 +2,15 @@ use anyhow::{anyhow, Result};
 use std::sync::Arc;
 use tracing;
 
+use crate::agents::code_actions::{code_actions_enabled, program_tools};
+use crate::agents::code_sandbox::CodeSandbox;
+use crate::agents::edit_transactions::edit_transactions_enabled;
 use crate::agents::extension_manager::ExtensionManager;
 use crate::agents::platform_tools;
 use crate::agents::router_tool_selector::{RouterToolSelectionStrategy, RouterToolSelector};
+use crate::agents::snapshots::SnapshotPolicy;
+use crate::agents::tool_descriptions::ToolDescriptions;
+use crate::agents::workspace_search::workspace_search_enabled;
 
 /// Manages tool indexing operations for the router when vector routing is enabled
 pub struct ToolRouterIndexManager;
@@ -85,6 +91,35 @@ impl ToolRouterIndexManager {
         // Add the standard platform tools
         tools.push(platform_tools::search_available_extensions_tool());
         tools.push(platform_tools::manage_extensions_tool());
+        tools.push(platform_tools::extension_logs_tool());
+        tools.push(platform_tools::anchor_tool());
+        tools.push(platform_tools::calculate_tool());
+
+        if SnapshotPolicy::from_config().enabled {
+            tools.push(platform_tools::undo_tool());
+        }
+
+        if workspace_search_enabled() {
+            tools.push(platform_tools::search_workspace_tool());
+        }
+
+        if edit_transactions_enabled() {
+            tools.push(platform_tools::edit_transaction_tool());
+        }
+
+        if code_actions_enabled() {
+            let extension_tools = extension_manager.get_prefixed_tools(None).await?;
+            tools.push(platform_tools::run_program_tool(&program_tools(
+                extension_tools,
+            )));
+        }
+
+        let sandbox = CodeSandbox::from_config();
+        if sandbox.is_available() {
+            tools.push(platform_tools::execute_code_tool(
+                &sandbox.available_languages(),
+            ));
+        }
 
         // Add resource tools if supported
         if extension_manager.supports_resources() {
@@ -92,6 +127,8 @@ impl ToolRouterIndexManager {
             tools.push(platform_tools::list_resources_tool());
         }
 
+        ToolDescriptions::from_config().apply(&mut tools);
+
         // Index all platform tools at once
         selector
             .index_tools(&tools, "platform")
@@ -102,6 +139,22 @@ impl ToolRouterIndexManager {
         Ok(())
     }
 
+    /// Indexes the platform tools and the tools of every extension, for a new selector
+    pub async fn index_all_tools(
+        selector: &Arc<Box<dyn RouterToolSelector>>,
+        extension_manager: &ExtensionManager,
+    ) -> Result<()> {
+        Self::index_platform_tools(selector, extension_manager).await?;
+        for name in extension_manager
+            .list_extensions()
+            .await
+            .unwrap_or_default()
+        {
+            Self::update_extension_tools(selector, extension_manager, &name, "add").await?;
+        }
+        Ok(())
+    }
+
     /// Helper to check if vector or llm tool router is enabled
     pub fn is_tool_router_enabled(selector: &Option<Arc<Box<dyn RouterToolSelector>>>) -> bool {
         selector.is_some()
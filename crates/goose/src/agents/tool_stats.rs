@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::workspace_routers::RouterSelector;
+use crate::agents::Agent;
+use crate::config::{Config, APP_STRATEGY};
+
+// Days after which a tool's past usage counts for half as much
+const HALF_LIFE_DAYS: f64 = 7.0;
+// Tools kept per workspace, the least relevant are dropped beyond this
+const MAX_TOOLS_PER_WORKSPACE: usize = 200;
+// Number of tools used to warm-start the router selector for a workspace
+const WARM_START_TOOLS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsageStats {
+    pub count: u64,
+    pub last_used: DateTime<Utc>,
+}
+
+impl ToolUsageStats {
+    /// Call count decayed by how long ago the tool was last used
+    fn score(&self, now: DateTime<Utc>) -> f64 {
+        let age_days = (now - self.last_used).num_seconds().max(0) as f64 / 86_400.0;
+        self.count as f64 * 0.5f64.powf(age_days / HALF_LIFE_DAYS)
+    }
+}
+
+type WorkspaceStats = HashMap<String, HashMap<String, ToolUsageStats>>;
+
+/// Drop the least relevant tools of a workspace beyond the limit
+fn prune(tools: &mut HashMap<String, ToolUsageStats>, now: DateTime<Utc>) {
+    while tools.len() > MAX_TOOLS_PER_WORKSPACE {
+        let Some(least_relevant) = tools
+            .iter()
+            .min_by(|a, b| a.1.score(now).total_cmp(&b.1.score(now)))
+            .map(|(name, _)| name.clone())
+        else {
+            return;
+        };
+        tools.remove(&least_relevant);
+    }
+}
+
+/// Tool call statistics per workspace, persisted across sessions so the router tool
+/// selector can start with the tools that were recently and frequently used in a workspace.
+/// Several goose processes can share the store, so each saves only the calls it recorded
+/// since its last save, added to what is in the file at the time.
+#[derive(Debug)]
+pub struct ToolStatsStore {
+    path: PathBuf,
+    workspaces: WorkspaceStats,
+    /// Calls recorded since the last save
+    pending: WorkspaceStats,
+}
+
+impl ToolStatsStore {
+    /// Load the store from the default location in the goose data directory
+    pub fn load_default() -> Result<Self> {
+        let data_dir = choose_app_strategy(APP_STRATEGY.clone())
+            .expect("goose requires a home dir")
+            .data_dir();
+        fs::create_dir_all(&data_dir)?;
+        Ok(Self::load(data_dir.join("tool_stats.json")))
+    }
+
+    /// Load the store from a file, starting empty if it is missing or unreadable
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let workspaces = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            workspaces,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Add the calls recorded since the last save to the statistics in the file, holding a
+    /// lock on it so concurrent saves are not lost, and pick up what others saved meanwhile
+    pub fn save(&mut self) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)?;
+        file.lock_exclusive()?;
+
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let mut workspaces: WorkspaceStats = serde_json::from_str(&contents).unwrap_or_default();
+        let now = Utc::now();
+        for (workspace, calls) in self.pending.drain() {
+            let tools = workspaces.entry(workspace).or_default();
+            for (tool_name, delta) in calls {
+                tools
+                    .entry(tool_name)
+                    .and_modify(|stats| {
+                        stats.count += delta.count;
+                        stats.last_used = stats.last_used.max(delta.last_used);
+                    })
+                    .or_insert(delta);
+            }
+            prune(tools, now);
+        }
+
+        file.seek(SeekFrom::Start(0))?;
+        file.set_len(0)?;
+        file.write_all(serde_json::to_string_pretty(&workspaces)?.as_bytes())?;
+        file.sync_all()?;
+        self.workspaces = workspaces;
+        Ok(())
+    }
+
+    fn workspace_key(workspace: &Path) -> String {
+        workspace.to_string_lossy().to_string()
+    }
+
+    pub fn record_tool_call(&mut self, workspace: &Path, tool_name: &str) {
+        self.record_tool_call_at(workspace, tool_name, Utc::now());
+    }
+
+    fn record_tool_call_at(&mut self, workspace: &Path, tool_name: &str, now: DateTime<Utc>) {
+        for workspaces in [&mut self.workspaces, &mut self.pending] {
+            workspaces
+                .entry(Self::workspace_key(workspace))
+                .or_default()
+                .entry(tool_name.to_string())
+                .and_modify(|stats| {
+                    stats.count += 1;
+                    stats.last_used = now;
+                })
+                .or_insert(ToolUsageStats {
+                    count: 1,
+                    last_used: now,
+                });
+        }
+        if let Some(tools) = self.workspaces.get_mut(&Self::workspace_key(workspace)) {
+            prune(tools, now);
+        }
+    }
+
+    /// The most relevant tools for a workspace, most relevant first
+    pub fn top_tools(&self, workspace: &Path, limit: usize) -> Vec<String> {
+        self.top_tools_at(workspace, limit, Utc::now())
+    }
+
+    fn top_tools_at(&self, workspace: &Path, limit: usize, now: DateTime<Utc>) -> Vec<String> {
+        let Some(tools) = self.workspaces.get(&Self::workspace_key(workspace)) else {
+            return Vec::new();
+        };
+        let mut ranked: Vec<(&String, f64)> = tools
+            .iter()
+            .map(|(name, stats)| (name, stats.score(now)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    pub fn get(&self, workspace: &Path, tool_name: &str) -> Option<&ToolUsageStats> {
+        self.workspaces
+            .get(&Self::workspace_key(workspace))?
+            .get(tool_name)
+    }
+}
+
+impl Agent {
+    /// Load persisted tool statistics, unless disabled with `GOOSE_ROUTER_PERSIST_STATS`
+    pub(super) async fn load_tool_stats_store(&self) {
+        if !Config::global()
+            .get_param::<bool>("GOOSE_ROUTER_PERSIST_STATS")
+            .unwrap_or(true)
+        {
+            return;
+        }
+        match ToolStatsStore::load_default() {
+            Ok(store) => *self.tool_stats_store.lock().await = Some(store),
+            Err(e) => tracing::warn!("Failed to load tool statistics: {}", e),
+        }
+    }
+
+    /// Seed a router selector's recent tools with the workspace's most relevant tools, if it
+    /// has not seen any calls yet
+    pub(super) async fn warm_start_router(&self, selector: &RouterSelector, workspace: &Path) {
+        if !selector
+            .get_recent_tool_calls(1)
+            .await
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return;
+        }
+
+        let top_tools = match self.tool_stats_store.lock().await.as_ref() {
+            Some(store) => store.top_tools(workspace, WARM_START_TOOLS),
+            None => return,
+        };
+        // Record the most relevant tool last so it is the most recent
+        for tool_name in top_tools.iter().rev() {
+            if let Err(e) = selector.record_tool_call(tool_name).await {
+                tracing::warn!("Failed to warm-start router with {}: {}", tool_name, e);
+            }
+        }
+    }
+
+    /// Add tool calls to the persisted statistics for a workspace
+    pub(super) async fn persist_tool_calls(&self, workspace: &Path, tool_names: &[String]) {
+        if tool_names.is_empty() {
+            return;
+        }
+        if let Some(store) = self.tool_stats_store.lock().await.as_mut() {
+            for tool_name in tool_names {
+                store.record_tool_call(workspace, tool_name);
+            }
+            if let Err(e) = store.save() {
+                tracing::warn!("Failed to save tool statistics: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ranking_prefers_recent_and_frequent_tools() {
+        let dir = TempDir::new().unwrap();
+        let mut store = ToolStatsStore::load(dir.path().join("tool_stats.json"));
+        let workspace = Path::new("/projects/goose");
+        let now = Utc::now();
+
+        for _ in 0..3 {
+            store.record_tool_call_at(workspace, "developer__shell", now);
+        }
+        // Used more often, but a month ago
+        for _ in 0..6 {
+            store.record_tool_call_at(workspace, "jira__search", now - Duration::days(30));
+        }
+        store.record_tool_call_at(workspace, "developer__text_editor", now);
+
+        assert_eq!(
+            store.top_tools_at(workspace, 2, now),
+            vec!["developer__shell", "developer__text_editor"]
+        );
+        assert!(store
+            .top_tools_at(Path::new("/projects/other"), 5, now)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_persists_across_loads() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tool_stats.json");
+        let workspace = Path::new("/projects/goose");
+
+        let mut store = ToolStatsStore::load(&path);
+        store.record_tool_call(workspace, "developer__shell");
+        store.record_tool_call(workspace, "developer__shell");
+        store.save().unwrap();
+
+        let reloaded = ToolStatsStore::load(&path);
+        assert_eq!(
+            reloaded.get(workspace, "developer__shell").unwrap().count,
+            2
+        );
+        assert_eq!(reloaded.top_tools(workspace, 5), vec!["developer__shell"]);
+    }
+
+    #[test]
+    fn test_concurrent_stores_do_not_lose_calls() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tool_stats.json");
+        let workspace = Path::new("/projects/goose");
+
+        // Two sessions load the store before either has saved
+        let mut first = ToolStatsStore::load(&path);
+        let mut second = ToolStatsStore::load(&path);
+        first.record_tool_call(workspace, "developer__shell");
+        first.save().unwrap();
+        second.record_tool_call(workspace, "developer__shell");
+        second.record_tool_call(workspace, "jira__search");
+        second.save().unwrap();
+        // Saving again adds nothing that was already saved
+        first.save().unwrap();
+
+        let reloaded = ToolStatsStore::load(&path);
+        assert_eq!(
+            reloaded.get(workspace, "developer__shell").unwrap().count,
+            2
+        );
+        assert_eq!(reloaded.get(workspace, "jira__search").unwrap().count, 1);
+        // The second session sees the first's calls after saving
+        assert_eq!(second.get(workspace, "developer__shell").unwrap().count, 2);
+    }
+}
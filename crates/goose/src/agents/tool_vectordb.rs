@@ -335,6 +335,15 @@ impl ToolVectorDB {
         Ok(tools)
     }
 
+    /// Remove the table with every tool in it
+    pub async fn drop_table(&self) -> Result<()> {
+        let connection = self.connection.write().await;
+        connection
+            .drop_table(&self.table_name)
+            .await
+            .with_context(|| format!("Failed to drop tools table '{}'", self.table_name))
+    }
+
     pub async fn remove_tool(&self, tool_name: &str) -> Result<()> {
         let connection = self.connection.read().await;
 
@@ -354,8 +363,13 @@ impl ToolVectorDB {
     }
 }
 
+/// A table name of its own for each selector, as several can be created in the same second
 pub fn generate_table_id() -> String {
-    Local::now().format("%Y%m%d_%H%M%S").to_string()
+    format!(
+        "{}_{}",
+        Local::now().format("%Y%m%d_%H%M%S"),
+        uuid::Uuid::new_v4().simple()
+    )
 }
 
 #[cfg(test)]
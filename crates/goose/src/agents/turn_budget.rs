@@ -0,0 +1,265 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::Agent;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use crate::providers::base::Provider;
+use mcp_core::Role;
+
+const QUICK_MAX_TURNS: u32 = 5;
+const MODERATE_MAX_TURNS: u32 = 20;
+const COMPLEX_MAX_TURNS: u32 = 60;
+
+/// Keywords that usually indicate a multi-step task rather than a question
+const MULTI_STEP_KEYWORDS: &[&str] = &[
+    "implement",
+    "refactor",
+    "migrate",
+    "build",
+    "create",
+    "set up",
+    "setup",
+    "debug",
+    "fix",
+    "add",
+    "write",
+    "port",
+    "upgrade",
+];
+
+/// Rough classification of how much work a request needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskComplexity {
+    Quick,
+    Moderate,
+    Complex,
+}
+
+impl TaskComplexity {
+    fn config_suffix(&self) -> &'static str {
+        match self {
+            TaskComplexity::Quick => "QUICK",
+            TaskComplexity::Moderate => "MODERATE",
+            TaskComplexity::Complex => "COMPLEX",
+        }
+    }
+
+    fn default_max_turns(&self) -> u32 {
+        match self {
+            TaskComplexity::Quick => QUICK_MAX_TURNS,
+            TaskComplexity::Moderate => MODERATE_MAX_TURNS,
+            TaskComplexity::Complex => COMPLEX_MAX_TURNS,
+        }
+    }
+}
+
+/// The number of provider turns a reply may take, and optionally the model to use for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TurnBudget {
+    /// The estimated complexity, if the budget came from an estimate
+    pub complexity: Option<TaskComplexity>,
+    pub max_turns: u32,
+    /// Model to use for this reply instead of the agent's current model
+    pub model: Option<String>,
+}
+
+impl TurnBudget {
+    pub fn new(max_turns: u32) -> Self {
+        Self {
+            complexity: None,
+            max_turns,
+            model: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    /// Budget for an estimated complexity. Each level can be tuned with
+    /// `GOOSE_TURN_BUDGET_<LEVEL>` and `GOOSE_<LEVEL>_TASK_MODEL`, e.g.
+    /// `GOOSE_TURN_BUDGET_COMPLEX=100` and `GOOSE_QUICK_TASK_MODEL=gpt-4o-mini`.
+    pub fn for_complexity(complexity: TaskComplexity) -> Self {
+        let config = Config::global();
+        let suffix = complexity.config_suffix();
+        let max_turns = config
+            .get_param(&format!("GOOSE_TURN_BUDGET_{}", suffix))
+            .unwrap_or(complexity.default_max_turns());
+        let model = config
+            .get_param(&format!("GOOSE_{}_TASK_MODEL", suffix))
+            .ok();
+
+        Self {
+            complexity: Some(complexity),
+            max_turns,
+            model,
+        }
+    }
+}
+
+/// Classifies the complexity of the task in a conversation before the agent starts working on it
+#[async_trait]
+pub trait ComplexityEstimator: Send + Sync {
+    async fn estimate(&self, messages: &[Message]) -> TaskComplexity;
+}
+
+/// Estimates complexity from the shape of the latest user message
+#[derive(Debug, Default)]
+pub struct HeuristicComplexityEstimator;
+
+impl HeuristicComplexityEstimator {
+    pub fn classify(text: &str) -> TaskComplexity {
+        let lower = text.to_lowercase();
+        let words = lower.split_whitespace().count();
+        let list_items = lower
+            .lines()
+            .map(str::trim_start)
+            .filter(|line| {
+                line.starts_with("- ")
+                    || line.starts_with("* ")
+                    || line
+                        .split_once(". ")
+                        .is_some_and(|(n, _)| n.chars().all(|c| c.is_ascii_digit()))
+            })
+            .count();
+        let tokens: Vec<&str> = lower
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .collect();
+        let has_keyword = MULTI_STEP_KEYWORDS.iter().any(|keyword| {
+            if keyword.contains(' ') {
+                lower.contains(keyword)
+            } else {
+                tokens.contains(keyword)
+            }
+        });
+
+        if words > 150 || list_items >= 3 || (has_keyword && words > 40) {
+            TaskComplexity::Complex
+        } else if has_keyword || words > 30 {
+            TaskComplexity::Moderate
+        } else {
+            TaskComplexity::Quick
+        }
+    }
+}
+
+#[async_trait]
+impl ComplexityEstimator for HeuristicComplexityEstimator {
+    async fn estimate(&self, messages: &[Message]) -> TaskComplexity {
+        let text = messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::User && !m.as_concat_text().trim().is_empty())
+            .map(|m| m.as_concat_text())
+            .unwrap_or_default();
+        Self::classify(&text)
+    }
+}
+
+impl Agent {
+    /// Override the turn budget for subsequent replies, bypassing any estimate.
+    /// Pass `None` to go back to estimating (or the flat `GOOSE_MAX_TURNS`).
+    pub async fn set_turn_budget(&self, budget: Option<TurnBudget>) {
+        *self.turn_budget_override.lock().await = budget;
+    }
+
+    /// Use a custom estimator to pick the turn budget for each reply
+    pub async fn set_complexity_estimator(&self, estimator: Arc<dyn ComplexityEstimator>) {
+        *self.complexity_estimator.lock().await = Some(estimator);
+    }
+
+    /// Resolve the budget for a reply: a caller override wins, then an estimate when an
+    /// estimator is set or `GOOSE_ADAPTIVE_TURN_BUDGET` is enabled, then `GOOSE_MAX_TURNS`.
+    pub(crate) async fn resolve_turn_budget(&self, messages: &[Message]) -> Option<TurnBudget> {
+        if let Some(budget) = self.turn_budget_override.lock().await.clone() {
+            return Some(budget);
+        }
+
+        let config = Config::global();
+        let estimator = self.complexity_estimator.lock().await.clone().or_else(|| {
+            config
+                .get_param::<bool>("GOOSE_ADAPTIVE_TURN_BUDGET")
+                .unwrap_or(false)
+                .then(|| Arc::new(HeuristicComplexityEstimator) as Arc<dyn ComplexityEstimator>)
+        });
+
+        if let Some(estimator) = estimator {
+            let complexity = estimator.estimate(messages).await;
+            return Some(TurnBudget::for_complexity(complexity));
+        }
+
+        config
+            .get_param::<u32>("GOOSE_MAX_TURNS")
+            .ok()
+            .map(TurnBudget::new)
+    }
+
+    /// The agent's provider with the model requested by a turn budget
+    pub(crate) async fn provider_for_budget(
+        &self,
+        budget: &TurnBudget,
+    ) -> Option<Arc<dyn Provider>> {
+        let model = budget.model.as_ref()?;
+        let provider = self.provider().await.ok()?;
+        match provider.with_model_override(&|_| ModelConfig::new(model.clone())) {
+            Ok(provider) => Some(provider),
+            Err(e) => {
+                tracing::warn!("Failed to create provider for model {}: {}", model, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_quick_question() {
+        assert_eq!(
+            HeuristicComplexityEstimator::classify("what time zone is the server in?"),
+            TaskComplexity::Quick
+        );
+    }
+
+    #[test]
+    fn test_classify_moderate_task() {
+        assert_eq!(
+            HeuristicComplexityEstimator::classify("fix the failing test in parser.rs"),
+            TaskComplexity::Moderate
+        );
+    }
+
+    #[test]
+    fn test_classify_complex_project() {
+        let request = "Please do the following:\n\
+            1. add a new endpoint\n\
+            2. write tests for it\n\
+            3. update the docs";
+        assert_eq!(
+            HeuristicComplexityEstimator::classify(request),
+            TaskComplexity::Complex
+        );
+    }
+
+    #[tokio::test]
+    async fn test_estimate_uses_latest_user_message() {
+        let messages = vec![
+            Message::user().with_text("implement a full authentication system with OAuth, session storage, password resets, rate limiting and audit logging, then deploy it to staging and verify everything end to end before writing the docs"),
+            Message::assistant().with_text("done"),
+            Message::user().with_text("thanks, what port is it on?"),
+        ];
+        assert_eq!(
+            HeuristicComplexityEstimator.estimate(&messages).await,
+            TaskComplexity::Quick
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Turn records posted to a webhook as a session progresses
+//!
+//! External systems can follow what an agent does by receiving a record of every turn: the
+//! assistant's message, the tools it called and the usage of the provider call.
+//!
+//! ```yaml
+//! GOOSE_TURN_WEBHOOK:
+//!   url: https://audit.example.com/goose/turns
+//!   max_retries: 3
+//!   headers:
+//!     X-Team: platform
+//! ```
+//!
+//! With a `GOOSE_TURN_WEBHOOK_SECRET` in the secret store, each request carries the
+//! hex encoded HMAC-SHA256 of its body in `X-Goose-Signature` as `sha256=<digest>`, which the
+//! receiver checks with the same secret. Records are posted in the background so a slow
+//! endpoint never holds up the reply; failed posts are retried with exponential backoff, and
+//! dropped with a warning once the retries run out.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::providers::base::ProviderUsage;
+use crate::providers::http_client;
+use crate::session::Identifier;
+
+pub const SIGNATURE_HEADER: &str = "X-Goose-Signature";
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry, doubled for each one after it
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_max_retries() -> u32 {
+    DEFAULT_MAX_RETRIES
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TurnWebhookConfig {
+    pub url: String,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// What the webhook is told about a turn
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnRecord {
+    pub session_id: Option<String>,
+    /// The turn within its reply, from 1
+    pub turn: u32,
+    pub timestamp: i64,
+    pub message: Message,
+    /// Names of the tools the message calls
+    pub tools_called: Vec<String>,
+    pub usage: ProviderUsage,
+}
+
+impl TurnRecord {
+    pub fn new(
+        session_id: Option<&Identifier>,
+        turn: u32,
+        message: &Message,
+        usage: &ProviderUsage,
+    ) -> Self {
+        let session_id = session_id.map(|id| match id {
+            Identifier::Name(name) => name.clone(),
+            Identifier::Path(path) => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.display().to_string()),
+        });
+        let tools_called = message
+            .content
+            .iter()
+            .filter_map(|content| match content {
+                MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+                _ => None,
+            })
+            .map(|call| call.name.clone())
+            .collect();
+        Self {
+            session_id,
+            turn,
+            timestamp: chrono::Utc::now().timestamp(),
+            message: message.clone(),
+            tools_called,
+            usage: usage.clone(),
+        }
+    }
+}
+
+/// The value of the signature header for a body
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body);
+    let digest: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", digest)
+}
+
+/// Whether a failed post may succeed when retried
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+#[derive(Clone)]
+pub struct TurnWebhook {
+    client: reqwest::Client,
+    config: TurnWebhookConfig,
+    secret: Option<String>,
+}
+
+impl TurnWebhook {
+    pub fn new(config: TurnWebhookConfig, secret: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client: http_client::default_client()?,
+            config,
+            secret,
+        })
+    }
+
+    /// The webhook from `GOOSE_TURN_WEBHOOK`, if one is configured
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let webhook: TurnWebhookConfig = config.get_param("GOOSE_TURN_WEBHOOK").ok()?;
+        let secret = config.get_secret("GOOSE_TURN_WEBHOOK_SECRET").ok();
+        match Self::new(webhook, secret) {
+            Ok(webhook) => Some(webhook),
+            Err(e) => {
+                tracing::error!("Failed to create turn webhook: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Post the record in the background
+    pub fn send(&self, record: TurnRecord) {
+        let webhook = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = webhook.post(&record).await {
+                tracing::warn!(
+                    "Dropped turn {} record after failing to post it to {}: {}",
+                    record.turn,
+                    webhook.config.url,
+                    e
+                );
+            }
+        });
+    }
+
+    async fn post(&self, record: &TurnRecord) -> Result<()> {
+        let body = serde_json::to_vec(record)?;
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let error = match self.post_once(&body).await {
+                Ok(status) if status.is_success() => return Ok(()),
+                Ok(status) if !is_retryable(status) => {
+                    return Err(anyhow!("Webhook returned {}", status))
+                }
+                Ok(status) => anyhow!("Webhook returned {}", status),
+                Err(e) => e.into(),
+            };
+            if attempt >= self.config.max_retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tracing::debug!("Retrying turn webhook in {:?}: {}", backoff, error);
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    async fn post_once(&self, body: &[u8]) -> reqwest::Result<reqwest::StatusCode> {
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .timeout(REQUEST_TIMEOUT)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body));
+        }
+        for (key, value) in &self.config.headers {
+            request = request.header(key, value);
+        }
+        Ok(request.send().await?.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_signature_matches_known_digest() {
+        // From RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_record_lists_tools_called() {
+        let message = Message::assistant()
+            .with_text("Let me look")
+            .with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            );
+        let usage = ProviderUsage::new("gpt-4o".to_string(), Usage::default());
+        let record = TurnRecord::new(
+            Some(&Identifier::Path("/tmp/sessions/20250101_1.jsonl".into())),
+            2,
+            &message,
+            &usage,
+        );
+        assert_eq!(record.session_id.as_deref(), Some("20250101_1"));
+        assert_eq!(record.tools_called, vec!["developer__shell"]);
+        assert!(!is_retryable(reqwest::StatusCode::BAD_REQUEST));
+        assert!(is_retryable(reqwest::StatusCode::BAD_GATEWAY));
+    }
+}
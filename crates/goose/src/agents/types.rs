@@ -1,12 +1,16 @@
+use crate::agents::frontend_tools::FrontendToolUpdate;
+use crate::config::ConfigOverrides;
+use crate::model::SamplingOverrides;
+use crate::providers::attribution::AttributionTags;
 use crate::session;
-use mcp_core::{Content, Tool, ToolResult};
+use mcp_core::Tool;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 
-/// Type alias for the tool result channel receiver
-pub type ToolResultReceiver = Arc<Mutex<mpsc::Receiver<(String, ToolResult<Vec<Content>>)>>>;
+/// Type alias for the channel receiving receipts, progress and results of frontend tool calls
+pub type ToolResultReceiver = Arc<Mutex<mpsc::Receiver<(String, FrontendToolUpdate)>>>;
 
 /// A frontend tool that will be executed by the frontend rather than an extension
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,4 +28,13 @@ pub struct SessionConfig {
     pub working_dir: PathBuf,
     /// ID of the schedule that triggered this session, if any
     pub schedule_id: Option<String>, // NEW
+    /// Temperature, max output tokens and top_p overrides for replies in this session
+    #[serde(default)]
+    pub sampling: Option<SamplingOverrides>,
+    /// Attribution tags charged with the usage of this session, such as team or user id
+    #[serde(default)]
+    pub tags: AttributionTags,
+    /// Config values this session uses instead of the process-wide ones, such as `GOOSE_MODE`
+    #[serde(default)]
+    pub config_overrides: ConfigOverrides,
 }
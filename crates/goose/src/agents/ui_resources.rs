@@ -0,0 +1,222 @@
+//! Rich UI returned by tools
+//!
+//! Some MCP servers answer a tool call with a user interface rather than text: an embedded
+//! resource with a `ui://` URI, or with HTML content. Such a resource is meant for the user,
+//! and sending its markup to the model wastes tokens on something it cannot see. Each UI
+//! resource in a tool result is kept for the user only, with a short text fallback for the
+//! model in its place, and is announced with an [`AgentEvent::UiResources`] so frontends can
+//! render it next to the tool call.
+//!
+//! [`AgentEvent::UiResources`]: super::AgentEvent::UiResources
+use futures::FutureExt;
+use mcp_core::{Content, ResourceContents, Role, ToolResult};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::tool_execution::ToolCallResult;
+
+const UI_SCHEME: &str = "ui://";
+/// Longest visible text of an HTML resource given to the model
+const FALLBACK_MAX_CHARS: usize = 2_000;
+
+static HIDDEN_ELEMENTS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(script|style|head)\b.*?</(script|style|head)>").unwrap());
+static TAGS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UiResourceKind {
+    /// An HTML document, rendered in a sandboxed frame
+    Html,
+    /// A page to load from its URL
+    ExternalUrl,
+    /// A component tree in the MCP-UI remote DOM format
+    RemoteDom,
+    /// A `ui://` resource of another type
+    Other,
+}
+
+impl UiResourceKind {
+    fn from_mime_type(mime_type: Option<&str>) -> Self {
+        match mime_type.unwrap_or_default() {
+            "text/html" => UiResourceKind::Html,
+            "text/uri-list" => UiResourceKind::ExternalUrl,
+            mime if mime.starts_with("application/vnd.mcp-ui.remote-dom") => {
+                UiResourceKind::RemoteDom
+            }
+            _ => UiResourceKind::Other,
+        }
+    }
+}
+
+/// A UI resource returned by a tool call, for a frontend to render
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UiResource {
+    /// The id of the tool request that returned it
+    pub request_id: String,
+    pub uri: String,
+    pub kind: UiResourceKind,
+    pub mime_type: Option<String>,
+    pub text: Option<String>,
+    /// Base64 encoded content, for resources that are not text
+    pub blob: Option<String>,
+}
+
+impl UiResource {
+    fn from_content(request_id: &str, content: &Content) -> Option<Self> {
+        let Content::Resource(resource) = content else {
+            return None;
+        };
+        let (uri, mime_type, text, blob) = match &resource.resource {
+            ResourceContents::TextResourceContents {
+                uri,
+                mime_type,
+                text,
+            } => (uri, mime_type, Some(text.clone()), None),
+            ResourceContents::BlobResourceContents {
+                uri,
+                mime_type,
+                blob,
+            } => (uri, mime_type, None, Some(blob.clone())),
+        };
+        if !uri.starts_with(UI_SCHEME) && mime_type.as_deref() != Some("text/html") {
+            return None;
+        }
+        Some(Self {
+            request_id: request_id.to_string(),
+            uri: uri.clone(),
+            kind: UiResourceKind::from_mime_type(mime_type.as_deref()),
+            mime_type: mime_type.clone(),
+            text,
+            blob,
+        })
+    }
+
+    /// What the model is told in place of the resource
+    fn fallback(&self) -> String {
+        let mut fallback = format!(
+            "The tool returned an interactive view ({}) that is shown to the user.",
+            self.uri
+        );
+        match (self.kind, &self.text) {
+            (UiResourceKind::Html, Some(html)) => {
+                let visible = visible_text(html);
+                if !visible.is_empty() {
+                    fallback.push_str(&format!(" Its text reads:\n{}", visible));
+                }
+            }
+            (UiResourceKind::ExternalUrl, Some(urls)) => {
+                fallback.push_str(&format!(" It loads {}", urls.trim()));
+            }
+            _ => {}
+        }
+        fallback
+    }
+}
+
+/// The text of an HTML document without its markup, shortened for the model
+fn visible_text(html: &str) -> String {
+    let without_hidden = HIDDEN_ELEMENTS.replace_all(html, " ");
+    let text = TAGS.replace_all(&without_hidden, " ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(FALLBACK_MAX_CHARS) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    }
+}
+
+/// The UI resources in a tool result
+pub fn ui_resources(request_id: &str, result: &ToolResult<Vec<Content>>) -> Vec<UiResource> {
+    result
+        .iter()
+        .flatten()
+        .filter_map(|content| UiResource::from_content(request_id, content))
+        .collect()
+}
+
+/// Keep the UI resources of a result for the user only, each followed by a text fallback for
+/// the model. Resources that already name their audience are left as they are.
+pub fn with_text_fallbacks(contents: Vec<Content>) -> Vec<Content> {
+    let mut processed = Vec::with_capacity(contents.len());
+    for content in contents {
+        let resource = match content.audience() {
+            None => UiResource::from_content("", &content),
+            Some(_) => None,
+        };
+        match resource {
+            Some(resource) => {
+                processed.push(content.with_audience(vec![Role::User]));
+                processed
+                    .push(Content::text(resource.fallback()).with_audience(vec![Role::Assistant]));
+            }
+            None => processed.push(content),
+        }
+    }
+    processed
+}
+
+impl ToolCallResult {
+    /// Give the model text fallbacks in place of the UI resources of the result
+    pub fn with_ui_fallbacks(self) -> Self {
+        Self {
+            result: Box::new(self.result.map(|result| result.map(with_text_fallbacks))),
+            notification_stream: self.notification_stream,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(uri: &str, mime_type: &str, text: &str) -> Content {
+        Content::resource(ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(mime_type.to_string()),
+            text: text.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_ui_resources_get_a_fallback() {
+        let html = "<html><head><style>p{}</style></head><body><h1>Flights</h1>\
+                    <p>AMS to SFO, 3 results</p><script>render()</script></body></html>";
+        let contents = vec![
+            Content::text("Found 3 flights"),
+            resource("ui://flights/results", "text/html", html),
+            resource("file:///notes.md", "text/markdown", "# Notes"),
+        ];
+
+        let resources = ui_resources("req_1", &Ok(contents.clone()));
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].request_id, "req_1");
+        assert_eq!(resources[0].kind, UiResourceKind::Html);
+        assert_eq!(resources[0].text.as_deref(), Some(html));
+
+        let processed = with_text_fallbacks(contents);
+        assert_eq!(processed.len(), 4);
+        assert_eq!(processed[1].audience(), Some(&vec![Role::User]));
+        assert_eq!(processed[2].audience(), Some(&vec![Role::Assistant]));
+        let fallback = processed[2].as_text().unwrap();
+        assert!(fallback.contains("ui://flights/results"));
+        assert!(fallback.contains("Flights AMS to SFO, 3 results"));
+        assert!(!fallback.contains("render()"));
+        // Other resources are sent to the model as before
+        assert!(processed[3].audience().is_none());
+    }
+
+    #[test]
+    fn test_kind_from_mime_type() {
+        assert_eq!(
+            UiResourceKind::from_mime_type(Some("text/uri-list")),
+            UiResourceKind::ExternalUrl
+        );
+        assert_eq!(
+            UiResourceKind::from_mime_type(Some("application/vnd.mcp-ui.remote-dom+javascript")),
+            UiResourceKind::RemoteDom
+        );
+        assert_eq!(UiResourceKind::from_mime_type(None), UiResourceKind::Other);
+    }
+}
@@ -0,0 +1,322 @@
+//! Tool routing per workspace
+//!
+//! Which tools are relevant depends on the project: the recent tool calls that weight the
+//! router's choices in one repository say little about another, and a project can bring its
+//! own extensions. Each workspace root, the enclosing git repository of a session's working
+//! directory or the directory itself, gets its own router tool selector. A selector is created
+//! the first time a reply runs in its workspace and is reused by later sessions in the same
+//! root, for as long as it holds the tools of the agent's current extensions. The reply
+//! carries its selector to the tool calls it makes, so replies in different workspaces can
+//! run side by side.
+//!
+//! Extensions that a reply enables are indexed into its selector, and into the agent's own
+//! selector that serves calls outside of replies. A selector that missed a change of
+//! extensions is rebuilt rather than reused. Only the most recently used workspaces keep their
+//! selector; the vector table of one that is dropped goes with it.
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+
+use crate::agents::router_fallback::configured_router_strategy;
+use crate::agents::router_tool_selector::{
+    create_tool_selector, RouterToolSelectionStrategy, RouterToolSelector,
+};
+use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
+use crate::agents::tool_vectordb::generate_table_id;
+use crate::providers::base::Provider;
+
+use super::Agent;
+
+/// Workspaces that keep their selector
+const MAX_WORKSPACES: usize = 8;
+
+pub(crate) type RouterSelector = Arc<Box<dyn RouterToolSelector>>;
+
+/// The root of the workspace a directory is in: the nearest enclosing git repository, or the
+/// directory itself outside of one
+pub fn workspace_root(dir: &Path) -> PathBuf {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .unwrap_or(dir)
+        .to_path_buf()
+}
+
+struct WorkspaceRouter {
+    root: PathBuf,
+    /// The extensions whose tools the selector holds, sorted
+    extensions: Vec<String>,
+    selector: RouterSelector,
+}
+
+/// The router tool selectors of recent workspaces, least recently used first
+#[derive(Default)]
+pub(super) struct WorkspaceRouters {
+    routers: Vec<WorkspaceRouter>,
+}
+
+impl WorkspaceRouters {
+    pub(super) fn clear(&mut self) {
+        self.routers.clear();
+    }
+
+    /// Record which extensions a selector holds, returning its workspace
+    fn record_extensions(
+        &mut self,
+        selector: &RouterSelector,
+        extensions: &[String],
+    ) -> Option<PathBuf> {
+        let router = self
+            .routers
+            .iter_mut()
+            .find(|router| Arc::ptr_eq(&router.selector, selector))?;
+        router.extensions = extensions.to_vec();
+        Some(router.root.clone())
+    }
+
+    fn owns(&self, selector: &RouterSelector) -> bool {
+        self.routers
+            .iter()
+            .any(|router| Arc::ptr_eq(&router.selector, selector))
+    }
+
+    /// The selector of a workspace, if it holds the tools of these extensions
+    fn get(&mut self, root: &Path, extensions: &[String]) -> Option<RouterSelector> {
+        let index = self.routers.iter().position(|router| router.root == root)?;
+        let router = self.routers.remove(index);
+        if router.extensions != extensions {
+            return None;
+        }
+        let selector = router.selector.clone();
+        self.routers.push(router);
+        Some(selector)
+    }
+
+    fn insert(&mut self, root: PathBuf, extensions: Vec<String>, selector: RouterSelector) {
+        self.routers.retain(|router| router.root != root);
+        self.routers.push(WorkspaceRouter {
+            root,
+            extensions,
+            selector,
+        });
+        if self.routers.len() > MAX_WORKSPACES {
+            self.routers.remove(0);
+        }
+    }
+}
+
+impl Agent {
+    async fn extension_names(&self) -> Vec<String> {
+        let mut names = self
+            .extension_manager
+            .lock()
+            .await
+            .list_extensions()
+            .await
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// A new selector for the configured strategy holding every tool, or None without a router
+    /// or once vector routing has failed
+    pub(super) async fn create_router_tool_selector(
+        &self,
+        provider: Arc<dyn Provider>,
+    ) -> Result<Option<RouterSelector>> {
+        let strategy = configured_router_strategy();
+        let selector = match strategy {
+            Some(RouterToolSelectionStrategy::Vector) => {
+                let table_name = generate_table_id();
+                match create_tool_selector(strategy, provider, Some(table_name)).await {
+                    Ok(selector) => Arc::new(selector),
+                    Err(e) => {
+                        self.degrade_tool_routing(format!("Failed to create tool selector: {}", e))
+                            .await;
+                        return Ok(None);
+                    }
+                }
+            }
+            Some(RouterToolSelectionStrategy::Llm) => {
+                let selector = create_tool_selector(strategy, provider, None)
+                    .await
+                    .map_err(|e| anyhow!("Failed to create tool selector: {}", e))?;
+                Arc::new(selector)
+            }
+            None => return Ok(None),
+        };
+        let extension_manager = self.extension_manager.lock().await;
+        if let Err(e) = ToolRouterIndexManager::index_all_tools(&selector, &extension_manager).await
+        {
+            if strategy != Some(RouterToolSelectionStrategy::Vector) {
+                return Err(e);
+            }
+            drop(extension_manager);
+            self.degrade_tool_routing(e.to_string()).await;
+            return Ok(None);
+        }
+        Ok(Some(selector))
+    }
+
+    /// The selector a reply in a workspace routes tools with, creating it the first time. The
+    /// agent's own selector becomes the workspace's when no workspace has it yet, as after the
+    /// provider was set. Replies without a workspace use the agent's own selector.
+    pub(super) async fn workspace_router(
+        &self,
+        workspace: Option<&Path>,
+    ) -> Option<RouterSelector> {
+        configured_router_strategy()?;
+        // None while routing is degraded, or released while idle
+        let own = self.router_tool_selector.lock().await.clone()?;
+        let Some(workspace) = workspace else {
+            return Some(own);
+        };
+        let root = workspace_root(workspace);
+        let extensions = self.extension_names().await;
+
+        {
+            let mut routers = self.workspace_routers.lock().await;
+            if let Some(selector) = routers.get(&root, &extensions) {
+                return Some(selector);
+            }
+            if !routers.owns(&own) {
+                routers.insert(root, extensions, own.clone());
+                return Some(own);
+            }
+        }
+        let provider = self.provider().await.ok()?;
+        match self.create_router_tool_selector(provider).await {
+            Ok(Some(selector)) => {
+                tracing::info!("Created a tool router for {}", root.display());
+                self.workspace_routers
+                    .lock()
+                    .await
+                    .insert(root, extensions, selector.clone());
+                Some(selector)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to create a tool router for {}: {}",
+                    root.display(),
+                    e
+                );
+                Some(own)
+            }
+        }
+    }
+
+    /// The selectors a change of extensions during a reply is indexed into: the reply's own
+    /// and the agent's, once each
+    pub(super) async fn selectors_to_index(
+        &self,
+        router: Option<&RouterSelector>,
+    ) -> Vec<RouterSelector> {
+        let own = self.router_tool_selector.lock().await.clone();
+        let mut selectors: Vec<RouterSelector> = router.cloned().into_iter().collect();
+        if let Some(own) = own.filter(|own| !selectors.iter().any(|s| Arc::ptr_eq(s, own))) {
+            selectors.push(own);
+        }
+        selectors
+    }
+
+    /// Record that a selector now holds the tools of the current extensions, so its workspace
+    /// keeps it
+    pub(super) async fn record_indexed(&self, selector: &RouterSelector) {
+        let extensions = self.extension_names().await;
+        self.workspace_routers
+            .lock()
+            .await
+            .record_extensions(selector, &extensions);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use mcp_core::{Content, Tool, ToolError};
+    use serde_json::Value;
+    use tempfile::TempDir;
+
+    struct StubSelector;
+
+    #[async_trait]
+    impl RouterToolSelector for StubSelector {
+        async fn select_tools(&self, _params: Value) -> Result<Vec<Content>, ToolError> {
+            Ok(vec![])
+        }
+        async fn index_tools(&self, _tools: &[Tool], _extension: &str) -> Result<(), ToolError> {
+            Ok(())
+        }
+        async fn remove_tool(&self, _tool_name: &str) -> Result<(), ToolError> {
+            Ok(())
+        }
+        async fn record_tool_call(&self, _tool_name: &str) -> Result<(), ToolError> {
+            Ok(())
+        }
+        async fn get_recent_tool_calls(&self, _limit: usize) -> Result<Vec<String>, ToolError> {
+            Ok(vec![])
+        }
+        fn selector_type(&self) -> RouterToolSelectionStrategy {
+            RouterToolSelectionStrategy::Llm
+        }
+    }
+
+    fn selector() -> RouterSelector {
+        Arc::new(Box::new(StubSelector))
+    }
+
+    #[test]
+    fn test_workspace_root_is_the_repository() {
+        let dir = TempDir::new().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir_all(repo.join("src/agents")).unwrap();
+        std::fs::create_dir_all(dir.path().join("notes")).unwrap();
+
+        assert_eq!(workspace_root(&repo.join("src/agents")), repo);
+        assert_eq!(workspace_root(&repo), repo);
+        let notes = dir.path().join("notes");
+        assert_eq!(workspace_root(&notes), notes);
+    }
+
+    #[test]
+    fn test_selectors_are_reused_per_workspace() {
+        let mut routers = WorkspaceRouters::default();
+        let extensions = vec!["developer".to_string()];
+        let first = selector();
+        routers.insert(PathBuf::from("/a"), extensions.clone(), first.clone());
+        routers.insert(PathBuf::from("/b"), extensions.clone(), selector());
+
+        let reused = routers.get(Path::new("/a"), &extensions).unwrap();
+        assert!(Arc::ptr_eq(&reused, &first));
+        assert!(routers.get(Path::new("/c"), &extensions).is_none());
+
+        // An extension was added while /a was in use, so /b lacks its tools and is dropped
+        let more = vec!["developer".to_string(), "jira".to_string()];
+        assert_eq!(
+            routers.record_extensions(&first, &more),
+            Some(PathBuf::from("/a"))
+        );
+        assert!(routers.owns(&first));
+        assert!(routers.get(Path::new("/b"), &more).is_none());
+        assert!(routers.get(Path::new("/b"), &extensions).is_none());
+        assert!(routers.get(Path::new("/a"), &more).is_some());
+    }
+
+    #[test]
+    fn test_least_recently_used_are_dropped() {
+        let mut routers = WorkspaceRouters::default();
+        for i in 0..MAX_WORKSPACES {
+            routers.insert(PathBuf::from(format!("/{}", i)), vec![], selector());
+        }
+        // Using the oldest keeps it over the next oldest
+        assert!(routers.get(Path::new("/0"), &[]).is_some());
+        routers.insert(PathBuf::from("/new"), vec![], selector());
+
+        assert!(routers.get(Path::new("/1"), &[]).is_none());
+        assert!(routers.get(Path::new("/0"), &[]).is_some());
+        assert!(routers.get(Path::new("/new"), &[]).is_some());
+    }
+}
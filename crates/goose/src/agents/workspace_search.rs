@@ -0,0 +1,536 @@
+//! Retrieval over the files of the session's working directory
+//!
+//! With `GOOSE_WORKSPACE_SEARCH: true` the model gets a search workspace tool, so it can find
+//! relevant code without an external extension. Text files under the working directory are
+//! split into chunks of lines, embedded, and kept in a LanceDB table for the workspace. Files
+//! ignored by git, hidden files and files over `GOOSE_WORKSPACE_SEARCH_MAX_FILE_SIZE` bytes
+//! are skipped. Before each search, only the files that changed since the last one are
+//! embedded again.
+//!
+//! Embeddings come from `GOOSE_EMBEDDING_MODEL_PROVIDER` if it is set, as for the vector
+//! tool router, or otherwise from the agent's provider.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Context, Result};
+use arrow::array::{Array, FixedSizeListBuilder, Float32Builder, StringArray, UInt32Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::{RecordBatch, RecordBatchIterator};
+use etcetera::base_strategy::{BaseStrategy, Xdg};
+use futures::TryStreamExt;
+use lancedb::connection::Connection;
+use lancedb::query::{ExecutableQuery, QueryBase};
+use mcp_core::{Content, ToolError, ToolResult};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+
+use crate::config::Config;
+use crate::providers::base::Provider;
+
+use super::router_tool_selector::create_embedding_provider;
+use super::Agent;
+
+const DEFAULT_MAX_FILE_SIZE: u64 = 256 * 1024;
+const DEFAULT_MAX_FILES: usize = 5_000;
+const DEFAULT_RESULTS: usize = 8;
+const MAX_RESULTS: usize = 50;
+const CHUNK_LINES: usize = 40;
+const EMBEDDING_BATCH_SIZE: usize = 64;
+const DELETE_BATCH_SIZE: usize = 100;
+
+/// Whether the model is offered the search workspace tool
+pub fn workspace_search_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_WORKSPACE_SEARCH")
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkspaceSearchLimits {
+    pub max_file_size: u64,
+    /// Maximum number of files indexed in a workspace
+    pub max_files: usize,
+}
+
+impl Default for WorkspaceSearchLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+}
+
+impl WorkspaceSearchLimits {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            max_file_size: config
+                .get_param("GOOSE_WORKSPACE_SEARCH_MAX_FILE_SIZE")
+                .unwrap_or(defaults.max_file_size),
+            max_files: config
+                .get_param("GOOSE_WORKSPACE_SEARCH_MAX_FILES")
+                .unwrap_or(defaults.max_files),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// First line of the chunk, numbered from 1
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+}
+
+/// Split text into chunks of whole lines, skipping chunks that are only whitespace
+pub fn chunk_text(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    lines
+        .chunks(CHUNK_LINES)
+        .enumerate()
+        .filter_map(|(i, lines)| {
+            let content = lines.join("\n");
+            if content.trim().is_empty() {
+                return None;
+            }
+            Some(Chunk {
+                start_line: i * CHUNK_LINES + 1,
+                end_line: i * CHUNK_LINES + lines.len(),
+                content,
+            })
+        })
+        .collect()
+}
+
+/// Files to index under `root` with their modification times, respecting .gitignore
+fn workspace_files(root: &Path, limits: &WorkspaceSearchLimits) -> Vec<(PathBuf, u64)> {
+    ignore::WalkBuilder::new(root)
+        .build()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            if metadata.len() > limits.max_file_size {
+                return None;
+            }
+            let modified = metadata
+                .modified()
+                .ok()?
+                .duration_since(UNIX_EPOCH)
+                .ok()?
+                .as_secs();
+            Some((entry.into_path(), modified))
+        })
+        .take(limits.max_files)
+        .collect()
+}
+
+/// The contents of a file if it is text
+fn read_text(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    if bytes.iter().take(8000).any(|&b| b == 0) {
+        return None;
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMatch {
+    /// Path relative to the workspace
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub content: String,
+}
+
+impl WorkspaceMatch {
+    pub fn to_text(&self) -> String {
+        format!(
+            "{}:{}-{}\n```\n{}\n```",
+            self.path, self.start_line, self.end_line, self.content
+        )
+    }
+}
+
+fn chunk_schema(dimension: i32) -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("path", DataType::Utf8, false),
+        Field::new("start_line", DataType::UInt32, false),
+        Field::new("end_line", DataType::UInt32, false),
+        Field::new("content", DataType::Utf8, false),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dimension,
+            ),
+            false,
+        ),
+    ]))
+}
+
+fn column<'a, T: 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T> {
+    batch
+        .column_by_name(name)
+        .with_context(|| format!("Missing {} column", name))?
+        .as_any()
+        .downcast_ref::<T>()
+        .with_context(|| format!("Invalid {} column type", name))
+}
+
+/// An incrementally updated index of the text files in a workspace
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    connection: Connection,
+    table_name: String,
+    /// Modification times of the indexed files, relative to the root
+    manifest_path: PathBuf,
+    embedding_provider: Arc<dyn Provider>,
+    limits: WorkspaceSearchLimits,
+    sync_lock: Mutex<()>,
+}
+
+impl WorkspaceIndex {
+    pub async fn open(root: PathBuf, embedding_provider: Arc<dyn Provider>) -> Result<Self> {
+        let db_path = Xdg::new()
+            .context("Failed to determine base strategy")?
+            .data_dir()
+            .join("goose")
+            .join("workspace_db");
+        tokio::fs::create_dir_all(&db_path)
+            .await
+            .context("Failed to create database directory")?;
+        let connection = lancedb::connect(db_path.to_str().unwrap())
+            .execute()
+            .await
+            .context("Failed to connect to LanceDB")?;
+
+        let digest = format!("{:x}", Sha256::digest(root.to_string_lossy().as_bytes()));
+        let table_name = format!("workspace_{}", &digest[..16]);
+        let manifest_path = db_path.join(format!("{}.json", table_name));
+
+        Ok(Self {
+            root,
+            connection,
+            table_name,
+            manifest_path,
+            embedding_provider,
+            limits: WorkspaceSearchLimits::from_config(),
+            sync_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn read_manifest(&self) -> HashMap<String, u64> {
+        std::fs::read_to_string(&self.manifest_path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    async fn table_exists(&self) -> Result<bool> {
+        let table_names = self
+            .connection
+            .table_names()
+            .execute()
+            .await
+            .context("Failed to list tables")?;
+        Ok(table_names.contains(&self.table_name))
+    }
+
+    /// Index files that were added or changed since the last sync and drop removed files,
+    /// returning the number of files that were embedded
+    pub async fn sync(&self) -> Result<usize> {
+        let _guard = self.sync_lock.lock().await;
+        let mut manifest = self.read_manifest();
+        let current: HashMap<String, (PathBuf, u64)> = workspace_files(&self.root, &self.limits)
+            .into_iter()
+            .map(|(path, modified)| {
+                let relative = path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&path)
+                    .to_string_lossy()
+                    .to_string();
+                (relative, (path, modified))
+            })
+            .collect();
+
+        let changed: Vec<(String, PathBuf, u64)> = current
+            .iter()
+            .filter(|(relative, (_, modified))| manifest.get(*relative) != Some(modified))
+            .map(|(relative, (path, modified))| (relative.clone(), path.clone(), *modified))
+            .collect();
+        // Changed files are dropped too, in case an earlier sync failed part way through
+        let stale: HashSet<String> = manifest
+            .keys()
+            .filter(|relative| !current.contains_key(*relative))
+            .cloned()
+            .chain(changed.iter().map(|(relative, _, _)| relative.clone()))
+            .collect();
+        if changed.is_empty() && stale.is_empty() {
+            return Ok(0);
+        }
+
+        if self.table_exists().await? {
+            let table = self
+                .connection
+                .open_table(&self.table_name)
+                .execute()
+                .await
+                .context("Failed to open workspace table")?;
+            let stale: Vec<String> = stale
+                .iter()
+                .map(|relative| format!("'{}'", relative.replace('\'', "''")))
+                .collect();
+            for paths in stale.chunks(DELETE_BATCH_SIZE) {
+                table
+                    .delete(&format!("path IN ({})", paths.join(", ")))
+                    .await
+                    .context("Failed to remove stale files")?;
+            }
+        }
+        manifest.retain(|relative, _| !stale.contains(relative));
+
+        let chunks: Vec<(String, Chunk)> = changed
+            .iter()
+            .filter_map(|(relative, path, _)| Some((relative, read_text(path)?)))
+            .flat_map(|(relative, text)| {
+                chunk_text(&text)
+                    .into_iter()
+                    .map(move |chunk| (relative.clone(), chunk))
+            })
+            .collect();
+        for batch in chunks.chunks(EMBEDDING_BATCH_SIZE) {
+            let texts = batch
+                .iter()
+                .map(|(relative, chunk)| format!("{}\n{}", relative, chunk.content))
+                .collect();
+            let vectors = self.embedding_provider.create_embeddings(texts).await?;
+            self.add_chunks(batch, vectors).await?;
+        }
+
+        for (relative, _, modified) in &changed {
+            manifest.insert(relative.clone(), *modified);
+        }
+        std::fs::write(&self.manifest_path, serde_json::to_string(&manifest)?)?;
+        Ok(changed.len())
+    }
+
+    async fn add_chunks(&self, chunks: &[(String, Chunk)], vectors: Vec<Vec<f32>>) -> Result<()> {
+        if vectors.len() != chunks.len() {
+            return Err(anyhow!(
+                "Expected {} embeddings but got {}",
+                chunks.len(),
+                vectors.len()
+            ));
+        }
+        let dimension = vectors.first().map_or(0, |v| v.len());
+
+        let mut vectors_builder =
+            FixedSizeListBuilder::new(Float32Builder::new(), dimension as i32);
+        for vector in &vectors {
+            if vector.len() != dimension {
+                return Err(anyhow!("Embeddings have inconsistent dimensions"));
+            }
+            vectors_builder.values().append_slice(vector);
+            vectors_builder.append(true);
+        }
+
+        let schema = chunk_schema(dimension as i32);
+        let paths: Vec<&str> = chunks.iter().map(|(path, _)| path.as_str()).collect();
+        let start_lines: Vec<u32> = chunks.iter().map(|(_, c)| c.start_line as u32).collect();
+        let end_lines: Vec<u32> = chunks.iter().map(|(_, c)| c.end_line as u32).collect();
+        let contents: Vec<&str> = chunks.iter().map(|(_, c)| c.content.as_str()).collect();
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(paths)),
+                Arc::new(UInt32Array::from(start_lines)),
+                Arc::new(UInt32Array::from(end_lines)),
+                Arc::new(StringArray::from(contents)),
+                Arc::new(vectors_builder.finish()),
+            ],
+        )
+        .context("Failed to create record batch")?;
+        let reader = RecordBatchIterator::new(vec![Ok(batch)].into_iter(), schema);
+
+        if self.table_exists().await? {
+            self.connection
+                .open_table(&self.table_name)
+                .execute()
+                .await
+                .context("Failed to open workspace table")?
+                .add(Box::new(reader))
+                .execute()
+                .await
+                .context("Failed to add chunks to the workspace table")?;
+        } else {
+            self.connection
+                .create_table(&self.table_name, Box::new(reader))
+                .execute()
+                .await
+                .context("Failed to create the workspace table")?;
+        }
+        Ok(())
+    }
+
+    /// Bring the index up to date and find the chunks most relevant to `query`
+    pub async fn search(&self, query: &str, k: usize) -> Result<Vec<WorkspaceMatch>> {
+        self.sync().await?;
+        if !self.table_exists().await? {
+            return Ok(Vec::new());
+        }
+
+        let query_vector = self
+            .embedding_provider
+            .create_embeddings(vec![query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding returned"))?;
+        let table = self
+            .connection
+            .open_table(&self.table_name)
+            .execute()
+            .await
+            .context("Failed to open workspace table")?;
+        let batches: Vec<RecordBatch> = table
+            .vector_search(query_vector)
+            .context("Failed to create vector search")?
+            .limit(k)
+            .execute()
+            .await
+            .context("Failed to execute vector search")?
+            .try_collect()
+            .await?;
+
+        let mut matches = Vec::new();
+        for batch in batches {
+            let paths = column::<StringArray>(&batch, "path")?;
+            let start_lines = column::<UInt32Array>(&batch, "start_line")?;
+            let end_lines = column::<UInt32Array>(&batch, "end_line")?;
+            let contents = column::<StringArray>(&batch, "content")?;
+            for i in 0..batch.num_rows() {
+                matches.push(WorkspaceMatch {
+                    path: paths.value(i).to_string(),
+                    start_line: start_lines.value(i) as usize,
+                    end_line: end_lines.value(i) as usize,
+                    content: contents.value(i).to_string(),
+                });
+            }
+        }
+        Ok(matches)
+    }
+}
+
+impl Agent {
+    /// The index for the current working directory, opened on first use
+    async fn workspace_index(&self) -> Result<Arc<WorkspaceIndex>> {
+        let root = match self.working_dir.lock().await.clone() {
+            Some(root) => root,
+            None => std::env::current_dir()?,
+        };
+        let mut index = self.workspace_index.lock().await;
+        if let Some(existing) = index.as_ref().filter(|index| index.root() == root) {
+            return Ok(existing.clone());
+        }
+
+        let embedding_provider = create_embedding_provider(self.provider().await?)?;
+        if !embedding_provider.supports_embeddings() {
+            return Err(anyhow!(
+                "The provider does not support embeddings, set GOOSE_EMBEDDING_MODEL_PROVIDER to one that does"
+            ));
+        }
+        let opened = Arc::new(WorkspaceIndex::open(root, embedding_provider).await?);
+        *index = Some(opened.clone());
+        Ok(opened)
+    }
+
+    /// Handle the search workspace platform tool
+    pub(super) async fn handle_search_workspace(
+        &self,
+        arguments: Value,
+    ) -> ToolResult<Vec<Content>> {
+        let query = arguments
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'query' parameter".into()))?;
+        let k = arguments
+            .get("k")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_RESULTS, |k| k as usize)
+            .clamp(1, MAX_RESULTS);
+
+        let index = self
+            .workspace_index()
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        let matches = index
+            .search(query, k)
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+
+        let text = if matches.is_empty() {
+            format!("No matches were found in {}", index.root().display())
+        } else {
+            matches
+                .iter()
+                .map(|m| m.to_text())
+                .collect::<Vec<_>>()
+                .join("\n\n")
+        };
+        Ok(vec![Content::text(text)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_chunk_text_numbers_lines() {
+        let text = (1..=90)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let chunks = chunk_text(&text);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 40));
+        assert_eq!((chunks[2].start_line, chunks[2].end_line), (81, 90));
+        assert!(chunks[2].content.starts_with("line 81\n"));
+
+        assert!(chunk_text("\n \n\t\n").is_empty());
+    }
+
+    #[test]
+    fn test_workspace_files_respect_gitignore_and_size() {
+        let dir = TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::write(dir.path().join(".gitignore"), "target/\n").unwrap();
+        std::fs::create_dir(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("target/build.rs"), "ignored").unwrap();
+        std::fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("big.txt"), "x".repeat(100)).unwrap();
+
+        let limits = WorkspaceSearchLimits {
+            max_file_size: 50,
+            ..Default::default()
+        };
+        let files: Vec<PathBuf> = workspace_files(dir.path(), &limits)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(files, vec![dir.path().join("main.rs")]);
+
+        std::fs::write(dir.path().join("image.png"), [0u8, 1, 2, 3]).unwrap();
+        assert!(read_text(&dir.path().join("image.png")).is_none());
+    }
+}
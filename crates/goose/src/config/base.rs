@@ -296,8 +296,9 @@ impl Config {
     /// Get a configuration value (non-secret).
     ///
     /// This will attempt to get the value from:
-    /// 1. Environment variable with the exact key name
-    /// 2. Configuration file
+    /// 1. Overrides of the session being served, see [`super::overrides`]
+    /// 2. Environment variable with the exact key name
+    /// 3. Configuration file
     ///
     /// The value will be deserialized into the requested type. This works with
     /// both simple types (String, i32, etc.) and complex types that implement
@@ -310,7 +311,12 @@ impl Config {
     /// - The value cannot be deserialized into the requested type
     /// - There is an error reading the config file
     pub fn get_param<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<T, ConfigError> {
-        // First check environment variables (convert to uppercase)
+        // Overrides of the session being served come before anything process-wide
+        if let Some(value) = super::overrides::session_override(key) {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        // Then check environment variables (convert to uppercase)
         let env_key = key.to_uppercase();
         if let Ok(val) = env::var(&env_key) {
             // Parse the environment variable value into a serde_json::Value
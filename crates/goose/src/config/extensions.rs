@@ -1,8 +1,13 @@
 use super::base::Config;
+use super::permission::PermissionManager;
+use super::ConfigError;
+use crate::agents::extension_manager::normalize;
+use crate::agents::extension_names::{check_unique, migrate_names, validate_name};
 use crate::agents::ExtensionConfig;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tracing::warn;
 use utoipa::ToSchema;
 
 pub const DEFAULT_EXTENSION: &str = "developer";
@@ -24,18 +29,55 @@ pub fn name_to_key(name: &str) -> String {
         .to_lowercase()
 }
 
+fn read_extensions(config: &Config) -> Result<HashMap<String, ExtensionEntry>, ConfigError> {
+    config.get_param("extensions")
+}
+
+/// Rename the configured extensions whose names are invalid or conflict with another
+/// extension's, saving the config and giving the renamed extensions' tools the permissions they
+/// had under the old name. Returns the renames made, old name first.
+fn migrate_extension_names(
+    config: &Config,
+    permissions: &mut PermissionManager,
+) -> Result<Vec<(String, String)>> {
+    let extensions = match read_extensions(config) {
+        Ok(extensions) => extensions,
+        Err(ConfigError::NotFound(_)) => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let (extensions, renames) = migrate_names(extensions);
+    if renames.is_empty() {
+        return Ok(renames);
+    }
+    config.set_param("extensions", serde_json::to_value(&extensions)?)?;
+    for (old, new) in &renames {
+        warn!(
+            "Renamed the extension '{}' to '{}', as its name was invalid or conflicted with another extension",
+            old, new
+        );
+        permissions.copy_extension(&normalize(old.clone()), new);
+    }
+    Ok(renames)
+}
+
 /// Extension configuration management
 pub struct ExtensionConfigManager;
 
 impl ExtensionConfigManager {
+    /// Rename the extensions that older configs let in with invalid or conflicting names. Run
+    /// once at startup, as reading the extensions leaves the config as it is.
+    pub fn migrate_names() -> Result<Vec<(String, String)>> {
+        migrate_extension_names(Config::global(), &mut PermissionManager::default())
+    }
+
     /// Get the extension configuration if enabled -- uses key
     pub fn get_config(key: &str) -> Result<Option<ExtensionConfig>> {
         let config = Config::global();
 
         // Try to get the extension entry
-        let extensions: HashMap<String, ExtensionEntry> = match config.get_param("extensions") {
+        let extensions = match read_extensions(config) {
             Ok(exts) => exts,
-            Err(super::ConfigError::NotFound(_)) => {
+            Err(ConfigError::NotFound(_)) => {
                 // Initialize with default developer extension
                 let defaults = HashMap::from([(
                     name_to_key(DEFAULT_EXTENSION), // Use key format for top-level key in config
@@ -45,6 +87,8 @@ impl ExtensionConfigManager {
                             name: DEFAULT_EXTENSION.to_string(),
                             display_name: Some(DEFAULT_DISPLAY_NAME.to_string()),
                             timeout: Some(DEFAULT_EXTENSION_TIMEOUT),
+                            tool_timeout: None,
+                            tool_filter: None,
                             bundled: Some(true),
                         },
                     },
@@ -68,11 +112,7 @@ impl ExtensionConfigManager {
         let config = Config::global();
 
         // Try to get the extension entry
-        let extensions: HashMap<String, ExtensionEntry> = match config.get_param("extensions") {
-            Ok(exts) => exts,
-            Err(super::ConfigError::NotFound(_)) => HashMap::new(),
-            Err(_) => HashMap::new(),
-        };
+        let extensions = read_extensions(config).unwrap_or_default();
 
         Ok(extensions
             .values()
@@ -84,11 +124,17 @@ impl ExtensionConfigManager {
     pub fn set(entry: ExtensionEntry) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
-            .unwrap_or_else(|_| HashMap::new());
+        let mut extensions = read_extensions(config).unwrap_or_default();
 
+        let name = entry.config.name();
+        validate_name(&name)?;
         let key = entry.config.key();
+        let others: Vec<String> = extensions
+            .iter()
+            .filter(|(other_key, _)| **other_key != key)
+            .map(|(_, other)| other.config.name())
+            .collect();
+        check_unique(&name, others.iter().map(String::as_str))?;
 
         extensions.insert(key, entry);
         config.set_param("extensions", serde_json::to_value(extensions)?)?;
@@ -99,9 +145,7 @@ impl ExtensionConfigManager {
     pub fn remove(key: &str) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
-            .unwrap_or_else(|_| HashMap::new());
+        let mut extensions = read_extensions(config).unwrap_or_default();
 
         extensions.remove(key);
         config.set_param("extensions", serde_json::to_value(extensions)?)?;
@@ -112,9 +156,7 @@ impl ExtensionConfigManager {
     pub fn set_enabled(key: &str, enabled: bool) -> Result<()> {
         let config = Config::global();
 
-        let mut extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
-            .unwrap_or_else(|_| HashMap::new());
+        let mut extensions = read_extensions(config).unwrap_or_default();
 
         if let Some(entry) = extensions.get_mut(key) {
             entry.enabled = enabled;
@@ -126,9 +168,9 @@ impl ExtensionConfigManager {
     /// Get all extensions and their configurations
     pub fn get_all() -> Result<Vec<ExtensionEntry>> {
         let config = Config::global();
-        let extensions: HashMap<String, ExtensionEntry> = match config.get_param("extensions") {
+        let extensions = match read_extensions(config) {
             Ok(exts) => exts,
-            Err(super::ConfigError::NotFound(_)) => HashMap::new(),
+            Err(ConfigError::NotFound(_)) => HashMap::new(),
             Err(e) => return Err(e.into()),
         };
         Ok(Vec::from_iter(extensions.values().cloned()))
@@ -136,23 +178,69 @@ impl ExtensionConfigManager {
 
     /// Get all extension names
     pub fn get_all_names() -> Result<Vec<String>> {
-        let config = Config::global();
-        Ok(config
-            .get_param("extensions")
-            .unwrap_or_else(|_| get_keys(Default::default())))
+        Ok(Self::get_all()?
+            .into_iter()
+            .map(|entry| entry.config.name())
+            .collect())
     }
 
     /// Check if an extension is enabled - FIXED to use key
     pub fn is_enabled(key: &str) -> Result<bool> {
         let config = Config::global();
-        let extensions: HashMap<String, ExtensionEntry> = config
-            .get_param("extensions")
-            .unwrap_or_else(|_| HashMap::new());
+        let extensions = read_extensions(config).unwrap_or_default();
 
         Ok(extensions.get(key).map(|e| e.enabled).unwrap_or(false))
     }
 }
 
-fn get_keys(entries: HashMap<String, ExtensionEntry>) -> Vec<String> {
-    entries.into_keys().collect()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::permission::PermissionLevel;
+    use tempfile::NamedTempFile;
+
+    fn entry(name: &str) -> ExtensionEntry {
+        ExtensionEntry {
+            enabled: true,
+            config: ExtensionConfig::Builtin {
+                name: name.to_string(),
+                display_name: None,
+                timeout: None,
+                tool_timeout: None,
+                tool_filter: None,
+                bundled: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_names_are_migrated_with_their_permissions() -> Result<()> {
+        let config_file = NamedTempFile::new()?;
+        let config = Config::new(config_file.path(), "goose-test")?;
+        let extensions = HashMap::from([
+            (name_to_key("client _"), entry("client _")),
+            (name_to_key("client 🚀"), entry("client 🚀")),
+        ]);
+        config.set_param("extensions", serde_json::to_value(&extensions)?)?;
+        let permission_file = NamedTempFile::new()?;
+        let mut permissions = PermissionManager::new(permission_file.path());
+        permissions.update_user_permission("client___read", PermissionLevel::AlwaysAllow);
+
+        // Reading leaves the names as they are
+        assert!(read_extensions(&config)?.contains_key(&name_to_key("client 🚀")));
+
+        let renames = migrate_extension_names(&config, &mut permissions)?;
+        assert_eq!(
+            renames,
+            vec![("client 🚀".to_string(), "client__2".to_string())]
+        );
+        assert!(read_extensions(&config)?.contains_key("client__2"));
+        assert_eq!(
+            permissions.get_user_permission("client__2__read"),
+            Some(PermissionLevel::AlwaysAllow)
+        );
+
+        assert!(migrate_extension_names(&config, &mut permissions)?.is_empty());
+        Ok(())
+    }
 }
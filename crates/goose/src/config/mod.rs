@@ -1,12 +1,14 @@
 pub mod base;
 mod experiments;
 pub mod extensions;
+pub mod overrides;
 pub mod permission;
 
 pub use crate::agents::ExtensionConfig;
 pub use base::{Config, ConfigError, APP_STRATEGY};
 pub use experiments::ExperimentManager;
 pub use extensions::{ExtensionConfigManager, ExtensionEntry};
+pub use overrides::ConfigOverrides;
 pub use permission::PermissionManager;
 
 pub use extensions::DEFAULT_DISPLAY_NAME;
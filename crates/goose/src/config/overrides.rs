@@ -0,0 +1,88 @@
+//! Configuration overrides scoped to a session
+//!
+//! The config is process-wide, so sessions served by one process would otherwise share every
+//! setting. A session can carry overrides in its `SessionConfig`, which the agent puts in scope
+//! while it replies: `Config::get_param` resolves keys from them before the environment and
+//! the config file, for the reply and everything it calls.
+//!
+//! ```json
+//! {"config_overrides": {"GOOSE_MODE": "approve", "GOOSE_ROUTER_TOOL_SELECTION_STRATEGY": "llm"}}
+//! ```
+//!
+//! Secrets are not overridden, and tasks spawned off the reply see the process-wide config.
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+
+/// Config keys and the values a session uses instead of the configured ones
+pub type ConfigOverrides = HashMap<String, Value>;
+
+tokio::task_local! {
+    static SESSION_OVERRIDES: Arc<ConfigOverrides>;
+}
+
+/// The value the session in scope sets for a key, if any
+pub(crate) fn session_override(key: &str) -> Option<Value> {
+    SESSION_OVERRIDES
+        .try_with(|overrides| overrides.get(key).cloned())
+        .ok()
+        .flatten()
+}
+
+/// Run a future with the overrides in scope
+pub async fn with_overrides<F: Future>(overrides: Arc<ConfigOverrides>, future: F) -> F::Output {
+    SESSION_OVERRIDES.scope(overrides, future).await
+}
+
+/// A stream that has the overrides in scope whenever it is polled
+pub struct OverriddenStream<S> {
+    overrides: Arc<ConfigOverrides>,
+    inner: S,
+}
+
+impl<S> OverriddenStream<S> {
+    pub fn new(overrides: Arc<ConfigOverrides>, inner: S) -> Self {
+        Self { overrides, inner }
+    }
+}
+
+impl<S: Stream + Unpin> Stream for OverriddenStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let overrides = this.overrides.clone();
+        SESSION_OVERRIDES.sync_scope(overrides, || this.inner.poll_next_unpin(cx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_overrides_apply_only_in_scope() {
+        let key = "GOOSE_TEST_SESSION_OVERRIDE_MODE";
+        let overrides = Arc::new(HashMap::from([(key.to_string(), json!("approve"))]));
+
+        let in_scope = with_overrides(overrides.clone(), async {
+            Config::global().get_param::<String>(key)
+        })
+        .await;
+        assert_eq!(in_scope.unwrap(), "approve");
+        assert!(Config::global().get_param::<String>(key).is_err());
+
+        let stream = futures::stream::iter(0..2)
+            .map(move |_| Config::global().get_param::<String>(key).ok())
+            .boxed();
+        let seen: Vec<_> = OverriddenStream::new(overrides, stream).collect().await;
+        assert_eq!(seen, vec![Some("approve".to_string()); 2]);
+    }
+}
@@ -189,6 +189,30 @@ impl PermissionManager {
             .expect("Failed to serialize permission config");
         fs::write(&self.config_path, yaml_content).expect("Failed to write to permission.yaml");
     }
+
+    /// Gives the tools of a renamed extension the permissions of its tools under the old name.
+    /// The old entries are kept, as another extension may still have the old name.
+    pub fn copy_extension(&mut self, from: &str, to: &str) {
+        let from_prefix = format!("{}__", from);
+        let copy = |principals: &mut Vec<String>| {
+            let copied: Vec<String> = principals
+                .iter()
+                .filter_map(|p| p.strip_prefix(&from_prefix))
+                .map(|tool| format!("{}__{}", to, tool))
+                .filter(|p| !principals.contains(p))
+                .collect();
+            principals.extend(copied);
+        };
+        for permission_config in self.permission_map.values_mut() {
+            copy(&mut permission_config.always_allow);
+            copy(&mut permission_config.ask_before);
+            copy(&mut permission_config.never_allow);
+        }
+
+        let yaml_content = serde_yaml::to_string(&self.permission_map)
+            .expect("Failed to serialize permission config");
+        fs::write(&self.config_path, yaml_content).expect("Failed to write to permission.yaml");
+    }
 }
 
 #[cfg(test)]
@@ -305,4 +329,28 @@ mod tests {
             .always_allow
             .contains(&"nonprefix__tool2".to_string()));
     }
+
+    #[test]
+    fn test_copy_extension() {
+        let mut manager = create_test_permission_manager();
+        manager.update_user_permission("client___read", PermissionLevel::AlwaysAllow);
+        manager.update_smart_approve_permission("client___write", PermissionLevel::AskBefore);
+        manager.update_user_permission("other__read", PermissionLevel::NeverAllow);
+
+        manager.copy_extension("client_", "client__2");
+
+        assert_eq!(
+            manager.get_user_permission("client__2__read"),
+            Some(PermissionLevel::AlwaysAllow)
+        );
+        assert_eq!(
+            manager.get_smart_approve_permission("client__2__write"),
+            Some(PermissionLevel::AskBefore)
+        );
+        assert_eq!(
+            manager.get_user_permission("client___read"),
+            Some(PermissionLevel::AlwaysAllow)
+        );
+        assert_eq!(manager.get_user_permission("other__2__read"), None);
+    }
 }
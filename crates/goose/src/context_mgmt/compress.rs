@@ -0,0 +1,338 @@
+//! Pruning low-information tokens from old context before it is sent
+//!
+//! Old tool outputs and embedded resources are often long and mostly filler: repeated log
+//! lines, boilerplate and function words the model can do without. When compression is on,
+//! each such section outside the most recent messages is pruned in the style of LLMLingua:
+//! repeated lines are collapsed, every word is scored by how much information it carries, and
+//! the lowest scoring words are dropped until the section is down to the level's share of its
+//! words. Words are scored by their rarity within the section, with function words scoring
+//! nothing and words holding digits or symbols, such as paths, identifiers and numbers,
+//! scoring higher.
+//!
+//! Only what is sent to the provider is pruned; the session keeps every message whole. The
+//! tokens saved are reported with the context usage of each turn.
+//!
+//! ```yaml
+//! # off (the default), light, medium or aggressive
+//! GOOSE_PROMPT_COMPRESSION: medium
+//! # Sections with fewer tokens than this are sent whole
+//! GOOSE_PROMPT_COMPRESSION_MIN_TOKENS: 200
+//! # The most recent messages are always sent whole
+//! GOOSE_PROMPT_COMPRESSION_KEEP_RECENT: 6
+//! ```
+use std::collections::HashMap;
+
+use mcp_core::resource::ResourceContents;
+use mcp_core::Content;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::token_counter::TokenCounter;
+
+const DEFAULT_MIN_TOKENS: usize = 200;
+const DEFAULT_KEEP_RECENT: usize = 6;
+
+/// Tells the model that what follows is not the tool's literal output
+const PRUNED_NOTE: &str = "[Pruned for brevity; call the tool again for the full output]";
+
+/// Words that carry little meaning on their own
+const FUNCTION_WORDS: &[&str] = &[
+    "a", "an", "the", "and", "or", "but", "of", "to", "in", "on", "at", "by", "for", "with",
+    "from", "as", "is", "are", "was", "were", "be", "been", "being", "it", "its", "this", "that",
+    "these", "those", "there", "here", "which", "who", "whom", "will", "would", "can", "could",
+    "should", "may", "might", "has", "have", "had", "do", "does", "did", "so", "than", "then",
+    "also", "just", "very", "into", "onto", "about", "such", "we", "you", "they", "he", "she", "i",
+    "our", "your", "their", "if", "when", "while", "all", "any", "each", "some",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionLevel {
+    #[default]
+    Off,
+    Light,
+    Medium,
+    Aggressive,
+}
+
+impl CompressionLevel {
+    /// Share of a section's words that is kept
+    pub fn keep_ratio(self) -> f64 {
+        match self {
+            CompressionLevel::Off => 1.0,
+            CompressionLevel::Light => 0.8,
+            CompressionLevel::Medium => 0.6,
+            CompressionLevel::Aggressive => 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PromptCompressor {
+    pub level: CompressionLevel,
+    /// Sections with fewer tokens than this are sent whole
+    pub min_tokens: usize,
+    /// The most recent messages, which are sent whole
+    pub keep_recent: usize,
+}
+
+/// Tokens saved by compressing one turn's prompt
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct CompressionStats {
+    pub level: CompressionLevel,
+    /// Sections that were pruned
+    pub sections: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl CompressionStats {
+    pub fn tokens_saved(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+}
+
+impl PromptCompressor {
+    /// The configured compressor, or None if compression is off
+    pub fn from_config() -> Option<Self> {
+        let config = Config::global();
+        let level: CompressionLevel = config
+            .get_param("GOOSE_PROMPT_COMPRESSION")
+            .unwrap_or_default();
+        if level == CompressionLevel::Off {
+            return None;
+        }
+        Some(Self {
+            level,
+            min_tokens: config
+                .get_param("GOOSE_PROMPT_COMPRESSION_MIN_TOKENS")
+                .unwrap_or(DEFAULT_MIN_TOKENS),
+            keep_recent: config
+                .get_param("GOOSE_PROMPT_COMPRESSION_KEEP_RECENT")
+                .unwrap_or(DEFAULT_KEEP_RECENT),
+        })
+    }
+
+    /// The messages with their old tool outputs and resources pruned, and what that saved
+    pub fn compress(
+        &self,
+        messages: &[Message],
+        counter: &TokenCounter,
+    ) -> (Vec<Message>, CompressionStats) {
+        let mut stats = CompressionStats {
+            level: self.level,
+            ..Default::default()
+        };
+        let mut messages = messages.to_vec();
+        let old = messages.len().saturating_sub(self.keep_recent);
+        for message in &mut messages[..old] {
+            for content in &mut message.content {
+                let MessageContent::ToolResponse(response) = content else {
+                    continue;
+                };
+                let Ok(contents) = &mut response.tool_result else {
+                    continue;
+                };
+                for content in contents {
+                    match content {
+                        Content::Text(text) => {
+                            self.compress_section(&mut text.text, true, counter, &mut stats)
+                        }
+                        Content::Resource(resource) => {
+                            if let ResourceContents::TextResourceContents { text, .. } =
+                                &mut resource.resource
+                            {
+                                self.compress_section(text, false, counter, &mut stats)
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        (messages, stats)
+    }
+
+    fn compress_section(
+        &self,
+        text: &mut String,
+        noted: bool,
+        counter: &TokenCounter,
+        stats: &mut CompressionStats,
+    ) {
+        let before = counter.count_tokens(text);
+        if before < self.min_tokens {
+            return;
+        }
+        let mut pruned = prune(text, self.level.keep_ratio());
+        if noted {
+            pruned = format!("{}\n{}", PRUNED_NOTE, pruned);
+        }
+        let after = counter.count_tokens(&pruned);
+        if after >= before {
+            return;
+        }
+        *text = pruned;
+        stats.sections += 1;
+        stats.tokens_before += before;
+        stats.tokens_after += after;
+    }
+}
+
+/// Collapse runs of identical lines into the line and a count of its repeats
+fn collapse_repeats(text: &str) -> Vec<String> {
+    let mut runs: Vec<(&str, usize)> = Vec::new();
+    for line in text.lines() {
+        match runs.last_mut() {
+            Some((last, count)) if *last == line => *count += 1,
+            _ => runs.push((line, 1)),
+        }
+    }
+    runs.into_iter()
+        .map(|(line, count)| match count {
+            1 => line.to_string(),
+            count => format!("{} [repeated {} times]", line, count),
+        })
+        .collect()
+}
+
+/// How much information a word carries within its section
+fn score(word: &str, count: usize, total: usize) -> f64 {
+    let normalized = word
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    if normalized.is_empty() || FUNCTION_WORDS.contains(&normalized.as_str()) {
+        return 0.0;
+    }
+    let rarity = (total as f64 / count as f64).ln();
+    let specific = word
+        .chars()
+        .any(|c| c.is_ascii_digit() || "/._:-=#@".contains(c));
+    if specific {
+        rarity + 2.0
+    } else {
+        rarity
+    }
+}
+
+/// Drop the lowest scoring words of the text until `keep_ratio` of them are left, keeping the
+/// order of the words, the lines they are on and the indentation of those lines
+pub fn prune(text: &str, keep_ratio: f64) -> String {
+    let lines = collapse_repeats(text);
+    let words: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|line| line.split_whitespace().collect())
+        .collect();
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in words.iter().flatten() {
+        *counts.entry(*word).or_default() += 1;
+    }
+    let total: usize = words.iter().map(Vec::len).sum();
+
+    // Every word by position, lowest score first
+    let mut ranked: Vec<(f64, usize, usize)> = Vec::with_capacity(total);
+    for (line, line_words) in words.iter().enumerate() {
+        for (i, word) in line_words.iter().enumerate() {
+            ranked.push((score(word, counts[word], total), line, i));
+        }
+    }
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let drop_count = total - (total as f64 * keep_ratio).ceil() as usize;
+    let mut dropped = vec![Vec::new(); lines.len()];
+    for &(_, line, i) in &ranked[..drop_count.min(ranked.len())] {
+        dropped[line].push(i);
+    }
+
+    let mut pruned = Vec::with_capacity(lines.len());
+    for (line, line_words) in lines.iter().zip(&words) {
+        let index = pruned.len();
+        let kept: Vec<&str> = line_words
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !dropped[index].contains(i))
+            .map(|(_, word)| *word)
+            .collect();
+        if kept.is_empty() && !line_words.is_empty() {
+            pruned.push(None);
+            continue;
+        }
+        let indent = &line[..line.len() - line.trim_start().len()];
+        pruned.push(Some(format!("{}{}", indent, kept.join(" "))));
+    }
+    pruned.into_iter().flatten().collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GPT_4O_TOKENIZER;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_prune_keeps_specific_words() {
+        let text =
+            "The build of the project failed at src/main.rs:42 because the value was moved\n\
+                    warning: unused import\n\
+                    warning: unused import\n\
+                    warning: unused import";
+        let pruned = prune(text, 0.5);
+        assert!(pruned.contains("src/main.rs:42"));
+        assert!(pruned.contains("[repeated 3 times]"));
+        assert!(!pruned.contains("The "));
+        assert_eq!(pruned.lines().count(), 2);
+        assert!(pruned.split_whitespace().count() < text.split_whitespace().count());
+    }
+
+    #[test]
+    fn test_only_old_tool_output_is_compressed() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+        let output =
+            "The command printed the following lines of output for the user to read. ".repeat(20);
+        let tool_output = |id: &str| {
+            Message::user().with_tool_response(id, Ok(vec![Content::text(output.clone())]))
+        };
+        let messages = vec![
+            Message::user().with_text("Run the build"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "make"}),
+                )),
+            ),
+            tool_output("1"),
+            Message::assistant().with_tool_request(
+                "2",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "make"}),
+                )),
+            ),
+            tool_output("2"),
+        ];
+        let compressor = PromptCompressor {
+            level: CompressionLevel::Medium,
+            min_tokens: 50,
+            keep_recent: 2,
+        };
+
+        let (compressed, stats) = compressor.compress(&messages, &counter);
+        assert_eq!(stats.sections, 1);
+        assert!(stats.tokens_saved() > 0);
+        let text = |message: &Message| match &message.content[0] {
+            MessageContent::ToolResponse(response) => response.tool_result.as_ref().unwrap()[0]
+                .as_text()
+                .unwrap()
+                .to_string(),
+            _ => panic!("expected a tool response"),
+        };
+        assert!(text(&compressed[2]).starts_with(PRUNED_NOTE));
+        assert_eq!(text(&compressed[4]), output);
+        // The session keeps the output whole
+        assert_eq!(text(&messages[2]), output);
+    }
+}
@@ -1,4 +1,5 @@
 mod common;
+pub mod compress;
 pub mod summarize;
 pub mod truncate;
 
@@ -0,0 +1,388 @@
+//! Local control API for a running agent
+//!
+//! Editor plugins and scripts can drive an agent that is already running by connecting to
+//! its control socket, a Unix domain socket configured with `GOOSE_CONTROL_SOCKET`. Each
+//! request and response is a single line of JSON:
+//!
+//! ```text
+//! > {"type": "send_message", "text": "run the tests"}
+//! < {"type": "ok"}
+//! > {"type": "status"}
+//! < {"type": "status", "status": {"session_id": "20250101_120000", "busy": true, ...}}
+//! > {"type": "cancel"}
+//! < {"type": "ok"}
+//! > {"type": "subscribe"}
+//! < {"type": "ok"}
+//! < {"type": "event", "event": {"type": "message", "message": {...}}}
+//! ```
+//!
+//! After `subscribe` the connection only carries events, so clients that also send commands
+//! use a second connection. The socket is only accessible to the current user.
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::message::Message;
+
+const EVENT_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlRequest {
+    /// Send a user message, replied to once the agent is free
+    SendMessage {
+        text: String,
+    },
+    /// Cancel the reply in progress
+    Cancel,
+    Status,
+    /// Stream events over this connection
+    Subscribe,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlStatus {
+    pub session_id: Option<String>,
+    /// Whether the agent is replying
+    pub busy: bool,
+    pub message_count: usize,
+    /// Messages sent over the socket that are waiting for the agent
+    pub queued_messages: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlEvent {
+    /// A message was added to the conversation
+    Message {
+        message: Message,
+    },
+    Status {
+        status: ControlStatus,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ControlResponse {
+    Ok,
+    Status { status: ControlStatus },
+    Event { event: ControlEvent },
+    Error { message: String },
+}
+
+/// A request from a client that the process hosting the agent has to act on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    SendMessage(String),
+    Cancel,
+}
+
+struct Shared {
+    commands: mpsc::UnboundedSender<ControlCommand>,
+    events: broadcast::Sender<ControlEvent>,
+    status: RwLock<ControlStatus>,
+}
+
+impl Shared {
+    fn handle(&self, request: ControlRequest) -> ControlResponse {
+        let command = match request {
+            ControlRequest::SendMessage { text } if text.trim().is_empty() => {
+                return ControlResponse::Error {
+                    message: "Message text cannot be empty".to_string(),
+                }
+            }
+            ControlRequest::SendMessage { text } => ControlCommand::SendMessage(text),
+            ControlRequest::Cancel => ControlCommand::Cancel,
+            ControlRequest::Status => {
+                return ControlResponse::Status {
+                    status: self.status.read().unwrap().clone(),
+                }
+            }
+            ControlRequest::Subscribe => return ControlResponse::Ok,
+        };
+        match self.commands.send(command) {
+            Ok(()) => ControlResponse::Ok,
+            Err(_) => ControlResponse::Error {
+                message: "The agent is shutting down".to_string(),
+            },
+        }
+    }
+}
+
+/// Serves the control socket for as long as it is alive, removing the socket when dropped
+pub struct ControlServer {
+    path: PathBuf,
+    shared: Arc<Shared>,
+    commands: mpsc::UnboundedReceiver<ControlCommand>,
+    listener: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl ControlServer {
+    /// Listen on a Unix domain socket at `path`. Must be called within a tokio runtime.
+    pub fn bind(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let (commands_tx, commands) = mpsc::unbounded_channel();
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let shared = Arc::new(Shared {
+            commands: commands_tx,
+            events,
+            status: RwLock::new(ControlStatus::default()),
+        });
+        let listener = Some(listen(&path, shared.clone())?);
+
+        Ok(Self {
+            path,
+            shared,
+            commands,
+            listener,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Wait for the next command from a client
+    pub async fn recv(&mut self) -> Option<ControlCommand> {
+        self.commands.recv().await
+    }
+
+    /// The next command from a client, if one is waiting
+    pub fn try_recv(&mut self) -> Option<ControlCommand> {
+        self.commands.try_recv().ok()
+    }
+
+    /// Send an event to all subscribed clients
+    pub fn publish(&self, event: ControlEvent) {
+        // Sending only fails when nobody is subscribed
+        let _ = self.shared.events.send(event);
+    }
+
+    pub fn status(&self) -> ControlStatus {
+        self.shared.status.read().unwrap().clone()
+    }
+
+    /// Update the status reported to clients, notifying subscribers if it changed
+    pub fn update_status(&self, update: impl FnOnce(&mut ControlStatus)) {
+        let status = {
+            let mut status = self.shared.status.write().unwrap();
+            let previous = status.clone();
+            update(&mut status);
+            if *status == previous {
+                return;
+            }
+            status.clone()
+        };
+        self.publish(ControlEvent::Status { status });
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        if let Some(listener) = self.listener.take() {
+            listener.abort();
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn listen(path: &Path, shared: Arc<Shared>) -> Result<tokio::task::JoinHandle<()>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if path.exists() {
+        // A socket left behind by a process that exited would make the bind fail
+        if std::os::unix::net::UnixStream::connect(path).is_ok() {
+            return Err(anyhow!(
+                "The control socket {} is in use by another agent",
+                path.display()
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = tokio::net::UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(tokio::spawn(accept_connections(listener, shared)))
+}
+
+#[cfg(not(unix))]
+fn listen(_path: &Path, _shared: Arc<Shared>) -> Result<tokio::task::JoinHandle<()>> {
+    Err(anyhow!(
+        "The control socket is only supported on unix platforms"
+    ))
+}
+
+#[cfg(unix)]
+async fn accept_connections(listener: tokio::net::UnixListener, shared: Arc<Shared>) {
+    // Connections are aborted along with this task when the server is dropped
+    let mut connections = tokio::task::JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let shared = shared.clone();
+                    connections.spawn(async move {
+                        if let Err(e) = serve_connection(stream, shared).await {
+                            tracing::debug!("Control connection closed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!("Failed to accept control connection: {}", e),
+            },
+            Some(_) = connections.join_next() => {}
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn serve_connection(stream: tokio::net::UnixStream, shared: Arc<Shared>) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request = match serde_json::from_str::<ControlRequest>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let message = format!("Invalid request: {}", e);
+                write_response(&mut writer, &ControlResponse::Error { message }).await?;
+                continue;
+            }
+        };
+
+        let subscribe = request == ControlRequest::Subscribe;
+        let mut events = shared.events.subscribe();
+        write_response(&mut writer, &shared.handle(request)).await?;
+        if subscribe {
+            loop {
+                let response = match events.recv().await {
+                    Ok(event) => ControlResponse::Event { event },
+                    Err(broadcast::error::RecvError::Lagged(missed)) => ControlResponse::Error {
+                        message: format!("Missed {} events", missed),
+                    },
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                };
+                write_response(&mut writer, &response).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn write_response(
+    writer: &mut tokio::net::unix::OwnedWriteHalf,
+    response: &ControlResponse,
+) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+    use tokio::net::unix::{OwnedReadHalf, OwnedWriteHalf};
+    use tokio::net::UnixStream;
+
+    async fn connect(path: &Path) -> (Lines<BufReader<OwnedReadHalf>>, OwnedWriteHalf) {
+        let (reader, writer) = UnixStream::connect(path).await.unwrap().into_split();
+        (BufReader::new(reader).lines(), writer)
+    }
+
+    async fn request(
+        lines: &mut Lines<BufReader<OwnedReadHalf>>,
+        writer: &mut OwnedWriteHalf,
+        request: &str,
+    ) -> serde_json::Value {
+        writer
+            .write_all(format!("{}\n", request).as_bytes())
+            .await
+            .unwrap();
+        let line = lines.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_commands_and_status() {
+        let dir = TempDir::new().unwrap();
+        let mut server = ControlServer::bind(dir.path().join("goose.sock")).unwrap();
+        server.update_status(|status| status.session_id = Some("session".to_string()));
+        let (mut lines, mut writer) = connect(server.path()).await;
+
+        let response = request(
+            &mut lines,
+            &mut writer,
+            r#"{"type": "send_message", "text": "run the tests"}"#,
+        )
+        .await;
+        assert_eq!(response["type"], "ok");
+        assert_eq!(
+            server.recv().await,
+            Some(ControlCommand::SendMessage("run the tests".to_string()))
+        );
+
+        request(&mut lines, &mut writer, r#"{"type": "cancel"}"#).await;
+        assert_eq!(server.try_recv(), Some(ControlCommand::Cancel));
+        assert_eq!(server.try_recv(), None);
+
+        let response = request(&mut lines, &mut writer, r#"{"type": "status"}"#).await;
+        assert_eq!(response["status"]["session_id"], "session");
+        assert_eq!(response["status"]["busy"], false);
+
+        let response = request(&mut lines, &mut writer, r#"{"type": "fly"}"#).await;
+        assert_eq!(response["type"], "error");
+        let response = request(
+            &mut lines,
+            &mut writer,
+            r#"{"type": "send_message", "text": " "}"#,
+        )
+        .await;
+        assert_eq!(response["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_subscribers_receive_events() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("goose.sock");
+        let server = ControlServer::bind(&path).unwrap();
+        let (mut lines, mut writer) = connect(&path).await;
+
+        let response = request(&mut lines, &mut writer, r#"{"type": "subscribe"}"#).await;
+        assert_eq!(response["type"], "ok");
+
+        server.update_status(|status| status.busy = true);
+        server.publish(ControlEvent::Message {
+            message: Message::assistant().with_text("All tests pass"),
+        });
+
+        let line = lines.next_line().await.unwrap().unwrap();
+        let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["event"]["type"], "status");
+        assert_eq!(event["event"]["status"]["busy"], true);
+        let line = lines.next_line().await.unwrap().unwrap();
+        let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(event["event"]["type"], "message");
+
+        // A second agent cannot take over the socket, and it is removed on drop
+        assert!(ControlServer::bind(&path).is_err());
+        drop(server);
+        assert!(!path.exists());
+    }
+}
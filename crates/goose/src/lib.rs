@@ -1,9 +1,11 @@
 pub mod agents;
 pub mod config;
 pub mod context_mgmt;
+pub mod control;
 pub mod message;
 pub mod model;
 pub mod permission;
+pub mod preflight;
 pub mod prompt_template;
 pub mod providers;
 pub mod recipe;
@@ -11,7 +13,9 @@ pub mod scheduler;
 pub mod scheduler_factory;
 pub mod scheduler_trait;
 pub mod session;
+pub mod shutdown;
 pub mod temporal_scheduler;
 pub mod token_counter;
+pub mod tool_errors;
 pub mod tool_monitor;
 pub mod tracing;
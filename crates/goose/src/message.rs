@@ -64,6 +64,9 @@ pub struct ToolConfirmationRequest {
     pub tool_name: String,
     pub arguments: Value,
     pub prompt: Option<String>,
+    /// A short summary of what the call will do, to help decide on it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impact: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
@@ -96,6 +99,15 @@ pub struct SummarizationRequested {
     pub msg: String,
 }
 
+/// Spoken input from a frontend, transcribed to text before the conversation is sent to a model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioContent {
+    /// Base64 encoded audio
+    pub data: String,
+    pub mime_type: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 /// Content passed inside a message, which can be both simple content and tool content
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -110,6 +122,7 @@ pub enum MessageContent {
     RedactedThinking(RedactedThinkingContent),
     ContextLengthExceeded(ContextLengthExceeded),
     SummarizationRequested(SummarizationRequested),
+    Audio(AudioContent),
 }
 
 impl MessageContent {
@@ -128,6 +141,13 @@ impl MessageContent {
         })
     }
 
+    pub fn audio<S: Into<String>, T: Into<String>>(data: S, mime_type: T) -> Self {
+        MessageContent::Audio(AudioContent {
+            data: data.into(),
+            mime_type: mime_type.into(),
+        })
+    }
+
     pub fn tool_request<S: Into<String>>(id: S, tool_call: ToolResult<ToolCall>) -> Self {
         MessageContent::ToolRequest(ToolRequest {
             id: id.into(),
@@ -153,6 +173,7 @@ impl MessageContent {
             tool_name,
             arguments,
             prompt,
+            impact: None,
         })
     }
 
@@ -238,6 +259,14 @@ impl MessageContent {
         }
     }
 
+    /// Get the audio content if this is an AudioContent variant
+    pub fn as_audio(&self) -> Option<&AudioContent> {
+        match self {
+            MessageContent::Audio(audio) => Some(audio),
+            _ => None,
+        }
+    }
+
     /// Get the thinking content if this is a ThinkingContent variant
     pub fn as_thinking(&self) -> Option<&ThinkingContent> {
         match self {
@@ -343,6 +372,11 @@ impl Message {
         self.with_content(MessageContent::image(data, mime_type))
     }
 
+    /// Add audio content to the message
+    pub fn with_audio<S: Into<String>, T: Into<String>>(self, data: S, mime_type: T) -> Self {
+        self.with_content(MessageContent::audio(data, mime_type))
+    }
+
     /// Add a tool request to the message
     pub fn with_tool_request<S: Into<String>>(
         self,
@@ -30,6 +30,14 @@ static MODEL_SPECIFIC_LIMITS: Lazy<HashMap<&'static str, usize>> = Lazy::new(||
     // Meta Llama models, https://github.com/meta-llama/llama-models/tree/main?tab=readme-ov-file#llama-models-1
     map.insert("llama3.2", 128_000);
     map.insert("llama3.3", 128_000);
+
+    // Models served by Groq, https://console.groq.com/docs/models
+    map.insert("llama-3.3-70b", 131_072);
+    map.insert("llama-3.1-8b", 131_072);
+    map.insert("llama3-70b-8192", 8_192);
+    map.insert("llama3-8b-8192", 8_192);
+    map.insert("mixtral-8x7b-32768", 32_768);
+    map.insert("gemma2-9b-it", 8_192);
     map
 });
 
@@ -48,12 +56,30 @@ pub struct ModelConfig {
     pub temperature: Option<f32>,
     /// Optional maximum tokens to generate
     pub max_tokens: Option<i32>,
+    /// Optional nucleus sampling setting (0.0 - 1.0)
+    #[serde(default)]
+    pub top_p: Option<f32>,
     /// Whether to interpret tool calls with toolshim
     pub toolshim: bool,
     /// Model to use for toolshim (optional as a default exists)
     pub toolshim_model: Option<String>,
 }
 
+/// Sampling settings for a single reply, overriding those of the provider's model config, e.g.
+/// a low temperature for planning and a higher one for creative writing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SamplingOverrides {
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<i32>,
+    pub top_p: Option<f32>,
+}
+
+impl SamplingOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
 /// Struct to represent model pattern matches and their limits
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelLimitConfig {
@@ -88,6 +114,7 @@ impl ModelConfig {
             context_limit,
             temperature,
             max_tokens: None,
+            top_p: None,
             toolshim,
             toolshim_model,
         }
@@ -146,6 +173,26 @@ impl ModelConfig {
         self
     }
 
+    /// Set the nucleus sampling probability
+    pub fn with_top_p(mut self, top_p: Option<f32>) -> Self {
+        self.top_p = top_p;
+        self
+    }
+
+    /// Apply per-call sampling overrides, keeping the configured value for any that are unset
+    pub fn with_sampling(mut self, sampling: &SamplingOverrides) -> Self {
+        if sampling.temperature.is_some() {
+            self.temperature = sampling.temperature;
+        }
+        if sampling.max_tokens.is_some() {
+            self.max_tokens = sampling.max_tokens;
+        }
+        if sampling.top_p.is_some() {
+            self.top_p = sampling.top_p;
+        }
+        self
+    }
+
     /// Set whether to interpret tool calls
     pub fn with_toolshim(mut self, toolshim: bool) -> Self {
         self.toolshim = toolshim;
@@ -188,11 +235,30 @@ mod tests {
         let config = ModelConfig::new("gpt-4-turbo".to_string());
         assert_eq!(config.context_limit(), 128_000);
 
+        let config = ModelConfig::new("mixtral-8x7b-32768".to_string());
+        assert_eq!(config.context_limit(), 32_768);
+
         // Test fallback to default
         let config = ModelConfig::new("unknown-model".to_string());
         assert_eq!(config.context_limit(), DEFAULT_CONTEXT_LIMIT);
     }
 
+    #[test]
+    fn test_with_sampling_overrides_only_set_fields() {
+        let config = ModelConfig::new("test-model".to_string())
+            .with_temperature(Some(0.7))
+            .with_max_tokens(Some(1000))
+            .with_sampling(&SamplingOverrides {
+                temperature: Some(0.1),
+                top_p: Some(0.9),
+                ..Default::default()
+            });
+        assert_eq!(config.temperature, Some(0.1));
+        assert_eq!(config.max_tokens, Some(1000));
+        assert_eq!(config.top_p, Some(0.9));
+        assert!(SamplingOverrides::default().is_empty());
+    }
+
     #[test]
     fn test_model_config_settings() {
         let config = ModelConfig::new("test-model".to_string())
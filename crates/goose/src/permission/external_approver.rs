@@ -0,0 +1,254 @@
+//! Tool approvals decided by a central policy service instead of the local user.
+//!
+//! ```yaml
+//! GOOSE_EXTERNAL_APPROVER:
+//!   url: https://policy.example.com/goose/approve
+//!   timeout_secs: 5
+//!   fallback: local
+//!   headers:
+//!     Authorization: Bearer <token>
+//! ```
+//!
+//! The service receives an [`ApprovalContext`] as JSON and answers with
+//! `{"decision": "allow" | "deny" | "ask_user"}`. When it does not answer in time, or
+//! answers with an error, the `fallback` rule applies; `local` hands the decision back to
+//! the regular permission rules.
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::providers::http_client;
+
+const DEFAULT_APPROVER_TIMEOUT_SECS: u64 = 5;
+
+/// What the policy service is told about a tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApprovalContext {
+    pub request_id: String,
+    pub tool_name: String,
+    pub arguments: Value,
+    /// The goose mode the agent is running in, e.g. `approve` or `smart_approve`
+    pub mode: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalDecision {
+    Allow,
+    Deny,
+    /// Defer to the local user
+    AskUser,
+}
+
+/// What to do when the external approver does not produce a decision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalFallback {
+    /// Apply the local permission rules as if no approver was configured
+    #[default]
+    Local,
+    Deny,
+    AskUser,
+}
+
+#[async_trait]
+pub trait ExternalApprover: Send + Sync {
+    async fn decide(&self, context: &ApprovalContext) -> Result<ApprovalDecision>;
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookResponse {
+    decision: ApprovalDecision,
+}
+
+/// Posts the approval context to an HTTP endpoint
+pub struct WebhookApprover {
+    client: reqwest::Client,
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+impl WebhookApprover {
+    pub fn new(url: impl Into<String>, headers: HashMap<String, String>) -> Result<Self> {
+        Ok(Self {
+            client: http_client::default_client()?,
+            url: url.into(),
+            headers,
+        })
+    }
+}
+
+#[async_trait]
+impl ExternalApprover for WebhookApprover {
+    async fn decide(&self, context: &ApprovalContext) -> Result<ApprovalDecision> {
+        let mut request = self.client.post(&self.url).json(context);
+        for (key, value) in &self.headers {
+            request = request.header(key, value);
+        }
+
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Approval service returned {}", response.status()));
+        }
+        Ok(response.json::<WebhookResponse>().await?.decision)
+    }
+}
+
+/// Approver settings as read from the `GOOSE_EXTERNAL_APPROVER` config key
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExternalApproverConfig {
+    pub url: String,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub fallback: ApprovalFallback,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// An external approver together with its timeout and fallback rule
+#[derive(Clone)]
+pub struct ExternalApproval {
+    approver: Arc<dyn ExternalApprover>,
+    timeout: Duration,
+    fallback: ApprovalFallback,
+}
+
+impl ExternalApproval {
+    pub fn new(approver: Arc<dyn ExternalApprover>) -> Self {
+        Self {
+            approver,
+            timeout: Duration::from_secs(DEFAULT_APPROVER_TIMEOUT_SECS),
+            fallback: ApprovalFallback::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_fallback(mut self, fallback: ApprovalFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
+    /// Build a webhook approver from `GOOSE_EXTERNAL_APPROVER`, if one is configured
+    pub fn from_config() -> Option<Self> {
+        let config: ExternalApproverConfig =
+            Config::global().get_param("GOOSE_EXTERNAL_APPROVER").ok()?;
+        match WebhookApprover::new(config.url, config.headers) {
+            Ok(approver) => Some(
+                Self::new(Arc::new(approver))
+                    .with_timeout(Duration::from_secs(
+                        config.timeout_secs.unwrap_or(DEFAULT_APPROVER_TIMEOUT_SECS),
+                    ))
+                    .with_fallback(config.fallback),
+            ),
+            Err(e) => {
+                tracing::error!("Failed to create external approver: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Ask the approver about a tool call. Returns `None` when the local rules should decide.
+    pub async fn consult(&self, context: &ApprovalContext) -> Option<ApprovalDecision> {
+        let outcome = tokio::time::timeout(self.timeout, self.approver.decide(context)).await;
+        match outcome {
+            Ok(Ok(decision)) => return Some(decision),
+            Ok(Err(e)) => tracing::warn!(
+                "External approver failed for '{}': {}",
+                context.tool_name,
+                e
+            ),
+            Err(_) => tracing::warn!(
+                "External approver timed out after {:?} for '{}'",
+                self.timeout,
+                context.tool_name
+            ),
+        }
+
+        match self.fallback {
+            ApprovalFallback::Local => None,
+            ApprovalFallback::Deny => Some(ApprovalDecision::Deny),
+            ApprovalFallback::AskUser => Some(ApprovalDecision::AskUser),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct FixedApprover {
+        decision: ApprovalDecision,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl ExternalApprover for FixedApprover {
+        async fn decide(&self, _context: &ApprovalContext) -> Result<ApprovalDecision> {
+            tokio::time::sleep(self.delay).await;
+            Ok(self.decision)
+        }
+    }
+
+    fn context() -> ApprovalContext {
+        ApprovalContext {
+            request_id: "1".to_string(),
+            tool_name: "developer__shell".to_string(),
+            arguments: json!({"command": "rm -rf build"}),
+            mode: "approve".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consult_returns_decision() {
+        let approval = ExternalApproval::new(Arc::new(FixedApprover {
+            decision: ApprovalDecision::Deny,
+            delay: Duration::ZERO,
+        }));
+        assert_eq!(
+            approval.consult(&context()).await,
+            Some(ApprovalDecision::Deny)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_timeout_uses_fallback() {
+        let approver = Arc::new(FixedApprover {
+            decision: ApprovalDecision::Allow,
+            delay: Duration::from_secs(5),
+        });
+
+        let local = ExternalApproval::new(approver.clone()).with_timeout(Duration::from_millis(10));
+        assert_eq!(local.consult(&context()).await, None);
+
+        let ask = ExternalApproval::new(approver)
+            .with_timeout(Duration::from_millis(10))
+            .with_fallback(ApprovalFallback::AskUser);
+        assert_eq!(
+            ask.consult(&context()).await,
+            Some(ApprovalDecision::AskUser)
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config: ExternalApproverConfig = serde_json::from_value(json!({
+            "url": "https://policy.example.com/approve",
+            "fallback": "deny"
+        }))
+        .unwrap();
+        assert_eq!(config.fallback, ApprovalFallback::Deny);
+        assert!(config.timeout_secs.is_none());
+    }
+}
@@ -1,7 +1,12 @@
+pub mod external_approver;
+pub mod network_policy;
 pub mod permission_confirmation;
 pub mod permission_judge;
 pub mod permission_store;
 
+pub use external_approver::{
+    ApprovalContext, ApprovalDecision, ApprovalFallback, ExternalApproval, ExternalApprover,
+};
 pub use permission_confirmation::{Permission, PermissionConfirmation};
 pub use permission_judge::detect_read_only_tools;
 pub use permission_store::ToolPermissionStore;
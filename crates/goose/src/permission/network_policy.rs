@@ -0,0 +1,191 @@
+//! Approval for extensions that reach the network, whatever the goose mode
+//!
+//! Extensions listed in `GOOSE_NETWORK_EXTENSIONS` are treated as sending data off the
+//! machine, so every call to their tools asks the user first, even in `auto` mode. The
+//! prompt names the host the call goes to when it can be found in the arguments. Choosing
+//! "always allow" allows that extension and host for the rest of the session.
+//!
+//! ```yaml
+//! GOOSE_NETWORK_EXTENSIONS:
+//!   - fetch
+//!   - github
+//! ```
+use std::collections::HashSet;
+
+use serde_json::Value;
+use url::Url;
+
+use crate::agents::extension_manager::normalize;
+use crate::config::Config;
+use crate::message::ToolRequest;
+
+/// Argument names that usually hold a host or an address
+const HOST_KEYS: &[&str] = &[
+    "url", "uri", "host", "hostname", "domain", "endpoint", "address",
+];
+
+/// The host a tool call goes to, from a URL or a host-like argument
+pub fn target_host(arguments: &Value) -> Option<String> {
+    match arguments {
+        Value::String(text) => Url::parse(text.trim())
+            .ok()
+            .filter(|url| url.has_host())
+            .and_then(|url| url.host_str().map(str::to_string)),
+        Value::Object(map) => {
+            for key in HOST_KEYS {
+                let Some(Value::String(value)) = map.get(*key) else {
+                    continue;
+                };
+                let value = value.trim();
+                if let Some(host) = target_host(&Value::String(value.to_string())) {
+                    return Some(host);
+                }
+                if !value.is_empty() && !value.contains(char::is_whitespace) {
+                    return Some(value.trim_end_matches('/').to_string());
+                }
+            }
+            map.values().find_map(target_host)
+        }
+        Value::Array(values) => values.iter().find_map(target_host),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NetworkPolicy {
+    /// Normalized names of the extensions that reach the network
+    extensions: Vec<String>,
+    /// Extension and host pairs the user always allowed in this session
+    allowed: HashSet<(String, Option<String>)>,
+}
+
+impl NetworkPolicy {
+    pub fn new(extensions: Vec<String>) -> Self {
+        Self {
+            extensions: extensions.into_iter().map(normalize).collect(),
+            allowed: HashSet::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_NETWORK_EXTENSIONS")
+                .unwrap_or_default(),
+        )
+    }
+
+    /// The network extension a tool belongs to, if any
+    pub fn network_extension(&self, tool_name: &str) -> Option<&str> {
+        let (extension, _) = tool_name.split_once("__")?;
+        self.extensions
+            .iter()
+            .find(|name| name.as_str() == extension)
+            .map(String::as_str)
+    }
+
+    /// Whether a call needs the user's approval before it runs
+    pub fn needs_approval(&self, tool_name: &str, arguments: &Value) -> bool {
+        match self.network_extension(tool_name) {
+            Some(extension) => !self
+                .allowed
+                .contains(&(extension.to_string(), target_host(arguments))),
+            None => false,
+        }
+    }
+
+    /// Allow calls to a tool's extension and host for the rest of the session
+    pub fn always_allow(&mut self, tool_name: &str, arguments: &Value) {
+        if let Some(extension) = self.network_extension(tool_name) {
+            self.allowed
+                .insert((extension.to_string(), target_host(arguments)));
+        }
+    }
+
+    /// The approval prompt for a call to a network extension
+    pub fn prompt(&self, tool_name: &str, arguments: &Value) -> Option<String> {
+        let extension = self.network_extension(tool_name)?;
+        Some(match target_host(arguments) {
+            Some(host) => format!(
+                "The {} extension would like to send data to {}. Allow? (y/n):",
+                extension, host
+            ),
+            None => format!(
+                "The {} extension would like to send data over the network. Allow? (y/n):",
+                extension
+            ),
+        })
+    }
+
+    /// Move calls to network extensions from the approved requests to those needing approval
+    pub fn require_approval(
+        &self,
+        approved: Vec<ToolRequest>,
+        needs_approval: &mut Vec<ToolRequest>,
+    ) -> Vec<ToolRequest> {
+        let (ask, approved): (Vec<ToolRequest>, Vec<ToolRequest>) =
+            approved.into_iter().partition(|request| {
+                request
+                    .tool_call
+                    .as_ref()
+                    .is_ok_and(|call| self.needs_approval(&call.name, &call.arguments))
+            });
+        needs_approval.extend(ask);
+        approved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_target_host() {
+        assert_eq!(
+            target_host(&json!({"url": "https://api.github.com/repos/block/goose"})),
+            Some("api.github.com".to_string())
+        );
+        assert_eq!(
+            target_host(&json!({"host": "db.internal.example.com"})),
+            Some("db.internal.example.com".to_string())
+        );
+        assert_eq!(
+            target_host(&json!({"request": {"target": "http://localhost:8080/health"}})),
+            Some("localhost".to_string())
+        );
+        assert_eq!(target_host(&json!({"query": "rust async"})), None);
+    }
+
+    #[test]
+    fn test_network_calls_need_approval() {
+        let mut policy = NetworkPolicy::new(vec!["Fetch".to_string()]);
+        let request = |id: &str, name: &str, url: &str| ToolRequest {
+            id: id.to_string(),
+            tool_call: Ok(ToolCall::new(name, json!({"url": url}))),
+        };
+
+        let mut needs_approval = Vec::new();
+        let approved = policy.require_approval(
+            vec![
+                request("1", "fetch__fetch", "https://example.com"),
+                request("2", "developer__shell", "https://example.com"),
+            ],
+            &mut needs_approval,
+        );
+        assert_eq!(approved.len(), 1);
+        assert_eq!(needs_approval[0].id, "1");
+        assert_eq!(
+            policy.prompt("fetch__fetch", &json!({"url": "https://example.com"})),
+            Some(
+                "The fetch extension would like to send data to example.com. Allow? (y/n):"
+                    .to_string()
+            )
+        );
+
+        policy.always_allow("fetch__fetch", &json!({"url": "https://example.com/a"}));
+        assert!(!policy.needs_approval("fetch__fetch", &json!({"url": "https://example.com/b"})));
+        assert!(policy.needs_approval("fetch__fetch", &json!({"url": "https://example.org"})));
+    }
+}
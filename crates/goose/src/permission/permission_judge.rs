@@ -2,6 +2,7 @@ use crate::agents::platform_tools::PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME;
 use crate::config::permission::PermissionLevel;
 use crate::config::PermissionManager;
 use crate::message::{Message, MessageContent, ToolRequest};
+use crate::permission::external_approver::{ApprovalContext, ApprovalDecision, ExternalApproval};
 use crate::providers::base::Provider;
 use chrono::Utc;
 use indoc::indoc;
@@ -167,6 +168,7 @@ pub async fn check_tool_permissions(
     tools_without_annotation: HashSet<String>,
     permission_manager: &mut PermissionManager,
     provider: Arc<dyn Provider>,
+    external_approval: Option<&ExternalApproval>,
 ) -> (PermissionCheckResult, Vec<String>) {
     let mut approved = vec![];
     let mut needs_approval = vec![];
@@ -178,7 +180,30 @@ pub async fn check_tool_permissions(
         if let Ok(tool_call) = request.tool_call.clone() {
             if mode == "chat" {
                 continue;
-            } else if mode == "auto" {
+            }
+
+            // 0. Let the external approver decide, falling back to the local rules below
+            if let Some(approval) = external_approval {
+                let context = ApprovalContext {
+                    request_id: request.id.clone(),
+                    tool_name: tool_call.name.clone(),
+                    arguments: tool_call.arguments.clone(),
+                    mode: mode.to_string(),
+                };
+                if let Some(decision) = approval.consult(&context).await {
+                    if tool_call.name == PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME {
+                        extension_request_ids.push(request.id.clone());
+                    }
+                    match decision {
+                        ApprovalDecision::Allow => approved.push(request.clone()),
+                        ApprovalDecision::AskUser => needs_approval.push(request.clone()),
+                        ApprovalDecision::Deny => denied.push(request.clone()),
+                    }
+                    continue;
+                }
+            }
+
+            if mode == "auto" {
                 approved.push(request.clone());
             } else {
                 if tool_call.name == PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME {
@@ -448,6 +473,7 @@ mod tests {
             tools_without_annotation,
             &mut permission_manager,
             provider,
+            None,
         )
         .await;
 
@@ -507,6 +533,7 @@ mod tests {
             tools_without_annotation,
             &mut permission_manager,
             provider,
+            None,
         )
         .await;
 
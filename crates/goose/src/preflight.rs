@@ -0,0 +1,441 @@
+//! Checks of the environment before a session starts
+//!
+//! [`run_preflight`] checks that the provider is configured and reachable, that the commands
+//! of enabled extensions are on the `PATH`, that the working directory exists and is
+//! writable, and that there is disk space for the vector stores. Each failed check carries a
+//! remediation that frontends can act on. More requirements can be declared in config:
+//!
+//! ```yaml
+//! GOOSE_PREFLIGHT:
+//!   required_binaries:
+//!     - docker
+//!     - kubectl
+//!   min_free_disk_mb: 1024
+//!   skip:
+//!     - provider_reachable
+//! ```
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use etcetera::base_strategy::{BaseStrategy, Xdg};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::agents::ExtensionConfig;
+use crate::config::{Config, ExtensionConfigManager};
+use crate::providers::verify::{verify, VerificationIssue};
+
+const DEFAULT_MIN_FREE_DISK_MB: u64 = 500;
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Requirements declared in `GOOSE_PREFLIGHT`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreflightConfig {
+    /// Commands that must be on the PATH, in addition to those of enabled extensions
+    pub required_binaries: Vec<String>,
+    /// Free space needed where the vector stores are kept
+    pub min_free_disk_mb: u64,
+    /// Ids of checks not to run
+    pub skip: Vec<String>,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            required_binaries: Vec::new(),
+            min_free_disk_mb: DEFAULT_MIN_FREE_DISK_MB,
+            skip: Vec::new(),
+        }
+    }
+}
+
+impl PreflightConfig {
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param("GOOSE_PREFLIGHT")
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+/// What would fix a failed check, for frontends to offer or run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Remediation {
+    /// Set configuration keys, e.g. with `goose configure`
+    Configure {
+        keys: Vec<String>,
+    },
+    /// Install a command or add its directory to the PATH
+    InstallBinary {
+        binary: String,
+    },
+    CreateDirectory {
+        path: String,
+    },
+    /// Make a directory writable by the current user
+    FixPermissions {
+        path: String,
+    },
+    FreeDiskSpace {
+        path: String,
+        required_mb: u64,
+    },
+    /// Check the network, the provider's host or its status, then try again
+    CheckConnectivity {
+        provider: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PreflightCheck {
+    /// Stable id of the check, e.g. `provider_reachable` or `extension_binary:github`
+    pub id: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub remediation: Option<Remediation>,
+}
+
+impl PreflightCheck {
+    fn new(id: impl Into<String>, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            status,
+            message: message.into(),
+            remediation: None,
+        }
+    }
+
+    fn with_remediation(mut self, remediation: Remediation) -> Self {
+        self.remediation = Some(remediation);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct PreflightReport {
+    /// Whether no check failed
+    pub ok: bool,
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    fn new(checks: Vec<PreflightCheck>) -> Self {
+        Self {
+            ok: checks.iter().all(|check| check.status != CheckStatus::Fail),
+            checks,
+        }
+    }
+}
+
+/// Where a command would be run from, searching the PATH for bare names
+pub fn find_executable(command: &str) -> Option<PathBuf> {
+    let path = Path::new(command);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .chain(std::iter::once(String::new()))
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    env::split_paths(&env::var_os("PATH")?).find_map(|dir| {
+        extensions.iter().find_map(|extension| {
+            let candidate = dir.join(format!("{}{}", command, extension));
+            candidate.is_file().then_some(candidate)
+        })
+    })
+}
+
+fn binary_check(id: String, binary: &str, needed_by: &str) -> PreflightCheck {
+    match find_executable(binary) {
+        Some(path) => PreflightCheck::new(
+            id,
+            CheckStatus::Pass,
+            format!("{} is at {}", binary, path.display()),
+        ),
+        None => PreflightCheck::new(
+            id,
+            CheckStatus::Fail,
+            format!("{} needs {}, which is not on the PATH", needed_by, binary),
+        )
+        .with_remediation(Remediation::InstallBinary {
+            binary: binary.to_string(),
+        }),
+    }
+}
+
+fn extension_checks(config: &PreflightConfig) -> Vec<PreflightCheck> {
+    let extensions = match ExtensionConfigManager::get_all() {
+        Ok(extensions) => extensions,
+        Err(e) => {
+            return vec![PreflightCheck::new(
+                "extensions",
+                CheckStatus::Fail,
+                format!("Extensions could not be read from the config: {}", e),
+            )
+            .with_remediation(Remediation::Configure {
+                keys: vec!["extensions".to_string()],
+            })]
+        }
+    };
+
+    let mut checks: Vec<PreflightCheck> = extensions
+        .into_iter()
+        .filter(|entry| entry.enabled)
+        .filter_map(|entry| match entry.config {
+            ExtensionConfig::Stdio { name, cmd, .. } => Some(binary_check(
+                format!("extension_binary:{}", name),
+                &cmd,
+                &format!("The {} extension", name),
+            )),
+            _ => None,
+        })
+        .collect();
+    checks.extend(config.required_binaries.iter().map(|binary| {
+        binary_check(
+            format!("required_binary:{}", binary),
+            binary,
+            "GOOSE_PREFLIGHT",
+        )
+    }));
+    checks
+}
+
+pub fn working_dir_check(working_dir: &Path) -> PreflightCheck {
+    let id = "working_dir";
+    let path = working_dir.display().to_string();
+    if !working_dir.is_dir() {
+        return PreflightCheck::new(
+            id,
+            CheckStatus::Fail,
+            format!("The working directory {} does not exist", path),
+        )
+        .with_remediation(Remediation::CreateDirectory { path });
+    }
+    let probe = working_dir.join(format!(".goose-preflight-{}", std::process::id()));
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = fs::remove_file(&probe);
+            PreflightCheck::new(
+                id,
+                CheckStatus::Pass,
+                format!("{} exists and is writable", path),
+            )
+        }
+        Err(e) => PreflightCheck::new(
+            id,
+            CheckStatus::Warn,
+            format!("{} is not writable, so files cannot be edited: {}", path, e),
+        )
+        .with_remediation(Remediation::FixPermissions { path }),
+    }
+}
+
+/// The nearest existing directory of a path, for measuring the space of a store not yet created
+fn existing_ancestor(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|ancestor| ancestor.is_dir())
+}
+
+pub fn disk_space_check(store_dir: &Path, min_free_mb: u64) -> PreflightCheck {
+    let id = "vector_store_disk";
+    let path = store_dir.display().to_string();
+    let available = existing_ancestor(store_dir)
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+        .and_then(fs2::available_space);
+    match available {
+        Ok(bytes) if bytes / (1024 * 1024) >= min_free_mb => PreflightCheck::new(
+            id,
+            CheckStatus::Pass,
+            format!("{} MB free for {}", bytes / (1024 * 1024), path),
+        ),
+        Ok(bytes) => PreflightCheck::new(
+            id,
+            CheckStatus::Warn,
+            format!(
+                "Only {} MB free for {}, {} MB are recommended for the vector stores",
+                bytes / (1024 * 1024),
+                path,
+                min_free_mb
+            ),
+        )
+        .with_remediation(Remediation::FreeDiskSpace {
+            path,
+            required_mb: min_free_mb,
+        }),
+        Err(e) => PreflightCheck::new(
+            id,
+            CheckStatus::Warn,
+            format!("Free space for {} could not be measured: {}", path, e),
+        ),
+    }
+}
+
+async fn provider_check(skip_reachability: bool) -> PreflightCheck {
+    let id = "provider_reachable";
+    let Ok(provider) = Config::global().get_param::<String>("GOOSE_PROVIDER") else {
+        return PreflightCheck::new(id, CheckStatus::Fail, "No provider is configured")
+            .with_remediation(Remediation::Configure {
+                keys: vec!["GOOSE_PROVIDER".to_string(), "GOOSE_MODEL".to_string()],
+            });
+    };
+    if skip_reachability {
+        return PreflightCheck::new(
+            id,
+            CheckStatus::Skipped,
+            format!("{} is configured but was not contacted", provider),
+        );
+    }
+
+    let verification = match tokio::time::timeout(PROVIDER_TIMEOUT, verify(&provider)).await {
+        Ok(Ok(verification)) => verification,
+        Ok(Err(e)) => {
+            return PreflightCheck::new(id, CheckStatus::Fail, e.to_string()).with_remediation(
+                Remediation::Configure {
+                    keys: vec!["GOOSE_PROVIDER".to_string()],
+                },
+            )
+        }
+        Err(_) => {
+            return PreflightCheck::new(
+                id,
+                CheckStatus::Fail,
+                format!(
+                    "{} did not respond within {} seconds",
+                    provider,
+                    PROVIDER_TIMEOUT.as_secs()
+                ),
+            )
+            .with_remediation(Remediation::CheckConnectivity { provider })
+        }
+    };
+
+    let remediation = match verification.issue {
+        None => return PreflightCheck::new(id, CheckStatus::Pass, verification.message),
+        Some(VerificationIssue::MissingConfig | VerificationIssue::InvalidKey) => {
+            // The message names the keys, frontends open the provider's settings
+            Remediation::Configure {
+                keys: vec!["GOOSE_PROVIDER".to_string()],
+            }
+        }
+        Some(VerificationIssue::ModelNotFound) => Remediation::Configure {
+            keys: vec!["GOOSE_MODEL".to_string()],
+        },
+        Some(_) => Remediation::CheckConnectivity {
+            provider: provider.clone(),
+        },
+    };
+    let status = match verification.issue {
+        // Both pass on their own, so they should not stop a session from starting
+        Some(VerificationIssue::RateLimited | VerificationIssue::ProviderUnavailable) => {
+            CheckStatus::Warn
+        }
+        _ => CheckStatus::Fail,
+    };
+    PreflightCheck::new(id, status, verification.message).with_remediation(remediation)
+}
+
+/// The data directory the tool selection and workspace search vector stores are kept in
+fn vector_store_dir() -> Option<PathBuf> {
+    Xdg::new().ok().map(|xdg| xdg.data_dir().join("goose"))
+}
+
+/// Check the environment for a session in `working_dir`
+pub async fn run_preflight(working_dir: &Path) -> PreflightReport {
+    let config = PreflightConfig::from_config();
+    let skipped = |id: &str| config.skip.iter().any(|skip| skip == id);
+
+    let mut checks = vec![provider_check(skipped("provider_reachable")).await];
+    checks.extend(extension_checks(&config));
+    checks.push(working_dir_check(working_dir));
+    if let Some(store_dir) = vector_store_dir() {
+        checks.push(disk_space_check(&store_dir, config.min_free_disk_mb));
+    }
+
+    for check in checks.iter_mut() {
+        let category = check.id.split(':').next().unwrap_or_default();
+        if check.status != CheckStatus::Skipped && (skipped(&check.id) || skipped(category)) {
+            *check = PreflightCheck::new(check.id.clone(), CheckStatus::Skipped, "Skipped");
+        }
+    }
+    PreflightReport::new(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_executable() {
+        let dir = TempDir::new().unwrap();
+        let tool = dir.path().join("tool");
+        fs::write(&tool, "").unwrap();
+        assert_eq!(find_executable(tool.to_str().unwrap()), Some(tool));
+        assert_eq!(find_executable("surely-not-a-real-goose-binary"), None);
+    }
+
+    #[test]
+    fn test_working_dir_and_disk_checks() {
+        let dir = TempDir::new().unwrap();
+        assert_eq!(working_dir_check(dir.path()).status, CheckStatus::Pass);
+
+        let missing = dir.path().join("missing");
+        let check = working_dir_check(&missing);
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert_eq!(
+            check.remediation,
+            Some(Remediation::CreateDirectory {
+                path: missing.display().to_string()
+            })
+        );
+
+        // The store does not need to exist yet
+        assert_eq!(
+            disk_space_check(&missing.join("tool_db"), 0).status,
+            CheckStatus::Pass
+        );
+        assert_eq!(
+            disk_space_check(dir.path(), u64::MAX).status,
+            CheckStatus::Warn
+        );
+    }
+
+    #[test]
+    fn test_report_fails_on_any_failed_check() {
+        let report = PreflightReport::new(vec![
+            PreflightCheck::new("working_dir", CheckStatus::Pass, "ok"),
+            PreflightCheck::new("vector_store_disk", CheckStatus::Warn, "low"),
+        ]);
+        assert!(report.ok);
+        let report = PreflightReport::new(vec![PreflightCheck::new(
+            "extension_binary:github",
+            CheckStatus::Fail,
+            "missing",
+        )]);
+        assert!(!report.ok);
+        assert_eq!(
+            serde_json::to_value(Remediation::InstallBinary {
+                binary: "npx".to_string()
+            })
+            .unwrap(),
+            serde_json::json!({"action": "install_binary", "binary": "npx"})
+        );
+    }
+}
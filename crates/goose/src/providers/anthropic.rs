@@ -3,12 +3,17 @@ use async_trait::async_trait;
 use axum::http::HeaderMap;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 use super::errors::ProviderError;
-use super::formats::anthropic::{create_request, get_usage, response_to_message};
+use super::formats::anthropic::{
+    add_web_search_tool, create_request, get_usage, response_to_message,
+};
+use super::http_client;
 use super::utils::{emit_debug_trace, get_model};
+use super::web_search::WebSearchConfig;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
@@ -34,6 +39,8 @@ pub struct AnthropicProvider {
     host: String,
     api_key: String,
     model: ModelConfig,
+    #[serde(skip)]
+    web_search: WebSearchConfig,
 }
 
 impl Default for AnthropicProvider {
@@ -51,7 +58,7 @@ impl AnthropicProvider {
             .get_param("ANTHROPIC_HOST")
             .unwrap_or_else(|_| "https://api.anthropic.com".to_string());
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -60,6 +67,7 @@ impl AnthropicProvider {
             host,
             api_key,
             model,
+            web_search: WebSearchConfig::from_config(),
         })
     }
 
@@ -139,12 +147,24 @@ impl Provider for AnthropicProvider {
                 ),
             ],
         )
+        .with_web_search()
     }
 
     fn get_model_config(&self) -> ModelConfig {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        true
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -155,7 +175,10 @@ impl Provider for AnthropicProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = create_request(&self.model, system, messages, tools)?;
+        if self.web_search.enabled {
+            add_web_search_tool(&mut payload, self.web_search.max_uses);
+        }
 
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("x-api-key", self.api_key.parse().unwrap());
@@ -0,0 +1,231 @@
+//! Attribution tags for usage chargeback
+//!
+//! Deployments that serve several teams can tag each session with who it runs for, such as the
+//! team, project or user id. The tags of a session apply while its reply runs: they are attached
+//! to every [`ProviderUsage`](super::base::ProviderUsage) created for it, stored in the session
+//! metadata next to its accumulated token counts, and written with each outbound filter audit
+//! entry.
+//!
+//! Tags set in the config apply to every session, and the tags of a `SessionConfig` are added on
+//! top, replacing configured tags with the same key:
+//!
+//! ```yaml
+//! GOOSE_ATTRIBUTION_TAGS:
+//!   team: platform
+//!   cost_center: "4410"
+//! ```
+//!
+//! Each provider created by the factory is also wrapped in an `AttributedProvider`, which names
+//! it in the usage of its responses. Behind a fallback chain or a lead/worker pair, the usage
+//! then says which provider answered, so the response is priced at that provider's costs.
+use std::collections::BTreeMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use mcp_core::tool::Tool;
+
+use super::base::{
+    FallbackProviderTrait, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage,
+};
+use super::batch::BatchProviderTrait;
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+pub type AttributionTags = BTreeMap<String, String>;
+
+tokio::task_local! {
+    static TAGS: AttributionTags;
+}
+
+/// The tags from `GOOSE_ATTRIBUTION_TAGS`
+pub fn configured_tags() -> AttributionTags {
+    Config::global()
+        .get_param("GOOSE_ATTRIBUTION_TAGS")
+        .unwrap_or_default()
+}
+
+/// The configured tags with those of a session added on top; blank keys and values are dropped
+pub fn merge_tags(configured: AttributionTags, session: &AttributionTags) -> AttributionTags {
+    let mut tags = configured;
+    tags.extend(session.iter().map(|(k, v)| (k.clone(), v.clone())));
+    tags.into_iter()
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+        .collect()
+}
+
+/// The tags of the reply being polled, empty outside of one
+pub fn current_tags() -> AttributionTags {
+    TAGS.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// A stream that makes `tags` current whenever it is polled
+pub struct Tagged<S> {
+    tags: AttributionTags,
+    inner: Pin<Box<S>>,
+}
+
+impl<S: Stream> Stream for Tagged<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S::Item>> {
+        let this = self.get_mut();
+        if this.tags.is_empty() {
+            return this.inner.as_mut().poll_next(cx);
+        }
+        TAGS.sync_scope(this.tags.clone(), || this.inner.as_mut().poll_next(cx))
+    }
+}
+
+pub fn tagged<S: Stream>(tags: AttributionTags, stream: S) -> Tagged<S> {
+    Tagged {
+        tags,
+        inner: Box::pin(stream),
+    }
+}
+
+/// A provider that names itself in the usage of its responses
+pub struct AttributedProvider {
+    inner: Arc<dyn Provider>,
+    name: String,
+}
+
+impl AttributedProvider {
+    pub fn wrap(name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        Arc::new(Self {
+            inner: provider,
+            name: name.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl Provider for AttributedProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "attributed",
+            "Attributed Provider",
+            "A provider that names itself in the usage of its responses",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let (message, usage) = self.inner.complete(system, messages, tools).await?;
+        Ok((message, usage.with_provider(&self.name)))
+    }
+
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let stream = self
+            .inner
+            .complete_streaming(system, messages, tools)
+            .await?;
+        let name = self.name.clone();
+        Ok(Box::pin(stream.map(move |item| {
+            item.map(|(message, usage)| (message, usage.map(|usage| usage.with_provider(&name))))
+        })))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> anyhow::Result<Arc<dyn Provider>> {
+        Ok(Self::wrap(
+            &self.name,
+            self.inner.with_model_override(update)?,
+        ))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.inner.supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        self.inner.as_fallback()
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.inner.as_batch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    fn tags(pairs: &[(&str, &str)]) -> AttributionTags {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_session_tags_replace_configured_ones() {
+        let merged = merge_tags(
+            tags(&[("team", "platform"), ("project", "goose")]),
+            &tags(&[("team", "data"), ("user", " u-42 "), ("empty", " ")]),
+        );
+        assert_eq!(
+            merged,
+            tags(&[("project", "goose"), ("team", "data"), ("user", "u-42")])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tags_are_current_while_polled() {
+        assert!(current_tags().is_empty());
+        let stream = tagged(
+            tags(&[("team", "platform")]),
+            futures::stream::iter(0..2).map(|_| current_tags()),
+        );
+        let seen: Vec<AttributionTags> = stream.collect().await;
+        assert_eq!(seen, vec![tags(&[("team", "platform")]); 2]);
+        assert!(current_tags().is_empty());
+    }
+}
@@ -3,13 +3,15 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde::Serialize;
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
-use super::azureauth::AzureAuth;
+use super::azureauth::{AzureAuth, AzureCredentials, ServicePrincipal};
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::http_client;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -67,14 +69,26 @@ impl AzureProvider {
             .get_param("AZURE_OPENAI_API_VERSION")
             .unwrap_or_else(|_| AZURE_DEFAULT_API_VERSION.to_string());
 
-        // Try to get API key first, if not found use Azure credential chain
-        let api_key = config.get_secret("AZURE_OPENAI_API_KEY").ok();
-        let auth = AzureAuth::new(api_key)?;
-
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
+        // Try to get API key first, then a service principal, otherwise use the Azure CLI
+        let api_key = config.get_secret("AZURE_OPENAI_API_KEY").ok();
+        let service_principal = match (
+            config.get_param::<String>("AZURE_TENANT_ID"),
+            config.get_param::<String>("AZURE_CLIENT_ID"),
+            config.get_secret::<String>("AZURE_CLIENT_SECRET"),
+        ) {
+            (Ok(tenant_id), Ok(client_id), Ok(client_secret)) => Some(ServicePrincipal {
+                tenant_id,
+                client_id,
+                client_secret,
+            }),
+            _ => None,
+        };
+        let auth = AzureAuth::new(api_key, service_principal, client.clone())?;
+
         Ok(Self {
             client,
             endpoint,
@@ -132,10 +146,10 @@ impl AzureProvider {
 
             // Set the correct header based on authentication type
             match self.auth.credential_type() {
-                super::azureauth::AzureCredentials::ApiKey(_) => {
+                AzureCredentials::ApiKey(_) => {
                     request_builder = request_builder.header("api-key", token_value.clone());
                 }
-                super::azureauth::AzureCredentials::DefaultCredential => {
+                AzureCredentials::ServicePrincipal(_) | AzureCredentials::DefaultCredential => {
                     request_builder = request_builder
                         .header("Authorization", format!("Bearer {}", token_value.clone()));
                 }
@@ -219,7 +233,7 @@ impl Provider for AzureProvider {
         ProviderMetadata::new(
             "azure_openai",
             "Azure OpenAI",
-            "Models through Azure OpenAI Service (uses Azure credential chain by default)",
+            "Models through Azure OpenAI Service (uses an API key, a service principal or the Azure CLI)",
             "gpt-4o",
             AZURE_OPENAI_KNOWN_MODELS.to_vec(),
             AZURE_DOC_URL,
@@ -227,6 +241,7 @@ impl Provider for AzureProvider {
                 ConfigKey::new("AZURE_OPENAI_ENDPOINT", true, false, None),
                 ConfigKey::new("AZURE_OPENAI_DEPLOYMENT_NAME", true, false, None),
                 ConfigKey::new("AZURE_OPENAI_API_VERSION", true, false, Some("2024-10-21")),
+                ConfigKey::new("AZURE_OPENAI_API_KEY", false, true, None),
             ],
         )
     }
@@ -235,6 +250,13 @@ impl Provider for AzureProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
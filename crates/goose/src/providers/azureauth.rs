@@ -1,4 +1,5 @@
 use chrono;
+use reqwest::Client;
 use serde::Deserialize;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
@@ -30,10 +31,29 @@ pub struct AuthToken {
 pub enum AzureCredentials {
     /// API key based authentication
     ApiKey(String),
+    /// Microsoft Entra ID service principal, for machines without the Azure CLI
+    ServicePrincipal(ServicePrincipal),
     /// Azure credential chain based authentication
     DefaultCredential,
 }
 
+/// The app registration a service principal signs in with
+#[derive(Clone)]
+pub struct ServicePrincipal {
+    pub tenant_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl std::fmt::Debug for ServicePrincipal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServicePrincipal")
+            .field("tenant_id", &self.tenant_id)
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
 /// Holds a cached token and its expiration time.
 #[derive(Debug, Clone)]
 struct CachedToken {
@@ -52,32 +72,50 @@ struct TokenResponse {
     expires_on: u64,
 }
 
+/// Response from the Microsoft Entra ID token endpoint
+#[derive(Debug, Clone, Deserialize)]
+struct EntraTokenResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: u64,
+}
+
+/// The scope of tokens for Azure OpenAI
+const COGNITIVE_SERVICES_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+
 /// Azure authentication handler that manages credentials and token caching.
 #[derive(Debug)]
 pub struct AzureAuth {
     credentials: AzureCredentials,
     cached_token: Arc<RwLock<Option<CachedToken>>>,
+    client: Client,
 }
 
 impl AzureAuth {
     /// Creates a new Azure authentication handler.
     ///
     /// Initializes the authentication handler by:
-    /// 1. Loading credentials from environment
-    /// 2. Setting up an HTTP client for token requests
+    /// 1. Choosing the credentials: an API key, then a service principal, then the Azure CLI
+    /// 2. Keeping the HTTP client for token requests
     /// 3. Initializing the token cache
     ///
     /// # Returns
     /// * `Result<Self, AuthError>` - A new AzureAuth instance or an error if initialization fails
-    pub fn new(api_key: Option<String>) -> Result<Self, AuthError> {
-        let credentials = match api_key {
-            Some(key) => AzureCredentials::ApiKey(key),
-            None => AzureCredentials::DefaultCredential,
+    pub fn new(
+        api_key: Option<String>,
+        service_principal: Option<ServicePrincipal>,
+        client: Client,
+    ) -> Result<Self, AuthError> {
+        let credentials = match (api_key, service_principal) {
+            (Some(key), _) => AzureCredentials::ApiKey(key),
+            (None, Some(principal)) => AzureCredentials::ServicePrincipal(principal),
+            (None, None) => AzureCredentials::DefaultCredential,
         };
 
         Ok(Self {
             credentials,
             cached_token: Arc::new(RwLock::new(None)),
+            client,
         })
     }
 
@@ -104,11 +142,13 @@ impl AzureAuth {
                 token_type: "Bearer".to_string(),
                 token_value: key.clone(),
             }),
-            AzureCredentials::DefaultCredential => self.get_default_credential_token().await,
+            AzureCredentials::ServicePrincipal(_) | AzureCredentials::DefaultCredential => {
+                self.get_cached_token().await
+            }
         }
     }
 
-    async fn get_default_credential_token(&self) -> Result<AuthToken, AuthError> {
+    async fn get_cached_token(&self) -> Result<AuthToken, AuthError> {
         // Try read lock first for better concurrency
         if let Some(cached) = self.cached_token.read().await.as_ref() {
             if cached.expires_at > Instant::now() {
@@ -126,7 +166,70 @@ impl AzureAuth {
             }
         }
 
-        // Get new token using Azure CLI credential
+        let (auth_token, expires_in) = match &self.credentials {
+            AzureCredentials::ServicePrincipal(principal) => {
+                self.get_service_principal_token(principal).await?
+            }
+            _ => Self::get_cli_token().await?,
+        };
+
+        let expires_at = Instant::now() + expires_in.saturating_sub(Duration::from_secs(30));
+
+        *token_guard = Some(CachedToken {
+            token: auth_token.clone(),
+            expires_at,
+        });
+
+        Ok(auth_token)
+    }
+
+    /// A token for the service principal from the client credentials flow, and its lifetime
+    async fn get_service_principal_token(
+        &self,
+        principal: &ServicePrincipal,
+    ) -> Result<(AuthToken, Duration), AuthError> {
+        let url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            principal.tenant_id
+        );
+        let response = self
+            .client
+            .post(url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", principal.client_id.as_str()),
+                ("client_secret", principal.client_secret.as_str()),
+                ("scope", COGNITIVE_SERVICES_SCOPE),
+            ])
+            .send()
+            .await
+            .map_err(|e| AuthError::TokenExchange(format!("Token request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AuthError::TokenExchange(format!(
+                "Microsoft Entra ID returned {}: {}",
+                status, body
+            )));
+        }
+
+        let token_response: EntraTokenResponse = response
+            .json()
+            .await
+            .map_err(|e| AuthError::TokenExchange(format!("Invalid token response: {}", e)))?;
+
+        Ok((
+            AuthToken {
+                token_type: token_response.token_type,
+                token_value: token_response.access_token,
+            },
+            Duration::from_secs(token_response.expires_in),
+        ))
+    }
+
+    /// A token from the signed in Azure CLI, and its lifetime
+    async fn get_cli_token() -> Result<(AuthToken, Duration), AuthError> {
         let output = tokio::process::Command::new("az")
             .args([
                 "account",
@@ -151,20 +254,49 @@ impl AzureAuth {
             token_type: token_response.token_type,
             token_value: token_response.access_token,
         };
+        let expires_in = Duration::from_secs(
+            token_response
+                .expires_on
+                .saturating_sub(chrono::Utc::now().timestamp() as u64),
+        );
 
-        let expires_at = Instant::now()
-            + Duration::from_secs(
-                token_response
-                    .expires_on
-                    .saturating_sub(chrono::Utc::now().timestamp() as u64)
-                    .saturating_sub(30),
-            );
+        Ok((auth_token, expires_in))
+    }
+}
 
-        *token_guard = Some(CachedToken {
-            token: auth_token.clone(),
-            expires_at,
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(auth_token)
+    #[test]
+    fn test_credentials_prefer_api_key_then_service_principal() {
+        let principal = ServicePrincipal {
+            tenant_id: "tenant".to_string(),
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+        };
+        let auth = AzureAuth::new(
+            Some("key".to_string()),
+            Some(principal.clone()),
+            Client::new(),
+        )
+        .unwrap();
+        assert!(matches!(
+            auth.credential_type(),
+            AzureCredentials::ApiKey(_)
+        ));
+
+        let auth = AzureAuth::new(None, Some(principal), Client::new()).unwrap();
+        assert!(matches!(
+            auth.credential_type(),
+            AzureCredentials::ServicePrincipal(_)
+        ));
+        assert!(!format!("{:?}", auth).contains("secret"));
+
+        let auth = AzureAuth::new(None, None, Client::new()).unwrap();
+        assert!(matches!(
+            auth.credential_type(),
+            AzureCredentials::DefaultCredential
+        ));
     }
 }
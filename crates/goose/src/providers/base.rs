@@ -1,14 +1,18 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
+use super::attribution::{current_tags, AttributionTags};
+use super::batch::BatchProviderTrait;
 use super::errors::ProviderError;
-use crate::message::Message;
+use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
 use utoipa::ToSchema;
 
+use futures::Stream;
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 /// A global store for the current model being used, we use this as when a provider returns, it tells us the real model, not an alias
 pub static CURRENT_MODEL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
@@ -32,6 +36,29 @@ pub struct ModelInfo {
     pub name: String,
     /// The maximum context length this model supports
     pub context_limit: usize,
+    /// USD per million input tokens, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_cost: Option<f64>,
+    /// USD per million output tokens, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_cost: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_tools: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supports_vision: Option<bool>,
+}
+
+impl ModelInfo {
+    pub fn new(name: impl Into<String>, context_limit: usize) -> Self {
+        Self {
+            name: name.into(),
+            context_limit,
+            input_cost: None,
+            output_cost: None,
+            supports_tools: None,
+            supports_vision: None,
+        }
+    }
 }
 
 /// Metadata about a provider's configuration requirements and capabilities
@@ -52,6 +79,9 @@ pub struct ProviderMetadata {
     pub model_doc_link: String,
     /// Required configuration keys
     pub config_keys: Vec<ConfigKey>,
+    /// Whether the provider can search the web itself when `GOOSE_WEB_SEARCH` is set
+    #[serde(default)]
+    pub web_search: bool,
 }
 
 impl ProviderMetadata {
@@ -71,16 +101,22 @@ impl ProviderMetadata {
             default_model: default_model.to_string(),
             known_models: model_names
                 .iter()
-                .map(|&name| ModelInfo {
-                    name: name.to_string(),
-                    context_limit: ModelConfig::new(name.to_string()).context_limit(),
+                .map(|&name| {
+                    ModelInfo::new(name, ModelConfig::new(name.to_string()).context_limit())
                 })
                 .collect(),
             model_doc_link: model_doc_link.to_string(),
             config_keys,
+            web_search: false,
         }
     }
 
+    /// Mark the provider as supporting native web search
+    pub fn with_web_search(mut self) -> Self {
+        self.web_search = true;
+        self
+    }
+
     pub fn empty() -> Self {
         Self {
             name: "".to_string(),
@@ -90,6 +126,7 @@ impl ProviderMetadata {
             known_models: vec![],
             model_doc_link: "".to_string(),
             config_keys: vec![],
+            web_search: false,
         }
     }
 }
@@ -117,19 +154,43 @@ impl ConfigKey {
 pub struct ProviderUsage {
     pub model: String,
     pub usage: Usage,
+    /// Attribution tags of the session the usage is charged to
+    #[serde(default, skip_serializing_if = "AttributionTags::is_empty")]
+    pub tags: AttributionTags,
+    /// The provider that answered, by its name in the config, when it is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
 impl ProviderUsage {
+    /// Usage tagged with the attribution tags of the reply in progress
     pub fn new(model: String, usage: Usage) -> Self {
-        Self { model, usage }
+        Self {
+            model,
+            usage,
+            tags: current_tags(),
+            provider: None,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Usage {
+    /// All input tokens, including those read from and written to the prompt cache
     pub input_tokens: Option<i32>,
     pub output_tokens: Option<i32>,
     pub total_tokens: Option<i32>,
+    /// Input tokens read from the prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_input_tokens: Option<i32>,
+    /// Input tokens written to the prompt cache
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_input_tokens: Option<i32>,
 }
 
 impl Usage {
@@ -142,6 +203,24 @@ impl Usage {
             input_tokens,
             output_tokens,
             total_tokens,
+            cache_read_input_tokens: None,
+            cache_write_input_tokens: None,
+        }
+    }
+
+    /// The usage with the input tokens the prompt cache served and stored
+    pub fn with_cache_tokens(mut self, read: Option<i32>, write: Option<i32>) -> Self {
+        self.cache_read_input_tokens = read;
+        self.cache_write_input_tokens = write;
+        self
+    }
+
+    /// The share of the input tokens read from the prompt cache, if the provider reports it
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let read = self.cache_read_input_tokens?;
+        match self.input_tokens {
+            Some(input) if input > 0 => Some(read as f64 / input as f64),
+            _ => None,
         }
     }
 }
@@ -157,6 +236,31 @@ pub trait LeadWorkerProviderTrait {
     fn get_active_model(&self) -> String;
 }
 
+/// Trait for FallbackProvider-specific functionality
+pub trait FallbackProviderTrait {
+    /// The model that answered the last request and whether it is a fallback, if it is not the
+    /// model that answered the request before
+    fn take_switch(&self) -> Option<(String, bool)>;
+}
+
+/// A response as it is generated, in parts and with the usage once it is known
+pub type MessageStream = Pin<
+    Box<dyn Stream<Item = Result<(Option<Message>, Option<ProviderUsage>), ProviderError>> + Send>,
+>;
+
+/// Join the next part of a streamed response onto what has arrived so far, extending the last
+/// text rather than starting a new one
+pub fn append_to_response(response: &mut Message, part: Message) {
+    for content in part.content {
+        match (response.content.last_mut(), content) {
+            (Some(MessageContent::Text(last)), MessageContent::Text(text)) => {
+                last.text.push_str(&text.text)
+            }
+            (_, content) => response.content.push(content),
+        }
+    }
+}
+
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -185,9 +289,45 @@ pub trait Provider: Send + Sync {
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError>;
 
+    /// Generate the next message as a stream of parts, for frontends that show the response
+    /// while it is written. The parts joined with [`append_to_response`] make the message
+    /// [`Provider::complete`] would return. Text arrives a chunk per part and tool requests
+    /// arrive whole, with the usage normally on the last part.
+    ///
+    /// The default implementation sends the whole response as one part.
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let (message, usage) = self.complete(system, messages, tools).await?;
+        Ok(Box::pin(futures::stream::once(async move {
+            Ok((Some(message), Some(usage)))
+        })))
+    }
+
+    /// Check if this provider streams responses as they are generated, rather than sending
+    /// them whole from [`Provider::complete_streaming`]
+    fn supports_streaming(&self) -> bool {
+        false
+    }
+
     /// Get the model config from the provider
     fn get_model_config(&self) -> ModelConfig;
 
+    /// This provider with its model config updated, as for a reply with its own sampling or
+    /// model. Wrappers keep wrapping the updated provider, so the rate limits, retries,
+    /// fallbacks and request filters stay in place.
+    fn with_model_override(
+        &self,
+        _update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Err(anyhow::anyhow!(
+            "This provider cannot be made with another model config"
+        ))
+    }
+
     /// Optional hook to fetch supported models asynchronously.
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         Ok(None)
@@ -198,6 +338,13 @@ pub trait Provider: Send + Sync {
         false
     }
 
+    /// Check if this provider caches prompts for its model, marking cache breakpoints in its
+    /// requests with [`formats::cache`](super::formats::cache) and reporting cache hits in
+    /// [`Usage`]
+    fn supports_prompt_caching(&self) -> bool {
+        false
+    }
+
     /// Create embeddings if supported. Default implementation returns an error.
     async fn create_embeddings(&self, _texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
         Err(ProviderError::ExecutionError(
@@ -211,6 +358,16 @@ pub trait Provider: Send + Sync {
         None
     }
 
+    /// Check if this provider is a FallbackProvider, to report when it switches models
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        None
+    }
+
+    /// The provider's batch API, if it has one
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        None
+    }
+
     /// Get the currently active model name
     /// For regular providers, this returns the configured model
     /// For LeadWorkerProvider, this returns the currently active model (lead or worker)
@@ -230,12 +387,37 @@ mod tests {
 
     use serde_json::json;
 
+    #[test]
+    fn test_streamed_parts_make_one_response() {
+        let mut response = Message::assistant();
+        for part in ["Let me ", "check the ", "logs."] {
+            append_to_response(&mut response, Message::assistant().with_text(part));
+        }
+        append_to_response(
+            &mut response,
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(mcp_core::ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "tail app.log"}),
+                )),
+            ),
+        );
+        assert_eq!(response.content.len(), 2);
+        assert_eq!(response.as_concat_text(), "Let me check the logs.");
+        assert!(response.is_tool_call());
+    }
+
     #[test]
     fn test_usage_creation() {
         let usage = Usage::new(Some(10), Some(20), Some(30));
         assert_eq!(usage.input_tokens, Some(10));
         assert_eq!(usage.output_tokens, Some(20));
         assert_eq!(usage.total_tokens, Some(30));
+        assert_eq!(usage.cache_hit_rate(), None);
+
+        let cached = usage.with_cache_tokens(Some(8), None);
+        assert_eq!(cached.cache_hit_rate(), Some(0.8));
     }
 
     #[test]
@@ -310,24 +492,15 @@ mod tests {
     #[test]
     fn test_model_info_creation() {
         // Test direct ModelInfo creation
-        let info = ModelInfo {
-            name: "test-model".to_string(),
-            context_limit: 1000,
-        };
+        let info = ModelInfo::new("test-model", 1000);
         assert_eq!(info.context_limit, 1000);
 
         // Test equality
-        let info2 = ModelInfo {
-            name: "test-model".to_string(),
-            context_limit: 1000,
-        };
+        let info2 = ModelInfo::new("test-model", 1000);
         assert_eq!(info, info2);
 
         // Test inequality
-        let info3 = ModelInfo {
-            name: "test-model".to_string(),
-            context_limit: 2000,
-        };
+        let info3 = ModelInfo::new("test-model", 2000);
         assert_ne!(info, info3);
     }
 }
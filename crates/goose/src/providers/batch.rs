@@ -0,0 +1,275 @@
+//! Batch completions for non-interactive workloads
+//!
+//! Some providers accept many requests as one batch job that completes within hours at a
+//! fraction of the interactive price. A provider with a batch API exposes it through
+//! [`Provider::as_batch`]; [`run_batch`] submits the requests, polls the job until it is done
+//! and returns the results. With a provider that has no batch API, the requests are completed
+//! one after another instead, so callers do not need to care.
+//!
+//! ```yaml
+//! # How often to check on a submitted batch
+//! GOOSE_BATCH_POLL_INTERVAL_SECS: 60
+//! # How long to wait for a batch before giving up on it
+//! GOOSE_BATCH_MAX_WAIT_SECS: 86400
+//! ```
+//!
+//! Wrappers that rewrite what is sent, such as PII redaction and outbound filters, expose the
+//! batch API of the provider they wrap with their rewriting applied to every request of the
+//! batch, and to its results where they rewrite responses too.
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use super::base::Provider;
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 60;
+const DEFAULT_MAX_WAIT_SECS: u64 = 24 * 60 * 60;
+
+/// One completion in a batch, answered without tools
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// Identifies the request's result, unique within the batch
+    pub custom_id: String,
+    pub system: String,
+    pub messages: Vec<Message>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchStatus {
+    InProgress { completed: usize, total: usize },
+    Completed,
+    Failed(String),
+}
+
+/// The answer to one request of a batch
+#[derive(Debug, Clone)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub response: Result<Message, String>,
+}
+
+/// Trait for providers with a batch API
+#[async_trait]
+pub trait BatchProviderTrait: Send + Sync {
+    /// Submit the requests as one batch job, returning the job's id
+    async fn submit_batch(&self, requests: &[BatchRequest]) -> Result<String, ProviderError>;
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, ProviderError>;
+
+    /// The results of a completed batch, in no particular order
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, ProviderError>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub poll_interval: Duration,
+    pub max_wait: Duration,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(DEFAULT_POLL_INTERVAL_SECS),
+            max_wait: Duration::from_secs(DEFAULT_MAX_WAIT_SECS),
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let defaults = Self::default();
+        Self {
+            poll_interval: config
+                .get_param("GOOSE_BATCH_POLL_INTERVAL_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.poll_interval),
+            max_wait: config
+                .get_param("GOOSE_BATCH_MAX_WAIT_SECS")
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.max_wait),
+        }
+    }
+}
+
+/// The batch API of the provider a wrapper wraps, for wrappers that expose it as their own
+pub fn inner_batch(inner: &dyn Provider) -> Result<&dyn BatchProviderTrait, ProviderError> {
+    inner.as_batch().ok_or_else(|| {
+        ProviderError::ExecutionError("The wrapped provider has no batch API".to_string())
+    })
+}
+
+/// Complete the requests through the provider's batch API when it has one, or one after
+/// another otherwise. Results are returned in the order of the requests.
+pub async fn run_batch(
+    provider: &dyn Provider,
+    requests: &[BatchRequest],
+    config: &BatchConfig,
+) -> Result<Vec<BatchResult>, ProviderError> {
+    let Some(batch) = provider.as_batch() else {
+        return complete_each(provider, requests).await;
+    };
+
+    let batch_id = batch.submit_batch(requests).await?;
+    tracing::info!(batch_id, requests = requests.len(), "Submitted batch");
+    let started = Instant::now();
+    loop {
+        match batch.batch_status(&batch_id).await? {
+            BatchStatus::Completed => break,
+            BatchStatus::Failed(reason) => {
+                return Err(ProviderError::ExecutionError(format!(
+                    "Batch {} failed: {}",
+                    batch_id, reason
+                )))
+            }
+            BatchStatus::InProgress { completed, total } => {
+                tracing::debug!(batch_id, completed, total, "Batch in progress");
+            }
+        }
+        if started.elapsed() >= config.max_wait {
+            return Err(ProviderError::ExecutionError(format!(
+                "Batch {} did not complete within {}s",
+                batch_id,
+                config.max_wait.as_secs()
+            )));
+        }
+        tokio::time::sleep(config.poll_interval).await;
+    }
+
+    let mut results = batch.batch_results(&batch_id).await?;
+    Ok(requests
+        .iter()
+        .map(|request| {
+            let position = results
+                .iter()
+                .position(|result| result.custom_id == request.custom_id);
+            match position {
+                Some(i) => results.swap_remove(i),
+                None => BatchResult {
+                    custom_id: request.custom_id.clone(),
+                    response: Err("The batch returned no result for this request".to_string()),
+                },
+            }
+        })
+        .collect())
+}
+
+async fn complete_each(
+    provider: &dyn Provider,
+    requests: &[BatchRequest],
+) -> Result<Vec<BatchResult>, ProviderError> {
+    let mut results = Vec::with_capacity(requests.len());
+    for request in requests {
+        let response = provider
+            .complete(&request.system, &request.messages, &[])
+            .await;
+        let response = match response {
+            Ok((message, _usage)) => Ok(message),
+            // Without a batch API these fail the same way for every request
+            Err(e @ ProviderError::Authentication(_)) => return Err(e),
+            Err(e) => Err(e.to_string()),
+        };
+        results.push(BatchResult {
+            custom_id: request.custom_id.clone(),
+            response,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModelConfig;
+    use crate::providers::base::{ProviderMetadata, ProviderUsage, Usage};
+    use mcp_core::tool::Tool;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct BatchingProvider {
+        polls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Provider for BatchingProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("batching".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Ok((
+                Message::assistant().with_text("interactive"),
+                ProviderUsage::new("batching".to_string(), Usage::default()),
+            ))
+        }
+
+        fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+            Some(self)
+        }
+    }
+
+    #[async_trait]
+    impl BatchProviderTrait for BatchingProvider {
+        async fn submit_batch(&self, _requests: &[BatchRequest]) -> Result<String, ProviderError> {
+            Ok("batch_1".to_string())
+        }
+
+        async fn batch_status(&self, _batch_id: &str) -> Result<BatchStatus, ProviderError> {
+            if self.polls.fetch_add(1, Ordering::SeqCst) < 2 {
+                Ok(BatchStatus::InProgress {
+                    completed: 0,
+                    total: 2,
+                })
+            } else {
+                Ok(BatchStatus::Completed)
+            }
+        }
+
+        async fn batch_results(&self, _batch_id: &str) -> Result<Vec<BatchResult>, ProviderError> {
+            Ok(vec![BatchResult {
+                custom_id: "b".to_string(),
+                response: Ok(Message::assistant().with_text("batched")),
+            }])
+        }
+    }
+
+    fn request(custom_id: &str) -> BatchRequest {
+        BatchRequest {
+            custom_id: custom_id.to_string(),
+            system: String::new(),
+            messages: vec![Message::user().with_text("Summarize this")],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_is_polled_until_complete() {
+        let provider = BatchingProvider {
+            polls: AtomicUsize::new(0),
+        };
+        let config = BatchConfig {
+            poll_interval: Duration::from_millis(1),
+            max_wait: Duration::from_secs(5),
+        };
+        let results = run_batch(&provider, &[request("a"), request("b")], &config)
+            .await
+            .unwrap();
+        assert_eq!(provider.polls.load(Ordering::SeqCst), 3);
+        assert_eq!(results[0].custom_id, "a");
+        assert!(results[0].response.is_err());
+        assert_eq!(
+            results[1].response.as_ref().unwrap().as_concat_text(),
+            "batched"
+        );
+    }
+}
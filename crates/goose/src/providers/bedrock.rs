@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
@@ -18,7 +19,8 @@ use crate::providers::utils::emit_debug_trace;
 
 // Import the migrated helper functions from providers/formats/bedrock.rs
 use super::formats::bedrock::{
-    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_tool_config,
+    add_cache_points, cache_point, from_bedrock_message, from_bedrock_usage, to_bedrock_message,
+    to_bedrock_tool_config,
 };
 
 pub const BEDROCK_DOC_LINK: &str =
@@ -29,6 +31,14 @@ pub const BEDROCK_KNOWN_MODELS: &[&str] = &[
     "anthropic.claude-3-5-sonnet-20240620-v1:0",
     "anthropic.claude-3-5-sonnet-20241022-v2:0",
 ];
+/// Models Bedrock caches prompts for; others reject requests with cache points
+const BEDROCK_CACHING_MODELS: &[&str] = &[
+    "anthropic.claude-3-7-sonnet",
+    "anthropic.claude-3-5-haiku",
+    "anthropic.claude-sonnet-4",
+    "anthropic.claude-opus-4",
+    "amazon.nova",
+];
 
 #[derive(Debug, serde::Serialize)]
 pub struct BedrockProvider {
@@ -95,6 +105,20 @@ impl Provider for BedrockProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        // Inference profiles prefix the model id with a region, as in `us.anthropic.claude-...`
+        BEDROCK_CACHING_MODELS
+            .iter()
+            .any(|model| self.model.model_name.contains(model))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -107,20 +131,33 @@ impl Provider for BedrockProvider {
     ) -> Result<(Message, ProviderUsage), ProviderError> {
         let model_name = &self.model.model_name;
 
+        let caching = self.supports_prompt_caching();
+        let mut bedrock_messages = messages
+            .iter()
+            .map(to_bedrock_message)
+            .collect::<Result<Vec<_>>>()?;
+        if caching {
+            add_cache_points(&mut bedrock_messages)?;
+        }
+
         let mut request = self
             .client
             .converse()
             .system(bedrock::SystemContentBlock::Text(system.to_string()))
             .model_id(model_name.to_string())
-            .set_messages(Some(
-                messages
-                    .iter()
-                    .map(to_bedrock_message)
-                    .collect::<Result<_>>()?,
-            ));
+            .set_messages(Some(bedrock_messages));
+        if caching {
+            request = request.system(bedrock::SystemContentBlock::CachePoint(cache_point()?));
+        }
 
         if !tools.is_empty() {
-            request = request.tool_config(to_bedrock_tool_config(tools)?);
+            let mut tool_config = to_bedrock_tool_config(tools)?;
+            if caching {
+                tool_config
+                    .tools
+                    .push(bedrock::Tool::CachePoint(cache_point()?));
+            }
+            request = request.tool_config(tool_config);
         }
 
         // Retry configuration
@@ -0,0 +1,351 @@
+//! Conformance checks for provider message formats
+//!
+//! A canonical set of conversations covering text, tool calls, tool errors, images and
+//! multi-part content is run through a provider format's request and response conversion,
+//! and compared against fixtures kept per format:
+//!
+//! - `<fixtures>/<format>/<case>.request.json` is the request created for the conversation.
+//!   A missing request fixture fails the check, so a format can't pass by recording its own
+//!   output. Fixtures are recorded with `GOOSE_RECORD_FIXTURES=1`, for a new format or after an
+//!   intended format change, and committed with it.
+//! - `<fixtures>/<format>/<case>.response.json` is a response as the provider returns it,
+//!   which has to convert to the last assistant message of the conversation. Response
+//!   fixtures are written by hand from the provider's documentation or a real response, and
+//!   cases without one are skipped.
+//!
+//! New providers can reuse the harness from their own tests:
+//!
+//! ```no_run
+//! use goose::model::ModelConfig;
+//! use goose::providers::conformance::{ConformanceHarness, FormatUnderTest};
+//! use goose::providers::formats::anthropic;
+//!
+//! let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string());
+//! let format = FormatUnderTest::new(
+//!     "anthropic",
+//!     move |messages| anthropic::create_request(&model_config, "system", messages, &[]),
+//!     anthropic::response_to_message,
+//! );
+//! ConformanceHarness::new("tests/fixtures/conformance").check(&format).unwrap();
+//! ```
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use mcp_core::tool::ToolCall;
+use mcp_core::{Content, Role, ToolError};
+use serde_json::{json, Value};
+
+use crate::message::{Message, MessageContent};
+
+/// A 1x1 transparent PNG
+const TINY_PNG: &str =
+    "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+/// A named conversation every provider format should handle
+#[derive(Debug, Clone)]
+pub struct ConformanceCase {
+    pub name: &'static str,
+    pub messages: Vec<Message>,
+}
+
+impl ConformanceCase {
+    fn new(name: &'static str, messages: Vec<Message>) -> Self {
+        Self { name, messages }
+    }
+
+    /// The assistant message a response fixture for this case converts to
+    pub fn expected_response(&self) -> Option<&Message> {
+        self.messages
+            .last()
+            .filter(|message| message.role == Role::Assistant)
+    }
+}
+
+fn shell_call(command: &str) -> ToolCall {
+    ToolCall::new("developer__shell", json!({ "command": command }))
+}
+
+/// The canonical conversations, in a stable order
+pub fn canonical_cases() -> Vec<ConformanceCase> {
+    vec![
+        ConformanceCase::new(
+            "text_reply",
+            vec![
+                Message::user().with_text("What is the capital of France?"),
+                Message::assistant().with_text("The capital of France is Paris."),
+            ],
+        ),
+        ConformanceCase::new(
+            "tool_request",
+            vec![
+                Message::user().with_text("Which files are in this directory?"),
+                Message::assistant()
+                    .with_text("Let me list them.")
+                    .with_tool_request("call_1", Ok(shell_call("ls"))),
+            ],
+        ),
+        ConformanceCase::new(
+            "tool_round_trip",
+            vec![
+                Message::user().with_text("Which files are in this directory?"),
+                Message::assistant()
+                    .with_text("Let me list them.")
+                    .with_tool_request("call_1", Ok(shell_call("ls"))),
+                Message::user()
+                    .with_tool_response("call_1", Ok(vec![Content::text("Cargo.toml\nsrc")])),
+                Message::assistant().with_text("There is a Cargo.toml and a src directory."),
+            ],
+        ),
+        ConformanceCase::new(
+            "tool_error",
+            vec![
+                Message::user().with_text("Read /etc/shadow"),
+                Message::assistant().with_tool_request("call_1", Ok(shell_call("cat /etc/shadow"))),
+                Message::user().with_tool_response(
+                    "call_1",
+                    Err(ToolError::ExecutionError("Permission denied".to_string())),
+                ),
+                Message::assistant().with_text("I don't have permission to read that file."),
+            ],
+        ),
+        ConformanceCase::new(
+            "image",
+            vec![
+                Message::user()
+                    .with_text("What is in this image?")
+                    .with_image(TINY_PNG, "image/png"),
+                Message::assistant().with_text("A single transparent pixel."),
+            ],
+        ),
+        ConformanceCase::new(
+            "multi_part",
+            vec![
+                Message::user().with_text("Show me the git status and the last commit."),
+                Message::assistant()
+                    .with_text("I'll check both.")
+                    .with_tool_request("call_1", Ok(shell_call("git status")))
+                    .with_tool_request("call_2", Ok(shell_call("git log -1"))),
+                Message::user()
+                    .with_tool_response("call_1", Ok(vec![Content::text("nothing to commit")]))
+                    .with_tool_response("call_2", Ok(vec![Content::text("commit 1a2b3c")])),
+                Message::assistant()
+                    .with_text("The tree is clean.")
+                    .with_text("The last commit is 1a2b3c."),
+            ],
+        ),
+    ]
+}
+
+type CreateRequestFn = dyn Fn(&[Message]) -> Result<Value> + Send + Sync;
+type ResponseToMessageFn = dyn Fn(Value) -> Result<Message> + Send + Sync;
+
+/// The request and response conversions of a provider format
+pub struct FormatUnderTest {
+    pub name: String,
+    create_request: Box<CreateRequestFn>,
+    response_to_message: Box<ResponseToMessageFn>,
+}
+
+impl FormatUnderTest {
+    pub fn new<C, R, E>(name: &str, create_request: C, response_to_message: R) -> Self
+    where
+        C: Fn(&[Message]) -> Result<Value, E> + Send + Sync + 'static,
+        R: Fn(Value) -> Result<Message> + Send + Sync + 'static,
+        E: Into<anyhow::Error>,
+    {
+        Self {
+            name: name.to_string(),
+            create_request: Box::new(move |messages: &[Message]| {
+                create_request(messages).map_err(Into::into)
+            }),
+            response_to_message: Box::new(response_to_message),
+        }
+    }
+}
+
+/// Message content reduced to what a provider is expected to preserve, with tool call ids
+/// numbered in order since some providers generate their own
+fn normalized_content(message: &Message) -> Value {
+    let mut tool_ids: Vec<String> = Vec::new();
+    let mut tool_index = |id: &str| match tool_ids.iter().position(|known| known == id) {
+        Some(index) => index,
+        None => {
+            tool_ids.push(id.to_string());
+            tool_ids.len() - 1
+        }
+    };
+    let content: Vec<Value> = message
+        .content
+        .iter()
+        .map(|content| match content {
+            MessageContent::Text(text) => json!({ "text": text.text }),
+            MessageContent::Image(image) => json!({ "image": image.mime_type }),
+            MessageContent::ToolRequest(request) => match &request.tool_call {
+                Ok(call) => json!({
+                    "tool_request": tool_index(&request.id),
+                    "name": call.name,
+                    "arguments": call.arguments,
+                }),
+                Err(e) => {
+                    json!({ "tool_request": tool_index(&request.id), "error": e.to_string() })
+                }
+            },
+            MessageContent::ToolResponse(response) => json!({
+                "tool_response": tool_index(&response.id),
+                "is_error": response.tool_result.is_err(),
+            }),
+            other => serde_json::to_value(other).unwrap_or(Value::Null),
+        })
+        .collect();
+    json!({ "role": message.role, "content": content })
+}
+
+/// Runs the canonical cases against the fixtures in a directory
+pub struct ConformanceHarness {
+    fixtures_dir: PathBuf,
+    record: bool,
+}
+
+impl ConformanceHarness {
+    pub fn new<P: Into<PathBuf>>(fixtures_dir: P) -> Self {
+        Self {
+            fixtures_dir: fixtures_dir.into(),
+            record: std::env::var("GOOSE_RECORD_FIXTURES").is_ok_and(|v| v == "1"),
+        }
+    }
+
+    fn fixture_path(&self, format: &FormatUnderTest, case: &str, kind: &str) -> PathBuf {
+        self.fixtures_dir
+            .join(&format.name)
+            .join(format!("{}.{}.json", case, kind))
+    }
+
+    fn read_fixture(path: &Path) -> Result<Value> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read fixture {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("Invalid JSON in fixture {}", path.display()))
+    }
+
+    fn record_fixture(path: &Path, value: &Value) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(value)? + "\n")
+            .with_context(|| format!("Failed to record fixture {}", path.display()))
+    }
+
+    /// Check one case, returning a description of the mismatch if it does not conform
+    fn check_case(&self, format: &FormatUnderTest, case: &ConformanceCase) -> Result<Vec<String>> {
+        let mut failures = Vec::new();
+
+        let request = (format.create_request)(&case.messages)
+            .with_context(|| format!("{}: failed to create request", case.name))?;
+        let request_path = self.fixture_path(format, case.name, "request");
+        if self.record {
+            Self::record_fixture(&request_path, &request)?;
+        } else if !request_path.exists() {
+            failures.push(format!(
+                "{}: no request fixture at {}, record it with GOOSE_RECORD_FIXTURES=1",
+                case.name,
+                request_path.display()
+            ));
+        } else if Self::read_fixture(&request_path)? != request {
+            failures.push(format!(
+                "{}: request differs from {}:\n{}",
+                case.name,
+                request_path.display(),
+                serde_json::to_string_pretty(&request)?
+            ));
+        }
+
+        let response_path = self.fixture_path(format, case.name, "response");
+        if let (Some(expected), true) = (case.expected_response(), response_path.exists()) {
+            let response = Self::read_fixture(&response_path)?;
+            let message = (format.response_to_message)(response)
+                .with_context(|| format!("{}: failed to convert response", case.name))?;
+            let (actual, expected) = (normalized_content(&message), normalized_content(expected));
+            if actual != expected {
+                failures.push(format!(
+                    "{}: response converted to {} but expected {}",
+                    case.name, actual, expected
+                ));
+            }
+        }
+
+        Ok(failures)
+    }
+
+    /// Check every canonical case, reporting all mismatches together
+    pub fn check(&self, format: &FormatUnderTest) -> Result<()> {
+        let mut failures = Vec::new();
+        for case in canonical_cases() {
+            match self.check_case(format, &case) {
+                Ok(case_failures) => failures.extend(case_failures),
+                Err(e) => failures.push(format!("{:#}", e)),
+            }
+        }
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "{} does not conform:\n{}",
+                format.name,
+                failures.join("\n\n")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalized_content_ignores_tool_ids() {
+        let a = Message::assistant()
+            .with_text("Let me list them.")
+            .with_tool_request("call_1", Ok(shell_call("ls")));
+        let b = Message::assistant()
+            .with_text("Let me list them.")
+            .with_tool_request("toolu_01", Ok(shell_call("ls")));
+        assert_eq!(normalized_content(&a), normalized_content(&b));
+
+        let c = Message::assistant()
+            .with_text("Let me list them.")
+            .with_tool_request("toolu_01", Ok(shell_call("ls -a")));
+        assert_ne!(normalized_content(&a), normalized_content(&c));
+    }
+
+    #[test]
+    fn test_requests_are_recorded_then_compared() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut harness = ConformanceHarness {
+            fixtures_dir: dir.path().to_path_buf(),
+            record: false,
+        };
+        let echo = FormatUnderTest::new(
+            "echo",
+            |messages: &[Message]| -> Result<Value> { Ok(json!(messages.len())) },
+            |_| Ok(Message::assistant()),
+        );
+        let error = harness.check(&echo).unwrap_err().to_string();
+        assert!(error.contains("text_reply: no request fixture"));
+        assert!(!dir.path().join("echo/text_reply.request.json").exists());
+
+        harness.record = true;
+        harness.check(&echo).unwrap();
+        assert!(dir.path().join("echo/text_reply.request.json").exists());
+        harness.record = false;
+        harness.check(&echo).unwrap();
+
+        let changed = FormatUnderTest::new(
+            "echo",
+            |messages: &[Message]| -> Result<Value> { Ok(json!(messages.len() + 1)) },
+            |_| Ok(Message::assistant()),
+        );
+        let error = harness.check(&changed).unwrap_err().to_string();
+        assert!(error.contains("text_reply: request differs"));
+    }
+}
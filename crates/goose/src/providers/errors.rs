@@ -23,6 +23,9 @@ pub enum ProviderError {
 
     #[error("Usage data error: {0}")]
     UsageError(String),
+
+    #[error("Request too large: {0}")]
+    PayloadTooLarge(String),
 }
 
 impl From<anyhow::Error> for ProviderError {
@@ -2,18 +2,26 @@ use std::sync::Arc;
 
 use super::{
     anthropic::AnthropicProvider,
+    attribution::AttributedProvider,
     azure::AzureProvider,
     base::{Provider, ProviderMetadata},
     bedrock::BedrockProvider,
     databricks::DatabricksProvider,
+    fallback::{FallbackProvider, FallbackTarget},
     gcpvertexai::GcpVertexAIProvider,
     githubcopilot::GithubCopilotProvider,
     google::GoogleProvider,
     groq::GroqProvider,
-    lead_worker::LeadWorkerProvider,
+    lead_worker::{DensityRouting, LeadWorkerProvider},
+    model_aliases::ModelAliases,
     ollama::OllamaProvider,
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
+    outbound_filter::FilteredProvider,
+    payload_guard::PayloadGuardProvider,
+    pii::PiiProvider,
+    rate_limit::RateLimitedProvider,
+    retry::RetryingProvider,
     sagemaker_tgi::SageMakerTgiProvider,
     snowflake::SnowflakeProvider,
     venice::VeniceProvider,
@@ -37,7 +45,8 @@ fn default_fallback_turns() -> usize {
 }
 
 pub fn providers() -> Vec<ProviderMetadata> {
-    vec![
+    let aliases = ModelAliases::from_config();
+    let mut providers = vec![
         AnthropicProvider::metadata(),
         AzureProvider::metadata(),
         BedrockProvider::metadata(),
@@ -52,21 +61,61 @@ pub fn providers() -> Vec<ProviderMetadata> {
         SageMakerTgiProvider::metadata(),
         VeniceProvider::metadata(),
         SnowflakeProvider::metadata(),
-    ]
+    ];
+    for metadata in &mut providers {
+        aliases.extend_metadata(metadata);
+    }
+    providers
+}
+
+/// A provider for another model of the configured provider
+pub fn create_for_model(model: &str) -> Result<Arc<dyn Provider>> {
+    let name: String = crate::config::Config::global().get_param("GOOSE_PROVIDER")?;
+    create(&name, ModelConfig::new(model.to_string()))
 }
 
 pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
 
+    // Model aliases from config pick their provider and underlying model
+    let (name, model) = ModelAliases::from_config().resolve(name, model);
+    let name = name.as_str();
+
     // Check for lead model environment variables
-    if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
+    let provider = if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
 
-        return create_lead_worker_from_env(name, &model, &lead_model_name);
-    }
+        create_lead_worker_from_env(name, &model, &lead_model_name)?
+    } else {
+        // Default: create regular provider
+        create_provider(name, model)?
+    };
+
+    // Unavailable providers hand their requests to the configured fallbacks
+    let provider = create_fallbacks_from_config(provider)?;
+
+    with_request_filters(name, provider)
+}
+
+/// Wrap a provider in the configured size limits, outbound filters and PII protection, which
+/// apply to every request whichever provider it goes to
+pub fn with_request_filters(name: &str, provider: Arc<dyn Provider>) -> Result<Arc<dyn Provider>> {
+    // Size limits apply to the request as it is finally sent, after the filters
+    let provider = PayloadGuardProvider::wrap_from_config(name, provider);
+
+    // Redact or block configured patterns before anything is sent
+    let provider = FilteredProvider::wrap_from_config(provider)?;
 
-    // Default: create regular provider
-    create_provider(name, model)
+    // Personal information is swapped for placeholders before the filters see the request
+    Ok(PiiProvider::wrap_from_config(provider))
+}
+
+/// A provider for exactly this provider and model, with its rate limits and retries but
+/// without the lead/worker model, fallbacks or request filters that [`create`] adds from the
+/// config. For callers that name the provider themselves.
+pub fn create_bare(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    let (name, model) = ModelAliases::from_config().resolve(name, model);
+    create_provider(&name, model)
 }
 
 /// Create a lead/worker provider from environment variables
@@ -93,8 +142,11 @@ fn create_lead_worker_from_env(
         .get_param::<usize>("GOOSE_LEAD_FALLBACK_TURNS")
         .unwrap_or(default_fallback_turns());
 
-    // Create model configs
-    let lead_model_config = ModelConfig::new(lead_model_name.to_string());
+    // Create model configs, the lead model may be an alias too
+    let (lead_provider_name, lead_model_config) = ModelAliases::from_config().resolve(
+        &lead_provider_name,
+        ModelConfig::new(lead_model_name.to_string()),
+    );
     let worker_model_config = default_model.clone();
 
     // Create the providers
@@ -102,34 +154,97 @@ fn create_lead_worker_from_env(
     let worker_provider = create_provider(default_provider_name, worker_model_config)?;
 
     // Create the lead/worker provider with configured settings
-    Ok(Arc::new(LeadWorkerProvider::new_with_settings(
+    let mut provider = LeadWorkerProvider::new_with_settings(
         lead_provider,
         worker_provider,
         lead_turns,
         failure_threshold,
         fallback_turns,
-    )))
+    );
+
+    // Optionally route later turns by tool call density
+    if config
+        .get_param::<String>("GOOSE_LEAD_ROUTING")
+        .is_ok_and(|routing| routing.eq_ignore_ascii_case("density"))
+    {
+        let defaults = DensityRouting::default();
+        provider = provider.with_density_routing(DensityRouting {
+            window: config
+                .get_param("GOOSE_LEAD_DENSITY_WINDOW")
+                .unwrap_or(defaults.window),
+            worker_threshold: config
+                .get_param("GOOSE_LEAD_DENSITY_WORKER_THRESHOLD")
+                .unwrap_or(defaults.worker_threshold),
+            lead_threshold: config
+                .get_param("GOOSE_LEAD_DENSITY_LEAD_THRESHOLD")
+                .unwrap_or(defaults.lead_threshold),
+        });
+    }
+
+    Ok(Arc::new(provider))
+}
+
+/// Wrap the provider in the fallback chain of `GOOSE_PROVIDER_FALLBACKS`, if there is one
+fn create_fallbacks_from_config(provider: Arc<dyn Provider>) -> Result<Arc<dyn Provider>> {
+    let targets: Vec<FallbackTarget> = crate::config::Config::global()
+        .get_param("GOOSE_PROVIDER_FALLBACKS")
+        .unwrap_or_default();
+    if targets.is_empty() {
+        return Ok(provider);
+    }
+
+    // A fallback that cannot be set up is left out rather than failing the provider itself
+    let aliases = ModelAliases::from_config();
+    let fallbacks: Vec<Arc<dyn Provider>> = targets
+        .into_iter()
+        .filter_map(|target| {
+            let (name, model) =
+                aliases.resolve(&target.provider, ModelConfig::new(target.model.clone()));
+            create_provider(&name, model)
+                .map_err(|e| {
+                    tracing::warn!(
+                        "Skipping fallback provider {} with model {}: {}",
+                        target.provider,
+                        target.model,
+                        e
+                    )
+                })
+                .ok()
+        })
+        .collect();
+    if fallbacks.is_empty() {
+        return Ok(provider);
+    }
+    Ok(Arc::new(FallbackProvider::new(provider, fallbacks)))
 }
 
 fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     // We use Arc instead of Box to be able to clone for multiple async tasks
-    match name {
-        "openai" => Ok(Arc::new(OpenAiProvider::from_env(model)?)),
-        "anthropic" => Ok(Arc::new(AnthropicProvider::from_env(model)?)),
-        "azure_openai" => Ok(Arc::new(AzureProvider::from_env(model)?)),
-        "aws_bedrock" => Ok(Arc::new(BedrockProvider::from_env(model)?)),
-        "databricks" => Ok(Arc::new(DatabricksProvider::from_env(model)?)),
-        "groq" => Ok(Arc::new(GroqProvider::from_env(model)?)),
-        "ollama" => Ok(Arc::new(OllamaProvider::from_env(model)?)),
-        "openrouter" => Ok(Arc::new(OpenRouterProvider::from_env(model)?)),
-        "gcp_vertex_ai" => Ok(Arc::new(GcpVertexAIProvider::from_env(model)?)),
-        "google" => Ok(Arc::new(GoogleProvider::from_env(model)?)),
-        "sagemaker_tgi" => Ok(Arc::new(SageMakerTgiProvider::from_env(model)?)),
-        "venice" => Ok(Arc::new(VeniceProvider::from_env(model)?)),
-        "snowflake" => Ok(Arc::new(SnowflakeProvider::from_env(model)?)),
-        "github_copilot" => Ok(Arc::new(GithubCopilotProvider::from_env(model)?)),
-        _ => Err(anyhow::anyhow!("Unknown provider: {}", name)),
-    }
+    let provider: Arc<dyn Provider> = match name {
+        "openai" => Arc::new(OpenAiProvider::from_env(model)?),
+        "anthropic" => Arc::new(AnthropicProvider::from_env(model)?),
+        "azure_openai" => Arc::new(AzureProvider::from_env(model)?),
+        "aws_bedrock" => Arc::new(BedrockProvider::from_env(model)?),
+        "databricks" => Arc::new(DatabricksProvider::from_env(model)?),
+        "groq" => Arc::new(GroqProvider::from_env(model)?),
+        "ollama" => Arc::new(OllamaProvider::from_env(model)?),
+        "openrouter" => Arc::new(OpenRouterProvider::from_env(model)?),
+        "gcp_vertex_ai" => Arc::new(GcpVertexAIProvider::from_env(model)?),
+        "google" => Arc::new(GoogleProvider::from_env(model)?),
+        "sagemaker_tgi" => Arc::new(SageMakerTgiProvider::from_env(model)?),
+        "venice" => Arc::new(VeniceProvider::from_env(model)?),
+        "snowflake" => Arc::new(SnowflakeProvider::from_env(model)?),
+        "github_copilot" => Arc::new(GithubCopilotProvider::from_env(model)?),
+        _ => return Err(anyhow::anyhow!("Unknown provider: {}", name)),
+    };
+
+    // Each attempt waits its turn under the rate limits, and rate limits and server errors
+    // are retried before any fallback takes over
+    let provider = RateLimitedProvider::wrap_from_config(name, provider);
+    let provider = RetryingProvider::wrap_from_config(name, provider);
+
+    // Usage names the provider that answered, so it is priced at that provider's costs
+    Ok(AttributedProvider::wrap(name, provider))
 }
 
 #[cfg(test)]
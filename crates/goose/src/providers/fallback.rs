@@ -0,0 +1,297 @@
+//! A chain of providers that moves on to the next one when a provider is unavailable
+//!
+//! `GOOSE_PROVIDER_FALLBACKS` lists the providers to try, in order, after the configured one:
+//!
+//! ```yaml
+//! GOOSE_PROVIDER_FALLBACKS:
+//!   - provider: anthropic
+//!     model: claude-sonnet-4
+//!   - provider: ollama
+//!     model: qwen2.5
+//! ```
+//!
+//! Every request starts with the configured provider, so the chain returns to it as soon as it
+//! answers again. Only failures that another provider may not have are passed down the chain:
+//! rate limits, server errors and authentication failures. A request that is too long or
+//! malformed fails the same way everywhere, so its error is returned as is. Batches go to the
+//! configured provider only, as a submitted batch job cannot move to another provider.
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use mcp_core::tool::Tool;
+use serde::{Deserialize, Serialize};
+
+use super::base::{
+    FallbackProviderTrait, MessageStream, Provider, ProviderMetadata, ProviderUsage,
+};
+use super::batch::BatchProviderTrait;
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// A provider and model of the fallback chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FallbackTarget {
+    pub provider: String,
+    pub model: String,
+}
+
+/// Whether another provider may succeed where this error happened
+pub fn should_fall_back(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::RateLimitExceeded(_)
+            | ProviderError::ServerError(_)
+            | ProviderError::Authentication(_)
+    )
+}
+
+pub struct FallbackProvider {
+    providers: Vec<Arc<dyn Provider>>,
+    /// The provider that answered the last request, and whether it differs from the one before
+    answered: Mutex<(usize, Option<String>)>,
+}
+
+impl FallbackProvider {
+    pub fn new(primary: Arc<dyn Provider>, fallbacks: Vec<Arc<dyn Provider>>) -> Self {
+        let mut providers = vec![primary];
+        providers.extend(fallbacks);
+        Self {
+            providers,
+            answered: Mutex::new((0, None)),
+        }
+    }
+
+    fn record_answer(&self, index: usize) {
+        let mut answered = self.answered.lock().unwrap();
+        if answered.0 != index {
+            let model = self.providers[index].get_model_config().model_name;
+            *answered = (index, Some(model));
+        }
+    }
+
+    /// Try each provider in turn until one answers or fails in a way the next would too
+    async fn first_answer<'a, T>(
+        &'a self,
+        request: impl Fn(&'a Arc<dyn Provider>) -> BoxFuture<'a, Result<T, ProviderError>>,
+    ) -> Result<T, ProviderError> {
+        let mut index = 0;
+        loop {
+            let provider = &self.providers[index];
+            match request(provider).await {
+                Ok(answer) => {
+                    self.record_answer(index);
+                    return Ok(answer);
+                }
+                Err(e) if should_fall_back(&e) && index + 1 < self.providers.len() => {
+                    tracing::warn!(
+                        "Provider for model {} failed, falling back to model {}: {}",
+                        provider.get_model_config().model_name,
+                        self.providers[index + 1].get_model_config().model_name,
+                        e
+                    );
+                    index += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl FallbackProviderTrait for FallbackProvider {
+    fn take_switch(&self) -> Option<(String, bool)> {
+        let mut answered = self.answered.lock().unwrap();
+        let index = answered.0;
+        answered.1.take().map(|model| (model, index > 0))
+    }
+}
+
+#[async_trait]
+impl Provider for FallbackProvider {
+    fn metadata() -> ProviderMetadata {
+        // A wrapper provider, configured through the providers it falls back to
+        ProviderMetadata::new(
+            "fallback",
+            "Fallback Provider",
+            "A provider that moves on to the next provider when one is unavailable",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.first_answer(|provider| Box::pin(provider.complete(system, messages, tools)))
+            .await
+    }
+
+    /// Only a failure to start the stream falls back; a stream that breaks off is not retried
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        self.first_answer(|provider| {
+            let stream = provider.complete_streaming(system, messages, tools);
+            Box::pin(stream)
+        })
+        .await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.providers[0].supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.providers[0].get_model_config()
+    }
+
+    /// The fallbacks keep their own models
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::new(
+            self.providers[0].with_model_override(update)?,
+            self.providers[1..].to_vec(),
+        )))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.providers[0].fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.providers[0].supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.providers[0].supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.providers[0].create_embeddings(texts).await
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        Some(self)
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.providers[0].as_batch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::attribution::AttributedProvider;
+    use crate::providers::base::Usage;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct MockProvider {
+        model: &'static str,
+        failure: Mutex<Option<ProviderError>>,
+        called: AtomicBool,
+    }
+
+    impl MockProvider {
+        fn new(model: &'static str, failure: Option<ProviderError>) -> Arc<Self> {
+            Arc::new(Self {
+                model,
+                failure: Mutex::new(failure),
+                called: AtomicBool::new(false),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new(self.model.to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.called.store(true, Ordering::SeqCst);
+            match self.failure.lock().unwrap().take() {
+                Some(e) => Err(e),
+                None => Ok((
+                    Message::assistant().with_text(self.model),
+                    ProviderUsage::new(self.model.to_string(), Usage::default()),
+                )),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_rate_limit_and_returns_to_primary() {
+        let primary = MockProvider::new(
+            "primary",
+            Some(ProviderError::RateLimitExceeded("slow down".to_string())),
+        );
+        let backup = MockProvider::new("backup", None);
+        let provider = FallbackProvider::new(primary, vec![backup]);
+
+        let (_, usage) = provider.complete("", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "backup");
+        assert_eq!(provider.take_switch(), Some(("backup".to_string(), true)));
+        assert_eq!(provider.take_switch(), None);
+
+        // The primary answers again on the next request
+        let (_, usage) = provider.complete("", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "primary");
+        assert_eq!(provider.take_switch(), Some(("primary".to_string(), false)));
+    }
+
+    #[tokio::test]
+    async fn test_usage_names_the_provider_that_answered() {
+        let primary = MockProvider::new(
+            "primary",
+            Some(ProviderError::ServerError("down".to_string())),
+        );
+        let backup = MockProvider::new("backup", None);
+        let provider = FallbackProvider::new(
+            AttributedProvider::wrap("openai", primary),
+            vec![AttributedProvider::wrap("anthropic", backup)],
+        );
+
+        let (_, usage) = provider.complete("", &[], &[]).await.unwrap();
+        assert_eq!(usage.provider.as_deref(), Some("anthropic"));
+    }
+
+    #[tokio::test]
+    async fn test_errors_every_provider_shares_are_returned() {
+        let primary = MockProvider::new(
+            "primary",
+            Some(ProviderError::ContextLengthExceeded("too long".to_string())),
+        );
+        let backup = MockProvider::new("backup", None);
+        let provider = FallbackProvider::new(primary, vec![backup.clone()]);
+
+        let result = provider.complete("", &[], &[]).await;
+        assert!(matches!(
+            result,
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        assert!(!backup.called.load(Ordering::SeqCst));
+        assert_eq!(provider.take_switch(), None);
+    }
+}
@@ -2,6 +2,10 @@ use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
+use crate::providers::formats::cache;
+use crate::providers::utils::{convert_image, ImageFormat};
+use crate::providers::web_search::{sources_text, Citation};
+use crate::tool_errors::tool_error_text;
 use anyhow::{anyhow, Result};
 use mcp_core::content::Content;
 use mcp_core::role::Role;
@@ -39,8 +43,8 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                         }));
                     }
                 }
-                MessageContent::ToolResponse(tool_response) => {
-                    if let Ok(result) = &tool_response.tool_result {
+                MessageContent::ToolResponse(tool_response) => match &tool_response.tool_result {
+                    Ok(result) => {
                         let text = result
                             .iter()
                             .filter_map(|c| match c {
@@ -56,7 +60,15 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                             "content": text
                         }));
                     }
-                }
+                    Err(e) => {
+                        content.push(json!({
+                            "type": "tool_result",
+                            "tool_use_id": tool_response.id,
+                            "content": tool_error_text(e),
+                            "is_error": true
+                        }));
+                    }
+                },
                 MessageContent::ToolConfirmationRequest(_tool_confirmation_request) => {
                     // Skip tool confirmation requests
                 }
@@ -66,6 +78,10 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SummarizationRequested(_) => {
                     // Skip
                 }
+                MessageContent::Audio(_) => {
+                    // Skip, audio input is transcribed before it reaches the provider and
+                    // spoken output is only for the frontend
+                }
                 MessageContent::Thinking(thinking) => {
                     content.push(json!({
                         "type": "thinking",
@@ -79,7 +95,9 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                         "data": redacted.data
                     }));
                 }
-                MessageContent::Image(_) => continue, // Anthropic doesn't support image content yet
+                MessageContent::Image(image) => {
+                    content.push(convert_image(image, &ImageFormat::Anthropic));
+                }
                 MessageContent::FrontendToolRequest(tool_request) => {
                     if let Ok(tool_call) = &tool_request.tool_call {
                         content.push(json!({
@@ -113,29 +131,7 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
         }));
     }
 
-    // Add "cache_control" to the last and second-to-last "user" messages.
-    // During each turn, we mark the final message with cache_control so the conversation can be
-    // incrementally cached. The second-to-last user message is also marked for caching with the
-    // cache_control parameter, so that this checkpoint can read from the previous cache.
-    let mut user_count = 0;
-    for message in anthropic_messages.iter_mut().rev() {
-        if message.get("role") == Some(&json!("user")) {
-            if let Some(content) = message.get_mut("content") {
-                if let Some(content_array) = content.as_array_mut() {
-                    if let Some(last_content) = content_array.last_mut() {
-                        last_content
-                            .as_object_mut()
-                            .unwrap()
-                            .insert("cache_control".to_string(), json!({ "type": "ephemeral" }));
-                    }
-                }
-            }
-            user_count += 1;
-            if user_count >= 2 {
-                break;
-            }
-        }
-    }
+    cache::mark_anthropic_messages(&mut anthropic_messages);
 
     anthropic_messages
 }
@@ -155,14 +151,7 @@ pub fn format_tools(tools: &[Tool]) -> Vec<Value> {
         }
     }
 
-    // Add "cache_control" to the last tool spec, if any. This means that all tool definitions,
-    // will be cached as a single prefix.
-    if let Some(last_tool) = tool_specs.last_mut() {
-        last_tool
-            .as_object_mut()
-            .unwrap()
-            .insert("cache_control".to_string(), json!({ "type": "ephemeral" }));
-    }
+    cache::mark_last_tool(&mut tool_specs);
 
     tool_specs
 }
@@ -172,7 +161,7 @@ pub fn format_system(system: &str) -> Value {
     json!([{
         "type": "text",
         "text": system,
-        "cache_control": { "type": "ephemeral" }
+        "cache_control": cache::cache_control()
     }])
 }
 
@@ -184,6 +173,7 @@ pub fn response_to_message(response: Value) -> Result<Message> {
         .ok_or_else(|| anyhow!("Invalid response format: missing content array"))?;
 
     let mut message = Message::assistant();
+    let mut citations = Vec::new();
 
     for block in content_blocks {
         match block.get("type").and_then(|t| t.as_str()) {
@@ -191,6 +181,7 @@ pub fn response_to_message(response: Value) -> Result<Message> {
                 if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
                     message = message.with_text(text.to_string());
                 }
+                citations.extend(web_search_citations(block));
             }
             Some("tool_use") => {
                 let id = block
@@ -230,9 +221,46 @@ pub fn response_to_message(response: Value) -> Result<Message> {
         }
     }
 
+    if let Some(sources) = sources_text(&citations) {
+        message = message.with_text(sources);
+    }
+
     Ok(message)
 }
 
+/// The web pages a text block cites, from the search run by the web search server tool
+fn web_search_citations(block: &Value) -> Vec<Citation> {
+    block
+        .get("citations")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|c| c.get("type").and_then(|t| t.as_str()) == Some("web_search_result_location"))
+        .filter_map(|c| {
+            let url = c.get("url").and_then(|u| u.as_str())?;
+            Some(Citation::new(url, c.get("title").and_then(|t| t.as_str())))
+        })
+        .collect()
+}
+
+/// Let the model search the web with Anthropic's web search server tool
+pub fn add_web_search_tool(payload: &mut Value, max_uses: Option<u32>) {
+    let mut tool = json!({
+        "type": "web_search_20250305",
+        "name": "web_search",
+    });
+    if let Some(max_uses) = max_uses {
+        tool["max_uses"] = json!(max_uses);
+    }
+    let payload = payload.as_object_mut().unwrap();
+    match payload.get_mut("tools").and_then(|t| t.as_array_mut()) {
+        Some(tools) => tools.push(tool),
+        None => {
+            payload.insert("tools".to_string(), json!([tool]));
+        }
+    }
+}
+
 /// Extract usage information from Anthropic's API response
 pub fn get_usage(data: &Value) -> Result<Usage> {
     // Extract usage data if available
@@ -241,18 +269,18 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
         // - input_tokens (fresh/uncached)
         // - cache_creation_input_tokens (being written to cache)
         // - cache_read_input_tokens (read from cache)
+        let cache_write_tokens = usage
+            .get("cache_creation_input_tokens")
+            .and_then(|v| v.as_u64());
+        let cache_read_tokens = usage
+            .get("cache_read_input_tokens")
+            .and_then(|v| v.as_u64());
         let total_input_tokens = usage
             .get("input_tokens")
             .and_then(|v| v.as_u64())
             .unwrap_or(0)
-            + usage
-                .get("cache_creation_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0)
-            + usage
-                .get("cache_read_input_tokens")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0);
+            + cache_write_tokens.unwrap_or(0)
+            + cache_read_tokens.unwrap_or(0);
 
         let input_tokens = Some(total_input_tokens as i32);
 
@@ -263,7 +291,12 @@ pub fn get_usage(data: &Value) -> Result<Usage> {
 
         let total_tokens = output_tokens.map(|o| total_input_tokens as i32 + o);
 
-        Ok(Usage::new(input_tokens, output_tokens, total_tokens))
+        Ok(
+            Usage::new(input_tokens, output_tokens, total_tokens).with_cache_tokens(
+                cache_read_tokens.map(|v| v as i32),
+                cache_write_tokens.map(|v| v as i32),
+            ),
+        )
     } else {
         tracing::debug!(
             "Failed to get usage data: {}",
@@ -326,6 +359,16 @@ pub fn create_request(
         }
     }
 
+    // Add top_p under the same restriction as temperature
+    if let Some(top_p) = model_config.top_p {
+        if !model_config.model_name.starts_with("claude-3-7-sonnet-") {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("top_p".to_string(), json!(top_p));
+        }
+    }
+
     // Add thinking parameters for claude-3-7-sonnet model
     let is_thinking_enabled = std::env::var("CLAUDE_THINKING_ENABLED").is_ok();
     if model_config.model_name.starts_with("claude-3-7-sonnet-") && is_thinking_enabled {
@@ -394,6 +437,56 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_web_search_response() -> Result<()> {
+        let response = json!({
+            "content": [
+                {"type": "text", "text": "I'll search for that."},
+                {
+                    "type": "server_tool_use",
+                    "id": "srvtoolu_1",
+                    "name": "web_search",
+                    "input": {"query": "rust 1.80 release date"}
+                },
+                {
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_1",
+                    "content": [{
+                        "type": "web_search_result",
+                        "url": "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html",
+                        "title": "Announcing Rust 1.80.0",
+                        "encrypted_content": "abc"
+                    }]
+                },
+                {
+                    "type": "text",
+                    "text": "Rust 1.80 was released on July 25, 2024.",
+                    "citations": [{
+                        "type": "web_search_result_location",
+                        "url": "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html",
+                        "title": "Announcing Rust 1.80.0",
+                        "cited_text": "The Rust team is happy to announce a new version of Rust, 1.80.0.",
+                        "encrypted_index": "def"
+                    }]
+                }
+            ],
+            "usage": {"input_tokens": 10, "output_tokens": 20}
+        });
+
+        let message = response_to_message(response)?;
+        assert_eq!(message.content.len(), 3);
+        assert!(!message.is_tool_call());
+        assert!(message.as_concat_text().ends_with(
+            "Sources:\n1. [Announcing Rust 1.80.0](https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html)"
+        ));
+
+        let mut payload = json!({"tools": [{"name": "calculator"}]});
+        add_web_search_tool(&mut payload, Some(3));
+        assert_eq!(payload["tools"][1]["type"], "web_search_20250305");
+        assert_eq!(payload["tools"][1]["max_uses"], 3);
+        Ok(())
+    }
+
     #[test]
     fn test_parse_tool_response() -> Result<()> {
         let response = json!({
@@ -9,7 +9,9 @@ use mcp_core::{Content, ResourceContents, Role, Tool, ToolCall, ToolError, ToolR
 use serde_json::Value;
 
 use super::super::base::Usage;
+use super::cache;
 use crate::message::{Message, MessageContent};
+use crate::tool_errors::tool_error_text;
 
 pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
     bedrock::Message::builder()
@@ -18,6 +20,9 @@ pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
             message
                 .content
                 .iter()
+                // Audio input is transcribed before it reaches the provider, and the spoken
+                // output attached to assistant messages is only for the frontend
+                .filter(|content| content.as_audio().is_none())
                 .map(to_bedrock_message_content)
                 .collect::<Result<_>>()?,
         ))
@@ -48,6 +53,9 @@ pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::C
         MessageContent::SummarizationRequested(_) => {
             bail!("SummarizationRequested should not get passed to the provider")
         }
+        MessageContent::Audio(_) => {
+            bail!("Audio is not sent to Bedrock")
+        }
         MessageContent::ToolRequest(tool_req) => {
             let tool_use_id = tool_req.id.to_string();
             let tool_use = if let Ok(call) = tool_req.tool_call.as_ref() {
@@ -91,12 +99,14 @@ pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::C
                         .map(|c| to_bedrock_tool_result_content_block(&tool_res.id, c))
                         .collect::<Result<_>>()?,
                 ),
-                Err(_) => None,
+                Err(e) => Some(vec![bedrock::ToolResultContentBlock::Text(
+                    tool_error_text(e),
+                )]),
             };
             bedrock::ContentBlock::ToolResult(
                 bedrock::ToolResultBlock::builder()
                     .tool_use_id(tool_res.id.to_string())
-                    .status(if content.is_some() {
+                    .status(if tool_res.tool_result.is_ok() {
                         bedrock::ToolResultStatus::Success
                     } else {
                         bedrock::ToolResultStatus::Error
@@ -144,6 +154,25 @@ pub fn to_bedrock_tool_config(tools: &[Tool]) -> Result<bedrock::ToolConfigurati
         .build()?)
 }
 
+/// A point in a request up to which Bedrock caches it
+pub fn cache_point() -> Result<bedrock::CachePointBlock> {
+    Ok(bedrock::CachePointBlock::builder()
+        .r#type(bedrock::CachePointType::Default)
+        .build()?)
+}
+
+/// Add cache points to the messages, after each user message that is a cache breakpoint
+pub fn add_cache_points(messages: &mut [bedrock::Message]) -> Result<()> {
+    for index in cache::user_breakpoints(messages, |message| {
+        message.role == bedrock::ConversationRole::User
+    }) {
+        messages[index]
+            .content
+            .push(bedrock::ContentBlock::CachePoint(cache_point()?));
+    }
+    Ok(())
+}
+
 pub fn to_bedrock_tool(tool: &Tool) -> Result<bedrock::Tool> {
     Ok(bedrock::Tool::ToolSpec(
         bedrock::ToolSpecification::builder()
@@ -286,10 +315,16 @@ pub fn from_bedrock_role(role: &bedrock::ConversationRole) -> Result<Role> {
 }
 
 pub fn from_bedrock_usage(usage: &bedrock::TokenUsage) -> Usage {
+    // Bedrock counts the input tokens read from and written to the cache apart from the others
+    let input_tokens = usage.input_tokens
+        + usage.cache_read_input_tokens.unwrap_or(0)
+        + usage.cache_write_input_tokens.unwrap_or(0);
     Usage {
-        input_tokens: Some(usage.input_tokens),
+        input_tokens: Some(input_tokens),
         output_tokens: Some(usage.output_tokens),
         total_tokens: Some(usage.total_tokens),
+        cache_read_input_tokens: usage.cache_read_input_tokens,
+        cache_write_input_tokens: usage.cache_write_input_tokens,
     }
 }
 
@@ -315,3 +350,20 @@ pub fn from_bedrock_json(document: &Document) -> Result<Value> {
         ),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spoken_audio_is_not_sent() {
+        let message = Message::assistant()
+            .with_text("The tests pass.")
+            .with_audio("UklGRg==", "audio/wav");
+        let converted = to_bedrock_message(&message).unwrap();
+        assert_eq!(
+            converted.content(),
+            &[bedrock::ContentBlock::Text("The tests pass.".to_string())]
+        );
+    }
+}
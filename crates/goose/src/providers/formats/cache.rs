@@ -0,0 +1,137 @@
+//! Prompt caching breakpoints shared by the provider formats
+//!
+//! Providers that cache prompts cache a request up to each breakpoint it marks, so the next
+//! request that starts with the same prefix is read from the cache at a fraction of the cost.
+//! Goose marks the system prompt, the last tool definition, which caches all of them as one
+//! prefix, and the last two user messages: the last one so the conversation so far is cached
+//! for the next turn, and the one before it so this turn reads what the previous turn cached.
+use serde_json::{json, Value};
+
+/// User messages marked as breakpoints, counting back from the last
+const CACHED_USER_MESSAGES: usize = 2;
+
+/// The breakpoint marker of the Anthropic API, which OpenRouter also passes on to Anthropic
+pub fn cache_control() -> Value {
+    json!({ "type": "ephemeral" })
+}
+
+/// The positions of the messages to mark as breakpoints, last first
+pub fn user_breakpoints<T>(messages: &[T], is_user: impl Fn(&T) -> bool) -> Vec<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, message)| is_user(message))
+        .map(|(index, _)| index)
+        .take(CACHED_USER_MESSAGES)
+        .collect()
+}
+
+/// Mark the breakpoints of messages in the Anthropic format, where each message holds an array
+/// of content blocks and the last block of a message is marked
+pub fn mark_anthropic_messages(messages: &mut [Value]) {
+    for index in user_breakpoints(messages, is_user_message) {
+        if let Some(block) = messages[index]
+            .get_mut("content")
+            .and_then(Value::as_array_mut)
+            .and_then(|blocks| blocks.last_mut())
+            .and_then(Value::as_object_mut)
+        {
+            block.insert("cache_control".to_string(), cache_control());
+        }
+    }
+}
+
+/// Mark the breakpoints of messages in the OpenAI format, turning the text content of the
+/// system prompt and user messages into a marked text block
+pub fn mark_openai_messages(messages: &mut [Value]) {
+    let mut marked = user_breakpoints(messages, is_user_message);
+    if let Some(system) = messages
+        .iter()
+        .position(|message| message.get("role") == Some(&json!("system")))
+    {
+        marked.push(system);
+    }
+    for index in marked {
+        if let Some(content) = messages[index].get_mut("content") {
+            if let Some(text) = content.as_str().map(str::to_string) {
+                *content = json!([{
+                    "type": "text",
+                    "text": text,
+                    "cache_control": cache_control(),
+                }]);
+            }
+        }
+    }
+}
+
+/// Mark the last of the tool definitions, caching all of them as one prefix. Definitions in the
+/// OpenAI format are marked on their function.
+pub fn mark_last_tool(tools: &mut [Value]) {
+    let Some(last) = tools.last_mut() else {
+        return;
+    };
+    let target = if last.get("function").is_some() {
+        last.get_mut("function")
+    } else {
+        Some(last)
+    };
+    if let Some(target) = target.and_then(Value::as_object_mut) {
+        target.insert("cache_control".to_string(), cache_control());
+    }
+}
+
+fn is_user_message(message: &Value) -> bool {
+    message.get("role") == Some(&json!("user"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marks_the_last_two_user_messages() {
+        let mut messages = vec![
+            json!({"role": "user", "content": [{"type": "text", "text": "one"}]}),
+            json!({"role": "assistant", "content": [{"type": "text", "text": "two"}]}),
+            json!({"role": "user", "content": [{"type": "text", "text": "three"}]}),
+            json!({"role": "assistant", "content": [{"type": "text", "text": "four"}]}),
+            json!({"role": "user", "content": [
+                {"type": "tool_result", "tool_use_id": "1"},
+                {"type": "text", "text": "five"}
+            ]}),
+        ];
+        mark_anthropic_messages(&mut messages);
+        let marked: Vec<bool> = messages
+            .iter()
+            .map(|message| message.to_string().contains("cache_control"))
+            .collect();
+        assert_eq!(marked, vec![false, false, true, false, true]);
+        assert!(messages[4]["content"][1].get("cache_control").is_some());
+        assert!(messages[4]["content"][0].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn test_marks_openai_messages_and_tools() {
+        let mut messages = vec![
+            json!({"role": "system", "content": "You are goose."}),
+            json!({"role": "user", "content": "hello"}),
+        ];
+        mark_openai_messages(&mut messages);
+        assert_eq!(messages[0]["content"][0]["text"], "You are goose.");
+        assert_eq!(messages[0]["content"][0]["cache_control"], cache_control());
+        assert_eq!(messages[1]["content"][0]["cache_control"], cache_control());
+
+        let mut tools = vec![
+            json!({"type": "function", "function": {"name": "a"}}),
+            json!({"type": "function", "function": {"name": "b"}}),
+        ];
+        mark_last_tool(&mut tools);
+        assert!(tools[0]["function"].get("cache_control").is_none());
+        assert_eq!(tools[1]["function"]["cache_control"], cache_control());
+
+        let mut tools = vec![json!({"name": "a", "input_schema": {}})];
+        mark_last_tool(&mut tools);
+        assert_eq!(tools[0]["cache_control"], cache_control());
+    }
+}
@@ -6,6 +6,7 @@ use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file,
     sanitize_function_name, ImageFormat,
 };
+use crate::tool_errors::tool_error_text;
 use anyhow::{anyhow, Error};
 use mcp_core::ToolError;
 use mcp_core::{Content, Role, Tool, ToolCall};
@@ -116,6 +117,11 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 MessageContent::SummarizationRequested(_) => {
                     continue;
                 }
+                MessageContent::Audio(_) => {
+                    // Audio input is transcribed before it reaches the provider and spoken
+                    // output is only for the frontend
+                    continue;
+                }
                 MessageContent::ToolResponse(response) => {
                     match &response.tool_result {
                         Ok(contents) => {
@@ -176,7 +182,7 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                             // A tool result error is shown as output so the model can interpret the error message
                             result.push(json!({
                                 "role": "tool",
-                                "content": format!("The tool call returned the following error:\n{}", e),
+                                "content": tool_error_text(e),
                                 "tool_call_id": response.id
                             }));
                         }
@@ -187,12 +193,7 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 }
                 MessageContent::Image(image) => {
                     // Handle direct image content
-                    content_array.push(json!({
-                        "type": "image_url",
-                        "image_url": {
-                            "url": convert_image(image, image_format)
-                        }
-                    }));
+                    content_array.push(convert_image(image, image_format));
                 }
                 MessageContent::FrontendToolRequest(req) => {
                     // Frontend tool requests are converted to text messages
@@ -553,6 +554,12 @@ pub fn create_request(
                     .unwrap()
                     .insert("temperature".to_string(), json!(temp));
             }
+            if let Some(top_p) = model_config.top_p {
+                payload
+                    .as_object_mut()
+                    .unwrap()
+                    .insert("top_p".to_string(), json!(top_p));
+            }
         }
 
         // o1 models use max_completion_tokens instead of max_tokens
@@ -979,6 +986,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
@@ -1011,6 +1019,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
@@ -1044,6 +1053,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
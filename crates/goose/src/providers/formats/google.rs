@@ -3,6 +3,8 @@ use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{is_valid_function_name, sanitize_function_name};
+use crate::providers::web_search::{sources_text, Citation};
+use crate::tool_errors::tool_error_text;
 use anyhow::Result;
 use mcp_core::content::Content;
 use mcp_core::role::Role;
@@ -106,11 +108,20 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                                 ));
                             }
                             Err(e) => {
-                                parts.push(json!({"text":format!("Error: {}", e)}));
+                                parts.push(json!({"text": tool_error_text(e)}));
                             }
                         }
                     }
 
+                    MessageContent::Image(image) => {
+                        parts.push(json!({
+                            "inline_data": {
+                                "mime_type": image.mime_type,
+                                "data": image.data,
+                            }
+                        }));
+                    }
+
                     _ => {}
                 }
             }
@@ -252,6 +263,9 @@ pub fn response_to_message(response: Value) -> Result<Message> {
             }
         }
     }
+    if let Some(sources) = sources_text(&grounding_citations(candidate)) {
+        content.push(MessageContent::text(sources));
+    }
     Ok(Message {
         role,
         created,
@@ -259,6 +273,41 @@ pub fn response_to_message(response: Value) -> Result<Message> {
     })
 }
 
+/// The web pages a candidate is grounded in, from the Google Search tool
+fn grounding_citations(candidate: &Value) -> Vec<Citation> {
+    candidate
+        .get("groundingMetadata")
+        .and_then(|m| m.get("groundingChunks"))
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|chunk| {
+            let web = chunk.get("web")?;
+            let uri = web.get("uri").and_then(|u| u.as_str())?;
+            Some(Citation::new(
+                uri,
+                web.get("title").and_then(|t| t.as_str()),
+            ))
+        })
+        .collect()
+}
+
+/// Ground responses in Google Search results
+pub fn add_google_search_tool(payload: &mut Value) {
+    let payload = payload.as_object_mut().unwrap();
+    let search = json!({"google_search": {}});
+    // Function declarations are sent as a single tool object, which becomes the first of a list
+    let tools = match payload.remove("tools") {
+        Some(Value::Array(mut tools)) => {
+            tools.push(search);
+            tools
+        }
+        Some(tool) => vec![tool, search],
+        None => vec![search],
+    };
+    payload.insert("tools".to_string(), Value::Array(tools));
+}
+
 /// Extract usage information from Google's API response
 pub fn get_usage(data: &Value) -> Result<Usage> {
     if let Some(usage_meta_data) = data.get("usageMetadata") {
@@ -308,6 +357,9 @@ pub fn create_request(
     if let Some(temp) = model_config.temperature {
         generation_config.insert("temperature".to_string(), json!(temp));
     }
+    if let Some(top_p) = model_config.top_p {
+        generation_config.insert("topP".to_string(), json!(top_p));
+    }
     if let Some(tokens) = model_config.max_tokens {
         generation_config.insert("maxOutputTokens".to_string(), json!(tokens));
     }
@@ -576,6 +628,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_response_to_message_with_grounding() {
+        let response = json!({
+            "candidates": [{
+                "content": {"parts": [{"text": "Rust 1.80 was released on July 25, 2024."}]},
+                "groundingMetadata": {
+                    "webSearchQueries": ["rust 1.80 release date"],
+                    "groundingChunks": [
+                        {"web": {"uri": "https://vertexaisearch.cloud.google.com/grounding-api-redirect/abc", "title": "rust-lang.org"}},
+                        {"web": {"uri": "https://vertexaisearch.cloud.google.com/grounding-api-redirect/abc", "title": "rust-lang.org"}}
+                    ]
+                }
+            }]
+        });
+        let message = response_to_message(response).unwrap();
+        assert_eq!(message.content.len(), 2);
+        assert_eq!(
+            message.content[1].as_text(),
+            Some("Sources:\n1. [rust-lang.org](https://vertexaisearch.cloud.google.com/grounding-api-redirect/abc)")
+        );
+
+        let mut payload = json!({"tools": {"functionDeclarations": []}});
+        add_google_search_tool(&mut payload);
+        assert_eq!(
+            payload["tools"],
+            json!([{"functionDeclarations": []}, {"google_search": {}}])
+        );
+    }
+
     #[test]
     fn test_response_to_message_with_valid_function_call() {
         let response = json!({
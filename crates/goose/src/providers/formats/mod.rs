@@ -1,5 +1,6 @@
 pub mod anthropic;
 pub mod bedrock;
+pub mod cache;
 pub mod databricks;
 pub mod gcpvertexai;
 pub mod google;
@@ -6,6 +6,8 @@ use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file,
     sanitize_function_name, ImageFormat,
 };
+use crate::providers::web_search::{sources_text, Citation};
+use crate::tool_errors::tool_error_text;
 use anyhow::{anyhow, Error};
 use mcp_core::ToolError;
 use mcp_core::{Content, Role, Tool, ToolCall};
@@ -14,6 +16,22 @@ use serde_json::{json, Value};
 /// Convert internal Message format to OpenAI's API message specification
 ///   some openai compatible endpoints use the anthropic image spec at the content level
 ///   even though the message structure is otherwise following openai, the enum switches this
+/// Add a part to the content of a message. Content that is only text stays a plain string, and
+/// takes the form of a list of parts once there is more.
+fn push_content(converted: &mut Value, part: Value) {
+    let mut parts = match converted["content"].take() {
+        Value::Null => Vec::new(),
+        Value::String(text) => vec![json!({"type": "text", "text": text})],
+        Value::Array(parts) => parts,
+        other => vec![other],
+    };
+    parts.push(part);
+    converted["content"] = match parts.as_slice() {
+        [part] if part["type"] == "text" => part["text"].clone(),
+        _ => json!(parts),
+    };
+}
+
 pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<Value> {
     let mut messages_spec = Vec::new();
     for message in messages {
@@ -27,20 +45,13 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
             match content {
                 MessageContent::Text(text) => {
                     if !text.text.is_empty() {
+                        push_content(&mut converted, json!({"type": "text", "text": text.text}));
                         // Check for image paths in the text
                         if let Some(image_path) = detect_image_path(&text.text) {
-                            // Try to load and convert the image
+                            // Try to load and convert the image, or else just send the text
                             if let Ok(image) = load_image_file(image_path) {
-                                converted["content"] = json!([
-                                    {"type": "text", "text": text.text},
-                                    convert_image(&image, image_format)
-                                ]);
-                            } else {
-                                // If image loading fails, just use the text
-                                converted["content"] = json!(text.text);
+                                push_content(&mut converted, convert_image(&image, image_format));
                             }
-                        } else {
-                            converted["content"] = json!(text.text);
                         }
                     }
                 }
@@ -58,6 +69,11 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 MessageContent::SummarizationRequested(_) => {
                     continue;
                 }
+                MessageContent::Audio(_) => {
+                    // Audio input is transcribed before it reaches the provider and spoken
+                    // output is only for the frontend
+                    continue;
+                }
                 MessageContent::ToolRequest(request) => match &request.tool_call {
                     Ok(tool_call) => {
                         let sanitized_name = sanitize_function_name(&tool_call.name);
@@ -144,7 +160,7 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                             // A tool result error is shown as output so the model can interpret the error message
                             output.push(json!({
                                 "role": "tool",
-                                "content": format!("The tool call returned the following error:\n{}", e),
+                                "content": tool_error_text(e),
                                 "tool_call_id": response.id
                             }));
                         }
@@ -155,7 +171,7 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 }
                 MessageContent::Image(image) => {
                     // Handle direct image content
-                    converted["content"] = json!([convert_image(image, image_format)]);
+                    push_content(&mut converted, convert_image(image, image_format));
                 }
                 MessageContent::FrontendToolRequest(request) => match &request.tool_call {
                     Ok(tool_call) => {
@@ -230,6 +246,10 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
         }
     }
 
+    if let Some(sources) = sources_text(&url_citations(&original)) {
+        content.push(MessageContent::text(sources));
+    }
+
     if let Some(tool_calls) = original.get("tool_calls") {
         if let Some(tool_calls_array) = tool_calls.as_array() {
             for tool_call in tool_calls_array {
@@ -281,6 +301,39 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
     })
 }
 
+/// The web pages a message cites, from the search run by search models
+fn url_citations(message: &Value) -> Vec<Citation> {
+    message
+        .get("annotations")
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|a| a.get("type").and_then(|t| t.as_str()) == Some("url_citation"))
+        .filter_map(|a| {
+            let citation = a.get("url_citation")?;
+            let url = citation.get("url").and_then(|u| u.as_str())?;
+            Some(Citation::new(
+                url,
+                citation.get("title").and_then(|t| t.as_str()),
+            ))
+        })
+        .collect()
+}
+
+/// Whether the model searches the web on the chat completions API. Only the search models accept
+/// `web_search_options`, and other models reject a request that has them.
+pub fn supports_web_search(model_name: &str) -> bool {
+    model_name.contains("search-preview")
+}
+
+/// Let a search model search the web, with the default search options
+pub fn add_web_search_options(payload: &mut Value) {
+    payload
+        .as_object_mut()
+        .unwrap()
+        .insert("web_search_options".to_string(), json!({}));
+}
+
 pub fn get_usage(data: &Value) -> Result<Usage, ProviderError> {
     let usage = data
         .get("usage")
@@ -305,7 +358,15 @@ pub fn get_usage(data: &Value) -> Result<Usage, ProviderError> {
             _ => None,
         });
 
-    Ok(Usage::new(input_tokens, output_tokens, total_tokens))
+    // Prompt tokens served from the cache, as OpenAI and OpenRouter report them
+    let cache_read_tokens = usage
+        .get("prompt_tokens_details")
+        .and_then(|details| details.get("cached_tokens"))
+        .and_then(|v| v.as_i64())
+        .map(|v| v as i32);
+
+    Ok(Usage::new(input_tokens, output_tokens, total_tokens)
+        .with_cache_tokens(cache_read_tokens, None))
 }
 
 /// Validates and fixes tool schemas to ensure they have proper parameter structure.
@@ -433,6 +494,12 @@ pub fn create_request(
                 .unwrap()
                 .insert("temperature".to_string(), json!(temp));
         }
+        if let Some(top_p) = model_config.top_p {
+            payload
+                .as_object_mut()
+                .unwrap()
+                .insert("top_p".to_string(), json!(top_p));
+        }
     }
 
     // o1 models use max_completion_tokens instead of max_tokens
@@ -768,6 +835,41 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_response_to_message_url_citations() -> anyhow::Result<()> {
+        let response = json!({
+            "choices": [{
+                "message": {
+                    "role": "assistant",
+                    "content": "Rust 1.80 was released on July 25, 2024 ([blog.rust-lang.org](https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html)).",
+                    "annotations": [{
+                        "type": "url_citation",
+                        "url_citation": {
+                            "start_index": 42,
+                            "end_index": 120,
+                            "url": "https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html",
+                            "title": "Announcing Rust 1.80.0"
+                        }
+                    }]
+                }
+            }]
+        });
+
+        let message = response_to_message(response)?;
+        assert_eq!(message.content.len(), 2);
+        assert_eq!(
+            message.content[1].as_text(),
+            Some("Sources:\n1. [Announcing Rust 1.80.0](https://blog.rust-lang.org/2024/07/25/Rust-1.80.0.html)")
+        );
+
+        let mut payload = json!({"model": "gpt-4o-search-preview"});
+        add_web_search_options(&mut payload);
+        assert_eq!(payload["web_search_options"], json!({}));
+        assert!(supports_web_search("gpt-4o-mini-search-preview"));
+        assert!(!supports_web_search("gpt-4o"));
+        Ok(())
+    }
+
     #[test]
     fn test_response_to_message_valid_toolrequest() -> anyhow::Result<()> {
         let response: Value = serde_json::from_str(OPENAI_TOOL_USE_RESPONSE)?;
@@ -857,6 +959,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
@@ -889,6 +992,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
@@ -922,6 +1026,7 @@ mod tests {
             context_limit: Some(4096),
             temperature: None,
             max_tokens: Some(1024),
+            top_p: None,
             toolshim: false,
             toolshim_model: None,
         };
@@ -63,6 +63,10 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SummarizationRequested(_) => {
                     // Skip
                 }
+                MessageContent::Audio(_) => {
+                    // Skip, audio input is transcribed before it reaches the provider and
+                    // spoken output is only for the frontend
+                }
                 MessageContent::Thinking(_thinking) => {
                     // Skip thinking for now
                 }
@@ -7,6 +7,8 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fmt, io};
 use tokio::sync::RwLock;
 
+use super::http_client;
+
 /// Represents errors that can occur during GCP authentication.
 ///
 /// This enum encompasses various error conditions that might arise during
@@ -240,7 +242,7 @@ impl AdcCredentials {
     }
 
     async fn load_from_metadata_server(base_url: &str) -> Result<Self, AuthError> {
-        let client = reqwest::Client::new();
+        let client = auth_client()?;
         let metadata_path = "/computeMetadata/v1/instance/service-accounts/default/token";
 
         let response = client
@@ -338,6 +340,13 @@ pub struct GcpAuth {
     cached_token: Arc<RwLock<Option<CachedToken>>>,
 }
 
+/// An HTTP client with the global proxy and TLS settings, for the metadata server and token
+/// exchange requests
+fn auth_client() -> Result<reqwest::Client, AuthError> {
+    http_client::default_client()
+        .map_err(|e| AuthError::Credentials(format!("Failed to create an HTTP client: {}", e)))
+}
+
 impl GcpAuth {
     /// Creates a new GCP authentication handler.
     ///
@@ -356,7 +365,7 @@ impl GcpAuth {
     pub async fn new() -> Result<Self, AuthError> {
         Ok(Self {
             credentials: AdcCredentials::load().await?,
-            client: reqwest::Client::new(),
+            client: auth_client()?,
             cached_token: Arc::new(RwLock::new(None)),
         })
     }
@@ -1,3 +1,4 @@
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
@@ -19,6 +20,7 @@ use crate::providers::formats::gcpvertexai::{
 
 use crate::providers::formats::gcpvertexai::GcpLocation::Iowa;
 use crate::providers::gcpauth::GcpAuth;
+use crate::providers::http_client;
 use crate::providers::utils::emit_debug_trace;
 use mcp_core::tool::Tool;
 
@@ -150,7 +152,7 @@ impl GcpVertexAIProvider {
         let location = Self::determine_location(config)?;
         let host = format!("https://{}-aiplatform.googleapis.com", location);
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
             .build()?;
 
@@ -520,6 +522,13 @@ impl Provider for GcpVertexAIProvider {
     fn get_model_config(&self) -> ModelConfig {
         self.model.clone()
     }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
 }
 
 #[cfg(test)]
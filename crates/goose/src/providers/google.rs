@@ -1,16 +1,21 @@
 use super::errors::ProviderError;
+use super::http_client;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
-use crate::providers::formats::google::{create_request, get_usage, response_to_message};
+use crate::providers::formats::google::{
+    add_google_search_tool, create_request, get_usage, response_to_message,
+};
 use crate::providers::utils::{
     emit_debug_trace, handle_response_google_compat, unescape_json_values,
 };
+use crate::providers::web_search::WebSearchConfig;
 use anyhow::Result;
 use async_trait::async_trait;
 use mcp_core::tool::Tool;
 use reqwest::Client;
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
@@ -34,6 +39,8 @@ pub struct GoogleProvider {
     host: String,
     api_key: String,
     model: ModelConfig,
+    #[serde(skip)]
+    web_search: WebSearchConfig,
 }
 
 impl Default for GoogleProvider {
@@ -51,7 +58,7 @@ impl GoogleProvider {
             .get_param("GOOGLE_HOST")
             .unwrap_or_else(|_| GOOGLE_API_HOST.to_string());
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -60,6 +67,7 @@ impl GoogleProvider {
             host,
             api_key,
             model,
+            web_search: WebSearchConfig::from_config(),
         })
     }
 
@@ -76,49 +84,17 @@ impl GoogleProvider {
                 ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
             })?;
 
-        let max_retries = 3;
-        let mut retries = 0;
-        let base_delay = Duration::from_secs(2);
-
-        loop {
-            let response = self
-                .client
-                .post(url.clone()) // Clone the URL for each retry
-                .header("CONTENT_TYPE", "application/json")
-                .json(&payload)
-                .send()
-                .await;
-
-            match response {
-                Ok(res) => {
-                    match handle_response_google_compat(res).await {
-                        Ok(result) => return Ok(result),
-                        Err(ProviderError::RateLimitExceeded(_)) => {
-                            retries += 1;
-                            if retries > max_retries {
-                                return Err(ProviderError::RateLimitExceeded(
-                                    "Max retries exceeded for rate limit error".to_string(),
-                                ));
-                            }
-
-                            let delay = 2u64.pow(retries);
-                            let total_delay = Duration::from_secs(delay) + base_delay;
-
-                            println!("Rate limit hit. Retrying in {:?}", total_delay);
-                            tokio::time::sleep(total_delay).await;
-                            continue;
-                        }
-                        Err(err) => return Err(err), // Other errors
-                    }
-                }
-                Err(err) => {
-                    return Err(ProviderError::RequestFailed(format!(
-                        "Request failed: {}",
-                        err
-                    )));
-                }
-            }
-        }
+        // Rate limits are retried by the provider's RetryingProvider
+        let response = self
+            .client
+            .post(url)
+            .header("CONTENT_TYPE", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|err| ProviderError::RequestFailed(format!("Request failed: {}", err)))?;
+
+        handle_response_google_compat(response).await
     }
 }
 
@@ -137,12 +113,20 @@ impl Provider for GoogleProvider {
                 ConfigKey::new("GOOGLE_HOST", false, false, Some(GOOGLE_API_HOST)),
             ],
         )
+        .with_web_search()
     }
 
     fn get_model_config(&self) -> ModelConfig {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -153,7 +137,10 @@ impl Provider for GoogleProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools)?;
+        let mut payload = create_request(&self.model, system, messages, tools)?;
+        if self.web_search.enabled {
+            add_google_search_tool(&mut payload);
+        }
 
         // Make request
         let response = self.post(payload.clone()).await?;
@@ -1,4 +1,5 @@
-use super::errors::ProviderError;
+use super::errors::{OpenAIError, ProviderError};
+use super::http_client;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
@@ -8,13 +9,21 @@ use anyhow::Result;
 use async_trait::async_trait;
 use mcp_core::Tool;
 use reqwest::{Client, StatusCode};
-use serde_json::Value;
+use serde_json::{from_value, Value};
+use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
 pub const GROQ_API_HOST: &str = "https://api.groq.com";
 pub const GROQ_DEFAULT_MODEL: &str = "llama-3.3-70b-versatile";
-pub const GROQ_KNOWN_MODELS: &[&str] = &["gemma2-9b-it", "llama-3.3-70b-versatile"];
+pub const GROQ_KNOWN_MODELS: &[&str] = &[
+    "llama-3.3-70b-versatile",
+    "llama-3.1-8b-instant",
+    "llama3-70b-8192",
+    "llama3-8b-8192",
+    "mixtral-8x7b-32768",
+    "gemma2-9b-it",
+];
 
 pub const GROQ_DOC_URL: &str = "https://console.groq.com/docs/models";
 
@@ -42,7 +51,7 @@ impl GroqProvider {
             .get_param("GROQ_HOST")
             .unwrap_or_else(|_| GROQ_API_HOST.to_string());
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -70,28 +79,70 @@ impl GroqProvider {
             .await?;
 
         let status = response.status();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
         let payload: Option<Value> = response.json().await.ok();
 
         match status {
-            StatusCode::OK => payload.ok_or_else( || ProviderError::RequestFailed("Response body is not valid JSON".to_string()) ),
-            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                Err(ProviderError::Authentication(format!("Authentication failed. Please ensure your API keys are valid and have the required permissions. \
-                    Status: {}. Response: {:?}", status, payload)))
-            }
-            StatusCode::PAYLOAD_TOO_LARGE => {
-                Err(ProviderError::ContextLengthExceeded(format!("{:?}", payload)))
-            }
-            StatusCode::TOO_MANY_REQUESTS => {
-                Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
-            }
-            StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-                Err(ProviderError::ServerError(format!("{:?}", payload)))
-            }
-            _ => {
-                tracing::debug!(
-                    "{}", format!("Provider request failed with status: {}. Payload: {:?}", status, payload)
-                );
-                Err(ProviderError::RequestFailed(format!("Request failed with status: {}", status)))
+            StatusCode::OK => payload.ok_or_else(|| {
+                ProviderError::RequestFailed("Response body is not valid JSON".to_string())
+            }),
+            _ => Err(map_error(status, retry_after.as_deref(), payload)),
+        }
+    }
+}
+
+/// Map a failed Groq response to a provider error.
+///
+/// Groq answers requests over a rate limit with a 429, or with a 413 when a single request has
+/// more tokens than the per-minute limit allows, and marks both with a `rate_limit_exceeded`
+/// code. Its message says how long to wait, as does the `retry-after` header in seconds.
+fn map_error(
+    status: StatusCode,
+    retry_after: Option<&str>,
+    payload: Option<Value>,
+) -> ProviderError {
+    let error = payload
+        .as_ref()
+        .and_then(|payload| payload.get("error"))
+        .and_then(|error| from_value::<OpenAIError>(error.clone()).ok());
+    let code = error.as_ref().and_then(|error| error.code.as_deref());
+
+    if status == StatusCode::TOO_MANY_REQUESTS || code == Some("rate_limit_exceeded") {
+        let message = error
+            .as_ref()
+            .map(|error| error.to_string())
+            .unwrap_or_else(|| format!("{:?}", payload));
+        return ProviderError::RateLimitExceeded(match retry_after {
+            Some(seconds) => format!("{} (retry after {}s)", message, seconds),
+            None => message,
+        });
+    }
+    if let Some(error) = &error {
+        if error.is_context_length_exceeded() {
+            return ProviderError::ContextLengthExceeded(error.to_string());
+        }
+    }
+
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+            ProviderError::Authentication(format!("Authentication failed. Please ensure your API keys are valid and have the required permissions. \
+                Status: {}. Response: {:?}", status, payload))
+        }
+        StatusCode::PAYLOAD_TOO_LARGE => ProviderError::ContextLengthExceeded(format!("{:?}", payload)),
+        StatusCode::INTERNAL_SERVER_ERROR | StatusCode::BAD_GATEWAY | StatusCode::SERVICE_UNAVAILABLE => {
+            ProviderError::ServerError(format!("{:?}", payload))
+        }
+        _ => {
+            tracing::debug!(
+                "{}", format!("Provider request failed with status: {}. Payload: {:?}", status, payload)
+            );
+            match error {
+                Some(error) => ProviderError::RequestFailed(format!("{} (status {})", error, status.as_u16())),
+                None => ProviderError::RequestFailed(format!("Request failed with status: {}", status)),
             }
         }
     }
@@ -118,6 +169,13 @@ impl Provider for GroqProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -152,3 +210,67 @@ impl Provider for GroqProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_known_models_have_their_context_limits() {
+        let limits: Vec<(String, usize)> = GroqProvider::metadata()
+            .known_models
+            .into_iter()
+            .map(|model| (model.name, model.context_limit))
+            .collect();
+        assert!(limits.contains(&("llama-3.3-70b-versatile".to_string(), 131_072)));
+        assert!(limits.contains(&("llama3-8b-8192".to_string(), 8_192)));
+        assert!(limits.contains(&("mixtral-8x7b-32768".to_string(), 32_768)));
+    }
+
+    #[test]
+    fn test_rate_limits_are_mapped() {
+        let payload = json!({"error": {
+            "message": "Rate limit reached for model `llama-3.3-70b-versatile` on tokens per minute (TPM). Please try again in 7.66s.",
+            "type": "tokens",
+            "code": "rate_limit_exceeded"
+        }});
+
+        let ProviderError::RateLimitExceeded(message) = map_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            Some("8"),
+            Some(payload.clone()),
+        ) else {
+            panic!("expected a rate limit error");
+        };
+        assert!(message.contains("try again in 7.66s"));
+        assert!(message.ends_with("(retry after 8s)"));
+
+        // A request over the per-minute token limit is too large only for now
+        assert!(matches!(
+            map_error(StatusCode::PAYLOAD_TOO_LARGE, None, Some(payload)),
+            ProviderError::RateLimitExceeded(_)
+        ));
+    }
+
+    #[test]
+    fn test_other_errors_are_mapped() {
+        let payload = json!({"error": {
+            "message": "Please reduce the length of the messages or completion.",
+            "type": "invalid_request_error",
+            "code": "context_length_exceeded"
+        }});
+        assert!(matches!(
+            map_error(StatusCode::BAD_REQUEST, None, Some(payload)),
+            ProviderError::ContextLengthExceeded(_)
+        ));
+        assert!(matches!(
+            map_error(StatusCode::UNAUTHORIZED, None, None),
+            ProviderError::Authentication(_)
+        ));
+        assert!(matches!(
+            map_error(StatusCode::SERVICE_UNAVAILABLE, None, None),
+            ProviderError::ServerError(_)
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Shared construction of HTTP clients for providers and HTTP based transports.
+//!
+//! Every client goes through [`client_builder`] so that proxy, TLS and connection
+//! pool settings only need to be configured once:
+//!
+//! ```yaml
+//! GOOSE_HTTP_PROXY: http://proxy.internal:3128
+//! GOOSE_NO_PROXY: localhost,127.0.0.1,.internal
+//! GOOSE_CA_CERT: /etc/ssl/certs/corporate-root.pem
+//! GOOSE_HTTP_POOL_MAX_IDLE: 8
+//! GOOSE_HTTP_POOL_IDLE_TIMEOUT: 90
+//! GOOSE_HTTP_CONNECT_TIMEOUT: 30
+//! ```
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use mcp_client::transport::HttpClientOptions;
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpClientSettings {
+    /// Proxy used for all requests, e.g. `http://proxy:3128`
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass the proxy
+    pub no_proxy: Option<String>,
+    /// PEM file with additional root certificates to trust
+    pub ca_cert_path: Option<PathBuf>,
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout_secs: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+}
+
+impl HttpClientSettings {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            proxy: config.get_param("GOOSE_HTTP_PROXY").ok(),
+            no_proxy: config.get_param("GOOSE_NO_PROXY").ok(),
+            ca_cert_path: config
+                .get_param::<String>("GOOSE_CA_CERT")
+                .ok()
+                .map(PathBuf::from),
+            pool_max_idle_per_host: config.get_param("GOOSE_HTTP_POOL_MAX_IDLE").ok(),
+            pool_idle_timeout_secs: config.get_param("GOOSE_HTTP_POOL_IDLE_TIMEOUT").ok(),
+            connect_timeout_secs: config.get_param("GOOSE_HTTP_CONNECT_TIMEOUT").ok(),
+        }
+    }
+
+    /// Read the configured root certificate bundle, if any
+    pub fn ca_cert_pem(&self) -> Result<Option<Vec<u8>>> {
+        self.ca_cert_path
+            .as_ref()
+            .map(|path| {
+                std::fs::read(path)
+                    .with_context(|| format!("Failed to read CA certificate {}", path.display()))
+            })
+            .transpose()
+    }
+
+    /// Apply these settings to a client builder
+    pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder> {
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = Proxy::all(proxy_url)
+                .with_context(|| format!("Invalid proxy URL {}", proxy_url))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(pem) = self.ca_cert_pem()? {
+            for cert in Certificate::from_pem_bundle(&pem)
+                .context("Failed to parse CA certificate bundle")?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(secs) = self.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.connect_timeout_secs {
+            builder = builder.connect_timeout(Duration::from_secs(secs));
+        }
+
+        Ok(builder)
+    }
+
+    /// The same settings for HTTP based MCP transports, which build their own clients
+    pub fn transport_options(&self) -> Result<HttpClientOptions> {
+        Ok(HttpClientOptions {
+            proxy: self.proxy.clone(),
+            no_proxy: self.no_proxy.clone(),
+            ca_cert_pem: self.ca_cert_pem()?,
+            pool_max_idle_per_host: self.pool_max_idle_per_host,
+            pool_idle_timeout: self.pool_idle_timeout_secs.map(Duration::from_secs),
+            connect_timeout: self.connect_timeout_secs.map(Duration::from_secs),
+        })
+    }
+}
+
+/// A client builder with the global HTTP settings applied. Callers add their own
+/// request timeout and headers before building.
+pub fn client_builder() -> Result<ClientBuilder> {
+    HttpClientSettings::from_config().apply(reqwest::Client::builder())
+}
+
+/// A client with the global HTTP settings and no request timeout
+pub fn default_client() -> Result<reqwest::Client> {
+    Ok(client_builder()?.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_proxy_and_pool_settings() {
+        let settings = HttpClientSettings {
+            proxy: Some("http://localhost:3128".to_string()),
+            no_proxy: Some("localhost,.internal".to_string()),
+            pool_max_idle_per_host: Some(2),
+            pool_idle_timeout_secs: Some(30),
+            connect_timeout_secs: Some(5),
+            ..Default::default()
+        };
+        let builder = settings.apply(reqwest::Client::builder()).unwrap();
+        assert!(builder.build().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_proxy_is_rejected() {
+        let settings = HttpClientSettings {
+            proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(settings.apply(reqwest::Client::builder()).is_err());
+    }
+
+    #[test]
+    fn test_missing_ca_cert_is_rejected() {
+        let settings = HttpClientSettings {
+            ca_cert_path: Some(PathBuf::from("/nonexistent/ca.pem")),
+            ..Default::default()
+        };
+        assert!(settings.apply(reqwest::Client::builder()).is_err());
+    }
+}
@@ -1,16 +1,85 @@
 use anyhow::Result;
+use async_stream::try_stream;
 use async_trait::async_trait;
+use futures::StreamExt;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{
+    append_to_response, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage,
+};
+use super::batch::BatchProviderTrait;
 use super::errors::ProviderError;
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
-use mcp_core::{tool::Tool, Content};
+use mcp_core::{tool::Tool, Content, Role};
+
+/// Routes turns after the initial lead turns by how tool-heavy the recent conversation is.
+///
+/// Mechanical turns, where the assistant mostly calls tools, go to the worker model while
+/// turns dominated by free-form text go to the lead model. Two thresholds give hysteresis:
+/// routing only moves to the worker once the tool density reaches `worker_threshold` and only
+/// moves back to the lead once it drops to `lead_threshold`, so it does not flap between models.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DensityRouting {
+    /// Number of recent assistant messages considered
+    pub window: usize,
+    /// Tool density at or above which turns are sent to the worker
+    pub worker_threshold: f64,
+    /// Tool density at or below which turns are sent back to the lead
+    pub lead_threshold: f64,
+}
+
+impl Default for DensityRouting {
+    fn default() -> Self {
+        Self {
+            window: 4,
+            worker_threshold: 0.7,
+            lead_threshold: 0.4,
+        }
+    }
+}
+
+impl DensityRouting {
+    /// Fraction of the assistant's recent output that is tool calls rather than text,
+    /// or `None` when there are no recent assistant messages to judge
+    pub fn tool_density(&self, messages: &[Message]) -> Option<f64> {
+        let mut tool_calls = 0;
+        let mut text_blocks = 0;
+        for message in messages
+            .iter()
+            .rev()
+            .filter(|m| m.role == Role::Assistant)
+            .take(self.window)
+        {
+            for content in &message.content {
+                match content {
+                    MessageContent::ToolRequest(_) => tool_calls += 1,
+                    MessageContent::Text(text) if !text.text.trim().is_empty() => text_blocks += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let total = tool_calls + text_blocks;
+        (total > 0).then(|| tool_calls as f64 / total as f64)
+    }
+
+    /// Whether the next turn should go to the worker, given where the previous turn went
+    pub fn route_to_worker(&self, on_worker: bool, density: Option<f64>) -> bool {
+        match density {
+            Some(d) if on_worker => d > self.lead_threshold,
+            Some(d) => d >= self.worker_threshold,
+            None => on_worker,
+        }
+    }
+}
 
 /// A provider that switches between a lead model and a worker model based on turn count
-/// and can fallback to lead model on consecutive failures
+/// and can fallback to lead model on consecutive failures. Clones share the turn and failure
+/// tracking.
+#[derive(Clone)]
 pub struct LeadWorkerProvider {
     lead_provider: Arc<dyn Provider>,
     worker_provider: Arc<dyn Provider>,
@@ -21,6 +90,8 @@ pub struct LeadWorkerProvider {
     fallback_turns: usize,
     in_fallback_mode: Arc<Mutex<bool>>,
     fallback_remaining: Arc<Mutex<usize>>,
+    density_routing: Option<DensityRouting>,
+    density_on_worker: Arc<Mutex<bool>>,
 }
 
 impl LeadWorkerProvider {
@@ -45,6 +116,8 @@ impl LeadWorkerProvider {
             fallback_turns: 2,               // Use lead model for 2 turns when in fallback mode
             in_fallback_mode: Arc::new(Mutex::new(false)),
             fallback_remaining: Arc::new(Mutex::new(0)),
+            density_routing: None,
+            density_on_worker: Arc::new(Mutex::new(true)),
         }
     }
 
@@ -73,9 +146,17 @@ impl LeadWorkerProvider {
             fallback_turns,
             in_fallback_mode: Arc::new(Mutex::new(false)),
             fallback_remaining: Arc::new(Mutex::new(0)),
+            density_routing: None,
+            density_on_worker: Arc::new(Mutex::new(true)),
         }
     }
 
+    /// Route turns after the initial lead turns by tool call density
+    pub fn with_density_routing(mut self, routing: DensityRouting) -> Self {
+        self.density_routing = Some(routing);
+        self
+    }
+
     /// Reset the turn counter and failure tracking (useful for new conversations)
     pub async fn reset_turn_count(&self) {
         let mut count = self.turn_count.lock().await;
@@ -86,6 +167,8 @@ impl LeadWorkerProvider {
         *fallback = false;
         let mut remaining = self.fallback_remaining.lock().await;
         *remaining = 0;
+        let mut on_worker = self.density_on_worker.lock().await;
+        *on_worker = true;
     }
 
     /// Get the current turn count
@@ -103,17 +186,72 @@ impl LeadWorkerProvider {
         *self.in_fallback_mode.lock().await
     }
 
-    /// Get the currently active provider based on turn count and fallback state
-    async fn get_active_provider(&self) -> Arc<dyn Provider> {
-        let count = *self.turn_count.lock().await;
+    /// Update the density routing state from the conversation so far. Returns whether the
+    /// next turn should go to the worker, or `None` when density routing is disabled.
+    async fn route_by_density(&self, messages: &[Message]) -> Option<bool> {
+        let routing = self.density_routing?;
+        let density = routing.tool_density(messages);
+        let mut on_worker = self.density_on_worker.lock().await;
+        let to_worker = routing.route_to_worker(*on_worker, density);
+        if to_worker != *on_worker {
+            tracing::info!(
+                "Tool call density {:.2} - routing to {} model",
+                density.unwrap_or_default(),
+                if to_worker { "worker" } else { "lead" }
+            );
+        }
+        *on_worker = to_worker;
+        Some(to_worker)
+    }
+
+    /// The provider for the next turn, and which of the two it is, for logging
+    async fn choose_provider(&self, messages: &[Message]) -> (Arc<dyn Provider>, &'static str) {
+        // Log which provider is being used
+        let turn_count = *self.turn_count.lock().await;
         let in_fallback = *self.in_fallback_mode.lock().await;
+        let fallback_remaining = *self.fallback_remaining.lock().await;
+        let density_to_worker = self.route_by_density(messages).await;
 
-        // Use lead provider if we're in initial turns OR in fallback mode
-        if count < self.lead_turns || in_fallback {
-            Arc::clone(&self.lead_provider)
+        let provider_type = if turn_count < self.lead_turns {
+            "lead (initial)"
+        } else if in_fallback {
+            "lead (fallback)"
+        } else if density_to_worker == Some(false) {
+            "lead (reasoning)"
         } else {
+            "worker"
+        };
+
+        // Get the active provider and model name
+        let provider = if provider_type == "worker" {
             Arc::clone(&self.worker_provider)
+        } else {
+            Arc::clone(&self.lead_provider)
+        };
+        let active_model_name = provider.get_model_config().model_name;
+
+        // Update the global current model store
+        super::base::set_current_model(&active_model_name);
+
+        if in_fallback {
+            tracing::info!(
+                "🔄 Using {} provider for turn {} (FALLBACK MODE: {} turns remaining) - Model: {}",
+                provider_type,
+                turn_count + 1,
+                fallback_remaining,
+                active_model_name
+            );
+        } else {
+            tracing::info!(
+                "Using {} provider for turn {} (lead_turns: {}) - Model: {}",
+                provider_type,
+                turn_count + 1,
+                self.lead_turns,
+                active_model_name
+            );
         }
+
+        (provider, provider_type)
     }
 
     /// Handle the result of a completion attempt and update failure tracking
@@ -178,20 +316,23 @@ impl LeadWorkerProvider {
                 let mut count = self.turn_count.lock().await;
                 *count += 1;
             }
-            Err(_) => {
-                // Technical failure - just log and let it bubble up
-                // For technical failures (API/LLM issues), we don't want to second-guess
-                // the model choice - just let the default model handle it
-                tracing::warn!(
-                    "Technical failure detected - API/LLM issue, will use default model"
-                );
-
-                // Don't increment turn count or failure tracking for technical failures
-                // as these are temporary infrastructure issues, not model capability issues
-            }
+            Err(e) => self.handle_technical_failure(e),
         }
     }
 
+    fn handle_technical_failure(&self, error: &ProviderError) {
+        // Technical failure - just log and let it bubble up
+        // For technical failures (API/LLM issues), we don't want to second-guess
+        // the model choice - just let the default model handle it
+        tracing::warn!(
+            "Technical failure detected - API/LLM issue, will use default model: {}",
+            error
+        );
+
+        // Don't increment turn count or failure tracking for technical failures
+        // as these are temporary infrastructure issues, not model capability issues
+    }
+
     /// Detect task-level failures in the model's response
     async fn detect_task_failures(&self, message: &Message) -> bool {
         let mut failure_indicators = 0;
@@ -324,55 +465,25 @@ impl Provider for LeadWorkerProvider {
         self.lead_provider.get_model_config()
     }
 
+    /// Both models are updated, and the turns taken so far still count
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self {
+            lead_provider: self.lead_provider.with_model_override(update)?,
+            worker_provider: self.worker_provider.with_model_override(update)?,
+            ..self.clone()
+        }))
+    }
+
     async fn complete(
         &self,
         system: &str,
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        // Get the active provider
-        let provider = self.get_active_provider().await;
-
-        // Log which provider is being used
-        let turn_count = *self.turn_count.lock().await;
-        let in_fallback = *self.in_fallback_mode.lock().await;
-        let fallback_remaining = *self.fallback_remaining.lock().await;
-
-        let provider_type = if turn_count < self.lead_turns {
-            "lead (initial)"
-        } else if in_fallback {
-            "lead (fallback)"
-        } else {
-            "worker"
-        };
-
-        // Get the active model name and update the global store
-        let active_model_name = if turn_count < self.lead_turns || in_fallback {
-            self.lead_provider.get_model_config().model_name.clone()
-        } else {
-            self.worker_provider.get_model_config().model_name.clone()
-        };
-
-        // Update the global current model store
-        super::base::set_current_model(&active_model_name);
-
-        if in_fallback {
-            tracing::info!(
-                "🔄 Using {} provider for turn {} (FALLBACK MODE: {} turns remaining) - Model: {}",
-                provider_type,
-                turn_count + 1,
-                fallback_remaining,
-                active_model_name
-            );
-        } else {
-            tracing::info!(
-                "Using {} provider for turn {} (lead_turns: {}) - Model: {}",
-                provider_type,
-                turn_count + 1,
-                self.lead_turns,
-                active_model_name
-            );
-        }
+        let (provider, provider_type) = self.choose_provider(messages).await;
 
         // Make the completion request
         let result = provider.complete(system, messages, tools).await;
@@ -407,6 +518,60 @@ impl Provider for LeadWorkerProvider {
         final_result
     }
 
+    /// Only a failure to start the stream is retried with the lead model. The turn is tracked
+    /// once the stream ends, with the response its parts make, and a stream that fails to
+    /// start or to finish is tracked as a failed completion is.
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let (provider, provider_type) = self.choose_provider(messages).await;
+        let mut parts = match provider.complete_streaming(system, messages, tools).await {
+            Ok(parts) => parts,
+            Err(e) => {
+                tracing::warn!("Technical failure with {} provider, retrying with default model (lead provider)", provider_type);
+                match self
+                    .lead_provider
+                    .complete_streaming(system, messages, tools)
+                    .await
+                {
+                    Ok(parts) => parts,
+                    Err(_) => {
+                        tracing::error!("❌ Default model (lead provider) also failed - returning original error");
+                        self.handle_technical_failure(&e);
+                        return Err(e);
+                    }
+                }
+            }
+        };
+
+        let tracking = self.clone();
+        Ok(Box::pin(try_stream! {
+            let mut response = Message::assistant();
+            let mut usage = None;
+            while let Some(part) = parts.next().await {
+                let (part, part_usage) = part.inspect_err(|e| tracking.handle_technical_failure(e))?;
+                if let Some(part) = &part {
+                    append_to_response(&mut response, part.clone());
+                }
+                if part_usage.is_some() {
+                    usage = part_usage.clone();
+                }
+                yield (part, part_usage);
+            }
+            if let Some(usage) = usage {
+                tracking.handle_completion_result(&Ok((response, usage))).await;
+            }
+        }))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        // Turns move between the providers, so both have to stream
+        self.lead_provider.supports_streaming() && self.worker_provider.supports_streaming()
+    }
+
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         // Combine models from both providers
         let lead_models = self.lead_provider.fetch_supported_models_async().await?;
@@ -430,6 +595,12 @@ impl Provider for LeadWorkerProvider {
         self.lead_provider.supports_embeddings() || self.worker_provider.supports_embeddings()
     }
 
+    fn supports_prompt_caching(&self) -> bool {
+        // Caching pays off only if both providers cache, as turns move between them
+        self.lead_provider.supports_prompt_caching()
+            && self.worker_provider.supports_prompt_caching()
+    }
+
     async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
         // Use the lead provider for embeddings if it supports them, otherwise use worker
         if self.lead_provider.supports_embeddings() {
@@ -447,6 +618,11 @@ impl Provider for LeadWorkerProvider {
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
         Some(self)
     }
+
+    /// Batches have no turns, so they go to the lead provider, whose model config this reports
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.lead_provider.as_batch()
+    }
 }
 
 #[cfg(test)]
@@ -533,6 +709,180 @@ mod tests {
         assert_eq!(usage.model, "lead");
     }
 
+    #[tokio::test]
+    async fn test_streamed_turns_are_counted() {
+        let lead_provider = Arc::new(MockProvider {
+            name: "lead".to_string(),
+            model_config: ModelConfig::new("lead-model".to_string()),
+        });
+        let worker_provider = Arc::new(MockProvider {
+            name: "worker".to_string(),
+            model_config: ModelConfig::new("worker-model".to_string()),
+        });
+        let provider = LeadWorkerProvider::new(lead_provider, worker_provider, Some(1));
+
+        for model in ["lead", "worker"] {
+            let parts: Vec<_> = provider
+                .complete_streaming("system", &[], &[])
+                .await
+                .unwrap()
+                .collect()
+                .await;
+            let (_, usage) = parts.last().unwrap().as_ref().unwrap();
+            assert_eq!(usage.as_ref().unwrap().model, model);
+        }
+        assert_eq!(provider.get_turn_count().await, 2);
+    }
+
+    // Starts streaming a response and fails before it ends
+    struct BrokenStreamProvider {
+        model_config: ModelConfig,
+    }
+
+    #[async_trait]
+    impl Provider for BrokenStreamProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Err(ProviderError::ExecutionError("Not streamed".to_string()))
+        }
+
+        async fn complete_streaming(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<MessageStream, ProviderError> {
+            Ok(Box::pin(futures::stream::iter(vec![
+                Ok((Some(Message::assistant().with_text("Partial")), None)),
+                Err(ProviderError::ExecutionError(
+                    "Connection reset".to_string(),
+                )),
+            ])))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_streams_are_tracked() {
+        let lead = || {
+            Arc::new(MockFailureProvider {
+                name: "lead".to_string(),
+                model_config: ModelConfig::new("lead-model".to_string()),
+                should_fail: false,
+            })
+        };
+        async fn stream(
+            provider: &LeadWorkerProvider,
+        ) -> Vec<Result<(Option<Message>, Option<ProviderUsage>), ProviderError>> {
+            provider
+                .complete_streaming("system", &[], &[])
+                .await
+                .unwrap()
+                .collect()
+                .await
+        }
+
+        // A worker stream that fails to start is retried with the lead model
+        let failing_worker = Arc::new(MockFailureProvider {
+            name: "worker".to_string(),
+            model_config: ModelConfig::new("worker-model".to_string()),
+            should_fail: true,
+        });
+        let provider = LeadWorkerProvider::new(lead(), failing_worker, Some(1));
+        for _ in 0..2 {
+            let parts = stream(&provider).await;
+            let (_, usage) = parts.last().unwrap().as_ref().unwrap();
+            assert_eq!(usage.as_ref().unwrap().model, "lead");
+        }
+        assert_eq!(provider.get_turn_count().await, 2);
+        assert_eq!(provider.get_failure_count().await, 0);
+
+        // A stream that fails part way is not counted as a turn
+        let broken_worker = Arc::new(BrokenStreamProvider {
+            model_config: ModelConfig::new("worker-model".to_string()),
+        });
+        let provider = LeadWorkerProvider::new(lead(), broken_worker, Some(1));
+        stream(&provider).await;
+        let parts = stream(&provider).await;
+        assert!(parts[0].is_ok());
+        assert!(parts.last().unwrap().is_err());
+        assert_eq!(provider.get_turn_count().await, 1);
+        assert_eq!(provider.get_failure_count().await, 0);
+        assert!(!provider.is_in_fallback_mode().await);
+    }
+
+    fn tool_turn() -> Message {
+        Message::assistant().with_tool_request(
+            "1",
+            Ok(mcp_core::ToolCall::new(
+                "developer__shell",
+                serde_json::json!({}),
+            )),
+        )
+    }
+
+    #[test]
+    fn test_density_routing_hysteresis() {
+        let routing = DensityRouting::default();
+        assert_eq!(routing.tool_density(&[]), None);
+
+        let tool_heavy = vec![tool_turn(), tool_turn(), tool_turn()];
+        assert_eq!(routing.tool_density(&tool_heavy), Some(1.0));
+
+        // Between the thresholds routing stays where it is
+        let mixed = vec![
+            tool_turn(),
+            Message::assistant().with_text("Let me think about the design"),
+            tool_turn(),
+        ];
+        let density = routing.tool_density(&mixed);
+        assert!(routing.route_to_worker(true, density));
+        assert!(!routing.route_to_worker(false, density));
+
+        let reasoning = vec![Message::assistant().with_text("Here is my analysis")];
+        assert!(!routing.route_to_worker(true, routing.tool_density(&reasoning)));
+        assert!(routing.route_to_worker(false, routing.tool_density(&tool_heavy)));
+    }
+
+    #[tokio::test]
+    async fn test_density_routing_switches_models() {
+        let lead_provider = Arc::new(MockProvider {
+            name: "lead".to_string(),
+            model_config: ModelConfig::new("lead-model".to_string()),
+        });
+        let worker_provider = Arc::new(MockProvider {
+            name: "worker".to_string(),
+            model_config: ModelConfig::new("worker-model".to_string()),
+        });
+        let provider = LeadWorkerProvider::new(lead_provider, worker_provider, Some(1))
+            .with_density_routing(DensityRouting::default());
+
+        let (_message, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "lead");
+
+        let tool_heavy = vec![tool_turn(), tool_turn()];
+        let (_message, usage) = provider.complete("system", &tool_heavy, &[]).await.unwrap();
+        assert_eq!(usage.model, "worker");
+
+        let reasoning = vec![
+            Message::assistant().with_text("The failure comes from a race in the scheduler"),
+            Message::assistant().with_text("We should restructure the locking"),
+        ];
+        let (_message, usage) = provider.complete("system", &reasoning, &[]).await.unwrap();
+        assert_eq!(usage.model, "lead");
+    }
+
     #[tokio::test]
     async fn test_technical_failure_retry() {
         let lead_provider = Arc::new(MockFailureProvider {
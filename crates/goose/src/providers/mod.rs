@@ -1,28 +1,45 @@
 pub mod anthropic;
+pub mod attribution;
 pub mod azure;
 pub mod azureauth;
 pub mod base;
+pub mod batch;
 pub mod bedrock;
+pub mod conformance;
 pub mod databricks;
 pub mod embedding;
 pub mod errors;
 mod factory;
+pub mod fallback;
 pub mod formats;
 mod gcpauth;
 pub mod gcpvertexai;
 pub mod githubcopilot;
 pub mod google;
 pub mod groq;
+pub mod http_client;
 pub mod lead_worker;
+pub mod model_aliases;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod outbound_filter;
+pub mod payload_guard;
+pub mod pii;
+pub mod quota;
+pub mod rate_limit;
+pub mod retry;
 pub mod sagemaker_tgi;
 pub mod snowflake;
+pub mod speech;
 pub mod toolshim;
+pub mod transcription;
 pub mod utils;
 pub mod utils_universal_openai_stream;
 pub mod venice;
+pub mod verify;
+pub mod web_search;
 
-pub use factory::{create, providers};
+pub use factory::{create, create_bare, create_for_model, providers, with_request_filters};
+pub use verify::verify;
@@ -0,0 +1,186 @@
+//! Custom model names defined in config
+//!
+//! Fine-tunes and models behind proxies often have names goose does not recognise, so their
+//! context limit falls back to the default. `GOOSE_MODEL_ALIASES` names them and describes them:
+//!
+//! ```yaml
+//! GOOSE_MODEL_ALIASES:
+//!   - name: support-bot
+//!     provider: openai
+//!     model: ft:gpt-4o-mini-2024-07-18:acme::9abc123
+//!     context_limit: 128000
+//!     input_cost: 0.30
+//!     output_cost: 1.20
+//!     supports_tools: true
+//! ```
+//!
+//! Aliases are listed with their provider's known models, and when a provider is created for an
+//! alias the request goes to the underlying model with the alias's context limit. Costs are in
+//! USD per million tokens.
+use serde::{Deserialize, Serialize};
+
+use super::base::{ModelInfo, ProviderMetadata, Usage};
+use crate::config::Config;
+use crate::model::ModelConfig;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelAlias {
+    /// The name used in config and frontends
+    pub name: String,
+    /// The provider serving the model
+    pub provider: String,
+    /// The model name the provider expects, defaults to the alias name
+    #[serde(default)]
+    pub model: Option<String>,
+    pub context_limit: Option<usize>,
+    /// USD per million input tokens
+    pub input_cost: Option<f64>,
+    /// USD per million output tokens
+    pub output_cost: Option<f64>,
+    pub supports_tools: Option<bool>,
+    pub supports_vision: Option<bool>,
+}
+
+impl ModelAlias {
+    pub fn target_model(&self) -> &str {
+        self.model.as_deref().unwrap_or(&self.name)
+    }
+
+    pub fn model_info(&self) -> ModelInfo {
+        let context_limit = self
+            .context_limit
+            .unwrap_or_else(|| ModelConfig::new(self.target_model().to_string()).context_limit());
+        ModelInfo {
+            input_cost: self.input_cost,
+            output_cost: self.output_cost,
+            supports_tools: self.supports_tools,
+            supports_vision: self.supports_vision,
+            ..ModelInfo::new(&self.name, context_limit)
+        }
+    }
+
+    /// The cost of a request's usage in USD, if the alias has costs
+    pub fn cost(&self, usage: &Usage) -> Option<f64> {
+        let input = usage.input_tokens.unwrap_or(0) as f64 * self.input_cost?;
+        let output = usage.output_tokens.unwrap_or(0) as f64 * self.output_cost?;
+        Some((input + output) / 1_000_000.0)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelAliases {
+    aliases: Vec<ModelAlias>,
+}
+
+impl ModelAliases {
+    pub fn new(aliases: Vec<ModelAlias>) -> Self {
+        Self { aliases }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_MODEL_ALIASES")
+                .unwrap_or_default(),
+        )
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ModelAlias> {
+        self.aliases.iter().find(|alias| alias.name == name)
+    }
+
+    /// The alias a provider's model was resolved from, if any
+    pub fn find_target(&self, provider: &str, model: &str) -> Option<&ModelAlias> {
+        self.aliases
+            .iter()
+            .find(|alias| alias.provider == provider && alias.target_model() == model)
+    }
+
+    /// Add the aliases served by a provider to its known models
+    pub fn extend_metadata(&self, metadata: &mut ProviderMetadata) {
+        for alias in self.aliases.iter().filter(|a| a.provider == metadata.name) {
+            metadata
+                .known_models
+                .retain(|model| model.name != alias.name);
+            metadata.known_models.push(alias.model_info());
+        }
+    }
+
+    /// The provider and model config to use for a requested model. An alias switches to its
+    /// provider and underlying model with the alias's context limit.
+    pub fn resolve(&self, provider: &str, model: ModelConfig) -> (String, ModelConfig) {
+        let Some(alias) = self.get(&model.model_name) else {
+            return (provider.to_string(), model);
+        };
+        let mut resolved = ModelConfig::new(alias.target_model().to_string());
+        resolved.context_limit = alias
+            .context_limit
+            .or(model.context_limit)
+            .or(resolved.context_limit);
+        resolved.temperature = model.temperature;
+        resolved.max_tokens = model.max_tokens;
+        resolved.top_p = model.top_p;
+        resolved.toolshim = model.toolshim;
+        resolved.toolshim_model = model.toolshim_model;
+        (alias.provider.clone(), resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases() -> ModelAliases {
+        serde_yaml::from_str::<Vec<ModelAlias>>(
+            r#"
+- name: support-bot
+  provider: openai
+  model: ft:gpt-4o-mini-2024-07-18:acme::9abc123
+  context_limit: 64000
+  input_cost: 0.30
+  output_cost: 1.20
+  supports_tools: true
+- name: proxy-llama
+  provider: ollama
+"#,
+        )
+        .map(ModelAliases::new)
+        .unwrap()
+    }
+
+    #[test]
+    fn test_resolve_alias() {
+        let aliases = aliases();
+        let (provider, model) = aliases.resolve(
+            "anthropic",
+            ModelConfig::new("support-bot".to_string()).with_max_tokens(Some(100)),
+        );
+        assert_eq!(provider, "openai");
+        assert_eq!(model.model_name, "ft:gpt-4o-mini-2024-07-18:acme::9abc123");
+        assert_eq!(model.context_limit(), 64_000);
+        assert_eq!(model.max_tokens, Some(100));
+
+        let (provider, model) = aliases.resolve(
+            "anthropic",
+            ModelConfig::new("claude-3-5-sonnet".to_string()),
+        );
+        assert_eq!(provider, "anthropic");
+        assert_eq!(model.model_name, "claude-3-5-sonnet");
+    }
+
+    #[test]
+    fn test_aliases_extend_metadata_and_cost() {
+        let aliases = aliases();
+        let mut metadata = ProviderMetadata::empty();
+        metadata.name = "openai".to_string();
+        aliases.extend_metadata(&mut metadata);
+        assert_eq!(metadata.known_models.len(), 1);
+        assert_eq!(metadata.known_models[0].name, "support-bot");
+        assert_eq!(metadata.known_models[0].context_limit, 64_000);
+
+        let usage = Usage::new(Some(1_000_000), Some(500_000), None);
+        let cost = aliases.get("support-bot").unwrap().cost(&usage).unwrap();
+        assert!((cost - 0.9).abs() < 1e-9);
+        assert_eq!(aliases.get("proxy-llama").unwrap().cost(&usage), None);
+    }
+}
@@ -1,5 +1,10 @@
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
+use super::http_client;
+use super::toolshim::{
+    augment_message_with_tool_calls, convert_tool_messages_to_text,
+    modify_system_prompt_for_tool_json, OllamaInterpreter,
+};
 use super::utils::{get_model, handle_response_openai_compat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -9,6 +14,8 @@ use async_trait::async_trait;
 use mcp_core::tool::Tool;
 use reqwest::Client;
 use serde_json::Value;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
 
@@ -25,6 +32,10 @@ pub struct OllamaProvider {
     client: Client,
     host: String,
     model: ModelConfig,
+    /// Set once the model has rejected a request with tools, after which tool calls are
+    /// interpreted from its text with toolshim
+    #[serde(skip)]
+    tools_unsupported: AtomicBool,
 }
 
 impl Default for OllamaProvider {
@@ -41,7 +52,7 @@ impl OllamaProvider {
             .get_param("OLLAMA_HOST")
             .unwrap_or_else(|_| OLLAMA_HOST.to_string());
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -49,6 +60,7 @@ impl OllamaProvider {
             client,
             host,
             model,
+            tools_unsupported: AtomicBool::new(false),
         })
     }
 
@@ -92,6 +104,72 @@ impl OllamaProvider {
 
         handle_response_openai_compat(response).await
     }
+
+    async fn request(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let payload = create_request(
+            &self.model,
+            system,
+            messages,
+            tools,
+            &super::utils::ImageFormat::OpenAi,
+        )?;
+
+        let response = self.post(payload.clone()).await?;
+        let message = response_to_message(response.clone())?;
+
+        let usage = match get_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+        super::utils::emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok((message, ProviderUsage::new(model, usage)))
+    }
+
+    /// Describe the tools in the system prompt instead of sending them, and interpret the
+    /// tool calls from the text of the response
+    async fn request_with_toolshim(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let system = modify_system_prompt_for_tool_json(system, tools);
+        let messages = convert_tool_messages_to_text(messages);
+        let (message, usage) = self.request(&system, &messages, &[]).await?;
+
+        let interpreter = OllamaInterpreter::new()?;
+        let message = augment_message_with_tool_calls(&interpreter, message, tools).await?;
+        Ok((message, usage))
+    }
+}
+
+/// Ollama rejects requests with tools for models without tool support, with an error like
+/// "registry.ollama.ai/library/gemma2:latest does not support tools"
+fn is_tools_unsupported(error: &ProviderError) -> bool {
+    matches!(error, ProviderError::RequestFailed(message) if message.contains("does not support tools"))
+}
+
+/// The names of the models in a response of the `/api/tags` endpoint
+fn tag_names(response: &Value) -> Option<Vec<String>> {
+    let mut models: Vec<String> = response
+        .get("models")?
+        .as_array()?
+        .iter()
+        .filter_map(|model| model.get("name").and_then(|name| name.as_str()))
+        .map(String::from)
+        .collect();
+    models.sort();
+    Some(models)
 }
 
 #[async_trait]
@@ -117,6 +195,13 @@ impl Provider for OllamaProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -127,27 +212,68 @@ impl Provider for OllamaProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(
-            &self.model,
-            system,
-            messages,
-            tools,
-            &super::utils::ImageFormat::OpenAi,
-        )?;
+        if tools.is_empty() {
+            return self.request(system, messages, tools).await;
+        }
+        if self.tools_unsupported.load(Ordering::Relaxed) {
+            return self.request_with_toolshim(system, messages, tools).await;
+        }
+        match self.request(system, messages, tools).await {
+            Err(e) if is_tools_unsupported(&e) => {
+                tracing::info!(
+                    "Model {} does not support tools, interpreting tool calls with toolshim",
+                    self.model.model_name
+                );
+                self.tools_unsupported.store(true, Ordering::Relaxed);
+                self.request_with_toolshim(system, messages, tools).await
+            }
+            result => result,
+        }
+    }
 
-        let response = self.post(payload.clone()).await?;
-        let message = response_to_message(response.clone())?;
+    /// Fetch the models pulled on the Ollama host from its /api/tags endpoint
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        let url = self.get_base_url()?.join("api/tags").map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+        let response = self.client.get(url).send().await?;
+        let json: Value = response.json().await?;
+        Ok(tag_names(&json))
+    }
+}
 
-        let usage = match get_usage(&response) {
-            Ok(usage) => usage,
-            Err(ProviderError::UsageError(e)) => {
-                tracing::debug!("Failed to get usage data: {}", e);
-                Usage::default()
-            }
-            Err(e) => return Err(e),
-        };
-        let model = get_model(&response);
-        super::utils::emit_debug_trace(&self.model, &payload, &response, &usage);
-        Ok((message, ProviderUsage::new(model, usage)))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tag_names() {
+        let response = json!({
+            "models": [
+                {"name": "qwen2.5:latest", "size": 4683087332u64},
+                {"name": "llama3.2:3b", "size": 2019393189u64}
+            ]
+        });
+        assert_eq!(
+            tag_names(&response),
+            Some(vec![
+                "llama3.2:3b".to_string(),
+                "qwen2.5:latest".to_string()
+            ])
+        );
+        assert_eq!(tag_names(&json!({})), None);
+    }
+
+    #[test]
+    fn test_tools_unsupported_error() {
+        let error = ProviderError::RequestFailed(
+            "registry.ollama.ai/library/gemma2:latest does not support tools (status 400)"
+                .to_string(),
+        );
+        assert!(is_tools_unsupported(&error));
+        assert!(!is_tools_unsupported(&ProviderError::RequestFailed(
+            "model not found (status 404)".to_string()
+        )));
     }
 }
@@ -1,16 +1,25 @@
 use anyhow::Result;
+use async_stream::try_stream;
 use async_trait::async_trait;
+use futures::StreamExt;
 use reqwest::Client;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{ConfigKey, MessageStream, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::batch::{BatchProviderTrait, BatchRequest, BatchResult, BatchStatus};
 use super::embedding::{EmbeddingCapable, EmbeddingRequest, EmbeddingResponse};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::formats::openai::{
+    add_web_search_options, create_request, get_usage, response_to_message, supports_web_search,
+};
+use super::http_client;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
-use crate::message::Message;
+use super::utils_universal_openai_stream::{OAIStreamChunk, OAIStreamCollector};
+use super::web_search::WebSearchConfig;
+use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use mcp_core::tool::Tool;
 
@@ -38,6 +47,8 @@ pub struct OpenAiProvider {
     project: Option<String>,
     model: ModelConfig,
     custom_headers: Option<HashMap<String, String>>,
+    #[serde(skip)]
+    web_search: WebSearchConfig,
 }
 
 impl Default for OpenAiProvider {
@@ -65,7 +76,7 @@ impl OpenAiProvider {
             .ok()
             .map(parse_custom_headers);
         let timeout_secs: u64 = config.get_param("OPENAI_TIMEOUT").unwrap_or(600);
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(timeout_secs))
             .build()?;
 
@@ -78,6 +89,7 @@ impl OpenAiProvider {
             project,
             model,
             custom_headers,
+            web_search: WebSearchConfig::from_config(),
         })
     }
 
@@ -103,7 +115,7 @@ impl OpenAiProvider {
         request
     }
 
-    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+    async fn send(&self, payload: &Value) -> Result<reqwest::Response, ProviderError> {
         let base_url = url::Url::parse(&self.host)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
         let url = base_url.join(&self.base_path).map_err(|e| {
@@ -117,10 +129,78 @@ impl OpenAiProvider {
 
         let request = self.add_headers(request);
 
-        let response = request.json(&payload).send().await?;
+        Ok(request.json(payload).send().await?)
+    }
+
+    fn endpoint(&self, path: &str) -> Result<url::Url, ProviderError> {
+        url::Url::parse(&self.host)
+            .and_then(|base_url| base_url.join(path))
+            .map_err(|e| {
+                ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+            })
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.add_headers(request.header("Authorization", format!("Bearer {}", self.api_key)))
+    }
 
+    /// The text of an uploaded file, such as the output of a batch
+    async fn file_content(&self, file_id: &str) -> Result<String, ProviderError> {
+        let url = self.endpoint(&format!("v1/files/{}/content", file_id))?;
+        let response = self.authorized(self.client.get(url)).send().await?;
+        if !response.status().is_success() {
+            return Err(ProviderError::RequestFailed(format!(
+                "Failed to download file {}: status {}",
+                file_id,
+                response.status()
+            )));
+        }
+        Ok(response.text().await?)
+    }
+
+    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+        let response = self.send(&payload).await?;
         handle_response_openai_compat(response).await
     }
+
+    fn create_payload(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<Value, ProviderError> {
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        if self.web_search.enabled && supports_web_search(&self.model.model_name) {
+            add_web_search_options(&mut payload);
+        }
+        Ok(payload)
+    }
+}
+
+/// The chunk carried by one line of a streamed response, if the line has one
+fn stream_chunk(line: &[u8]) -> Result<Option<OAIStreamChunk>, ProviderError> {
+    let line = String::from_utf8_lossy(line);
+    let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+        return Ok(None);
+    };
+    if data.is_empty() || data == "[DONE]" {
+        return Ok(None);
+    }
+    let value: Value = serde_json::from_str(data)
+        .map_err(|e| ProviderError::RequestFailed(format!("Invalid stream chunk: {e}")))?;
+    if let Some(error) = value.get("error") {
+        let message = error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(ProviderError::RequestFailed(format!(
+            "Stream failed: {message}"
+        )));
+    }
+    serde_json::from_value(value)
+        .map(Some)
+        .map_err(|e| ProviderError::RequestFailed(format!("Invalid stream chunk: {e}")))
 }
 
 #[async_trait]
@@ -143,12 +223,20 @@ impl Provider for OpenAiProvider {
                 ConfigKey::new("OPENAI_TIMEOUT", false, false, Some("600")),
             ],
         )
+        .with_web_search()
     }
 
     fn get_model_config(&self) -> ModelConfig {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -159,7 +247,7 @@ impl Provider for OpenAiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        let payload = self.create_payload(system, messages, tools)?;
 
         // Make request
         let response = self.post(payload.clone()).await?;
@@ -179,6 +267,78 @@ impl Provider for OpenAiProvider {
         Ok((message, ProviderUsage::new(model, usage)))
     }
 
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let mut payload = self.create_payload(system, messages, tools)?;
+        let object = payload.as_object_mut().unwrap();
+        object.insert("stream".to_string(), json!(true));
+        object.insert("stream_options".to_string(), json!({"include_usage": true}));
+
+        let response = self.send(&payload).await?;
+        let status = response.status();
+        if !status.is_success() {
+            // Errors come back as a regular JSON response
+            return Err(match handle_response_openai_compat(response).await {
+                Err(e) => e,
+                Ok(_) => {
+                    ProviderError::RequestFailed(format!("Request failed with status: {}", status))
+                }
+            });
+        }
+
+        let model_config = self.model.clone();
+        Ok(Box::pin(try_stream! {
+            let mut collector = OAIStreamCollector::new();
+            let mut pending: Vec<u8> = Vec::new();
+            let mut bytes = response.bytes_stream();
+            while let Some(received) = bytes.next().await {
+                let received = received.map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+                pending.extend_from_slice(&received);
+                // Lines can be split across network chunks, so only complete ones are parsed
+                while let Some(end) = pending.iter().position(|byte| *byte == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    let Some(chunk) = stream_chunk(&line)? else {
+                        continue;
+                    };
+                    collector.add_chunk(&chunk);
+                    for choice in chunk.choices.iter().filter(|choice| choice.index == 0) {
+                        if let Some(text) = choice.delta.content.as_deref().filter(|t| !t.is_empty()) {
+                            yield (Some(Message::assistant().with_text(text)), None);
+                        }
+                    }
+                }
+            }
+
+            let response = serde_json::to_value(collector.build_response())
+                .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
+            let mut message = response_to_message(response.clone())?;
+            let usage = match get_usage(&response) {
+                Ok(usage) => usage,
+                Err(e) => {
+                    tracing::debug!("Failed to get usage data: {}", e);
+                    Usage::default()
+                }
+            };
+            let model = get_model(&response);
+            emit_debug_trace(&model_config, &payload, &response, &usage);
+
+            // The text has been streamed, the tool requests follow whole
+            message
+                .content
+                .retain(|content| !matches!(content, MessageContent::Text(_)));
+            let rest = (!message.content.is_empty()).then_some(message);
+            yield (rest, Some(ProviderUsage::new(model, usage)));
+        }))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        true
+    }
+
     /// Fetch supported models from OpenAI; returns Err on any failure, Ok(None) if no data
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         // List available models via OpenAI API
@@ -223,6 +383,10 @@ impl Provider for OpenAiProvider {
         true
     }
 
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        Some(self)
+    }
+
     async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
         EmbeddingCapable::create_embeddings(self, texts)
             .await
@@ -230,6 +394,113 @@ impl Provider for OpenAiProvider {
     }
 }
 
+/// Batches go through the files and batches endpoints of the same host, each request as one
+/// line of an uploaded JSONL file
+#[async_trait]
+impl BatchProviderTrait for OpenAiProvider {
+    async fn submit_batch(&self, requests: &[BatchRequest]) -> Result<String, ProviderError> {
+        let endpoint = format!("/{}", self.base_path.trim_start_matches('/'));
+        let mut lines = Vec::with_capacity(requests.len());
+        for request in requests {
+            let body = self.create_payload(&request.system, &request.messages, &[])?;
+            lines.push(
+                json!({
+                    "custom_id": request.custom_id,
+                    "method": "POST",
+                    "url": endpoint,
+                    "body": body,
+                })
+                .to_string(),
+            );
+        }
+
+        let file =
+            reqwest::multipart::Part::bytes(lines.join("\n").into_bytes()).file_name("batch.jsonl");
+        let form = reqwest::multipart::Form::new()
+            .text("purpose", "batch")
+            .part("file", file);
+        let response = self
+            .authorized(self.client.post(self.endpoint("v1/files")?))
+            .multipart(form)
+            .send()
+            .await?;
+        let uploaded = handle_response_openai_compat(response).await?;
+        let file_id = uploaded["id"].as_str().ok_or_else(|| {
+            ProviderError::RequestFailed("Batch file upload returned no file id".to_string())
+        })?;
+
+        let response = self
+            .authorized(self.client.post(self.endpoint("v1/batches")?))
+            .json(&json!({
+                "input_file_id": file_id,
+                "endpoint": endpoint,
+                "completion_window": "24h",
+            }))
+            .send()
+            .await?;
+        let batch = handle_response_openai_compat(response).await?;
+        batch["id"].as_str().map(str::to_string).ok_or_else(|| {
+            ProviderError::RequestFailed("Batch creation returned no id".to_string())
+        })
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, ProviderError> {
+        let url = self.endpoint(&format!("v1/batches/{}", batch_id))?;
+        let response = self.authorized(self.client.get(url)).send().await?;
+        let batch = handle_response_openai_compat(response).await?;
+        let status = batch["status"].as_str().unwrap_or_default();
+        Ok(match status {
+            "completed" => BatchStatus::Completed,
+            "failed" | "expired" | "cancelling" | "cancelled" => {
+                let reason = batch["errors"]["data"][0]["message"]
+                    .as_str()
+                    .unwrap_or(status);
+                BatchStatus::Failed(reason.to_string())
+            }
+            _ => BatchStatus::InProgress {
+                completed: batch["request_counts"]["completed"].as_u64().unwrap_or(0) as usize,
+                total: batch["request_counts"]["total"].as_u64().unwrap_or(0) as usize,
+            },
+        })
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, ProviderError> {
+        let url = self.endpoint(&format!("v1/batches/{}", batch_id))?;
+        let response = self.authorized(self.client.get(url)).send().await?;
+        let batch = handle_response_openai_compat(response).await?;
+
+        // Successful requests are in the output file, failed ones in the error file
+        let mut results = Vec::new();
+        for key in ["output_file_id", "error_file_id"] {
+            let Some(file_id) = batch[key].as_str() else {
+                continue;
+            };
+            let content = self.file_content(file_id).await?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                let entry: Value = serde_json::from_str(line).map_err(|e| {
+                    ProviderError::RequestFailed(format!("Invalid batch output: {e}"))
+                })?;
+                let custom_id = entry["custom_id"].as_str().unwrap_or_default().to_string();
+                let body = &entry["response"]["body"];
+                let response = if entry["response"]["status_code"].as_u64() == Some(200) {
+                    response_to_message(body.clone()).map_err(|e| e.to_string())
+                } else {
+                    Err(body["error"]["message"]
+                        .as_str()
+                        .or_else(|| entry["error"]["message"].as_str())
+                        .unwrap_or("The request failed")
+                        .to_string())
+                };
+                results.push(BatchResult {
+                    custom_id,
+                    response,
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
 fn parse_custom_headers(s: String) -> HashMap<String, String> {
     s.split(',')
         .filter_map(|header| {
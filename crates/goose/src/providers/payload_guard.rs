@@ -0,0 +1,434 @@
+//! Hard caps on the size of requests sent to a provider
+//!
+//! An accidental paste of a huge log or a tool that returns a whole database dump can make a
+//! request that the provider rejects as too large, or that costs far more than intended. With
+//! `GOOSE_PAYLOAD_LIMITS` every request is measured before it is sent, and one over a limit
+//! fails with an error that names its largest sections. Limits are set per provider, with
+//! `default` for the providers that are not listed:
+//!
+//! ```yaml
+//! GOOSE_PAYLOAD_LIMITS:
+//!   default:
+//!     max_bytes: 5000000
+//!   anthropic:
+//!     max_bytes: 30000000
+//!     max_tokens: 180000
+//!   ollama:
+//!     max_tokens: 30000
+//! ```
+//!
+//! Bytes are those of the JSON the request is made of, which is close to what goes over the
+//! wire for every provider format. Tokens are counted with the model's tokenizer. Each request
+//! of a batch is held to the limits on its own.
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mcp_core::Tool;
+use serde::{Deserialize, Serialize};
+use tokio::sync::OnceCell;
+
+use super::base::{
+    FallbackProviderTrait, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage,
+};
+use super::batch::{inner_batch, BatchProviderTrait, BatchRequest, BatchResult, BatchStatus};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::token_counter::TokenCounter;
+
+/// Key of the limits for providers without their own
+const DEFAULT_KEY: &str = "default";
+/// Sections named in the error when a request is over a limit
+const LARGEST_SECTIONS: usize = 3;
+/// Characters of a section shown to help find it
+const PREVIEW_CHARS: usize = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PayloadLimit {
+    pub max_bytes: Option<usize>,
+    pub max_tokens: Option<usize>,
+}
+
+impl PayloadLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_bytes.is_none() && self.max_tokens.is_none()
+    }
+
+    /// The limit for a provider from `GOOSE_PAYLOAD_LIMITS`
+    pub fn from_config(provider_name: &str) -> Self {
+        let limits: HashMap<String, PayloadLimit> = Config::global()
+            .get_param("GOOSE_PAYLOAD_LIMITS")
+            .unwrap_or_default();
+        limits
+            .get(provider_name)
+            .or_else(|| limits.get(DEFAULT_KEY))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// One part of a request and its size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayloadSection {
+    pub name: String,
+    /// The start of the section's text
+    pub preview: String,
+    pub bytes: usize,
+    pub tokens: Option<usize>,
+}
+
+fn preview(text: &str) -> String {
+    let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}...", &line[..end]),
+        None => line,
+    }
+}
+
+/// What a message is made of, to tell the user which part of it to look at
+fn describe_message(index: usize, message: &Message) -> (String, String) {
+    let role = match message.role {
+        mcp_core::Role::User => "user",
+        mcp_core::Role::Assistant => "assistant",
+    };
+    let mut name = format!("message {} ({})", index + 1, role);
+    let mut text = message.as_concat_text();
+    for content in &message.content {
+        match content {
+            MessageContent::ToolResponse(response) => {
+                name = format!("{}, result of tool call {}", name, response.id);
+                if let Ok(result) = &response.tool_result {
+                    text = result
+                        .iter()
+                        .filter_map(|content| content.as_text())
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                }
+                break;
+            }
+            MessageContent::ToolRequest(request) => {
+                if let Ok(call) = &request.tool_call {
+                    name = format!("{}, call to {}", name, call.name);
+                    text = call.arguments.to_string();
+                }
+                break;
+            }
+            _ => {}
+        }
+    }
+    (name, preview(&text))
+}
+
+/// Measure the sections of a request
+pub fn measure(
+    system: &str,
+    messages: &[Message],
+    tools: &[Tool],
+    counter: Option<&TokenCounter>,
+) -> Vec<PayloadSection> {
+    let mut sections = vec![PayloadSection {
+        name: "system prompt".to_string(),
+        preview: preview(system),
+        bytes: system.len(),
+        tokens: counter.map(|counter| counter.count_tokens(system)),
+    }];
+    if !tools.is_empty() {
+        sections.push(PayloadSection {
+            name: format!("{} tool definitions", tools.len()),
+            preview: String::new(),
+            bytes: serde_json::to_string(tools).map_or(0, |json| json.len()),
+            tokens: counter.map(|counter| counter.count_tokens_for_tools(tools)),
+        });
+    }
+    for (index, message) in messages.iter().enumerate() {
+        let (name, preview) = describe_message(index, message);
+        let json = serde_json::to_string(message).unwrap_or_default();
+        sections.push(PayloadSection {
+            name,
+            preview,
+            bytes: json.len(),
+            tokens: counter.map(|counter| counter.count_tokens(&json)),
+        });
+    }
+    sections
+}
+
+/// An actionable error if the sections are over the limit, naming the largest of them
+pub fn check(limit: &PayloadLimit, sections: &[PayloadSection]) -> Result<(), ProviderError> {
+    let bytes: usize = sections.iter().map(|section| section.bytes).sum();
+    let tokens: usize = sections.iter().filter_map(|section| section.tokens).sum();
+
+    let (total, max, unit) = match (limit.max_bytes, limit.max_tokens) {
+        (Some(max), _) if bytes > max => (bytes, max, "bytes"),
+        (_, Some(max)) if tokens > max => (tokens, max, "tokens"),
+        _ => return Ok(()),
+    };
+    let size = |section: &PayloadSection| match unit {
+        "bytes" => section.bytes,
+        _ => section.tokens.unwrap_or_default(),
+    };
+
+    let mut largest: Vec<&PayloadSection> = sections.iter().collect();
+    largest.sort_by_key(|section| std::cmp::Reverse(size(section)));
+    let listed: Vec<String> = largest
+        .iter()
+        .take(LARGEST_SECTIONS)
+        .map(|section| {
+            let mut line = format!("  - {}: {} {}", section.name, size(section), unit);
+            if !section.preview.is_empty() {
+                line.push_str(&format!(" \"{}\"", section.preview));
+            }
+            line
+        })
+        .collect();
+    Err(ProviderError::PayloadTooLarge(format!(
+        "the request is {} {}, over the limit of {}. The largest parts are:\n{}\n\
+         Shorten or remove them, e.g. by summarizing the conversation or starting a new session, \
+         or raise the limit in GOOSE_PAYLOAD_LIMITS",
+        total,
+        unit,
+        max,
+        listed.join("\n")
+    )))
+}
+
+/// A provider that refuses requests over the configured limits before they are sent
+pub struct PayloadGuardProvider {
+    inner: Arc<dyn Provider>,
+    limit: PayloadLimit,
+    counter: OnceCell<Option<Arc<TokenCounter>>>,
+}
+
+impl PayloadGuardProvider {
+    pub fn new(inner: Arc<dyn Provider>, limit: PayloadLimit) -> Self {
+        Self {
+            inner,
+            limit,
+            counter: OnceCell::new(),
+        }
+    }
+
+    /// Wrap a provider in its configured limits, if it has any
+    pub fn wrap_from_config(provider_name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let limit = PayloadLimit::from_config(provider_name);
+        if limit.is_unlimited() {
+            return provider;
+        }
+        Arc::new(Self::new(provider, limit))
+    }
+
+    /// The tokenizer of the model, loaded off the async runtime the first time it is needed
+    async fn counter(&self) -> Option<Arc<TokenCounter>> {
+        self.limit.max_tokens?;
+        self.counter
+            .get_or_init(|| async {
+                let model_config = self.inner.get_model_config();
+                tokio::task::spawn_blocking(move || {
+                    Arc::new(TokenCounter::for_model(&model_config))
+                })
+                .await
+                .map_err(|e| tracing::warn!("Failed to load tokenizer for payload limits: {}", e))
+                .ok()
+            })
+            .await
+            .clone()
+    }
+}
+
+#[async_trait]
+impl Provider for PayloadGuardProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "payload_guard",
+            "Payload Guard Provider",
+            "A provider that refuses requests over the configured size limits",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let counter = self.counter().await;
+        let sections = measure(system, messages, tools, counter.as_deref());
+        check(&self.limit, &sections)?;
+        self.inner.complete(system, messages, tools).await
+    }
+
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let counter = self.counter().await;
+        let sections = measure(system, messages, tools, counter.as_deref());
+        check(&self.limit, &sections)?;
+        self.inner.complete_streaming(system, messages, tools).await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> anyhow::Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::new(
+            self.inner.with_model_override(update)?,
+            self.limit,
+        )))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.inner.supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        if let Some(max_bytes) = self.limit.max_bytes {
+            let bytes: usize = texts.iter().map(String::len).sum();
+            if bytes > max_bytes {
+                return Err(ProviderError::PayloadTooLarge(format!(
+                    "the {} texts to embed are {} bytes, over the limit of {}",
+                    texts.len(),
+                    bytes,
+                    max_bytes
+                )));
+            }
+        }
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        self.inner.as_fallback()
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.inner
+            .as_batch()
+            .map(|_| self as &dyn BatchProviderTrait)
+    }
+}
+
+/// The batch API of the wrapped provider, refusing a batch with a request over the limits
+#[async_trait]
+impl BatchProviderTrait for PayloadGuardProvider {
+    async fn submit_batch(&self, requests: &[BatchRequest]) -> Result<String, ProviderError> {
+        let counter = self.counter().await;
+        for request in requests {
+            // Batch requests are answered without tools
+            let sections = measure(&request.system, &request.messages, &[], counter.as_deref());
+            check(&self.limit, &sections).map_err(|e| match e {
+                ProviderError::PayloadTooLarge(reason) => ProviderError::PayloadTooLarge(format!(
+                    "batch request {}: {}",
+                    request.custom_id, reason
+                )),
+                e => e,
+            })?;
+        }
+        inner_batch(self.inner.as_ref())?
+            .submit_batch(requests)
+            .await
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, ProviderError> {
+        inner_batch(self.inner.as_ref())?
+            .batch_status(batch_id)
+            .await
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, ProviderError> {
+        inner_batch(self.inner.as_ref())?
+            .batch_results(batch_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GPT_4O_TOKENIZER;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_largest_sections_are_named() {
+        let paste = "error: connection refused\n".repeat(1000);
+        let messages = vec![
+            Message::user().with_text("Why does the build fail?"),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new(
+                    "developer__shell",
+                    json!({"command": "cargo build"}),
+                )),
+            ),
+            Message::user().with_tool_response("call_1", Ok(vec![mcp_core::Content::text(paste)])),
+        ];
+        let sections = measure("You are goose.", &messages, &[], None);
+        assert_eq!(sections.len(), 4);
+
+        let unlimited = PayloadLimit::default();
+        assert!(check(&unlimited, &sections).is_ok());
+
+        let limit = PayloadLimit {
+            max_bytes: Some(10_000),
+            max_tokens: None,
+        };
+        let Err(ProviderError::PayloadTooLarge(message)) = check(&limit, &sections) else {
+            panic!("expected the request to be over the limit");
+        };
+        let first = message.lines().nth(1).unwrap();
+        assert!(first.contains("message 3 (user), result of tool call call_1"));
+        assert!(first.contains("\"error: connection refused error: connection refused"));
+        assert!(message.contains("GOOSE_PAYLOAD_LIMITS"));
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let counter = TokenCounter::new(GPT_4O_TOKENIZER);
+        let messages = vec![Message::user().with_text("word ".repeat(500))];
+        let sections = measure("", &messages, &[], Some(&counter));
+        let tokens: usize = sections.iter().filter_map(|s| s.tokens).sum();
+        assert!(tokens >= 500);
+
+        let over = PayloadLimit {
+            max_bytes: None,
+            max_tokens: Some(100),
+        };
+        assert!(check(&over, &sections).is_err());
+        let under = PayloadLimit {
+            max_bytes: Some(1_000_000),
+            max_tokens: Some(tokens),
+        };
+        assert!(check(&under, &sections).is_ok());
+    }
+}
@@ -0,0 +1,590 @@
+//! Keeps personal information in the conversation from reaching the provider
+//!
+//! With `GOOSE_PII_PROTECTION` enabled, emails, phone numbers and names found in user input are
+//! replaced with stable placeholders such as `<EMAIL_1>` before anything is sent, and the same
+//! value always gets the same placeholder. Values are only replaced where they stand on their own,
+//! so a name is not replaced inside a longer word. The provider's response has the real values put
+//! back, so tool calls run locally with them. This holds for streamed responses and batches as
+//! well. Names are those introduced with "my name is" and any listed in `GOOSE_PII_NAMES`:
+//!
+//! ```yaml
+//! GOOSE_PII_PROTECTION: true
+//! GOOSE_PII_NAMES:
+//!   - Jane Doe
+//! ```
+//!
+//! The mapping only lives in memory, for as long as the provider.
+use std::sync::{Arc, Mutex};
+
+use async_stream::try_stream;
+use async_trait::async_trait;
+use futures::StreamExt;
+use mcp_core::role::Role;
+use mcp_core::{Content, Tool};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use super::base::{
+    FallbackProviderTrait, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage,
+};
+use super::batch::{inner_batch, BatchProviderTrait, BatchRequest, BatchResult, BatchStatus};
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+use crate::model::ModelConfig;
+
+static EMAIL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap());
+// A country code, or else separators between the groups, so plain ten digit numbers such as
+// ids and timestamps are not taken for phone numbers
+static PHONE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"(?:\+\d{1,3}[\s.-]?(?:\(\d{3}\)|\d{3})[\s.-]?\d{3}[\s.-]?\d{4}",
+        r"|(?:\(\d{3}\)\s?|\b\d{3}[\s.-])\d{3}[\s.-]\d{4})\b"
+    ))
+    .unwrap()
+});
+static INTRODUCED_NAME: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i:my name is)\s+([A-Z][a-z]+(?:\s+[A-Z][a-z]+)?)").unwrap());
+
+/// Longest text from an unclosed `<` that a streamed response holds back, as it may be the start
+/// of a placeholder that the next part completes
+const MAX_PLACEHOLDER_CHARS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Name,
+}
+
+impl PiiKind {
+    fn label(&self) -> &'static str {
+        match self {
+            PiiKind::Email => "EMAIL",
+            PiiKind::Phone => "PHONE",
+            PiiKind::Name => "NAME",
+        }
+    }
+}
+
+/// Personal information found in a piece of text
+pub fn detect_pii(text: &str, names: &[String]) -> Vec<(PiiKind, String)> {
+    let mut found: Vec<(PiiKind, String)> = Vec::new();
+    for m in EMAIL.find_iter(text) {
+        found.push((PiiKind::Email, m.as_str().to_string()));
+    }
+    for m in PHONE.find_iter(text) {
+        found.push((PiiKind::Phone, m.as_str().to_string()));
+    }
+    for captures in INTRODUCED_NAME.captures_iter(text) {
+        found.push((PiiKind::Name, captures[1].to_string()));
+    }
+    for name in names {
+        if !name.trim().is_empty() && text.contains(name.trim()) {
+            found.push((PiiKind::Name, name.trim().to_string()));
+        }
+    }
+    found
+}
+
+/// A pattern for a value that only matches where it stands on its own
+fn bounded(value: &str) -> String {
+    let boundary = |c: Option<char>| match c {
+        Some(c) if c.is_alphanumeric() || c == '_' => r"\b",
+        _ => "",
+    };
+    format!(
+        "{}{}{}",
+        boundary(value.chars().next()),
+        regex::escape(value),
+        boundary(value.chars().last())
+    )
+}
+
+/// Where the text stops being safe to restore: at an unclosed `<` near its end, or at its end
+fn unfinished_placeholder(text: &str) -> usize {
+    let Some(start) = text.rfind('<') else {
+        return text.len();
+    };
+    let tail = &text[start..];
+    if tail.contains('>') || tail.len() >= MAX_PLACEHOLDER_CHARS {
+        text.len()
+    } else {
+        start
+    }
+}
+
+/// The placeholders handed out so far and the values they stand for
+#[derive(Default, Clone)]
+pub struct PiiVault {
+    names: Vec<String>,
+    entries: Vec<(String, String)>,
+    /// Matches any of the values, rebuilt when one is added
+    pattern: Option<Regex>,
+}
+
+// Never print the values
+impl std::fmt::Debug for PiiVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PiiVault")
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl PiiVault {
+    pub fn new(names: Vec<String>) -> Self {
+        Self {
+            names,
+            entries: Vec::new(),
+            pattern: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The placeholder for a value, handing out a new one the first time it is seen
+    fn placeholder(&mut self, kind: PiiKind, value: &str) -> String {
+        if let Some((placeholder, _)) = self.entries.iter().find(|(_, v)| v == value) {
+            return placeholder.clone();
+        }
+        let count = self
+            .entries
+            .iter()
+            .filter(|(placeholder, _)| placeholder.starts_with(&format!("<{}_", kind.label())))
+            .count();
+        let placeholder = format!("<{}_{}>", kind.label(), count + 1);
+        self.entries.push((placeholder.clone(), value.to_string()));
+        self.pattern = Some(self.build_pattern());
+        placeholder
+    }
+
+    fn build_pattern(&self) -> Regex {
+        let mut values: Vec<&str> = self
+            .entries
+            .iter()
+            .map(|(_, value)| value.as_str())
+            .collect();
+        // Longest first, so a name inside an email is replaced as part of the email
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+        let pattern = values
+            .into_iter()
+            .map(bounded)
+            .collect::<Vec<_>>()
+            .join("|");
+        Regex::new(&pattern).expect("escaped values make a valid pattern")
+    }
+
+    /// Add what is found in user input to the vault
+    pub fn learn(&mut self, text: &str) {
+        for (kind, value) in detect_pii(text, &self.names) {
+            self.placeholder(kind, &value);
+        }
+    }
+
+    /// Replace every known value with its placeholder, where it stands on its own
+    pub fn substitute(&self, text: &str) -> String {
+        let Some(values) = &self.pattern else {
+            return text.to_string();
+        };
+        values
+            .replace_all(text, |captures: &Captures| {
+                let matched = &captures[0];
+                self.entries
+                    .iter()
+                    .find(|(_, value)| value == matched)
+                    .map(|(placeholder, _)| placeholder.clone())
+                    .unwrap_or_else(|| matched.to_string())
+            })
+            .into_owned()
+    }
+
+    /// Put the real values back in place of their placeholders
+    pub fn restore(&self, text: &str) -> String {
+        self.entries
+            .iter()
+            .fold(text.to_string(), |text, (placeholder, value)| {
+                text.replace(placeholder, value)
+            })
+    }
+
+    fn map_value(value: &mut Value, f: &impl Fn(&str) -> String) {
+        match value {
+            Value::String(text) => *text = f(text),
+            Value::Array(values) => values
+                .iter_mut()
+                .for_each(|value| Self::map_value(value, f)),
+            Value::Object(map) => map.values_mut().for_each(|value| Self::map_value(value, f)),
+            _ => {}
+        }
+    }
+
+    fn map_message(message: &mut Message, f: &impl Fn(&str) -> String) {
+        for content in message.content.iter_mut() {
+            match content {
+                MessageContent::Text(text) => text.text = f(&text.text),
+                MessageContent::ToolRequest(request) => {
+                    if let Ok(tool_call) = &mut request.tool_call {
+                        Self::map_value(&mut tool_call.arguments, f);
+                    }
+                }
+                MessageContent::ToolResponse(response) => {
+                    if let Ok(contents) = &mut response.tool_result {
+                        for content in contents.iter_mut() {
+                            if let Content::Text(text) = content {
+                                text.text = f(&text.text);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Learn from the user's messages and return the request with placeholders substituted
+    pub fn protect_request(
+        &mut self,
+        system: &str,
+        messages: &[Message],
+    ) -> (String, Vec<Message>) {
+        for message in messages.iter().filter(|m| m.role == Role::User) {
+            for content in &message.content {
+                if let MessageContent::Text(text) = content {
+                    self.learn(&text.text);
+                }
+            }
+        }
+        let mut messages = messages.to_vec();
+        for message in messages.iter_mut() {
+            Self::map_message(message, &|text| self.substitute(text));
+        }
+        (self.substitute(system), messages)
+    }
+
+    /// Put the real values back into a response, including its tool call arguments
+    pub fn restore_message(&self, mut message: Message) -> Message {
+        Self::map_message(&mut message, &|text| self.restore(text));
+        message
+    }
+
+    /// Put the real values back into a part of a streamed response. Text that may end in part
+    /// of a placeholder is kept in `held` until the rest of it arrives.
+    fn restore_part(&self, mut part: Message, held: &mut String) -> Message {
+        let mut content = Vec::new();
+        for item in std::mem::take(&mut part.content) {
+            if let MessageContent::Text(text) = item {
+                held.push_str(&text.text);
+                let ready: String = held.drain(..unfinished_placeholder(held)).collect();
+                if !ready.is_empty() {
+                    content.push(MessageContent::text(self.restore(&ready)));
+                }
+                continue;
+            }
+            if !held.is_empty() {
+                content.push(MessageContent::text(self.restore(&std::mem::take(held))));
+            }
+            let mut restored = Message::assistant().with_content(item);
+            Self::map_message(&mut restored, &|text| self.restore(text));
+            content.extend(restored.content);
+        }
+        part.content = content;
+        part
+    }
+}
+
+/// Put the real values back into a streamed response as its parts arrive
+fn restore_stream(vault: PiiVault, mut parts: MessageStream) -> MessageStream {
+    Box::pin(try_stream! {
+        let mut held = String::new();
+        while let Some(part) = parts.next().await {
+            let (message, usage) = part?;
+            yield (message.map(|message| vault.restore_part(message, &mut held)), usage);
+        }
+        if !held.is_empty() {
+            yield (Some(Message::assistant().with_text(vault.restore(&held))), None);
+        }
+    })
+}
+
+/// A provider that never sees the personal information in the conversation
+pub struct PiiProvider {
+    inner: Arc<dyn Provider>,
+    vault: Mutex<PiiVault>,
+}
+
+impl PiiProvider {
+    pub fn new(inner: Arc<dyn Provider>, vault: PiiVault) -> Self {
+        Self {
+            inner,
+            vault: Mutex::new(vault),
+        }
+    }
+
+    /// Wrap a provider in PII protection, if it is enabled
+    pub fn wrap_from_config(provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let config = Config::global();
+        if !config
+            .get_param::<bool>("GOOSE_PII_PROTECTION")
+            .unwrap_or(false)
+        {
+            return provider;
+        }
+        let names: Vec<String> = config.get_param("GOOSE_PII_NAMES").unwrap_or_default();
+        Arc::new(Self::new(provider, PiiVault::new(names)))
+    }
+}
+
+#[async_trait]
+impl Provider for PiiProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "pii",
+            "PII Protected Provider",
+            "A provider that substitutes placeholders for personal information before it is sent",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let (system, messages) = self.vault.lock().unwrap().protect_request(system, messages);
+        let (response, usage) = self.inner.complete(&system, &messages, tools).await?;
+        let response = self.vault.lock().unwrap().restore_message(response);
+        Ok((response, usage))
+    }
+
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let (system, messages) = self.vault.lock().unwrap().protect_request(system, messages);
+        let parts = self
+            .inner
+            .complete_streaming(&system, &messages, tools)
+            .await?;
+        let vault = self.vault.lock().unwrap().clone();
+        Ok(restore_stream(vault, parts))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> anyhow::Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::new(
+            self.inner.with_model_override(update)?,
+            self.vault.lock().unwrap().clone(),
+        )))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.inner.supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let texts = {
+            let vault = self.vault.lock().unwrap();
+            texts.iter().map(|text| vault.substitute(text)).collect()
+        };
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        self.inner.as_fallback()
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.inner
+            .as_batch()
+            .map(|_| self as &dyn BatchProviderTrait)
+    }
+}
+
+/// The batch API of the wrapped provider, with placeholders in the requests and the real values
+/// back in the results
+#[async_trait]
+impl BatchProviderTrait for PiiProvider {
+    async fn submit_batch(&self, requests: &[BatchRequest]) -> Result<String, ProviderError> {
+        let requests: Vec<BatchRequest> = {
+            let mut vault = self.vault.lock().unwrap();
+            requests
+                .iter()
+                .map(|request| {
+                    let (system, messages) =
+                        vault.protect_request(&request.system, &request.messages);
+                    BatchRequest {
+                        custom_id: request.custom_id.clone(),
+                        system,
+                        messages,
+                    }
+                })
+                .collect()
+        };
+        inner_batch(self.inner.as_ref())?
+            .submit_batch(&requests)
+            .await
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, ProviderError> {
+        inner_batch(self.inner.as_ref())?
+            .batch_status(batch_id)
+            .await
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, ProviderError> {
+        let results = inner_batch(self.inner.as_ref())?
+            .batch_results(batch_id)
+            .await?;
+        let vault = self.vault.lock().unwrap();
+        Ok(results
+            .into_iter()
+            .map(|result| BatchResult {
+                custom_id: result.custom_id,
+                response: result
+                    .response
+                    .map(|message| vault.restore_message(message)),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_pii() {
+        let found = detect_pii(
+            "My name is Jane Doe, email jane.doe@example.com or call +1 415-555-0134. Ask Bob Smith.",
+            &["Bob Smith".to_string()],
+        );
+        assert_eq!(
+            found,
+            vec![
+                (PiiKind::Email, "jane.doe@example.com".to_string()),
+                (PiiKind::Phone, "+1 415-555-0134".to_string()),
+                (PiiKind::Name, "Jane Doe".to_string()),
+                (PiiKind::Name, "Bob Smith".to_string()),
+            ]
+        );
+        assert!(detect_pii("Build 2025 took 1234567 ms", &[]).is_empty());
+
+        // Without a country code the groups of a phone number need separators
+        assert!(detect_pii("Order 4155550134 shipped", &[]).is_empty());
+        assert_eq!(
+            detect_pii("Call (415) 555-0134 or 415.555.0134", &[]),
+            vec![
+                (PiiKind::Phone, "(415) 555-0134".to_string()),
+                (PiiKind::Phone, "415.555.0134".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_substitutes_and_restores() {
+        let mut vault = PiiVault::new(vec![]);
+        let messages = vec![
+            Message::user().with_text("My name is Jane Doe, send the report to jane@example.com"),
+            Message::user()
+                .with_tool_response("1", Ok(vec![Content::text("Sent to jane@example.com")])),
+        ];
+        let (_, protected) = vault.protect_request("", &messages);
+        assert_eq!(
+            protected[0].as_concat_text(),
+            "My name is <NAME_1>, send the report to <EMAIL_1>"
+        );
+        assert_eq!(
+            protected[1].content[0].as_tool_response_text().unwrap(),
+            "Sent to <EMAIL_1>"
+        );
+
+        // The same value keeps its placeholder on the next request
+        let (_, protected) = vault.protect_request("", &messages[..1]);
+        assert!(protected[0].as_concat_text().ends_with("<EMAIL_1>"));
+        assert_eq!(vault.len(), 2);
+
+        let response = Message::assistant().with_tool_request(
+            "2",
+            Ok(ToolCall::new(
+                "developer__shell",
+                json!({"command": "mail -s Report <EMAIL_1>"}),
+            )),
+        );
+        let restored = vault.restore_message(response);
+        let call = restored.content[0].as_tool_request().unwrap();
+        assert_eq!(
+            call.tool_call.as_ref().unwrap().arguments,
+            json!({"command": "mail -s Report jane@example.com"})
+        );
+    }
+
+    #[test]
+    fn test_values_are_only_replaced_on_their_own() {
+        let mut vault = PiiVault::new(vec!["Ann".to_string()]);
+        vault.learn("Ask Ann");
+        assert_eq!(
+            vault.substitute("Annual report for Ann, see Ann_notes"),
+            "Annual report for <NAME_1>, see Ann_notes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_placeholders_split_across_streamed_parts_are_restored() {
+        let mut vault = PiiVault::new(vec![]);
+        vault.learn("mail jane@example.com");
+        let parts: Vec<Result<(Option<Message>, Option<ProviderUsage>), ProviderError>> =
+            ["Sending to <EM", "AIL_1> now", " <"]
+                .into_iter()
+                .map(|text| Ok((Some(Message::assistant().with_text(text)), None)))
+                .collect();
+        let restored: Vec<String> = restore_stream(vault, Box::pin(futures::stream::iter(parts)))
+            .map(|part| part.unwrap().0.unwrap().as_concat_text())
+            .collect()
+            .await;
+        assert_eq!(
+            restored,
+            vec!["Sending to ", "jane@example.com now", " ", "<"]
+        );
+    }
+}
@@ -0,0 +1,441 @@
+//! Daily and monthly token quotas per provider
+//!
+//! Usage is counted across sessions in `provider_usage.json` in the goose data directory, and
+//! checked against the quotas in `GOOSE_PROVIDER_QUOTAS`:
+//!
+//! ```yaml
+//! GOOSE_PROVIDER_QUOTAS:
+//!   - provider: anthropic
+//!     daily_tokens: 2000000
+//!     monthly_tokens: 40000000
+//!     warn_at: 0.8
+//!     fallback_provider: ollama
+//!     fallback_model: qwen2.5
+//! ```
+//!
+//! Past `warn_at` of a limit the agent emits a warning. At the limit it switches to the
+//! fallback provider if there is one, and scheduled jobs are skipped until the window resets
+//! unless `defer_scheduled` is false. Windows are calendar days and months in UTC.
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use fs2::FileExt;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::base::Usage;
+use crate::config::{Config, APP_STRATEGY};
+
+const DEFAULT_WARN_AT: f64 = 0.8;
+
+fn default_warn_at() -> f64 {
+    DEFAULT_WARN_AT
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderQuota {
+    pub provider: String,
+    #[serde(default)]
+    pub daily_tokens: Option<u64>,
+    #[serde(default)]
+    pub monthly_tokens: Option<u64>,
+    /// Fraction of a limit at which to warn
+    #[serde(default = "default_warn_at")]
+    pub warn_at: f64,
+    #[serde(default)]
+    pub fallback_provider: Option<String>,
+    /// Model of the fallback provider, its default model if not set
+    #[serde(default)]
+    pub fallback_model: Option<String>,
+    /// Skip scheduled jobs once a limit is reached
+    #[serde(default = "default_true")]
+    pub defer_scheduled: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaWindow {
+    Daily,
+    Monthly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaLevel {
+    Warning,
+    Exceeded,
+}
+
+/// How close a provider is to one of its quotas
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct QuotaStatus {
+    pub provider: String,
+    pub window: QuotaWindow,
+    pub level: QuotaLevel,
+    pub used: u64,
+    pub limit: u64,
+    pub resets_at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for QuotaStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let window = match self.window {
+            QuotaWindow::Daily => "daily",
+            QuotaWindow::Monthly => "monthly",
+        };
+        write!(
+            f,
+            "{} has used {} of its {} {} token quota, which resets at {}",
+            self.provider,
+            self.used,
+            window,
+            self.limit,
+            self.resets_at.format("%Y-%m-%d %H:%M UTC")
+        )
+    }
+}
+
+/// Tokens used by a provider in the current day and month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowUsage {
+    pub day: String,
+    pub day_tokens: u64,
+    pub month: String,
+    pub month_tokens: u64,
+}
+
+impl WindowUsage {
+    /// Start new windows if the day or month has changed
+    fn roll(&mut self, now: DateTime<Utc>) {
+        let day = now.format("%Y-%m-%d").to_string();
+        if self.day != day {
+            self.day = day;
+            self.day_tokens = 0;
+        }
+        let month = now.format("%Y-%m").to_string();
+        if self.month != month {
+            self.month = month;
+            self.month_tokens = 0;
+        }
+    }
+
+    fn add(&mut self, tokens: u64, now: DateTime<Utc>) {
+        self.roll(now);
+        self.day_tokens += tokens;
+        self.month_tokens += tokens;
+    }
+}
+
+fn window_reset(window: QuotaWindow, now: DateTime<Utc>) -> DateTime<Utc> {
+    let date = now.date_naive();
+    let next = match window {
+        QuotaWindow::Daily => date + Duration::days(1),
+        QuotaWindow::Monthly => {
+            let (year, month) = if date.month() == 12 {
+                (date.year() + 1, 1)
+            } else {
+                (date.year(), date.month() + 1)
+            };
+            NaiveDate::from_ymd_opt(year, month, 1).unwrap_or(date)
+        }
+    };
+    Utc.from_utc_datetime(&next.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// Tokens counted from a request's usage
+pub fn usage_tokens(usage: &Usage) -> u64 {
+    let total = usage
+        .total_tokens
+        .unwrap_or_else(|| usage.input_tokens.unwrap_or(0) + usage.output_tokens.unwrap_or(0));
+    total.max(0) as u64
+}
+
+fn read_ledger(path: &Path) -> HashMap<String, WindowUsage> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Add tokens to a provider's usage in the ledger file, holding a lock so concurrent records
+/// from other processes are not lost. The ledger is written to a temporary file and renamed
+/// over the old one, so it is never read half written; the lock is on a file of its own since
+/// the ledger file is replaced.
+fn record_in_file(
+    path: &Path,
+    provider: &str,
+    tokens: u64,
+    now: DateTime<Utc>,
+) -> Result<WindowUsage> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path.with_extension("json.lock"))?;
+    lock.lock_exclusive()?;
+
+    let mut providers = read_ledger(path);
+    let usage = providers.entry(provider.to_string()).or_default();
+    usage.add(tokens, now);
+    let usage = usage.clone();
+
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, serde_json::to_string_pretty(&providers)?)?;
+    fs::rename(&temp, path)?;
+    Ok(usage)
+}
+
+/// Usage per provider, shared by every goose process on the machine
+#[derive(Debug)]
+pub struct UsageLedger {
+    path: Option<PathBuf>,
+}
+
+impl UsageLedger {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path }
+    }
+
+    pub fn load_default() -> Self {
+        Self::new(
+            choose_app_strategy(APP_STRATEGY.clone())
+                .ok()
+                .map(|strategy| strategy.data_dir().join("provider_usage.json")),
+        )
+    }
+
+    fn load(&self) -> HashMap<String, WindowUsage> {
+        self.path.as_deref().map(read_ledger).unwrap_or_default()
+    }
+
+    pub fn usage(&self, provider: &str, now: DateTime<Utc>) -> WindowUsage {
+        let mut usage = self.load().remove(provider).unwrap_or_default();
+        usage.roll(now);
+        usage
+    }
+
+    /// Add tokens to a provider's usage, returning its new usage. The file is read and
+    /// written on the blocking pool.
+    pub async fn record(
+        &self,
+        provider: &str,
+        tokens: u64,
+        now: DateTime<Utc>,
+    ) -> Result<WindowUsage> {
+        let Some(path) = self.path.clone() else {
+            let mut usage = WindowUsage::default();
+            usage.add(tokens, now);
+            return Ok(usage);
+        };
+        let provider = provider.to_string();
+        tokio::task::spawn_blocking(move || record_in_file(&path, &provider, tokens, now)).await?
+    }
+}
+
+#[derive(Debug)]
+pub struct ProviderQuotas {
+    quotas: Vec<ProviderQuota>,
+    ledger: UsageLedger,
+    /// The last level reported per provider, so each warning is only raised once
+    reported: HashMap<String, QuotaLevel>,
+}
+
+impl ProviderQuotas {
+    pub fn new(quotas: Vec<ProviderQuota>, ledger: UsageLedger) -> Self {
+        Self {
+            quotas,
+            ledger,
+            reported: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_PROVIDER_QUOTAS")
+                .unwrap_or_default(),
+            UsageLedger::load_default(),
+        )
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&ProviderQuota> {
+        self.quotas.iter().find(|quota| quota.provider == provider)
+    }
+
+    fn status_for(
+        quota: &ProviderQuota,
+        usage: &WindowUsage,
+        now: DateTime<Utc>,
+    ) -> Option<QuotaStatus> {
+        [
+            (QuotaWindow::Daily, quota.daily_tokens, usage.day_tokens),
+            (
+                QuotaWindow::Monthly,
+                quota.monthly_tokens,
+                usage.month_tokens,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(window, limit, used)| {
+            let limit = limit?;
+            let level = if used >= limit {
+                QuotaLevel::Exceeded
+            } else if used as f64 >= limit as f64 * quota.warn_at {
+                QuotaLevel::Warning
+            } else {
+                return None;
+            };
+            Some(QuotaStatus {
+                provider: quota.provider.clone(),
+                window,
+                level,
+                used,
+                limit,
+                resets_at: window_reset(window, now),
+            })
+        })
+        .max_by_key(|status| (status.level, status.resets_at))
+    }
+
+    /// The most pressing quota a provider is near or over, if any
+    pub fn status(&self, provider: &str, now: DateTime<Utc>) -> Option<QuotaStatus> {
+        let quota = self.get(provider)?;
+        Self::status_for(quota, &self.ledger.usage(provider, now), now)
+    }
+
+    /// Count a request's tokens against the provider, returning its status when it first
+    /// reaches a warning or its limit
+    pub async fn record(
+        &mut self,
+        provider: &str,
+        usage: &Usage,
+        now: DateTime<Utc>,
+    ) -> Option<QuotaStatus> {
+        let quota = self.get(provider)?.clone();
+        let window_usage = match self.ledger.record(provider, usage_tokens(usage), now).await {
+            Ok(window_usage) => window_usage,
+            Err(e) => {
+                tracing::warn!("Failed to record provider usage: {}", e);
+                return None;
+            }
+        };
+        let Some(status) = Self::status_for(&quota, &window_usage, now) else {
+            self.reported.remove(provider);
+            return None;
+        };
+        if self.reported.get(provider) == Some(&status.level) {
+            return None;
+        }
+        self.reported.insert(provider.to_string(), status.level);
+        Some(status)
+    }
+
+    /// The exceeded quota a scheduled job on this provider should wait for, if any
+    pub fn deferral(&self, provider: &str, now: DateTime<Utc>) -> Option<QuotaStatus> {
+        if !self.get(provider)?.defer_scheduled {
+            return None;
+        }
+        self.status(provider, now)
+            .filter(|status| status.level == QuotaLevel::Exceeded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn quotas(dir: &TempDir) -> ProviderQuotas {
+        ProviderQuotas::new(
+            vec![ProviderQuota {
+                provider: "anthropic".to_string(),
+                daily_tokens: Some(1_000),
+                monthly_tokens: Some(10_000),
+                warn_at: 0.8,
+                fallback_provider: Some("ollama".to_string()),
+                fallback_model: None,
+                defer_scheduled: true,
+            }],
+            UsageLedger::new(Some(dir.path().join("provider_usage.json"))),
+        )
+    }
+
+    fn at(date: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(date)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn usage(tokens: i32) -> Usage {
+        Usage::new(None, None, Some(tokens))
+    }
+
+    #[tokio::test]
+    async fn test_warns_once_then_exceeds() {
+        let dir = TempDir::new().unwrap();
+        let mut quotas = quotas(&dir);
+        let now = at("2025-06-30T10:00:00Z");
+
+        assert_eq!(quotas.record("anthropic", &usage(500), now).await, None);
+        let warning = quotas.record("anthropic", &usage(300), now).await.unwrap();
+        assert_eq!(warning.level, QuotaLevel::Warning);
+        assert_eq!(warning.window, QuotaWindow::Daily);
+        assert_eq!(warning.resets_at, at("2025-07-01T00:00:00Z"));
+        assert_eq!(quotas.record("anthropic", &usage(100), now).await, None);
+        assert_eq!(quotas.deferral("anthropic", now), None);
+
+        let exceeded = quotas.record("anthropic", &usage(100), now).await.unwrap();
+        assert_eq!(exceeded.level, QuotaLevel::Exceeded);
+        assert!(quotas.deferral("anthropic", now).is_some());
+        assert_eq!(quotas.record("openai", &usage(100_000), now).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_records_add_up() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("provider_usage.json");
+        let now = at("2025-06-30T10:00:00Z");
+
+        // Each ledger stands in for another goose process sharing the file
+        let records = (0..8).map(|_| {
+            let ledger = UsageLedger::new(Some(path.clone()));
+            async move { ledger.record("anthropic", 100, now).await.unwrap() }
+        });
+        futures::future::join_all(records).await;
+
+        let usage = UsageLedger::new(Some(path)).usage("anthropic", now);
+        assert_eq!((usage.day_tokens, usage.month_tokens), (800, 800));
+    }
+
+    #[tokio::test]
+    async fn test_windows_reset() {
+        let dir = TempDir::new().unwrap();
+        let mut quotas = quotas(&dir);
+        quotas
+            .record("anthropic", &usage(1_000), at("2025-12-31T23:00:00Z"))
+            .await;
+        assert!(quotas
+            .deferral("anthropic", at("2025-12-31T23:30:00Z"))
+            .is_some());
+
+        let next_day = at("2026-01-01T00:30:00Z");
+        assert_eq!(quotas.status("anthropic", next_day), None);
+        let usage = quotas.ledger.usage("anthropic", next_day);
+        assert_eq!((usage.day_tokens, usage.month_tokens), (0, 0));
+        assert_eq!(
+            window_reset(QuotaWindow::Monthly, at("2025-12-31T23:00:00Z")),
+            at("2026-01-01T00:00:00Z")
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Client side rate limits for providers
+//!
+//! Sub-recipes and subagents can send many requests to the same provider at once, enough to
+//! run into the provider's own rate limits and fail with 429s. With `GOOSE_RATE_LIMITS`, the
+//! requests to a provider wait their turn instead: each provider has a token bucket for
+//! requests and one for tokens, refilled evenly over the minute, and a request is only sent
+//! once both hold enough for it. Waiting requests are sent in the order they were made.
+//! Limits are set per provider, with `default` for the providers that are not listed:
+//!
+//! ```yaml
+//! GOOSE_RATE_LIMITS:
+//!   default:
+//!     requests_per_minute: 60
+//!   anthropic:
+//!     requests_per_minute: 50
+//!     tokens_per_minute: 40000
+//! ```
+//!
+//! The buckets of a provider are shared by every agent in the process, so subagents count
+//! against the same limits as the agent that started them. The tokens of a request are
+//! estimated from its size before it is sent, and corrected by the usage the provider reports.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use mcp_core::Tool;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use super::base::{
+    FallbackProviderTrait, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage, Usage,
+};
+use super::batch::BatchProviderTrait;
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// Key of the limits for providers without their own
+const DEFAULT_KEY: &str = "default";
+/// Rough size of a token in the JSON of a request, for estimating before it is sent
+const BYTES_PER_TOKEN: usize = 4;
+
+/// The limiters of each provider, shared by every instance of the provider
+static LIMITERS: Lazy<Mutex<HashMap<String, Arc<RateLimiter>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimit {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+
+    /// The limit for a provider from `GOOSE_RATE_LIMITS`
+    pub fn from_config(provider_name: &str) -> Self {
+        let limits: HashMap<String, RateLimit> = Config::global()
+            .get_param("GOOSE_RATE_LIMITS")
+            .unwrap_or_default();
+        limits
+            .get(provider_name)
+            .or_else(|| limits.get(DEFAULT_KEY))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// A bucket holding up to a minute's worth of capacity, refilled continuously
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        let capacity = per_minute.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until the bucket holds `amount`, or None if it already does. An amount over
+    /// the capacity only waits for a full bucket, so that it is sent at all.
+    fn wait_for(&self, amount: f64) -> Option<Duration> {
+        let missing = amount.min(self.capacity) - self.available;
+        (missing > 0.0).then(|| Duration::from_secs_f64(missing / self.per_second))
+    }
+
+    /// Take from the bucket, which may go below empty for a request over the capacity or
+    /// one that used more tokens than estimated
+    fn take(&mut self, amount: f64) {
+        self.available = (self.available - amount).min(self.capacity);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Buckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+/// The rate limits of one provider
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<Buckets>,
+    /// Held by the request that is next to go, so that waiting requests go in order
+    queue: tokio::sync::Mutex<()>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        let now = Instant::now();
+        Self {
+            limit,
+            buckets: Mutex::new(Buckets {
+                requests: limit.requests_per_minute.map(|rpm| Bucket::new(rpm, now)),
+                tokens: limit.tokens_per_minute.map(|tpm| Bucket::new(tpm, now)),
+            }),
+            queue: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// The shared limiter of a provider, replaced if its limit has changed
+    pub fn for_provider(provider_name: &str, limit: RateLimit) -> Arc<Self> {
+        let mut limiters = LIMITERS.lock().unwrap();
+        match limiters.get(provider_name) {
+            Some(limiter) if limiter.limit == limit => limiter.clone(),
+            _ => {
+                let limiter = Arc::new(Self::new(limit));
+                limiters.insert(provider_name.to_string(), limiter.clone());
+                limiter
+            }
+        }
+    }
+
+    /// Wait until a request of about `tokens` tokens can be sent, then count it
+    pub async fn acquire(&self, tokens: usize) {
+        let _turn = self.queue.lock().await;
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let now = Instant::now();
+                let request_wait = buckets.requests.as_mut().and_then(|bucket| {
+                    bucket.refill(now);
+                    bucket.wait_for(1.0)
+                });
+                let token_wait = buckets.tokens.as_mut().and_then(|bucket| {
+                    bucket.refill(now);
+                    bucket.wait_for(tokens as f64)
+                });
+                let wait = request_wait.max(token_wait);
+                if wait.is_none() {
+                    if let Some(bucket) = buckets.requests.as_mut() {
+                        bucket.take(1.0);
+                    }
+                    if let Some(bucket) = buckets.tokens.as_mut() {
+                        bucket.take(tokens as f64);
+                    }
+                }
+                wait
+            };
+            match wait {
+                Some(wait) => {
+                    tracing::debug!("Rate limited, waiting {:?} to send the request", wait);
+                    tokio::time::sleep(wait).await;
+                }
+                None => return,
+            }
+        }
+    }
+
+    /// Correct the tokens counted for a request once its usage is known
+    pub fn record_usage(&self, estimated: usize, usage: &Usage) {
+        let used = match (usage.total_tokens, usage.input_tokens, usage.output_tokens) {
+            (Some(total), _, _) => total,
+            (None, Some(input), output) => input + output.unwrap_or(0),
+            _ => return,
+        };
+        let mut buckets = self.buckets.lock().unwrap();
+        if let Some(bucket) = buckets.tokens.as_mut() {
+            bucket.refill(Instant::now());
+            bucket.take(used.max(0) as f64 - estimated as f64);
+        }
+    }
+}
+
+/// The tokens of a request, estimated from the size of its JSON
+pub fn estimate_tokens(system: &str, messages: &[Message], tools: &[Tool]) -> usize {
+    let messages = serde_json::to_string(messages).map_or(0, |json| json.len());
+    let tools = serde_json::to_string(tools).map_or(0, |json| json.len());
+    (system.len() + messages + tools).div_ceil(BYTES_PER_TOKEN)
+}
+
+/// A provider that holds requests back until they are within the provider's rate limits
+pub struct RateLimitedProvider {
+    inner: Arc<dyn Provider>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Arc<dyn Provider>, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    /// Wrap a provider in its configured rate limits, if it has any
+    pub fn wrap_from_config(provider_name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let limit = RateLimit::from_config(provider_name);
+        if limit.is_unlimited() {
+            return provider;
+        }
+        Arc::new(Self::new(
+            provider,
+            RateLimiter::for_provider(provider_name, limit),
+        ))
+    }
+}
+
+#[async_trait]
+impl Provider for RateLimitedProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "rate_limited",
+            "Rate Limited Provider",
+            "A provider that queues requests to stay within the configured rate limits",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let estimated = estimate_tokens(system, messages, tools);
+        self.limiter.acquire(estimated).await;
+        let (message, usage) = self.inner.complete(system, messages, tools).await?;
+        self.limiter.record_usage(estimated, &usage.usage);
+        Ok((message, usage))
+    }
+
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        let estimated = estimate_tokens(system, messages, tools);
+        self.limiter.acquire(estimated).await;
+        let stream = self
+            .inner
+            .complete_streaming(system, messages, tools)
+            .await?;
+        let limiter = self.limiter.clone();
+        Ok(Box::pin(stream.inspect(move |part| {
+            if let Ok((_, Some(usage))) = part {
+                limiter.record_usage(estimated, &usage.usage);
+            }
+        })))
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> anyhow::Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::new(
+            self.inner.with_model_override(update)?,
+            self.limiter.clone(),
+        )))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.inner.supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let bytes: usize = texts.iter().map(String::len).sum();
+        self.limiter.acquire(bytes.div_ceil(BYTES_PER_TOKEN)).await;
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        self.inner.as_fallback()
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.inner.as_batch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_wait_for_the_bucket() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_minute: Some(60),
+            tokens_per_minute: None,
+        });
+        let start = Instant::now();
+        for _ in 0..60 {
+            limiter.acquire(0).await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(10));
+
+        // The bucket refills at one request a second
+        limiter.acquire(0).await;
+        limiter.acquire(0).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(2) && elapsed < Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_are_corrected_by_usage() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_minute: None,
+            tokens_per_minute: Some(600),
+        });
+        let start = Instant::now();
+        limiter.acquire(100).await;
+        // The request used far more than estimated, leaving the bucket empty
+        limiter.record_usage(100, &Usage::new(Some(500), Some(100), Some(600)));
+        limiter.acquire(300).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(30) && elapsed < Duration::from_secs(31));
+
+        // A request over the whole budget waits for a full bucket rather than forever
+        limiter.acquire(1_000).await;
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(120) && elapsed < Duration::from_secs(121));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waiting_requests_go_in_order() {
+        let limiter = Arc::new(RateLimiter::new(RateLimit {
+            requests_per_minute: Some(1),
+            tokens_per_minute: None,
+        }));
+        limiter.acquire(0).await;
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for i in 0..3 {
+            let limiter = limiter.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                limiter.acquire(0).await;
+                order.lock().unwrap().push(i);
+            }));
+            tokio::task::yield_now().await;
+        }
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_limiters_are_shared_per_provider() {
+        let limit = RateLimit {
+            requests_per_minute: Some(10),
+            tokens_per_minute: None,
+        };
+        let first = RateLimiter::for_provider("rate_limit_test", limit);
+        let second = RateLimiter::for_provider("rate_limit_test", limit);
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let changed = RateLimiter::for_provider(
+            "rate_limit_test",
+            RateLimit {
+                requests_per_minute: Some(20),
+                ..limit
+            },
+        );
+        assert!(!Arc::ptr_eq(&first, &changed));
+    }
+}
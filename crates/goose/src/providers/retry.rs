@@ -0,0 +1,424 @@
+//! Retries with exponential backoff for every provider
+//!
+//! Each provider created by the factory is wrapped in a `RetryingProvider`, which retries
+//! requests that failed on a rate limit or a server error. The delay before each retry grows
+//! by the backoff multiplier up to the maximum interval, with 20% jitter either way so clients
+//! that failed together do not retry together.
+//!
+//! ```yaml
+//! GOOSE_PROVIDER_MAX_RETRIES: 3
+//! GOOSE_PROVIDER_INITIAL_RETRY_INTERVAL_MS: 1000
+//! GOOSE_PROVIDER_BACKOFF_MULTIPLIER: 2.0
+//! GOOSE_PROVIDER_MAX_RETRY_INTERVAL_MS: 30000
+//! ```
+//!
+//! The same keys prefixed with the provider's name instead of `GOOSE_PROVIDER`, such as
+//! `DATABRICKS_MAX_RETRIES`, apply to that provider only. `GOOSE_PROVIDER_MAX_RETRIES: 0`
+//! turns retries off.
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mcp_core::tool::Tool;
+use serde::Deserialize;
+use tracing::Instrument;
+
+use super::base::{
+    FallbackProviderTrait, LeadWorkerProviderTrait, MessageStream, Provider, ProviderMetadata,
+    ProviderUsage,
+};
+use super::batch::BatchProviderTrait;
+use super::errors::ProviderError;
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// Default maximum number of retries
+const DEFAULT_MAX_RETRIES: usize = 3;
+/// Default initial interval for retry (in milliseconds)
+const DEFAULT_INITIAL_RETRY_INTERVAL_MS: u64 = 1000;
+/// Default retry backoff multiplier
+const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
+/// Default maximum interval for retry (in milliseconds)
+const DEFAULT_MAX_RETRY_INTERVAL_MS: u64 = 30_000;
+
+/// Databricks serving endpoints rate limit aggressively, so they keep their longer backoff
+const DATABRICKS_MAX_RETRIES: usize = 6;
+const DATABRICKS_INITIAL_RETRY_INTERVAL_MS: u64 = 5000;
+const DATABRICKS_MAX_RETRY_INTERVAL_MS: u64 = 320_000;
+
+/// Retry configuration for handling rate limit and server errors
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Maximum number of retry attempts
+    pub max_retries: usize,
+    /// Initial interval between retries in milliseconds
+    pub initial_interval_ms: u64,
+    /// Multiplier for backoff (exponential)
+    pub backoff_multiplier: f64,
+    /// Maximum interval between retries in milliseconds
+    pub max_interval_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            initial_interval_ms: DEFAULT_INITIAL_RETRY_INTERVAL_MS,
+            backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+            max_interval_ms: DEFAULT_MAX_RETRY_INTERVAL_MS,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The defaults for a provider, before any configuration
+    pub fn defaults_for(provider_name: &str) -> Self {
+        match provider_name {
+            "databricks" => Self {
+                max_retries: DATABRICKS_MAX_RETRIES,
+                initial_interval_ms: DATABRICKS_INITIAL_RETRY_INTERVAL_MS,
+                max_interval_ms: DATABRICKS_MAX_RETRY_INTERVAL_MS,
+                ..Self::default()
+            },
+            _ => Self::default(),
+        }
+    }
+
+    /// The configuration for a provider, where its own keys take precedence over the shared ones
+    pub fn from_config(provider_name: &str) -> Self {
+        Self::read(Config::global(), provider_name)
+    }
+
+    fn read(config: &Config, provider_name: &str) -> Self {
+        let defaults = Self::defaults_for(provider_name);
+        Self {
+            max_retries: retry_param(config, provider_name, "MAX_RETRIES")
+                .unwrap_or(defaults.max_retries),
+            initial_interval_ms: retry_param(config, provider_name, "INITIAL_RETRY_INTERVAL_MS")
+                .unwrap_or(defaults.initial_interval_ms),
+            backoff_multiplier: retry_param(config, provider_name, "BACKOFF_MULTIPLIER")
+                .unwrap_or(defaults.backoff_multiplier),
+            max_interval_ms: retry_param(config, provider_name, "MAX_RETRY_INTERVAL_MS")
+                .unwrap_or(defaults.max_interval_ms),
+        }
+    }
+
+    /// Calculate the delay for a specific retry attempt (with jitter)
+    pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
+        if attempt == 0 {
+            return Duration::from_millis(0);
+        }
+
+        // Calculate exponential backoff
+        let exponent = (attempt - 1) as i32;
+        let base_delay_ms =
+            (self.initial_interval_ms as f64 * self.backoff_multiplier.powi(exponent)) as u64;
+
+        // Apply max limit
+        let capped_delay_ms = std::cmp::min(base_delay_ms, self.max_interval_ms);
+
+        // Add jitter (+/-20% randomness) to avoid thundering herd problem
+        let jitter_factor = 0.8 + (rand::random::<f64>() * 0.4); // Between 0.8 and 1.2
+        let jittered_delay_ms = (capped_delay_ms as f64 * jitter_factor) as u64;
+
+        Duration::from_millis(jittered_delay_ms)
+    }
+}
+
+/// A retry setting of the provider, or the shared one, read as the number it is. Strings such
+/// as `"3"` are still accepted, as older configs stored them that way.
+fn retry_param<T>(config: &Config, provider_name: &str, key: &str) -> Option<T>
+where
+    T: for<'de> Deserialize<'de> + std::str::FromStr,
+{
+    let read = |name: String| -> Option<T> {
+        config.get_param::<T>(&name).ok().or_else(|| {
+            config
+                .get_param::<String>(&name)
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+        })
+    };
+    read(format!("{}_{}", provider_name.to_uppercase(), key))
+        .or_else(|| read(format!("GOOSE_PROVIDER_{}", key)))
+}
+
+/// Whether the same request may succeed when sent again
+pub fn is_retryable(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::RateLimitExceeded(_) | ProviderError::ServerError(_)
+    )
+}
+
+/// A provider that retries rate limited and failed requests with backoff
+pub struct RetryingProvider {
+    inner: Arc<dyn Provider>,
+    name: String,
+    config: RetryConfig,
+}
+
+impl RetryingProvider {
+    pub fn new(name: &str, inner: Arc<dyn Provider>, config: RetryConfig) -> Self {
+        Self {
+            inner,
+            name: name.to_string(),
+            config,
+        }
+    }
+
+    /// Wrap a provider in its configured retries, unless they are turned off
+    pub fn wrap_from_config(name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let config = RetryConfig::from_config(name);
+        if config.max_retries == 0 {
+            return provider;
+        }
+        Arc::new(Self::new(name, provider, config))
+    }
+
+    async fn with_retries<T, F, Fut>(&self, request: F) -> Result<T, ProviderError>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T, ProviderError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let span = tracing::info_span!(
+                "provider_request",
+                provider = %self.name,
+                model = %self.inner.get_model_config().model_name,
+                attempt
+            );
+            match request().instrument(span).await {
+                Err(e) if is_retryable(&e) && attempt < self.config.max_retries => {
+                    attempt += 1;
+                    let delay = self.config.delay_for_attempt(attempt);
+                    tracing::warn!(
+                        "{} request failed (attempt {}/{}), retrying in {:?}: {}",
+                        self.name,
+                        attempt,
+                        self.config.max_retries,
+                        delay,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for RetryingProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "retrying",
+            "Retrying Provider",
+            "A provider that retries rate limited and failed requests with backoff",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.with_retries(|| self.inner.complete(system, messages, tools))
+            .await
+    }
+
+    /// Only a failure to start the stream is retried; a stream that breaks off is not
+    async fn complete_streaming(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<MessageStream, ProviderError> {
+        self.with_retries(|| self.inner.complete_streaming(system, messages, tools))
+            .await
+    }
+
+    fn supports_streaming(&self) -> bool {
+        self.inner.supports_streaming()
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> anyhow::Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self {
+            inner: self.inner.with_model_override(update)?,
+            name: self.name.clone(),
+            config: self.config.clone(),
+        }))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    fn supports_prompt_caching(&self) -> bool {
+        self.inner.supports_prompt_caching()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.with_retries(|| self.inner.create_embeddings(texts.clone()))
+            .await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn as_fallback(&self) -> Option<&dyn FallbackProviderTrait> {
+        self.inner.as_fallback()
+    }
+
+    fn as_batch(&self) -> Option<&dyn BatchProviderTrait> {
+        self.inner.as_batch()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct FlakyProvider {
+        failures: AtomicUsize,
+        calls: AtomicUsize,
+        model: ModelConfig,
+    }
+
+    #[async_trait]
+    impl Provider for FlakyProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model.clone()
+        }
+
+        fn with_model_override(
+            &self,
+            update: &dyn Fn(ModelConfig) -> ModelConfig,
+        ) -> anyhow::Result<Arc<dyn Provider>> {
+            Ok(Arc::new(FlakyProvider {
+                failures: AtomicUsize::new(self.failures.load(Ordering::SeqCst)),
+                calls: AtomicUsize::new(0),
+                model: update(self.model.clone()),
+            }))
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.failures.load(Ordering::SeqCst) > 0 {
+                self.failures.fetch_sub(1, Ordering::SeqCst);
+                return Err(ProviderError::RateLimitExceeded("slow down".to_string()));
+            }
+            Ok((
+                Message::assistant().with_text("done"),
+                ProviderUsage::new("flaky".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn retrying(failures: usize, max_retries: usize) -> (Arc<FlakyProvider>, RetryingProvider) {
+        let inner = Arc::new(FlakyProvider {
+            failures: AtomicUsize::new(failures),
+            calls: AtomicUsize::new(0),
+            model: ModelConfig::new("flaky".to_string()),
+        });
+        let config = RetryConfig {
+            max_retries,
+            initial_interval_ms: 1,
+            ..RetryConfig::default()
+        };
+        (inner.clone(), RetryingProvider::new("flaky", inner, config))
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_success_or_limit() {
+        let (inner, provider) = retrying(2, 3);
+        assert!(provider.complete("", &[], &[]).await.is_ok());
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+
+        let (inner, provider) = retrying(5, 2);
+        assert!(matches!(
+            provider.complete("", &[], &[]).await,
+            Err(ProviderError::RateLimitExceeded(_))
+        ));
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_model_override_keeps_retrying() {
+        let (_, provider) = retrying(1, 1);
+        let updated = provider
+            .with_model_override(&|_| ModelConfig::new("steady".to_string()))
+            .unwrap();
+        assert_eq!(updated.get_model_config().model_name, "steady");
+        assert!(updated.complete("", &[], &[]).await.is_ok());
+    }
+
+    #[test]
+    fn test_delay_is_capped_and_jittered() {
+        let config = RetryConfig {
+            max_retries: 10,
+            initial_interval_ms: 1000,
+            backoff_multiplier: 2.0,
+            max_interval_ms: 5000,
+        };
+        assert_eq!(config.delay_for_attempt(0), Duration::ZERO);
+        let second = config.delay_for_attempt(2).as_millis();
+        assert!((1600..=2400).contains(&second));
+        let capped = config.delay_for_attempt(8).as_millis();
+        assert!((4000..=6000).contains(&capped));
+        assert!(!is_retryable(&ProviderError::ContextLengthExceeded(
+            "too long".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_numeric_settings_are_read() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let config = Config::new(file.path(), "goose-test").unwrap();
+        config
+            .set_param("RETRYTEST_MAX_RETRIES", serde_json::json!(0))
+            .unwrap();
+        config
+            .set_param("RETRYTEST_BACKOFF_MULTIPLIER", serde_json::json!(1.5))
+            .unwrap();
+        config
+            .set_param("RETRYTEST_MAX_RETRY_INTERVAL_MS", serde_json::json!("9000"))
+            .unwrap();
+
+        let read = RetryConfig::read(&config, "retrytest");
+        assert_eq!(read.max_retries, 0);
+        assert_eq!(read.backoff_multiplier, 1.5);
+        assert_eq!(read.max_interval_ms, 9000);
+    }
+}
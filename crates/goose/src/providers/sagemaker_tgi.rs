@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
@@ -287,6 +288,13 @@ impl Provider for SageMakerTgiProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -323,6 +331,7 @@ impl Provider for SageMakerTgiProvider {
                         input_tokens: Some(0),  // Would need to tokenize input to get accurate count
                         output_tokens: Some(0), // Would need to tokenize output to get accurate count
                         total_tokens: Some(0),
+                        ..Default::default()
                     };
 
                     // Add debug trace
@@ -0,0 +1,261 @@
+//! Text to speech for assistant replies
+//!
+//! When a speech provider is configured, the agent renders the text of each assistant message
+//! to audio and attaches it to the message it emits, so voice frontends can play responses
+//! without their own text to speech. The audio is only sent to the frontend; it is not added
+//! to the conversation sent to the model.
+//!
+//! ```yaml
+//! # An OpenAI compatible /v1/audio/speech endpoint
+//! GOOSE_TTS_PROVIDER: openai
+//! GOOSE_TTS_MODEL: tts-1
+//! GOOSE_TTS_VOICE: alloy
+//!
+//! # Or a local command that reads text on stdin and writes WAV audio to stdout
+//! GOOSE_TTS_PROVIDER: command
+//! GOOSE_TTS_COMMAND: piper --model en_US-lessac-medium.onnx --output_file -
+//! ```
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use mcp_core::Role;
+use serde_json::json;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::http_client;
+use crate::config::Config;
+use crate::message::{AudioContent, Message, MessageContent};
+
+const DEFAULT_OPENAI_MODEL: &str = "tts-1";
+const DEFAULT_OPENAI_VOICE: &str = "alloy";
+
+#[async_trait]
+pub trait SpeechProvider: Send + Sync {
+    /// Render text to encoded audio
+    async fn synthesize(&self, text: &str) -> Result<AudioContent>;
+}
+
+fn encode_audio(audio: &[u8], mime_type: &str) -> AudioContent {
+    AudioContent {
+        data: base64::engine::general_purpose::STANDARD.encode(audio),
+        mime_type: mime_type.to_string(),
+    }
+}
+
+/// Speech through an OpenAI compatible API
+pub struct OpenAiSpeech {
+    client: reqwest::Client,
+    host: String,
+    api_key: String,
+    model: String,
+    voice: String,
+}
+
+impl OpenAiSpeech {
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("OPENAI_API_KEY")?;
+        let host: String = config
+            .get_param("GOOSE_TTS_HOST")
+            .or_else(|_| config.get_param("OPENAI_HOST"))
+            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let client = http_client::client_builder()?
+            .timeout(Duration::from_secs(600))
+            .build()?;
+
+        Ok(Self {
+            client,
+            host,
+            api_key,
+            model: config
+                .get_param("GOOSE_TTS_MODEL")
+                .unwrap_or_else(|_| DEFAULT_OPENAI_MODEL.to_string()),
+            voice: config
+                .get_param("GOOSE_TTS_VOICE")
+                .unwrap_or_else(|_| DEFAULT_OPENAI_VOICE.to_string()),
+        })
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for OpenAiSpeech {
+    async fn synthesize(&self, text: &str) -> Result<AudioContent> {
+        let url = url::Url::parse(&self.host)?.join("v1/audio/speech")?;
+        let response = self
+            .client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&json!({
+                "model": self.model,
+                "voice": self.voice,
+                "input": text,
+                "response_format": "mp3",
+            }))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Speech synthesis failed ({}): {}", status, body));
+        }
+        Ok(encode_audio(&response.bytes().await?, "audio/mpeg"))
+    }
+}
+
+/// Speech from a local command, which reads text on stdin and writes WAV audio to stdout
+pub struct CommandSpeech {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandSpeech {
+    /// Create from a command line, split on whitespace
+    pub fn new(command: &str) -> Result<Self> {
+        let mut parts = command.split_whitespace().map(String::from);
+        let program = parts
+            .next()
+            .ok_or_else(|| anyhow!("The speech command is empty"))?;
+        Ok(Self {
+            program,
+            args: parts.collect(),
+        })
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let command: String = Config::global()
+            .get_param("GOOSE_TTS_COMMAND")
+            .map_err(|_| anyhow!("GOOSE_TTS_COMMAND must be set to use command speech"))?;
+        Self::new(&command)
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for CommandSpeech {
+    async fn synthesize(&self, text: &str) -> Result<AudioContent> {
+        let mut child = Command::new(&self.program)
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", self.program))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        stdin.write_all(text.as_bytes()).await?;
+        drop(stdin);
+
+        let output = child.wait_with_output().await?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} failed: {}",
+                self.program,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(encode_audio(&output.stdout, "audio/wav"))
+    }
+}
+
+/// The configured speech provider, or None if `GOOSE_TTS_PROVIDER` is unset
+pub fn create_speech_provider() -> Result<Option<Arc<dyn SpeechProvider>>> {
+    let Ok(name) = Config::global().get_param::<String>("GOOSE_TTS_PROVIDER") else {
+        return Ok(None);
+    };
+    let provider: Arc<dyn SpeechProvider> = match name.to_lowercase().as_str() {
+        "openai" => Arc::new(OpenAiSpeech::from_env()?),
+        "command" => Arc::new(CommandSpeech::from_env()?),
+        other => return Err(anyhow!("Unknown speech provider '{}'", other)),
+    };
+    Ok(Some(provider))
+}
+
+/// The parts of a message worth reading aloud, leaving out fenced code blocks
+pub fn speakable_text(message: &Message) -> String {
+    let mut in_code_block = false;
+    message
+        .as_concat_text()
+        .lines()
+        .filter(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                return false;
+            }
+            !in_code_block
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Attach spoken audio of an assistant message's text to the message
+pub async fn speak_message(message: &mut Message, provider: &dyn SpeechProvider) -> Result<()> {
+    if message.role != Role::Assistant {
+        return Ok(());
+    }
+    let text = speakable_text(message);
+    if text.is_empty() {
+        return Ok(());
+    }
+    let audio = provider.synthesize(&text).await?;
+    message.content.push(MessageContent::Audio(audio));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoSpeech;
+
+    #[async_trait]
+    impl SpeechProvider for EchoSpeech {
+        async fn synthesize(&self, text: &str) -> Result<AudioContent> {
+            Ok(encode_audio(text.as_bytes(), "audio/wav"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_speak_message_attaches_audio() {
+        let mut message =
+            Message::assistant().with_text("Run this:\n```sh\nls -la\n```\nIt lists the files.");
+        speak_message(&mut message, &EchoSpeech).await.unwrap();
+
+        let audio = message.content.last().unwrap().as_audio().unwrap();
+        let spoken = base64::engine::general_purpose::STANDARD
+            .decode(&audio.data)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(spoken).unwrap(),
+            "Run this:\nIt lists the files."
+        );
+    }
+
+    #[tokio::test]
+    async fn test_only_assistant_text_is_spoken() {
+        let mut user = Message::user().with_text("hello");
+        speak_message(&mut user, &EchoSpeech).await.unwrap();
+        assert_eq!(user.content.len(), 1);
+
+        let mut empty = Message::assistant();
+        speak_message(&mut empty, &EchoSpeech).await.unwrap();
+        assert!(empty.content.is_empty());
+    }
+
+    #[test]
+    fn test_command_speech_parses_command() {
+        let speech = CommandSpeech::new("piper --model voice.onnx --output_file -").unwrap();
+        assert_eq!(speech.program, "piper");
+        assert_eq!(
+            speech.args,
+            vec!["--model", "voice.onnx", "--output_file", "-"]
+        );
+        assert!(CommandSpeech::new("  ").is_err());
+    }
+}
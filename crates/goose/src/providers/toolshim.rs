@@ -31,6 +31,7 @@
 //!
 
 use super::errors::ProviderError;
+use super::http_client;
 use super::ollama::OLLAMA_DEFAULT_PORT;
 use super::ollama::OLLAMA_HOST;
 use crate::message::{Message, MessageContent};
@@ -69,7 +70,7 @@ pub struct OllamaInterpreter {
 
 impl OllamaInterpreter {
     pub fn new() -> Result<Self, ProviderError> {
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()
             .expect("Failed to create HTTP client");
@@ -0,0 +1,309 @@
+//! Speech to text for audio attached to messages
+//!
+//! Voice frontends can attach recorded audio to a user message instead of transcribing it
+//! themselves. Before the conversation is sent to a model, each audio attachment on a user
+//! message is replaced with its transcript using the configured transcription provider. The
+//! transcribed messages are returned to the frontend with `AgentEvent::Transcribed`, so it can
+//! show what was heard and send the text rather than the audio with later replies:
+//!
+//! ```yaml
+//! # An OpenAI compatible /v1/audio/transcriptions endpoint
+//! GOOSE_TRANSCRIPTION_PROVIDER: openai
+//! GOOSE_TRANSCRIPTION_MODEL: whisper-1
+//!
+//! # Or a local whisper.cpp build
+//! GOOSE_TRANSCRIPTION_PROVIDER: whisper_cpp
+//! GOOSE_WHISPER_CPP_BINARY: whisper-cli
+//! GOOSE_WHISPER_CPP_MODEL: ~/models/ggml-base.en.bin
+//! ```
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::Engine;
+use mcp_core::Role;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use super::http_client;
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+const DEFAULT_OPENAI_MODEL: &str = "whisper-1";
+const DEFAULT_WHISPER_CPP_BINARY: &str = "whisper-cli";
+
+#[async_trait]
+pub trait TranscriptionProvider: Send + Sync {
+    /// Transcribe encoded audio, e.g. a WAV or MP3 file, to text
+    async fn transcribe(&self, audio: Vec<u8>, mime_type: &str) -> Result<String>;
+}
+
+/// File extension for an audio mime type, used to name uploads and temporary files
+fn file_extension(mime_type: &str) -> &'static str {
+    match mime_type.split(';').next().unwrap_or_default().trim() {
+        "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+        "audio/mpeg" | "audio/mp3" => "mp3",
+        "audio/mp4" | "audio/m4a" | "audio/x-m4a" => "m4a",
+        "audio/ogg" => "ogg",
+        "audio/webm" => "webm",
+        "audio/flac" | "audio/x-flac" => "flac",
+        _ => "wav",
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TranscriptionResponse {
+    text: String,
+}
+
+/// Transcription through an OpenAI compatible API
+pub struct OpenAiTranscription {
+    client: reqwest::Client,
+    host: String,
+    api_key: String,
+    model: String,
+}
+
+impl OpenAiTranscription {
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let api_key: String = config.get_secret("OPENAI_API_KEY")?;
+        let host: String = config
+            .get_param("GOOSE_TRANSCRIPTION_HOST")
+            .or_else(|_| config.get_param("OPENAI_HOST"))
+            .unwrap_or_else(|_| "https://api.openai.com".to_string());
+        let model: String = config
+            .get_param("GOOSE_TRANSCRIPTION_MODEL")
+            .unwrap_or_else(|_| DEFAULT_OPENAI_MODEL.to_string());
+        let client = http_client::client_builder()?
+            .timeout(Duration::from_secs(600))
+            .build()?;
+
+        Ok(Self {
+            client,
+            host,
+            api_key,
+            model,
+        })
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAiTranscription {
+    async fn transcribe(&self, audio: Vec<u8>, mime_type: &str) -> Result<String> {
+        let url = url::Url::parse(&self.host)?.join("v1/audio/transcriptions")?;
+        let file = Part::bytes(audio)
+            .file_name(format!("audio.{}", file_extension(mime_type)))
+            .mime_str(mime_type)?;
+        let form = Form::new()
+            .text("model", self.model.clone())
+            .text("response_format", "json")
+            .part("file", file);
+
+        let response = self
+            .client
+            .post(url)
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .multipart(form)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Transcription failed ({}): {}", status, body));
+        }
+        Ok(response.json::<TranscriptionResponse>().await?.text)
+    }
+}
+
+/// Transcription with a local whisper.cpp binary. Older builds of whisper.cpp only read
+/// 16kHz WAV files.
+pub struct WhisperCppTranscription {
+    binary: PathBuf,
+    model: PathBuf,
+}
+
+impl WhisperCppTranscription {
+    pub fn new(binary: PathBuf, model: PathBuf) -> Self {
+        Self { binary, model }
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let config = Config::global();
+        let expand = |path: String| PathBuf::from(shellexpand::tilde(&path).into_owned());
+        let binary = config
+            .get_param::<String>("GOOSE_WHISPER_CPP_BINARY")
+            .unwrap_or_else(|_| DEFAULT_WHISPER_CPP_BINARY.to_string());
+        let model = config
+            .get_param::<String>("GOOSE_WHISPER_CPP_MODEL")
+            .map_err(|_| anyhow!("GOOSE_WHISPER_CPP_MODEL must be set to a whisper.cpp model"))?;
+        Ok(Self::new(expand(binary), expand(model)))
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for WhisperCppTranscription {
+    async fn transcribe(&self, audio: Vec<u8>, mime_type: &str) -> Result<String> {
+        let mut file = tempfile::Builder::new()
+            .suffix(&format!(".{}", file_extension(mime_type)))
+            .tempfile()?;
+        file.write_all(&audio)?;
+        file.flush()?;
+
+        let output = Command::new(&self.binary)
+            .arg("-m")
+            .arg(&self.model)
+            .arg("-f")
+            .arg(file.path())
+            .arg("--no-timestamps")
+            .arg("--no-prints")
+            .output()
+            .await
+            .with_context(|| format!("Failed to run {}", self.binary.display()))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "whisper.cpp failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let transcript = String::from_utf8_lossy(&output.stdout);
+        Ok(transcript
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "))
+    }
+}
+
+/// The configured transcription provider, or None if `GOOSE_TRANSCRIPTION_PROVIDER` is unset
+pub fn create_transcription_provider() -> Result<Option<Arc<dyn TranscriptionProvider>>> {
+    let Ok(name) = Config::global().get_param::<String>("GOOSE_TRANSCRIPTION_PROVIDER") else {
+        return Ok(None);
+    };
+    let provider: Arc<dyn TranscriptionProvider> = match name.to_lowercase().as_str() {
+        "openai" => Arc::new(OpenAiTranscription::from_env()?),
+        "whisper_cpp" | "whisper.cpp" => Arc::new(WhisperCppTranscription::from_env()?),
+        other => return Err(anyhow!("Unknown transcription provider '{}'", other)),
+    };
+    Ok(Some(provider))
+}
+
+/// Whether any user message has audio to transcribe. Audio on assistant messages is spoken
+/// output for the frontend and is left alone.
+pub fn has_audio(messages: &[Message]) -> bool {
+    messages
+        .iter()
+        .filter(|m| m.role == Role::User)
+        .flat_map(|m| m.content.iter())
+        .any(|c| c.as_audio().is_some())
+}
+
+/// A user message with its audio replaced by the transcript, and its index in the conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscribedMessage {
+    pub index: usize,
+    pub message: Message,
+}
+
+/// Replace each audio attachment on user messages with its transcript, returning the messages
+/// that changed
+pub async fn transcribe_messages(
+    messages: &mut [Message],
+    provider: &dyn TranscriptionProvider,
+) -> Result<Vec<TranscribedMessage>> {
+    let mut transcribed = Vec::new();
+    for (index, message) in messages.iter_mut().enumerate() {
+        if message.role != Role::User || !message.content.iter().any(|c| c.as_audio().is_some()) {
+            continue;
+        }
+        for content in message.content.iter_mut() {
+            let MessageContent::Audio(audio) = content else {
+                continue;
+            };
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(&audio.data)
+                .context("Audio data is not valid base64")?;
+            let transcript = provider.transcribe(bytes, &audio.mime_type).await?;
+            *content = MessageContent::text(transcript.trim());
+        }
+        transcribed.push(TranscribedMessage {
+            index,
+            message: message.clone(),
+        });
+    }
+    Ok(transcribed)
+}
+
+/// Put transcribed messages in place of the messages with audio they came from
+pub fn apply_transcripts(messages: &mut [Message], transcribed: &[TranscribedMessage]) {
+    for TranscribedMessage { index, message } in transcribed {
+        if let Some(slot) = messages.get_mut(*index) {
+            *slot = message.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoTranscription;
+
+    #[async_trait]
+    impl TranscriptionProvider for EchoTranscription {
+        async fn transcribe(&self, audio: Vec<u8>, mime_type: &str) -> Result<String> {
+            Ok(format!(
+                "{} ({})",
+                String::from_utf8(audio)?,
+                file_extension(mime_type)
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_messages_replaces_audio() {
+        let audio = base64::engine::general_purpose::STANDARD.encode("list my files");
+        let mut messages = vec![
+            Message::assistant()
+                .with_text("How can I help?")
+                .with_audio("c3Bva2Vu", "audio/mpeg"),
+            Message::user()
+                .with_audio(audio, "audio/webm;codecs=opus")
+                .with_text("in the current directory"),
+        ];
+        assert!(has_audio(&messages));
+
+        let original = messages.clone();
+        let transcribed = transcribe_messages(&mut messages, &EchoTranscription)
+            .await
+            .unwrap();
+
+        assert!(!has_audio(&messages));
+        // Only the user message changed, and a frontend can put it in place of its audio
+        assert_eq!(transcribed.len(), 1);
+        assert_eq!(transcribed[0].index, 1);
+        let mut frontend = original;
+        apply_transcripts(&mut frontend, &transcribed);
+        assert_eq!(frontend, messages);
+        // Spoken assistant output is not transcribed
+        assert!(messages[0].content[1].as_audio().is_some());
+        assert_eq!(
+            messages[1].as_concat_text(),
+            "list my files (webm)\nin the current directory"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_audio_is_an_error() {
+        let mut messages = vec![Message::user().with_audio("not base64!", "audio/wav")];
+        assert!(transcribe_messages(&mut messages, &EchoTranscription)
+            .await
+            .is_err());
+    }
+}
@@ -136,6 +136,15 @@ impl OAIStreamCollector {
     }
 
     pub fn add_chunk(&mut self, chunk: &OAIStreamChunk) {
+        if let Some(id) = chunk.id.as_ref().filter(|id| !id.is_empty()) {
+            self.id = Some(id.clone());
+        }
+        if let Some(model) = &chunk.model {
+            self.model = Some(model.clone());
+        }
+        if chunk.usage.is_some() {
+            self.usage = chunk.usage.clone();
+        }
         for ch in chunk.choices.iter() {
             // Always ensure choice exists, even if all fields are absent!
             let idx = ch.index;
@@ -347,6 +356,8 @@ data: [DONE]
             "Hello! How can I assist you today? 🌍"
         );
         assert_eq!(choice.finish_reason, "stop");
+        assert_eq!(resp.model, "gpt-4o-2024-11-20");
+        assert_eq!(resp.usage.and_then(|u| u.completion_tokens), Some(13));
     }
     const CLAUDE_STREAM: &str = r#"
 data: {"choices":[{"index":0,"delta":{"content":"I","role":"assistant"}}],"created":1747613682,"id":"938bb8e2-6276-4a58-bca3-c675cfe7f2f5","model":"claude-3.5-sonnet"}
@@ -4,10 +4,12 @@ use chrono::Utc;
 use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::sync::Arc;
 use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
+use super::http_client;
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use mcp_core::{tool::Tool, Role, ToolCall, ToolResult};
@@ -103,7 +105,7 @@ impl VeniceProvider {
         // Ensure we only keep the bare model id internally
         model.model_name = strip_flags(&model.model_name).to_string();
 
-        let client = Client::builder()
+        let client = http_client::client_builder()?
             .timeout(Duration::from_secs(600))
             .build()?;
 
@@ -250,6 +252,13 @@ impl Provider for VeniceProvider {
         self.model.clone()
     }
 
+    fn with_model_override(
+        &self,
+        update: &dyn Fn(ModelConfig) -> ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        Ok(Arc::new(Self::from_env(update(self.get_model_config()))?))
+    }
+
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         // Fetch supported models via Venice API
         let base_url = url::Url::parse(&self.host)
@@ -554,6 +563,7 @@ impl Provider for VeniceProvider {
             input_tokens: usage_data["prompt_tokens"].as_i64().map(|v| v as i32),
             output_tokens: usage_data["completion_tokens"].as_i64().map(|v| v as i32),
             total_tokens: usage_data["total_tokens"].as_i64().map(|v| v as i32),
+            ..Default::default()
         };
 
         Ok((
@@ -0,0 +1,265 @@
+//! Checks that a provider's credentials work before a session starts
+//!
+//! [`verify`] makes the cheapest authenticated call the provider supports, listing its models
+//! where possible and otherwise a one token completion, and turns a failure into an issue
+//! with a hint the user can act on, instead of failing on the first real completion.
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::base::{ConfigKey, ProviderMetadata};
+use super::errors::ProviderError;
+use super::{create_bare, providers};
+use crate::config::Config;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// Why a provider could not be used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerificationIssue {
+    /// Required configuration keys are not set
+    MissingConfig,
+    /// The provider rejected the credentials
+    InvalidKey,
+    /// The account is out of quota or credit
+    InsufficientQuota,
+    /// The provider is rate limiting requests
+    RateLimited,
+    /// The provider could not be reached, usually because the host is wrong
+    WrongHost,
+    /// The configured model does not exist for this account
+    ModelNotFound,
+    /// The provider is reachable but failing
+    ProviderUnavailable,
+    Other,
+}
+
+/// Result of verifying a provider
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Verification {
+    pub provider: String,
+    pub issue: Option<VerificationIssue>,
+    /// What went wrong and what to do about it, or a confirmation
+    pub message: String,
+    /// The models the credentials can use, if the provider lists them
+    pub models: Option<Vec<String>>,
+}
+
+impl Verification {
+    pub fn is_ok(&self) -> bool {
+        self.issue.is_none()
+    }
+
+    fn failed(provider: &str, issue: VerificationIssue, message: String) -> Self {
+        Self {
+            provider: provider.to_string(),
+            issue: Some(issue),
+            message,
+            models: None,
+        }
+    }
+}
+
+fn is_set(config: &Config, key: &ConfigKey) -> bool {
+    std::env::var(&key.name).is_ok() || config.get(&key.name, key.secret).is_ok()
+}
+
+fn missing_keys(metadata: &ProviderMetadata) -> Vec<String> {
+    let config = Config::global();
+    metadata
+        .config_keys
+        .iter()
+        .filter(|key| key.required && key.default.is_none() && !is_set(config, key))
+        .map(|key| key.name.clone())
+        .collect()
+}
+
+fn key_names(metadata: &ProviderMetadata, secret: bool) -> String {
+    let names: Vec<&str> = metadata
+        .config_keys
+        .iter()
+        .filter(|key| key.secret == secret)
+        .map(|key| key.name.as_str())
+        .collect();
+    if names.is_empty() {
+        "the provider's settings".to_string()
+    } else {
+        names.join(", ")
+    }
+}
+
+/// Classify a provider error and suggest a fix
+pub fn classify_error(
+    metadata: &ProviderMetadata,
+    model: &str,
+    error: &ProviderError,
+) -> (VerificationIssue, String) {
+    let detail = error.to_string();
+    let lower = detail.to_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| lower.contains(word));
+
+    let issue = match error {
+        ProviderError::Authentication(_) => VerificationIssue::InvalidKey,
+        _ if mentions(&["quota", "insufficient", "billing", "credit balance"]) => {
+            VerificationIssue::InsufficientQuota
+        }
+        ProviderError::RateLimitExceeded(_) => VerificationIssue::RateLimited,
+        _ if mentions(&["model_not_found", "model not found", "does not exist"]) => {
+            VerificationIssue::ModelNotFound
+        }
+        _ if mentions(&[
+            "error sending request",
+            "dns",
+            "connection refused",
+            "404 not found",
+            "invalid url",
+        ]) =>
+        {
+            VerificationIssue::WrongHost
+        }
+        ProviderError::ServerError(_) => VerificationIssue::ProviderUnavailable,
+        _ => VerificationIssue::Other,
+    };
+
+    let hint = match issue {
+        VerificationIssue::InvalidKey => format!(
+            "{} rejected the credentials. Check {} with `goose configure`",
+            metadata.display_name,
+            key_names(metadata, true)
+        ),
+        VerificationIssue::InsufficientQuota => format!(
+            "The {} account is out of quota or credit. Check its billing settings",
+            metadata.display_name
+        ),
+        VerificationIssue::RateLimited => format!(
+            "{} is rate limiting requests. Wait a moment and try again",
+            metadata.display_name
+        ),
+        VerificationIssue::ModelNotFound => format!(
+            "{} has no model named {} for this account. See {}",
+            metadata.display_name, model, metadata.model_doc_link
+        ),
+        VerificationIssue::WrongHost => format!(
+            "{} could not be reached. Check {} with `goose configure`",
+            metadata.display_name,
+            key_names(metadata, false)
+        ),
+        VerificationIssue::ProviderUnavailable => format!(
+            "{} is not available right now. Try again later",
+            metadata.display_name
+        ),
+        VerificationIssue::MissingConfig | VerificationIssue::Other => {
+            format!("{} could not be used", metadata.display_name)
+        }
+    };
+    (issue, format!("{}: {}", hint, detail))
+}
+
+/// Check that a provider is configured and its credentials work, with a minimal
+/// authenticated call. The model is `GOOSE_MODEL` if this is the configured provider, or
+/// otherwise the provider's default model.
+pub async fn verify(provider_name: &str) -> anyhow::Result<Verification> {
+    let metadata = providers()
+        .into_iter()
+        .find(|metadata| metadata.name == provider_name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown provider: {}", provider_name))?;
+
+    let missing = missing_keys(&metadata);
+    if !missing.is_empty() {
+        return Ok(Verification::failed(
+            provider_name,
+            VerificationIssue::MissingConfig,
+            format!(
+                "{} needs {}. Set them with `goose configure`",
+                metadata.display_name,
+                missing.join(", ")
+            ),
+        ));
+    }
+
+    let config = Config::global();
+    let model = config
+        .get_param::<String>("GOOSE_PROVIDER")
+        .ok()
+        .filter(|configured| configured == provider_name)
+        .and_then(|_| config.get_param::<String>("GOOSE_MODEL").ok())
+        .unwrap_or_else(|| metadata.default_model.clone());
+    // The provider itself, so a working fallback or lead model cannot hide a failure
+    let provider = match create_bare(
+        provider_name,
+        ModelConfig::new(model.clone()).with_max_tokens(Some(1)),
+    ) {
+        Ok(provider) => provider,
+        Err(e) => {
+            return Ok(Verification::failed(
+                provider_name,
+                VerificationIssue::MissingConfig,
+                format!("{} could not be set up: {}", metadata.display_name, e),
+            ))
+        }
+    };
+
+    let outcome = match provider.fetch_supported_models_async().await {
+        Ok(Some(models)) => Ok(Some(models)),
+        // Providers that cannot list models are checked with the smallest completion
+        Ok(None) => provider
+            .complete("Reply with OK", &[Message::user().with_text("ping")], &[])
+            .await
+            .map(|_| None),
+        Err(e) => Err(e),
+    };
+
+    Ok(match outcome {
+        Ok(models) => Verification {
+            provider: provider_name.to_string(),
+            issue: None,
+            message: format!("{} is configured and reachable", metadata.display_name),
+            models,
+        },
+        Err(e) => {
+            let (issue, message) = classify_error(&metadata, &model, &e);
+            Verification::failed(provider_name, issue, message)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Provider;
+    use crate::providers::openai::OpenAiProvider;
+
+    #[test]
+    fn test_classify_error() {
+        let metadata = OpenAiProvider::metadata();
+        let classify = |error: ProviderError| classify_error(&metadata, "gpt-4o", &error).0;
+
+        assert_eq!(
+            classify(ProviderError::Authentication("Incorrect API key".into())),
+            VerificationIssue::InvalidKey
+        );
+        assert_eq!(
+            classify(ProviderError::RateLimitExceeded(
+                "You exceeded your current quota".into()
+            )),
+            VerificationIssue::InsufficientQuota
+        );
+        assert_eq!(
+            classify(ProviderError::RateLimitExceeded("Too many requests".into())),
+            VerificationIssue::RateLimited
+        );
+        assert_eq!(
+            classify(ProviderError::ExecutionError(
+                "error sending request for url (https://api.openai.co/v1/models)".into()
+            )),
+            VerificationIssue::WrongHost
+        );
+
+        let (_, message) = classify_error(
+            &metadata,
+            "gpt-4o",
+            &ProviderError::Authentication("Incorrect API key".into()),
+        );
+        assert!(message.contains("OPENAI_API_KEY"));
+    }
+}
@@ -0,0 +1,99 @@
+//! Web search run by the provider itself
+//!
+//! OpenAI, Google and Anthropic can search the web while they generate a response, and cite the
+//! pages they used. With `GOOSE_WEB_SEARCH` the providers that support it turn their search on,
+//! which is listed as `web_search` in their metadata. It works without a search extension and
+//! without a tool call round trip through goose.
+//!
+//! ```yaml
+//! GOOSE_WEB_SEARCH: true
+//! # Searches per request, for Anthropic
+//! GOOSE_WEB_SEARCH_MAX_USES: 5
+//! ```
+//!
+//! On OpenAI's chat completions API search needs one of the search models, such as
+//! `gpt-4o-search-preview`, and requests to other models are sent without search. The citations
+//! of a response are added to its message as a list of sources after the text, so they are shown
+//! to the user and stay in the conversation.
+use crate::config::Config;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WebSearchConfig {
+    pub enabled: bool,
+    /// Most searches the model may run for one request, where the provider allows a limit
+    pub max_uses: Option<u32>,
+}
+
+impl WebSearchConfig {
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            enabled: config.get_param("GOOSE_WEB_SEARCH").unwrap_or(false),
+            max_uses: config.get_param("GOOSE_WEB_SEARCH_MAX_USES").ok(),
+        }
+    }
+}
+
+/// A page the response cites
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    pub url: String,
+    pub title: Option<String>,
+}
+
+impl Citation {
+    pub fn new(url: impl Into<String>, title: Option<&str>) -> Self {
+        Self {
+            url: url.into(),
+            title: title
+                .map(str::trim)
+                .filter(|title| !title.is_empty())
+                .map(str::to_string),
+        }
+    }
+}
+
+/// The sources section added after the text of a response, listing each page once in the order
+/// it was first cited
+pub fn sources_text(citations: &[Citation]) -> Option<String> {
+    let mut seen = Vec::new();
+    for citation in citations {
+        if !citation.url.is_empty() && !seen.iter().any(|c: &&Citation| c.url == citation.url) {
+            seen.push(citation);
+        }
+    }
+    if seen.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = seen
+        .iter()
+        .enumerate()
+        .map(|(index, citation)| match &citation.title {
+            Some(title) => format!("{}. [{}]({})", index + 1, title, citation.url),
+            None => format!("{}. {}", index + 1, citation.url),
+        })
+        .collect();
+    Some(format!("Sources:\n{}", lines.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sources_are_listed_once() {
+        assert_eq!(sources_text(&[]), None);
+        let citations = vec![
+            Citation::new("https://www.rust-lang.org/", Some("Rust")),
+            Citation::new("https://doc.rust-lang.org/std/", Some(" ")),
+            Citation::new(
+                "https://www.rust-lang.org/",
+                Some("Rust Programming Language"),
+            ),
+        ];
+        assert_eq!(
+            sources_text(&citations).unwrap(),
+            "Sources:\n1. [Rust](https://www.rust-lang.org/)\n2. https://doc.rust-lang.org/std/"
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! Packing a recipe into a single shareable archive.
+//!
+//! An archive holds the root recipe, every sub-recipe it references, and any files used as
+//! defaults for `file` parameters, each with a SHA-256 hash. Values of extension `envs` are
+//! stripped while packing and their names moved to `env_keys`, so secrets never end up in
+//! an archive and the importer is asked for them instead.
+//!
+//! Paths inside the archive are relative to the closest directory containing all packed
+//! files, so relative references between recipes still resolve after unpacking.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use super::{RecipeParameterInputType, RecipeTree, SubRecipeError};
+
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+pub const ARCHIVE_FILE_EXTENSION: &str = "goose-recipe";
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error(transparent)]
+    Recipe(#[from] SubRecipeError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid archive: {0}")]
+    Format(String),
+    #[error("Unsupported archive format version {0}")]
+    UnsupportedVersion(u32),
+    #[error("Integrity check failed for '{0}'")]
+    Integrity(String),
+    #[error("Archive contains an unsafe path '{0}'")]
+    UnsafePath(String),
+    #[error("'{}' already exists", .0.display())]
+    AlreadyExists(PathBuf),
+}
+
+/// A single file in the archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Path relative to the archive root, always using `/` separators
+    pub path: String,
+    /// Hex encoded SHA-256 of the decoded content
+    pub sha256: String,
+    /// Base64 encoded file content
+    pub content: String,
+}
+
+impl ArchiveEntry {
+    fn new(path: String, bytes: &[u8]) -> Self {
+        Self {
+            path,
+            sha256: sha256_hex(bytes),
+            content: base64::engine::general_purpose::STANDARD.encode(bytes),
+        }
+    }
+
+    /// Decode the content, checking it against its hash
+    pub fn bytes(&self) -> Result<Vec<u8>, ArchiveError> {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&self.content)
+            .map_err(|_| ArchiveError::Integrity(self.path.clone()))?;
+        if sha256_hex(&bytes) != self.sha256 {
+            return Err(ArchiveError::Integrity(self.path.clone()));
+        }
+        Ok(bytes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeArchive {
+    pub format_version: u32,
+    /// Path of the root recipe within the archive
+    pub root: String,
+    pub files: Vec<ArchiveEntry>,
+    /// Hash over the paths and hashes of all files, covering the archive as a whole
+    pub digest: String,
+}
+
+impl RecipeArchive {
+    /// Pack a recipe file together with everything it references
+    pub fn pack(recipe_path: &Path) -> Result<Self, ArchiveError> {
+        let tree = RecipeTree::from_file(recipe_path, RecipeTree::max_depth_from_config())?;
+
+        let mut recipe_files = Vec::new();
+        let mut other_files = Vec::new();
+        collect_files(&tree, &mut recipe_files, &mut other_files);
+        let root_path = recipe_files
+            .first()
+            .cloned()
+            .ok_or_else(|| ArchiveError::Format("Recipe has no file path".to_string()))?;
+
+        let base_dir = common_dir(recipe_files.iter().chain(other_files.iter()));
+        let relative = |path: &Path| -> String {
+            let components: Vec<String> = path
+                .strip_prefix(&base_dir)
+                .unwrap_or(path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            components.join("/")
+        };
+
+        let mut files = BTreeMap::new();
+        for path in &recipe_files {
+            let content = fs::read_to_string(path)?;
+            let sanitized = strip_extension_secrets(path, &content);
+            files.insert(relative(path), sanitized.into_bytes());
+        }
+        for path in &other_files {
+            files.insert(relative(path), fs::read(path)?);
+        }
+
+        let files: Vec<ArchiveEntry> = files
+            .into_iter()
+            .map(|(path, bytes)| ArchiveEntry::new(path, &bytes))
+            .collect();
+        let digest = archive_digest(&files);
+        Ok(Self {
+            format_version: ARCHIVE_FORMAT_VERSION,
+            root: relative(&root_path),
+            files,
+            digest,
+        })
+    }
+
+    /// Check the format version, the archive digest and the hash of every file
+    pub fn verify(&self) -> Result<(), ArchiveError> {
+        if self.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(ArchiveError::UnsupportedVersion(self.format_version));
+        }
+        if archive_digest(&self.files) != self.digest {
+            return Err(ArchiveError::Integrity("archive".to_string()));
+        }
+        if !self.files.iter().any(|f| f.path == self.root) {
+            return Err(ArchiveError::Format(format!(
+                "root recipe '{}' is missing",
+                self.root
+            )));
+        }
+        for file in &self.files {
+            safe_relative_path(&file.path)?;
+            file.bytes()?;
+        }
+        Ok(())
+    }
+
+    pub fn read(path: &Path) -> Result<Self, ArchiveError> {
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| ArchiveError::Format(e.to_string()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), ArchiveError> {
+        let content =
+            serde_json::to_string_pretty(self).map_err(|e| ArchiveError::Format(e.to_string()))?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Verify the archive and extract it into `dest`, returning the path of the root recipe.
+    /// Existing files are never overwritten.
+    pub fn unpack(&self, dest: &Path) -> Result<PathBuf, ArchiveError> {
+        self.verify()?;
+
+        let targets = self
+            .files
+            .iter()
+            .map(|file| Ok((dest.join(safe_relative_path(&file.path)?), file)))
+            .collect::<Result<Vec<_>, ArchiveError>>()?;
+        if let Some((existing, _)) = targets.iter().find(|(target, _)| target.exists()) {
+            return Err(ArchiveError::AlreadyExists(existing.clone()));
+        }
+
+        for (target, file) in targets {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, file.bytes()?)?;
+        }
+        Ok(dest.join(safe_relative_path(&self.root)?))
+    }
+}
+
+/// Pack a recipe file into an archive at `archive_path`
+pub fn pack_recipe(recipe_path: &Path, archive_path: &Path) -> Result<RecipeArchive, ArchiveError> {
+    let archive = RecipeArchive::pack(recipe_path)?;
+    archive.write(archive_path)?;
+    Ok(archive)
+}
+
+/// Extract an archive into `dest`, returning the path of the root recipe
+pub fn unpack_recipe(archive_path: &Path, dest: &Path) -> Result<PathBuf, ArchiveError> {
+    RecipeArchive::read(archive_path)?.unpack(dest)
+}
+
+/// Recipe files of the tree in depth-first order, root first, and the files they reference
+fn collect_files(
+    tree: &RecipeTree,
+    recipe_files: &mut Vec<PathBuf>,
+    other_files: &mut Vec<PathBuf>,
+) {
+    if let Some(path) = &tree.path {
+        if !recipe_files.contains(path) {
+            recipe_files.push(path.clone());
+        }
+        let recipe_dir = path.parent().unwrap_or(Path::new(""));
+        for parameter in tree.recipe.parameters.iter().flatten() {
+            if !matches!(parameter.input_type, RecipeParameterInputType::File) {
+                continue;
+            }
+            let Some(default) = &parameter.default else {
+                continue;
+            };
+            if let Ok(file) = recipe_dir.join(default).canonicalize() {
+                if file.is_file() && !other_files.contains(&file) {
+                    other_files.push(file);
+                }
+            }
+        }
+    }
+    for child in &tree.children {
+        collect_files(child, recipe_files, other_files);
+    }
+}
+
+/// The deepest directory containing all of the given files
+fn common_dir<'a>(paths: impl Iterator<Item = &'a PathBuf>) -> PathBuf {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        common = Some(match common {
+            None => dir,
+            Some(current) => current
+                .components()
+                .zip(dir.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    common.unwrap_or_default()
+}
+
+/// Reject absolute paths and paths that escape the destination directory
+fn safe_relative_path(path: &str) -> Result<PathBuf, ArchiveError> {
+    let relative = PathBuf::from(path);
+    if path.is_empty()
+        || !relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(ArchiveError::UnsafePath(path.to_string()));
+    }
+    Ok(relative)
+}
+
+/// Remove values of extension environment variables, keeping their names as `env_keys`.
+/// Content that cannot be parsed is packed unchanged.
+fn strip_extension_secrets(path: &Path, content: &str) -> String {
+    let is_json = matches!(path.extension().and_then(|ext| ext.to_str()), Some("json"));
+    let parsed: Option<Value> = if is_json {
+        serde_json::from_str(content).ok()
+    } else {
+        serde_yaml::from_str(content).ok()
+    };
+    let Some(mut recipe) = parsed else {
+        tracing::warn!(
+            "Could not parse {} to strip extension secrets, packing it as is",
+            path.display()
+        );
+        return content.to_string();
+    };
+
+    let mut changed = false;
+    if let Some(extensions) = recipe
+        .get_mut("extensions")
+        .and_then(Value::as_sequence_mut)
+    {
+        for extension in extensions.iter_mut().filter_map(Value::as_mapping_mut) {
+            let Some(Value::Mapping(envs)) = extension.remove("envs") else {
+                continue;
+            };
+            let mut env_keys: Vec<Value> = extension
+                .get("env_keys")
+                .and_then(Value::as_sequence)
+                .cloned()
+                .unwrap_or_default();
+            for key in envs.keys() {
+                if !env_keys.contains(key) {
+                    env_keys.push(key.clone());
+                }
+            }
+            extension.insert(Value::from("env_keys"), Value::Sequence(env_keys));
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return content.to_string();
+    }
+    let serialized = if is_json {
+        serde_json::to_string_pretty(&recipe).map_err(|e| e.to_string())
+    } else {
+        serde_yaml::to_string(&recipe).map_err(|e| e.to_string())
+    };
+    serialized.unwrap_or_else(|_| content.to_string())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn archive_digest(files: &[ArchiveEntry]) -> String {
+    let mut hasher = Sha256::new();
+    for file in files {
+        hasher.update(file.path.as_bytes());
+        hasher.update([0]);
+        hasher.update(file.sha256.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(dir: &Path, file: &str, content: &str) -> PathBuf {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn fixture(dir: &Path) -> PathBuf {
+        write(
+            dir,
+            "shared/notes.yaml",
+            "title: Notes\ndescription: notes\ninstructions: Write notes\n",
+        );
+        write(dir, "recipes/template.md", "# {{ title }}\n");
+        write(
+            dir,
+            "recipes/main.yaml",
+            r#"title: Main
+description: main recipe
+instructions: Do the work
+parameters:
+  - key: template
+    input_type: file
+    requirement: optional
+    description: Template to fill
+    default: template.md
+extensions:
+  - type: stdio
+    name: tracker
+    cmd: tracker-mcp
+    args: []
+    envs:
+      TRACKER_TOKEN: secret-value
+sub_recipes:
+  - name: notes
+    path: ../shared/notes.yaml
+"#,
+        )
+    }
+
+    #[test]
+    fn test_pack_and_unpack_round_trip() {
+        let source = TempDir::new().unwrap();
+        let archive = RecipeArchive::pack(&fixture(source.path())).unwrap();
+
+        assert_eq!(archive.root, "recipes/main.yaml");
+        let paths: Vec<&str> = archive.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "recipes/main.yaml",
+                "recipes/template.md",
+                "shared/notes.yaml"
+            ]
+        );
+        archive.verify().unwrap();
+
+        let root = archive
+            .files
+            .iter()
+            .find(|f| f.path == archive.root)
+            .unwrap();
+        let root_content = String::from_utf8(root.bytes().unwrap()).unwrap();
+        assert!(!root_content.contains("secret-value"));
+        assert!(root_content.contains("TRACKER_TOKEN"));
+
+        let dest = TempDir::new().unwrap();
+        let archive_path = dest.path().join("main.goose-recipe");
+        archive.write(&archive_path).unwrap();
+        let unpacked = unpack_recipe(&archive_path, &dest.path().join("out")).unwrap();
+
+        let tree = RecipeTree::from_file(&unpacked, 5).unwrap();
+        assert_eq!(tree.children.len(), 1);
+        assert_eq!(tree.children[0].recipe.title, "Notes");
+    }
+
+    #[test]
+    fn test_tampered_archive_is_rejected() {
+        let source = TempDir::new().unwrap();
+        let mut archive = RecipeArchive::pack(&fixture(source.path())).unwrap();
+        archive.files[0].content =
+            base64::engine::general_purpose::STANDARD.encode("title: Evil\n");
+        assert!(matches!(archive.verify(), Err(ArchiveError::Integrity(_))));
+    }
+
+    #[test]
+    fn test_unsafe_paths_are_rejected() {
+        assert!(safe_relative_path("recipes/main.yaml").is_ok());
+        assert!(safe_relative_path("../main.yaml").is_err());
+        assert!(safe_relative_path("/etc/passwd").is_err());
+    }
+}
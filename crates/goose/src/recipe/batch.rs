@@ -0,0 +1,136 @@
+//! Recipes that run their prompt once per input, as a batch.
+//!
+//! A recipe with a `batch` section answers its prompt once for every input rather than once
+//! in a session. A scheduled run of such a recipe is submitted to the provider's batch API
+//! when it is eligible: the recipe has a prompt and needs no tools, so each input is answered
+//! in a single completion. The answers are written to the run's session, each after the
+//! prompt it answers.
+//!
+//! ```yaml
+//! prompt: |
+//!   Summarize this document in three bullet points:
+//!   {{ input }}
+//! batch:
+//!   inputs:
+//!     - "Quarterly report text..."
+//!   input_files:
+//!     - ./docs/report-1.md
+//!     - ./docs/report-2.md
+//! ```
+//!
+//! Without an `{{ input }}` placeholder the input is appended to the prompt.
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::Recipe;
+use crate::message::Message;
+use crate::providers::batch::{BatchRequest, BatchResult};
+
+const INPUT_PLACEHOLDER: &str = "{{ input }}";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RecipeBatch {
+    /// Inputs given inline
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<String>,
+    /// Files whose contents are inputs, relative to the recipe file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_files: Vec<PathBuf>,
+}
+
+impl RecipeBatch {
+    /// Whether the recipe can be answered input by input in single completions
+    pub fn eligible(recipe: &Recipe) -> bool {
+        recipe.batch.is_some()
+            && recipe.prompt.is_some()
+            && recipe.extensions.as_ref().is_none_or(|e| e.is_empty())
+            && recipe.sub_recipes.as_ref().is_none_or(|s| s.is_empty())
+    }
+
+    /// All inputs, inline ones first, with file paths resolved against `base_dir`
+    pub fn load_inputs(&self, base_dir: &Path) -> Result<Vec<String>> {
+        let mut inputs = self.inputs.clone();
+        for path in &self.input_files {
+            let path = base_dir.join(path);
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read batch input {}", path.display()))?;
+            inputs.push(content);
+        }
+        Ok(inputs)
+    }
+
+    /// One request per input, each asking the prompt about that input
+    pub fn requests(system: &str, prompt: &str, inputs: &[String]) -> Vec<BatchRequest> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| BatchRequest {
+                custom_id: format!("input-{}", i + 1),
+                system: system.to_string(),
+                messages: vec![Message::user().with_text(prompt_for(prompt, input))],
+            })
+            .collect()
+    }
+
+    /// The session of a batch run: each request's prompt followed by its answer, or by a
+    /// note of why it has none
+    pub fn session_messages(requests: &[BatchRequest], results: &[BatchResult]) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(requests.len() * 2);
+        for (request, result) in requests.iter().zip(results) {
+            messages.extend(request.messages.iter().cloned());
+            messages.push(match &result.response {
+                Ok(message) => message.clone(),
+                Err(e) => Message::assistant()
+                    .with_text(format!("No answer for {}: {}", request.custom_id, e)),
+            });
+        }
+        messages
+    }
+}
+
+fn prompt_for(prompt: &str, input: &str) -> String {
+    if prompt.contains(INPUT_PLACEHOLDER) {
+        prompt.replace(INPUT_PLACEHOLDER, input)
+    } else {
+        format!("{}\n\n{}", prompt, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_and_session_messages() {
+        let inputs = vec!["first doc".to_string(), "second doc".to_string()];
+        let requests = RecipeBatch::requests("Be brief", "Summarize:\n{{ input }}", &inputs);
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].custom_id, "input-2");
+        assert_eq!(
+            requests[0].messages[0].as_concat_text(),
+            "Summarize:\nfirst doc"
+        );
+        assert_eq!(
+            RecipeBatch::requests("", "Summarize", &inputs[..1])[0].messages[0].as_concat_text(),
+            "Summarize\n\nfirst doc"
+        );
+
+        let results = vec![
+            BatchResult {
+                custom_id: "input-1".to_string(),
+                response: Ok(Message::assistant().with_text("A summary")),
+            },
+            BatchResult {
+                custom_id: "input-2".to_string(),
+                response: Err("expired".to_string()),
+            },
+        ];
+        let messages = RecipeBatch::session_messages(&requests, &results);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1].as_concat_text(), "A summary");
+        assert!(messages[3].as_concat_text().contains("expired"));
+    }
+}
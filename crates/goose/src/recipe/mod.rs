@@ -1,8 +1,23 @@
 use std::fmt;
 
+use crate::agents::conversation_template::TemplateTurn;
 use crate::agents::extension::ExtensionConfig;
+use crate::agents::termination::TerminationCondition;
 use serde::{Deserialize, Serialize};
 
+pub mod archive;
+pub mod batch;
+pub mod output_check;
+pub mod sub_recipe;
+
+pub use archive::{pack_recipe, unpack_recipe, RecipeArchive};
+pub use batch::RecipeBatch;
+pub use output_check::{OutputCheckReport, OutputChecks, RetryScope};
+pub use sub_recipe::{
+    sub_recipe_instructions, AgentRunner, RecipeTree, SubRecipe, SubRecipeError, SubRecipeReport,
+    SubRecipeRunner, SubRecipeStatus,
+};
+
 fn default_version() -> String {
     "1.0.0".to_string()
 }
@@ -25,6 +40,12 @@ fn default_version() -> String {
 /// * `activities` - Activity labels that appear when loading the Recipe
 /// * `author` - Information about the Recipe's creator and metadata
 /// * `parameters` - Additional parameters for the Recipe
+/// * `stop_conditions` - Conditions that end the run once an assistant turn satisfies them
+/// * `acceptance_criteria` - A checklist the run must resolve before it finishes
+/// * `conversation_template` - Example turns the model sees before the conversation
+/// * `sub_recipes` - Other recipe files this Recipe delegates to, which may nest further
+/// * `batch` - Inputs the prompt is answered for one by one, as a batch
+/// * `output_checks` - Checks the final output must pass, and how the run is retried until it does
 ///
 /// # Example
 ///
@@ -52,6 +73,12 @@ fn default_version() -> String {
 ///     author: None,
 ///     settings: None,
 ///     parameters: None,
+///     stop_conditions: None,
+///     acceptance_criteria: None,
+///     conversation_template: None,
+///     sub_recipes: None,
+///     batch: None,
+///     output_checks: None,
 /// };
 ///
 #[derive(Serialize, Deserialize, Debug)]
@@ -89,6 +116,24 @@ pub struct Recipe {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<RecipeParameter>>, // any additional parameters for the recipe
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_conditions: Option<Vec<TerminationCondition>>, // conditions that end the run early
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acceptance_criteria: Option<Vec<String>>, // checklist the run must resolve before finishing
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_template: Option<Vec<TemplateTurn>>, // example turns seeded before the conversation
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub_recipes: Option<Vec<SubRecipe>>, // nested recipes this recipe delegates to
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch: Option<RecipeBatch>, // inputs the prompt is answered for one by one
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_checks: Option<OutputChecks>, // checks the final output must pass, with retries
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -176,6 +221,12 @@ pub struct RecipeBuilder {
     activities: Option<Vec<String>>,
     author: Option<Author>,
     parameters: Option<Vec<RecipeParameter>>,
+    stop_conditions: Option<Vec<TerminationCondition>>,
+    acceptance_criteria: Option<Vec<String>>,
+    conversation_template: Option<Vec<TemplateTurn>>,
+    sub_recipes: Option<Vec<SubRecipe>>,
+    batch: Option<RecipeBatch>,
+    output_checks: Option<OutputChecks>,
 }
 
 impl Recipe {
@@ -206,6 +257,12 @@ impl Recipe {
             activities: None,
             author: None,
             parameters: None,
+            stop_conditions: None,
+            acceptance_criteria: None,
+            conversation_template: None,
+            sub_recipes: None,
+            batch: None,
+            output_checks: None,
         }
     }
 }
@@ -275,6 +332,42 @@ impl RecipeBuilder {
         self
     }
 
+    /// Sets the conditions that end the run early
+    pub fn stop_conditions(mut self, stop_conditions: Vec<TerminationCondition>) -> Self {
+        self.stop_conditions = Some(stop_conditions);
+        self
+    }
+
+    /// Sets the checklist the run must resolve before it finishes
+    pub fn acceptance_criteria(mut self, acceptance_criteria: Vec<String>) -> Self {
+        self.acceptance_criteria = Some(acceptance_criteria);
+        self
+    }
+
+    /// Sets the example turns the model sees before the conversation
+    pub fn conversation_template(mut self, conversation_template: Vec<TemplateTurn>) -> Self {
+        self.conversation_template = Some(conversation_template);
+        self
+    }
+
+    /// Sets the sub-recipes the Recipe delegates to
+    pub fn sub_recipes(mut self, sub_recipes: Vec<SubRecipe>) -> Self {
+        self.sub_recipes = Some(sub_recipes);
+        self
+    }
+
+    /// Sets the inputs the prompt is answered for one by one
+    pub fn batch(mut self, batch: RecipeBatch) -> Self {
+        self.batch = Some(batch);
+        self
+    }
+
+    /// Sets the checks the final output must pass, and how the run is retried until it does
+    pub fn output_checks(mut self, output_checks: OutputChecks) -> Self {
+        self.output_checks = Some(output_checks);
+        self
+    }
+
     /// Builds the Recipe instance
     ///
     /// Returns an error if any required fields are missing
@@ -298,6 +391,12 @@ impl RecipeBuilder {
             activities: self.activities,
             author: self.author,
             parameters: self.parameters,
+            stop_conditions: self.stop_conditions,
+            acceptance_criteria: self.acceptance_criteria,
+            conversation_template: self.conversation_template,
+            sub_recipes: self.sub_recipes,
+            batch: self.batch,
+            output_checks: self.output_checks,
         })
     }
 }
@@ -0,0 +1,375 @@
+//! Checks on the final output of a recipe run, retried with a correction when they fail.
+//!
+//! A recipe can declare `output_checks` that its final output, the text of the last assistant
+//! response, has to pass: a regex it must match, a JSON schema it must be valid against, or a
+//! script that must accept it. A script gets the output on stdin and runs in the working
+//! directory of the run; it passes by exiting with 0, and what it prints otherwise says why the
+//! output was rejected.
+//!
+//! When a check fails the run is tried again with a corrective instruction that lists the
+//! failures, up to `max_attempts` attempts in all. With the `turn` scope the correction is sent
+//! as the next message of the same conversation; with the `recipe` scope the run starts over
+//! from the prompt, with the correction added to it. The run result reports the attempts made
+//! and the failures of the last one.
+//!
+//! ```yaml
+//! output_checks:
+//!   checks:
+//!     - type: regex
+//!       pattern: "(?m)^## Summary"
+//!     - type: json_schema
+//!       schema:
+//!         type: object
+//!         required: [status]
+//!     - type: script
+//!       command: ./scripts/check_report.sh
+//!   retry:
+//!     max_attempts: 3
+//!     scope: turn
+//! ```
+use std::fmt;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::agents::termination::parse_json_output;
+use crate::message::Message;
+use mcp_core::role::Role;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// How long a check script has to decide
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The most of a script's output kept as the reason it rejected the output
+const MAX_REASON_CHARS: usize = 2000;
+
+fn default_max_attempts() -> u32 {
+    DEFAULT_MAX_ATTEMPTS
+}
+
+/// A check the final output of a run has to pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OutputCheck {
+    /// The output matches the regex
+    Regex { pattern: String },
+    /// The output is JSON, optionally in a fenced code block, valid against the schema
+    JsonSchema { schema: Value },
+    /// The shell command exits with 0 when given the output on stdin
+    Script { command: String },
+}
+
+/// What a failed check is tried again with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryScope {
+    /// The last turn, continuing the conversation with the correction
+    #[default]
+    Turn,
+    /// The whole recipe, from the prompt with the correction added
+    Recipe,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputRetry {
+    /// Attempts in all, the first one included
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub scope: RetryScope,
+}
+
+impl Default for OutputRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            scope: RetryScope::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputChecks {
+    pub checks: Vec<OutputCheck>,
+    #[serde(default)]
+    pub retry: OutputRetry,
+}
+
+/// A check the output did not pass, and why
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputCheckFailure {
+    pub check: String,
+    pub reason: String,
+}
+
+impl fmt::Display for OutputCheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.check, self.reason)
+    }
+}
+
+/// The outcome of the output checks of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputCheckReport {
+    pub attempts: u32,
+    pub passed: bool,
+    /// The failures of the last attempt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<OutputCheckFailure>,
+}
+
+impl fmt::Display for OutputCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let attempts = if self.attempts == 1 {
+            "1 attempt".to_string()
+        } else {
+            format!("{} attempts", self.attempts)
+        };
+        if self.passed {
+            return write!(f, "Output checks passed after {}", attempts);
+        }
+        write!(f, "Output checks failed after {}:", attempts)?;
+        for failure in &self.failures {
+            write!(f, "\n- {}", failure)?;
+        }
+        Ok(())
+    }
+}
+
+impl OutputCheck {
+    fn describe(&self) -> String {
+        match self {
+            Self::Regex { pattern } => format!("regex '{}'", pattern),
+            Self::JsonSchema { .. } => "JSON schema".to_string(),
+            Self::Script { command } => format!("script '{}'", command),
+        }
+    }
+
+    /// Check the output, returning why it fails the check
+    pub async fn check(&self, output: &str, working_dir: &Path) -> Result<(), String> {
+        match self {
+            Self::Regex { pattern } => {
+                let re = Regex::new(pattern).map_err(|e| format!("invalid regex: {}", e))?;
+                if re.is_match(output) {
+                    Ok(())
+                } else {
+                    Err("the output does not match".to_string())
+                }
+            }
+            Self::JsonSchema { schema } => {
+                let validator = jsonschema::validator_for(schema)
+                    .map_err(|e| format!("invalid schema: {}", e))?;
+                let json = parse_json_output(output).ok_or("the output is not JSON")?;
+                let errors: Vec<String> = validator
+                    .iter_errors(&json)
+                    .map(|e| e.to_string())
+                    .collect();
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors.join("; "))
+                }
+            }
+            Self::Script { command } => run_script(command, output, working_dir).await,
+        }
+    }
+}
+
+async fn run_script(command: &str, output: &str, working_dir: &Path) -> Result<(), String> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .current_dir(working_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A script that exits without reading its input closes the pipe, which is not an error
+        let _ = stdin.write_all(output.as_bytes()).await;
+    }
+    let result = tokio::time::timeout(SCRIPT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", SCRIPT_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("failed to run: {}", e))?;
+    if result.status.success() {
+        return Ok(());
+    }
+
+    let printed = [result.stderr, result.stdout]
+        .iter()
+        .map(|bytes| String::from_utf8_lossy(bytes).trim().to_string())
+        .find(|text| !text.is_empty())
+        .unwrap_or_else(|| format!("exited with {}", result.status));
+    Err(printed.chars().take(MAX_REASON_CHARS).collect())
+}
+
+impl OutputChecks {
+    /// The checks the output fails
+    pub async fn evaluate(&self, output: &str, working_dir: &Path) -> Vec<OutputCheckFailure> {
+        let mut failures = Vec::new();
+        for check in &self.checks {
+            if let Err(reason) = check.check(output, working_dir).await {
+                failures.push(OutputCheckFailure {
+                    check: check.describe(),
+                    reason,
+                });
+            }
+        }
+        failures
+    }
+
+    /// Check the output of an attempt and record it in the report, returning the correction to
+    /// try again with, or None once the checks pass or no attempts are left
+    pub async fn review(
+        &self,
+        report: &mut OutputCheckReport,
+        output: &str,
+        working_dir: &Path,
+    ) -> Option<String> {
+        report.attempts += 1;
+        report.failures = self.evaluate(output, working_dir).await;
+        report.passed = report.failures.is_empty();
+        if report.passed || report.attempts >= self.retry.max_attempts.max(1) {
+            return None;
+        }
+        Some(correction(&report.failures))
+    }
+}
+
+/// The instruction that asks for the output again, saying what was wrong with it
+pub fn correction(failures: &[OutputCheckFailure]) -> String {
+    let mut text = "Your final output did not pass the checks this task requires:\n".to_string();
+    for failure in failures {
+        text.push_str(&format!("- {}\n", failure));
+    }
+    text.push_str("Fix these problems and give the complete final output again.");
+    text
+}
+
+/// The final output of a run: the text of the assistant messages that end it, which are the
+/// chunks of one response when it was streamed
+pub fn final_output(messages: &[Message]) -> String {
+    let start = messages
+        .iter()
+        .rposition(|message| message.role != Role::Assistant)
+        .map_or(0, |index| index + 1);
+    messages[start..]
+        .iter()
+        .map(Message::as_concat_text)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn checks(yaml: &str) -> OutputChecks {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_regex_and_schema_checks() {
+        let checks = checks(
+            r#"
+checks:
+  - type: regex
+    pattern: "status"
+  - type: json_schema
+    schema:
+      type: object
+      required: [status]
+      properties:
+        status:
+          enum: [ok, failed]
+"#,
+        );
+        assert_eq!(checks.retry.max_attempts, DEFAULT_MAX_ATTEMPTS);
+        assert_eq!(checks.retry.scope, RetryScope::Turn);
+        let dir = std::env::temp_dir();
+
+        let passing = "```json\n{\"status\": \"ok\"}\n```";
+        assert!(checks.evaluate(passing, &dir).await.is_empty());
+
+        let failures = checks.evaluate("{\"status\": \"unknown\"}", &dir).await;
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].check, "JSON schema");
+
+        let failures = checks.evaluate("All done!", &dir).await;
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[1].reason, "the output is not JSON");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_script_checks_read_the_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let check = OutputCheck::Script {
+            command: "grep -q DONE || { echo 'no DONE marker' >&2; exit 1; }".to_string(),
+        };
+        assert!(check.check("all DONE", dir.path()).await.is_ok());
+        assert_eq!(
+            check.check("not yet", dir.path()).await.unwrap_err(),
+            "no DONE marker"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_review_retries_until_attempts_run_out() {
+        let checks = OutputChecks {
+            checks: vec![OutputCheck::Regex {
+                pattern: "^OK$".to_string(),
+            }],
+            retry: OutputRetry {
+                max_attempts: 2,
+                scope: RetryScope::Recipe,
+            },
+        };
+        let dir = std::env::temp_dir();
+        let mut report = OutputCheckReport::default();
+
+        let correction = checks.review(&mut report, "nope", &dir).await.unwrap();
+        assert!(correction.contains("regex '^OK$': the output does not match"));
+        assert!(checks.review(&mut report, "still no", &dir).await.is_none());
+        assert_eq!(report.attempts, 2);
+        assert!(!report.passed);
+        assert!(report
+            .to_string()
+            .starts_with("Output checks failed after 2 attempts"));
+
+        let mut report = OutputCheckReport::default();
+        assert!(checks.review(&mut report, "OK", &dir).await.is_none());
+        assert!(report.passed);
+        assert_eq!(report.to_string(), "Output checks passed after 1 attempt");
+    }
+
+    #[test]
+    fn test_final_output_joins_streamed_chunks() {
+        let messages = vec![
+            Message::user().with_text("Summarize"),
+            Message::assistant().with_text("{\"status\":"),
+            Message::assistant().with_text(" \"ok\"}"),
+        ];
+        assert_eq!(final_output(&messages), "{\"status\": \"ok\"}");
+        assert_eq!(
+            parse_json_output(&final_output(&messages)),
+            Some(json!({"status": "ok"}))
+        );
+    }
+}
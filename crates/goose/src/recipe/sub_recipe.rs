@@ -0,0 +1,571 @@
+//! Nested sub-recipe trees.
+//!
+//! A recipe can declare `sub_recipes`, each pointing at another recipe file which may
+//! declare sub-recipes of its own. [`RecipeTree`] resolves the full tree up front so that
+//! runaway nesting and cycles are rejected before anything runs, and [`RecipeTree::run`]
+//! executes it bottom-up, handing each recipe the reports of the sub-recipes beneath it.
+//! [`AgentRunner`] runs each recipe headless in an agent of its own.
+//!
+//! ```yaml
+//! sub_recipes:
+//!   - name: lint
+//!     path: ./lint.yaml
+//!   - name: release_notes
+//!     path: ../shared/release_notes.yaml
+//!     values:
+//!       audience: customers
+//! ```
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::{FutureExt, StreamExt};
+use mcp_core::role::Role;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::Recipe;
+use crate::agents::{Agent, AgentEvent};
+use crate::config::Config;
+use crate::message::Message;
+use crate::prompt_template::render_inline_once;
+use crate::providers::base::Provider;
+
+pub const DEFAULT_MAX_SUB_RECIPE_DEPTH: usize = 5;
+
+/// A reference from a recipe to another recipe file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SubRecipe {
+    pub name: String,
+    /// Path to the recipe file, relative to the recipe that declares it
+    pub path: String,
+    /// Parameter values passed to the sub-recipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum SubRecipeError {
+    #[error("Sub-recipe '{name}' exceeds the maximum nesting depth of {max_depth}")]
+    DepthExceeded { name: String, max_depth: usize },
+    #[error("Sub-recipe cycle detected: {}", .chain.join(" -> "))]
+    Cycle { chain: Vec<String> },
+    #[error("Failed to load sub-recipe '{}' from {}: {}", .name, .path.display(), .message)]
+    Load {
+        name: String,
+        path: PathBuf,
+        message: String,
+    },
+}
+
+/// A recipe together with its fully resolved sub-recipes
+#[derive(Debug)]
+pub struct RecipeTree {
+    pub name: String,
+    /// The file the recipe was loaded from, if it came from a file
+    pub path: Option<PathBuf>,
+    /// 0 for the root recipe
+    pub depth: usize,
+    pub recipe: Recipe,
+    pub values: HashMap<String, String>,
+    pub children: Vec<RecipeTree>,
+}
+
+impl RecipeTree {
+    /// The maximum nesting depth, configurable with `GOOSE_MAX_SUB_RECIPE_DEPTH`
+    pub fn max_depth_from_config() -> usize {
+        Config::global()
+            .get_param("GOOSE_MAX_SUB_RECIPE_DEPTH")
+            .unwrap_or(DEFAULT_MAX_SUB_RECIPE_DEPTH)
+    }
+
+    /// Load a recipe file and resolve all of its sub-recipes
+    pub fn from_file(path: &Path, max_depth: usize) -> Result<Self, SubRecipeError> {
+        let path = canonical_path(&path.display().to_string(), path)?;
+        let recipe = load_recipe_file(&path.display().to_string(), &path)?;
+        let base_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let mut root = Self::new(
+            recipe.title.clone(),
+            Some(path.clone()),
+            0,
+            recipe,
+            HashMap::new(),
+        );
+        let mut stack = vec![(path, root.name.clone())];
+        root.resolve_children(&base_dir, &mut stack, max_depth)?;
+        Ok(root)
+    }
+
+    /// Resolve the sub-recipes of an already loaded recipe, relative to `base_dir`
+    pub fn resolve(
+        recipe: Recipe,
+        base_dir: &Path,
+        max_depth: usize,
+    ) -> Result<Self, SubRecipeError> {
+        let mut root = Self::new(recipe.title.clone(), None, 0, recipe, HashMap::new());
+        let mut stack = Vec::new();
+        root.resolve_children(base_dir, &mut stack, max_depth)?;
+        Ok(root)
+    }
+
+    fn new(
+        name: String,
+        path: Option<PathBuf>,
+        depth: usize,
+        recipe: Recipe,
+        values: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            name,
+            path,
+            depth,
+            recipe,
+            values,
+            children: Vec::new(),
+        }
+    }
+
+    /// Load each declared sub-recipe, tracking the chain of ancestor files to detect cycles
+    fn resolve_children(
+        &mut self,
+        base_dir: &Path,
+        stack: &mut Vec<(PathBuf, String)>,
+        max_depth: usize,
+    ) -> Result<(), SubRecipeError> {
+        let sub_recipes = self.recipe.sub_recipes.clone().unwrap_or_default();
+        for sub_recipe in sub_recipes {
+            let depth = self.depth + 1;
+            if depth > max_depth {
+                return Err(SubRecipeError::DepthExceeded {
+                    name: sub_recipe.name,
+                    max_depth,
+                });
+            }
+
+            let path = canonical_path(&sub_recipe.name, &base_dir.join(&sub_recipe.path))?;
+            if let Some(start) = stack.iter().position(|(p, _)| p == &path) {
+                let mut chain: Vec<String> = stack[start..]
+                    .iter()
+                    .map(|(_, name)| name.clone())
+                    .collect();
+                chain.push(sub_recipe.name);
+                return Err(SubRecipeError::Cycle { chain });
+            }
+
+            let recipe = load_recipe_file(&sub_recipe.name, &path)?;
+            let mut child = Self::new(
+                sub_recipe.name.clone(),
+                Some(path.clone()),
+                depth,
+                recipe,
+                sub_recipe.values.unwrap_or_default(),
+            );
+
+            let child_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            stack.push((path, sub_recipe.name));
+            child.resolve_children(&child_dir, stack, max_depth)?;
+            stack.pop();
+
+            self.children.push(child);
+        }
+        Ok(())
+    }
+
+    /// The number of recipes in the tree, including this one
+    pub fn recipe_count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(RecipeTree::recipe_count)
+            .sum::<usize>()
+    }
+
+    /// The deepest nesting level below this recipe
+    pub fn height(&self) -> usize {
+        self.children
+            .iter()
+            .map(|child| child.height() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// A report for the tree where nothing has run yet
+    pub fn report(&self) -> SubRecipeReport {
+        SubRecipeReport {
+            name: self.name.clone(),
+            status: SubRecipeStatus::Pending,
+            output: None,
+            error: None,
+            children: self.children.iter().map(RecipeTree::report).collect(),
+        }
+    }
+
+    /// Run the tree bottom-up: every sub-recipe finishes before the recipe that declared it,
+    /// which then receives the reports of its sub-recipes.
+    pub fn run<'a>(&'a self, runner: &'a dyn SubRecipeRunner) -> BoxFuture<'a, SubRecipeReport> {
+        async move {
+            let children = self.run_children(runner).await;
+            let (status, output, error) = match runner.run(self, &children).await {
+                Ok(output) => (SubRecipeStatus::Completed, Some(output), None),
+                Err(e) => (SubRecipeStatus::Failed, None, Some(e.to_string())),
+            };
+
+            SubRecipeReport {
+                name: self.name.clone(),
+                status,
+                output,
+                error,
+                children,
+            }
+        }
+        .boxed()
+    }
+
+    /// Run the sub-recipes of this recipe, in the order they are declared, without running
+    /// the recipe itself
+    pub async fn run_children(&self, runner: &dyn SubRecipeRunner) -> Vec<SubRecipeReport> {
+        let mut children = Vec::with_capacity(self.children.len());
+        for child in &self.children {
+            children.push(child.run(runner).await);
+        }
+        children
+    }
+}
+
+/// Instructions telling a recipe how its sub-recipes went, or None when it has none
+pub fn sub_recipe_instructions(children: &[SubRecipeReport]) -> Option<String> {
+    if children.is_empty() {
+        return None;
+    }
+    let summaries: Vec<String> = children.iter().map(SubRecipeReport::summary).collect();
+    Some(format!(
+        "The sub-recipes of this recipe have already run:\n{}",
+        summaries.join("\n")
+    ))
+}
+
+/// Executes a single recipe from a [`RecipeTree`]
+#[async_trait]
+pub trait SubRecipeRunner: Send + Sync {
+    /// Run `node` once all of its sub-recipes have finished, returning its output
+    async fn run(&self, node: &RecipeTree, children: &[SubRecipeReport]) -> anyhow::Result<String>;
+}
+
+/// Runs each recipe headless in a fresh agent on a shared provider, with the recipe's
+/// extensions, its instructions and the reports of its sub-recipes in the system prompt, and
+/// its prompt as the only message. The output is the last thing the assistant said.
+pub struct AgentRunner {
+    provider: Arc<dyn Provider>,
+}
+
+impl AgentRunner {
+    pub fn new(provider: Arc<dyn Provider>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl SubRecipeRunner for AgentRunner {
+    async fn run(&self, node: &RecipeTree, children: &[SubRecipeReport]) -> anyhow::Result<String> {
+        let render = |template: &str| {
+            render_inline_once(template, &node.values)
+                .with_context(|| format!("Failed to render sub-recipe '{}'", node.name))
+        };
+
+        let agent = Agent::new();
+        agent.update_provider(self.provider.clone()).await?;
+        for extension in node.recipe.extensions.iter().flatten() {
+            agent
+                .add_extension(extension.clone())
+                .await
+                .with_context(|| format!("Failed to add extension '{}'", extension.name()))?;
+        }
+        if let Some(instructions) = &node.recipe.instructions {
+            agent.extend_system_prompt(render(instructions)?).await;
+        }
+        if let Some(instructions) = sub_recipe_instructions(children) {
+            agent.extend_system_prompt(instructions).await;
+        }
+
+        let prompt = match &node.recipe.prompt {
+            Some(prompt) => render(prompt)?,
+            None => "Carry out your instructions.".to_string(),
+        };
+        let mut stream = agent
+            .reply(&[Message::user().with_text(prompt)], None)
+            .await?;
+        let mut output = String::new();
+        while let Some(event) = stream.next().await {
+            if let AgentEvent::Message(message) = event? {
+                let text = message.as_concat_text();
+                if message.role == Role::Assistant && !text.trim().is_empty() {
+                    output = text;
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubRecipeStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// The outcome of running a recipe and everything beneath it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubRecipeReport {
+    pub name: String,
+    pub status: SubRecipeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SubRecipeReport>,
+}
+
+impl SubRecipeReport {
+    /// Whether this recipe or any recipe beneath it failed
+    pub fn has_failures(&self) -> bool {
+        self.status == SubRecipeStatus::Failed || self.children.iter().any(|c| c.has_failures())
+    }
+
+    /// Count of recipes in the tree for each status
+    pub fn status_counts(&self) -> HashMap<SubRecipeStatus, usize> {
+        let mut counts = HashMap::new();
+        self.count_into(&mut counts);
+        counts
+    }
+
+    fn count_into(&self, counts: &mut HashMap<SubRecipeStatus, usize>) {
+        *counts.entry(self.status).or_insert(0) += 1;
+        for child in &self.children {
+            child.count_into(counts);
+        }
+    }
+
+    /// Render the tree as an indented list for the parent recipe to read
+    pub fn summary(&self) -> String {
+        let mut lines = Vec::new();
+        self.summary_into(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn summary_into(&self, indent: usize, lines: &mut Vec<String>) {
+        let status = serde_json::to_string(&self.status).unwrap_or_default();
+        let detail = match (&self.error, &self.output) {
+            (Some(error), _) => format!(": {}", error),
+            (None, Some(output)) => match output.lines().next() {
+                Some(first) if !first.trim().is_empty() => format!(": {}", first.trim()),
+                _ => String::new(),
+            },
+            (None, None) => String::new(),
+        };
+        lines.push(format!(
+            "{}- {} [{}]{}",
+            "  ".repeat(indent),
+            self.name,
+            status.trim_matches('"'),
+            detail
+        ));
+        for child in &self.children {
+            child.summary_into(indent + 1, lines);
+        }
+    }
+}
+
+fn canonical_path(name: &str, path: &Path) -> Result<PathBuf, SubRecipeError> {
+    path.canonicalize().map_err(|e| SubRecipeError::Load {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn load_recipe_file(name: &str, path: &Path) -> Result<Recipe, SubRecipeError> {
+    let load_error = |message: String| SubRecipeError::Load {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        message,
+    };
+
+    let content = std::fs::read_to_string(path).map_err(|e| load_error(e.to_string()))?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") | Some("jsonl") => {
+            serde_json::from_str(&content).map_err(|e| load_error(e.to_string()))
+        }
+        _ => serde_yaml::from_str(&content).map_err(|e| load_error(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ModelConfig;
+    use crate::providers::base::{ProviderMetadata, ProviderUsage, Usage};
+    use crate::providers::errors::ProviderError;
+    use mcp_core::tool::Tool;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn write_recipe(dir: &Path, file: &str, title: &str, subs: &[(&str, &str)]) -> PathBuf {
+        let mut content = format!(
+            "title: {}\ndescription: test\ninstructions: do the thing\n",
+            title
+        );
+        if !subs.is_empty() {
+            content.push_str("sub_recipes:\n");
+            for (name, path) in subs {
+                content.push_str(&format!("  - name: {}\n    path: {}\n", name, path));
+            }
+        }
+        let path = dir.join(file);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    struct EchoRunner;
+
+    #[async_trait]
+    impl SubRecipeRunner for EchoRunner {
+        async fn run(
+            &self,
+            node: &RecipeTree,
+            children: &[SubRecipeReport],
+        ) -> anyhow::Result<String> {
+            if node.name == "broken" {
+                anyhow::bail!("broken on purpose");
+            }
+            Ok(format!("{} saw {} sub-recipes", node.name, children.len()))
+        }
+    }
+
+    #[test]
+    fn test_resolves_nested_tree() {
+        let dir = TempDir::new().unwrap();
+        write_recipe(dir.path(), "leaf.yaml", "Leaf", &[]);
+        write_recipe(
+            dir.path(),
+            "middle.yaml",
+            "Middle",
+            &[("leaf", "leaf.yaml")],
+        );
+        let root = write_recipe(
+            dir.path(),
+            "root.yaml",
+            "Root",
+            &[("middle", "middle.yaml"), ("leaf", "leaf.yaml")],
+        );
+
+        let tree = RecipeTree::from_file(&root, DEFAULT_MAX_SUB_RECIPE_DEPTH).unwrap();
+        assert_eq!(tree.recipe_count(), 4);
+        assert_eq!(tree.height(), 2);
+        assert_eq!(tree.children[0].children[0].depth, 2);
+    }
+
+    #[test]
+    fn test_rejects_cycles() {
+        let dir = TempDir::new().unwrap();
+        write_recipe(dir.path(), "a.yaml", "A", &[("b", "b.yaml")]);
+        write_recipe(dir.path(), "b.yaml", "B", &[("a", "a.yaml")]);
+
+        let err = RecipeTree::from_file(&dir.path().join("a.yaml"), 10).unwrap_err();
+        match err {
+            SubRecipeError::Cycle { chain } => assert_eq!(chain, vec!["A", "b", "a"]),
+            other => panic!("expected a cycle, got {other}"),
+        }
+    }
+
+    #[test]
+    fn test_rejects_excessive_depth() {
+        let dir = TempDir::new().unwrap();
+        write_recipe(dir.path(), "c.yaml", "C", &[]);
+        write_recipe(dir.path(), "b.yaml", "B", &[("c", "c.yaml")]);
+        let root = write_recipe(dir.path(), "a.yaml", "A", &[("b", "b.yaml")]);
+
+        assert!(RecipeTree::from_file(&root, 2).is_ok());
+        assert!(matches!(
+            RecipeTree::from_file(&root, 1),
+            Err(SubRecipeError::DepthExceeded { max_depth: 1, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_aggregates_reports() {
+        let dir = TempDir::new().unwrap();
+        write_recipe(dir.path(), "leaf.yaml", "Leaf", &[]);
+        write_recipe(dir.path(), "broken.yaml", "Broken", &[]);
+        let root = write_recipe(
+            dir.path(),
+            "root.yaml",
+            "Root",
+            &[("leaf", "leaf.yaml"), ("broken", "broken.yaml")],
+        );
+
+        let tree = RecipeTree::from_file(&root, DEFAULT_MAX_SUB_RECIPE_DEPTH).unwrap();
+        let report = tree.run(&EchoRunner).await;
+
+        assert_eq!(report.status, SubRecipeStatus::Completed);
+        assert_eq!(report.output.as_deref(), Some("Root saw 2 sub-recipes"));
+        assert!(report.has_failures());
+        assert_eq!(report.status_counts()[&SubRecipeStatus::Completed], 2);
+        assert_eq!(
+            report.summary(),
+            "- Root [completed]: Root saw 2 sub-recipes\n  \
+             - leaf [completed]: leaf saw 0 sub-recipes\n  \
+             - broken [failed]: broken on purpose"
+        );
+    }
+
+    struct ReportProvider;
+
+    #[async_trait]
+    impl Provider for ReportProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("test-model".to_string())
+        }
+
+        async fn complete(
+            &self,
+            system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            let text = if system.contains("- leaf [completed]: no reports") {
+                "saw the leaf report"
+            } else {
+                "no reports"
+            };
+            Ok((
+                Message::assistant().with_text(text),
+                ProviderUsage::new("mock".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_runner_hands_reports_to_the_parent() {
+        let dir = TempDir::new().unwrap();
+        write_recipe(dir.path(), "leaf.yaml", "Leaf", &[]);
+        let root = write_recipe(dir.path(), "root.yaml", "Root", &[("leaf", "leaf.yaml")]);
+
+        let tree = RecipeTree::from_file(&root, DEFAULT_MAX_SUB_RECIPE_DEPTH).unwrap();
+        let report = tree.run(&AgentRunner::new(Arc::new(ReportProvider))).await;
+
+        assert!(!report.has_failures(), "{}", report.summary());
+        assert_eq!(report.children[0].output.as_deref(), Some("no reports"));
+        assert_eq!(report.output.as_deref(), Some("saw the leaf report"));
+    }
+}
@@ -17,8 +17,11 @@ use crate::agents::{Agent, SessionConfig};
 use crate::config::{self, Config};
 use crate::message::Message;
 use crate::providers::base::Provider as GooseProvider; // Alias to avoid conflict in test section
+use crate::providers::batch::{run_batch, BatchConfig};
 use crate::providers::create;
-use crate::recipe::Recipe;
+use crate::providers::quota::{ProviderQuotas, QuotaStatus};
+use crate::recipe::output_check::final_output;
+use crate::recipe::{OutputCheckReport, Recipe, RecipeBatch, RetryScope};
 use crate::scheduler_trait::SchedulerTrait;
 use crate::session;
 use crate::session::storage::SessionMetadata;
@@ -124,6 +127,12 @@ pub struct ScheduledJob {
     pub process_start_time: Option<DateTime<Utc>>,
 }
 
+/// The exceeded quota of the configured provider that scheduled jobs wait for, if any
+fn quota_deferral() -> Option<QuotaStatus> {
+    let provider: String = Config::global().get_param("GOOSE_PROVIDER").ok()?;
+    ProviderQuotas::from_config().deferral(&provider, Utc::now())
+}
+
 async fn persist_jobs_from_arc(
     storage_path: &Path,
     jobs_arc: &Arc<Mutex<JobsMap>>,
@@ -254,6 +263,15 @@ impl Scheduler {
                     return;
                 }
 
+                if let Some(status) = quota_deferral() {
+                    tracing::info!(
+                        "Deferring job '{}' until the provider quota resets: {}",
+                        &task_job_id,
+                        status
+                    );
+                    return;
+                }
+
                 let current_time = Utc::now();
                 let mut needs_persist = false;
                 {
@@ -410,6 +428,15 @@ impl Scheduler {
                         return;
                     }
 
+                    if let Some(status) = quota_deferral() {
+                        tracing::info!(
+                            "Deferring job '{}' until the provider quota resets: {}",
+                            &task_job_id,
+                            status
+                        );
+                        return;
+                    }
+
                     let current_time = Utc::now();
                     let mut needs_persist = false;
                     {
@@ -769,6 +796,15 @@ impl Scheduler {
                             return;
                         }
 
+                        if let Some(status) = quota_deferral() {
+                            tracing::info!(
+                                "Deferring job '{}' until the provider quota resets: {}",
+                                &task_job_id,
+                                status
+                            );
+                            return;
+                        }
+
                         let current_time = Utc::now();
                         let mut needs_persist = false;
                         {
@@ -1059,6 +1095,21 @@ async fn run_scheduled_job_internal(
     }
     tracing::info!("Agent configured with provider for job '{}'", job.id);
 
+    if let Some(stop_conditions) = recipe.stop_conditions.clone() {
+        agent.set_termination_conditions(stop_conditions).await;
+    }
+    if let Some(acceptance_criteria) = recipe.acceptance_criteria.clone() {
+        agent.set_acceptance_criteria(acceptance_criteria).await;
+    }
+    if let Some(turns) = recipe.conversation_template.clone() {
+        if let Err(e) = agent.set_conversation_template(turns).await {
+            return Err(JobExecutionError {
+                job_id: job.id.clone(),
+                error: format!("Invalid conversation template: {}", e),
+            });
+        }
+    }
+
     let session_id_for_return = session::generate_session_id();
 
     // Update the job with the session ID if we have access to the jobs arc
@@ -1073,6 +1124,12 @@ async fn run_scheduled_job_internal(
         crate::session::storage::Identifier::Name(session_id_for_return.clone()),
     );
 
+    if RecipeBatch::eligible(&recipe) {
+        run_batch_job(&job, &recipe, recipe_path, &agent, &session_file_path).await?;
+        tracing::info!("Finished job: {}", job.id);
+        return Ok(session_id_for_return);
+    }
+
     if let Some(prompt_text) = recipe.prompt {
         let mut all_session_messages: Vec<Message> =
             vec![Message::user().with_text(prompt_text.clone())];
@@ -1091,90 +1148,170 @@ async fn run_scheduled_job_internal(
             id: crate::session::storage::Identifier::Name(session_id_for_return.clone()),
             working_dir: current_dir.clone(),
             schedule_id: Some(job.id.clone()),
+            sampling: None,
+            tags: Default::default(),
+            config_overrides: Default::default(),
         };
 
-        match agent
-            .reply(&all_session_messages, Some(session_config.clone()))
-            .await
-        {
-            Ok(mut stream) => {
-                use futures::StreamExt;
-
-                while let Some(message_result) = stream.next().await {
-                    // Check if the task has been cancelled
-                    tokio::task::yield_now().await;
-
-                    match message_result {
-                        Ok(AgentEvent::Message(msg)) => {
-                            if msg.role == mcp_core::role::Role::Assistant {
-                                tracing::info!("[Job {}] Assistant: {:?}", job.id, msg.content);
+        let output_checks = recipe.output_checks.clone();
+        let mut output_report = OutputCheckReport::default();
+        loop {
+            match agent
+                .reply(&all_session_messages, Some(session_config.clone()))
+                .await
+            {
+                Ok(mut stream) => {
+                    use futures::StreamExt;
+
+                    while let Some(message_result) = stream.next().await {
+                        // Check if the task has been cancelled
+                        tokio::task::yield_now().await;
+
+                        match message_result {
+                            Ok(AgentEvent::Message(msg)) => {
+                                if msg.role == mcp_core::role::Role::Assistant {
+                                    tracing::info!("[Job {}] Assistant: {:?}", job.id, msg.content);
+                                }
+                                all_session_messages.push(msg);
+                            }
+                            Ok(AgentEvent::TextChunk(_)) => {
+                                // The response follows whole once it is complete
+                            }
+                            Ok(AgentEvent::McpNotification(_)) => {
+                                // Handle notifications if needed
+                            }
+                            Ok(AgentEvent::ModelChange { .. }) => {
+                                // Model change events are informational, just continue
+                            }
+                            Ok(AgentEvent::TurnBudget(_)) => {
+                                // Turn budget events are informational, just continue
+                            }
+                            Ok(AgentEvent::ExtensionsDegraded(_)) => {
+                                // Degraded extensions are logged by the agent, just continue
+                            }
+                            Ok(AgentEvent::PolicyViolations(_)) => {
+                                // Policy violations are informational, just continue
+                            }
+                            Ok(AgentEvent::InputQueued(_)) => {
+                                // Queued input acknowledgements are informational, just continue
+                            }
+                            Ok(AgentEvent::QuotaWarning(status)) => {
+                                tracing::warn!("[Job {}] {}", job.id, status);
+                            }
+                            Ok(AgentEvent::ContextUsage(_)) => {
+                                // Context usage is informational, just continue
+                            }
+                            Ok(AgentEvent::ToolRouting(status)) => {
+                                tracing::warn!("[Job {}] {}", job.id, status);
+                            }
+                            Ok(AgentEvent::UiResources(_)) => {
+                                // Interactive views are only rendered by frontends
+                            }
+                            Ok(AgentEvent::Transcribed(_)) => {
+                                // Recipes are not spoken, so there is nothing to transcribe
+                            }
+                            Ok(AgentEvent::Cancelled) => {
+                                tracing::warn!("[Job {}] Reply cancelled", job.id);
+                            }
+                            Err(e) => {
+                                tracing::error!(
+                                    "[Job {}] Error receiving message from agent: {}",
+                                    job.id,
+                                    e
+                                );
+                                break;
                             }
-                            all_session_messages.push(msg);
-                        }
-                        Ok(AgentEvent::McpNotification(_)) => {
-                            // Handle notifications if needed
-                        }
-                        Ok(AgentEvent::ModelChange { .. }) => {
-                            // Model change events are informational, just continue
-                        }
-                        Err(e) => {
-                            tracing::error!(
-                                "[Job {}] Error receiving message from agent: {}",
-                                job.id,
-                                e
-                            );
-                            break;
                         }
                     }
                 }
+                Err(e) => {
+                    return Err(JobExecutionError {
+                        job_id: job.id.clone(),
+                        error: format!("Agent failed to reply for recipe '{}': {}", job.source, e),
+                    });
+                }
+            }
 
-                match crate::session::storage::read_metadata(&session_file_path) {
-                    Ok(mut updated_metadata) => {
-                        updated_metadata.message_count = all_session_messages.len();
-                        if let Err(e) = crate::session::storage::save_messages_with_metadata(
-                            &session_file_path,
-                            &updated_metadata,
-                            &all_session_messages,
-                        ) {
-                            tracing::error!(
-                                "[Job {}] Failed to persist final messages: {}",
-                                job.id,
-                                e
-                            );
-                        }
-                    }
-                    Err(e) => {
-                        tracing::error!(
-                            "[Job {}] Failed to read updated metadata before final save: {}",
-                            job.id,
-                            e
-                        );
-                        let fallback_metadata = crate::session::storage::SessionMetadata {
-                            working_dir: current_dir.clone(),
-                            description: String::new(),
-                            schedule_id: Some(job.id.clone()),
-                            message_count: all_session_messages.len(),
-                            total_tokens: None,
-                            input_tokens: None,
-                            output_tokens: None,
-                            accumulated_total_tokens: None,
-                            accumulated_input_tokens: None,
-                            accumulated_output_tokens: None,
-                        };
-                        if let Err(e_fb) = crate::session::storage::save_messages_with_metadata(
-                            &session_file_path,
-                            &fallback_metadata,
-                            &all_session_messages,
-                        ) {
-                            tracing::error!("[Job {}] Failed to persist final messages with fallback metadata: {}", job.id, e_fb);
-                        }
-                    }
+            let Some(checks) = &output_checks else {
+                break;
+            };
+            let output = final_output(&all_session_messages);
+            let Some(correction) = checks
+                .review(&mut output_report, &output, &current_dir)
+                .await
+            else {
+                break;
+            };
+            tracing::warn!(
+                "[Job {}] Output checks failed on attempt {}, trying again",
+                job.id,
+                output_report.attempts
+            );
+            match checks.retry.scope {
+                RetryScope::Turn => {
+                    all_session_messages.push(Message::user().with_text(correction))
+                }
+                RetryScope::Recipe => {
+                    all_session_messages = vec![
+                        Message::user().with_text(format!("{}\n\n{}", prompt_text, correction))
+                    ];
+                }
+            }
+        }
+
+        match crate::session::storage::read_metadata(&session_file_path) {
+            Ok(mut updated_metadata) => {
+                updated_metadata.message_count = all_session_messages.len();
+                if let Err(e) = crate::session::storage::save_messages_with_metadata(
+                    &session_file_path,
+                    &updated_metadata,
+                    &all_session_messages,
+                ) {
+                    tracing::error!("[Job {}] Failed to persist final messages: {}", job.id, e);
                 }
             }
             Err(e) => {
+                tracing::error!(
+                    "[Job {}] Failed to read updated metadata before final save: {}",
+                    job.id,
+                    e
+                );
+                let fallback_metadata = crate::session::storage::SessionMetadata {
+                    working_dir: current_dir.clone(),
+                    description: String::new(),
+                    schedule_id: Some(job.id.clone()),
+                    message_count: all_session_messages.len(),
+                    total_tokens: None,
+                    input_tokens: None,
+                    output_tokens: None,
+                    accumulated_total_tokens: None,
+                    accumulated_input_tokens: None,
+                    accumulated_output_tokens: None,
+                    tags: Default::default(),
+                    reply_cancelled: false,
+                    accumulated_cost: None,
+                    unpriced_responses: 0,
+                };
+                if let Err(e_fb) = crate::session::storage::save_messages_with_metadata(
+                    &session_file_path,
+                    &fallback_metadata,
+                    &all_session_messages,
+                ) {
+                    tracing::error!(
+                        "[Job {}] Failed to persist final messages with fallback metadata: {}",
+                        job.id,
+                        e_fb
+                    );
+                }
+            }
+        }
+
+        if output_checks.is_some() {
+            tracing::info!("[Job {}] {}", job.id, output_report);
+            if !output_report.passed {
                 return Err(JobExecutionError {
                     job_id: job.id.clone(),
-                    error: format!("Agent failed to reply for recipe '{}': {}", job.source, e),
+                    error: output_report.to_string(),
                 });
             }
         }
@@ -1206,6 +1343,56 @@ async fn run_scheduled_job_internal(
     Ok(session_id_for_return)
 }
 
+/// Answer a batch recipe's prompt for each of its inputs, through the provider's batch API
+/// when it has one, and save the answers as the job's session
+async fn run_batch_job(
+    job: &ScheduledJob,
+    recipe: &Recipe,
+    recipe_path: &Path,
+    agent: &Agent,
+    session_file_path: &Path,
+) -> std::result::Result<(), JobExecutionError> {
+    let job_error = |error: String| JobExecutionError {
+        job_id: job.id.clone(),
+        error,
+    };
+    let (Some(batch), Some(prompt)) = (&recipe.batch, &recipe.prompt) else {
+        return Ok(());
+    };
+
+    let base_dir = recipe_path.parent().unwrap_or_else(|| Path::new("."));
+    let inputs = batch
+        .load_inputs(base_dir)
+        .map_err(|e| job_error(e.to_string()))?;
+    let system = recipe.instructions.clone().unwrap_or_default();
+    let requests = RecipeBatch::requests(&system, prompt, &inputs);
+
+    let provider = agent
+        .provider()
+        .await
+        .map_err(|e| job_error(format!("No provider for batch: {}", e)))?;
+    tracing::info!(
+        "[Job {}] Running {} inputs as a batch (batch API: {})",
+        job.id,
+        requests.len(),
+        provider.as_batch().is_some()
+    );
+    let results = run_batch(provider.as_ref(), &requests, &BatchConfig::from_config())
+        .await
+        .map_err(|e| job_error(format!("Batch for recipe '{}' failed: {}", job.source, e)))?;
+
+    let messages = RecipeBatch::session_messages(&requests, &results);
+    let metadata = crate::session::storage::SessionMetadata {
+        working_dir: std::env::current_dir().unwrap_or_default(),
+        description: recipe.title.clone(),
+        schedule_id: Some(job.id.clone()),
+        message_count: messages.len(),
+        ..Default::default()
+    };
+    crate::session::storage::save_messages_with_metadata(session_file_path, &metadata, &messages)
+        .map_err(|e| job_error(format!("Failed to persist batch results: {}", e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1304,6 +1491,12 @@ mod tests {
             author: None,
             parameters: None,
             settings: None,
+            stop_conditions: None,
+            acceptance_criteria: None,
+            conversation_template: None,
+            sub_recipes: None,
+            batch: None,
+            output_checks: None,
         };
         let mut recipe_file = File::create(&recipe_filename)?;
         writeln!(
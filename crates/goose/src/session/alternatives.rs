@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::message::Message;
+use crate::session::storage::read_messages;
+use mcp_core::Role;
+
+/// An assistant turn generated again with another provider or model, kept next to the
+/// original for comparison. The session history itself is not changed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternative {
+    /// Index in the session of the assistant message this is an alternative to
+    pub message_index: usize,
+    pub provider: String,
+    pub model: String,
+    /// Unix timestamp of when the alternative was generated
+    pub created: i64,
+    pub message: Message,
+    pub input_tokens: Option<i32>,
+    pub output_tokens: Option<i32>,
+}
+
+/// Alternatives are stored in a file alongside the session, e.g. `20250101_120000.alternatives.json`
+pub fn alternatives_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("alternatives.json")
+}
+
+/// All alternatives generated for a session, oldest first
+pub fn list_alternatives(session_file: &Path) -> Result<Vec<Alternative>> {
+    let path = alternatives_path(session_file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+pub fn add_alternative(session_file: &Path, alternative: Alternative) -> Result<()> {
+    let mut alternatives = list_alternatives(session_file)?;
+    alternatives.push(alternative);
+    fs::write(
+        alternatives_path(session_file),
+        serde_json::to_string_pretty(&alternatives)?,
+    )?;
+    Ok(())
+}
+
+/// The history the assistant message at an index was generated from
+pub fn history_before(session_file: &Path, message_index: usize) -> Result<Vec<Message>> {
+    let mut messages = read_messages(session_file)?;
+    match messages.get(message_index) {
+        Some(message) if message.role == Role::Assistant => {
+            messages.truncate(message_index);
+            Ok(messages)
+        }
+        Some(_) => Err(anyhow!(
+            "Message {} is not an assistant turn",
+            message_index
+        )),
+        None => Err(anyhow!(
+            "The session has {} messages, there is no message {}",
+            messages.len(),
+            message_index
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::{save_messages_with_metadata, SessionMetadata};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_history_and_alternatives() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("20250101_120000.jsonl");
+        let messages = vec![
+            Message::user().with_text("Summarize the README"),
+            Message::assistant().with_text("It is a README"),
+        ];
+        save_messages_with_metadata(
+            &session_file,
+            &SessionMetadata::new(dir.path().to_path_buf()),
+            &messages,
+        )
+        .unwrap();
+
+        let history = history_before(&session_file, 1).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history_before(&session_file, 0).is_err());
+        assert!(history_before(&session_file, 2).is_err());
+
+        add_alternative(
+            &session_file,
+            Alternative {
+                message_index: 1,
+                provider: "openai".to_string(),
+                model: "gpt-4o".to_string(),
+                created: 0,
+                message: Message::assistant().with_text("The README explains how to install goose"),
+                input_tokens: Some(12),
+                output_tokens: Some(9),
+            },
+        )
+        .unwrap();
+        let alternatives = list_alternatives(&session_file).unwrap();
+        assert_eq!(alternatives.len(), 1);
+        assert_eq!(alternatives[0].model, "gpt-4o");
+        // The session itself is untouched
+        assert_eq!(read_messages(&session_file).unwrap().len(), 2);
+    }
+}
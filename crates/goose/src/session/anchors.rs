@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use mcp_core::Role;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::session::storage::read_messages;
+
+/// A named point in a conversation, e.g. "decision point A". The anchored content is kept
+/// verbatim next to the session file, so it can be recalled after the surrounding history
+/// has been summarized or truncated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Anchor {
+    pub name: String,
+    /// Unix timestamp of when the anchor was set
+    pub created: i64,
+    pub role: Role,
+    /// Index of the anchored message in the session, if the anchor marks a whole message
+    pub message_index: Option<usize>,
+    /// The anchored content, verbatim
+    pub content: String,
+}
+
+impl Anchor {
+    pub fn new(name: impl Into<String>, role: Role, content: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            created: Utc::now().timestamp(),
+            role,
+            message_index: None,
+            content: content.into(),
+        }
+    }
+}
+
+/// Anchors are stored in a file alongside the session, e.g. `20250101_120000.anchors.json`
+pub fn anchors_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("anchors.json")
+}
+
+/// All anchors in a session, in the order they were set
+pub fn list_anchors(session_file: &Path) -> Result<Vec<Anchor>> {
+    let path = anchors_path(session_file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save_anchors(session_file: &Path, anchors: &[Anchor]) -> Result<()> {
+    fs::write(
+        anchors_path(session_file),
+        serde_json::to_string_pretty(anchors)?,
+    )?;
+    Ok(())
+}
+
+pub fn get_anchor(session_file: &Path, name: &str) -> Result<Option<Anchor>> {
+    Ok(list_anchors(session_file)?
+        .into_iter()
+        .find(|anchor| anchor.name == name.trim()))
+}
+
+/// Set an anchor, replacing any existing anchor with the same name
+pub fn set_anchor(session_file: &Path, mut anchor: Anchor) -> Result<()> {
+    anchor.name = anchor.name.trim().to_string();
+    if anchor.name.is_empty() {
+        return Err(anyhow!("Anchor name cannot be empty"));
+    }
+    let mut anchors = list_anchors(session_file)?;
+    anchors.retain(|existing| existing.name != anchor.name);
+    anchors.push(anchor);
+    save_anchors(session_file, &anchors)
+}
+
+/// Remove an anchor, returning whether it existed
+pub fn remove_anchor(session_file: &Path, name: &str) -> Result<bool> {
+    let mut anchors = list_anchors(session_file)?;
+    let count = anchors.len();
+    anchors.retain(|anchor| anchor.name != name.trim());
+    if anchors.len() == count {
+        return Ok(false);
+    }
+    save_anchors(session_file, &anchors)?;
+    Ok(true)
+}
+
+/// Anchor a message of the session by its index, keeping its text verbatim
+pub fn anchor_message(session_file: &Path, name: &str, message_index: usize) -> Result<Anchor> {
+    let messages = read_messages(session_file)?;
+    let message = messages
+        .get(message_index)
+        .ok_or_else(|| anyhow!("The session has no message {}", message_index))?;
+
+    let mut anchor = Anchor::new(name, message.role.clone(), message.as_concat_text());
+    anchor.message_index = Some(message_index);
+    set_anchor(session_file, anchor.clone())?;
+    Ok(anchor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::session::storage::{save_messages_with_metadata, SessionMetadata};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_set_recall_and_replace_anchors() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("session.jsonl");
+
+        set_anchor(
+            &session_file,
+            Anchor::new("decision A", Role::Assistant, "Use sqlite for storage"),
+        )
+        .unwrap();
+        set_anchor(
+            &session_file,
+            Anchor::new("decision A", Role::Assistant, "Use postgres for storage"),
+        )
+        .unwrap();
+        set_anchor(&session_file, Anchor::new("goal", Role::User, "Ship v2")).unwrap();
+
+        let anchors = list_anchors(&session_file).unwrap();
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(
+            get_anchor(&session_file, " decision A ")
+                .unwrap()
+                .unwrap()
+                .content,
+            "Use postgres for storage"
+        );
+
+        assert!(remove_anchor(&session_file, "goal").unwrap());
+        assert!(!remove_anchor(&session_file, "goal").unwrap());
+        assert!(get_anchor(&session_file, "goal").unwrap().is_none());
+        assert!(set_anchor(&session_file, Anchor::new(" ", Role::User, "")).is_err());
+    }
+
+    #[test]
+    fn test_anchor_message_from_session() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("session.jsonl");
+        let messages = vec![
+            Message::user().with_text("Which database should we use?"),
+            Message::assistant().with_text("Postgres, because we need concurrent writers."),
+        ];
+        save_messages_with_metadata(&session_file, &SessionMetadata::default(), &messages).unwrap();
+
+        let anchor = anchor_message(&session_file, "db choice", 1).unwrap();
+        assert_eq!(anchor.role, Role::Assistant);
+        assert_eq!(anchor.message_index, Some(1));
+        assert_eq!(
+            get_anchor(&session_file, "db choice")
+                .unwrap()
+                .unwrap()
+                .content,
+            "Postgres, because we need concurrent writers."
+        );
+        assert!(anchor_message(&session_file, "missing", 5).is_err());
+    }
+}
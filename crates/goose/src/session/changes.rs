@@ -0,0 +1,243 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::message::Message;
+use crate::providers::base::Provider;
+
+/// The tree git compares against in a repository without commits
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+/// Longest diff sent to the model for a summary, in characters
+const MAX_SUMMARY_DIFF_CHARS: usize = 20_000;
+
+/// Lines added and removed in one file. Binary files have no line counts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChange {
+    pub path: String,
+    pub insertions: Option<u64>,
+    pub deletions: Option<u64>,
+}
+
+/// The uncommitted changes in a session's working directory, relative to HEAD
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    /// Unix timestamp of when the changes were collected
+    pub created: i64,
+    /// Short hash of the commit the changes are relative to, if there is one
+    pub head: Option<String>,
+    /// Changed files that git tracks
+    pub files: Vec<FileChange>,
+    /// New files that git does not track yet and does not ignore
+    pub untracked: Vec<String>,
+    /// Output of `git diff --stat`
+    pub diff_stat: String,
+    /// A description of the changes written by the model
+    pub summary: Option<String>,
+}
+
+impl ChangeSummary {
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty() && self.untracked.is_empty()
+    }
+
+    /// The summary as plain text, for the model or a terminal
+    pub fn to_text(&self) -> String {
+        if self.is_empty() {
+            return "There are no uncommitted changes in the working directory".to_string();
+        }
+        let mut text = String::new();
+        if let Some(summary) = &self.summary {
+            text.push_str(summary.trim());
+            text.push_str("\n\n");
+        }
+        text.push_str(self.diff_stat.trim_end());
+        if !self.untracked.is_empty() {
+            text.push_str("\nUntracked files:");
+            for path in &self.untracked {
+                text.push_str(&format!("\n  {}", path));
+            }
+        }
+        text
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The commit to compare against, and its short hash if the repository has commits
+fn diff_base(dir: &Path) -> (String, Option<String>) {
+    match git(dir, &["rev-parse", "--short", "HEAD"]) {
+        Ok(head) => ("HEAD".to_string(), Some(head.trim().to_string())),
+        Err(_) => (EMPTY_TREE.to_string(), None),
+    }
+}
+
+pub fn is_git_repo(dir: &Path) -> bool {
+    git(dir, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|out| out.trim() == "true")
+}
+
+/// Parse the output of `git diff --numstat`
+pub fn parse_numstat(numstat: &str) -> Vec<FileChange> {
+    numstat
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let insertions = parts.next()?;
+            let deletions = parts.next()?;
+            let path = parts.next()?;
+            Some(FileChange {
+                path: path.to_string(),
+                insertions: insertions.parse().ok(),
+                deletions: deletions.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+/// Collect the uncommitted changes in a directory, or None if it is not in a git repository
+pub fn collect_changes(dir: &Path) -> Result<Option<ChangeSummary>> {
+    if !is_git_repo(dir) {
+        return Ok(None);
+    }
+    let (base, head) = diff_base(dir);
+    let files = parse_numstat(&git(dir, &["diff", &base, "--numstat"])?);
+    let diff_stat = git(dir, &["diff", &base, "--stat"])?;
+    let untracked = git(dir, &["ls-files", "--others", "--exclude-standard"])?
+        .lines()
+        .map(str::to_string)
+        .collect();
+    Ok(Some(ChangeSummary {
+        created: Utc::now().timestamp(),
+        head,
+        files,
+        untracked,
+        diff_stat,
+        summary: None,
+    }))
+}
+
+/// The full diff of the uncommitted changes to tracked files
+pub fn working_tree_diff(dir: &Path) -> Result<String> {
+    let (base, _) = diff_base(dir);
+    git(dir, &["diff", &base])
+}
+
+/// Ask the model to describe a diff in a few sentences
+pub async fn describe_changes(
+    provider: Arc<dyn Provider>,
+    summary: &ChangeSummary,
+    diff: &str,
+) -> Result<String> {
+    let mut diff: String = diff.chars().take(MAX_SUMMARY_DIFF_CHARS).collect();
+    if diff.len() < MAX_SUMMARY_DIFF_CHARS && !summary.untracked.is_empty() {
+        diff.push_str(&format!(
+            "\n\nNew untracked files: {}",
+            summary.untracked.join(", ")
+        ));
+    }
+    let prompt = format!(
+        "Summarize these working tree changes for a reviewer in a few sentences or bullet \
+         points. Say what changed and why it matters, not how git describes it.\n\n{}\n\n{}",
+        summary.diff_stat.trim_end(),
+        diff
+    );
+    let (message, _) = provider
+        .complete(
+            "You summarize code changes concisely. Reply with only the summary.",
+            &[Message::user().with_text(&prompt)],
+            &[],
+        )
+        .await?;
+    Ok(message.as_concat_text())
+}
+
+/// Change summaries are stored in a file alongside the session, e.g. `20250101_120000.changes.json`
+pub fn changes_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("changes.json")
+}
+
+/// The last change summary attached to a session
+pub fn read_change_summary(session_file: &Path) -> Result<Option<ChangeSummary>> {
+    let path = changes_path(session_file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// Attach a change summary to a session, replacing the previous one
+pub fn save_change_summary(session_file: &Path, summary: &ChangeSummary) -> Result<()> {
+    fs::write(
+        changes_path(session_file),
+        serde_json::to_string_pretty(summary)?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_numstat() {
+        let files = parse_numstat("3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n");
+        assert_eq!(
+            files,
+            vec![
+                FileChange {
+                    path: "src/lib.rs".to_string(),
+                    insertions: Some(3),
+                    deletions: Some(1),
+                },
+                FileChange {
+                    path: "logo.png".to_string(),
+                    insertions: None,
+                    deletions: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_change_summary_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("20250101_120000.jsonl");
+        assert!(read_change_summary(&session_file).unwrap().is_none());
+
+        let summary = ChangeSummary {
+            created: 0,
+            head: Some("1a2b3c4".to_string()),
+            files: parse_numstat("3\t1\tsrc/lib.rs"),
+            untracked: vec!["notes.md".to_string()],
+            diff_stat: " src/lib.rs | 4 +++-\n".to_string(),
+            summary: Some("Handle empty input".to_string()),
+        };
+        save_change_summary(&session_file, &summary).unwrap();
+        assert_eq!(
+            read_change_summary(&session_file).unwrap(),
+            Some(summary.clone())
+        );
+        assert_eq!(
+            summary.to_text(),
+            "Handle empty input\n\n src/lib.rs | 4 +++-\nUntracked files:\n  notes.md"
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::message::Message;
+use crate::session::storage::{read_messages, read_metadata, save_messages_with_metadata};
+
+/// A named point in a session's history, e.g. "before refactor". The session can be exported
+/// or branched as it was at the tag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub name: String,
+    /// Unix timestamp of when the tag was set
+    pub created: i64,
+    /// Number of messages in the session at the tag
+    pub message_count: usize,
+}
+
+/// Checkpoints are stored in a file alongside the session, e.g. `20250101_120000.checkpoints.json`
+pub fn checkpoints_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("checkpoints.json")
+}
+
+/// All checkpoints in a session, in the order they were tagged
+pub fn list_checkpoints(session_file: &Path) -> Result<Vec<Checkpoint>> {
+    let path = checkpoints_path(session_file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+fn save_checkpoints(session_file: &Path, checkpoints: &[Checkpoint]) -> Result<()> {
+    fs::write(
+        checkpoints_path(session_file),
+        serde_json::to_string_pretty(checkpoints)?,
+    )?;
+    Ok(())
+}
+
+pub fn get_checkpoint(session_file: &Path, name: &str) -> Result<Option<Checkpoint>> {
+    Ok(list_checkpoints(session_file)?
+        .into_iter()
+        .find(|checkpoint| checkpoint.name == name.trim()))
+}
+
+/// Tag the session as it is after its first `message_count` messages, replacing any
+/// checkpoint with the same name
+pub fn tag_checkpoint(session_file: &Path, name: &str, message_count: usize) -> Result<Checkpoint> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(anyhow!("Checkpoint name cannot be empty"));
+    }
+    let available = read_messages(session_file)?.len();
+    if message_count > available {
+        return Err(anyhow!(
+            "The session has {} messages, cannot tag message {}",
+            available,
+            message_count
+        ));
+    }
+
+    let checkpoint = Checkpoint {
+        name: name.to_string(),
+        created: Utc::now().timestamp(),
+        message_count,
+    };
+    let mut checkpoints = list_checkpoints(session_file)?;
+    checkpoints.retain(|existing| existing.name != checkpoint.name);
+    checkpoints.push(checkpoint.clone());
+    save_checkpoints(session_file, &checkpoints)?;
+    Ok(checkpoint)
+}
+
+/// Remove a checkpoint, returning whether it existed
+pub fn remove_checkpoint(session_file: &Path, name: &str) -> Result<bool> {
+    let mut checkpoints = list_checkpoints(session_file)?;
+    let count = checkpoints.len();
+    checkpoints.retain(|checkpoint| checkpoint.name != name.trim());
+    if checkpoints.len() == count {
+        return Ok(false);
+    }
+    save_checkpoints(session_file, &checkpoints)?;
+    Ok(true)
+}
+
+/// The messages of the session as it was at a checkpoint
+pub fn messages_at_checkpoint(session_file: &Path, name: &str) -> Result<Vec<Message>> {
+    let checkpoint = get_checkpoint(session_file, name)?
+        .ok_or_else(|| anyhow!("No checkpoint named '{}'", name))?;
+    let mut messages = read_messages(session_file)?;
+    messages.truncate(checkpoint.message_count);
+    Ok(messages)
+}
+
+/// Start a new session from the history of an existing one at a checkpoint. The new session
+/// keeps the working directory and the checkpoints tagged up to that point.
+pub fn branch_from_checkpoint(
+    session_file: &Path,
+    name: &str,
+    new_session_file: &Path,
+) -> Result<()> {
+    if new_session_file.exists() {
+        return Err(anyhow!(
+            "Session file {} already exists",
+            new_session_file.display()
+        ));
+    }
+    let messages = messages_at_checkpoint(session_file, name)?;
+    let mut metadata = read_metadata(session_file)?;
+    metadata.description = format!("{} (from {})", metadata.description, name.trim())
+        .trim_start()
+        .to_string();
+    metadata.message_count = messages.len();
+    save_messages_with_metadata(new_session_file, &metadata, &messages)?;
+
+    let inherited: Vec<Checkpoint> = list_checkpoints(session_file)?
+        .into_iter()
+        .filter(|checkpoint| checkpoint.message_count <= messages.len())
+        .collect();
+    save_checkpoints(new_session_file, &inherited)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::SessionMetadata;
+    use tempfile::TempDir;
+
+    fn session_with_messages(dir: &TempDir) -> PathBuf {
+        let session_file = dir.path().join("20250101_120000.jsonl");
+        let messages = vec![
+            Message::user().with_text("Refactor the parser"),
+            Message::assistant().with_text("Done"),
+            Message::user().with_text("Now deploy it"),
+            Message::assistant().with_text("Deployed"),
+        ];
+        let mut metadata = SessionMetadata::new(dir.path().to_path_buf());
+        metadata.description = "Parser work".to_string();
+        save_messages_with_metadata(&session_file, &metadata, &messages).unwrap();
+        session_file
+    }
+
+    #[test]
+    fn test_tag_and_read_checkpoints() {
+        let dir = TempDir::new().unwrap();
+        let session_file = session_with_messages(&dir);
+
+        tag_checkpoint(&session_file, "before refactor", 0).unwrap();
+        tag_checkpoint(&session_file, " after refactor ", 2).unwrap();
+        assert!(tag_checkpoint(&session_file, "too far", 5).is_err());
+        assert!(tag_checkpoint(&session_file, "  ", 1).is_err());
+
+        let names: Vec<String> = list_checkpoints(&session_file)
+            .unwrap()
+            .into_iter()
+            .map(|checkpoint| checkpoint.name)
+            .collect();
+        assert_eq!(names, vec!["before refactor", "after refactor"]);
+
+        let messages = messages_at_checkpoint(&session_file, "after refactor").unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].as_concat_text(), "Done");
+
+        assert!(remove_checkpoint(&session_file, "before refactor").unwrap());
+        assert!(!remove_checkpoint(&session_file, "before refactor").unwrap());
+    }
+
+    #[test]
+    fn test_branch_from_checkpoint() {
+        let dir = TempDir::new().unwrap();
+        let session_file = session_with_messages(&dir);
+        tag_checkpoint(&session_file, "after refactor", 2).unwrap();
+        tag_checkpoint(&session_file, "after deploy", 4).unwrap();
+
+        let branch = dir.path().join("20250102_090000.jsonl");
+        branch_from_checkpoint(&session_file, "after refactor", &branch).unwrap();
+
+        assert_eq!(read_messages(&branch).unwrap().len(), 2);
+        let metadata = read_metadata(&branch).unwrap();
+        assert_eq!(metadata.description, "Parser work (from after refactor)");
+        assert_eq!(metadata.message_count, 2);
+        let inherited = list_checkpoints(&branch).unwrap();
+        assert_eq!(inherited.len(), 1);
+        assert_eq!(inherited[0].name, "after refactor");
+
+        assert!(branch_from_checkpoint(&session_file, "after deploy", &branch).is_err());
+    }
+}
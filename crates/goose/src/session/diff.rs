@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::Result;
+use mcp_core::Role;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::message::{Message, MessageContent};
+use crate::session::storage::{read_messages, read_metadata, SessionMetadata};
+
+/// A session's metadata and messages, loaded for comparison
+#[derive(Debug, Clone)]
+pub struct SessionSnapshot {
+    pub id: String,
+    pub metadata: SessionMetadata,
+    pub messages: Vec<Message>,
+}
+
+impl SessionSnapshot {
+    pub fn new(id: impl Into<String>, metadata: SessionMetadata, messages: Vec<Message>) -> Self {
+        Self {
+            id: id.into(),
+            metadata,
+            messages,
+        }
+    }
+
+    /// Load a snapshot from a session file
+    pub fn load(id: impl Into<String>, session_file: &Path) -> Result<Self> {
+        Ok(Self::new(
+            id,
+            read_metadata(session_file)?,
+            read_messages(session_file)?,
+        ))
+    }
+
+    /// Number of calls made to each tool over the session
+    pub fn tool_usage(&self) -> BTreeMap<String, usize> {
+        let mut usage = BTreeMap::new();
+        for message in &self.messages {
+            for content in &message.content {
+                let tool_call = match content {
+                    MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+                    MessageContent::FrontendToolRequest(request) => request.tool_call.as_ref().ok(),
+                    _ => None,
+                };
+                if let Some(tool_call) = tool_call {
+                    *usage.entry(tool_call.name.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+        usage
+    }
+
+    /// How the session ended
+    pub fn outcome(&self) -> SessionOutcome {
+        let tool_errors = self
+            .messages
+            .iter()
+            .flat_map(|m| m.content.iter())
+            .filter(|c| matches!(c, MessageContent::ToolResponse(r) if r.tool_result.is_err()))
+            .count();
+        let final_response = self
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == Role::Assistant && !m.as_concat_text().trim().is_empty())
+            .map(|m| m.as_concat_text());
+
+        SessionOutcome {
+            final_response,
+            tool_errors,
+            ended_with_assistant: self
+                .messages
+                .last()
+                .is_some_and(|m| m.role == Role::Assistant),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SessionOutcome {
+    /// The last non-empty assistant text in the session
+    pub final_response: Option<String>,
+    /// Number of tool calls that returned an error
+    pub tool_errors: usize,
+    /// Whether the conversation ended on an assistant message rather than mid-turn
+    pub ended_with_assistant: bool,
+}
+
+/// A short description of a message for the diff report
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MessageSummary {
+    pub index: usize,
+    pub role: Role,
+    pub text: String,
+    /// Names of the tools requested in the message
+    pub tool_calls: Vec<String>,
+}
+
+impl MessageSummary {
+    fn new(index: usize, message: &Message) -> Self {
+        let tool_calls = message
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                MessageContent::ToolRequest(request) => request.tool_call.as_ref().ok(),
+                _ => None,
+            })
+            .map(|call| call.name.clone())
+            .collect();
+        Self {
+            index,
+            role: message.role.clone(),
+            text: message.as_concat_text(),
+            tool_calls,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct MessageDiff {
+    pub left_count: usize,
+    pub right_count: usize,
+    /// Number of leading messages that are identical in both sessions
+    pub common_prefix: usize,
+    /// Messages after the point where the sessions diverge
+    pub left_only: Vec<MessageSummary>,
+    pub right_only: Vec<MessageSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ToolCountChange {
+    pub tool: String,
+    pub left: usize,
+    pub right: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ToolUsageDiff {
+    /// Tools only used in the right session
+    pub added: Vec<String>,
+    /// Tools only used in the left session
+    pub removed: Vec<String>,
+    /// Tools used in both sessions a different number of times
+    pub changed: Vec<ToolCountChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TokenDiff {
+    pub left: Option<i32>,
+    pub right: Option<i32>,
+    /// `right - left`, when both are known
+    pub delta: Option<i32>,
+}
+
+impl TokenDiff {
+    fn new(left: Option<i32>, right: Option<i32>) -> Self {
+        Self {
+            left,
+            right,
+            delta: left.zip(right).map(|(l, r)| r - l),
+        }
+    }
+}
+
+/// Token usage over the whole session, the basis for comparing cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UsageDiff {
+    pub total_tokens: TokenDiff,
+    pub input_tokens: TokenDiff,
+    pub output_tokens: TokenDiff,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OutcomeDiff {
+    pub left: SessionOutcome,
+    pub right: SessionOutcome,
+    pub same_final_response: bool,
+}
+
+/// Structured comparison of two sessions, e.g. a run before and after a prompt or model change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct SessionDiff {
+    pub left_id: String,
+    pub right_id: String,
+    pub messages: MessageDiff,
+    pub tools: ToolUsageDiff,
+    pub usage: UsageDiff,
+    pub outcome: OutcomeDiff,
+}
+
+impl SessionDiff {
+    /// Whether the two sessions have the same messages
+    pub fn is_identical(&self) -> bool {
+        self.messages.left_only.is_empty() && self.messages.right_only.is_empty()
+    }
+}
+
+fn same_message(left: &Message, right: &Message) -> bool {
+    left.role == right.role && left.content == right.content
+}
+
+/// Compare two sessions
+pub fn diff_sessions(left: &SessionSnapshot, right: &SessionSnapshot) -> SessionDiff {
+    let common_prefix = left
+        .messages
+        .iter()
+        .zip(&right.messages)
+        .take_while(|(l, r)| same_message(l, r))
+        .count();
+    let summarize = |messages: &[Message]| {
+        messages
+            .iter()
+            .enumerate()
+            .skip(common_prefix)
+            .map(|(i, m)| MessageSummary::new(i, m))
+            .collect::<Vec<_>>()
+    };
+
+    let left_tools = left.tool_usage();
+    let right_tools = right.tool_usage();
+    let all_tools: BTreeSet<&String> = left_tools.keys().chain(right_tools.keys()).collect();
+    let mut tools = ToolUsageDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for tool in all_tools {
+        match (left_tools.get(tool), right_tools.get(tool)) {
+            (None, Some(_)) => tools.added.push(tool.clone()),
+            (Some(_), None) => tools.removed.push(tool.clone()),
+            (Some(&l), Some(&r)) if l != r => tools.changed.push(ToolCountChange {
+                tool: tool.clone(),
+                left: l,
+                right: r,
+            }),
+            _ => {}
+        }
+    }
+
+    let (lm, rm) = (&left.metadata, &right.metadata);
+    let usage = UsageDiff {
+        total_tokens: TokenDiff::new(lm.accumulated_total_tokens, rm.accumulated_total_tokens),
+        input_tokens: TokenDiff::new(lm.accumulated_input_tokens, rm.accumulated_input_tokens),
+        output_tokens: TokenDiff::new(lm.accumulated_output_tokens, rm.accumulated_output_tokens),
+    };
+
+    let left_outcome = left.outcome();
+    let right_outcome = right.outcome();
+    let same_final_response = left_outcome.final_response == right_outcome.final_response;
+
+    SessionDiff {
+        left_id: left.id.clone(),
+        right_id: right.id.clone(),
+        messages: MessageDiff {
+            left_count: left.messages.len(),
+            right_count: right.messages.len(),
+            common_prefix,
+            left_only: summarize(&left.messages),
+            right_only: summarize(&right.messages),
+        },
+        tools,
+        usage,
+        outcome: OutcomeDiff {
+            left: left_outcome,
+            right: right_outcome,
+            same_final_response,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    fn snapshot(id: &str, messages: Vec<Message>, total_tokens: Option<i32>) -> SessionSnapshot {
+        let metadata = SessionMetadata {
+            accumulated_total_tokens: total_tokens,
+            ..Default::default()
+        };
+        SessionSnapshot::new(id, metadata, messages)
+    }
+
+    fn tool_request(id: &str, tool: &str) -> Message {
+        Message::assistant().with_tool_request(id, Ok(ToolCall::new(tool, json!({}))))
+    }
+
+    #[test]
+    fn test_diff_divergent_sessions() {
+        let prompt = Message::user().with_text("list the files");
+        let before = snapshot(
+            "before",
+            vec![
+                prompt.clone(),
+                tool_request("1", "developer__shell"),
+                Message::assistant().with_text("There are 3 files"),
+            ],
+            Some(1200),
+        );
+        let after = snapshot(
+            "after",
+            vec![
+                prompt,
+                tool_request("1", "developer__list_dir"),
+                tool_request("2", "developer__list_dir"),
+                Message::assistant().with_text("There are 3 files"),
+            ],
+            Some(900),
+        );
+
+        let diff = diff_sessions(&before, &after);
+        assert!(!diff.is_identical());
+        assert_eq!(diff.messages.common_prefix, 1);
+        assert_eq!(diff.messages.left_only.len(), 2);
+        assert_eq!(diff.messages.right_only.len(), 3);
+        assert_eq!(diff.tools.added, vec!["developer__list_dir"]);
+        assert_eq!(diff.tools.removed, vec!["developer__shell"]);
+        assert_eq!(diff.usage.total_tokens.delta, Some(-300));
+        assert!(diff.outcome.same_final_response);
+    }
+
+    #[test]
+    fn test_diff_identical_sessions() {
+        let messages = vec![
+            Message::user().with_text("hi"),
+            Message::assistant().with_text("hello"),
+        ];
+        let diff = diff_sessions(
+            &snapshot("a", messages.clone(), None),
+            &snapshot("b", messages, Some(10)),
+        );
+        assert!(diff.is_identical());
+        assert_eq!(diff.messages.common_prefix, 2);
+        assert_eq!(diff.usage.total_tokens.delta, None);
+        assert!(diff.outcome.left.ended_with_assistant);
+    }
+}
@@ -0,0 +1,339 @@
+//! Crash-safe, append-only writing of session files
+//!
+//! A session file is a log in JSONL: the session's metadata, then one message per line. Saving
+//! a session appends to the log rather than rewriting it, so a crash while saving can lose at
+//! most the lines being written and never the messages already saved. Messages that extend
+//! those on disk are appended as they are. A change to the metadata appends a metadata event,
+//! and a change to earlier messages, as when the history is rewound or summarized, appends a
+//! truncate event followed by the new messages.
+//!
+//! Reading replays the log. A last line cut short by a crash is dropped, and the next save
+//! rewrites the log. Once replaced lines outnumber the live ones, the log is compacted: the
+//! current metadata and messages are written to a temporary file that then replaces the log
+//! in a single rename.
+//!
+//! Older versions of goose read a session file as its metadata and then only messages, and fail
+//! on an event line. Logs the process appended events to are compacted when it exits, through
+//! [`crate::shutdown`], so a session file holds events only while it is in use or after a crash.
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::storage::SessionMetadata;
+use crate::message::Message;
+
+/// How the lines of events start, telling them apart from messages without parsing them
+const EVENT_PREFIX: &str = "{\"session_event\":";
+/// Replaced lines tolerated in a log before it is compacted
+const COMPACT_MIN_STALE: usize = 32;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "session_event", rename_all = "snake_case")]
+enum SessionEvent {
+    /// Replaces the metadata of the session
+    Metadata { metadata: Value },
+    /// Drops every message after the first `keep`
+    Truncate { keep: usize },
+}
+
+/// A session file as replayed from its log
+#[derive(Debug, Default)]
+pub struct Journal {
+    metadata: Option<Value>,
+    /// The live messages, as the JSON they are stored as
+    lines: Vec<String>,
+    /// Lines of the log that no longer hold live metadata or messages
+    stale: usize,
+    /// Whether the log ends in a complete line, so that more can be appended to it
+    clean_end: bool,
+}
+
+impl Journal {
+    /// Replay the session file at `path`, which is empty if the file does not exist
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self {
+                    clean_end: true,
+                    ..Default::default()
+                })
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut lines: Vec<&[u8]> = bytes.split(|b| *b == b'\n').collect();
+        // What follows the last newline, which is empty unless a write was cut short
+        let tail = lines.pop().unwrap_or_default();
+        let clean_end = tail.is_empty();
+        if serde_json::from_slice::<Value>(tail).is_ok() {
+            // A whole record that only lacks its newline
+            lines.push(tail);
+        } else if !clean_end {
+            tracing::warn!(
+                "Dropping an incomplete last line of {} bytes from session file {}",
+                tail.len(),
+                path.display()
+            );
+        }
+
+        let mut journal = Self {
+            clean_end,
+            ..Default::default()
+        };
+        let mut total = 0;
+        for (i, line) in lines.into_iter().enumerate() {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            total += 1;
+            let line = String::from_utf8(line.to_vec())?;
+            if line.starts_with(EVENT_PREFIX) {
+                match serde_json::from_str(&line)? {
+                    SessionEvent::Metadata { metadata } => journal.metadata = Some(metadata),
+                    SessionEvent::Truncate { keep } => journal.lines.truncate(keep),
+                }
+            } else if i == 0 && serde_json::from_str::<SessionMetadata>(&line).is_ok() {
+                journal.metadata = Some(serde_json::from_str(&line)?);
+            } else {
+                journal.lines.push(line);
+            }
+        }
+        journal.stale = total - journal.lines.len() - journal.metadata.is_some() as usize;
+        Ok(journal)
+    }
+
+    /// The metadata of the session, if the file has any
+    pub fn metadata(&self) -> Option<SessionMetadata> {
+        self.metadata
+            .clone()
+            .and_then(|metadata| serde_json::from_value(metadata).ok())
+    }
+
+    /// The messages of the session, as the JSON they are stored as
+    pub fn message_lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    fn is_empty(&self) -> bool {
+        self.metadata.is_none() && self.lines.is_empty()
+    }
+}
+
+fn event_line(event: &SessionEvent) -> Result<String> {
+    Ok(serde_json::to_string(event)?)
+}
+
+/// Save the metadata and messages of a session, appending only what changed since the file
+/// was last saved
+pub fn save(path: &Path, metadata: &SessionMetadata, messages: &[Message]) -> Result<()> {
+    let journal = Journal::read(path)?;
+    let metadata = serde_json::to_value(metadata)?;
+    let lines = messages
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    if journal.is_empty() || !journal.clean_end {
+        return write_compacted(path, &metadata, &lines);
+    }
+
+    let kept = journal
+        .lines
+        .iter()
+        .zip(&lines)
+        .take_while(|(saved, line)| saved == line)
+        .count();
+    let mut appended = Vec::new();
+    let mut stale = journal.stale;
+    if journal.metadata.as_ref() != Some(&metadata) {
+        stale += journal.metadata.is_some() as usize;
+        appended.push(event_line(&SessionEvent::Metadata {
+            metadata: metadata.clone(),
+        })?);
+    }
+    if kept < journal.lines.len() {
+        stale += journal.lines.len() - kept + 1;
+        appended.push(event_line(&SessionEvent::Truncate { keep: kept })?);
+    }
+    appended.extend_from_slice(&lines[kept..]);
+
+    if stale >= COMPACT_MIN_STALE && stale > lines.len() {
+        write_compacted(path, &metadata, &lines)
+    } else {
+        append(path, &appended)
+    }
+}
+
+/// Save new metadata for a session, keeping its messages
+pub fn save_metadata(path: &Path, metadata: &SessionMetadata) -> Result<()> {
+    let journal = Journal::read(path)?;
+    let metadata = serde_json::to_value(metadata)?;
+    if journal.is_empty() || !journal.clean_end {
+        return write_compacted(path, &metadata, &journal.lines);
+    }
+    if journal.metadata.as_ref() == Some(&metadata) {
+        return Ok(());
+    }
+    append(path, &[event_line(&SessionEvent::Metadata { metadata })?])
+}
+
+/// Compact the log if it holds any events, leaving it in the form older readers understand
+pub fn close(path: &Path) -> Result<()> {
+    let journal = Journal::read(path)?;
+    if journal.stale == 0 && journal.clean_end {
+        return Ok(());
+    }
+    compact(path)
+}
+
+/// Rewrite the log with only its live metadata and messages
+pub fn compact(path: &Path) -> Result<()> {
+    let journal = Journal::read(path)?;
+    let metadata = match journal.metadata {
+        Some(metadata) => metadata,
+        None => serde_json::to_value(SessionMetadata::default())?,
+    };
+    write_compacted(path, &metadata, &journal.lines)
+}
+
+/// Append lines to the log, syncing them to disk before returning
+fn append(path: &Path, lines: &[String]) -> Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    let mut buffer = String::new();
+    for line in lines {
+        buffer.push_str(line);
+        buffer.push('\n');
+    }
+    let mut file = OpenOptions::new().append(true).create(true).open(path)?;
+    // One write, so that a crash leaves at most its last line incomplete
+    file.write_all(buffer.as_bytes())?;
+    file.sync_data()?;
+    crate::shutdown::close_on_exit(path);
+    Ok(())
+}
+
+/// Replace the log with the metadata and messages, written to a temporary file first so that
+/// a crash while writing leaves the previous log intact
+fn write_compacted(path: &Path, metadata: &Value, lines: &[String]) -> Result<()> {
+    let partial = path.with_extension("jsonl.partial");
+    let mut file = io::BufWriter::new(fs::File::create(&partial)?);
+    serde_json::to_writer(&mut file, metadata)?;
+    writeln!(file)?;
+    for line in lines {
+        writeln!(file, "{}", line)?;
+    }
+    file.into_inner()?.sync_all()?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn line_count(path: &Path) -> usize {
+        fs::read_to_string(path).unwrap().lines().count()
+    }
+
+    fn texts(path: &Path) -> Vec<String> {
+        Journal::read(path)
+            .unwrap()
+            .message_lines()
+            .iter()
+            .map(|line| {
+                serde_json::from_str::<Message>(line)
+                    .unwrap()
+                    .as_concat_text()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_saves_append_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let mut metadata = SessionMetadata::new(dir.path().to_path_buf());
+        let mut messages = vec![
+            Message::user().with_text("one"),
+            Message::assistant().with_text("two"),
+        ];
+        save(&path, &metadata, &messages).unwrap();
+        assert_eq!(line_count(&path), 3);
+
+        messages.push(Message::user().with_text("three"));
+        save(&path, &metadata, &messages).unwrap();
+        assert_eq!(line_count(&path), 4);
+
+        metadata.description = "Counting".to_string();
+        save(&path, &metadata, &messages).unwrap();
+        assert_eq!(line_count(&path), 5);
+        let journal = Journal::read(&path).unwrap();
+        assert_eq!(journal.metadata().unwrap().description, "Counting");
+
+        // Rewinding truncates and appends the new messages
+        messages.truncate(1);
+        messages.push(Message::assistant().with_text("deux"));
+        save(&path, &metadata, &messages).unwrap();
+        assert_eq!(line_count(&path), 7);
+        assert_eq!(texts(&path), vec!["one", "deux"]);
+
+        close(&path).unwrap();
+        assert_eq!(line_count(&path), 3);
+        assert_eq!(texts(&path), vec!["one", "deux"]);
+        // Every line is the metadata or a message again
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains(EVENT_PREFIX));
+        let journal = Journal::read(&path).unwrap();
+        assert_eq!(journal.stale, 0);
+        assert_eq!(journal.metadata().unwrap().description, "Counting");
+    }
+
+    #[test]
+    fn test_recovers_from_an_incomplete_last_line() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let metadata = SessionMetadata::new(dir.path().to_path_buf());
+        let mut messages = vec![Message::user().with_text("one")];
+        save(&path, &metadata, &messages).unwrap();
+
+        // A crash in the middle of appending a message
+        let partial = serde_json::to_string(&Message::assistant().with_text("two")).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&partial.as_bytes()[..partial.len() / 2])
+            .unwrap();
+
+        assert_eq!(texts(&path), vec!["one"]);
+
+        messages.push(Message::assistant().with_text("two again"));
+        save(&path, &metadata, &messages).unwrap();
+        assert_eq!(texts(&path), vec!["one", "two again"]);
+        assert_eq!(line_count(&path), 3);
+    }
+
+    #[test]
+    fn test_compacts_once_mostly_stale() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let metadata = SessionMetadata::new(dir.path().to_path_buf());
+        let messages = vec![Message::user().with_text("one")];
+        save(&path, &metadata, &messages).unwrap();
+
+        for i in 0..COMPACT_MIN_STALE {
+            let edited = vec![Message::user().with_text(format!("one, edit {}", i))];
+            save(&path, &metadata, &edited).unwrap();
+        }
+        // Each edit adds a truncate event and a message, until the log is compacted
+        assert!(line_count(&path) < 2 * COMPACT_MIN_STALE);
+        assert_eq!(
+            texts(&path),
+            vec![format!("one, edit {}", COMPACT_MIN_STALE - 1)]
+        );
+    }
+}
@@ -1,4 +1,12 @@
+pub mod alternatives;
+pub mod anchors;
+pub mod changes;
+pub mod checkpoints;
+pub mod diff;
 pub mod info;
+pub mod journal;
+pub mod search;
+pub mod state;
 pub mod storage;
 
 // Re-export common session types and functions
@@ -8,4 +16,11 @@ pub use storage::{
     Identifier, SessionMetadata,
 };
 
+pub use alternatives::Alternative;
+pub use anchors::Anchor;
+pub use changes::ChangeSummary;
+pub use checkpoints::Checkpoint;
+pub use diff::{diff_sessions, SessionDiff, SessionSnapshot};
 pub use info::{get_session_info, SessionInfo};
+pub use search::{search_sessions, SessionSearchResult};
+pub use state::SessionState;
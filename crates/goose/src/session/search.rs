@@ -0,0 +1,493 @@
+//! Search across persisted sessions
+//!
+//! Sessions are indexed into `search_index.json` in the session directory, with their
+//! description and the text of their messages. Only sessions that changed since the last
+//! search are read again. Keyword search ranks messages with BM25, and semantic search
+//! compares an embedding of the query with an embedding of each session, computed the first
+//! time a session is searched that way and kept in the index until the session changes.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{anyhow, Result};
+use mcp_core::Role;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::providers::base::Provider;
+use crate::providers::embedding::cosine_similarity;
+use crate::session::storage::{ensure_session_dir, list_sessions, read_messages, read_metadata};
+
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+/// Weight of a match in the session description over one in a message
+const DESCRIPTION_BOOST: f64 = 2.0;
+/// Matching messages returned per session
+const MAX_MATCHES: usize = 3;
+const SNIPPET_CHARS: usize = 160;
+/// Characters of a session embedded for semantic search
+const MAX_DOCUMENT_CHARS: usize = 8_000;
+const EMBEDDING_BATCH_SIZE: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    #[default]
+    Keyword,
+    Semantic,
+}
+
+/// Lowercased words of a text, ignoring single characters
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexedMessage {
+    index: usize,
+    role: Role,
+    text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IndexedSession {
+    /// Modification time of the session file in seconds, to tell when it changed
+    modified: u64,
+    description: String,
+    working_dir: String,
+    messages: Vec<IndexedMessage>,
+    #[serde(default)]
+    embedding: Option<Vec<f32>>,
+}
+
+impl IndexedSession {
+    fn read(session_file: &Path, modified: u64) -> Result<Self> {
+        let metadata = read_metadata(session_file)?;
+        let messages = read_messages(session_file)?
+            .iter()
+            .enumerate()
+            .filter_map(|(index, message)| {
+                let text = message.as_concat_text();
+                if text.trim().is_empty() {
+                    return None;
+                }
+                Some(IndexedMessage {
+                    index,
+                    role: message.role.clone(),
+                    text,
+                })
+            })
+            .collect();
+        Ok(Self {
+            modified,
+            description: metadata.description,
+            working_dir: metadata.working_dir.to_string_lossy().to_string(),
+            messages,
+            embedding: None,
+        })
+    }
+
+    /// The text embedded for semantic search: the description and what the user asked
+    fn document(&self) -> String {
+        let mut document = self.description.clone();
+        for message in self.messages.iter().filter(|m| m.role == Role::User) {
+            document.push('\n');
+            document.push_str(&message.text);
+        }
+        document.chars().take(MAX_DOCUMENT_CHARS).collect()
+    }
+}
+
+/// A message of a session that matched the query
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageMatch {
+    pub message_index: usize,
+    pub role: Role,
+    /// The text around the first matching word
+    pub snippet: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchResult {
+    pub session_id: String,
+    pub description: String,
+    pub working_dir: String,
+    pub score: f64,
+    /// The best matching messages, most relevant first
+    pub matches: Vec<MessageMatch>,
+}
+
+fn snap_to_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// The text around the first occurrence of one of the terms
+fn snippet(text: &str, terms: &HashSet<String>) -> String {
+    let lower = text.to_lowercase();
+    // Offsets in the lowercased text only line up when lowercasing kept the length
+    let position = (lower.len() == text.len())
+        .then(|| {
+            terms
+                .iter()
+                .filter_map(|term| lower.find(term.as_str()))
+                .min()
+        })
+        .flatten()
+        .unwrap_or(0);
+    let start = snap_to_char_boundary(text, position.saturating_sub(SNIPPET_CHARS / 4));
+    let truncated = text[start..].chars().count() > SNIPPET_CHARS;
+    let snippet: String = text[start..].chars().take(SNIPPET_CHARS).collect();
+    let snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    match (start > 0, truncated) {
+        (true, true) => format!("…{}…", snippet),
+        (true, false) => format!("…{}", snippet),
+        (false, true) => format!("{}…", snippet),
+        (false, false) => snippet,
+    }
+}
+
+/// BM25 scores of documents for the query terms
+fn bm25(documents: &[Vec<String>], terms: &HashSet<String>) -> Vec<f64> {
+    let count = documents.len() as f64;
+    let average_length = documents.iter().map(Vec::len).sum::<usize>() as f64 / count.max(1.0);
+    let frequencies: HashMap<&str, f64> = terms
+        .iter()
+        .map(|term| {
+            let frequency = documents
+                .iter()
+                .filter(|document| document.contains(term))
+                .count();
+            (term.as_str(), frequency as f64)
+        })
+        .collect();
+
+    documents
+        .iter()
+        .map(|document| {
+            let length = document.len() as f64;
+            terms
+                .iter()
+                .map(|term| {
+                    let occurrences = document.iter().filter(|word| *word == term).count() as f64;
+                    if occurrences == 0.0 {
+                        return 0.0;
+                    }
+                    let frequency = frequencies[term.as_str()];
+                    let idf = ((count - frequency + 0.5) / (frequency + 0.5) + 1.0).ln();
+                    idf * occurrences * (BM25_K1 + 1.0)
+                        / (occurrences
+                            + BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length.max(1.0)))
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Keyword score of each session with its best matching messages
+type KeywordMatches<'a> = HashMap<&'a str, (f64, Vec<(f64, &'a IndexedMessage)>)>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SessionSearchIndex {
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    sessions: HashMap<String, IndexedSession>,
+}
+
+impl SessionSearchIndex {
+    /// Open the index at a path, starting an empty one if it does not exist or is unreadable
+    pub fn open(path: PathBuf) -> Self {
+        let mut index: Self = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        index.path = Some(path);
+        index
+    }
+
+    pub fn open_default() -> Result<Self> {
+        Ok(Self::open(ensure_session_dir()?.join("search_index.json")))
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Bring the index up to date with these sessions, returning how many were read again
+    pub fn sync(&mut self, sessions: Vec<(String, PathBuf)>) -> usize {
+        let ids: HashSet<&str> = sessions.iter().map(|(id, _)| id.as_str()).collect();
+        self.sessions.retain(|id, _| ids.contains(id.as_str()));
+
+        let mut updated = 0;
+        for (id, path) in &sessions {
+            let modified = path
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            if self
+                .sessions
+                .get(id)
+                .is_some_and(|session| session.modified == modified)
+            {
+                continue;
+            }
+            match IndexedSession::read(path, modified) {
+                Ok(session) => {
+                    self.sessions.insert(id.clone(), session);
+                    updated += 1;
+                }
+                Err(e) => tracing::warn!("Failed to index session {}: {}", id, e),
+            }
+        }
+        updated
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            fs::write(path, serde_json::to_string(self)?)?;
+        }
+        Ok(())
+    }
+
+    /// Keyword scores of each session and its matching messages, best first
+    fn keyword_matches(&self, terms: &HashSet<String>) -> KeywordMatches<'_> {
+        let mut owners = Vec::new();
+        let mut documents = Vec::new();
+        for (id, session) in &self.sessions {
+            owners.push((id.as_str(), None));
+            documents.push(tokenize(&session.description));
+            for message in &session.messages {
+                owners.push((id.as_str(), Some(message)));
+                documents.push(tokenize(&message.text));
+            }
+        }
+
+        let mut sessions: KeywordMatches<'_> = HashMap::new();
+        for ((id, message), score) in owners.into_iter().zip(bm25(&documents, terms)) {
+            if score <= 0.0 {
+                continue;
+            }
+            let entry = sessions.entry(id).or_default();
+            match message {
+                Some(message) => {
+                    entry.0 = entry.0.max(score);
+                    entry.1.push((score, message));
+                }
+                None => entry.0 = entry.0.max(score * DESCRIPTION_BOOST),
+            }
+        }
+        for (_, matches) in sessions.values_mut() {
+            matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+            matches.truncate(MAX_MATCHES);
+        }
+        sessions
+    }
+
+    fn result(
+        &self,
+        id: &str,
+        score: f64,
+        matches: &[(f64, &IndexedMessage)],
+        terms: &HashSet<String>,
+    ) -> SessionSearchResult {
+        let session = &self.sessions[id];
+        SessionSearchResult {
+            session_id: id.to_string(),
+            description: session.description.clone(),
+            working_dir: session.working_dir.clone(),
+            score,
+            matches: matches
+                .iter()
+                .map(|(_, message)| MessageMatch {
+                    message_index: message.index,
+                    role: message.role.clone(),
+                    snippet: snippet(&message.text, terms),
+                })
+                .collect(),
+        }
+    }
+
+    /// Sessions whose description or messages contain the words of the query, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SessionSearchResult> {
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut results: Vec<SessionSearchResult> = self
+            .keyword_matches(&terms)
+            .into_iter()
+            .map(|(id, (score, matches))| self.result(id, score, &matches, &terms))
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(limit);
+        results
+    }
+
+    /// Sessions about the same thing as the query, best first, embedding the sessions that
+    /// have not been embedded yet
+    pub async fn search_semantic(
+        &mut self,
+        embedding_provider: &dyn Provider,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<SessionSearchResult>> {
+        let missing: Vec<String> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.embedding.is_none())
+            .map(|(id, _)| id.clone())
+            .collect();
+        for batch in missing.chunks(EMBEDDING_BATCH_SIZE) {
+            let documents = batch
+                .iter()
+                .map(|id| self.sessions[id].document())
+                .collect();
+            let embeddings = embedding_provider.create_embeddings(documents).await?;
+            for (id, embedding) in batch.iter().zip(embeddings) {
+                if let Some(session) = self.sessions.get_mut(id) {
+                    session.embedding = Some(embedding);
+                }
+            }
+        }
+
+        let query_embedding = embedding_provider
+            .create_embeddings(vec![query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("No embedding returned for the query"))?;
+
+        let terms: HashSet<String> = tokenize(query).into_iter().collect();
+        let keyword_matches = self.keyword_matches(&terms);
+        let mut results: Vec<SessionSearchResult> = self
+            .sessions
+            .iter()
+            .filter_map(|(id, session)| {
+                let score = cosine_similarity(&query_embedding, session.embedding.as_ref()?);
+                let matches = keyword_matches
+                    .get(id.as_str())
+                    .map(|(_, matches)| matches.as_slice())
+                    .unwrap_or_default();
+                Some(self.result(id, score as f64, matches, &terms))
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+/// Open the default index and bring it up to date with the sessions on disk
+pub fn open_synced_index() -> Result<SessionSearchIndex> {
+    let mut index = SessionSearchIndex::open_default()?;
+    if index.sync(list_sessions()?) > 0 {
+        index.save()?;
+    }
+    Ok(index)
+}
+
+/// Keyword search over every persisted session
+pub fn search_sessions(query: &str, limit: usize) -> Result<Vec<SessionSearchResult>> {
+    Ok(open_synced_index()?.search(query, limit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::Message;
+    use crate::session::storage::{save_messages_with_metadata, SessionMetadata};
+    use tempfile::TempDir;
+
+    fn write_session(dir: &TempDir, id: &str, description: &str, texts: &[&str]) -> PathBuf {
+        let path = dir.path().join(format!("{}.jsonl", id));
+        let mut metadata = SessionMetadata::new(dir.path().to_path_buf());
+        metadata.description = description.to_string();
+        let messages: Vec<Message> = texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                if i % 2 == 0 {
+                    Message::user().with_text(*text)
+                } else {
+                    Message::assistant().with_text(*text)
+                }
+            })
+            .collect();
+        save_messages_with_metadata(&path, &metadata, &messages).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_keyword_search() {
+        let dir = TempDir::new().unwrap();
+        let sessions = vec![
+            (
+                "20250101_100000".to_string(),
+                write_session(
+                    &dir,
+                    "20250101_100000",
+                    "Fix auth bug",
+                    &[
+                        "Login fails with an expired token error",
+                        "The auth middleware compared the token expiry in the wrong timezone",
+                    ],
+                ),
+            ),
+            (
+                "20250102_100000".to_string(),
+                write_session(
+                    &dir,
+                    "20250102_100000",
+                    "Release notes",
+                    &["Write the release notes for 1.2", "Done"],
+                ),
+            ),
+        ];
+
+        let mut index = SessionSearchIndex::open(dir.path().join("search_index.json"));
+        assert_eq!(index.sync(sessions.clone()), 2);
+        index.save().unwrap();
+
+        let mut index = SessionSearchIndex::open(dir.path().join("search_index.json"));
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.sync(sessions.clone()), 0);
+
+        let results = index.search("auth token", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].session_id, "20250101_100000");
+        assert_eq!(results[0].matches.len(), 2);
+        assert!(results[0].matches[0]
+            .snippet
+            .to_lowercase()
+            .contains("token"));
+        assert!(index.search("kubernetes", 10).is_empty());
+
+        // Removed sessions leave the index
+        assert_eq!(index.sync(sessions[1..].to_vec()), 0);
+        assert!(index.search("auth", 10).is_empty());
+    }
+
+    #[test]
+    fn test_snippet() {
+        let terms: HashSet<String> = ["needle".to_string()].into();
+        let text = format!("{} needle {}", "hay ".repeat(100), "hay ".repeat(100));
+        let around = snippet(&text, &terms);
+        assert!(around.starts_with('…') && around.ends_with('…'));
+        assert!(around.contains("needle"));
+        assert_eq!(snippet("short text", &terms), "short text");
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::agents::extension::ExtensionConfig;
+use crate::tool_monitor::ToolMonitorState;
+
+/// The agent's state in a session besides its messages, so a restarted process can continue
+/// the session with the same extensions, instructions and tool limits
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionState {
+    /// Unix timestamp of when the state was saved
+    pub saved: i64,
+    /// Number of messages in the session when the state was saved
+    pub message_count: usize,
+    #[serde(default)]
+    pub extensions: Vec<ExtensionConfig>,
+    #[serde(default)]
+    pub system_prompt_extras: Vec<String>,
+    #[serde(default)]
+    pub tool_monitor: Option<ToolMonitorState>,
+}
+
+impl SessionState {
+    pub fn new(message_count: usize) -> Self {
+        Self {
+            saved: Utc::now().timestamp(),
+            message_count,
+            ..Default::default()
+        }
+    }
+}
+
+/// The state is stored in a file alongside the session, e.g. `20250101_120000.state.json`
+pub fn state_path(session_file: &Path) -> PathBuf {
+    session_file.with_extension("state.json")
+}
+
+/// The last saved state of a session, if any was saved
+pub fn read_state(session_file: &Path) -> Result<Option<SessionState>> {
+    let path = state_path(session_file);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+/// Save the state, replacing the previous one. It is written to a temporary file first so a
+/// crash while saving leaves the previous state intact.
+pub fn save_state(session_file: &Path, state: &SessionState) -> Result<()> {
+    let path = state_path(session_file);
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, serde_json::to_string_pretty(state)?)?;
+    fs::rename(partial, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_read_state() {
+        let dir = TempDir::new().unwrap();
+        let session_file = dir.path().join("20250101_120000.jsonl");
+        assert!(read_state(&session_file).unwrap().is_none());
+
+        let mut state = SessionState::new(4);
+        state.system_prompt_extras = vec!["Answer in French".to_string()];
+        save_state(&session_file, &state).unwrap();
+
+        let read = read_state(&session_file).unwrap().unwrap();
+        assert_eq!(read.message_count, 4);
+        assert_eq!(read.system_prompt_extras, state.system_prompt_extras);
+        assert!(read.tool_monitor.is_none());
+        assert!(!state_path(&session_file)
+            .with_extension("json.partial")
+            .exists());
+    }
+}
@@ -1,11 +1,12 @@
+use super::journal::{self, Journal};
 use crate::message::Message;
+use crate::providers::attribution::AttributionTags;
 use crate::providers::base::Provider;
 use anyhow::Result;
 use chrono::Local;
 use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{self, BufRead, Write};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -41,6 +42,23 @@ pub struct SessionMetadata {
     pub accumulated_input_tokens: Option<i32>,
     /// The number of output tokens used in the session. Accumulated across all messages.
     pub accumulated_output_tokens: Option<i32>,
+    /// Attribution tags the usage of the session is charged to
+    #[serde(default, skip_serializing_if = "AttributionTags::is_empty")]
+    pub tags: AttributionTags,
+    /// The last reply was cancelled without a message telling the model where it stopped, so
+    /// the next prompt tells it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub reply_cancelled: bool,
+    /// The cost of the session in USD, accumulated across its responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accumulated_cost: Option<f64>,
+    /// Responses whose model has no known costs, charged at the highest known costs
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unpriced_responses: u32,
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
 }
 
 // Custom deserializer to handle old sessions without working_dir
@@ -61,6 +79,14 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: Option<i32>,
             accumulated_output_tokens: Option<i32>,
             working_dir: Option<PathBuf>,
+            #[serde(default)]
+            tags: AttributionTags,
+            #[serde(default)]
+            reply_cancelled: bool,
+            #[serde(default)]
+            accumulated_cost: Option<f64>,
+            #[serde(default)]
+            unpriced_responses: u32,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -82,6 +108,10 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: helper.accumulated_input_tokens,
             accumulated_output_tokens: helper.accumulated_output_tokens,
             working_dir,
+            tags: helper.tags,
+            reply_cancelled: helper.reply_cancelled,
+            accumulated_cost: helper.accumulated_cost,
+            unpriced_responses: helper.unpriced_responses,
         })
     }
 }
@@ -106,6 +136,10 @@ impl SessionMetadata {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            tags: AttributionTags::new(),
+            reply_cancelled: false,
+            accumulated_cost: None,
+            unpriced_responses: 0,
         }
     }
 }
@@ -225,38 +259,18 @@ pub fn read_messages_with_truncation(
     session_file: &Path,
     max_content_size: Option<usize>,
 ) -> Result<Vec<Message>> {
-    let file = fs::OpenOptions::new()
+    fs::OpenOptions::new()
         .read(true)
         .write(true)
         .create(true)
         .truncate(false)
         .open(session_file)?;
 
-    let reader = io::BufReader::new(file);
-    let mut lines = reader.lines();
-    let mut messages = Vec::new();
-
-    // Read the first line as metadata or create default if empty/missing
-    if let Some(line) = lines.next() {
-        let line = line?;
-        // Try to parse as metadata, but if it fails, treat it as a message
-        if let Ok(_metadata) = serde_json::from_str::<SessionMetadata>(&line) {
-            // Metadata successfully parsed, continue with the rest of the lines as messages
-        } else {
-            // This is not metadata, it's a message
-            let message = parse_message_with_truncation(&line, max_content_size)?;
-            messages.push(message);
-        }
-    }
-
-    // Read the rest of the lines as messages
-    for line in lines {
-        let line = line?;
-        let message = parse_message_with_truncation(&line, max_content_size)?;
-        messages.push(message);
-    }
-
-    Ok(messages)
+    Journal::read(session_file)?
+        .message_lines()
+        .iter()
+        .map(|line| parse_message_with_truncation(line, max_content_size))
+        .collect()
 }
 
 /// Parse a message from JSON string with optional content truncation
@@ -405,33 +419,12 @@ fn truncate_json_string(json_str: &str, max_content_size: usize) -> String {
 ///
 /// Returns default empty metadata if the file doesn't exist or has no metadata.
 pub fn read_metadata(session_file: &Path) -> Result<SessionMetadata> {
-    if !session_file.exists() {
-        return Ok(SessionMetadata::default());
-    }
-
-    let file = fs::File::open(session_file)?;
-    let mut reader = io::BufReader::new(file);
-    let mut first_line = String::new();
-
-    // Read just the first line
-    if reader.read_line(&mut first_line)? > 0 {
-        // Try to parse as metadata
-        match serde_json::from_str::<SessionMetadata>(&first_line) {
-            Ok(metadata) => Ok(metadata),
-            Err(_) => {
-                // If the first line isn't metadata, return default
-                Ok(SessionMetadata::default())
-            }
-        }
-    } else {
-        // Empty file, return default
-        Ok(SessionMetadata::default())
-    }
+    Ok(Journal::read(session_file)?.metadata().unwrap_or_default())
 }
 
 /// Write messages to a session file with metadata
 ///
-/// Overwrites the file with metadata as the first line, followed by all messages in JSONL format.
+/// Saves the metadata and messages to the session file's log.
 /// If a provider is supplied, it will automatically generate a description when appropriate.
 pub async fn persist_messages(
     session_file: &Path,
@@ -461,27 +454,15 @@ pub async fn persist_messages(
 
 /// Write messages to a session file with the provided metadata
 ///
-/// Overwrites the file with metadata as the first line, followed by all messages in JSONL format.
+/// Appends what changed since the file was last saved to its log; see [`journal`].
+///
+/// [`journal`]: super::journal
 pub fn save_messages_with_metadata(
     session_file: &Path,
     metadata: &SessionMetadata,
     messages: &[Message],
 ) -> Result<()> {
-    let file = File::create(session_file).expect("The path specified does not exist");
-    let mut writer = io::BufWriter::new(file);
-
-    // Write metadata as the first line
-    serde_json::to_writer(&mut writer, &metadata)?;
-    writeln!(writer)?;
-
-    // Write all messages
-    for message in messages {
-        serde_json::to_writer(&mut writer, &message)?;
-        writeln!(writer)?;
-    }
-
-    writer.flush()?;
-    Ok(())
+    journal::save(session_file, metadata, messages)
 }
 
 /// Generate a description for the session using the provider
@@ -536,11 +517,7 @@ pub async fn generate_description(
 
 /// Update only the metadata in a session file, preserving all messages
 pub async fn update_metadata(session_file: &Path, metadata: &SessionMetadata) -> Result<()> {
-    // Read all messages from the file
-    let messages = read_messages(session_file)?;
-
-    // Rewrite the file with the new metadata and existing messages
-    save_messages_with_metadata(session_file, metadata, &messages)
+    journal::save_metadata(session_file, metadata)
 }
 
 #[cfg(test)]
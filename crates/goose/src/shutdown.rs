@@ -0,0 +1,229 @@
+//! Cleanup when the process is interrupted or terminated
+//!
+//! Extensions run as child processes in their own process groups, which are stopped when the
+//! agent is dropped. On SIGINT or SIGTERM the process usually exits before that happens, and
+//! the extensions keep running along with the temporary files large tool results were
+//! written to.
+//!
+//! A [`ShutdownGuard`] listens for those signals. On the first one it marks shutdown as
+//! started, so running replies stop before their next turn, terminates every extension
+//! process, removes the registered temporary files, compacts the session files the process
+//! appended to and exits with the conventional status. Dropping the guard when the process
+//! exits normally compacts the session files too.
+//! Installing a guard is opt-in so that applications embedding goose keep control of their
+//! signals; they can call [`cleanup`] from their own handlers instead.
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+/// How long extension processes get to exit before they are killed
+pub const DEFAULT_GRACE: Duration = Duration::from_secs(2);
+
+static SHUTDOWN: LazyLock<watch::Sender<bool>> = LazyLock::new(|| watch::Sender::new(false));
+static ARTIFACTS: LazyLock<Mutex<Vec<PathBuf>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+static SESSION_FILES: LazyLock<Mutex<HashSet<PathBuf>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Whether shutdown has started
+pub fn is_shutting_down() -> bool {
+    *SHUTDOWN.borrow()
+}
+
+/// Resolves once shutdown has started, for work that should stop early
+pub async fn shutdown_started() {
+    let mut started = SHUTDOWN.subscribe();
+    let _ = started.wait_for(|started| *started).await;
+}
+
+/// Remove a temporary file or directory if the process is shut down by a signal
+pub fn remove_on_shutdown(path: impl Into<PathBuf>) {
+    ARTIFACTS.lock().unwrap().push(path.into());
+}
+
+fn remove_artifacts() {
+    let artifacts: Vec<PathBuf> = ARTIFACTS.lock().unwrap().drain(..).collect();
+    for path in artifacts {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match removed {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to remove {}: {}", path.display(), e),
+        }
+    }
+}
+
+/// Compact a session file when the process exits, see [`crate::session::journal`]
+pub fn close_on_exit(path: &Path) {
+    let mut files = SESSION_FILES.lock().unwrap();
+    if !files.contains(path) {
+        files.insert(path.to_path_buf());
+    }
+}
+
+/// Compact the session files the process appended to
+pub fn close_session_files() {
+    let files: Vec<PathBuf> = SESSION_FILES.lock().unwrap().drain().collect();
+    for path in files {
+        if let Err(e) = crate::session::journal::close(&path) {
+            tracing::warn!("Failed to compact session file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Start shutting down: stop running work, terminate the extension processes and remove
+/// the temporary files. This blocks for up to `grace` while the processes exit.
+pub fn cleanup(grace: Duration) {
+    SHUTDOWN.send_replace(true);
+    mcp_client::transport::children::terminate_all(grace);
+    remove_artifacts();
+    close_session_files();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownSignal {
+    /// SIGINT, or Ctrl+C on Windows
+    Interrupt,
+    /// SIGTERM, or the console being closed on Windows
+    Terminate,
+    /// SIGHUP, when the terminal goes away
+    Hangup,
+}
+
+impl ShutdownSignal {
+    pub const ALL: [ShutdownSignal; 3] = [
+        ShutdownSignal::Interrupt,
+        ShutdownSignal::Terminate,
+        ShutdownSignal::Hangup,
+    ];
+
+    /// The exit status of a process killed by the signal
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ShutdownSignal::Interrupt => 130,
+            ShutdownSignal::Terminate => 143,
+            ShutdownSignal::Hangup => 129,
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal(signals: &[ShutdownSignal]) -> ShutdownSignal {
+    use tokio::signal::unix::{signal, Signal, SignalKind};
+
+    // Only the requested signals get a handler, the others keep their default action
+    let listen = |wanted: ShutdownSignal, kind: SignalKind| -> Option<Signal> {
+        if !signals.contains(&wanted) {
+            return None;
+        }
+        signal(kind)
+            .map_err(|e| tracing::warn!("Failed to handle {:?}: {}", wanted, e))
+            .ok()
+    };
+    async fn recv(signal: &mut Option<Signal>) {
+        match signal {
+            Some(signal) => {
+                signal.recv().await;
+            }
+            None => std::future::pending().await,
+        }
+    }
+
+    let mut interrupt = listen(ShutdownSignal::Interrupt, SignalKind::interrupt());
+    let mut terminate = listen(ShutdownSignal::Terminate, SignalKind::terminate());
+    let mut hangup = listen(ShutdownSignal::Hangup, SignalKind::hangup());
+    tokio::select! {
+        _ = recv(&mut interrupt) => ShutdownSignal::Interrupt,
+        _ = recv(&mut terminate) => ShutdownSignal::Terminate,
+        _ = recv(&mut hangup) => ShutdownSignal::Hangup,
+    }
+}
+
+#[cfg(windows)]
+async fn wait_for_signal(signals: &[ShutdownSignal]) -> ShutdownSignal {
+    let interrupt = async {
+        if signals.contains(&ShutdownSignal::Interrupt) {
+            let _ = tokio::signal::ctrl_c().await;
+        } else {
+            std::future::pending::<()>().await;
+        }
+    };
+    let close = async {
+        let wanted = signals.contains(&ShutdownSignal::Terminate)
+            || signals.contains(&ShutdownSignal::Hangup);
+        match tokio::signal::windows::ctrl_close() {
+            Ok(mut close) if wanted => {
+                close.recv().await;
+            }
+            _ => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        _ = interrupt => ShutdownSignal::Interrupt,
+        _ = close => ShutdownSignal::Terminate,
+    }
+}
+
+/// Cleans up and exits when the process receives a shutdown signal, until it is dropped
+pub struct ShutdownGuard {
+    listener: JoinHandle<()>,
+}
+
+impl ShutdownGuard {
+    /// Handle every shutdown signal
+    pub fn install() -> Self {
+        Self::install_for(&ShutdownSignal::ALL)
+    }
+
+    /// Handle only these signals, e.g. leaving Ctrl+C to an interactive session that uses it
+    /// to interrupt a reply
+    pub fn install_for(signals: &[ShutdownSignal]) -> Self {
+        let signals = signals.to_vec();
+        let listener = tokio::spawn(async move {
+            let signal = wait_for_signal(&signals).await;
+            tracing::info!("Received {:?}, shutting down", signal);
+            let _ = tokio::task::spawn_blocking(|| cleanup(DEFAULT_GRACE)).await;
+            std::process::exit(signal.exit_code());
+        });
+        Self { listener }
+    }
+}
+
+impl Drop for ShutdownGuard {
+    fn drop(&mut self) {
+        self.listener.abort();
+        close_session_files();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_registered_artifacts_are_removed() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("mcp_response.txt");
+        let nested = dir.path().join("responses");
+        fs::write(&file, "large output").unwrap();
+        fs::create_dir_all(nested.join("inner")).unwrap();
+
+        remove_on_shutdown(&file);
+        remove_on_shutdown(&nested);
+        remove_on_shutdown(dir.path().join("already_removed.txt"));
+        remove_artifacts();
+
+        assert!(!file.exists());
+        assert!(!nested.exists());
+        assert!(ARTIFACTS.lock().unwrap().is_empty());
+    }
+}
@@ -1,29 +1,112 @@
 use include_dir::{include_dir, Dir};
 use mcp_core::Tool;
+use once_cell::sync::Lazy;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
+use std::sync::{Arc, RwLock};
 use tokenizers::tokenizer::Tokenizer;
 
 use crate::message::Message;
+use crate::model::ModelConfig;
 
 // The embedded directory with all possible tokenizer files.
 // If one of them doesn’t exist, we’ll download it at startup.
 static TOKENIZER_FILES: Dir = include_dir!("$CARGO_MANIFEST_DIR/../../tokenizer_files");
 
-/// The `TokenCounter` now stores exactly one `Tokenizer`.
+/// Token counting for custom or fine-tuned models that the HuggingFace tokenizers do not cover.
+///
+/// Embedders register one with [`register_tokenizer`], and every [`TokenCounter`] made with
+/// [`TokenCounter::for_model`] for a matching model uses it, including context usage, payload
+/// limits and the truncation and summarization of long conversations.
+pub trait CustomTokenizer: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Registered tokenizers by model name pattern, in the order they were registered
+static CUSTOM_TOKENIZERS: Lazy<RwLock<Vec<(String, Arc<dyn CustomTokenizer>)>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Use `tokenizer` for models whose name matches `model_pattern`, where `*` matches any run of
+/// characters. A later registration takes precedence over an earlier one for the same model.
+pub fn register_tokenizer(model_pattern: impl Into<String>, tokenizer: Arc<dyn CustomTokenizer>) {
+    let model_pattern = model_pattern.into();
+    let mut tokenizers = CUSTOM_TOKENIZERS.write().unwrap();
+    tokenizers.retain(|(pattern, _)| *pattern != model_pattern);
+    tokenizers.push((model_pattern, tokenizer));
+}
+
+/// The pattern and tokenizer registered for a model, if any
+fn registered_tokenizer(model_name: &str) -> Option<(String, Arc<dyn CustomTokenizer>)> {
+    CUSTOM_TOKENIZERS
+        .read()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|(pattern, _)| matches_pattern(pattern, model_name))
+        .cloned()
+}
+
+/// Whether `name` matches `pattern`, where `*` matches any run of characters
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+enum Backend {
+    HuggingFace(Tokenizer),
+    Custom(Arc<dyn CustomTokenizer>),
+}
+
+/// Counts tokens with a HuggingFace tokenizer or a registered [`CustomTokenizer`].
 pub struct TokenCounter {
-    tokenizer: Tokenizer,
+    backend: Backend,
 }
 
 impl TokenCounter {
+    /// Creates a `TokenCounter` for a model: its registered tokenizer if there is one, otherwise
+    /// the HuggingFace tokenizer of its config.
+    pub fn for_model(model: &ModelConfig) -> Self {
+        match registered_tokenizer(&model.model_name) {
+            Some((_, tokenizer)) => Self {
+                backend: Backend::Custom(tokenizer),
+            },
+            None => Self::new(model.tokenizer_name()),
+        }
+    }
+
+    /// Identifies the tokenizer [`TokenCounter::for_model`] uses, to tell when a counter can be
+    /// reused for another model
+    pub fn tokenizer_key(model: &ModelConfig) -> String {
+        match registered_tokenizer(&model.model_name) {
+            Some((pattern, _)) => format!("custom:{}", pattern),
+            None => model.tokenizer_name().to_string(),
+        }
+    }
+
     /// Creates a new `TokenCounter` using the given HuggingFace tokenizer name.
     ///
     /// * `tokenizer_name` might look like "Xenova--gpt-4o"
     ///   or "Qwen--Qwen2.5-Coder-32B-Instruct", etc.
     pub fn new(tokenizer_name: &str) -> Self {
         match Self::load_from_embedded(tokenizer_name) {
-            Ok(tokenizer) => Self { tokenizer },
+            Ok(tokenizer) => Self {
+                backend: Backend::HuggingFace(tokenizer),
+            },
             Err(e) => {
                 println!(
                     "Tokenizer '{}' not found in embedded dir: {}",
@@ -75,7 +158,9 @@ impl TokenCounter {
         let tokenizer = Tokenizer::from_bytes(&file_content)
             .map_err(|e| format!("Failed to parse tokenizer after download: {}", e))?;
 
-        Ok(Self { tokenizer })
+        Ok(Self {
+            backend: Backend::HuggingFace(tokenizer),
+        })
     }
 
     /// Download from Hugging Face into the local directory if not already present.
@@ -108,8 +193,10 @@ impl TokenCounter {
 
     /// Count tokens for a piece of text using our single tokenizer.
     pub fn count_tokens(&self, text: &str) -> usize {
-        let encoding = self.tokenizer.encode(text, false).unwrap();
-        encoding.len()
+        match &self.backend {
+            Backend::HuggingFace(tokenizer) => tokenizer.encode(text, false).unwrap().len(),
+            Backend::Custom(tokenizer) => tokenizer.count_tokens(text),
+        }
     }
 
     pub fn count_tokens_for_tools(&self, tools: &[Tool]) -> usize {
@@ -240,6 +327,31 @@ mod tests {
     use mcp_core::tool::Tool;
     use serde_json::json;
 
+    struct WordTokenizer;
+
+    impl CustomTokenizer for WordTokenizer {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    #[test]
+    fn test_registered_tokenizer_is_used_for_matching_models() {
+        assert!(matches_pattern("acme-*-ft", "acme-7b-ft"));
+        assert!(matches_pattern("acme-*", "acme-"));
+        assert!(!matches_pattern("acme-*-ft", "acme-7b"));
+        assert!(!matches_pattern("acme", "acme-7b"));
+
+        register_tokenizer("test-words-*", Arc::new(WordTokenizer));
+        let model = ModelConfig::new("test-words-v2".to_string());
+        let counter = TokenCounter::for_model(&model);
+        assert_eq!(counter.count_tokens("one two  three"), 3);
+        assert_eq!(TokenCounter::tokenizer_key(&model), "custom:test-words-*");
+
+        let other = ModelConfig::new("gpt-4o".to_string());
+        assert_eq!(TokenCounter::tokenizer_key(&other), GPT_4O_TOKENIZER);
+    }
+
     #[test]
     fn test_claude_tokenizer() {
         let counter = TokenCounter::new(CLAUDE_TOKENIZER);
@@ -0,0 +1,244 @@
+//! Structured tool errors for the model
+//!
+//! Extensions report failures as free text, in whatever words their authors chose. Before a
+//! failed tool call is sent to the model it is mapped into a [`ToolErrorPayload`]: a category,
+//! whether calling again unchanged may succeed, and a hint on what to do instead. Every provider
+//! format presents the payload the same way, which gives the model something concrete to
+//! correct its next call with.
+//!
+//! An extension that knows better than the text heuristics can return the payload itself, as
+//! the JSON text of its execution error:
+//!
+//! ```json
+//! {"category": "invalid_arguments", "message": "branch 'mian' does not exist",
+//!  "retryable": false, "remediation": "List the branches first", "data": {"branches": ["main"]}}
+//! ```
+use mcp_core::ToolError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorCategory {
+    /// The arguments did not match the tool's schema or were rejected by the tool
+    InvalidArguments,
+    /// No tool with the requested name is available
+    UnknownTool,
+    /// A file, resource or other target of the call does not exist
+    NotFound,
+    PermissionDenied,
+    Timeout,
+    RateLimited,
+    /// The extension or a service behind it could not be reached
+    Unavailable,
+    /// Any other failure while the tool ran
+    Execution,
+}
+
+impl ToolErrorCategory {
+    /// Whether the same call may succeed when made again
+    pub fn retryable(self) -> bool {
+        matches!(
+            self,
+            ToolErrorCategory::Timeout
+                | ToolErrorCategory::RateLimited
+                | ToolErrorCategory::Unavailable
+        )
+    }
+
+    /// What the model should do about an error in this category
+    pub fn remediation(self) -> &'static str {
+        match self {
+            ToolErrorCategory::InvalidArguments => {
+                "Check the arguments against the tool's schema and description, fix them and call the tool again."
+            }
+            ToolErrorCategory::UnknownTool => {
+                "Call only tools from the list of available tools, using their exact names."
+            }
+            ToolErrorCategory::NotFound => {
+                "Verify the path or identifier exists, for example by listing or searching first, before calling again."
+            }
+            ToolErrorCategory::PermissionDenied => {
+                "Do not retry the same call. Use a location you have access to, or ask the user to grant access."
+            }
+            ToolErrorCategory::Timeout => {
+                "Retry once if the operation is expected to be quick; otherwise narrow its scope so it finishes sooner."
+            }
+            ToolErrorCategory::RateLimited => {
+                "Wait before calling again, and make fewer calls to this tool."
+            }
+            ToolErrorCategory::Unavailable => {
+                "The tool's extension could not be reached. Retry once; if it fails again, continue without this tool and tell the user."
+            }
+            ToolErrorCategory::Execution => {
+                "Read the error message, change the approach or the arguments, and do not repeat the identical call."
+            }
+        }
+    }
+}
+
+/// A tool error in the form the model sees it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolErrorPayload {
+    pub category: ToolErrorCategory,
+    pub message: String,
+    #[serde(default)]
+    pub retryable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+    /// Details the extension attached to the error
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub data: Value,
+}
+
+impl ToolErrorPayload {
+    /// An error of a category, with that category's defaults
+    pub fn new(category: ToolErrorCategory, message: impl Into<String>) -> Self {
+        Self {
+            category,
+            message: message.into(),
+            retryable: category.retryable(),
+            remediation: Some(category.remediation().to_string()),
+            data: Value::Null,
+        }
+    }
+
+    pub fn from_tool_error(error: &ToolError) -> Self {
+        match error {
+            ToolError::InvalidParameters(message) | ToolError::SchemaError(message) => {
+                Self::new(ToolErrorCategory::InvalidArguments, message)
+            }
+            ToolError::NotFound(message) => Self::new(ToolErrorCategory::UnknownTool, message),
+            ToolError::Timeout(message) => Self::new(ToolErrorCategory::Timeout, message),
+            ToolError::ExecutionError(message) => {
+                Self::from_extension(message).unwrap_or_else(|| Self::classify(message))
+            }
+            error => Self::new(ToolErrorCategory::Execution, error.to_string()),
+        }
+    }
+
+    /// A payload an extension returned itself, filling in the defaults it left out
+    fn from_extension(message: &str) -> Option<Self> {
+        let message = message.trim();
+        if !message.starts_with('{') {
+            return None;
+        }
+        let value: Value = serde_json::from_str(message).ok()?;
+        let mut payload: Self = serde_json::from_value(value.clone()).ok()?;
+        if value.get("retryable").is_none() {
+            payload.retryable = payload.category.retryable();
+        }
+        if payload.remediation.is_none() {
+            payload.remediation = Some(payload.category.remediation().to_string());
+        }
+        Some(payload)
+    }
+
+    /// Categorize a free text error by the wording common to shells, file systems and HTTP
+    fn classify(message: &str) -> Self {
+        let lower = message.to_lowercase();
+        let has = |patterns: &[&str]| patterns.iter().any(|p| lower.contains(p));
+        let category = if has(&[
+            "permission denied",
+            "access denied",
+            "forbidden",
+            "unauthorized",
+        ]) {
+            ToolErrorCategory::PermissionDenied
+        } else if has(&["rate limit", "too many requests"]) {
+            ToolErrorCategory::RateLimited
+        } else if has(&["timed out", "timeout", "deadline exceeded"]) {
+            ToolErrorCategory::Timeout
+        } else if has(&[
+            "transport error",
+            "connection refused",
+            "connection reset",
+            "service unavailable",
+            "not initialized",
+            "broken pipe",
+        ]) {
+            ToolErrorCategory::Unavailable
+        } else if has(&[
+            "code=-32602",
+            "invalid params",
+            "invalid argument",
+            "missing field",
+        ]) {
+            ToolErrorCategory::InvalidArguments
+        } else if has(&["code=-32601", "method not found", "unknown tool"]) {
+            ToolErrorCategory::UnknownTool
+        } else if has(&["no such file", "not found", "does not exist"]) {
+            ToolErrorCategory::NotFound
+        } else {
+            ToolErrorCategory::Execution
+        };
+        Self::new(category, message)
+    }
+
+    /// The text a provider format sends in place of the failed call's result
+    pub fn to_model_text(&self) -> String {
+        let payload = serde_json::to_string_pretty(self).unwrap_or_else(|_| self.message.clone());
+        format!("The tool call returned the following error:\n{}", payload)
+    }
+}
+
+/// The text to send to the model for a failed tool call
+pub fn tool_error_text(error: &ToolError) -> String {
+    ToolErrorPayload::from_tool_error(error).to_model_text()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_errors_are_categorized() {
+        let payload = ToolErrorPayload::from_tool_error(&ToolError::ExecutionError(
+            "cat: /etc/shadow: Permission denied".to_string(),
+        ));
+        assert_eq!(payload.category, ToolErrorCategory::PermissionDenied);
+        assert!(!payload.retryable);
+        assert!(payload.remediation.is_some());
+
+        let payload = ToolErrorPayload::from_tool_error(&ToolError::ExecutionError(
+            "Transport error: Connection refused".to_string(),
+        ));
+        assert_eq!(payload.category, ToolErrorCategory::Unavailable);
+        assert!(payload.retryable);
+
+        let payload =
+            ToolErrorPayload::from_tool_error(&ToolError::NotFound("developer__shel".to_string()));
+        assert_eq!(payload.category, ToolErrorCategory::UnknownTool);
+
+        let payload = ToolErrorPayload::from_tool_error(&ToolError::ExecutionError(
+            "exit status 1".to_string(),
+        ));
+        assert_eq!(payload.category, ToolErrorCategory::Execution);
+    }
+
+    #[test]
+    fn test_extension_payload_is_kept() {
+        let error = ToolError::ExecutionError(
+            json!({
+                "category": "invalid_arguments",
+                "message": "branch 'mian' does not exist",
+                "data": {"branches": ["main"]}
+            })
+            .to_string(),
+        );
+        let payload = ToolErrorPayload::from_tool_error(&error);
+        assert_eq!(payload.category, ToolErrorCategory::InvalidArguments);
+        assert_eq!(payload.message, "branch 'mian' does not exist");
+        assert_eq!(payload.data, json!({"branches": ["main"]}));
+        assert!(!payload.retryable);
+        assert_eq!(
+            payload.remediation.as_deref(),
+            Some(ToolErrorCategory::InvalidArguments.remediation())
+        );
+
+        let text = tool_error_text(&error);
+        assert!(text.contains("\"category\": \"invalid_arguments\""));
+        assert!(text.contains("\"remediation\""));
+    }
+}
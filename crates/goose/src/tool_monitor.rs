@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::config::Config;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolCall {
@@ -17,39 +21,328 @@ impl ToolCall {
     }
 }
 
+/// Limits how often a tool may be called within a short window of time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BurstLimit {
+    pub max_calls: u32,
+    pub window_secs: u64,
+}
+
+impl BurstLimit {
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.window_secs)
+    }
+}
+
+/// The set of limits applied to a tool. Every limit is optional; `None` means unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepetitionPolicy {
+    /// Maximum number of consecutive calls with identical arguments
+    #[serde(default)]
+    pub max_repetitions: Option<u32>,
+    /// Maximum number of calls to the tool within a single reply
+    #[serde(default)]
+    pub max_per_turn: Option<u32>,
+    /// Maximum number of calls to the tool over the lifetime of the monitor
+    #[serde(default)]
+    pub max_per_session: Option<u32>,
+    #[serde(default)]
+    pub burst: Option<BurstLimit>,
+}
+
+impl RepetitionPolicy {
+    pub fn with_max_repetitions(mut self, max_repetitions: u32) -> Self {
+        self.max_repetitions = Some(max_repetitions);
+        self
+    }
+
+    pub fn with_max_per_turn(mut self, max_per_turn: u32) -> Self {
+        self.max_per_turn = Some(max_per_turn);
+        self
+    }
+
+    pub fn with_max_per_session(mut self, max_per_session: u32) -> Self {
+        self.max_per_session = Some(max_per_session);
+        self
+    }
+
+    pub fn with_burst(mut self, max_calls: u32, window_secs: u64) -> Self {
+        self.burst = Some(BurstLimit {
+            max_calls,
+            window_secs,
+        });
+        self
+    }
+}
+
+/// Repetition policy as read from the `GOOSE_TOOL_REPETITION_POLICY` config key
+///
+/// ```yaml
+/// GOOSE_TOOL_REPETITION_POLICY:
+///   default:
+///     max_per_turn: 10
+///   tools:
+///     developer__check_build_status:
+///       burst: { max_calls: 30, window_secs: 60 }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepetitionConfig {
+    #[serde(default)]
+    pub default: RepetitionPolicy,
+    #[serde(default)]
+    pub tools: HashMap<String, RepetitionPolicy>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RepetitionViolation {
+    #[error("'{tool}' was called with identical arguments more than {limit} times in a row")]
+    Repetitions { tool: String, limit: u32 },
+    #[error("'{tool}' exceeded the limit of {limit} calls per turn")]
+    PerTurn { tool: String, limit: u32 },
+    #[error("'{tool}' exceeded the limit of {limit} calls per session")]
+    PerSession { tool: String, limit: u32 },
+    #[error("'{tool}' was called more than {limit} times within {window_secs}s")]
+    Burst {
+        tool: String,
+        limit: u32,
+        window_secs: u64,
+    },
+}
+
+// Changed arguments listed in a diff before the rest are summarized
+const MAX_DIFF_LINES: usize = 8;
+// Longest argument value shown in a diff
+const MAX_DIFF_VALUE_CHARS: usize = 60;
+
+fn compact_value(value: &serde_json::Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > MAX_DIFF_VALUE_CHARS {
+        let truncated: String = text.chars().take(MAX_DIFF_VALUE_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        text
+    }
+}
+
+/// Collect the differences between two argument values, one line per changed path
+fn diff_arguments(
+    path: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    lines: &mut Vec<String>,
+) {
+    match (old, new) {
+        (serde_json::Value::Object(old), serde_json::Value::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                match (old.get(key), new.get(key)) {
+                    (Some(o), Some(n)) => diff_arguments(&key_path, o, n, lines),
+                    (None, Some(n)) => lines.push(format!("+ {}: {}", key_path, compact_value(n))),
+                    (Some(o), None) => lines.push(format!("- {}: {}", key_path, compact_value(o))),
+                    (None, None) => {}
+                }
+            }
+        }
+        _ if old != new => lines.push(format!(
+            "~ {}: {} -> {}",
+            if path.is_empty() { "(arguments)" } else { path },
+            compact_value(old),
+            compact_value(new)
+        )),
+        _ => {}
+    }
+}
+
+/// The part of a monitor's state that outlives the process: its policies and the calls made
+/// in the session so far. Per-turn counts and burst windows start over on restore.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolMonitorState {
+    pub policy: RepetitionPolicy,
+    #[serde(default)]
+    pub overrides: HashMap<String, RepetitionPolicy>,
+    pub last_call: Option<ToolCall>,
+    #[serde(default)]
+    pub repeat_count: u32,
+    #[serde(default)]
+    pub call_counts: HashMap<String, u32>,
+    #[serde(default)]
+    pub argument_diffs: bool,
+}
+
 #[derive(Debug)]
 pub struct ToolMonitor {
-    max_repetitions: Option<u32>,
+    policy: RepetitionPolicy,
+    overrides: HashMap<String, RepetitionPolicy>,
     last_call: Option<ToolCall>,
     repeat_count: u32,
     call_counts: HashMap<String, u32>,
+    turn_counts: HashMap<String, u32>,
+    recent_calls: HashMap<String, VecDeque<Instant>>,
+    /// Whether repeated calls get a diff of their arguments against the previous call
+    argument_diffs: bool,
+    turn_arguments: HashMap<String, serde_json::Value>,
 }
 
 impl ToolMonitor {
     pub fn new(max_repetitions: Option<u32>) -> Self {
-        Self {
+        Self::with_policy(RepetitionPolicy {
             max_repetitions,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_policy(policy: RepetitionPolicy) -> Self {
+        Self {
+            policy,
+            overrides: HashMap::new(),
             last_call: None,
             repeat_count: 0,
             call_counts: HashMap::new(),
+            turn_counts: HashMap::new(),
+            recent_calls: HashMap::new(),
+            argument_diffs: false,
+            turn_arguments: HashMap::new(),
+        }
+    }
+
+    /// Build a monitor from `GOOSE_TOOL_REPETITION_POLICY`. An explicit
+    /// `max_repetitions` takes precedence over the configured default.
+    pub fn from_config(max_repetitions: Option<u32>) -> Self {
+        let config: RepetitionConfig = Config::global()
+            .get_param("GOOSE_TOOL_REPETITION_POLICY")
+            .unwrap_or_default();
+
+        let mut policy = config.default;
+        if max_repetitions.is_some() {
+            policy.max_repetitions = max_repetitions;
+        }
+
+        let mut monitor = Self::with_policy(policy);
+        for (tool_name, policy) in config.tools {
+            monitor.set_tool_override(tool_name, policy);
+        }
+        monitor.set_argument_diffs(
+            Config::global()
+                .get_param("GOOSE_TOOL_ARGUMENT_DIFFS")
+                .unwrap_or(false),
+        );
+        monitor
+    }
+
+    /// Show the model how its arguments changed when it calls a tool again within a turn,
+    /// to help it converge instead of thrashing on small adjustments
+    pub fn set_argument_diffs(&mut self, enabled: bool) {
+        self.argument_diffs = enabled;
+    }
+
+    /// Use a dedicated policy for a single tool instead of the default one
+    pub fn set_tool_override(&mut self, tool_name: impl Into<String>, policy: RepetitionPolicy) {
+        self.overrides.insert(tool_name.into(), policy);
+    }
+
+    pub fn policy_for(&self, tool_name: &str) -> &RepetitionPolicy {
+        self.overrides.get(tool_name).unwrap_or(&self.policy)
+    }
+
+    /// Mark the start of a new turn, clearing the per-turn counters
+    pub fn start_turn(&mut self) {
+        self.turn_counts.clear();
+        self.turn_arguments.clear();
+    }
+
+    /// Record a call's arguments, returning a note describing how they differ from the
+    /// previous call to the same tool in this turn. Returns None unless argument diffs are
+    /// enabled and the arguments changed.
+    pub fn argument_diff(&mut self, tool_call: &ToolCall) -> Option<String> {
+        if !self.argument_diffs {
+            return None;
+        }
+        let previous = self
+            .turn_arguments
+            .insert(tool_call.name.clone(), tool_call.parameters.clone())?;
+
+        let mut lines = Vec::new();
+        diff_arguments("", &previous, &tool_call.parameters, &mut lines);
+        if lines.is_empty() {
+            return None;
         }
+        let remaining = lines.len().saturating_sub(MAX_DIFF_LINES);
+        lines.truncate(MAX_DIFF_LINES);
+        if remaining > 0 {
+            lines.push(format!("... and {} more changes", remaining));
+        }
+
+        Some(format!(
+            "Note: '{}' was already called this turn. Arguments changed from the previous call:\n{}\nIf the result is still not what you need, reconsider the approach rather than adjusting arguments again.",
+            tool_call.name,
+            lines.join("\n")
+        ))
     }
 
     pub fn check_tool_call(&mut self, tool_call: ToolCall) -> bool {
-        let total_calls = self.call_counts.entry(tool_call.name.clone()).or_insert(0);
+        self.check(tool_call).is_ok()
+    }
+
+    /// Check a tool call against its policy, recording it when allowed
+    pub fn check(&mut self, tool_call: ToolCall) -> Result<(), RepetitionViolation> {
+        self.check_at(tool_call, Instant::now())
+    }
+
+    fn check_at(&mut self, tool_call: ToolCall, now: Instant) -> Result<(), RepetitionViolation> {
+        let policy = self.policy_for(&tool_call.name).clone();
+        let name = tool_call.name.clone();
+
+        let total_calls = self.call_counts.entry(name.clone()).or_insert(0);
         *total_calls += 1;
 
-        if self.max_repetitions.is_none() {
-            self.last_call = Some(tool_call);
-            self.repeat_count = 1;
-            return true;
+        if let Some(limit) = policy.max_per_session {
+            if *total_calls > limit {
+                return Err(RepetitionViolation::PerSession { tool: name, limit });
+            }
         }
 
-        if let Some(last) = &self.last_call {
-            if last.matches(&tool_call) {
-                self.repeat_count += 1;
-                if self.repeat_count > self.max_repetitions.unwrap() {
-                    return false;
+        let turn_calls = self.turn_counts.entry(name.clone()).or_insert(0);
+        *turn_calls += 1;
+        if let Some(limit) = policy.max_per_turn {
+            if *turn_calls > limit {
+                return Err(RepetitionViolation::PerTurn { tool: name, limit });
+            }
+        }
+
+        if let Some(burst) = policy.burst {
+            let recent = self.recent_calls.entry(name.clone()).or_default();
+            while recent
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= burst.window())
+            {
+                recent.pop_front();
+            }
+            if recent.len() as u32 >= burst.max_calls {
+                return Err(RepetitionViolation::Burst {
+                    tool: name,
+                    limit: burst.max_calls,
+                    window_secs: burst.window_secs,
+                });
+            }
+            recent.push_back(now);
+        }
+
+        if let Some(limit) = policy.max_repetitions {
+            if let Some(last) = &self.last_call {
+                if last.matches(&tool_call) {
+                    self.repeat_count += 1;
+                    if self.repeat_count > limit {
+                        return Err(RepetitionViolation::Repetitions { tool: name, limit });
+                    }
+                } else {
+                    self.repeat_count = 1;
                 }
             } else {
                 self.repeat_count = 1;
@@ -59,7 +352,29 @@ impl ToolMonitor {
         }
 
         self.last_call = Some(tool_call);
-        true
+        Ok(())
+    }
+
+    /// Capture the state needed to continue the session in another process
+    pub fn state(&self) -> ToolMonitorState {
+        ToolMonitorState {
+            policy: self.policy.clone(),
+            overrides: self.overrides.clone(),
+            last_call: self.last_call.clone(),
+            repeat_count: self.repeat_count,
+            call_counts: self.call_counts.clone(),
+            argument_diffs: self.argument_diffs,
+        }
+    }
+
+    pub fn from_state(state: ToolMonitorState) -> Self {
+        let mut monitor = Self::with_policy(state.policy);
+        monitor.overrides = state.overrides;
+        monitor.last_call = state.last_call;
+        monitor.repeat_count = state.repeat_count;
+        monitor.call_counts = state.call_counts;
+        monitor.argument_diffs = state.argument_diffs;
+        monitor
     }
 
     pub fn get_stats(&self) -> HashMap<String, u32> {
@@ -70,5 +385,157 @@ impl ToolMonitor {
         self.last_call = None;
         self.repeat_count = 0;
         self.call_counts.clear();
+        self.turn_counts.clear();
+        self.recent_calls.clear();
+        self.turn_arguments.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(name: &str, args: serde_json::Value) -> ToolCall {
+        ToolCall::new(name.to_string(), args)
+    }
+
+    #[test]
+    fn test_consecutive_repetitions() {
+        let mut monitor = ToolMonitor::new(Some(2));
+        assert!(monitor.check_tool_call(call("shell", json!({"cmd": "ls"}))));
+        assert!(monitor.check_tool_call(call("shell", json!({"cmd": "ls"}))));
+        assert!(!monitor.check_tool_call(call("shell", json!({"cmd": "ls"}))));
+        // Different arguments reset the streak
+        assert!(monitor.check_tool_call(call("shell", json!({"cmd": "pwd"}))));
+    }
+
+    #[test]
+    fn test_per_turn_limit_resets_on_new_turn() {
+        let mut monitor =
+            ToolMonitor::with_policy(RepetitionPolicy::default().with_max_per_turn(2));
+        assert!(monitor.check(call("shell", json!({"cmd": "a"}))).is_ok());
+        assert!(monitor.check(call("shell", json!({"cmd": "b"}))).is_ok());
+        assert_eq!(
+            monitor.check(call("shell", json!({"cmd": "c"}))),
+            Err(RepetitionViolation::PerTurn {
+                tool: "shell".to_string(),
+                limit: 2
+            })
+        );
+
+        monitor.start_turn();
+        assert!(monitor.check(call("shell", json!({"cmd": "d"}))).is_ok());
+    }
+
+    #[test]
+    fn test_per_session_limit_survives_turns() {
+        let mut monitor =
+            ToolMonitor::with_policy(RepetitionPolicy::default().with_max_per_session(2));
+        assert!(monitor.check(call("shell", json!({}))).is_ok());
+        monitor.start_turn();
+        assert!(monitor.check(call("shell", json!({}))).is_ok());
+        monitor.start_turn();
+        assert!(matches!(
+            monitor.check(call("shell", json!({}))),
+            Err(RepetitionViolation::PerSession { .. })
+        ));
+
+        monitor.reset();
+        assert!(monitor.check(call("shell", json!({}))).is_ok());
+    }
+
+    #[test]
+    fn test_burst_window_slides() {
+        let mut monitor = ToolMonitor::with_policy(RepetitionPolicy::default().with_burst(2, 10));
+        let start = Instant::now();
+        assert!(monitor.check_at(call("fetch", json!({})), start).is_ok());
+        assert!(monitor
+            .check_at(call("fetch", json!({})), start + Duration::from_secs(1))
+            .is_ok());
+        assert!(matches!(
+            monitor.check_at(call("fetch", json!({})), start + Duration::from_secs(2)),
+            Err(RepetitionViolation::Burst { .. })
+        ));
+        // Once the first call falls out of the window there is room again
+        assert!(monitor
+            .check_at(call("fetch", json!({})), start + Duration::from_secs(10))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_tool_override_allows_polling() {
+        let mut monitor = ToolMonitor::with_policy(
+            RepetitionPolicy::default()
+                .with_max_repetitions(1)
+                .with_max_per_turn(3),
+        );
+        monitor.set_tool_override(
+            "check_build_status",
+            RepetitionPolicy::default().with_max_per_turn(10),
+        );
+
+        for _ in 0..10 {
+            assert!(monitor
+                .check(call("check_build_status", json!({"id": 1})))
+                .is_ok());
+        }
+        assert!(monitor
+            .check(call("check_build_status", json!({"id": 1})))
+            .is_err());
+
+        assert!(monitor.check(call("shell", json!({"cmd": "ls"}))).is_ok());
+        assert!(monitor.check(call("shell", json!({"cmd": "ls"}))).is_err());
+    }
+
+    #[test]
+    fn test_argument_diff_on_repeated_calls() {
+        let mut monitor = ToolMonitor::new(None);
+        let first = call("search", json!({"query": "foo", "limit": 10}));
+        assert_eq!(monitor.argument_diff(&first), None);
+        assert_eq!(monitor.argument_diff(&first), None);
+
+        monitor.set_argument_diffs(true);
+        assert_eq!(monitor.argument_diff(&first), None);
+        let diff = monitor
+            .argument_diff(&call(
+                "search",
+                json!({"query": "foo bar", "filters": {"lang": "rust"}}),
+            ))
+            .unwrap();
+        assert!(diff.contains("~ query: \"foo\" -> \"foo bar\""));
+        assert!(diff.contains("- limit: 10"));
+        assert!(diff.contains("+ filters: {\"lang\":\"rust\"}"));
+
+        // Identical arguments and other tools do not produce a diff
+        assert_eq!(
+            monitor.argument_diff(&call(
+                "search",
+                json!({"query": "foo bar", "filters": {"lang": "rust"}})
+            )),
+            None
+        );
+        assert_eq!(monitor.argument_diff(&call("shell", json!({}))), None);
+
+        // A new turn starts fresh
+        monitor.start_turn();
+        assert_eq!(
+            monitor.argument_diff(&call("search", json!({"query": "baz"}))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_state_survives_round_trip() {
+        let mut monitor =
+            ToolMonitor::with_policy(RepetitionPolicy::default().with_max_per_session(2));
+        assert!(monitor.check_tool_call(call("shell", json!({"cmd": "ls"}))));
+        assert!(monitor.check_tool_call(call("shell", json!({"cmd": "pwd"}))));
+
+        let state = serde_json::to_string(&monitor.state()).unwrap();
+        let mut restored = ToolMonitor::from_state(serde_json::from_str(&state).unwrap());
+        assert_eq!(restored.get_stats(), monitor.get_stats());
+        // The session limit counts the calls made before the restore
+        assert!(!restored.check_tool_call(call("shell", json!({"cmd": "ls"}))));
     }
 }
@@ -0,0 +1,61 @@
+use goose::model::ModelConfig;
+use goose::providers::conformance::{ConformanceHarness, FormatUnderTest};
+use goose::providers::formats::{anthropic, databricks, google, openai};
+use goose::providers::utils::ImageFormat;
+
+fn harness() -> ConformanceHarness {
+    ConformanceHarness::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/conformance"
+    ))
+}
+
+const SYSTEM: &str = "You are a helpful assistant.";
+
+#[test]
+fn test_openai_format_conformance() {
+    let model_config = ModelConfig::new("gpt-4o".to_string());
+    let format = FormatUnderTest::new(
+        "openai",
+        move |messages| {
+            openai::create_request(&model_config, SYSTEM, messages, &[], &ImageFormat::OpenAi)
+        },
+        openai::response_to_message,
+    );
+    harness().check(&format).unwrap();
+}
+
+#[test]
+fn test_databricks_format_conformance() {
+    let model_config = ModelConfig::new("databricks-meta-llama-3-3-70b-instruct".to_string());
+    let format = FormatUnderTest::new(
+        "databricks",
+        move |messages| {
+            databricks::create_request(&model_config, SYSTEM, messages, &[], &ImageFormat::OpenAi)
+        },
+        databricks::response_to_message,
+    );
+    harness().check(&format).unwrap();
+}
+
+#[test]
+fn test_anthropic_format_conformance() {
+    let model_config = ModelConfig::new("claude-3-5-sonnet-latest".to_string());
+    let format = FormatUnderTest::new(
+        "anthropic",
+        move |messages| anthropic::create_request(&model_config, SYSTEM, messages, &[]),
+        anthropic::response_to_message,
+    );
+    harness().check(&format).unwrap();
+}
+
+#[test]
+fn test_google_format_conformance() {
+    let model_config = ModelConfig::new("gemini-2.0-flash".to_string());
+    let format = FormatUnderTest::new(
+        "google",
+        move |messages| google::create_request(&model_config, SYSTEM, messages, &[]),
+        google::response_to_message,
+    );
+    harness().check(&format).unwrap();
+}
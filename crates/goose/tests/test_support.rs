@@ -409,5 +409,9 @@ pub fn create_test_session_metadata(message_count: usize, working_dir: &str) ->
         accumulated_total_tokens: Some(100),
         accumulated_input_tokens: Some(50),
         accumulated_output_tokens: Some(50),
+        tags: Default::default(),
+        reply_cancelled: false,
+        accumulated_cost: None,
+        unpriced_responses: 0,
     }
 }
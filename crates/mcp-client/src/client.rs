@@ -11,6 +11,7 @@ use std::sync::{
 };
 use thiserror::Error;
 use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
 use tower::{timeout::TimeoutLayer, Layer, Service, ServiceExt};
 
 use crate::{McpService, TransportHandle};
@@ -41,6 +42,9 @@ pub enum Error {
     #[error("Request timed out")]
     Timeout(#[from] tower::timeout::error::Elapsed),
 
+    #[error("Request cancelled")]
+    Cancelled,
+
     #[error("Error from mcp-server: {0}")]
     ServerBoxError(BoxError),
 
@@ -99,10 +103,27 @@ pub trait McpClientTrait: Send + Sync {
 
     async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error>;
 
+    /// Call a tool, giving up with [`Error::Cancelled`] if `cancel` fires before the result
+    /// arrives. Clients that can tell the server to stop working on the call do so.
+    async fn call_tool_cancellable(
+        &self,
+        name: &str,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        tokio::select! {
+            result = self.call_tool(name, arguments) => result,
+            _ = cancel.cancelled() => Err(Error::Cancelled),
+        }
+    }
+
     async fn list_prompts(&self, next_cursor: Option<String>) -> Result<ListPromptsResult, Error>;
 
     async fn get_prompt(&self, name: &str, arguments: Value) -> Result<GetPromptResult, Error>;
 
+    /// Check that the server is still alive and answering requests
+    async fn ping(&self) -> Result<(), Error>;
+
     async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage>;
 }
 
@@ -170,8 +191,20 @@ where
     where
         R: for<'de> Deserialize<'de>,
     {
-        let mut service = self.service.lock().await;
-        service.ready().await.map_err(|_| Error::NotReady)?;
+        self.send_cancellable_request(method, params, None).await
+    }
+
+    /// Send a JSON-RPC request, telling the server with `notifications/cancelled` to stop
+    /// working on it if `cancel` fires before the response arrives.
+    async fn send_cancellable_request<R>(
+        &self,
+        method: &str,
+        params: Value,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<R, Error>
+    where
+        R: for<'de> Deserialize<'de>,
+    {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
 
         let mut params = params.clone();
@@ -186,26 +219,50 @@ where
             params: Some(params),
         });
 
-        let response_msg = service
-            .call(request)
-            .await
-            .map_err(|e| Error::McpServerError {
-                server: self
-                    .server_info
-                    .as_ref()
-                    .map(|s| s.name.clone())
-                    .unwrap_or("".to_string()),
-                method: method.to_string(),
-                // we don't need include params because it can be really large
-                source: Box::<Error>::new(e.into()),
-            })?;
+        // The service is only locked to send the request, so that other requests and the
+        // cancellation notification can go out while waiting for the response
+        let response = {
+            let mut service = self.service.lock().await;
+            service.ready().await.map_err(|_| Error::NotReady)?;
+            service.call(request)
+        };
+        let response = match cancel {
+            Some(cancel) => tokio::select! {
+                response = response => response,
+                _ = cancel.cancelled() => {
+                    let params = json!({
+                        "requestId": id,
+                        "reason": "The client cancelled the request",
+                    });
+                    if let Err(e) = self.send_notification("notifications/cancelled", params).await {
+                        tracing::warn!("Failed to cancel request {}: {}", id, e);
+                    }
+                    return Err(Error::Cancelled);
+                }
+            },
+            None => response.await,
+        };
+
+        let response_msg = response.map_err(|e| Error::McpServerError {
+            server: self
+                .server_info
+                .as_ref()
+                .map(|s| s.name.clone())
+                .unwrap_or("".to_string()),
+            method: method.to_string(),
+            // we don't need include params because it can be really large
+            source: Box::<Error>::new(e.into()),
+        })?;
 
         match response_msg {
             JsonRpcMessage::Response(JsonRpcResponse {
-                id, result, error, ..
+                id: response_id,
+                result,
+                error,
+                ..
             }) => {
                 // Verify id matches
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+                if response_id != Some(id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcResponse".to_string(),
                     ));
@@ -221,8 +278,12 @@ where
                     Err(Error::UnexpectedResponse("missing result".to_string()))
                 }
             }
-            JsonRpcMessage::Error(JsonRpcError { id, error, .. }) => {
-                if id != Some(self.next_id.load(Ordering::SeqCst) - 1) {
+            JsonRpcMessage::Error(JsonRpcError {
+                id: response_id,
+                error,
+                ..
+            }) => {
+                if response_id != Some(id) {
                     return Err(Error::UnexpectedResponse(
                         "id mismatch for JsonRpcError".to_string(),
                     ));
@@ -273,6 +334,31 @@ where
     fn completed_initialization(&self) -> bool {
         self.server_capabilities.is_some()
     }
+
+    async fn call_tool_inner(
+        &self,
+        name: &str,
+        arguments: Value,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<CallToolResult, Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+        // If tools is not supported, return an error
+        if self.server_capabilities.as_ref().unwrap().tools.is_none() {
+            return Err(Error::RpcError {
+                code: METHOD_NOT_FOUND,
+                message: "Server does not support 'tools' capability".to_string(),
+            });
+        }
+
+        let params = serde_json::json!({ "name": name, "arguments": arguments });
+
+        // TODO ERROR: check that if there is an error, we send back is_error: true with msg
+        // https://modelcontextprotocol.io/docs/concepts/tools#error-handling-2
+        self.send_cancellable_request("tools/call", params, cancel)
+            .await
+    }
 }
 
 #[async_trait::async_trait]
@@ -374,22 +460,16 @@ where
     }
 
     async fn call_tool(&self, name: &str, arguments: Value) -> Result<CallToolResult, Error> {
-        if !self.completed_initialization() {
-            return Err(Error::NotInitialized);
-        }
-        // If tools is not supported, return an error
-        if self.server_capabilities.as_ref().unwrap().tools.is_none() {
-            return Err(Error::RpcError {
-                code: METHOD_NOT_FOUND,
-                message: "Server does not support 'tools' capability".to_string(),
-            });
-        }
-
-        let params = serde_json::json!({ "name": name, "arguments": arguments });
+        self.call_tool_inner(name, arguments, None).await
+    }
 
-        // TODO ERROR: check that if there is an error, we send back is_error: true with msg
-        // https://modelcontextprotocol.io/docs/concepts/tools#error-handling-2
-        self.send_request("tools/call", params).await
+    async fn call_tool_cancellable(
+        &self,
+        name: &str,
+        arguments: Value,
+        cancel: CancellationToken,
+    ) -> Result<CallToolResult, Error> {
+        self.call_tool_inner(name, arguments, Some(&cancel)).await
     }
 
     async fn list_prompts(&self, next_cursor: Option<String>) -> Result<ListPromptsResult, Error> {
@@ -430,6 +510,14 @@ where
         self.send_request("prompts/get", params).await
     }
 
+    async fn ping(&self) -> Result<(), Error> {
+        if !self.completed_initialization() {
+            return Err(Error::NotInitialized);
+        }
+        let _: Value = self.send_request("ping", serde_json::json!({})).await?;
+        Ok(())
+    }
+
     async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
         let (tx, rx) = mpsc::channel(16);
         self.notification_subscribers.lock().await.push(tx);
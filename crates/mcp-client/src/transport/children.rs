@@ -0,0 +1,106 @@
+//! The child processes started by stdio transports
+//!
+//! Extension processes run in their own process group on Unix, so a Ctrl+C in the terminal
+//! does not reach them, and they outlive the host if it exits before its transports are
+//! dropped. Every child is registered here while it runs so that a signal handler in the
+//! host can reap all of them with [`terminate_all`].
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use nix::sys::signal::{kill, Signal};
+#[cfg(unix)]
+use nix::unistd::Pid;
+
+/// Interval at which terminated processes are checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Process ids of the running children, which are also their process group ids on Unix
+static CHILDREN: LazyLock<Mutex<HashSet<u32>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+pub fn register(pid: u32) {
+    CHILDREN.lock().unwrap().insert(pid);
+}
+
+pub fn unregister(pid: u32) {
+    CHILDREN.lock().unwrap().remove(&pid);
+}
+
+/// Process ids of the registered children
+pub fn running() -> Vec<u32> {
+    CHILDREN.lock().unwrap().iter().copied().collect()
+}
+
+/// Ask a child and the processes it started to exit, and kill them after the grace period
+#[cfg(unix)]
+pub fn terminate(pid: u32, grace: Duration) {
+    let group = Pid::from_raw(-(pid as i32));
+    if kill(group, Signal::SIGTERM).is_ok() {
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline && kill(group, None).is_ok() {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+        let _ = kill(group, Signal::SIGKILL);
+    }
+    unregister(pid);
+}
+
+/// Kill a child and the processes it started; Windows has no polite equivalent of SIGTERM
+/// for console processes, so the grace period is not used
+#[cfg(windows)]
+pub fn terminate(pid: u32, _grace: Duration) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    unregister(pid);
+}
+
+/// Terminate every registered child, sharing one grace period between them
+pub fn terminate_all(grace: Duration) {
+    let children = running();
+    if children.is_empty() {
+        return;
+    }
+    tracing::info!("Terminating {} extension processes", children.len());
+
+    #[cfg(unix)]
+    {
+        for pid in &children {
+            let _ = kill(Pid::from_raw(-(*pid as i32)), Signal::SIGTERM);
+        }
+        let deadline = Instant::now() + grace;
+        while Instant::now() < deadline
+            && children
+                .iter()
+                .any(|pid| kill(Pid::from_raw(-(*pid as i32)), None).is_ok())
+        {
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    for pid in children {
+        terminate(pid, Duration::ZERO);
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminate_all_reaps_process_groups() {
+        let mut command = std::process::Command::new("sleep");
+        command.arg("30");
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        let mut child = command.spawn().unwrap();
+        register(child.id());
+        assert!(running().contains(&child.id()));
+
+        terminate_all(Duration::from_secs(1));
+        assert!(!running().contains(&child.id()));
+        assert!(!child.wait().unwrap().success());
+    }
+}
@@ -28,6 +28,9 @@ pub enum Error {
     #[error("SSE connection error: {0}")]
     SseConnection(String),
 
+    #[error("WebSocket connection error: {0}")]
+    WebSocketConnection(String),
+
     #[error("HTTP error: {status} - {message}")]
     HttpError { status: u16, message: String },
 }
@@ -73,8 +76,13 @@ pub async fn serialize_and_send(
     }
 }
 
+pub mod children;
+
 pub mod stdio;
-pub use stdio::StdioTransport;
+pub use stdio::{LogBuffer, StdioTransport};
 
 pub mod sse;
-pub use sse::SseTransport;
+pub use sse::{HttpClientOptions, SseTransport};
+
+pub mod websocket;
+pub use websocket::{ReconnectPolicy, WebSocketTransport};
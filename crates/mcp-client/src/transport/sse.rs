@@ -16,6 +16,59 @@ use super::{serialize_and_send, Transport, TransportHandle};
 // Timeout for the endpoint discovery
 const ENDPOINT_TIMEOUT_SECS: u64 = 5;
 
+/// Proxy, TLS and connection pool settings for the HTTP client used to POST messages
+#[derive(Debug, Clone, Default)]
+pub struct HttpClientOptions {
+    /// Proxy used for all requests, e.g. `http://proxy:3128`
+    pub proxy: Option<String>,
+    /// Comma separated hosts that bypass the proxy
+    pub no_proxy: Option<String>,
+    /// PEM encoded root certificates to trust in addition to the system roots
+    pub ca_cert_pem: Option<Vec<u8>>,
+    pub pool_max_idle_per_host: Option<usize>,
+    pub pool_idle_timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+}
+
+impl HttpClientOptions {
+    pub fn build(&self) -> Result<HttpClient, Error> {
+        let mut builder = HttpClient::builder();
+
+        if let Some(proxy_url) = &self.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| Error::SseConnection(format!("Invalid proxy URL: {}", e)))?
+                .no_proxy(
+                    self.no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(pem) = &self.ca_cert_pem {
+            let certs = reqwest::Certificate::from_pem_bundle(pem)
+                .map_err(|e| Error::SseConnection(format!("Invalid CA certificate: {}", e)))?;
+            for cert in certs {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        builder
+            .build()
+            .map_err(|e| Error::SseConnection(format!("Failed to build HTTP client: {}", e)))
+    }
+}
+
 /// The SSE-based actor that continuously:
 /// - Reads incoming events from the SSE stream.
 /// - Sends outgoing messages via HTTP POST (once the post endpoint is known).
@@ -38,13 +91,23 @@ impl SseActor {
         sender: mpsc::Sender<JsonRpcMessage>,
         sse_url: String,
         post_endpoint: Arc<RwLock<Option<String>>>,
+    ) -> Self {
+        Self::with_http_client(receiver, sender, sse_url, post_endpoint, HttpClient::new())
+    }
+
+    pub fn with_http_client(
+        receiver: mpsc::Receiver<String>,
+        sender: mpsc::Sender<JsonRpcMessage>,
+        sse_url: String,
+        post_endpoint: Arc<RwLock<Option<String>>>,
+        http_client: HttpClient,
     ) -> Self {
         Self {
             receiver,
             sender,
             sse_url,
             post_endpoint,
-            http_client: HttpClient::new(),
+            http_client,
         }
     }
 
@@ -203,6 +266,7 @@ impl TransportHandle for SseTransportHandle {
 pub struct SseTransport {
     sse_url: String,
     env: HashMap<String, String>,
+    http_options: HttpClientOptions,
 }
 
 /// The SSE transport spawns an `SseActor` on `start()`.
@@ -211,9 +275,16 @@ impl SseTransport {
         Self {
             sse_url: sse_url.into(),
             env,
+            http_options: HttpClientOptions::default(),
         }
     }
 
+    /// Configure the client used to POST messages to the server
+    pub fn with_http_options(mut self, http_options: HttpClientOptions) -> Self {
+        self.http_options = http_options;
+        self
+    }
+
     /// Waits for the endpoint to be set, up to 10 attempts.
     async fn wait_for_endpoint(
         post_endpoint: Arc<RwLock<Option<String>>>,
@@ -252,7 +323,9 @@ impl Transport for SseTransport {
         let post_endpoint_clone = Arc::clone(&post_endpoint);
 
         // Build the actor
-        let actor = SseActor::new(rx, otx, self.sse_url.clone(), post_endpoint);
+        let http_client = self.http_options.build()?;
+        let actor =
+            SseActor::with_http_client(rx, otx, self.sse_url.clone(), post_endpoint, http_client);
 
         // Spawn the actor task
         tokio::spawn(actor.run());
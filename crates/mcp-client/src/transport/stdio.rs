@@ -1,11 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
 
 use async_trait::async_trait;
 use mcp_core::protocol::JsonRpcMessage;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::sync::{mpsc, Mutex};
 
 // Import nix crate components instead of libc
@@ -14,11 +14,63 @@ use nix::sys::signal::{kill, Signal};
 #[cfg(unix)]
 use nix::unistd::{getpgid, Pid};
 
-use super::{serialize_and_send, Error, Transport, TransportHandle};
+use super::{children, serialize_and_send, Error, Transport, TransportHandle};
 
 // Global to track process groups we've created
 static PROCESS_GROUP: AtomicI32 = AtomicI32::new(-1);
 
+// Number of stderr lines kept per process unless configured otherwise
+const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+/// A bounded buffer holding the most recent lines a child process wrote to stderr
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    lines: Arc<std::sync::Mutex<VecDeque<String>>>,
+    capacity: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_CAPACITY)
+    }
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Append a line, dropping the oldest line once the buffer is full
+    pub fn push(&self, line: impl Into<String>) {
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() >= self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.into());
+    }
+
+    /// The last `n` lines, oldest first
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        lines
+            .iter()
+            .skip(lines.len().saturating_sub(n))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 /// A `StdioTransport` uses a child process's stdin/stdout as a communication channel.
 ///
 /// It uses channels for message passing and handles responses asynchronously through a background task.
@@ -26,25 +78,20 @@ pub struct StdioActor {
     receiver: Option<mpsc::Receiver<String>>,
     sender: Option<mpsc::Sender<JsonRpcMessage>>,
     process: Child, // we store the process to keep it alive
+    /// Id of the process and its process group, registered until the actor is dropped
+    pid: Option<u32>,
     error_sender: mpsc::Sender<Error>,
     stdin: Option<ChildStdin>,
     stdout: Option<ChildStdout>,
     stderr: Option<ChildStderr>,
+    stderr_log: LogBuffer,
 }
 
 impl Drop for StdioActor {
     fn drop(&mut self) {
-        // Get the process group ID before attempting cleanup
-        #[cfg(unix)]
-        if let Some(pid) = self.process.id() {
-            if let Ok(pgid) = getpgid(Some(Pid::from_raw(pid as i32))) {
-                // Send SIGTERM to the entire process group
-                let _ = kill(Pid::from_raw(-pgid.as_raw()), Signal::SIGTERM);
-                // Give processes a moment to cleanup
-                std::thread::sleep(std::time::Duration::from_millis(100));
-                // Force kill if still running
-                let _ = kill(Pid::from_raw(-pgid.as_raw()), Signal::SIGKILL);
-            }
+        // Terminate the entire process group, giving processes a moment to cleanup
+        if let Some(pid) = self.pid {
+            children::terminate(pid, std::time::Duration::from_millis(100));
         }
     }
 }
@@ -57,6 +104,10 @@ impl StdioActor {
         let stdin = self.stdin.take().expect("stdin should be available");
         let msg_inbox = self.receiver.take().expect("receiver should be available");
         let msg_outbox = self.sender.take().expect("sender should be available");
+        let stderr = self.stderr.take().expect("stderr should be available");
+
+        // Drain stderr as it is written so diagnostics are available while the process runs
+        let stderr_task = tokio::spawn(Self::handle_proc_stderr(stderr, self.stderr_log.clone()));
 
         let incoming = Self::handle_proc_output(stdout, msg_outbox);
         let outgoing = Self::handle_proc_input(stdin, msg_inbox);
@@ -79,22 +130,25 @@ impl StdioActor {
             }
         }
 
-        // Then always try to read stderr before cleaning up
-        let mut stderr_buffer = Vec::new();
-        if let Some(mut stderr) = self.stderr.take() {
-            if let Ok(bytes) = stderr.read_to_end(&mut stderr_buffer).await {
-                let err_msg = if bytes > 0 {
-                    String::from_utf8_lossy(&stderr_buffer).to_string()
-                } else {
-                    "Process ended unexpectedly".to_string()
-                };
-
-                tracing::info!("Process stderr: {}", err_msg);
-                let _ = self
-                    .error_sender
-                    .send(Error::StdioProcessError(err_msg))
-                    .await;
-            }
+        // Then wait for the rest of stderr before reporting the failure
+        let _ = stderr_task.await;
+        let err_msg = if self.stderr_log.is_empty() {
+            "Process ended unexpectedly".to_string()
+        } else {
+            self.stderr_log.tail(self.stderr_log.len()).join("\n")
+        };
+
+        tracing::info!("Process stderr: {}", err_msg);
+        let _ = self
+            .error_sender
+            .send(Error::StdioProcessError(err_msg))
+            .await;
+    }
+
+    async fn handle_proc_stderr(stderr: ChildStderr, log: LogBuffer) {
+        let mut lines = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log.push(line);
         }
     }
 
@@ -195,6 +249,7 @@ pub struct StdioTransport {
     command: String,
     args: Vec<String>,
     env: HashMap<String, String>,
+    stderr_log: LogBuffer,
 }
 
 impl StdioTransport {
@@ -207,9 +262,21 @@ impl StdioTransport {
             command: command.into(),
             args,
             env,
+            stderr_log: LogBuffer::default(),
         }
     }
 
+    /// Capture the process's stderr into the given buffer
+    pub fn with_stderr_log(mut self, stderr_log: LogBuffer) -> Self {
+        self.stderr_log = stderr_log;
+        self
+    }
+
+    /// The buffer holding the most recent stderr output of the process
+    pub fn stderr_log(&self) -> LogBuffer {
+        self.stderr_log.clone()
+    }
+
     async fn spawn_process(&self) -> Result<(Child, ChildStdin, ChildStdout, ChildStderr), Error> {
         let mut command = Command::new(&self.command);
         command
@@ -270,14 +337,22 @@ impl Transport for StdioTransport {
         let (inbox_tx, inbox_rx) = mpsc::channel(32);
         let (error_tx, error_rx) = mpsc::channel(1);
 
+        // Registered so the host can reap the process if it is interrupted
+        let pid = process.id();
+        if let Some(pid) = pid {
+            children::register(pid);
+        }
+
         let actor = StdioActor {
             receiver: Some(outbox_rx), // client to process
             sender: Some(inbox_tx),    // process to client
             process,
+            pid,
             error_sender: error_tx,
             stdin: Some(stdin),
             stdout: Some(stdout),
             stderr: Some(stderr),
+            stderr_log: self.stderr_log.clone(),
         };
 
         tokio::spawn(actor.run());
@@ -305,3 +380,19 @@ impl Transport for StdioTransport {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buffer_keeps_most_recent_lines() {
+        let log = LogBuffer::new(3);
+        for i in 0..5 {
+            log.push(format!("line {}", i));
+        }
+        assert_eq!(log.len(), 3);
+        assert_eq!(log.tail(2), vec!["line 3", "line 4"]);
+        assert_eq!(log.tail(10), vec!["line 2", "line 3", "line 4"]);
+    }
+}
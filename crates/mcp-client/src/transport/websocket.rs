@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{SinkExt, StreamExt};
+use mcp_core::protocol::JsonRpcMessage;
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::warn;
+
+use super::{serialize_and_send, Error, Transport, TransportHandle};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How long to wait for the server to answer the replayed `initialize` after a reconnect
+const REINITIALIZE_TIMEOUT_SECS: u64 = 10;
+
+/// How the transport reconnects when the connection drops
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Attempts before giving up; 0 never reconnects
+    pub max_attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before an attempt, doubling from the initial delay up to the maximum
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+async fn connect(url: &str, headers: &HashMap<String, String>) -> Result<Socket, Error> {
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| Error::WebSocketConnection(format!("Invalid URL {}: {}", url, e)))?;
+    for (key, value) in headers {
+        let name = HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| Error::WebSocketConnection(format!("Invalid header {}: {}", key, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| Error::WebSocketConnection(format!("Invalid header {}: {}", key, e)))?;
+        request.headers_mut().insert(name, value);
+    }
+    let (socket, _response) = connect_async(request)
+        .await
+        .map_err(|e| Error::WebSocketConnection(e.to_string()))?;
+    Ok(socket)
+}
+
+/// The WebSocket actor that forwards messages between the handle and the socket, and
+/// reconnects when the connection drops.
+///
+/// Requests in flight when the connection drops are lost and time out in the client. After a
+/// reconnect the `initialize` handshake is replayed, since the server treats the new connection
+/// as a new session.
+pub struct WebSocketActor {
+    /// Receives messages (requests/notifications) from the handle
+    receiver: mpsc::Receiver<String>,
+    /// Sends messages (responses) back to the handle
+    sender: mpsc::Sender<JsonRpcMessage>,
+    url: String,
+    headers: HashMap<String, String>,
+    reconnect: ReconnectPolicy,
+    /// The `initialize` request and its id, once the client has sent it
+    initialize: Option<(u64, String)>,
+    /// The `notifications/initialized` notification, once the client has sent it
+    initialized: Option<String>,
+}
+
+impl WebSocketActor {
+    pub async fn run(mut self, mut socket: Socket) {
+        loop {
+            if !self.forward(&mut socket).await {
+                let _ = socket.close(None).await;
+                break;
+            }
+            match self.reconnect().await {
+                Some(reconnected) => socket = reconnected,
+                None => {
+                    tracing::error!(
+                        "WebSocket connection to {} dropped and could not be re-established",
+                        self.url
+                    );
+                    break;
+                }
+            }
+        }
+        tracing::info!("WebSocketActor shut down.");
+    }
+
+    /// Forward messages until the connection drops, returning true, or the handle is gone,
+    /// returning false
+    async fn forward(&mut self, socket: &mut Socket) -> bool {
+        loop {
+            tokio::select! {
+                outgoing = self.receiver.recv() => {
+                    let Some(text) = outgoing else {
+                        return false;
+                    };
+                    self.remember_handshake(&text);
+                    if let Err(e) = socket.send(Message::text(text)).await {
+                        warn!("WebSocket send failed: {e}");
+                        return true;
+                    }
+                }
+                incoming = socket.next() => match incoming {
+                    Some(Ok(Message::Text(text))) => self.deliver(text.as_str()).await,
+                    Some(Ok(Message::Binary(bytes))) => match std::str::from_utf8(&bytes) {
+                        Ok(text) => self.deliver(text).await,
+                        Err(_) => warn!("Ignoring binary WebSocket message that is not UTF-8"),
+                    },
+                    Some(Ok(Message::Close(_))) | None => {
+                        tracing::info!("WebSocket connection to {} closed", self.url);
+                        return true;
+                    }
+                    // Pings are answered by the socket itself
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!("Error reading WebSocket: {e}");
+                        return true;
+                    }
+                },
+            }
+        }
+    }
+
+    async fn deliver(&self, text: &str) {
+        match serde_json::from_str::<JsonRpcMessage>(text) {
+            Ok(message) => {
+                let _ = self.sender.send(message).await;
+            }
+            Err(err) => warn!("Failed to parse WebSocket message: {err}"),
+        }
+    }
+
+    fn remember_handshake(&mut self, text: &str) {
+        match serde_json::from_str::<JsonRpcMessage>(text) {
+            Ok(JsonRpcMessage::Request(request)) if request.method == "initialize" => {
+                if let Some(id) = request.id {
+                    self.initialize = Some((id, text.to_string()));
+                }
+            }
+            Ok(JsonRpcMessage::Notification(notification))
+                if notification.method == "notifications/initialized" =>
+            {
+                self.initialized = Some(text.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    async fn reconnect(&self) -> Option<Socket> {
+        for attempt in 1..=self.reconnect.max_attempts {
+            let delay = self.reconnect.delay_for_attempt(attempt);
+            warn!(
+                "Reconnecting to {} in {:?} (attempt {}/{})",
+                self.url, delay, attempt, self.reconnect.max_attempts
+            );
+            tokio::time::sleep(delay).await;
+            let mut socket = match connect(&self.url, &self.headers).await {
+                Ok(socket) => socket,
+                Err(e) => {
+                    warn!("Reconnect failed: {e}");
+                    continue;
+                }
+            };
+            match self.reinitialize(&mut socket).await {
+                Ok(()) => return Some(socket),
+                Err(e) => warn!("Re-initializing after reconnect failed: {e}"),
+            }
+        }
+        None
+    }
+
+    /// Replay the handshake on a new connection, swallowing the server's answer, which the
+    /// client already had from the first connection
+    async fn reinitialize(&self, socket: &mut Socket) -> Result<(), Error> {
+        let Some((id, initialize)) = &self.initialize else {
+            return Ok(());
+        };
+        socket
+            .send(Message::text(initialize.clone()))
+            .await
+            .map_err(|e| Error::WebSocketConnection(e.to_string()))?;
+
+        let answered = async {
+            while let Some(incoming) = socket.next().await {
+                let Message::Text(text) =
+                    incoming.map_err(|e| Error::WebSocketConnection(e.to_string()))?
+                else {
+                    continue;
+                };
+                match serde_json::from_str::<JsonRpcMessage>(text.as_str()) {
+                    Ok(JsonRpcMessage::Response(response)) if response.id == Some(*id) => {
+                        return Ok(())
+                    }
+                    Ok(JsonRpcMessage::Error(error)) if error.id == Some(*id) => {
+                        return Err(Error::WebSocketConnection(error.error.message))
+                    }
+                    Ok(message) => {
+                        let _ = self.sender.send(message).await;
+                    }
+                    Err(err) => warn!("Failed to parse WebSocket message: {err}"),
+                }
+            }
+            Err(Error::WebSocketConnection(
+                "Connection closed during initialization".to_string(),
+            ))
+        };
+        timeout(Duration::from_secs(REINITIALIZE_TIMEOUT_SECS), answered)
+            .await
+            .map_err(|_| {
+                Error::WebSocketConnection("Timed out re-initializing the session".to_string())
+            })??;
+
+        if let Some(initialized) = &self.initialized {
+            socket
+                .send(Message::text(initialized.clone()))
+                .await
+                .map_err(|e| Error::WebSocketConnection(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct WebSocketTransportHandle {
+    sender: mpsc::Sender<String>,
+    receiver: Arc<Mutex<mpsc::Receiver<JsonRpcMessage>>>,
+}
+
+#[async_trait]
+impl TransportHandle for WebSocketTransportHandle {
+    async fn send(&self, message: JsonRpcMessage) -> Result<(), Error> {
+        serialize_and_send(&self.sender, message).await
+    }
+
+    async fn receive(&self) -> Result<JsonRpcMessage, Error> {
+        let mut receiver = self.receiver.lock().await;
+        receiver.recv().await.ok_or(Error::ChannelClosed)
+    }
+}
+
+/// A transport to an MCP server behind a WebSocket endpoint (`ws://` or `wss://`), with each
+/// JSON-RPC message sent as one text frame
+#[derive(Clone)]
+pub struct WebSocketTransport {
+    url: String,
+    headers: HashMap<String, String>,
+    reconnect: ReconnectPolicy,
+}
+
+/// The WebSocket transport connects on `start()` and spawns a `WebSocketActor`.
+impl WebSocketTransport {
+    pub fn new<S: Into<String>>(url: S, headers: HashMap<String, String>) -> Self {
+        Self {
+            url: url.into(),
+            headers,
+            reconnect: ReconnectPolicy::default(),
+        }
+    }
+
+    /// Configure how the transport reconnects when the connection drops
+    pub fn with_reconnect(mut self, reconnect: ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    type Handle = WebSocketTransportHandle;
+
+    async fn start(&self) -> Result<Self::Handle, Error> {
+        // The first connection must succeed; only later drops are retried
+        let socket = connect(&self.url, &self.headers).await?;
+
+        let (tx, rx) = mpsc::channel(32);
+        let (otx, orx) = mpsc::channel(32);
+        let actor = WebSocketActor {
+            receiver: rx,
+            sender: otx,
+            url: self.url.clone(),
+            headers: self.headers.clone(),
+            reconnect: self.reconnect,
+            initialize: None,
+            initialized: None,
+        };
+        tokio::spawn(actor.run(socket));
+
+        Ok(WebSocketTransportHandle {
+            sender: tx,
+            receiver: Arc::new(Mutex::new(orx)),
+        })
+    }
+
+    async fn close(&self) -> Result<(), Error> {
+        // The actor closes the socket once every handle is dropped
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconnect_delay_doubles_up_to_max() {
+        let policy = ReconnectPolicy {
+            max_attempts: 10,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(3),
+        };
+        assert_eq!(policy.delay_for_attempt(1), Duration::from_millis(500));
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_secs(2));
+        assert_eq!(policy.delay_for_attempt(4), Duration::from_secs(3));
+        assert_eq!(policy.delay_for_attempt(40), Duration::from_secs(3));
+    }
+}
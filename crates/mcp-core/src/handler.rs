@@ -18,6 +18,8 @@ pub enum ToolError {
     SchemaError(String),
     #[error("Tool not found: {0}")]
     NotFound(String),
+    #[error("Timed out: {0}")]
+    Timeout(String),
 }
 
 pub type ToolResult<T> = std::result::Result<T, ToolError>;
@@ -426,6 +426,11 @@ where
                 "resources/read" => this.handle_resources_read(req.request).await,
                 "prompts/list" => this.handle_prompts_list(req.request).await,
                 "prompts/get" => this.handle_prompts_get(req.request).await,
+                "ping" => {
+                    let mut response = this.create_response(req.request.id);
+                    response.result = Some(serde_json::json!({}));
+                    Ok(response)
+                }
                 _ => {
                     let mut response = this.create_response(req.request.id);
                     response.error = Some(RouterError::MethodNotFound(req.request.method).into());